@@ -0,0 +1,129 @@
+//! GitHub Actions step outputs and job summary (`--github-output`).
+//!
+//! Inside a GitHub Actions workflow, update-tickets can hand its results to
+//! later steps without them re-parsing stdout. At the end of the run it
+//! appends to the file named by `GITHUB_OUTPUT`:
+//! - `updated`, `already_completed`, `skipped`, `failed` - ticket counts
+//! - `urls` - URLs of the updated tickets (would-be updated, in dry runs), one
+//!   per line
+//!
+//! and a Markdown table of the counts, followed by the updated tickets, to the
+//! file named by `GITHUB_STEP_SUMMARY`, which GitHub renders on the run's
+//! summary page. Outside of Actions (either variable unset), the
+//! corresponding part is skipped with a note.
+
+use std::env;
+use std::fmt::Write as _;
+use std::fs::OpenOptions;
+use std::io::Write as _;
+
+use crate::error::Error;
+use crate::utils;
+
+const NAME: &str = "update-tickets";
+
+/// Delimiter of the multiline `urls` output.
+const DELIMITER: &str = "RELEASE_LINEAR_TICKET_UPDATE_EOF";
+
+macro_rules! log {
+    ($level:ident, $fmt:expr) => {
+        utils::log_at(utils::LogLevel::$level, NAME, format_args!($fmt));
+    };
+    ($fmt:expr) => {
+        utils::log(NAME, format_args!($fmt));
+    };
+}
+
+/// Results of an update-tickets run, as reported to GitHub Actions.
+pub struct RunResults<'a> {
+    /// Release the run was for (`--release-tag`), for the summary heading
+    pub release_tag: Option<&'a str>,
+    /// Whether nothing was changed (`--dry-run`)
+    pub dry_run: bool,
+    pub updated: usize,
+    pub already_completed: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    /// URLs of the updated tickets, in processing order
+    pub urls: Vec<String>,
+}
+
+/// Writes the step outputs and the job summary of `results`.
+///
+/// # Errors
+/// Returns an error if `GITHUB_OUTPUT` or `GITHUB_STEP_SUMMARY` names a file
+/// that can't be appended to.
+pub fn write(results: &RunResults<'_>) -> Result<(), Error> {
+    append_to_env_file("GITHUB_OUTPUT", &step_outputs(results))?;
+    append_to_env_file("GITHUB_STEP_SUMMARY", &job_summary(results))
+}
+
+/// The `name=value` lines of the step outputs.
+fn step_outputs(results: &RunResults<'_>) -> String {
+    let mut out = String::new();
+    for (name, count) in [
+        ("updated", results.updated),
+        ("already_completed", results.already_completed),
+        ("skipped", results.skipped),
+        ("failed", results.failed),
+    ] {
+        let _ = writeln!(out, "{name}={count}");
+    }
+    let _ = writeln!(out, "urls<<{DELIMITER}");
+    for url in &results.urls {
+        let _ = writeln!(out, "{url}");
+    }
+    let _ = writeln!(out, "{DELIMITER}");
+    out
+}
+
+/// The Markdown job summary.
+fn job_summary(results: &RunResults<'_>) -> String {
+    let mut out = String::from("### Linear tickets");
+    if let Some(tag) = results.release_tag {
+        let _ = write!(out, " for {tag}");
+    }
+    if results.dry_run {
+        out.push_str(" (dry run)");
+    }
+    out.push_str("\n\n| Outcome | Tickets |\n|---|---|\n");
+    let updated = if results.dry_run {
+        "Would update"
+    } else {
+        "Updated"
+    };
+    for (outcome, count) in [
+        (updated, results.updated),
+        ("Already completed", results.already_completed),
+        ("Skipped", results.skipped),
+        ("Failed", results.failed),
+    ] {
+        let _ = writeln!(out, "| {outcome} | {count} |");
+    }
+    if !results.urls.is_empty() {
+        out.push('\n');
+        for url in &results.urls {
+            let _ = writeln!(out, "- {url}");
+        }
+    }
+    out.push('\n');
+    out
+}
+
+/// Appends `text` to the file named by the environment variable `var`, or
+/// logs that it's skipped if the variable isn't set.
+fn append_to_env_file(var: &str, text: &str) -> Result<(), Error> {
+    let Some(path) = env::var_os(var).filter(|path| !path.is_empty()) else {
+        log!(Warn, "{var} is not set; skipping it (--github-output)");
+        return Ok(());
+    };
+    let display = path.to_string_lossy();
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(text.as_bytes()))
+        .map_err(|e| {
+            Error::io(format!("Failed to write {var} file {display}"), e)
+        })
+}
@@ -0,0 +1,138 @@
+//! Release attachments on Linear tickets (`--attach-release`).
+//!
+//! With `--attach-release`, update-tickets attaches the GitHub release
+//! (`--release-tag`, looked up like the `{release_url}` of
+//! `--comment-template`) to every ticket it moves, as a Linear attachment
+//! titled `Release TAG`. Before any ticket is processed, the tickets that
+//! already carry an attachment with the release URL are listed once, with
+//! `attachmentsForURL`; they are skipped like tickets already marked for the
+//! release, so re-running the orchestrator for a tag after a partial failure
+//! only processes the tickets the earlier run didn't reach.
+//!
+//! Linear keeps one attachment per URL and issue, so attaching twice (e.g.
+//! from concurrent runs) updates the attachment rather than duplicating it.
+
+use std::collections::HashSet;
+
+use crate::comments;
+use crate::error::Error;
+use crate::github_api::GitHub;
+use crate::utils::{self, LinearApi};
+
+/// Attachments listed per `attachmentsForURL` page.
+const PAGE_SIZE: usize = 100;
+
+/// The release attached to tickets, and the tickets that already have it.
+#[derive(Debug)]
+pub struct AttachedRelease {
+    pub tag: String,
+    pub url: String,
+    /// UUIDs of the issues the release is attached to
+    attached: HashSet<String>,
+}
+
+impl AttachedRelease {
+    /// Looks up the URL of release `tag` on GitHub, and the issues it is
+    /// already attached to.
+    ///
+    /// # GraphQL Query
+    /// ```graphql
+    /// query($url: String!, $after: String) {
+    ///   attachmentsForURL(url: $url, first: 100, after: $after) {
+    ///     nodes { issue { id } }
+    ///     pageInfo { hasNextPage endCursor }
+    ///   }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the release can't be found or the attachments
+    /// can't be listed.
+    pub fn load(
+        tag: &str,
+        github: &GitHub,
+        linear: &LinearApi,
+    ) -> Result<Self, Error> {
+        let (url, _) = comments::view_release(tag, github)?;
+        let mut attached = HashSet::new();
+        let mut after: Option<String> = None;
+        loop {
+            let query = format!(
+                r#"{{"query": "query($url: String!, $after: String) {{ attachmentsForURL(url: $url, first: {PAGE_SIZE}, after: $after) {{ nodes {{ issue {{ id }} }} pageInfo {{ hasNextPage endCursor }} }} }}", "variables": {{"url": {}, "after": {}}}}}"#,
+                utils::json_string(&url),
+                after
+                    .as_deref()
+                    .map_or_else(|| "null".to_string(), utils::json_string)
+            );
+            let response = utils::graphql_request(&query, linear)?;
+            let messages = response.error_messages().join("\n");
+            if !messages.trim().is_empty() {
+                return Err(Error::LinearApi(format!(
+                    "Failed to list the attachments of release {tag}: {}",
+                    messages.trim()
+                )));
+            }
+            let page = response.get("data.attachmentsForURL");
+            attached.extend(page.get("nodes").items().iter().filter_map(
+                |node| node.get("issue.id").as_str().map(String::from),
+            ));
+            after = page
+                .get("pageInfo.endCursor")
+                .as_str()
+                .filter(|_| {
+                    page.get("pageInfo.hasNextPage").as_bool() == Some(true)
+                })
+                .map(String::from);
+            if after.is_none() {
+                break;
+            }
+        }
+        Ok(Self {
+            tag: tag.to_string(),
+            url,
+            attached,
+        })
+    }
+
+    /// Number of issues the release was attached to before this run.
+    pub fn count(&self) -> usize {
+        self.attached.len()
+    }
+
+    /// Whether the release is attached to the issue with UUID `issue_id`.
+    pub fn is_attached(&self, issue_id: &str) -> bool {
+        self.attached.contains(issue_id)
+    }
+
+    /// Attaches the release to the issue with UUID `issue_id`.
+    ///
+    /// # GraphQL Mutation
+    /// ```graphql
+    /// mutation($input: AttachmentCreateInput!) {
+    ///   attachmentCreate(input: $input) { success }
+    /// }
+    /// ```
+    /// with the issue, the release URL and `Release TAG` as the title.
+    ///
+    /// # Errors
+    /// Returns an error if the mutation returns `success: false`.
+    pub fn attach(
+        &self,
+        issue_id: &str,
+        linear: &LinearApi,
+    ) -> Result<(), Error> {
+        let query = format!(
+            r#"{{"query": "mutation($input: AttachmentCreateInput!) {{ attachmentCreate(input: $input) {{ success }} }}", "variables": {{"input": {{"issueId": {}, "url": {}, "title": {}}}}}}}"#,
+            utils::json_string(issue_id),
+            utils::json_string(&self.url),
+            utils::json_string(&format!("Release {}", self.tag))
+        );
+        let response = utils::graphql_request(&query, linear)?;
+        if response.get("data.attachmentCreate.success").as_bool() == Some(true)
+        {
+            Ok(())
+        } else {
+            Err(Error::LinearApi(format!("Attachment failed: {response}")))
+        }
+    }
+}
@@ -0,0 +1,319 @@
+//! Response cache and rate limiter shared across processes (`--cache-dir`).
+//!
+//! When the orchestrator runs several releases concurrently, each release is
+//! its own pipeline of processes. They share a cache directory so that work
+//! done by one pipeline is reused by the others:
+//! - `github/` - `gh pr view` responses, so a PR listed in several releases
+//!   is fetched once
+//! - `github-labels/`, `github-files/` - PR labels and changed files, for
+//!   `--pr-label` and `--path-filter` (see [`crate::scope`])
+//! - `releases/` - release notes read by parse-notes, so repeated runs
+//!   against a release don't fetch them again
+//! - `linear/` - each team's completed workflow state, looked up once per team
+//!   instead of once per ticket
+//!
+//! The same directory holds the state of the global rate limiter
+//! (`--rate-limit`), so all pipelines together stay under one request rate,
+//! and the count of the request budget (`--api-budget`), so all of them
+//! together send at most that many requests.
+//!
+//! ## File Format
+//! Each cache entry is a file `<namespace>/<key>` holding the cached value.
+//! Entries are written to a temporary file and renamed into place, so a
+//! concurrent reader sees either the whole entry or none. The rate limiter's
+//! `rate-limit` file holds the arrival time of its token bucket (see
+//! [`RateLimiter`]), a Unix time in milliseconds; it is only read and written
+//! while locked. The
+//! `api-budget` file likewise holds the number of requests asked for so far.
+
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::error::Error;
+
+/// A cache directory shared by concurrent pipelines.
+#[derive(Debug, Clone)]
+pub struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    /// Opens the cache in `dir`, creating the directory if needed.
+    ///
+    /// # Errors
+    /// Returns an error if the directory can't be created.
+    pub fn open(dir: &str) -> Result<Self, Error> {
+        fs::create_dir_all(dir).map_err(|e| {
+            Error::io(format!("Failed to create cache dir {dir}"), e)
+        })?;
+        Ok(Self {
+            dir: PathBuf::from(dir),
+        })
+    }
+
+    /// Reads the cached value of `key`, if any.
+    #[must_use]
+    pub fn get(&self, namespace: &str, key: &str) -> Option<String> {
+        fs::read_to_string(self.entry_path(namespace, key)).ok()
+    }
+
+    /// Stores `value` as the cached value of `key`.
+    ///
+    /// # Errors
+    /// Returns an error if the entry can't be written.
+    pub fn put(
+        &self,
+        namespace: &str,
+        key: &str,
+        value: &str,
+    ) -> Result<(), Error> {
+        let path = self.entry_path(namespace, key);
+        let display = path.display();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                let context =
+                    format!("Failed to create cache dir {}", parent.display());
+                Error::io(context, e)
+            })?;
+        }
+        // Another process may write the same entry at the same time, so each
+        // writer needs its own temporary file.
+        let tmp_path =
+            path.with_extension(format!("{}.tmp", std::process::id()));
+        fs::write(&tmp_path, value).map_err(|e| {
+            Error::io(format!("Failed to write cache entry {display}"), e)
+        })?;
+        fs::rename(&tmp_path, &path).map_err(|e| {
+            Error::io(format!("Failed to write cache entry {display}"), e)
+        })
+    }
+
+    fn entry_path(&self, namespace: &str, key: &str) -> PathBuf {
+        let key: String = key
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '.' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.dir.join(namespace).join(key)
+    }
+}
+
+/// Paces GitHub and Linear (or Jira) requests with a token bucket of
+/// `--rate-limit N/s`: up to N requests at once, then one every 1/N second.
+///
+/// The bucket is kept as its theoretical arrival time, the Unix time (in
+/// milliseconds) at which the bucket would be empty again: a request may go
+/// once that time is at most one second's worth of requests ahead of now.
+/// With `--cache-dir`, it is kept in the cache directory and shared by every
+/// process using it; otherwise it is kept by this process alone.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    requests_per_sec: u32,
+    path: Option<PathBuf>,
+    /// The arrival time, when not kept in `path`
+    arrival_ms: Arc<Mutex<u64>>,
+    /// Time between requests once the bucket is empty
+    interval_ms: u64,
+    /// How far the arrival time may run ahead of now (the bucket's size)
+    burst_ms: u64,
+}
+
+impl RateLimiter {
+    /// A limiter allowing `requests_per_sec` requests per second, shared
+    /// through `cache_dir` if given.
+    #[must_use]
+    pub fn new(requests_per_sec: u32, cache_dir: Option<&str>) -> Self {
+        let per_sec = u64::from(requests_per_sec.max(1));
+        let interval_ms = 1000 / per_sec;
+        Self {
+            requests_per_sec,
+            path: cache_dir.map(|dir| PathBuf::from(dir).join("rate-limit")),
+            arrival_ms: Arc::new(Mutex::new(0)),
+            interval_ms,
+            burst_ms: interval_ms * (per_sec - 1),
+        }
+    }
+
+    /// The number of requests allowed per second.
+    #[must_use]
+    pub fn requests_per_sec(&self) -> u32 {
+        self.requests_per_sec
+    }
+
+    /// Waits until this process may send its next request.
+    ///
+    /// # Process
+    /// 1. Locks the bucket (the `rate-limit` file, blocking while another
+    ///    process holds it)
+    /// 2. Takes a token: the request may go once the arrival time, moved up
+    ///    to now if it has passed, is less than a bucket ahead of now. The
+    ///    arrival time moves one interval further.
+    /// 3. Unlocks the bucket and sleeps until the request may go
+    ///
+    /// # Errors
+    /// Returns an error if the file can't be locked, read or written.
+    pub fn wait(&self) -> Result<(), Error> {
+        let now = now_ms();
+        let arrival =
+            self.update(|arrival| arrival.max(now) + self.interval_ms)?;
+        let allowed =
+            (arrival - self.interval_ms).saturating_sub(self.burst_ms);
+        thread::sleep(Duration::from_millis(allowed.saturating_sub(now)));
+        Ok(())
+    }
+
+    /// Holds back every request sharing the bucket for `pause`, after an API
+    /// answered that it's rate limiting them. Requests then resume one
+    /// interval apart, until the bucket refills.
+    ///
+    /// # Errors
+    /// Returns an error if the file can't be locked, read or written.
+    pub fn pause(&self, pause: Duration) -> Result<(), Error> {
+        let resume =
+            now_ms() + u64::try_from(pause.as_millis()).unwrap_or(u64::MAX / 2);
+        self.update(|arrival| arrival.max(resume + self.burst_ms))
+            .map(drop)
+    }
+
+    /// Replaces the arrival time with `next(arrival)` while holding the
+    /// bucket's lock, returning the new arrival time.
+    fn update(&self, next: impl FnOnce(u64) -> u64) -> Result<u64, Error> {
+        let Some(ref path) = self.path else {
+            let mut arrival = self.arrival_ms.lock().map_err(|_| {
+                Error::Other("Rate limiter lock poisoned".to_string())
+            })?;
+            *arrival = next(*arrival);
+            return Ok(*arrival);
+        };
+        let display = path.display();
+        let error = |e: std::io::Error| {
+            Error::io(format!("Failed to update rate limit file {display}"), e)
+        };
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .map_err(error)?;
+        file.lock().map_err(error)?;
+
+        let mut contents = String::new();
+        file.read_to_string(&mut contents).map_err(error)?;
+        let arrival = next(contents.trim().parse::<u64>().unwrap_or(0));
+        file.set_len(0).map_err(error)?;
+        file.seek(SeekFrom::Start(0)).map_err(error)?;
+        write!(file, "{arrival}").map_err(error)?;
+        Ok(arrival)
+    }
+}
+
+/// Caps the number of GitHub and Linear (or Jira) requests of a run
+/// (`--api-budget`).
+///
+/// With `--cache-dir`, the count is kept in the cache directory and shared by
+/// every process using it; otherwise it is kept by this process alone.
+#[derive(Debug, Clone)]
+pub struct ApiBudget {
+    limit: u64,
+    path: Option<PathBuf>,
+    used: Arc<AtomicU64>,
+    exhausted: Arc<AtomicBool>,
+}
+
+impl ApiBudget {
+    /// Exit code of a run that stopped sending requests because its budget
+    /// ran out: what it output is correct but incomplete.
+    pub const EXHAUSTED_EXIT_CODE: i32 = 3;
+
+    /// A budget of `limit` requests, shared through `cache_dir` if given.
+    #[must_use]
+    pub fn new(limit: u64, cache_dir: Option<&str>) -> Self {
+        Self {
+            limit,
+            path: cache_dir.map(|dir| PathBuf::from(dir).join("api-budget")),
+            used: Arc::new(AtomicU64::new(0)),
+            exhausted: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// The number of requests allowed.
+    #[must_use]
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// Claims one request from the budget.
+    ///
+    /// # Errors
+    /// Returns an error if the budget is used up (the request must not be
+    /// sent) or the shared count can't be updated.
+    pub fn spend(&self) -> Result<(), Error> {
+        let used = match self.path {
+            Some(ref path) => increment_file(path)?,
+            None => self.used.fetch_add(1, Ordering::Relaxed) + 1,
+        };
+        if used <= self.limit {
+            return Ok(());
+        }
+        self.exhausted.store(true, Ordering::Relaxed);
+        Err(Error::Other(format!(
+            "API budget of {} requests exhausted (--api-budget)",
+            self.limit
+        )))
+    }
+
+    /// Whether a request of this process was refused for lack of budget.
+    #[must_use]
+    pub fn exhausted(&self) -> bool {
+        self.exhausted.load(Ordering::Relaxed)
+    }
+
+    /// Records that another process (a pipeline stage) ran out of budget.
+    pub fn mark_exhausted(&self) {
+        self.exhausted.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Adds one to the count in the file at `path`, under lock, returning the
+/// new count.
+fn increment_file(path: &Path) -> Result<u64, Error> {
+    let display = path.display();
+    let error = |e: std::io::Error| {
+        Error::io(format!("Failed to update API budget file {display}"), e)
+    };
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .map_err(error)?;
+    file.lock().map_err(error)?;
+
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).map_err(error)?;
+    let used = contents.trim().parse::<u64>().unwrap_or(0) + 1;
+    file.set_len(0).map_err(error)?;
+    file.seek(SeekFrom::Start(0)).map_err(error)?;
+    write!(file, "{used}").map_err(error)?;
+    Ok(used)
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| {
+            u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX)
+        })
+}
@@ -0,0 +1,135 @@
+//! Optional Linear API features, detected at startup.
+//!
+//! Not every endpoint update-tickets talks to offers Linear's full schema: an
+//! API gateway in front of Linear (`--linear-api-url`) or an older API
+//! version may lack the batch mutation or issue search. Rather than failing
+//! on every ticket mid-run, update-tickets probes the schema once with an
+//! introspection query, turns off the features the API doesn't offer, and
+//! logs each one:
+//!
+//! | Feature | Schema field | Without it |
+//! |---------|--------------|------------|
+//! | Batched `--quiet-updates` | `Mutation.issueBatchUpdate` | tickets are moved one at a time |
+//! | Issue search fallback | `Query.searchIssues` | tickets Linear doesn't resolve by identifier fail |
+//! | Unsubscribed comments | `CommentCreateInput.doNotSubscribeToIssue` | comments subscribe the API user |
+//!
+//! If introspection itself is unavailable (some gateways block it), every
+//! feature is assumed to be available, as before detection existed.
+
+use crate::utils::{self, LinearApi};
+
+const NAME: &str = "update-tickets";
+
+macro_rules! log {
+    ($level:ident, $fmt:expr) => {
+        utils::log_at(utils::LogLevel::$level, NAME, format_args!($fmt));
+    };
+    ($fmt:expr) => {
+        utils::log(NAME, format_args!($fmt));
+    };
+}
+
+/// Which optional features the Linear API offers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    /// `issueBatchUpdate`, for `--quiet-updates`
+    pub batch_update: bool,
+    /// `searchIssues`, for identifiers Linear no longer resolves directly
+    pub issue_search: bool,
+    /// `doNotSubscribeToIssue` on `commentCreate`, for `--quiet-updates`
+    pub comment_no_subscribe: bool,
+}
+
+/// Everything is available until the schema says otherwise.
+impl Default for Capabilities {
+    fn default() -> Self {
+        Self {
+            batch_update: true,
+            issue_search: true,
+            comment_no_subscribe: true,
+        }
+    }
+}
+
+impl Capabilities {
+    /// Probes the Linear API's schema, logging every feature it lacks.
+    ///
+    /// # GraphQL Query
+    /// ```graphql
+    /// query {
+    ///   mutation: __type(name: "Mutation") { fields { name } }
+    ///   query: __type(name: "Query") { fields { name } }
+    ///   commentInput: __type(name: "CommentCreateInput") { inputFields { name } }
+    /// }
+    /// ```
+    pub fn probe(linear: &LinearApi) -> Self {
+        let query = r#"{"query": "query { mutation: __type(name: \"Mutation\") { fields { name } } query: __type(name: \"Query\") { fields { name } } commentInput: __type(name: \"CommentCreateInput\") { inputFields { name } } }"}"#;
+        let response = match utils::graphql_request(query, linear) {
+            Ok(response) => response,
+            Err(e) => {
+                log!(
+                    Warn,
+                    "could not probe Linear API features ({e}); assuming all are available"
+                );
+                return Self::default();
+            }
+        };
+        let data = response.get("data");
+        if !response.error_messages().is_empty()
+            || data.get("mutation").is_null()
+            || data.get("query").is_null()
+        {
+            log!(
+                Warn,
+                "Linear API schema introspection is unavailable; assuming all features are available"
+            );
+            return Self::default();
+        }
+
+        let has = |path: &str, name: &str| {
+            data.get(path)
+                .items()
+                .iter()
+                .any(|field| field.get("name").as_str() == Some(name))
+        };
+        let capabilities = Self {
+            batch_update: has("mutation.fields", "issueBatchUpdate"),
+            issue_search: has("query.fields", "searchIssues"),
+            // A missing input type means the field can't be relied on either.
+            comment_no_subscribe: has(
+                "commentInput.inputFields",
+                "doNotSubscribeToIssue",
+            ),
+        };
+        capabilities.report();
+        capabilities
+    }
+
+    /// Logs each feature turned off, and what happens instead.
+    fn report(self) {
+        for (available, field, consequence) in [
+            (
+                self.batch_update,
+                "issueBatchUpdate",
+                "--quiet-updates moves tickets one at a time",
+            ),
+            (
+                self.issue_search,
+                "searchIssues",
+                "tickets Linear doesn't resolve by identifier won't be searched for",
+            ),
+            (
+                self.comment_no_subscribe,
+                "doNotSubscribeToIssue",
+                "comments subscribe the API user to the ticket",
+            ),
+        ] {
+            if !available {
+                log!(
+                    Warn,
+                    "Linear API has no {field}, disabled: {consequence}"
+                );
+            }
+        }
+    }
+}
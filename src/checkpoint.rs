@@ -0,0 +1,103 @@
+//! Checkpoint of processed tickets (`--checkpoint`).
+//!
+//! With `--checkpoint FILE`, update-tickets appends every ticket it processed
+//! successfully to `FILE` as it goes. When a run fails part way (a network
+//! failure, a cancelled job), the next run with the same `--checkpoint`
+//! skips the tickets in `FILE` without looking them up, and processes only
+//! the rest of its input: the failed tickets and those never reached.
+//!
+//! Unlike `--queue-file`, the input is still read (and streamed) as usual, so
+//! a re-run must be given the same input. The file is deleted once a run
+//! ends without failures, so it never carries over to the next release.
+//!
+//! ## File Format
+//! One ticket ID per line, in processing order. Lines are appended as
+//! tickets are processed, so the file is valid even if the run is killed.
+
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+
+use crate::error::Error;
+
+/// The tickets processed by earlier runs, and the file this run adds to.
+#[derive(Debug)]
+pub struct Checkpoint {
+    path: String,
+    processed: HashSet<String>,
+}
+
+impl Checkpoint {
+    /// Reads the tickets an earlier run left in `path`.
+    ///
+    /// # Errors
+    /// Returns an error if `path` exists but can't be read.
+    pub fn load(path: &str) -> Result<Self, Error> {
+        let processed = match fs::read_to_string(path) {
+            Ok(contents) => contents
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(String::from)
+                .collect(),
+            Err(e) if e.kind() == ErrorKind::NotFound => HashSet::new(),
+            Err(e) => {
+                return Err(Error::io(
+                    format!("Failed to read checkpoint file {path}"),
+                    e,
+                ));
+            }
+        };
+        Ok(Self {
+            path: path.to_string(),
+            processed,
+        })
+    }
+
+    /// Path of the checkpoint file.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Number of tickets processed by earlier runs.
+    pub fn count(&self) -> usize {
+        self.processed.len()
+    }
+
+    /// Whether an earlier run processed `issue_id`.
+    pub fn contains(&self, issue_id: &str) -> bool {
+        self.processed.contains(issue_id)
+    }
+
+    /// Appends a successfully processed ticket to the checkpoint file.
+    ///
+    /// # Errors
+    /// Returns an error if the file can't be written.
+    pub fn record(&self, issue_id: &str) -> Result<(), Error> {
+        let path = &self.path;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| {
+                Error::io(format!("Failed to open checkpoint file {path}"), e)
+            })?;
+        writeln!(file, "{issue_id}").map_err(|e| {
+            Error::io(format!("Failed to write checkpoint file {path}"), e)
+        })
+    }
+
+    /// Deletes the checkpoint file, once a run ended without failures.
+    ///
+    /// # Errors
+    /// Returns an error if the file exists but can't be deleted.
+    pub fn finish(&self) -> Result<(), Error> {
+        match fs::remove_file(&self.path) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(Error::io(
+                format!("Failed to remove checkpoint file {}", self.path),
+                e,
+            )),
+            _ => Ok(()),
+        }
+    }
+}
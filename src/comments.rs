@@ -0,0 +1,499 @@
+//! Paced, resumable Linear comment posting.
+//!
+//! `commentCreate` hits Linear's spam protections much sooner than state
+//! updates, so every comment goes through a [`Commenter`], which:
+//! - waits at least `--comment-interval` between comments
+//! - pauses for `--comment-batch-pause` after every `--comment-batch-size`
+//!   comments
+//! - is retried after Linear's rate limiting like every other request (see
+//!   [`utils::is_rate_limited`])
+//! - with `--comment-progress FILE`, records each posted comment so an
+//!   interrupted run can be re-run without posting duplicates
+//!
+//! ## Release Marker
+//! With `--comment-release`, each completed ticket gets a comment naming the
+//! release, ending in an HTML comment marker (see [`release_marker`]) that
+//! Linear stores but doesn't render. Tickets already carrying the marker are
+//! skipped by later runs for the same tag.
+//!
+//! `--comment-template FILE` replaces the default text with FILE, a Markdown
+//! template rendered per ticket by [`CommentTemplate`] with the release URL
+//! and date and the ticket's PR links; the marker is still appended.
+//!
+//! ## Progress File Format
+//! One posted comment per line, as `TICKET<TAB>KEY`, where the key names the
+//! comment (e.g. `blocked-by:ABC-1`). Lines are appended as comments are
+//! posted, so the file is valid even if the run is killed.
+
+use std::collections::HashSet;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::capabilities::Capabilities;
+use crate::config::{Config, Notifications};
+use crate::error::Error;
+use crate::exit_status;
+use crate::github_api::GitHub;
+use crate::json::{self, Value};
+use crate::protocol::PrRef;
+use crate::utils::{self, LinearApi};
+
+const NAME: &str = "update-tickets";
+
+/// Default minimum delay between two comments, in milliseconds.
+pub const DEFAULT_INTERVAL_MS: u64 = 1000;
+/// Default number of comments per batch.
+pub const DEFAULT_BATCH_SIZE: usize = 25;
+/// Default pause between batches, in seconds.
+pub const DEFAULT_BATCH_PAUSE_SECS: u64 = 30;
+
+macro_rules! log {
+    ($level:ident, $fmt:expr) => {
+        utils::log_at(utils::LogLevel::$level, NAME, format_args!($fmt));
+    };
+    ($fmt:expr) => {
+        utils::log(NAME, format_args!($fmt));
+    };
+}
+
+/// The marker identifying comments posted for release `tag`.
+pub fn release_marker(tag: &str) -> String {
+    format!("<!-- release-linear-ticket-update:release={tag} -->")
+}
+
+/// The `--comment-release` comment body for release `tag`.
+pub fn release_comment(tag: &str) -> String {
+    format!("Completed in release {tag}.\n\n{}", release_marker(tag))
+}
+
+/// Names of the `--comment-template` placeholders (see [`CommentTemplate`]).
+const PLACEHOLDERS: [&str; 6] =
+    ["tag", "release_url", "date", "prs", "pr_list", "ticket"];
+
+/// A `--comment-template` release comment, in Markdown (which Linear renders).
+///
+/// # Placeholders
+/// - `{tag}` - the release tag
+/// - `{release_url}` - the release's GitHub page
+/// - `{date}` - the day the release was published (`YYYY-MM-DD`, UTC), or
+///   today for a draft
+/// - `{prs}` - a Markdown list of the ticket's PR links (empty when the input
+///   named no PR for it, e.g. plain ticket IDs rather than ndjson records)
+/// - `{pr_list}` - the same PRs as comma-separated Markdown links, e.g.
+///   `[#12](https://...), [acme/lib#3](https://...)`, for use within a line
+/// - `{ticket}` - the ticket identifier
+#[derive(Debug, Clone)]
+pub struct CommentTemplate {
+    text: String,
+    tag: String,
+    /// e.g. `https://github.com/owner/repo/releases/tag/v1.2.3`
+    release_url: String,
+    /// `YYYY-MM-DD`
+    date: String,
+}
+
+impl CommentTemplate {
+    /// Reads the template file and looks up the release URL and date with
+    /// `gh` (or the API, with `--github-api`). Braced words that aren't
+    /// placeholders are logged, as likely typos, and left as they are.
+    ///
+    /// # Errors
+    /// Returns an error if the file can't be read or the release can't be
+    /// found.
+    pub fn load(path: &str, tag: &str, github: &GitHub) -> Result<Self, Error> {
+        let text = std::fs::read_to_string(path).map_err(|e| {
+            Error::io(format!("Failed to read comment template {path}"), e)
+        })?;
+        for name in unknown_placeholders(&text) {
+            log!(
+                Warn,
+                "warning: {{{name}}} in comment template {path} is not a placeholder"
+            );
+        }
+        let (release_url, date) = view_release(tag, github)?;
+        Ok(Self {
+            text: text.trim_end().to_string(),
+            tag: tag.to_string(),
+            release_url,
+            date,
+        })
+    }
+
+    /// Renders the comment for `ticket`, found in `prs`, ending in the
+    /// release marker.
+    pub fn render(&self, ticket: &str, prs: &[PrRef]) -> String {
+        let pr_list = prs
+            .iter()
+            .map(|pr| format!("[{pr}]({})", self.pr_url(pr)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let prs = prs
+            .iter()
+            .map(|pr| format!("- {}", self.pr_url(pr)))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let text = [
+            ("tag", self.tag.as_str()),
+            ("release_url", self.release_url.as_str()),
+            ("date", self.date.as_str()),
+            ("prs", prs.as_str()),
+            ("pr_list", pr_list.as_str()),
+            ("ticket", ticket),
+        ]
+        .iter()
+        .fold(self.text.clone(), |text, (name, value)| {
+            text.replace(&format!("{{{name}}}"), value)
+        });
+        format!("{}\n\n{}", text.trim_end(), release_marker(&self.tag))
+    }
+
+    /// The URL of `pr`: in the release's repository unless it names another
+    /// one, on the same GitHub host either way.
+    fn pr_url(&self, pr: &PrRef) -> String {
+        let repo_url = self
+            .release_url
+            .split_once("/releases/")
+            .map_or(self.release_url.as_str(), |(repo_url, _)| repo_url);
+        match pr.repo {
+            Some(ref repo) => {
+                // Scheme and host: everything before the third `/`.
+                let host_end = repo_url
+                    .match_indices('/')
+                    .nth(2)
+                    .map_or(repo_url.len(), |(index, _)| index);
+                format!("{}/{repo}/pull/{}", &repo_url[..host_end], pr.number)
+            }
+            None => format!("{repo_url}/pull/{}", pr.number),
+        }
+    }
+}
+
+/// Braced words of `text` (`{name}`, with letters and underscores) that
+/// aren't [`PLACEHOLDERS`], each once.
+fn unknown_placeholders(text: &str) -> Vec<&str> {
+    let mut unknown: Vec<&str> = Vec::new();
+    for (start, _) in text.match_indices('{') {
+        let rest = &text[start + 1..];
+        let Some(end) = rest.find('}') else {
+            break;
+        };
+        let name = &rest[..end];
+        if !name.is_empty()
+            && name.bytes().all(|b| b.is_ascii_lowercase() || b == b'_')
+            && !PLACEHOLDERS.contains(&name)
+            && !unknown.contains(&name)
+        {
+            unknown.push(name);
+        }
+    }
+    unknown
+}
+
+/// Looks up the GitHub page and publication date of release `tag` with
+/// `gh release view` (or, with `--github-api`, the REST release).
+///
+/// # Returns
+/// The release URL and its `YYYY-MM-DD` publication date (UTC), or today's
+/// date if the release isn't published yet (a draft).
+pub fn view_release(
+    tag: &str,
+    github: &GitHub,
+) -> Result<(String, String), Error> {
+    let response = match github {
+        GitHub::Cli(host) => gh_release_view(tag, host.as_deref())?,
+        GitHub::Api(api) => {
+            let release = api.release(tag).map_err(|e| {
+                e.context(format!("Failed to get release {tag}"))
+            })?;
+            // The `gh release view --json` field names.
+            Value::Object(vec![
+                ("url".to_string(), release.get("html_url").clone()),
+                (
+                    "publishedAt".to_string(),
+                    release.get("published_at").clone(),
+                ),
+            ])
+        }
+    };
+    let url = match response.get("url").str_or_empty() {
+        "" => {
+            return Err(Error::GitHub(format!(
+                "GitHub returned no URL for release {tag}"
+            )));
+        }
+        url => url.to_string(),
+    };
+    // Drafts have no (or a zero) publication time.
+    let published =
+        utils::parse_timestamp(response.get("publishedAt").str_or_empty())
+            .unwrap_or_else(|_| {
+                SystemTime::now().duration_since(UNIX_EPOCH).map_or(
+                    0,
+                    |elapsed| {
+                        i64::try_from(elapsed.as_secs()).unwrap_or(i64::MAX)
+                    },
+                )
+            });
+    Ok((url, utils::utc_date(published)))
+}
+
+/// Runs `gh release view` for the URL and publication time of release
+/// `tag`.
+///
+/// # Errors
+/// Returns an error if `gh` fails or prints invalid JSON.
+fn gh_release_view(
+    tag: &str,
+    github_host: Option<&str>,
+) -> Result<Value, Error> {
+    let output = utils::gh_command(github_host)
+        .args(["release", "view", tag, "--json", "url,publishedAt"])
+        .output()
+        .map_err(|e| {
+            exit_status::spawn_error("Failed to execute gh command", e)
+        })?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::GitHub(format!(
+            "Failed to get release {tag}: {}",
+            stderr.trim()
+        )));
+    }
+    json::parse(&String::from_utf8_lossy(&output.stdout))
+        .map_err(|e| e.context("Invalid gh release view output"))
+}
+
+/// What happened to a comment passed to [`Commenter::post`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Posted {
+    /// The comment was created
+    Created,
+    /// The progress file shows it was posted by an earlier run
+    AlreadyPosted,
+}
+
+/// The `--comment-*` pacing flags, each falling back to its default when
+/// unset.
+#[derive(Debug, Default)]
+pub struct Pacing {
+    /// Minimum delay between comments (`--comment-interval`)
+    pub interval_ms: Option<u64>,
+    /// Comments per batch (`--comment-batch-size`)
+    pub batch_size: Option<usize>,
+    /// Pause between comment batches (`--comment-batch-pause`)
+    pub batch_pause_secs: Option<u64>,
+    /// File recording posted comments, for resuming (`--comment-progress`)
+    pub progress: Option<String>,
+}
+
+impl Pacing {
+    /// Whether any of the flags is given.
+    pub fn is_set(&self) -> bool {
+        self.interval_ms.is_some()
+            || self.batch_size.is_some()
+            || self.batch_pause_secs.is_some()
+            || self.progress.is_some()
+    }
+}
+
+/// Posts comments with pacing, batching and resumable progress.
+#[derive(Debug)]
+pub struct Commenter {
+    interval: Duration,
+    batch_size: usize,
+    batch_pause: Duration,
+    /// Progress file path (`--comment-progress`)
+    progress_path: Option<String>,
+    /// Keep the API user from being subscribed to commented tickets
+    /// (`--quiet-updates`)
+    no_subscribe: bool,
+    /// `(ticket, key)` pairs already posted, from the progress file
+    posted: HashSet<(String, String)>,
+    /// Comments created by this run
+    created: usize,
+    last_created: Option<Instant>,
+}
+
+impl Default for Commenter {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_millis(DEFAULT_INTERVAL_MS),
+            batch_size: DEFAULT_BATCH_SIZE,
+            batch_pause: Duration::from_secs(DEFAULT_BATCH_PAUSE_SECS),
+            progress_path: None,
+            no_subscribe: false,
+            posted: HashSet::new(),
+            created: 0,
+            last_created: None,
+        }
+    }
+}
+
+impl Commenter {
+    /// Builds a commenter from the `--comment-*` flags, loading the progress
+    /// file if one is given and exists. Comments only skip subscribing if the
+    /// Linear API supports it (`capabilities`).
+    ///
+    /// # Errors
+    /// Returns an error if the progress file exists but can't be read.
+    pub fn from_config(
+        config: &Config,
+        capabilities: Capabilities,
+    ) -> Result<Self, Error> {
+        let pacing = &config.comment_pacing;
+        let mut commenter = Self {
+            interval: Duration::from_millis(
+                pacing.interval_ms.unwrap_or(DEFAULT_INTERVAL_MS),
+            ),
+            batch_size: pacing.batch_size.unwrap_or(DEFAULT_BATCH_SIZE),
+            batch_pause: Duration::from_secs(
+                pacing.batch_pause_secs.unwrap_or(DEFAULT_BATCH_PAUSE_SECS),
+            ),
+            progress_path: pacing.progress.clone(),
+            no_subscribe: config.notifications == Notifications::Quiet
+                && capabilities.comment_no_subscribe,
+            ..Self::default()
+        };
+
+        if let Some(ref path) = commenter.progress_path {
+            match std::fs::read_to_string(path) {
+                Ok(contents) => {
+                    commenter.posted = contents
+                        .lines()
+                        .filter_map(|line| line.split_once('\t'))
+                        .map(|(ticket, key)| {
+                            (ticket.to_string(), key.to_string())
+                        })
+                        .collect();
+                    let count = commenter.posted.len();
+                    log!(
+                        "resuming comments: {count} already posted per {path}"
+                    );
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    return Err(Error::io(
+                        format!("Failed to read comment progress file {path}"),
+                        e,
+                    ));
+                }
+            }
+        }
+        Ok(commenter)
+    }
+
+    /// Posts `body` on `issue_id`, unless the comment named `key` was already
+    /// posted there according to the progress file.
+    ///
+    /// Waits as needed to respect the interval and batch pause first.
+    ///
+    /// # Errors
+    /// Returns an error if the comment can't be created or the progress file
+    /// can't be written.
+    pub fn post(
+        &mut self,
+        issue_id: &str,
+        key: &str,
+        body: &str,
+        linear: &LinearApi,
+    ) -> Result<Posted, Error> {
+        let entry = (issue_id.to_string(), key.to_string());
+        if self.posted.contains(&entry) {
+            return Ok(Posted::AlreadyPosted);
+        }
+
+        self.wait_for_turn();
+
+        create_comment(issue_id, body, self.no_subscribe, linear)?;
+
+        self.created += 1;
+        self.last_created = Some(Instant::now());
+        self.record(&entry)?;
+        self.posted.insert(entry);
+        Ok(Posted::Created)
+    }
+
+    /// Sleeps until the next comment may be created.
+    fn wait_for_turn(&self) {
+        if self.batch_size > 0
+            && self.created > 0
+            && self.created.is_multiple_of(self.batch_size)
+        {
+            let created = self.created;
+            let secs = self.batch_pause.as_secs();
+            log!("posted {created} comments, pausing {secs}s");
+            thread::sleep(self.batch_pause);
+            return;
+        }
+        if let Some(last) = self.last_created {
+            thread::sleep(self.interval.saturating_sub(last.elapsed()));
+        }
+    }
+
+    /// Appends a posted comment to the progress file, if any.
+    fn record(&self, (ticket, key): &(String, String)) -> Result<(), Error> {
+        let Some(ref path) = self.progress_path else {
+            return Ok(());
+        };
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| {
+                Error::io(
+                    format!("Failed to open comment progress file {path}"),
+                    e,
+                )
+            })?;
+        writeln!(file, "{ticket}\t{key}").map_err(|e| {
+            Error::io(
+                format!("Failed to write comment progress file {path}"),
+                e,
+            )
+        })
+    }
+}
+
+/// Adds a comment to a Linear issue.
+///
+/// With `no_subscribe`, `doNotSubscribeToIssue` keeps the API user from
+/// becoming a subscriber of the issue (and receiving its notifications).
+///
+/// # GraphQL Mutation
+/// ```graphql
+/// mutation($issueId: String!, $body: String!, $noSubscribe: Boolean!) {
+///   commentCreate(input: {
+///     issueId: $issueId, body: $body, doNotSubscribeToIssue: $noSubscribe
+///   }) {
+///     success
+///   }
+/// }
+/// ```
+///
+/// # Errors
+/// Returns an error if the request fails or the mutation returns
+/// `success: false`.
+pub fn create_comment(
+    issue_id: &str,
+    body: &str,
+    no_subscribe: bool,
+    linear: &LinearApi,
+) -> Result<(), Error> {
+    let query = format!(
+        r#"{{"query": "mutation($issueId: String!, $body: String!, $noSubscribe: Boolean!) {{ commentCreate(input: {{ issueId: $issueId, body: $body, doNotSubscribeToIssue: $noSubscribe }}) {{ success }} }}", "variables": {{"issueId": {}, "body": {}, "noSubscribe": {no_subscribe}}}}}"#,
+        utils::json_string(issue_id),
+        utils::json_string(body)
+    );
+
+    let response = utils::graphql_request(&query, linear)?;
+    utils::ensure_no_graphql_errors(&response)?;
+    let success = response.get("data.commentCreate.success").as_bool();
+
+    if success == Some(true) {
+        Ok(())
+    } else {
+        Err(Error::LinearApi(format!("Comment failed: {response}")))
+    }
+}
@@ -0,0 +1,176 @@
+//! GitHub commit status for the release (`--commit-status`).
+//!
+//! The orchestrator can report the health of a Linear sync on the release
+//! tag's commit as a commit status with context [`CONTEXT`]. It posts
+//! `pending` before the pipeline starts and `success` or `failure` once it
+//! finishes, each linking to the given target URL (typically the CI run that
+//! produced the logs). Branch protection rules and dashboards can then surface
+//! failed syncs like any other check.
+//!
+//! Statuses are posted with `gh api` (or, with `--github-api`, straight to
+//! the REST API), so they use the same authentication and `--github-host` as
+//! the rest of the GitHub access.
+
+use crate::error::Error;
+use crate::exit_status;
+use crate::github_api::GitHub;
+use crate::json::Value;
+use crate::utils;
+
+const NAME: &str = "orchestrator";
+
+/// Context of the commit status posted on the release commit.
+pub const CONTEXT: &str = "release/linear-sync";
+
+macro_rules! log {
+    ($fmt:expr) => {
+        utils::log(NAME, format_args!($fmt));
+    };
+}
+
+/// State of the Linear sync, as reported to GitHub.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    Pending,
+    Success,
+    Failure,
+}
+
+impl State {
+    fn as_str(self) -> &'static str {
+        match self {
+            State::Pending => "pending",
+            State::Success => "success",
+            State::Failure => "failure",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            State::Pending => "Syncing Linear tickets",
+            State::Success => "Linear tickets synced",
+            State::Failure => "Linear ticket sync failed",
+        }
+    }
+}
+
+/// Resolves the commit a release tag points at.
+///
+/// # Errors
+/// Returns an error if `gh` (or the API) fails or the tag doesn't exist.
+pub fn tag_commit(tag: &str, github: &GitHub) -> Result<String, Error> {
+    let github_host = match github {
+        GitHub::Cli(host) => host.as_deref(),
+        GitHub::Api(api) => {
+            let (owner, name) = api.current_repo()?;
+            let commit = api
+                .rest(
+                    "GET",
+                    &format!(
+                        "repos/{owner}/{name}/commits/{}",
+                        utils::percent_encode(tag)
+                    ),
+                    None,
+                )
+                .map_err(|e| {
+                    e.context(format!(
+                        "Failed to resolve the commit of tag {tag}"
+                    ))
+                })?;
+            return match commit.get("sha").str_or_empty() {
+                "" => Err(Error::GitHub(format!("Tag {tag} has no commit"))),
+                sha => Ok(sha.to_string()),
+            };
+        }
+    };
+    let output = utils::gh_command(github_host)
+        .args(["api", &format!("repos/{{owner}}/{{repo}}/commits/{tag}")])
+        .args(["--jq", ".sha"])
+        .output()
+        .map_err(|e| {
+            exit_status::spawn_error("Failed to execute gh command", e)
+        })?;
+
+    if !output.status.success() {
+        return Err(Error::GitHub(format!(
+            "Failed to resolve the commit of tag {tag}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    let sha = String::from_utf8(output.stdout)
+        .map_err(|e| Error::command(format!("Invalid UTF-8 from gh: {e}")))?;
+    let sha = sha.trim();
+    if sha.is_empty() {
+        return Err(Error::GitHub(format!("Tag {tag} has no commit")));
+    }
+    Ok(sha.to_string())
+}
+
+/// Posts the [`CONTEXT`] commit status on `sha`.
+///
+/// # Errors
+/// Returns an error if `gh` (or the API) fails to post the status.
+pub fn post(
+    sha: &str,
+    state: State,
+    target_url: &str,
+    github: &GitHub,
+) -> Result<(), Error> {
+    match github {
+        GitHub::Cli(host) => gh_post(sha, state, target_url, host.as_deref())?,
+        GitHub::Api(api) => {
+            let (owner, name) = api.current_repo()?;
+            let status = Value::Object(
+                [
+                    ("state", state.as_str()),
+                    ("context", CONTEXT),
+                    ("description", state.description()),
+                    ("target_url", target_url),
+                ]
+                .into_iter()
+                .map(|(field, value)| {
+                    (field.to_string(), Value::String(value.to_string()))
+                })
+                .collect(),
+            );
+            api.rest(
+                "POST",
+                &format!("repos/{owner}/{name}/statuses/{sha}"),
+                Some(&status),
+            )
+            .map_err(|e| e.context("Failed to post commit status"))?;
+        }
+    }
+
+    let state = state.as_str();
+    log!("Posted {CONTEXT} status {state} on {sha}");
+    Ok(())
+}
+
+/// Posts the [`CONTEXT`] commit status on `sha` with `gh api`.
+fn gh_post(
+    sha: &str,
+    state: State,
+    target_url: &str,
+    github_host: Option<&str>,
+) -> Result<(), Error> {
+    let output = utils::gh_command(github_host)
+        .args(["api", "--method", "POST"])
+        .arg(format!("repos/{{owner}}/{{repo}}/statuses/{sha}"))
+        .args(["-f", &format!("state={}", state.as_str())])
+        .args(["-f", &format!("context={CONTEXT}")])
+        .args(["-f", &format!("description={}", state.description())])
+        .args(["-f", &format!("target_url={target_url}")])
+        .output()
+        .map_err(|e| {
+            exit_status::spawn_error("Failed to execute gh command", e)
+        })?;
+
+    if !output.status.success() {
+        return Err(Error::GitHub(format!(
+            "Failed to post commit status: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
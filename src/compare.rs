@@ -0,0 +1,154 @@
+//! PRs merged between two releases (`--discover compare`).
+//!
+//! Hand-written release notes sometimes leave PRs out. With
+//! `--discover compare` (or `both`), parse-notes also lists the commits
+//! between the previous release and the release
+//! (`gh api --paginate repos/{owner}/{repo}/compare/PREV...TAG`, or the same
+//! REST endpoint with `--github-api`), and takes each PR from the first line
+//! of a commit's message:
+//!
+//! ```text
+//! Merge pull request #123 from acme/feature   (merge commit)
+//! Add the export button (#123)                (squash merge)
+//! ```
+//!
+//! Commits of a rebase merge don't name their PR, so they are missed. The
+//! previous release is the one created before the release (see
+//! [`crate::release_range`]).
+//!
+//! The extract-commits mode lists the same commits to search their whole
+//! messages for tickets (see [`crate::extract_commits`]).
+
+use crate::error::Error;
+use crate::exit_status;
+use crate::github_api::GitHub;
+use crate::json;
+use crate::protocol::PrRef;
+use crate::utils;
+
+/// A commit between two releases.
+#[derive(Debug, Clone)]
+pub struct Commit {
+    /// Full SHA
+    pub sha: String,
+    /// The whole commit message
+    pub message: String,
+}
+
+impl Commit {
+    /// The first line of the message.
+    #[must_use]
+    pub fn headline(&self) -> &str {
+        self.message.lines().next().unwrap_or_default()
+    }
+}
+
+/// The PRs merged between releases `base` and `head` of the current
+/// repository, in commit order, as named by their commits.
+///
+/// # Errors
+/// Returns an error if the comparison can't be fetched.
+pub fn merged_prs(
+    github: &GitHub,
+    base: &str,
+    head: &str,
+) -> Result<Vec<PrRef>, Error> {
+    let commits = commits(github, base, head)?;
+    Ok(commits
+        .iter()
+        .filter_map(|commit| pr_of(commit.headline()))
+        .collect())
+}
+
+/// Every commit between `base` and `head` of the current repository, oldest
+/// first.
+///
+/// # Errors
+/// Returns an error if the comparison can't be fetched.
+pub fn commits(
+    github: &GitHub,
+    base: &str,
+    head: &str,
+) -> Result<Vec<Commit>, Error> {
+    fetch_commits(github, base, head)
+        .map_err(|e| e.context(format!("Failed to compare {base}...{head}")))
+}
+
+/// Fetches the commits between `base` and `head`.
+///
+/// # Errors
+/// Returns an error if `gh` (or the API) fails.
+fn fetch_commits(
+    github: &GitHub,
+    base: &str,
+    head: &str,
+) -> Result<Vec<Commit>, Error> {
+    let github_host = match github {
+        GitHub::Cli(host) => host.as_deref(),
+        GitHub::Api(api) => {
+            let commits = api.compare_commits(base, head)?;
+            return Ok(commits
+                .iter()
+                .map(|commit| Commit {
+                    sha: commit.get("sha").str_or_empty().to_string(),
+                    message: commit
+                        .get("commit.message")
+                        .str_or_empty()
+                        .to_string(),
+                })
+                .collect());
+        }
+    };
+    let endpoint = format!(
+        "repos/{{owner}}/{{repo}}/compare/{}...{}",
+        utils::percent_encode(base),
+        utils::percent_encode(head)
+    );
+    let output = utils::gh_command(github_host)
+        .args(["api", "--paginate", &endpoint])
+        .args(["--jq", ".commits[] | [.sha, .commit.message] | @json"])
+        .output()
+        .map_err(|e| {
+            exit_status::spawn_error("Failed to execute gh command", e)
+        })?;
+
+    if !output.status.success() {
+        return Err(Error::GitHub(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    let lines = String::from_utf8(output.stdout)
+        .map_err(|e| Error::command(format!("Invalid UTF-8 from gh: {e}")))?;
+    // One `[sha, message]` array per line
+    lines
+        .lines()
+        .map(|line| {
+            let commit = json::parse(line)?;
+            let field = |index: usize| {
+                commit
+                    .items()
+                    .get(index)
+                    .map_or("", json::Value::str_or_empty)
+                    .to_string()
+            };
+            Ok(Commit {
+                sha: field(0),
+                message: field(1),
+            })
+        })
+        .collect()
+}
+
+/// The PR a commit headline names: `Merge pull request #123 from ...` or
+/// `... (#123)`.
+fn pr_of(headline: &str) -> Option<PrRef> {
+    let number =
+        if let Some(rest) = headline.strip_prefix("Merge pull request #") {
+            rest.split(|c: char| !c.is_ascii_digit()).next()?
+        } else {
+            let (_, number) =
+                headline.trim_end().strip_suffix(')')?.rsplit_once("(#")?;
+            number
+        };
+    PrRef::parse(number).ok()
+}
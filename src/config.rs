@@ -5,34 +5,804 @@
 //! handling with stdin represented by "-".
 
 use std::env;
+use std::fs;
+use std::iter;
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex};
+
+use crate::cache::{ApiBudget, Cache, RateLimiter};
+use crate::comments::Pacing;
+use crate::config_file;
+use crate::cycle::Cycle;
+use crate::deployment::Deployment;
+use crate::error::Error;
+use crate::exit_status;
+use crate::explain::Subject;
+use crate::extract_commits::{CommitRange, CommitSource};
+use crate::filter::Filter;
+use crate::github_api::{GitHub, GithubApi};
+use crate::jira::{JiraApi, JiraCredentials};
+use crate::labels::CustomField;
+use crate::pipeline::{Pipeline, Stage, StageInput};
+use crate::protocol::{Output, PrRef, Record};
+use crate::scope::PrScope;
+use crate::serve::{self, Server};
+use crate::snapshot::Snapshot;
+use crate::update_tickets;
+use crate::utils::{
+    self, DEFAULT_LINEAR_API_URL, LinearApi, LogFormat, LogLevel, LogSettings,
+};
 
 /// Operational mode for the application.
 ///
-/// The application can run in four distinct modes:
+/// The application can run in these modes:
 /// - Individual pipeline stages (parse-notes, extract-tickets, update-tickets)
 /// - Orchestrator mode that chains all stages together
+/// - A teams report for checking workflow state matching
+/// - A read-only release report mapping PRs to tickets and their states
+/// - A release verification that every ticket is completed
+/// - The JSON Schema of the machine outputs
 #[derive(Debug, Clone, Copy)]
 pub enum Mode {
     /// Parse release notes to extract PR numbers
     ParseNotes,
     /// Extract Linear ticket IDs from PR content
     ExtractTickets,
+    /// Extract Linear ticket IDs from the commits of a release
+    ExtractCommits,
     /// Update Linear tickets to completed state
     UpdateTickets,
     /// Run the complete pipeline (parse -> extract -> update)
     Orchestrator,
+    /// List Linear teams and their workflow states
+    Teams,
+    /// Report a release's PRs, their tickets and the tickets' current states
+    Report,
+    /// Check that every ticket of a release is completed
+    Verify,
+    /// Print the JSON Schema of the machine outputs
+    Schema,
+    /// Move tickets back to the states a journaled run moved them out of
+    Rollback,
+    /// Run the update path on a throwaway issue of a sandbox team
+    Selftest,
+    /// Run a pipeline defined in the config file
+    Run,
+    /// Run the orchestrator for every release published to a GitHub webhook
+    Serve,
+}
+
+/// How update-tickets handles blocked-by relations between tickets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dependencies {
+    /// Process tickets in input order (streaming)
+    Ignore,
+    /// Update blockers before the tickets they block
+    Order,
+    /// Like `Order`, and comment on blocked tickets in the release when their
+    /// blocker is completed
+    OrderAndNote,
+}
+
+/// How extract-tickets treats PRs that were never merged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergePolicy {
+    /// Skip tickets of open, draft and closed-without-merge PRs (the default,
+    /// `--require-merged`)
+    RequireMerged,
+    /// Extract tickets from every PR, logging the unmerged ones
+    /// (`--allow-unmerged`)
+    AllowUnmerged,
+}
+
+/// Whether extract-tickets reports PRs without any Linear reference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnlinkedPrs {
+    /// PRs without tickets contribute nothing, silently (the default)
+    Ignore,
+    /// Log each such PR and list them at the end (`--warn-unlinked-prs`)
+    Warn,
+}
+
+/// Whether extract-tickets leaves out text of other PRs of a stack or merge
+/// queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StackedPrs {
+    /// Skip sibling PRs' commits, merge-queue and merge commits, and stack
+    /// listings (the default)
+    Detect,
+    /// Search all of a PR's text (`--no-stack-detection`)
+    Ignore,
+}
+
+/// What extract-tickets does about tickets referenced only by Linear URLs of
+/// another workspace than `--linear-org`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OtherOrgUrls {
+    /// Leave them out, logging each (the default)
+    Drop,
+    /// Output them with their workspace's organization, for update-tickets
+    /// to skip unless it is the ticket's workspace (`tag`)
+    Tag,
+}
+
+/// What update-tickets does about input tickets whose team key isn't a team
+/// of the workspace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnknownTeams {
+    /// Log each unknown team key, and look its tickets up anyway (the
+    /// default)
+    Warn,
+    /// Skip their tickets without looking them up (`--drop-unknown-teams`)
+    Drop,
+}
+
+/// What update-tickets does about input tickets marked as a duplicate of
+/// another Linear issue (`--follow-duplicates`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Duplicates {
+    /// Process the input ticket only (the default)
+    Ignore,
+    /// Process the issue it duplicates instead (`canonical`)
+    Canonical,
+    /// Process both the input ticket and the issue it duplicates (`both`)
+    Both,
+}
+
+/// How update-tickets exits when some tickets failed to update.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartialFailure {
+    /// Log the failed tickets and exit successfully (the default)
+    Succeed,
+    /// Exit with [`exit_status::PARTIAL`] (`--fail-on-partial`)
+    Fail,
+}
+
+/// What update-tickets does about tickets an earlier release already
+/// completed, according to the `--history-file` (`--repeat-release`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepeatRelease {
+    /// Flag them in the report (the default)
+    Warn,
+    /// Flag them and exit with an error at the end of the run
+    Fail,
+}
+
+/// How loudly update-tickets changes tickets in Linear.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Notifications {
+    /// One `issueUpdate` per ticket, as it is processed
+    Normal,
+    /// Batched state updates and no auto-subscription to commented tickets
+    /// (`--quiet-updates`)
+    Quiet,
+}
+
+/// Whether update-tickets reports its results to GitHub Actions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GithubOutput {
+    /// Results only go to stdout and the log (the default)
+    Off,
+    /// Also write step outputs and a job summary (`--github-output`)
+    Write,
+}
+
+/// What update-tickets prints on stdout in dry-run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DryRunOutput {
+    /// The tickets that would be updated (as per `--url-format`)
+    Tickets,
+    /// A table of every ticket with its current and target state, team and
+    /// result (`--diff`)
+    Diff,
+}
+
+/// How update-tickets looks up ticket metadata.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lookups {
+    /// One `issue` query per ticket, as it is processed
+    Single,
+    /// Every ticket up front, many per query (`--batch-lookups`)
+    Batched,
+}
+
+/// How extract-tickets fetches PRs from GitHub.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrFetches {
+    /// One `gh pr view` per PR, as it is read (the default)
+    Single,
+    /// Every PR up front, many per GraphQL query (`--batch-prs`)
+    Batched,
+}
+
+/// Whether update-tickets attaches the release to the tickets it moves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReleaseAttachment {
+    /// No attachments (the default)
+    Off,
+    /// Attach the release URL, and skip tickets that already have it
+    /// (`--attach-release`)
+    Attach,
+}
+
+/// Where parse-notes finds the PRs of a release (`--discover`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Discover {
+    /// PR references in the release notes (the default)
+    Notes,
+    /// Commits between the previous release and the release (see
+    /// [`crate::compare`])
+    Compare,
+    /// Both, so the commits supplement the notes
+    Both,
+}
+
+impl Discover {
+    /// Whether the release notes are read.
+    #[must_use]
+    pub fn notes(self) -> bool {
+        matches!(self, Self::Notes | Self::Both)
+    }
+
+    /// Whether the commits since the previous release are read.
+    #[must_use]
+    pub fn compare(self) -> bool {
+        matches!(self, Self::Compare | Self::Both)
+    }
+
+    /// The `--discover` value.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Notes => "notes",
+            Self::Compare => "compare",
+            Self::Both => "both",
+        }
+    }
+}
+
+/// How GitHub is reached (see [`crate::github_api`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GithubAccess {
+    /// Through the `gh` CLI (the default)
+    Cli,
+    /// Through the REST and GraphQL APIs with `GITHUB_TOKEN`
+    /// (`--github-api`)
+    Api,
+}
+
+/// Output format of every stage (`--output-format`).
+///
+/// See [`crate::protocol`] for the record layouts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// Bare identifiers (update-tickets: ticket URLs), one per line
+    #[default]
+    Text,
+    /// One JSON record per line
+    Ndjson,
+    /// A JSON array of the records, printed when the stage finishes
+    Json,
+}
+
+impl OutputFormat {
+    /// The format's `--output-format` value.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Text => "text",
+            Self::Ndjson => "ndjson",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// How update-tickets prints processed tickets on stdout (`--url-format`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UrlFormat {
+    /// The ticket identifier, e.g. `ABC-123`
+    Id,
+    /// The ticket's Linear URL (the default)
+    Url,
+    /// A Markdown link, e.g. `[ABC-123](https://linear.app/...)`
+    Markdown,
+}
+
+/// Code forge parse-notes and extract-tickets read releases and PRs from
+/// (`--forge`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Forge {
+    /// GitHub, through `gh` (the default)
+    #[default]
+    GitHub,
+    /// GitLab merge requests, through `glab` (see [`crate::gitlab`])
+    GitLab,
+}
+
+impl Forge {
+    /// The forge's `--forge` value, also the namespace of its cached
+    /// responses.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::GitHub => "github",
+            Self::GitLab => "gitlab",
+        }
+    }
+}
+
+/// Issue tracker update-tickets completes tickets in (`--tracker`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Tracker {
+    /// Linear, through its GraphQL API (the default)
+    #[default]
+    Linear,
+    /// Jira, through its REST API (see [`crate::jira`])
+    Jira,
+}
+
+impl Tracker {
+    /// The tracker's `--tracker` value.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Linear => "linear",
+            Self::Jira => "jira",
+        }
+    }
+}
+
+/// Team key renames applied to ticket IDs (`--prefix-map OLD=NEW`).
+///
+/// After a Linear team key is renamed, PRs keep referencing the old key;
+/// mapping `OLD` to `NEW` turns `OLD-123` into `NEW-123`. Each ticket is
+/// remapped at most once, by the first matching entry.
+#[derive(Debug, Clone, Default)]
+pub struct PrefixMap(Vec<(String, String)>);
+
+impl PrefixMap {
+    /// Adds an `OLD=NEW` entry.
+    ///
+    /// # Errors
+    /// Returns an error unless both keys are three uppercase ASCII letters,
+    /// like the team key of a ticket ID.
+    fn push(&mut self, entry: &str) -> Result<(), Error> {
+        let is_key = |key: &str| {
+            key.len() == 3 && key.bytes().all(|b| b.is_ascii_uppercase())
+        };
+        match entry.split_once('=') {
+            Some((old, new)) if is_key(old) && is_key(new) => {
+                self.0.push((old.to_string(), new.to_string()));
+                Ok(())
+            }
+            _ => Err(Error::Config(format!(
+                "Invalid --prefix-map {entry}: expected OLD=NEW, e.g. ABC=XYZ"
+            ))),
+        }
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The `OLD=NEW` entries, in the order given.
+    pub fn entries(&self) -> impl Iterator<Item = String> + '_ {
+        self.0.iter().map(|(old, new)| format!("{old}={new}"))
+    }
+
+    /// Remaps a ticket ID's team key, returning it unchanged if no entry
+    /// matches.
+    #[must_use]
+    pub fn apply(&self, ticket: &str) -> String {
+        let Some((key, number)) = ticket.split_once('-') else {
+            return ticket.to_string();
+        };
+        match self.0.iter().find(|(old, _)| old == key) {
+            Some((_, new)) => format!("{new}-{number}"),
+            None => ticket.to_string(),
+        }
+    }
+}
+
+/// Linear workspaces of other teams (`--org-map TEAM=ORG:KEY`).
+///
+/// When a release references tickets of several Linear workspaces, the
+/// tickets of a mapped team are looked up and updated in workspace `ORG`,
+/// with its API key, while all others use `--linear-org` and
+/// `--linear-api-key`. `KEY` is the API key itself, or `env:NAME` to read it
+/// from environment variable `NAME`.
+#[derive(Debug, Clone, Default)]
+pub struct OrgMap(Vec<OrgRoute>);
+
+/// One `TEAM=ORG:KEY` entry of the [`OrgMap`].
+#[derive(Debug, Clone)]
+pub struct OrgRoute {
+    /// Team key of the tickets routed to the workspace, e.g. `OPS`
+    pub team: String,
+    /// Linear organization identifier of the workspace (for ticket URLs)
+    pub org: String,
+    /// The API key, or `env:NAME`
+    api_key: String,
+}
+
+impl OrgMap {
+    /// Adds the comma-separated `TEAM=ORG:KEY` entries of `value`.
+    ///
+    /// # Errors
+    /// Returns an error if an entry is malformed or maps a team twice.
+    fn push(&mut self, value: &str) -> Result<(), Error> {
+        for entry in value.split(',').map(str::trim) {
+            let route = entry
+                .split_once('=')
+                .and_then(|(team, rest)| {
+                    let (org, api_key) = rest.split_once(':')?;
+                    Some(OrgRoute {
+                        team: team.to_string(),
+                        org: org.to_string(),
+                        api_key: api_key.to_string(),
+                    })
+                })
+                .filter(|route| {
+                    route.team.starts_with(|c: char| c.is_ascii_uppercase())
+                        && route.team.bytes().all(|b| {
+                            b.is_ascii_uppercase() || b.is_ascii_digit()
+                        })
+                        && !route.org.is_empty()
+                        && !route.api_key.is_empty()
+                })
+                .ok_or_else(|| {
+                    Error::Config(format!(
+                        "Invalid --org-map {entry}: expected TEAM=ORG:KEY, e.g. OPS=acme-ops:env:LINEAR_OPS_API_KEY"
+                    ))
+                })?;
+            if self.0.iter().any(|other| other.team == route.team) {
+                return Err(Error::Config(format!(
+                    "--org-map maps team {} more than once",
+                    route.team
+                )));
+            }
+            self.0.push(route);
+        }
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The `TEAM=ORG:KEY` entries, in the order given.
+    pub fn entries(&self) -> impl Iterator<Item = String> + '_ {
+        self.0.iter().map(|route| {
+            format!("{}={}:{}", route.team, route.org, route.api_key)
+        })
+    }
+
+    /// The routes, in the order given.
+    #[must_use]
+    pub fn routes(&self) -> &[OrgRoute] {
+        &self.0
+    }
+}
+
+impl OrgRoute {
+    /// The workspace's API key, read from the environment for `env:NAME`.
+    ///
+    /// # Errors
+    /// Returns an error if the environment variable isn't set.
+    pub fn api_key(&self) -> Result<String, Error> {
+        let Some(name) = self.api_key.strip_prefix("env:") else {
+            return Ok(self.api_key.clone());
+        };
+        env::var(name)
+            .ok()
+            .filter(|key| !key.is_empty())
+            .ok_or_else(|| {
+                exit_status::credentials(&format!(
+                    "{name} not set, for the API key of workspace {} (--org-map)",
+                    self.org
+                ))
+            })
+    }
+}
+
+/// Tokens that match the ticket pattern but are never tickets, such as
+/// `UTF-8` or `SHA-256`, which extract-tickets drops: the built-in list
+/// (unless `--no-default-ignores`) and every `--ignore-token`.
+#[derive(Debug, Clone)]
+pub struct FalsePositives {
+    /// Whether the built-in list applies
+    builtin: bool,
+    /// `--ignore-token` extended regexes, in the order given
+    extra: Vec<String>,
+}
+
+impl FalsePositives {
+    /// Prefixes of the built-in list: encodings, hashes and ciphers,
+    /// standards and time zones, each followed by a hyphen and a number.
+    pub const BUILTIN_PREFIXES: [&str; 15] = [
+        "UTF", "UCS", "SHA", "AES", "RSA", "RFC", "ISO", "IEC", "IEEE", "ECMA",
+        "PEP", "CVE", "CWE", "UTC", "GMT",
+    ];
+
+    /// Adds an `--ignore-token` extended regex.
+    ///
+    /// # Errors
+    /// Returns an error if grep can't compile the regex.
+    fn push(&mut self, pattern: &str) -> Result<(), Error> {
+        utils::grep_whole_lines("", pattern).map_err(|e| {
+            Error::Config(format!("Invalid --ignore-token {pattern}: {e}"))
+        })?;
+        self.extra.push(pattern.to_string());
+        Ok(())
+    }
+
+    /// Whether the built-in list applies (no `--no-default-ignores`).
+    #[must_use]
+    pub fn builtin(&self) -> bool {
+        self.builtin
+    }
+
+    /// The `--ignore-token` regexes, in the order given.
+    #[must_use]
+    pub fn extra(&self) -> &[String] {
+        &self.extra
+    }
+
+    /// One extended regex matching every ignored token in full, or `None`
+    /// if nothing is ignored.
+    #[must_use]
+    pub fn pattern(&self) -> Option<String> {
+        let builtin = self
+            .builtin
+            .then(|| format!("({})-[0-9]+", Self::BUILTIN_PREFIXES.join("|")));
+        let alternatives: Vec<String> = builtin
+            .into_iter()
+            .chain(self.extra.iter().map(|pattern| format!("({pattern})")))
+            .collect();
+        (!alternatives.is_empty()).then(|| alternatives.join("|"))
+    }
+}
+
+impl Default for FalsePositives {
+    fn default() -> Self {
+        Self {
+            builtin: true,
+            extra: Vec::new(),
+        }
+    }
+}
+
+/// A range of releases of the current repository (see
+/// [`crate::release_range`]).
+#[derive(Debug, Clone)]
+pub struct ReleaseRange {
+    /// The range holds the releases created after this one (`--since-tag`)
+    pub since: String,
+    /// Last release of the range (`--until-tag`); the latest without it
+    pub until: Option<String>,
+}
+
+/// Known-bad references parse-notes and extract-tickets drop, such as docs
+/// tickets or revert PRs (`--ignore-tickets`, `--ignore-prs`).
+///
+/// Each flag takes a file, one entry per line (blank lines and lines
+/// starting with `#` are skipped), or, if no such file exists, a
+/// comma-separated list.
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreList {
+    /// Uppercased ticket IDs, in the order given
+    tickets: Vec<String>,
+    prs: Vec<PrRef>,
+}
+
+impl IgnoreList {
+    /// Adds the tickets of an `--ignore-tickets` value.
+    ///
+    /// # Errors
+    /// Returns an error if the file can't be read or an entry contains
+    /// whitespace.
+    fn push_tickets(&mut self, value: &str) -> Result<(), Error> {
+        for entry in Self::entries("--ignore-tickets", value)? {
+            if entry.contains(char::is_whitespace) {
+                return Err(Error::Config(format!(
+                    "Invalid --ignore-tickets entry {entry}: expected a ticket ID like ABC-123"
+                )));
+            }
+            self.tickets.push(entry.to_ascii_uppercase());
+        }
+        Ok(())
+    }
+
+    /// Adds the PRs of an `--ignore-prs` value.
+    ///
+    /// # Errors
+    /// Returns an error if the file can't be read or an entry isn't a PR
+    /// identifier (`123` or `owner/repo#123`).
+    fn push_prs(&mut self, value: &str) -> Result<(), Error> {
+        for entry in Self::entries("--ignore-prs", value)? {
+            let pr = PrRef::parse(&entry)
+                .map_err(|e| e.context("Invalid --ignore-prs entry"))?;
+            self.prs.push(pr);
+        }
+        Ok(())
+    }
+
+    /// The entries of a `flag` value: the lines of file `value`, or else its
+    /// comma-separated items.
+    ///
+    /// # Errors
+    /// Returns an error if `value` is a file that can't be read, or neither
+    /// a file nor a list.
+    fn entries(flag: &str, value: &str) -> Result<Vec<String>, Error> {
+        let is_file = fs::metadata(value).is_ok_and(|meta| meta.is_file());
+        if is_file {
+            let contents = fs::read_to_string(value).map_err(|e| {
+                Error::io(format!("Failed to read {flag} {value}"), e)
+            })?;
+            return Ok(contents
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(String::from)
+                .collect());
+        }
+        let entries: Vec<String> = value
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(String::from)
+            .collect();
+        if entries.is_empty() {
+            return Err(Error::Config(format!(
+                "Invalid {flag} {value}: expected a file or a comma-separated list"
+            )));
+        }
+        Ok(entries)
+    }
+
+    /// The ignored ticket IDs, in the order given.
+    #[must_use]
+    pub fn tickets(&self) -> &[String] {
+        &self.tickets
+    }
+
+    /// The ignored PRs, in the order given.
+    #[must_use]
+    pub fn prs(&self) -> &[PrRef] {
+        &self.prs
+    }
+
+    /// Whether ticket `ticket` is ignored.
+    #[must_use]
+    pub fn ignores_ticket(&self, ticket: &str) -> bool {
+        self.tickets
+            .iter()
+            .any(|ignored| ignored.eq_ignore_ascii_case(ticket))
+    }
+
+    /// Whether PR `pr` is ignored. A bare number only matches PRs of the
+    /// current repository.
+    #[must_use]
+    pub fn ignores_pr(&self, pr: &PrRef) -> bool {
+        self.prs.contains(pr)
+    }
+}
+
+/// Linear's workflow state types, the values `--target-state-type` accepts.
+pub const STATE_TYPES: [&str; 6] = [
+    "triage",
+    "backlog",
+    "unstarted",
+    "started",
+    "completed",
+    "canceled",
+];
+
+/// Workflow states to move tickets to instead of the completed state, chosen
+/// by release tag (`--state-route PATTERN=STATE`).
+///
+/// Lets the same pipeline advance tickets through pre-production states for
+/// release candidates, e.g. `*-rc*=In Staging`, and complete them only for
+/// the final release. `PATTERN` is matched against the whole tag, with `*`
+/// matching any run of characters; the first matching route is used.
+#[derive(Debug, Clone, Default)]
+pub struct StateRoutes(Vec<(String, String)>);
+
+impl StateRoutes {
+    /// Adds a `PATTERN=STATE` route.
+    ///
+    /// # Errors
+    /// Returns an error if the pattern or state is empty.
+    fn push(&mut self, entry: &str) -> Result<(), Error> {
+        match entry.split_once('=') {
+            Some((pattern, state))
+                if !pattern.is_empty() && !state.trim().is_empty() =>
+            {
+                self.0.push((pattern.to_string(), state.trim().to_string()));
+                Ok(())
+            }
+            _ => Err(Error::Config(format!(
+                "Invalid --state-route {entry}: expected PATTERN=STATE, e.g. '*-rc*=In Staging'"
+            ))),
+        }
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The `PATTERN=STATE` routes, in the order given.
+    pub fn entries(&self) -> impl Iterator<Item = String> + '_ {
+        self.0
+            .iter()
+            .map(|(pattern, state)| format!("{pattern}={state}"))
+    }
+
+    /// The first route whose pattern matches `tag`, as `(pattern, state)`.
+    #[must_use]
+    pub fn route(&self, tag: &str) -> Option<(&str, &str)> {
+        self.0
+            .iter()
+            .find(|(pattern, _)| glob_matches(pattern, tag))
+            .map(|(pattern, state)| (pattern.as_str(), state.as_str()))
+    }
+
+    /// Whether `state_name` is the state of any route (case-insensitive).
+    #[must_use]
+    pub fn is_route_state(&self, state_name: &str) -> bool {
+        self.0
+            .iter()
+            .any(|(_, state)| state.eq_ignore_ascii_case(state_name))
+    }
+}
+
+/// Matches `text` against a pattern where `*` stands for any run of
+/// characters.
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No `*`: the whole text must match.
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(idx) => rest = &rest[idx + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
 }
 
 /// Source of input data for commands.
 ///
 /// Supports Unix-style input handling where stdin can be explicitly
 /// requested with "-" or implicitly used when no files are provided.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum InputSource {
     /// Read from standard input
     Stdin,
     /// Read from a file at the given path
     File(String),
+    /// Read the records of the previous stage of an in-process pipeline
+    /// (`--in-process`), until it finishes
+    Channel(Arc<Mutex<Receiver<Record>>>),
+}
+
+/// How the orchestrator runs the stages of a pipeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stages {
+    /// As processes of the binary, piped together (the default)
+    Spawned,
+    /// On threads of this process, connected by channels (`--in-process`)
+    InProcess,
 }
 
 /// Application configuration parsed from command-line arguments.
@@ -43,21 +813,270 @@ pub enum InputSource {
 pub struct Config {
     /// The operational mode to run
     pub mode: Mode,
-    /// GitHub release tag (required for parse-notes and orchestrator modes)
+    /// GitHub release tag (required for parse-notes, extract-commits, report,
+    /// verify and orchestrator modes, and for update-tickets'
+    /// `--comment-release`, `--release-issue-team` and `--history-file`).
+    /// With several `--release-tag`s, the first one.
     pub release_tag: Option<String>,
+    /// Every `--release-tag` given, in order (only the orchestrator accepts
+    /// more than one)
+    pub release_tags: Vec<String>,
+    /// Releases processed instead of a `--release-tag` (`--since-tag`,
+    /// `--until-tag`)
+    pub release_range: Option<ReleaseRange>,
+    /// Number of releases the orchestrator processes concurrently (`--jobs`)
+    pub jobs: Option<usize>,
+    /// Number of PRs extract-tickets fetches, and tickets update-tickets
+    /// updates, concurrently (`--concurrency`)
+    pub concurrency: Option<usize>,
+    /// How update-tickets looks up tickets (`--batch-lookups`)
+    pub lookups: Lookups,
+    /// Cache directory shared by concurrent pipelines (`--cache-dir`)
+    pub cache_dir: Option<String>,
+    /// Token bucket pacing the run's requests to `--rate-limit` per second,
+    /// across every process sharing the cache directory if any
+    pub rate_limiter: Option<RateLimiter>,
+    /// Cap on the run's GitHub and Linear (or Jira) requests, shared
+    /// through the cache directory (`--api-budget`)
+    pub api_budget: Option<ApiBudget>,
     /// Linear API key for authentication (can also come from environment)
     pub linear_api_key: Option<String>,
     /// Linear organization identifier (can also come from environment)
     pub linear_org: Option<String>,
+    /// Workspaces of teams whose tickets aren't in the `--linear-org` one
+    /// (`--org-map`)
+    pub org_map: OrgMap,
+    /// Issue tracker tickets are completed in (`--tracker`)
+    pub tracker: Tracker,
+    /// Jira site and credentials (`--jira-base-url`, `--jira-api-token`,
+    /// `--jira-email`, can also come from environment)
+    pub jira: JiraCredentials,
     /// Input sources (files or stdin) for processing
     pub input_sources: Vec<InputSource>,
-    /// Whether to run in dry-run mode (preview without making changes)
+    /// Whether to run in dry-run mode (preview without making changes; for
+    /// parse-notes and extract-tickets, log counts instead of printing
+    /// output)
     pub dry_run: bool,
     /// If true, update tickets regardless of current workflow state.
     ///
     /// By default, tickets are only updated if their current state name is
-    /// "Passing" (case-insensitive).
+    /// "Passing" (case-insensitive), or one of `from_states`.
     pub update_all_statuses: bool,
+    /// Workflow states tickets are updated from instead of "Passing"
+    /// (`--from-states`, case-insensitive)
+    pub from_states: Vec<String>,
+    /// Optional `--filter` expression, parsed and type-checked up front and
+    /// evaluated per ticket against its Linear metadata. Tickets that don't
+    /// match are skipped.
+    pub filter: Option<Filter>,
+    /// Only update tickets assigned to this user (`--assignee`, `me` or an
+    /// email)
+    pub assignee: Option<String>,
+    /// Only update tickets created by this user (`--creator`, `me` or an
+    /// email)
+    pub creator: Option<String>,
+    /// What update-tickets does about input tickets whose team key isn't a
+    /// team of the workspace (`--drop-unknown-teams`)
+    pub unknown_teams: UnknownTeams,
+    /// What update-tickets does about input tickets marked as a duplicate of
+    /// another issue (`--follow-duplicates`)
+    pub duplicates: Duplicates,
+    /// How update-tickets exits when some tickets failed to update
+    /// (`--fail-on-partial`)
+    pub partial_failure: PartialFailure,
+    /// Path to write the hash-stamped dry-run manifest to (`--manifest`)
+    pub manifest: Option<String>,
+    /// Manifest hash that the current plan must match before applying
+    /// (`--approve`)
+    pub approve: Option<String>,
+    /// Dry-run manifest to compare the run against (`--compare-manifest`)
+    pub compare_manifest: Option<String>,
+    /// Blocked-by relation handling (`--order-by-dependencies`,
+    /// `--note-blocked`)
+    pub dependencies: Dependencies,
+    /// Code forge releases and PRs are read from (`--forge`)
+    pub forge: Forge,
+    /// Where parse-notes finds the PRs of a release (`--discover`)
+    pub discover: Discover,
+    /// GitHub host override passed to `gh` as `GH_HOST` (`--github-host`)
+    pub github_host: Option<String>,
+    /// Whether GitHub is reached through `gh` or its APIs (`--github-api`)
+    pub github_access: GithubAccess,
+    /// Current repository for `--github-api` (`--github-repo`)
+    pub github_repo: Option<String>,
+    /// Linear GraphQL endpoint override (can also come from environment)
+    pub linear_api_url: Option<String>,
+    /// Extra HTTP headers sent to the Linear API (`--linear-header`)
+    pub linear_headers: Vec<String>,
+    /// OAuth access token of a Linear app acting as the bot user that makes
+    /// the changes (`--linear-actor-token`, can also come from environment)
+    pub linear_actor_token: Option<String>,
+    /// Static `HOST:PORT:ADDRESS` resolutions for the Linear API
+    /// (`--linear-resolve`)
+    pub linear_resolve: Vec<String>,
+    /// Workspace snapshot read instead of the Linear API by dry runs and the
+    /// teams report (`--linear-snapshot`)
+    pub linear_snapshot: Option<String>,
+    /// File that unparseable Linear and Jira API responses are appended to
+    /// (`--debug-dump`)
+    pub debug_dump: Option<String>,
+    /// How much the run logs to stderr (`--quiet`, `-v`, `-vv`)
+    pub log_level: LogLevel,
+    /// How the run writes its log lines (`--log-format`)
+    pub log_format: LogFormat,
+    /// Locale of the end-of-run summary text (`--locale`)
+    pub locale: Option<String>,
+    /// Directory containing `<LOCALE>.txt` templates (`--locale-dir`)
+    pub locale_dir: Option<String>,
+    /// Output format of every stage (`--output-format`)
+    pub output_format: OutputFormat,
+    /// How update-tickets prints processed tickets (`--url-format`)
+    pub url_format: UrlFormat,
+    /// Ticket or PR whose decisions every stage traces (`--explain`)
+    pub explain: Option<Subject>,
+    /// Whether update-tickets writes `GITHUB_OUTPUT` and
+    /// `GITHUB_STEP_SUMMARY` (`--github-output`)
+    pub github_output: GithubOutput,
+    /// What update-tickets prints in dry-run (`--diff`)
+    pub dry_run_output: DryRunOutput,
+    /// Team key renames applied by extract-tickets and update-tickets
+    /// (`--prefix-map`)
+    pub prefix_map: PrefixMap,
+    /// Extended regex of ticket IDs that are never extracted or updated
+    /// (`--exclude-pattern`)
+    pub exclude_pattern: Option<String>,
+    /// Extended regex Linear ticket IDs must match in full
+    /// (`--ticket-pattern`); 3 uppercase letters, a hyphen and a number when
+    /// unset
+    pub ticket_pattern: Option<String>,
+    /// Handling of unmerged PRs in extract-tickets (`--require-merged`,
+    /// `--allow-unmerged`)
+    pub merge_policy: MergePolicy,
+    /// Earliest merge time of PRs extract-tickets takes tickets from
+    /// (`--merged-after`, ISO 8601)
+    pub merged_after: Option<String>,
+    /// Latest merge time of PRs extract-tickets takes tickets from
+    /// (`--merged-before`, ISO 8601)
+    pub merged_before: Option<String>,
+    /// Whether extract-tickets warns about PRs without tickets
+    /// (`--warn-unlinked-prs`)
+    pub unlinked_prs: UnlinkedPrs,
+    /// Whether extract-tickets skips text of sibling PRs
+    /// (`--no-stack-detection`)
+    pub stacked_prs: StackedPrs,
+    /// What extract-tickets does about tickets referenced only by Linear URLs
+    /// of another workspace (`--other-org-urls`)
+    pub other_org_urls: OtherOrgUrls,
+    /// Commit trailer keys whose values link tickets (`--trailer-keys`,
+    /// case-insensitive); `Linear` when empty
+    pub trailer_keys: Vec<String>,
+    /// Tickets and PRs parse-notes and extract-tickets drop
+    /// (`--ignore-tickets`, `--ignore-prs`)
+    pub ignore_list: IgnoreList,
+    /// Tokens extract-tickets never takes for tickets (`--ignore-token`,
+    /// `--no-default-ignores`)
+    pub false_positives: FalsePositives,
+    /// Path to write extract-tickets' per-PR ticket mapping to
+    /// (`--mapping-file`)
+    pub mapping_file: Option<String>,
+    /// Most comments extract-tickets scans per PR (`--max-comments`); all of
+    /// them when unset
+    pub max_comments: Option<usize>,
+    /// How extract-tickets fetches PRs (`--batch-prs`)
+    pub pr_fetches: PrFetches,
+    /// PRs extract-tickets takes tickets from (`--pr-label`,
+    /// `--path-filter`)
+    pub pr_scope: PrScope,
+    /// Commits extract-commits searches (`--previous-tag`,
+    /// `--commit-source`)
+    pub commit_range: CommitRange,
+    /// Seconds to wait for the first line of an interactive stdin
+    /// (`--stdin-timeout`, 0 waits forever)
+    pub stdin_timeout_secs: Option<u64>,
+    /// Pacing of the comments posted (`--comment-interval`,
+    /// `--comment-batch-size`, `--comment-batch-pause`, `--comment-progress`)
+    pub comment_pacing: Pacing,
+    /// Comment the release on each completed ticket, and skip tickets that
+    /// already carry its marker (`--comment-release`, implied by
+    /// `--comment-template`)
+    pub comment_release: bool,
+    /// Template file of the release comment (`--comment-template`)
+    pub comment_template: Option<String>,
+    /// Team key to create the `Release <TAG>` tracking issue in
+    /// (`--release-issue-team`)
+    pub release_issue_team: Option<String>,
+    /// Linear project (name or slug ID) representing the release; only its
+    /// started tickets are completed (`--linear-project`)
+    pub linear_project: Option<String>,
+    /// Linear project (name or slug ID) updated tickets are added to
+    /// (`--add-to-project`)
+    pub add_to_project: Option<String>,
+    /// Label applied to updated tickets, created if missing (`--add-label`)
+    pub add_label: Option<String>,
+    /// Label group and value label set on updated tickets
+    /// (`--set-custom-field`)
+    pub custom_field: Option<CustomField>,
+    /// Cycle of each team updated tickets are added to (`--linear-cycle`)
+    pub linear_cycle: Option<Cycle>,
+    /// Whether the release URL is attached to updated tickets
+    /// (`--attach-release`)
+    pub release_attachment: ReleaseAttachment,
+    /// Workflow states to move tickets to instead of completing them, by
+    /// release tag (`--state-route`)
+    pub state_routes: StateRoutes,
+    /// Name of the workflow state to move tickets to instead of the completed
+    /// state, unless a `--state-route` applies (`--target-state`)
+    pub target_state: Option<String>,
+    /// Type the target workflow state must have (`--target-state-type`)
+    pub target_state_type: Option<String>,
+    /// Whether to keep Linear notification noise down (`--quiet-updates`)
+    pub notifications: Notifications,
+    /// File recording the tickets not yet processed, for resuming
+    /// (`--queue-file`)
+    pub queue_file: Option<String>,
+    /// File recording the tickets processed so far, for skipping them in a
+    /// re-run (`--checkpoint`)
+    pub checkpoint: Option<String>,
+    /// File recording every attempted state change, for audits and
+    /// `rollback` (`--journal`)
+    pub journal: Option<String>,
+    /// Sandbox team the selftest mode creates its throwaway issue in
+    /// (`--team`)
+    pub selftest_team: Option<String>,
+    /// Run history shared across runs (`--history-file`)
+    pub history_file: Option<String>,
+    /// Handling of tickets completed by an earlier release
+    /// (`--repeat-release`)
+    pub repeat_release: RepeatRelease,
+    /// Failed runs in a row after which a ticket is quarantined
+    /// (`--quarantine-after`)
+    pub quarantine_after: Option<usize>,
+    /// Quarantined tickets to retry in this run (`--clear-quarantine`)
+    pub clear_quarantine: Vec<String>,
+    /// Target URL of the `release/linear-sync` commit status the orchestrator
+    /// posts on the release commit (`--commit-status`)
+    pub commit_status_url: Option<String>,
+    /// Deployment markers the release must pass before the orchestrator
+    /// updates tickets (`--deployment-environment`, `--deployment-url`,
+    /// `--deployment-timeout`)
+    pub deployment: Deployment,
+    /// Endpoint a `ticket.released` event is posted to for every moved ticket
+    /// (`--webhook-url`)
+    pub webhook_url: Option<String>,
+    /// Secret the events are signed with (`--webhook-secret`)
+    pub webhook_secret: Option<String>,
+    /// Slack incoming webhook the run's summary is posted to
+    /// (`--slack-webhook`)
+    pub slack_webhook: Option<String>,
+    /// Pipeline of the config file to run (`run NAME`)
+    pub pipeline: Option<Pipeline>,
+    /// Webhook server settings (`serve`)
+    pub server: Option<Server>,
+    /// How the orchestrator runs the stages (`--in-process`)
+    pub stages: Stages,
+    /// Where the stage's output goes: stdout, or the next stage of an
+    /// in-process pipeline
+    pub output: Output,
 }
 
 impl Config {
@@ -69,14 +1088,15 @@ impl Config {
     ///
     /// # Errors
     /// Returns an error if neither the flag nor environment variable is set.
-    pub fn get_linear_api_key(&self) -> Result<String, String> {
+    pub fn get_linear_api_key(&self) -> Result<String, Error> {
         let env_api_key = env::var("LINEAR_API_KEY").ok();
         self.linear_api_key
             .as_ref()
             .or(env_api_key.as_ref())
             .ok_or_else(|| {
-                "LINEAR_API_KEY not provided via --linear-api-key flag or environment variable"
-                    .to_string()
+                exit_status::credentials(
+                    "LINEAR_API_KEY not provided via --linear-api-key flag or environment variable",
+                )
             })
             .map(String::from)
     }
@@ -90,15 +1110,251 @@ impl Config {
     ///
     /// # Errors
     /// Returns an error if neither the flag nor environment variable is set.
-    pub fn get_linear_org(&self) -> Result<String, String> {
-        let env_linear_org = env::var("LINEAR_ORG").ok();
+    pub fn get_linear_org(&self) -> Result<String, Error> {
+        self.linear_org().ok_or_else(|| {
+            exit_status::credentials(
+                "LINEAR_ORG not provided via --linear-org flag or environment variable",
+            )
+        })
+    }
+
+    /// The Linear organization identifier (`--linear-org`, then
+    /// `LINEAR_ORG`), if any.
+    #[must_use]
+    pub fn linear_org(&self) -> Option<String> {
         self.linear_org
-            .as_ref()
-            .or(env_linear_org.as_ref())
+            .clone()
+            .or_else(|| env::var("LINEAR_ORG").ok())
+    }
+
+    /// Gets the Linear GraphQL endpoint from config or environment variable.
+    ///
+    /// # Precedence
+    /// 1. --linear-api-url CLI flag
+    /// 2. `LINEAR_API_URL` environment variable
+    /// 3. `https://api.linear.app/graphql`
+    #[must_use]
+    pub fn get_linear_api_url(&self) -> String {
+        self.linear_api_url
+            .clone()
+            .or_else(|| env::var("LINEAR_API_URL").ok())
+            .unwrap_or_else(|| DEFAULT_LINEAR_API_URL.to_string())
+    }
+
+    /// How to reach GitHub: through `gh`, or with `--github-api` through
+    /// its APIs (see [`crate::github_api`]).
+    ///
+    /// # Errors
+    /// Returns an error if `--github-api` is given without a token.
+    pub fn github(&self) -> Result<GitHub, Error> {
+        match self.github_access {
+            GithubAccess::Cli => Ok(GitHub::Cli(self.github_host.clone())),
+            GithubAccess::Api => GithubApi::new(
+                self.github_host.as_deref(),
+                self.github_repo.as_deref(),
+            )
+            .map(GitHub::Api),
+        }
+    }
+
+    /// Builds the Linear API connection settings (key, endpoint, headers,
+    /// static resolutions).
+    ///
+    /// # Errors
+    /// Returns an error if the Linear API key is not provided.
+    pub fn linear_api(&self) -> Result<LinearApi, Error> {
+        Ok(LinearApi {
+            api_key: self.get_linear_api_key()?,
+            url: self.get_linear_api_url(),
+            headers: self.linear_headers.clone(),
+            resolve: self.linear_resolve.clone(),
+            actor_token: self.get_linear_actor_token(),
+            limiter: self.rate_limiter.clone(),
+            budget: self.api_budget.clone(),
+            debug_dump: self.debug_dump.clone(),
+        })
+    }
+
+    /// Gets the Jira site URL from config or environment variable, without
+    /// a trailing slash.
+    ///
+    /// # Precedence
+    /// 1. --jira-base-url CLI flag
+    /// 2. `JIRA_BASE_URL` environment variable
+    ///
+    /// # Errors
+    /// Returns an error if neither the flag nor environment variable is set.
+    pub fn get_jira_base_url(&self) -> Result<String, Error> {
+        self.jira
+            .base_url
+            .clone()
+            .or_else(|| env::var("JIRA_BASE_URL").ok())
+            .map(|url| url.trim_end_matches('/').to_string())
             .ok_or_else(|| {
-                "LINEAR_ORG not provided via --linear-org flag or environment variable".to_string()
+                exit_status::credentials(
+                    "JIRA_BASE_URL not provided via --jira-base-url flag or environment variable",
+                )
             })
-            .map(String::from)
+    }
+
+    /// Gets the Jira API token from config or environment variable.
+    ///
+    /// # Precedence
+    /// 1. --jira-api-token CLI flag
+    /// 2. `JIRA_API_TOKEN` environment variable
+    ///
+    /// # Errors
+    /// Returns an error if neither the flag nor environment variable is set.
+    pub fn get_jira_api_token(&self) -> Result<String, Error> {
+        self.jira
+            .api_token
+            .clone()
+            .or_else(|| env::var("JIRA_API_TOKEN").ok())
+            .ok_or_else(|| {
+                exit_status::credentials(
+                    "JIRA_API_TOKEN not provided via --jira-api-token flag or environment variable",
+                )
+            })
+    }
+
+    /// Gets the OAuth token mutations are made with from config or
+    /// environment variable (`--linear-actor-token`, then
+    /// `LINEAR_ACTOR_TOKEN`), if any.
+    #[must_use]
+    pub fn get_linear_actor_token(&self) -> Option<String> {
+        self.linear_actor_token
+            .clone()
+            .or_else(|| env::var("LINEAR_ACTOR_TOKEN").ok())
+            .filter(|token| !token.is_empty())
+    }
+
+    /// Gets the secret webhook events are signed with from config or
+    /// environment variable (`--webhook-secret`, then `WEBHOOK_SECRET`), if
+    /// any.
+    #[must_use]
+    pub fn get_webhook_secret(&self) -> Option<String> {
+        self.webhook_secret
+            .clone()
+            .or_else(|| env::var("WEBHOOK_SECRET").ok())
+            .filter(|secret| !secret.is_empty())
+    }
+
+    /// Gets the Slack incoming webhook the run's summary is posted to from
+    /// config or environment variable (`--slack-webhook`, then
+    /// `SLACK_WEBHOOK_URL`), if any.
+    #[must_use]
+    pub fn get_slack_webhook(&self) -> Option<String> {
+        self.slack_webhook
+            .clone()
+            .or_else(|| env::var("SLACK_WEBHOOK_URL").ok())
+            .filter(|url| !url.is_empty())
+    }
+
+    /// Gets the Jira account email from config or environment variable
+    /// (`--jira-email`, then `JIRA_EMAIL`), if any.
+    #[must_use]
+    pub fn get_jira_email(&self) -> Option<String> {
+        self.jira
+            .email
+            .clone()
+            .or_else(|| env::var("JIRA_EMAIL").ok())
+    }
+
+    /// Builds the Jira API connection settings.
+    ///
+    /// With an account email, the token is sent with Basic authentication
+    /// (Jira Cloud API tokens); otherwise as a bearer token (Data Center
+    /// personal access tokens).
+    ///
+    /// # Errors
+    /// Returns an error if the site URL or API token is missing.
+    pub fn jira_api(&self) -> Result<JiraApi, Error> {
+        let token = self.get_jira_api_token()?;
+        let authorization = match self.get_jira_email() {
+            Some(email) => format!(
+                "Basic {}",
+                utils::base64_encode(&format!("{email}:{token}"))
+            ),
+            None => format!("Bearer {token}"),
+        };
+        Ok(JiraApi {
+            base_url: self.get_jira_base_url()?,
+            authorization,
+            limiter: self.rate_limiter.clone(),
+            budget: self.api_budget.clone(),
+            debug_dump: self.debug_dump.clone(),
+        })
+    }
+
+    /// Whether `ticket` is denylisted: the `--exclude-pattern` matches the
+    /// whole ticket ID.
+    ///
+    /// # Errors
+    /// Returns an error if grep can't be run.
+    pub fn is_excluded(&self, ticket: &str) -> Result<bool, Error> {
+        let Some(ref pattern) = self.exclude_pattern else {
+            return Ok(false);
+        };
+        Ok(!utils::grep_whole_lines(ticket, pattern)?.is_empty())
+    }
+
+    /// Whether `ticket` is a Linear ticket ID: the `--ticket-pattern`
+    /// matches it in full, or by default it's 3 uppercase letters, a hyphen
+    /// and a number (`ABC-123`).
+    ///
+    /// # Errors
+    /// Returns an error if grep can't be run.
+    pub fn is_ticket_id(&self, ticket: &str) -> Result<bool, Error> {
+        let Some(ref pattern) = self.ticket_pattern else {
+            return Ok(update_tickets::is_default_ticket_id(ticket));
+        };
+        Ok(!utils::grep_whole_lines(ticket, pattern)?.is_empty())
+    }
+
+    /// Whether tickets in the workflow state `state_name` may be updated
+    /// (without `--update-all-statuses`): it's one of the `--from-states`,
+    /// or "Passing" by default.
+    #[must_use]
+    pub fn is_source_state(&self, state_name: &str) -> bool {
+        if self.from_states.is_empty() {
+            return update_tickets::state_is_passing(state_name);
+        }
+        self.from_states
+            .iter()
+            .any(|state| state.eq_ignore_ascii_case(state_name))
+    }
+
+    /// The states of [`Config::is_source_state`] for log messages, e.g.
+    /// `"QA Done" or "Staged"`.
+    #[must_use]
+    pub fn source_states_label(&self) -> String {
+        if self.from_states.is_empty() {
+            return "\"Passing\"".to_string();
+        }
+        self.from_states
+            .iter()
+            .map(|state| format!("\"{state}\""))
+            .collect::<Vec<_>>()
+            .join(" or ")
+    }
+
+    /// Loads the `--linear-snapshot` file, if one was given.
+    ///
+    /// # Errors
+    /// Returns an error if the snapshot can't be read or is malformed.
+    pub fn snapshot(&self) -> Result<Option<Snapshot>, Error> {
+        self.linear_snapshot
+            .as_deref()
+            .map(Snapshot::load)
+            .transpose()
+    }
+
+    /// Opens the `--cache-dir` cache, if one was given.
+    ///
+    /// # Errors
+    /// Returns an error if the cache directory can't be created.
+    pub fn cache(&self) -> Result<Option<Cache>, Error> {
+        self.cache_dir.as_deref().map(Cache::open).transpose()
     }
 
     /// Parses command-line arguments into a Config struct.
@@ -110,15 +1366,130 @@ impl Config {
     /// Modes:
     ///   parse-notes        Parse release notes for PR numbers
     ///   extract-tickets    Extract Linear tickets from PRs
+    ///   extract-commits    Extract Linear tickets from a release's commits
     ///   update-tickets     Update Linear tickets to completed
+    ///   teams              List Linear teams and workflow states
+    ///   report             Report a release's PRs, tickets and states
+    ///   verify             Check that a release's tickets are completed
+    ///   schema             Print the JSON Schema of machine outputs
+    ///   rollback           Move journaled tickets back to their old states
+    ///   selftest           Smoke-test the update path on a sandbox team
+    ///   run NAME           Run a pipeline defined in the config file
+    ///   serve              Run the orchestrator for GitHub release webhooks
     ///   (default)          Run orchestrator mode (full pipeline)
     ///
     /// Options:
     ///   --release-tag TAG      GitHub release tag
+    ///   --since-tag TAG        Process the releases after TAG
+    ///   --until-tag TAG        Last release processed with --since-tag
+    ///   --previous-tag TAG     Tag extract-commits starts after
+    ///   --commit-source SRC    github or git (extract-commits)
     ///   --linear-api-key KEY   Linear API authentication key
     ///   --linear-org ORG       Linear organization identifier
+    ///   --org-map TEAM=ORG:KEY Route a team's tickets to another workspace
+    ///   --linear-actor-token TOKEN  Make changes as a Linear OAuth app
+    ///   --tracker TRACKER      linear or jira
+    ///   --jira-base-url URL    Jira site URL
+    ///   --jira-api-token TOKEN Jira API token
+    ///   --jira-email EMAIL     Jira account email (Basic authentication)
     ///   --dry-run              Preview changes without updating
+    ///   --quiet                Log only failures and warnings
+    ///   -v, --verbose          Log per-ticket details (-vv: GraphQL payloads)
+    ///   --log-format FMT       text or json (one JSON object per log line)
     ///   --update-all-statuses  Update regardless of current Linear state
+    ///   --from-states STATES   Update tickets in these states, not "Passing"
+    ///   --filter EXPR          Only update tickets matching EXPR
+    ///   --assignee me|EMAIL    Only update tickets assigned to this user
+    ///   --creator me|EMAIL     Only update tickets created by this user
+    ///   --drop-unknown-teams   Skip tickets of teams not in the workspace
+    ///   --follow-duplicates MODE  Update the issue a duplicate ticket duplicates
+    ///   --fail-on-partial      Exit with status 4 if some tickets failed
+    ///   --manifest FILE        Write a dry-run manifest of intended changes
+    ///   --approve HASH         Apply only if the plan matches this manifest
+    ///   --compare-manifest FILE  Report changes since a dry-run manifest
+    ///   --queue-file FILE      Record unprocessed tickets for resuming
+    ///   --checkpoint FILE      Record processed tickets to skip on re-run
+    ///   --journal FILE         Log attempted state changes, for audits and rollback
+    ///   --team KEY             Sandbox team of the selftest mode
+    ///   --order-by-dependencies  Update blockers before blocked tickets
+    ///   --note-blocked         Also comment on blocked tickets
+    ///   --forge FORGE          github or gitlab (MRs, through glab)
+    ///   --discover MODE        Find PRs in the notes, compared commits or both
+    ///   --github-host HOST     GitHub host for gh (GH_HOST)
+    ///   --github-api           Reach GitHub with GITHUB_TOKEN instead of gh
+    ///   --github-repo REPO     Current repository for --github-api
+    ///   --linear-api-url URL   Linear GraphQL endpoint
+    ///   --linear-header H      Extra Linear API header (repeatable)
+    ///   --linear-resolve R     Pin HOST:PORT:ADDRESS for Linear (repeatable)
+    ///   --linear-snapshot FILE Read Linear data from a workspace snapshot
+    ///   --debug-dump FILE      Append unparseable API responses to FILE
+    ///   --locale LOCALE        Language of the end-of-run summary
+    ///   --locale-dir DIR       Directory of <LOCALE>.txt templates
+    ///   --output-format FMT    text, ndjson or json
+    ///   --url-format FMT       id, url or markdown (update-tickets)
+    ///   --explain ID           Trace every decision about a ticket or PR
+    ///   --github-output        Write GitHub Actions outputs and summary
+    ///   --diff                 Print a table of every ticket in dry-run
+    ///   --require-merged       Skip tickets of unmerged PRs (default)
+    ///   --allow-unmerged       Extract tickets from unmerged PRs too
+    ///   --merged-after TIME    Skip PRs merged before TIME
+    ///   --merged-before TIME   Skip PRs merged after TIME
+    ///   --mapping-file FILE    Write the per-PR ticket mapping (JSON)
+    ///   --max-comments N       Scan at most N comments per PR
+    ///   --batch-prs            Fetch PRs many per GitHub GraphQL query
+    ///   --pr-label LABEL       Only take tickets of PRs with this label
+    ///   --path-filter GLOB     Only take tickets of PRs changing these paths
+    ///   --warn-unlinked-prs    Report PRs without Linear tickets
+    ///   --no-stack-detection   Search text of stacked sibling PRs too
+    ///   --other-org-urls MODE  drop or tag tickets of other workspaces' URLs
+    ///   --trailer-keys KEYS    Commit trailers linking tickets (Linear: ...)
+    ///   --ignore-tickets LIST  Never extract these tickets (file or a,b,...)
+    ///   --ignore-prs LIST      Skip these PRs (file or 12,owner/repo#34,...)
+    ///   --ignore-token REGEX   Never take tokens matching REGEX for tickets
+    ///   --no-default-ignores   Drop the built-in UTF-8, SHA-256, ... list
+    ///   --prefix-map OLD=NEW   Remap renamed team keys (repeatable)
+    ///   --exclude-pattern RE   Never extract or update matching tickets
+    ///   --ticket-pattern RE    Linear ticket IDs (default: ABC-123)
+    ///   --stdin-timeout SECS   Give up on a silent terminal stdin (0: never)
+    ///   --jobs N               Releases processed concurrently (orchestrator)
+    ///   --in-process           Run the orchestrator's stages on threads
+    ///   --concurrency N        PRs fetched and tickets updated concurrently
+    ///   --batch-lookups        Look up tickets many per Linear query
+    ///   --cache-dir DIR        Cache shared by concurrent pipelines
+    ///   --rate-limit N[/s]     Requests per second across all pipelines
+    ///   --api-budget N         Most GitHub and Linear requests of the run
+    ///   --comment-interval MS  Minimum delay between comments
+    ///   --comment-batch-size N Comments per batch
+    ///   --comment-batch-pause S  Pause between comment batches
+    ///   --comment-progress FILE  Record posted comments for resuming
+    ///   --comment-release      Comment the release on completed tickets
+    ///   --comment-template FILE  Release comment with the release URL and PRs
+    ///   --release-issue-team KEY  Create a release checklist issue in KEY
+    ///   --linear-project PROJECT  Complete only the release project's tickets
+    ///   --add-to-project PROJECT  Add updated tickets to a Linear project
+    ///   --add-label LABEL      Apply a label to updated tickets
+    ///   --set-custom-field NAME=VALUE  Set label group NAME to VALUE on them
+    ///   --linear-cycle CYCLE   Add updated tickets to a cycle of their team
+    ///   --attach-release       Attach the release to updated tickets
+    ///   --webhook-url URL      POST a ticket.released event per updated ticket
+    ///   --webhook-secret SECRET  Sign the events with HMAC-SHA256
+    ///   --slack-webhook URL    Post a summary of the run to Slack
+    ///   --state-route PATTERN=STATE  Move tickets of matching tags to STATE
+    ///   --target-state NAME    Move tickets to NAME instead of completing them
+    ///   --target-state-type TYPE  Move tickets to a state of TYPE
+    ///   --quiet-updates        Batch updates to reduce notification noise
+    ///   --history-file FILE    Record processed tickets across runs
+    ///   --repeat-release MODE  warn or fail on tickets of earlier releases
+    ///   --quarantine-after N   Stop retrying tickets that failed N runs in a row
+    ///   --clear-quarantine ID  Retry a quarantined ticket
+    ///   --commit-status URL    Post the sync result on the release commit
+    ///   --deployment-environment ENV  Wait for a GitHub deployment to ENV
+    ///   --deployment-url URL   Wait for URL to report the release version
+    ///   --deployment-timeout SECS  How long to wait for the deployment
+    ///   --config FILE          Read default flags from a TOML file
+    ///   --no-config            Don't read .release-linear.toml
+    ///   --listen ADDR          Address serve listens on
+    ///   --github-webhook-secret SECRET  Secret of serve's GitHub webhook
     ///
     /// Files:
     ///   -                      Read from stdin (can be mixed with files)
@@ -130,9 +1501,14 @@ impl Config {
     /// - If no input sources for extract-tickets/update-tickets, defaults to
     ///   stdin
     ///
+    /// # Config File
+    /// In orchestrator mode, flags not given on the command line are read
+    /// from `.release-linear.toml` or `--config FILE` (see
+    /// [`crate::config_file`]).
+    ///
     /// # Errors
     /// Returns an error if stdin (-) is specified more than once.
-    pub fn from_args() -> Result<Self, String> {
+    pub fn from_args() -> Result<Self, Error> {
         let args: Vec<String> = env::args().collect();
 
         if args.iter().any(|arg| arg == "--help" || arg == "-h") {
@@ -148,75 +1524,782 @@ impl Config {
             std::process::exit(0);
         }
 
-        let (mode, start_idx) = parse_mode_and_start_index(&args)?;
+        Self::from_arg_list(&args[1..])
+    }
+
+    /// The configuration of `mode` with every flag at its default, for a
+    /// library to set the fields it needs, e.g.:
+    ///
+    /// ```ignore
+    /// let mut config = Config::new(Mode::UpdateTickets);
+    /// config.linear_org = Some("acme".to_string());
+    /// config.dry_run = true;
+    /// ```
+    ///
+    /// Unlike [`Config::from_arg_list`], the fields aren't validated
+    /// together.
+    #[must_use]
+    pub fn new(mode: Mode) -> Self {
+        let mut parsed = ParsedArgs::new();
+        apply_defaults(mode, &mut parsed);
+        Self::from_parsed(mode, parsed)
+    }
+
+    /// How the run logs (`--quiet`, `-v`, `-vv`, `--log-format`).
+    #[must_use]
+    pub fn log_settings(&self) -> LogSettings {
+        LogSettings {
+            level: self.log_level,
+            format: self.log_format,
+        }
+    }
+
+    /// Parses `args`, the command line after the program name, as
+    /// [`Config::from_args`] does, e.g. `["parse-notes", "--release-tag",
+    /// "v1.2.3"]`. `--help` and `--version` are not accepted.
+    ///
+    /// # Errors
+    /// Returns an error if the arguments are invalid.
+    pub fn from_arg_list(args: &[String]) -> Result<Self, Error> {
+        let args: Vec<String> = iter::once(env!("CARGO_PKG_NAME").to_string())
+            .chain(args.iter().cloned())
+            .collect();
+        let (mode, start_idx) = parse_mode_and_start_index(&args)?;
+        if matches!(mode, Mode::Schema) && args.len() > start_idx {
+            return Err(Error::Config(
+                "schema does not accept arguments".to_string(),
+            ));
+        }
+        if matches!(mode, Mode::Run) {
+            return parse_run(&args, start_idx);
+        }
+        if matches!(mode, Mode::Serve) {
+            return parse_serve(&args, start_idx);
+        }
+        let args = apply_config_file(mode, args, start_idx)?;
         let mut parsed = parse_flags_and_inputs(mode, &args, start_idx)?;
         apply_defaults(mode, &mut parsed);
         validate_config(mode, &parsed)?;
 
-        Ok(Self {
+        Ok(Self::from_parsed(mode, parsed))
+    }
+
+    /// Builds the configuration of `mode` from validated flags.
+    fn from_parsed(mode: Mode, parsed: ParsedArgs) -> Self {
+        Self {
             mode,
+            // Derived from several flags, before they are moved.
+            release_range: parsed.release_range(),
+            comment_release: parsed.comment_release(),
+            api_budget: parsed.api_budget(),
+            rate_limiter: parsed.rate_limiter(),
             release_tag: parsed.release_tag,
+            release_tags: parsed.release_tags,
+            jobs: parsed.jobs,
+            concurrency: parsed.concurrency,
+            lookups: parsed.lookups,
+            cache_dir: parsed.cache_dir,
             linear_api_key: parsed.linear_api_key,
             linear_org: parsed.linear_org,
+            org_map: parsed.org_map,
+            tracker: parsed.tracker,
+            jira: parsed.jira,
             input_sources: parsed.input_sources,
             dry_run: parsed.dry_run,
             update_all_statuses: parsed.update_all_statuses,
-        })
+            from_states: parsed.from_states,
+            filter: parsed.filter,
+            assignee: parsed.assignee,
+            creator: parsed.creator,
+            unknown_teams: parsed.unknown_teams,
+            duplicates: parsed.duplicates,
+            partial_failure: parsed.partial_failure,
+            manifest: parsed.manifest,
+            approve: parsed.approve,
+            compare_manifest: parsed.compare_manifest,
+            dependencies: parsed.dependencies,
+            forge: parsed.forge,
+            discover: parsed.discover,
+            github_host: parsed.github_host,
+            github_access: parsed.github_access,
+            github_repo: parsed.github_repo,
+            linear_api_url: parsed.linear_api_url,
+            linear_headers: parsed.linear_headers,
+            linear_actor_token: parsed.linear_actor_token,
+            linear_resolve: parsed.linear_resolve,
+            linear_snapshot: parsed.linear_snapshot,
+            debug_dump: parsed.debug_dump,
+            log_level: parsed.log_level,
+            log_format: parsed.log_format,
+            locale: parsed.locale,
+            locale_dir: parsed.locale_dir,
+            output_format: parsed.output_format,
+            github_output: parsed.github_output,
+            dry_run_output: parsed.dry_run_output,
+            url_format: parsed.url_format,
+            explain: parsed.explain,
+            prefix_map: parsed.prefix_map,
+            exclude_pattern: parsed.exclude_pattern,
+            ticket_pattern: parsed.ticket_pattern,
+            merge_policy: parsed.merge_policy,
+            merged_after: parsed.merged_after,
+            merged_before: parsed.merged_before,
+            unlinked_prs: parsed.unlinked_prs,
+            stacked_prs: parsed.stacked_prs,
+            other_org_urls: parsed.other_org_urls,
+            trailer_keys: parsed.trailer_keys,
+            ignore_list: parsed.ignore_list,
+            false_positives: parsed.false_positives,
+            mapping_file: parsed.mapping_file,
+            max_comments: parsed.max_comments,
+            pr_fetches: parsed.pr_fetches,
+            pr_scope: parsed.pr_scope,
+            commit_range: parsed.commit_range,
+            stdin_timeout_secs: parsed.stdin_timeout_secs,
+            comment_pacing: parsed.comment_pacing,
+            comment_template: parsed.comment_template,
+            release_issue_team: parsed.release_issue_team,
+            linear_project: parsed.linear_project,
+            add_to_project: parsed.add_to_project,
+            add_label: parsed.add_label,
+            custom_field: parsed.custom_field,
+            linear_cycle: parsed.linear_cycle,
+            release_attachment: parsed.release_attachment,
+            state_routes: parsed.state_routes,
+            target_state: parsed.target_state,
+            target_state_type: parsed.target_state_type,
+            notifications: parsed.notifications,
+            queue_file: parsed.queue_file,
+            checkpoint: parsed.checkpoint,
+            journal: parsed.journal,
+            selftest_team: parsed.selftest_team,
+            history_file: parsed.history_file,
+            repeat_release: parsed.repeat_release,
+            quarantine_after: parsed.quarantine_after,
+            clear_quarantine: parsed.clear_quarantine,
+            commit_status_url: parsed.commit_status_url,
+            deployment: parsed.deployment,
+            webhook_url: parsed.webhook_url,
+            webhook_secret: parsed.webhook_secret,
+            slack_webhook: parsed.slack_webhook,
+            pipeline: parsed.pipeline,
+            server: parsed.server,
+            stages: parsed.stages,
+            output: Output::Stdout,
+        }
     }
 }
 
 #[derive(Debug)]
 struct ParsedArgs {
     release_tag: Option<String>,
+    release_tags: Vec<String>,
+    since_tag: Option<String>,
+    until_tag: Option<String>,
+    jobs: Option<usize>,
+    concurrency: Option<usize>,
+    lookups: Lookups,
+    cache_dir: Option<String>,
+    rate_limit: Option<u32>,
+    api_budget: Option<u64>,
     linear_api_key: Option<String>,
     linear_org: Option<String>,
+    org_map: OrgMap,
+    tracker: Tracker,
+    jira: JiraCredentials,
     input_sources: Vec<InputSource>,
     dry_run: bool,
     update_all_statuses: bool,
+    from_states: Vec<String>,
+    filter: Option<Filter>,
+    assignee: Option<String>,
+    creator: Option<String>,
+    unknown_teams: UnknownTeams,
+    duplicates: Duplicates,
+    partial_failure: PartialFailure,
+    manifest: Option<String>,
+    approve: Option<String>,
+    compare_manifest: Option<String>,
+    dependencies: Dependencies,
+    forge: Forge,
+    discover: Discover,
+    github_host: Option<String>,
+    github_access: GithubAccess,
+    github_repo: Option<String>,
+    linear_api_url: Option<String>,
+    linear_headers: Vec<String>,
+    linear_actor_token: Option<String>,
+    linear_resolve: Vec<String>,
+    linear_snapshot: Option<String>,
+    debug_dump: Option<String>,
+    log_level: LogLevel,
+    log_format: LogFormat,
+    locale: Option<String>,
+    locale_dir: Option<String>,
+    output_format: OutputFormat,
+    github_output: GithubOutput,
+    dry_run_output: DryRunOutput,
+    url_format: UrlFormat,
+    explain: Option<Subject>,
+    prefix_map: PrefixMap,
+    exclude_pattern: Option<String>,
+    ticket_pattern: Option<String>,
+    merge_policy: MergePolicy,
+    merged_after: Option<String>,
+    merged_before: Option<String>,
+    unlinked_prs: UnlinkedPrs,
+    stacked_prs: StackedPrs,
+    other_org_urls: OtherOrgUrls,
+    trailer_keys: Vec<String>,
+    ignore_list: IgnoreList,
+    false_positives: FalsePositives,
+    mapping_file: Option<String>,
+    max_comments: Option<usize>,
+    pr_scope: PrScope,
+    commit_range: CommitRange,
+    pr_fetches: PrFetches,
+    stdin_timeout_secs: Option<u64>,
+    comment_pacing: Pacing,
+    comment_release: bool,
+    comment_template: Option<String>,
+    release_issue_team: Option<String>,
+    linear_project: Option<String>,
+    add_to_project: Option<String>,
+    add_label: Option<String>,
+    custom_field: Option<CustomField>,
+    linear_cycle: Option<Cycle>,
+    release_attachment: ReleaseAttachment,
+    state_routes: StateRoutes,
+    target_state: Option<String>,
+    target_state_type: Option<String>,
+    notifications: Notifications,
+    queue_file: Option<String>,
+    checkpoint: Option<String>,
+    journal: Option<String>,
+    selftest_team: Option<String>,
+    history_file: Option<String>,
+    repeat_release: RepeatRelease,
+    quarantine_after: Option<usize>,
+    clear_quarantine: Vec<String>,
+    commit_status_url: Option<String>,
+    deployment: Deployment,
+    webhook_url: Option<String>,
+    webhook_secret: Option<String>,
+    slack_webhook: Option<String>,
+    pipeline: Option<Pipeline>,
+    server: Option<Server>,
+    stages: Stages,
 }
 
-fn parse_mode_and_start_index(
-    args: &[String],
-) -> Result<(Mode, usize), String> {
+impl ParsedArgs {
+    /// Whether tickets get a release comment: with `--comment-release`, or
+    /// the `--comment-template` that implies it.
+    fn comment_release(&self) -> bool {
+        self.comment_release || self.comment_template.is_some()
+    }
+
+    /// The `--since-tag` range, if given.
+    fn release_range(&self) -> Option<ReleaseRange> {
+        Some(ReleaseRange {
+            since: self.since_tag.clone()?,
+            until: self.until_tag.clone(),
+        })
+    }
+
+    /// The `--api-budget`, shared through the `--cache-dir` if given.
+    fn api_budget(&self) -> Option<ApiBudget> {
+        self.api_budget
+            .map(|limit| ApiBudget::new(limit, self.cache_dir.as_deref()))
+    }
+
+    /// The `--rate-limit`, shared through the `--cache-dir` if given.
+    fn rate_limiter(&self) -> Option<RateLimiter> {
+        self.rate_limit.map(|requests_per_sec| {
+            RateLimiter::new(requests_per_sec, self.cache_dir.as_deref())
+        })
+    }
+
+    /// Every flag at its default.
+    fn new() -> Self {
+        Self {
+            release_tag: None,
+            release_tags: Vec::new(),
+            since_tag: None,
+            until_tag: None,
+            jobs: None,
+            concurrency: None,
+            lookups: Lookups::Single,
+            cache_dir: None,
+            rate_limit: None,
+            api_budget: None,
+            linear_api_key: None,
+            linear_org: None,
+            org_map: OrgMap::default(),
+            tracker: Tracker::Linear,
+            jira: JiraCredentials::default(),
+            input_sources: Vec::new(),
+            dry_run: false,
+            update_all_statuses: false,
+            from_states: Vec::new(),
+            filter: None,
+            assignee: None,
+            creator: None,
+            unknown_teams: UnknownTeams::Warn,
+            duplicates: Duplicates::Ignore,
+            partial_failure: PartialFailure::Succeed,
+            manifest: None,
+            approve: None,
+            compare_manifest: None,
+            dependencies: Dependencies::Ignore,
+            forge: Forge::GitHub,
+            discover: Discover::Notes,
+            github_host: None,
+            github_access: GithubAccess::Cli,
+            github_repo: None,
+            linear_api_url: None,
+            linear_headers: Vec::new(),
+            linear_actor_token: None,
+            linear_resolve: Vec::new(),
+            linear_snapshot: None,
+            debug_dump: None,
+            log_level: LogLevel::Info,
+            log_format: LogFormat::Text,
+            locale: None,
+            locale_dir: None,
+            output_format: OutputFormat::Text,
+            github_output: GithubOutput::Off,
+            dry_run_output: DryRunOutput::Tickets,
+            url_format: UrlFormat::Url,
+            explain: None,
+            prefix_map: PrefixMap::default(),
+            exclude_pattern: None,
+            ticket_pattern: None,
+            merge_policy: MergePolicy::RequireMerged,
+            merged_after: None,
+            merged_before: None,
+            unlinked_prs: UnlinkedPrs::Ignore,
+            stacked_prs: StackedPrs::Detect,
+            other_org_urls: OtherOrgUrls::Drop,
+            trailer_keys: Vec::new(),
+            ignore_list: IgnoreList::default(),
+            false_positives: FalsePositives::default(),
+            mapping_file: None,
+            max_comments: None,
+            pr_scope: PrScope::default(),
+            commit_range: CommitRange::default(),
+            pr_fetches: PrFetches::Single,
+            stdin_timeout_secs: None,
+            comment_pacing: Pacing::default(),
+            comment_release: false,
+            comment_template: None,
+            release_issue_team: None,
+            linear_project: None,
+            add_to_project: None,
+            add_label: None,
+            custom_field: None,
+            linear_cycle: None,
+            release_attachment: ReleaseAttachment::Off,
+            state_routes: StateRoutes::default(),
+            target_state: None,
+            target_state_type: None,
+            notifications: Notifications::Normal,
+            queue_file: None,
+            checkpoint: None,
+            journal: None,
+            selftest_team: None,
+            history_file: None,
+            repeat_release: RepeatRelease::Warn,
+            quarantine_after: None,
+            clear_quarantine: Vec::new(),
+            commit_status_url: None,
+            deployment: Deployment::default(),
+            webhook_url: None,
+            webhook_secret: None,
+            slack_webhook: None,
+            pipeline: None,
+            server: None,
+            stages: Stages::Spawned,
+        }
+    }
+}
+
+fn parse_mode_and_start_index(args: &[String]) -> Result<(Mode, usize), Error> {
     let first = args.get(1).ok_or_else(|| {
-        "Orchestrator mode requires --release-tag flag".to_string()
+        Error::Config(
+            "Orchestrator mode requires --release-tag flag".to_string(),
+        )
     })?;
-    if first.starts_with("--") {
+    if first.starts_with("--") || LOG_LEVEL_FLAGS.contains(&first.as_str()) {
         return Ok((Mode::Orchestrator, 1));
     }
-    let mode = match first.as_str() {
+    let mode = parse_mode_name(first)
+        .ok_or_else(|| Error::Config(format!("Unknown mode: {first}")))?;
+    Ok((mode, 2))
+}
+
+/// The mode named `name` on the command line (or by a pipeline stage).
+fn parse_mode_name(name: &str) -> Option<Mode> {
+    Some(match name {
         "parse-notes" => Mode::ParseNotes,
         "extract-tickets" => Mode::ExtractTickets,
+        "extract-commits" => Mode::ExtractCommits,
         "update-tickets" => Mode::UpdateTickets,
-        other => return Err(format!("Unknown mode: {other}")),
+        "teams" => Mode::Teams,
+        "report" => Mode::Report,
+        "verify" => Mode::Verify,
+        "schema" => Mode::Schema,
+        "rollback" => Mode::Rollback,
+        "selftest" => Mode::Selftest,
+        "run" => Mode::Run,
+        "serve" => Mode::Serve,
+        _ => return None,
+    })
+}
+
+/// Inserts the flags of the config file (see [`crate::config_file`]) ahead
+/// of the command-line flags, leaving out those given on the command line,
+/// and removes `--config` and `--no-config`.
+///
+/// Only orchestrator mode reads a config file: the stages it spawns get
+/// every setting as flags.
+///
+/// # Errors
+/// Returns an error if `--config` is given without a path or outside
+/// orchestrator mode, or the config file is invalid.
+fn apply_config_file(
+    mode: Mode,
+    args: Vec<String>,
+    start_idx: usize,
+) -> Result<Vec<String>, Error> {
+    let mut config_path = None;
+    let mut no_config = false;
+    let mut flags = Vec::new();
+    let mut rest = args.iter().skip(start_idx);
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--config" => {
+                let path = rest.next().ok_or_else(|| {
+                    Error::Config("--config requires a value".to_string())
+                })?;
+                config_path = Some(path.clone());
+            }
+            "--no-config" => no_config = true,
+            _ => flags.push(arg.clone()),
+        }
+    }
+    if !matches!(mode, Mode::Orchestrator) {
+        if config_path.is_some() || no_config {
+            return Err(Error::Config(
+                "Only orchestrator mode accepts --config and --no-config"
+                    .to_string(),
+            ));
+        }
+        return Ok(args);
+    }
+    if config_path.is_some() && no_config {
+        return Err(Error::Config(
+            "--config cannot be combined with --no-config".to_string(),
+        ));
+    }
+
+    let mut file_args = Vec::new();
+    let path = config_path
+        .clone()
+        .unwrap_or_else(config_file::default_path);
+    // A missing default file just means there are no defaults.
+    if !no_config
+        && (config_path.is_some() || std::path::Path::new(&path).exists())
+    {
+        // A log level on the command line replaces the file's.
+        let log_level_given = flags
+            .iter()
+            .any(|flag| LOG_LEVEL_FLAGS.contains(&flag.as_str()));
+        for entry in config_file::read(&path)? {
+            let overridden = flags.contains(&entry.flag)
+                || (log_level_given
+                    && LOG_LEVEL_FLAGS.contains(&entry.flag.as_str()));
+            if !overridden {
+                file_args.extend(entry.args);
+            }
+        }
+        // Checked on their own, so errors name the file.
+        let mut checked = vec![String::new()];
+        checked.extend(file_args.iter().cloned());
+        parse_flags_and_inputs(mode, &checked, 1)
+            .map_err(|e| e.context(path.as_str()))?;
+    }
+
+    let mut merged = args[..start_idx].to_vec();
+    merged.extend(file_args);
+    merged.extend(flags);
+    Ok(merged)
+}
+
+/// Parses `run NAME [--config FILE] [--release-tag TAG] [--dry-run]`, and
+/// loads pipeline `NAME` from the config file (see [`crate::pipeline`]).
+///
+/// # Errors
+/// Returns an error if another flag is given, the pipeline can't be loaded,
+/// or one of its stages is invalid.
+fn parse_run(args: &[String], start_idx: usize) -> Result<Config, Error> {
+    let mut parsed = ParsedArgs::new();
+    let mut name = None;
+    let mut path = None;
+    let mut i = start_idx;
+    while let Some(arg) = args.get(i) {
+        if let Some(value) = take_flag_value(args, &mut i, "--config")? {
+            path = Some(value);
+        } else if let Some(value) =
+            take_flag_value(args, &mut i, "--release-tag")?
+        {
+            if parsed.release_tag.replace(value).is_some() {
+                return Err(Error::Config(
+                    "run accepts one --release-tag".to_string(),
+                ));
+            }
+        } else if arg == "--dry-run" {
+            parsed.dry_run = true;
+            i += 1;
+        } else if parse_log_level_flag(arg, &mut parsed)? {
+            i += 1;
+        } else if let Some(value) =
+            take_flag_value(args, &mut i, "--log-format")?
+        {
+            parsed.log_format = parse_log_format(&value)?;
+        } else if arg.starts_with('-') {
+            return Err(Error::Config(format!(
+                "run does not accept {arg}; set the flags of each stage in the pipeline"
+            )));
+        } else if name.replace(arg.clone()).is_some() {
+            return Err(Error::Config(
+                "run takes a single pipeline name".to_string(),
+            ));
+        } else {
+            i += 1;
+        }
+    }
+    let name = name.ok_or_else(|| {
+        Error::Config(
+            "run requires the name of a pipeline defined in the config file"
+                .to_string(),
+        )
+    })?;
+    let path = path.unwrap_or_else(config_file::default_path);
+    let entries = config_file::read_pipeline(&path, &name)?;
+    let mut stages = Vec::new();
+    for (idx, entry) in entries.iter().enumerate() {
+        let at = |e: Error| {
+            e.context(format!(
+                "{path}: stage {} ({}) of pipeline {name}",
+                idx + 1,
+                entry.stage
+            ))
+        };
+        let mut stage =
+            pipeline_stage(entry, entries.get(idx + 1), &parsed).map_err(at)?;
+        // A reading stage takes the records of the stage producing them, or
+        // the run's stdin at the start.
+        let producers = reads_records_of(&entry.stage);
+        if !producers.is_empty()
+            && let Some(previous) = idx.checked_sub(1).map(|idx| &entries[idx])
+        {
+            if !producers.contains(&previous.stage.as_str()) {
+                let producer = producers.join(" or ");
+                return Err(at(Error::Config(format!(
+                    "it reads {producer} records, so it must come first or right after {producer}"
+                ))));
+            }
+            stage.input = StageInput::Piped;
+        }
+        stages.push(stage);
+    }
+    parsed.pipeline = Some(Pipeline { name, stages });
+    Ok(Config::from_parsed(Mode::Run, parsed))
+}
+
+/// Parses `serve [--listen ADDR] [--github-webhook-secret SECRET]
+/// [FLAGS...]`, where `FLAGS` are the orchestrator flags every release is
+/// processed with (see [`crate::serve`]). They are checked as if the
+/// orchestrator was run with them.
+///
+/// # Errors
+/// Returns an error if a flag names the releases to process, or the
+/// orchestrator flags are invalid.
+fn parse_serve(args: &[String], start_idx: usize) -> Result<Config, Error> {
+    let mut parsed = ParsedArgs::new();
+    let mut server = Server {
+        listen: serve::DEFAULT_LISTEN.to_string(),
+        secret: None,
+        args: Vec::new(),
+        repository: None,
     };
-    Ok((mode, 2))
+    let mut i = start_idx;
+    while let Some(arg) = args.get(i) {
+        let flag = arg.split('=').next().unwrap_or_default();
+        if let Some(value) = take_flag_value(args, &mut i, "--listen")? {
+            server.listen = value;
+        } else if let Some(value) =
+            take_flag_value(args, &mut i, "--github-webhook-secret")?
+        {
+            server.secret = Some(value);
+        } else if let Some(value) =
+            take_flag_value(args, &mut i, "--log-format")?
+        {
+            // The stages of each release log in the server's format.
+            parsed.log_format = parse_log_format(&value)?;
+            server.args.extend(["--log-format".to_string(), value]);
+        } else if ["--release-tag", "--since-tag", "--until-tag"]
+            .contains(&flag)
+        {
+            return Err(Error::Config(format!(
+                "serve does not accept {flag}; it processes the release of each webhook event"
+            )));
+        } else {
+            // The stages of each release log as much as the server.
+            parse_log_level_flag(arg, &mut parsed)?;
+            server.args.push(arg.clone());
+            i += 1;
+        }
+    }
+    let checked = server
+        .release_config("v0.0.0")
+        .map_err(|e| e.context("Invalid orchestrator flags for serve"))?;
+    if checked.forge != Forge::GitHub {
+        return Err(Error::Config(
+            "serve takes GitHub release webhooks; --forge gitlab is not supported"
+                .to_string(),
+        ));
+    }
+    server.repository = checked.github_repo;
+    parsed.server = Some(server);
+    Ok(Config::from_parsed(Mode::Serve, parsed))
+}
+
+/// The modes whose ndjson records stage `mode` reads, if it reads any:
+/// extract-tickets reads PRs from parse-notes, update-tickets tickets from
+/// extract-tickets or extract-commits.
+fn reads_records_of(mode: &str) -> &'static [&'static str] {
+    match mode {
+        "extract-tickets" => &["parse-notes"],
+        "update-tickets" => &["extract-tickets", "extract-commits"],
+        _ => &[],
+    }
+}
+
+/// Builds a stage of a `run` pipeline from its config file entry: passes
+/// `run`'s `--release-tag`, `--dry-run`, log level and `--log-format`
+/// (unless the stage sets its own) on to it, and makes it write
+/// ndjson when the `next` stage reads its output. The result is checked as
+/// if the stage was run on its own.
+///
+/// # Errors
+/// Returns an error if the entry doesn't name a mode a pipeline can run, or
+/// its flags are invalid.
+fn pipeline_stage(
+    entry: &config_file::StageEntry,
+    next: Option<&config_file::StageEntry>,
+    run: &ParsedArgs,
+) -> Result<Stage, Error> {
+    let mode = parse_mode_name(&entry.stage)
+        .filter(|mode| {
+            !matches!(mode, Mode::Schema | Mode::Run | Mode::Selftest)
+        })
+        .ok_or_else(|| {
+            Error::Config(format!(
+                "unknown stage {}; expected parse-notes, extract-tickets, extract-commits, update-tickets, teams, report, verify or rollback",
+                entry.stage
+            ))
+        })?;
+    let mut args = entry.args.clone();
+    let has = |args: &[String], flag: &str| {
+        args.iter()
+            .any(|arg| arg == flag || arg.starts_with(&format!("{flag}=")))
+    };
+    if let Some(ref tag) = run.release_tag
+        && matches!(
+            mode,
+            Mode::ParseNotes
+                | Mode::ExtractCommits
+                | Mode::UpdateTickets
+                | Mode::Report
+                | Mode::Verify
+        )
+        && !has(&args, "--release-tag")
+    {
+        args.extend(["--release-tag".to_string(), tag.clone()]);
+    }
+    if run.dry_run
+        && matches!(mode, Mode::UpdateTickets | Mode::Rollback)
+        && !has(&args, "--dry-run")
+    {
+        args.push("--dry-run".to_string());
+    }
+    if let Some(flag) = run.log_level.flag()
+        && !LOG_LEVEL_FLAGS.iter().any(|level| has(&args, level))
+    {
+        args.push(flag.to_string());
+    }
+    if run.log_format != LogFormat::Text && !has(&args, "--log-format") {
+        args.extend([
+            "--log-format".to_string(),
+            run.log_format.name().to_string(),
+        ]);
+    }
+    if next.is_some_and(|next| {
+        reads_records_of(&next.stage).contains(&entry.stage.as_str())
+    }) {
+        if has(&args, "--output-format") {
+            return Err(Error::Config(
+                "its output is piped to the next stage as ndjson, so it can't set output-format"
+                    .to_string(),
+            ));
+        }
+        args.extend(["--output-format".to_string(), "ndjson".to_string()]);
+    }
+
+    let mut checked = vec![String::new()];
+    checked.extend(args.iter().cloned());
+    let mut parsed = parse_flags_and_inputs(mode, &checked, 1)?;
+    apply_defaults(mode, &mut parsed);
+    validate_config(mode, &parsed)?;
+    Ok(Stage {
+        mode: entry.stage.clone(),
+        args,
+        input: StageInput::Own,
+    })
 }
 
 fn parse_flags_and_inputs(
     mode: Mode,
     args: &[String],
     start_idx: usize,
-) -> Result<ParsedArgs, String> {
-    let mut parsed = ParsedArgs {
-        release_tag: None,
-        linear_api_key: None,
-        linear_org: None,
-        input_sources: Vec::new(),
-        dry_run: false,
-        update_all_statuses: false,
-    };
+) -> Result<ParsedArgs, Error> {
+    let mut parsed = ParsedArgs::new();
 
     let mut stdin_used = false;
     let mut i = start_idx;
     while i < args.len() {
-        if parse_common_flags(args, &mut i, &mut parsed)? {
+        if parse_common_flags(args, &mut i, &mut parsed)?
+            || parse_extraction_flags(args, &mut i, &mut parsed)?
+            || parse_reference_flags(args, &mut i, &mut parsed)?
+            || parse_approval_flags(args, &mut i, &mut parsed)?
+            || parse_output_flags(args, &mut i, &mut parsed)?
+            || parse_forge_flags(args, &mut i, &mut parsed)?
+            || parse_network_flags(args, &mut i, &mut parsed)?
+            || parse_tracker_flags(args, &mut i, &mut parsed)?
+            || parse_release_tracking_flags(args, &mut i, &mut parsed)?
+            || parse_grouping_flags(args, &mut i, &mut parsed)?
+            || parse_pipeline_flags(args, &mut i, &mut parsed)?
+            || parse_deployment_flags(args, &mut i, &mut parsed)?
+            || parse_history_flags(args, &mut i, &mut parsed)?
+            || parse_webhook_flags(args, &mut i, &mut parsed)?
+        {
             continue;
         }
 
-        let arg = args
-            .get(i)
-            .ok_or_else(|| "Internal error while parsing args".to_string())?;
+        let arg = args.get(i).ok_or_else(|| {
+            Error::Other("Internal error while parsing args".to_string())
+        })?;
 
         if arg == "-" {
             handle_stdin_arg(mode, &mut parsed, &mut stdin_used)?;
@@ -225,7 +2308,7 @@ fn parse_flags_and_inputs(
         }
 
         if arg.starts_with("--") {
-            return Err(format!("Unknown flag: {arg}"));
+            return Err(Error::Config(format!("Unknown flag: {arg}")));
         }
 
         handle_file_arg(mode, arg, &mut parsed)?;
@@ -235,239 +2318,3079 @@ fn parse_flags_and_inputs(
     Ok(parsed)
 }
 
-fn parse_common_flags(
+/// Parses the flags choosing which PRs extract-tickets takes and how it
+/// fetches them (`--require-merged`, `--allow-unmerged`,
+/// `--warn-unlinked-prs`, `--no-stack-detection`, `--merged-after`,
+/// `--merged-before`, `--mapping-file`, `--max-comments`, `--batch-prs`,
+/// `--pr-label`, `--path-filter`).
+fn parse_extraction_flags(
     args: &[String],
     i: &mut usize,
     parsed: &mut ParsedArgs,
-) -> Result<bool, String> {
-    let arg = args
-        .get(*i)
-        .ok_or_else(|| "Internal error while parsing args".to_string())?;
+) -> Result<bool, Error> {
+    let arg = args.get(*i).ok_or_else(|| {
+        Error::Other("Internal error while parsing args".to_string())
+    })?;
 
-    if arg == "--dry-run" {
-        parsed.dry_run = true;
+    if arg == "--require-merged" {
+        parsed.merge_policy = MergePolicy::RequireMerged;
         *i += 1;
         return Ok(true);
     }
 
-    if arg == "--update-all-statuses" {
-        parsed.update_all_statuses = true;
+    if arg == "--allow-unmerged" {
+        parsed.merge_policy = MergePolicy::AllowUnmerged;
         *i += 1;
         return Ok(true);
     }
 
-    if let Some(value) = arg.strip_prefix("--release-tag=") {
-        parsed.release_tag = Some(value.to_string());
+    if arg == "--warn-unlinked-prs" {
+        parsed.unlinked_prs = UnlinkedPrs::Warn;
         *i += 1;
         return Ok(true);
     }
-    if arg == "--release-tag" {
-        let value = args
-            .get(*i + 1)
-            .ok_or_else(|| "Missing value for --release-tag".to_string())?;
-        parsed.release_tag = Some(value.clone());
-        *i += 2;
+
+    if arg == "--no-stack-detection" {
+        parsed.stacked_prs = StackedPrs::Ignore;
+        *i += 1;
         return Ok(true);
     }
 
-    if let Some(value) = arg.strip_prefix("--linear-api-key=") {
-        parsed.linear_api_key = Some(value.to_string());
-        *i += 1;
+    if let Some(value) = take_flag_value(args, i, "--merged-after")? {
+        utils::parse_timestamp(&value)
+            .map_err(|e| e.context("Invalid --merged-after"))?;
+        parsed.merged_after = Some(value);
         return Ok(true);
     }
-    if arg == "--linear-api-key" {
-        let value = args
-            .get(*i + 1)
-            .ok_or_else(|| "Missing value for --linear-api-key".to_string())?;
-        parsed.linear_api_key = Some(value.clone());
-        *i += 2;
+
+    if let Some(value) = take_flag_value(args, i, "--merged-before")? {
+        utils::parse_timestamp(&value)
+            .map_err(|e| e.context("Invalid --merged-before"))?;
+        parsed.merged_before = Some(value);
         return Ok(true);
     }
 
-    if let Some(value) = arg.strip_prefix("--linear-org=") {
-        parsed.linear_org = Some(value.to_string());
-        *i += 1;
+    if let Some(value) = take_flag_value(args, i, "--mapping-file")? {
+        parsed.mapping_file = Some(value);
         return Ok(true);
     }
-    if arg == "--linear-org" {
-        let value = args
-            .get(*i + 1)
-            .ok_or_else(|| "Missing value for --linear-org".to_string())?;
-        parsed.linear_org = Some(value.clone());
-        *i += 2;
+
+    if let Some(value) = take_flag_value(args, i, "--max-comments")? {
+        parsed.max_comments = Some(parse_number("--max-comments", &value)?);
         return Ok(true);
     }
 
-    Ok(false)
-}
+    if arg == "--batch-prs" {
+        parsed.pr_fetches = PrFetches::Batched;
+        *i += 1;
+        return Ok(true);
+    }
 
-fn handle_stdin_arg(
-    mode: Mode,
-    parsed: &mut ParsedArgs,
-    stdin_used: &mut bool,
-) -> Result<(), String> {
-    match mode {
-        Mode::ExtractTickets | Mode::UpdateTickets => {
-            if *stdin_used {
-                return Err(
-                    "stdin (-) cannot be specified more than once".to_string()
-                );
-            }
-            parsed.input_sources.push(InputSource::Stdin);
-            *stdin_used = true;
-            Ok(())
+    if let Some(value) = take_flag_value(args, i, "--pr-label")? {
+        if value.trim().is_empty() {
+            return Err(Error::Config(
+                "--pr-label must not be empty".to_string(),
+            ));
         }
-        Mode::ParseNotes => Err(
-            "parse-notes reads from stdin implicitly; '-' is not accepted"
-                .to_string(),
-        ),
-        Mode::Orchestrator => {
-            Err("Orchestrator mode does not accept stdin ('-')".to_string())
+        parsed.pr_scope.labels.push(value.trim().to_string());
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--path-filter")? {
+        if value.trim().is_empty() {
+            return Err(Error::Config(
+                "--path-filter must not be empty".to_string(),
+            ));
         }
+        parsed.pr_scope.paths.push(value.trim().to_string());
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--previous-tag")? {
+        parsed.commit_range.previous_tag = Some(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--commit-source")? {
+        parsed.commit_range.source = CommitSource::parse(&value)?;
+        return Ok(true);
     }
+
+    Ok(false)
 }
 
-fn handle_file_arg(
-    mode: Mode,
-    arg: &str,
+/// Parses the flags choosing which references extract-tickets takes for
+/// tickets (`--prefix-map`, `--exclude-pattern`, `--ticket-pattern`,
+/// `--ignore-token`, `--no-default-ignores`, `--other-org-urls`,
+/// `--trailer-keys`, `--ignore-tickets`, `--ignore-prs`).
+fn parse_reference_flags(
+    args: &[String],
+    i: &mut usize,
     parsed: &mut ParsedArgs,
-) -> Result<(), String> {
-    match mode {
-        Mode::ExtractTickets | Mode::UpdateTickets => {
-            parsed
-                .input_sources
-                .push(InputSource::File(arg.to_string()));
-            Ok(())
-        }
-        Mode::ParseNotes => {
-            Err("parse-notes does not accept file arguments".to_string())
-        }
-        Mode::Orchestrator => {
-            Err("Orchestrator mode does not accept file arguments".to_string())
-        }
+) -> Result<bool, Error> {
+    let arg = args.get(*i).ok_or_else(|| {
+        Error::Other("Internal error while parsing args".to_string())
+    })?;
+
+    if arg == "--no-default-ignores" {
+        parsed.false_positives.builtin = false;
+        *i += 1;
+        return Ok(true);
     }
-}
 
-fn apply_defaults(mode: Mode, parsed: &mut ParsedArgs) {
-    if parsed.input_sources.is_empty()
-        && matches!(mode, Mode::ExtractTickets | Mode::UpdateTickets)
-    {
-        parsed.input_sources.push(InputSource::Stdin);
+    if let Some(value) = take_flag_value(args, i, "--ignore-token")? {
+        parsed.false_positives.push(&value)?;
+        return Ok(true);
     }
-}
 
-fn validate_config(mode: Mode, parsed: &ParsedArgs) -> Result<(), String> {
-    match mode {
-        Mode::ParseNotes => {
-            if parsed.linear_api_key.is_some()
-                || parsed.linear_org.is_some()
-                || parsed.dry_run
-                || parsed.update_all_statuses
-            {
-                return Err(
-                    "parse-notes does not accept Linear credentials, --dry-run, or --update-all-statuses"
-                        .to_string(),
-                );
-            }
-        }
-        Mode::ExtractTickets => {
-            if parsed.release_tag.is_some()
-                || parsed.linear_api_key.is_some()
-                || parsed.linear_org.is_some()
-                || parsed.dry_run
-                || parsed.update_all_statuses
-            {
-                return Err(
-                    "extract-tickets does not accept --release-tag, Linear credentials, --dry-run, or --update-all-statuses"
-                        .to_string(),
-                );
-            }
-        }
-        Mode::UpdateTickets => {
-            if parsed.release_tag.is_some() {
-                return Err(
-                    "update-tickets does not accept --release-tag".to_string()
-                );
-            }
-        }
-        Mode::Orchestrator => {
-            if parsed.release_tag.is_none() {
-                return Err(
-                    "Orchestrator mode requires --release-tag flag".to_string()
-                );
+    if let Some(value) = take_flag_value(args, i, "--prefix-map")? {
+        parsed.prefix_map.push(&value)?;
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--exclude-pattern")? {
+        // Let grep compile the pattern now rather than on the first ticket.
+        utils::grep_whole_lines("", &value).map_err(|e| {
+            Error::Config(format!("Invalid --exclude-pattern {value}: {e}"))
+        })?;
+        parsed.exclude_pattern = Some(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--ticket-pattern")? {
+        utils::run_grep("", &value).map_err(|e| {
+            Error::Config(format!("Invalid --ticket-pattern {value}: {e}"))
+        })?;
+        parsed.ticket_pattern = Some(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--other-org-urls")? {
+        parsed.other_org_urls = match value.as_str() {
+            "drop" => OtherOrgUrls::Drop,
+            "tag" => OtherOrgUrls::Tag,
+            other => {
+                return Err(Error::Config(format!(
+                    "Invalid --other-org-urls {other}: expected drop or tag"
+                )));
             }
-            if !parsed.input_sources.is_empty() {
-                return Err(
-                    "Orchestrator mode does not accept input files".to_string()
-                );
+        };
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--trailer-keys")? {
+        for key in value.split(',').map(str::trim) {
+            if key.is_empty() || key.contains(char::is_whitespace) {
+                return Err(Error::Config(format!(
+                    "Invalid --trailer-keys {value}: expected comma-separated trailer keys"
+                )));
             }
+            parsed.trailer_keys.push(key.to_string());
         }
+        return Ok(true);
     }
-    Ok(())
-}
 
-fn handle_help(args: &[String]) {
-    let mode_arg = args.get(1).map(std::string::String::as_str);
-    match mode_arg {
-        Some("parse-notes") => print_parse_notes_help(),
-        Some("extract-tickets") => print_extract_tickets_help(),
-        Some("update-tickets") => print_update_tickets_help(),
-        _ => print_general_help(),
+    if let Some(value) = take_flag_value(args, i, "--ignore-tickets")? {
+        parsed.ignore_list.push_tickets(&value)?;
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--ignore-prs")? {
+        parsed.ignore_list.push_prs(&value)?;
+        return Ok(true);
     }
+
+    Ok(false)
 }
 
-fn print_general_help() {
-    println!(concat!(
-        "release-linear-ticket-update ",
-        env!("CARGO_PKG_VERSION"),
-        "\n",
-        "\n",
-        "A tool for automatically marking Linear tickets as completed when a GitHub release is published.\n",
-        "\n",
-        "USAGE:\n",
-        "    release-linear-ticket-update [MODE] [OPTIONS] [FILES...]\n",
-        "\n",
-        "MODES:\n",
-        "    parse-notes        Parse release notes to extract PR numbers\n",
-        "    extract-tickets    Extract Linear ticket IDs from PR content\n",
-        "    update-tickets     Update Linear tickets to completed state\n",
-        "    (default)          Run orchestrator mode (full pipeline)\n",
-        "\n",
-        "OPTIONS:\n",
-        "    --help, -h         Print this help message\n",
-        "    --version          Print version information\n",
-        "\n",
-        "    --release-tag TAG\n",
-        "            GitHub release tag (required for parse-notes and orchestrator modes)\n",
-        "\n",
-        "    --linear-api-key KEY\n",
-        "            Linear API authentication key (can also be set via LINEAR_API_KEY env var)\n",
-        "\n",
-        "    --linear-org ORG\n",
-        "            Linear organization identifier (can also be set via LINEAR_ORG env var)\n",
-        "\n",
-        "    --dry-run\n",
-        "            Preview changes without updating\n",
-        "\n",
-        "    --update-all-statuses\n",
-        "            Update regardless of current Linear state (default: only \"Passing\")\n",
-        "\n",
-        "See 'release-linear-ticket-update <MODE> --help' for more information on a specific mode."
-    ));
+/// Parses a `--log-format` value.
+///
+/// # Errors
+/// Returns an error if it is neither `text` nor `json`.
+fn parse_log_format(value: &str) -> Result<LogFormat, Error> {
+    match value {
+        "text" => Ok(LogFormat::Text),
+        "json" => Ok(LogFormat::Json),
+        other => Err(Error::Config(format!(
+            "Invalid --log-format {other}: expected text or json"
+        ))),
+    }
 }
 
-fn print_parse_notes_help() {
-    println!(concat!(
-        "release-linear-ticket-update parse-notes\n",
-        "\n",
-        "Extracts Pull Request numbers from release notes.\n",
+/// The flags setting the log level (see [`parse_log_level_flag`]).
+const LOG_LEVEL_FLAGS: [&str; 4] = ["--quiet", "-v", "--verbose", "-vv"];
+
+/// Parses `arg` if it is `--quiet`, `-v`/`--verbose` (twice for debug) or
+/// `-vv` into `parsed.log_level`.
+///
+/// # Returns
+/// Whether `arg` is one of them.
+///
+/// # Errors
+/// Returns an error if `--quiet` is combined with `--verbose`.
+fn parse_log_level_flag(
+    arg: &str,
+    parsed: &mut ParsedArgs,
+) -> Result<bool, Error> {
+    let current = parsed.log_level;
+    let level = match arg {
+        "--quiet" => LogLevel::Warn,
+        "-v" | "--verbose" if current == LogLevel::Verbose => LogLevel::Debug,
+        "-v" | "--verbose" => current.max(LogLevel::Verbose),
+        "-vv" => LogLevel::Debug,
+        _ => return Ok(false),
+    };
+    if current != LogLevel::Info
+        && (current == LogLevel::Warn) != (level == LogLevel::Warn)
+    {
+        return Err(Error::Config(
+            "--quiet and --verbose cannot be used together".to_string(),
+        ));
+    }
+    parsed.log_level = level;
+    Ok(true)
+}
+
+fn parse_common_flags(
+    args: &[String],
+    i: &mut usize,
+    parsed: &mut ParsedArgs,
+) -> Result<bool, Error> {
+    let arg = args.get(*i).ok_or_else(|| {
+        Error::Other("Internal error while parsing args".to_string())
+    })?;
+
+    if arg == "--dry-run" {
+        parsed.dry_run = true;
+        *i += 1;
+        return Ok(true);
+    }
+
+    if parse_log_level_flag(arg, parsed)? {
+        *i += 1;
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--log-format")? {
+        parsed.log_format = parse_log_format(&value)?;
+        return Ok(true);
+    }
+
+    if arg == "--update-all-statuses" {
+        parsed.update_all_statuses = true;
+        *i += 1;
+        return Ok(true);
+    }
+
+    if arg == "--drop-unknown-teams" {
+        parsed.unknown_teams = UnknownTeams::Drop;
+        *i += 1;
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--follow-duplicates")? {
+        parsed.duplicates = match value.as_str() {
+            "canonical" => Duplicates::Canonical,
+            "both" => Duplicates::Both,
+            other => {
+                return Err(Error::Config(format!(
+                    "Invalid --follow-duplicates {other}: expected canonical or both"
+                )));
+            }
+        };
+        return Ok(true);
+    }
+
+    if arg == "--fail-on-partial" {
+        parsed.partial_failure = PartialFailure::Fail;
+        *i += 1;
+        return Ok(true);
+    }
+
+    if arg == "--order-by-dependencies" {
+        if parsed.dependencies == Dependencies::Ignore {
+            parsed.dependencies = Dependencies::Order;
+        }
+        *i += 1;
+        return Ok(true);
+    }
+
+    if arg == "--note-blocked" {
+        parsed.dependencies = Dependencies::OrderAndNote;
+        *i += 1;
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--release-tag")? {
+        parsed.release_tag.get_or_insert_with(|| value.clone());
+        parsed.release_tags.push(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--since-tag")? {
+        parsed.since_tag = Some(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--until-tag")? {
+        parsed.until_tag = Some(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--linear-api-key")? {
+        parsed.linear_api_key = Some(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--linear-org")? {
+        parsed.linear_org = Some(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--org-map")? {
+        parsed.org_map.push(&value)?;
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--filter")? {
+        let filter = Filter::parse(&value)
+            .map_err(|e| e.context("Invalid --filter expression"))?;
+        parsed.filter = Some(filter);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--assignee")? {
+        parsed.assignee = Some(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--creator")? {
+        parsed.creator = Some(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--stdin-timeout")? {
+        parsed.stdin_timeout_secs =
+            Some(parse_number("--stdin-timeout", &value)?);
+        return Ok(true);
+    }
+
+    Ok(false)
+}
+
+/// Parses the flags of the approval workflow and resumable runs
+/// (`--manifest`, `--approve`, `--compare-manifest`, `--queue-file`,
+/// `--checkpoint`, `--journal`).
+fn parse_approval_flags(
+    args: &[String],
+    i: &mut usize,
+    parsed: &mut ParsedArgs,
+) -> Result<bool, Error> {
+    if let Some(value) = take_flag_value(args, i, "--manifest")? {
+        parsed.manifest = Some(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--approve")? {
+        parsed.approve = Some(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--compare-manifest")? {
+        parsed.compare_manifest = Some(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--queue-file")? {
+        parsed.queue_file = Some(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--checkpoint")? {
+        parsed.checkpoint = Some(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--journal")? {
+        parsed.journal = Some(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--team")? {
+        parsed.selftest_team = Some(value);
+        return Ok(true);
+    }
+
+    Ok(false)
+}
+
+/// Parses the flags shaping output (`--locale`, `--locale-dir`,
+/// `--output-format`, `--github-output`, `--diff`, `--url-format`,
+/// `--explain`).
+fn parse_output_flags(
+    args: &[String],
+    i: &mut usize,
+    parsed: &mut ParsedArgs,
+) -> Result<bool, Error> {
+    if let Some(value) = take_flag_value(args, i, "--locale")? {
+        if value.is_empty()
+            || !value
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(Error::Config(format!(
+                "Invalid --locale {value}: expected a name like de or pt-BR"
+            )));
+        }
+        parsed.locale = Some(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--locale-dir")? {
+        parsed.locale_dir = Some(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--output-format")? {
+        parsed.output_format = match value.as_str() {
+            "text" => OutputFormat::Text,
+            "ndjson" => OutputFormat::Ndjson,
+            "json" => OutputFormat::Json,
+            other => {
+                return Err(Error::Config(format!(
+                    "Invalid --output-format {other}: expected text, ndjson or json"
+                )));
+            }
+        };
+        return Ok(true);
+    }
+
+    if args.get(*i).is_some_and(|arg| arg == "--github-output") {
+        parsed.github_output = GithubOutput::Write;
+        *i += 1;
+        return Ok(true);
+    }
+
+    if args.get(*i).is_some_and(|arg| arg == "--diff") {
+        parsed.dry_run_output = DryRunOutput::Diff;
+        *i += 1;
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--url-format")? {
+        parsed.url_format = match value.as_str() {
+            "id" => UrlFormat::Id,
+            "url" => UrlFormat::Url,
+            "markdown" => UrlFormat::Markdown,
+            other => {
+                return Err(Error::Config(format!(
+                    "Invalid --url-format {other}: expected id, url or markdown"
+                )));
+            }
+        };
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--explain")? {
+        parsed.explain = Some(Subject::parse(&value)?);
+        return Ok(true);
+    }
+
+    Ok(false)
+}
+
+/// Parses the flags choosing where releases and their PRs come from
+/// (`--forge`, `--discover`).
+fn parse_forge_flags(
+    args: &[String],
+    i: &mut usize,
+    parsed: &mut ParsedArgs,
+) -> Result<bool, Error> {
+    if let Some(value) = take_flag_value(args, i, "--forge")? {
+        parsed.forge = match value.as_str() {
+            "github" => Forge::GitHub,
+            "gitlab" => Forge::GitLab,
+            other => {
+                return Err(Error::Config(format!(
+                    "Invalid --forge {other}: expected github or gitlab"
+                )));
+            }
+        };
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--discover")? {
+        parsed.discover = match value.as_str() {
+            "notes" => Discover::Notes,
+            "compare" => Discover::Compare,
+            "both" => Discover::Both,
+            other => {
+                return Err(Error::Config(format!(
+                    "Invalid --discover {other}: expected notes, compare or both"
+                )));
+            }
+        };
+        return Ok(true);
+    }
+
+    Ok(false)
+}
+
+/// Parses the endpoint override flags (`--github-host`, `--github-api`,
+/// `--github-repo`, `--linear-api-url`, `--linear-header`,
+/// `--linear-resolve`).
+fn parse_network_flags(
+    args: &[String],
+    i: &mut usize,
+    parsed: &mut ParsedArgs,
+) -> Result<bool, Error> {
+    if let Some(value) = take_flag_value(args, i, "--github-host")? {
+        parsed.github_host = Some(value);
+        return Ok(true);
+    }
+
+    if args[*i] == "--github-api" {
+        parsed.github_access = GithubAccess::Api;
+        *i += 1;
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--github-repo")? {
+        let valid = value.split_once('/').is_some_and(|(owner, name)| {
+            !owner.is_empty() && !name.is_empty() && !name.contains('/')
+        });
+        if !valid {
+            return Err(Error::Config(format!(
+                "Invalid --github-repo {value}: expected OWNER/REPO"
+            )));
+        }
+        parsed.github_repo = Some(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--linear-api-url")? {
+        parsed.linear_api_url = Some(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--linear-actor-token")? {
+        parsed.linear_actor_token = Some(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--linear-header")? {
+        if !value.contains(':') {
+            return Err(Error::Config(format!(
+                "Invalid --linear-header {value}: expected 'Name: value'"
+            )));
+        }
+        parsed.linear_headers.push(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--linear-resolve")? {
+        if value.splitn(3, ':').count() != 3 {
+            return Err(Error::Config(format!(
+                "Invalid --linear-resolve {value}: expected HOST:PORT:ADDRESS"
+            )));
+        }
+        parsed.linear_resolve.push(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--linear-snapshot")? {
+        parsed.linear_snapshot = Some(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--debug-dump")? {
+        parsed.debug_dump = Some(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--concurrency")? {
+        parsed.concurrency = Some(parse_number("--concurrency", &value)?);
+        return Ok(true);
+    }
+
+    if args.get(*i).is_some_and(|arg| arg == "--batch-lookups") {
+        parsed.lookups = Lookups::Batched;
+        *i += 1;
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--cache-dir")? {
+        parsed.cache_dir = Some(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--rate-limit")? {
+        // `10/s` reads as well as `10`
+        let requests_per_sec = value.strip_suffix("/s").unwrap_or(&value);
+        parsed.rate_limit =
+            Some(requests_per_sec.parse().map_err(|_| {
+                Error::Config(format!(
+                    "Invalid --rate-limit {value}: expected requests per second, e.g. 10 or 10/s"
+                ))
+            })?);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--api-budget")? {
+        parsed.api_budget = Some(parse_number("--api-budget", &value)?);
+        return Ok(true);
+    }
+
+    Ok(false)
+}
+
+/// Parses the issue tracker flags (`--tracker`, `--jira-base-url`,
+/// `--jira-api-token`, `--jira-email`).
+fn parse_tracker_flags(
+    args: &[String],
+    i: &mut usize,
+    parsed: &mut ParsedArgs,
+) -> Result<bool, Error> {
+    if let Some(value) = take_flag_value(args, i, "--tracker")? {
+        parsed.tracker = match value.as_str() {
+            "linear" => Tracker::Linear,
+            "jira" => Tracker::Jira,
+            other => {
+                return Err(Error::Config(format!(
+                    "Invalid --tracker {other}: expected linear or jira"
+                )));
+            }
+        };
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--jira-base-url")? {
+        if !value.starts_with("https://") && !value.starts_with("http://") {
+            return Err(Error::Config(format!(
+                "Invalid --jira-base-url {value}: expected an http(s):// URL"
+            )));
+        }
+        parsed.jira.base_url = Some(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--jira-api-token")? {
+        parsed.jira.api_token = Some(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--jira-email")? {
+        parsed.jira.email = Some(value);
+        return Ok(true);
+    }
+
+    Ok(false)
+}
+
+/// Parses the flags choosing how the orchestrator runs its pipelines
+/// (`--in-process`, `--jobs`).
+fn parse_pipeline_flags(
+    args: &[String],
+    i: &mut usize,
+    parsed: &mut ParsedArgs,
+) -> Result<bool, Error> {
+    if args.get(*i).is_some_and(|arg| arg == "--in-process") {
+        parsed.stages = Stages::InProcess;
+        *i += 1;
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--jobs")? {
+        parsed.jobs = Some(parse_number("--jobs", &value)?);
+        return Ok(true);
+    }
+
+    Ok(false)
+}
+
+/// Parses the orchestrator's deployment flags (`--commit-status`,
+/// `--deployment-environment`, `--deployment-url`, `--deployment-timeout`).
+fn parse_deployment_flags(
+    args: &[String],
+    i: &mut usize,
+    parsed: &mut ParsedArgs,
+) -> Result<bool, Error> {
+    if let Some(value) = take_flag_value(args, i, "--commit-status")? {
+        parsed.commit_status_url = Some(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--deployment-environment")? {
+        if value.trim().is_empty() {
+            return Err(Error::Config(
+                "--deployment-environment must name an environment".to_string(),
+            ));
+        }
+        parsed.deployment.environment = Some(value.trim().to_string());
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--deployment-url")? {
+        if !value.starts_with("http://") && !value.starts_with("https://") {
+            return Err(Error::Config(format!(
+                "Invalid --deployment-url {value}: expected an http:// or https:// URL"
+            )));
+        }
+        parsed.deployment.url = Some(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--deployment-timeout")? {
+        parsed.deployment.timeout_secs =
+            Some(parse_number("--deployment-timeout", &value)?);
+        return Ok(true);
+    }
+
+    Ok(false)
+}
+
+/// Parses the webhook flags (`--webhook-url`, `--webhook-secret`,
+/// `--slack-webhook`).
+fn parse_webhook_flags(
+    args: &[String],
+    i: &mut usize,
+    parsed: &mut ParsedArgs,
+) -> Result<bool, Error> {
+    if let Some(value) = take_flag_value(args, i, "--webhook-url")? {
+        if !value.starts_with("http://") && !value.starts_with("https://") {
+            return Err(Error::Config(format!(
+                "Invalid --webhook-url {value}: expected an http:// or https:// URL"
+            )));
+        }
+        parsed.webhook_url = Some(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--webhook-secret")? {
+        if value.is_empty() {
+            return Err(Error::Config(
+                "--webhook-secret must not be empty".to_string(),
+            ));
+        }
+        parsed.webhook_secret = Some(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--slack-webhook")? {
+        if !value.starts_with("http://") && !value.starts_with("https://") {
+            return Err(Error::Config(format!(
+                "Invalid --slack-webhook {value}: expected an http:// or https:// URL"
+            )));
+        }
+        parsed.slack_webhook = Some(value);
+        return Ok(true);
+    }
+
+    Ok(false)
+}
+
+/// Parses the run history flags (`--history-file`, `--repeat-release`,
+/// `--quarantine-after`, `--clear-quarantine`).
+fn parse_history_flags(
+    args: &[String],
+    i: &mut usize,
+    parsed: &mut ParsedArgs,
+) -> Result<bool, Error> {
+    if let Some(value) = take_flag_value(args, i, "--history-file")? {
+        parsed.history_file = Some(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--repeat-release")? {
+        parsed.repeat_release = match value.as_str() {
+            "warn" => RepeatRelease::Warn,
+            "fail" => RepeatRelease::Fail,
+            other => {
+                return Err(Error::Config(format!(
+                    "Invalid --repeat-release {other}: expected warn or fail"
+                )));
+            }
+        };
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--quarantine-after")? {
+        parsed.quarantine_after =
+            Some(parse_number("--quarantine-after", &value)?);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--clear-quarantine")? {
+        parsed.clear_quarantine.push(value);
+        return Ok(true);
+    }
+
+    Ok(false)
+}
+
+/// Parses the flags grouping updated tickets in Linear
+/// (`--linear-project`, `--add-to-project`, `--add-label`,
+/// `--set-custom-field`, `--linear-cycle`, `--attach-release`).
+fn parse_grouping_flags(
+    args: &[String],
+    i: &mut usize,
+    parsed: &mut ParsedArgs,
+) -> Result<bool, Error> {
+    if let Some(value) = take_flag_value(args, i, "--linear-project")? {
+        parsed.linear_project = Some(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--add-to-project")? {
+        parsed.add_to_project = Some(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--add-label")? {
+        if value.trim().is_empty() {
+            return Err(Error::Config(
+                "--add-label must name a label".to_string(),
+            ));
+        }
+        parsed.add_label = Some(value.trim().to_string());
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--set-custom-field")? {
+        parsed.custom_field = Some(CustomField::parse(&value)?);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--linear-cycle")? {
+        parsed.linear_cycle = Some(Cycle::parse(&value)?);
+        return Ok(true);
+    }
+
+    if args.get(*i).is_some_and(|arg| arg == "--attach-release") {
+        parsed.release_attachment = ReleaseAttachment::Attach;
+        *i += 1;
+        return Ok(true);
+    }
+
+    Ok(false)
+}
+
+/// Parses the release tracking flags (`--quiet-updates`, `--comment-release`,
+/// `--comment-template`, `--release-issue-team`, `--state-route`,
+/// `--target-state`, `--target-state-type`) and the
+/// comment pacing flags
+/// (`--comment-interval`, `--comment-batch-size`, `--comment-batch-pause`,
+/// `--comment-progress`).
+fn parse_release_tracking_flags(
+    args: &[String],
+    i: &mut usize,
+    parsed: &mut ParsedArgs,
+) -> Result<bool, Error> {
+    if args.get(*i).is_some_and(|arg| arg == "--quiet-updates") {
+        parsed.notifications = Notifications::Quiet;
+        *i += 1;
+        return Ok(true);
+    }
+
+    if args.get(*i).is_some_and(|arg| arg == "--comment-release") {
+        parsed.comment_release = true;
+        *i += 1;
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--comment-template")? {
+        parsed.comment_template = Some(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--release-issue-team")? {
+        parsed.release_issue_team = Some(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--state-route")? {
+        parsed.state_routes.push(&value)?;
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--from-states")? {
+        for state in value.split(',').map(str::trim) {
+            if state.is_empty() {
+                return Err(Error::Config(format!(
+                    "Invalid --from-states {value}: expected comma-separated workflow state names"
+                )));
+            }
+            parsed.from_states.push(state.to_string());
+        }
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--target-state")? {
+        if value.trim().is_empty() {
+            return Err(Error::Config(
+                "--target-state must name a workflow state".to_string(),
+            ));
+        }
+        parsed.target_state = Some(value.trim().to_string());
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--target-state-type")? {
+        if !STATE_TYPES.contains(&value.as_str()) {
+            return Err(Error::Config(format!(
+                "Invalid --target-state-type {value}: expected one of {}",
+                STATE_TYPES.join(", ")
+            )));
+        }
+        parsed.target_state_type = Some(value);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--comment-interval")? {
+        parsed.comment_pacing.interval_ms =
+            Some(parse_number("--comment-interval", &value)?);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--comment-batch-size")? {
+        parsed.comment_pacing.batch_size =
+            Some(parse_number("--comment-batch-size", &value)?);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--comment-batch-pause")? {
+        parsed.comment_pacing.batch_pause_secs =
+            Some(parse_number("--comment-batch-pause", &value)?);
+        return Ok(true);
+    }
+
+    if let Some(value) = take_flag_value(args, i, "--comment-progress")? {
+        parsed.comment_pacing.progress = Some(value);
+        return Ok(true);
+    }
+
+    Ok(false)
+}
+
+/// Parses a non-negative integer flag value.
+fn parse_number<T: std::str::FromStr>(
+    flag: &str,
+    value: &str,
+) -> Result<T, Error> {
+    value.parse().map_err(|_| {
+        Error::Config(format!(
+            "Invalid {flag} {value}: expected a non-negative integer"
+        ))
+    })
+}
+
+/// Consumes a flag that takes a value, in either `--flag=value` or
+/// `--flag value` form.
+///
+/// # Returns
+/// - `Ok(Some(value))` if `args[*i]` is `flag`; `i` is advanced past it
+/// - `Ok(None)` if `args[*i]` is a different argument; `i` is unchanged
+///
+/// # Errors
+/// Returns an error if the flag is the last argument and has no value.
+fn take_flag_value(
+    args: &[String],
+    i: &mut usize,
+    flag: &str,
+) -> Result<Option<String>, Error> {
+    let Some(arg) = args.get(*i) else {
+        return Ok(None);
+    };
+
+    if let Some(value) = arg
+        .strip_prefix(flag)
+        .and_then(|rest| rest.strip_prefix('='))
+    {
+        *i += 1;
+        return Ok(Some(value.to_string()));
+    }
+    if arg == flag {
+        let value = args.get(*i + 1).ok_or_else(|| {
+            Error::Config(format!("Missing value for {flag}"))
+        })?;
+        *i += 2;
+        return Ok(Some(value.clone()));
+    }
+
+    Ok(None)
+}
+
+fn handle_stdin_arg(
+    mode: Mode,
+    parsed: &mut ParsedArgs,
+    stdin_used: &mut bool,
+) -> Result<(), Error> {
+    match mode {
+        Mode::ParseNotes | Mode::ExtractTickets | Mode::UpdateTickets => {
+            if *stdin_used {
+                return Err(Error::Config(
+                    "stdin (-) cannot be specified more than once; pass other producers as named pipes or process substitutions, e.g. <(cmd)".to_string()
+                ));
+            }
+            parsed.input_sources.push(InputSource::Stdin);
+            *stdin_used = true;
+            Ok(())
+        }
+        Mode::Orchestrator => Err(Error::Config(
+            "Orchestrator mode does not accept stdin ('-')".to_string(),
+        )),
+        Mode::ExtractCommits => Err(Error::Config(
+            "extract-commits does not accept stdin ('-')".to_string(),
+        )),
+        Mode::Teams => Err(Error::Config(
+            "teams does not accept stdin ('-')".to_string(),
+        )),
+        Mode::Report => Err(Error::Config(
+            "report does not accept stdin ('-')".to_string(),
+        )),
+        Mode::Verify => Err(Error::Config(
+            "verify does not accept stdin ('-')".to_string(),
+        )),
+        Mode::Schema => Err(Error::Config(
+            "schema does not accept stdin ('-')".to_string(),
+        )),
+        Mode::Rollback => Err(Error::Config(
+            "rollback does not accept stdin ('-')".to_string(),
+        )),
+        Mode::Selftest => Err(Error::Config(
+            "selftest does not accept stdin ('-')".to_string(),
+        )),
+        Mode::Run => {
+            Err(Error::Config("run does not accept stdin ('-')".to_string()))
+        }
+        Mode::Serve => Err(Error::Config(
+            "serve does not accept stdin ('-')".to_string(),
+        )),
+    }
+}
+
+fn handle_file_arg(
+    mode: Mode,
+    arg: &str,
+    parsed: &mut ParsedArgs,
+) -> Result<(), Error> {
+    match mode {
+        Mode::ParseNotes | Mode::ExtractTickets | Mode::UpdateTickets => {
+            parsed
+                .input_sources
+                .push(InputSource::File(arg.to_string()));
+            Ok(())
+        }
+        Mode::Orchestrator => Err(Error::Config(
+            "Orchestrator mode does not accept file arguments".to_string(),
+        )),
+        Mode::ExtractCommits => Err(Error::Config(
+            "extract-commits does not accept file arguments".to_string(),
+        )),
+        Mode::Teams => Err(Error::Config(
+            "teams does not accept file arguments".to_string(),
+        )),
+        Mode::Report => Err(Error::Config(
+            "report does not accept file arguments".to_string(),
+        )),
+        Mode::Verify => Err(Error::Config(
+            "verify does not accept file arguments".to_string(),
+        )),
+        Mode::Schema => Err(Error::Config(
+            "schema does not accept file arguments".to_string(),
+        )),
+        Mode::Rollback => Err(Error::Config(
+            "rollback does not accept file arguments".to_string(),
+        )),
+        Mode::Selftest => Err(Error::Config(
+            "selftest does not accept file arguments".to_string(),
+        )),
+        Mode::Run => Err(Error::Config(
+            "run takes a single pipeline name".to_string(),
+        )),
+        Mode::Serve => Err(Error::Config(
+            "serve does not accept file arguments".to_string(),
+        )),
+    }
+}
+
+fn apply_defaults(mode: Mode, parsed: &mut ParsedArgs) {
+    if parsed.input_sources.is_empty()
+        && matches!(mode, Mode::ExtractTickets | Mode::UpdateTickets)
+    {
+        parsed.input_sources.push(InputSource::Stdin);
+    }
+}
+
+fn validate_config(mode: Mode, parsed: &ParsedArgs) -> Result<(), Error> {
+    if parsed.selftest_team.is_some() && !matches!(mode, Mode::Selftest) {
+        return Err(Error::Config("Only selftest accepts --team".to_string()));
+    }
+    if parsed.commit_range.is_set() && !matches!(mode, Mode::ExtractCommits) {
+        return Err(Error::Config(
+            "Only extract-commits accepts --previous-tag and --commit-source"
+                .to_string(),
+        ));
+    }
+    validate_release_range(mode, parsed)?;
+    validate_discover(mode, parsed)?;
+    match mode {
+        Mode::ParseNotes => validate_parse_notes(parsed)?,
+        Mode::ExtractTickets => validate_extract_tickets(parsed)?,
+        Mode::ExtractCommits => validate_extract_commits(parsed)?,
+        Mode::UpdateTickets => validate_update_tickets(parsed)?,
+        Mode::Orchestrator => {
+            if parsed.release_tag.is_none() && parsed.since_tag.is_none() {
+                return Err(Error::Config(
+                    "Orchestrator mode requires --release-tag (or --since-tag)"
+                        .to_string(),
+                ));
+            }
+            if !parsed.input_sources.is_empty() {
+                return Err(Error::Config(
+                    "Orchestrator mode does not accept input files".to_string(),
+                ));
+            }
+            if parsed.stdin_timeout_secs.is_some() {
+                return Err(Error::Config(
+                    "Orchestrator mode does not accept --stdin-timeout"
+                        .to_string(),
+                ));
+            }
+            if parsed.deployment.timeout_secs.is_some()
+                && parsed.deployment.environment.is_none()
+                && parsed.deployment.url.is_none()
+            {
+                return Err(Error::Config("--deployment-timeout requires --deployment-environment or --deployment-url".to_string()));
+            }
+        }
+        Mode::Teams => validate_teams(parsed)?,
+        Mode::Report => validate_report(parsed)?,
+        Mode::Verify => validate_verify(parsed)?,
+        Mode::Rollback => validate_rollback(parsed)?,
+        Mode::Selftest => validate_selftest(parsed)?,
+        // Anything after `schema` is rejected before flags are parsed, and
+        // `run` and `serve` parse their own (see `parse_run`, `parse_serve`)
+        Mode::Schema | Mode::Run | Mode::Serve => {}
+    }
+
+    validate_run_mode(mode, parsed)?;
+    validate_explain(mode, parsed)?;
+    validate_tracker(parsed)?;
+    validate_forge(parsed)?;
+    validate_merge_window(parsed)?;
+    validate_history(parsed)?;
+    validate_org_map(parsed)?;
+    validate_parallel(mode, parsed)
+}
+
+/// Checks `--discover`: only the modes reading release notes take it, and
+/// the comparison needs a release, of GitHub.
+fn validate_discover(mode: Mode, parsed: &ParsedArgs) -> Result<(), Error> {
+    if parsed.discover == Discover::Notes {
+        return Ok(());
+    }
+    if !matches!(
+        mode,
+        Mode::ParseNotes | Mode::Report | Mode::Verify | Mode::Orchestrator
+    ) {
+        return Err(Error::Config("--discover is only accepted by parse-notes, report, verify and orchestrator mode".to_string()));
+    }
+    if parsed.forge == Forge::GitLab {
+        return Err(Error::Config(
+            "--discover cannot be combined with --forge gitlab".to_string(),
+        ));
+    }
+    if parsed.release_tag.is_none() && parsed.since_tag.is_none() {
+        return Err(Error::Config(format!(
+            "--discover {} requires --release-tag (or --since-tag)",
+            parsed.discover.name()
+        )));
+    }
+    Ok(())
+}
+
+/// Checks the release range flags (`--since-tag`, `--until-tag`), which
+/// stand in for `--release-tag` in parse-notes and the orchestrator.
+fn validate_release_range(
+    mode: Mode,
+    parsed: &ParsedArgs,
+) -> Result<(), Error> {
+    if parsed.since_tag.is_none() {
+        if parsed.until_tag.is_some() {
+            return Err(Error::Config(
+                "--until-tag requires --since-tag".to_string(),
+            ));
+        }
+        return Ok(());
+    }
+    if !matches!(mode, Mode::ParseNotes | Mode::Orchestrator) {
+        return Err(Error::Config(
+            "Only parse-notes and orchestrator modes accept --since-tag"
+                .to_string(),
+        ));
+    }
+    if parsed.release_tag.is_some() {
+        return Err(Error::Config(
+            "--since-tag cannot be combined with --release-tag".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Checks that `--org-map` isn't combined with flags that look things up in,
+/// or record tickets of, a single Linear workspace.
+fn validate_org_map(parsed: &ParsedArgs) -> Result<(), Error> {
+    if parsed.org_map.is_empty() {
+        return Ok(());
+    }
+    for (flag, given) in [
+        ("--batch-lookups", parsed.lookups == Lookups::Batched),
+        ("--linear-project", parsed.linear_project.is_some()),
+        ("--add-to-project", parsed.add_to_project.is_some()),
+        ("--add-label", parsed.add_label.is_some()),
+        ("--set-custom-field", parsed.custom_field.is_some()),
+        (
+            "--attach-release",
+            parsed.release_attachment == ReleaseAttachment::Attach,
+        ),
+        (
+            "--assignee/--creator",
+            parsed.assignee.is_some() || parsed.creator.is_some(),
+        ),
+        ("--linear-actor-token", parsed.linear_actor_token.is_some()),
+        ("--linear-snapshot", parsed.linear_snapshot.is_some()),
+        ("--journal", parsed.journal.is_some()),
+        (
+            "--manifest/--approve/--compare-manifest",
+            parsed.manifest.is_some()
+                || parsed.approve.is_some()
+                || parsed.compare_manifest.is_some(),
+        ),
+    ] {
+        if given {
+            return Err(Error::Config(format!(
+                "{flag} cannot be combined with --org-map"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Checks that `--explain` is given to a pipeline stage or the orchestrator,
+/// which trace their decisions; the other modes decide nothing per ticket.
+fn validate_explain(mode: Mode, parsed: &ParsedArgs) -> Result<(), Error> {
+    if parsed.explain.is_none()
+        || matches!(
+            mode,
+            Mode::ParseNotes
+                | Mode::ExtractTickets
+                | Mode::UpdateTickets
+                | Mode::Orchestrator
+        )
+    {
+        return Ok(());
+    }
+    Err(Error::Config("--explain is only accepted by parse-notes, extract-tickets, update-tickets and orchestrator mode".to_string()))
+}
+
+/// Checks the forge flags: `--github-repo` only names the repository for
+/// `--github-api`, and with `--forge gitlab`, GitHub's access flags and the
+/// features that call `gh` outside parse-notes and extract-tickets don't
+/// apply.
+fn validate_forge(parsed: &ParsedArgs) -> Result<(), Error> {
+    if parsed.github_repo.is_some() && parsed.github_access != GithubAccess::Api
+    {
+        return Err(Error::Config(
+            "--github-repo requires --github-api".to_string(),
+        ));
+    }
+    if parsed.forge != Forge::GitLab {
+        return Ok(());
+    }
+    reject_flags(
+        "--forge gitlab",
+        &[
+            ("--github-host", parsed.github_host.is_some()),
+            ("--github-api", parsed.github_access == GithubAccess::Api),
+            ("--commit-status", parsed.commit_status_url.is_some()),
+            (
+                "--deployment-environment",
+                parsed.deployment.environment.is_some(),
+            ),
+            ("--comment-template", parsed.comment_template.is_some()),
+            (
+                "--attach-release",
+                parsed.release_attachment == ReleaseAttachment::Attach,
+            ),
+            ("--batch-prs", parsed.pr_fetches == PrFetches::Batched),
+            ("--pr-label/--path-filter", parsed.pr_scope.is_set()),
+        ],
+    )
+}
+
+/// Checks the flags for running several releases (`--release-tag` given
+/// more than once or `--since-tag`, `--jobs`, `--cache-dir`, `--rate-limit`)
+/// and for fetching
+/// PRs and updating tickets concurrently (`--concurrency`).
+fn validate_parallel(mode: Mode, parsed: &ParsedArgs) -> Result<(), Error> {
+    if parsed.jobs == Some(0) {
+        return Err(Error::Config("--jobs must be at least 1".to_string()));
+    }
+    if parsed.concurrency == Some(0) {
+        return Err(Error::Config(
+            "--concurrency must be at least 1".to_string(),
+        ));
+    }
+    if parsed.concurrency.is_some() {
+        // Blockers must be updated before the tickets they block, and the
+        // planned modes apply an up-front plan in one pass.
+        for (flag, given) in [
+            (
+                "--order-by-dependencies/--note-blocked",
+                parsed.dependencies != Dependencies::Ignore,
+            ),
+            ("--approve", parsed.approve.is_some()),
+            (
+                "--quiet-updates",
+                parsed.notifications != Notifications::Normal,
+            ),
+        ] {
+            if given {
+                return Err(Error::Config(format!(
+                    "--concurrency cannot be combined with {flag}"
+                )));
+            }
+        }
+    }
+    if parsed.rate_limit == Some(0) {
+        return Err(Error::Config(
+            "--rate-limit must be at least 1".to_string(),
+        ));
+    }
+    if parsed.api_budget == Some(0) {
+        return Err(Error::Config(
+            "--api-budget must be at least 1".to_string(),
+        ));
+    }
+    if !matches!(mode, Mode::Orchestrator) {
+        if parsed.release_tags.len() > 1 {
+            return Err(Error::Config(
+                "Only orchestrator mode accepts more than one --release-tag"
+                    .to_string(),
+            ));
+        }
+        if parsed.jobs.is_some() {
+            return Err(Error::Config(
+                "Only orchestrator mode accepts --jobs".to_string(),
+            ));
+        }
+        if parsed.stages == Stages::InProcess {
+            return Err(Error::Config(
+                "Only orchestrator mode accepts --in-process".to_string(),
+            ));
+        }
+        return Ok(());
+    }
+
+    let several = if parsed.release_tags.len() > 1 {
+        Some("more than one --release-tag")
+    } else {
+        // A range may hold any number of releases.
+        parsed.since_tag.as_ref().map(|_| "--since-tag")
+    };
+    if let Some(several) = several {
+        // These files belong to a single run, and concurrent releases would
+        // overwrite each other's.
+        for (flag, given) in [
+            ("--manifest", parsed.manifest.is_some()),
+            ("--approve", parsed.approve.is_some()),
+            ("--compare-manifest", parsed.compare_manifest.is_some()),
+            ("--queue-file", parsed.queue_file.is_some()),
+            ("--checkpoint", parsed.checkpoint.is_some()),
+            ("--mapping-file", parsed.mapping_file.is_some()),
+            // The endpoint only reports the version deployed now.
+            ("--deployment-url", parsed.deployment.url.is_some()),
+            // Each release would write the same step outputs.
+            (
+                "--github-output",
+                parsed.github_output == GithubOutput::Write,
+            ),
+            // Each release would print its own array.
+            (
+                "--output-format json",
+                parsed.output_format == OutputFormat::Json,
+            ),
+        ] {
+            if given {
+                return Err(Error::Config(format!(
+                    "{flag} cannot be combined with {several}"
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks the `--history-file` flags.
+fn validate_history(parsed: &ParsedArgs) -> Result<(), Error> {
+    if parsed.history_file.is_some()
+        && parsed.release_tag.is_none()
+        && parsed.since_tag.is_none()
+    {
+        return Err(Error::Config(
+            "--history-file requires --release-tag".to_string(),
+        ));
+    }
+    if parsed.repeat_release != RepeatRelease::Warn
+        && parsed.history_file.is_none()
+    {
+        return Err(Error::Config(
+            "--repeat-release requires --history-file".to_string(),
+        ));
+    }
+    if parsed.quarantine_after == Some(0) {
+        return Err(Error::Config(
+            "--quarantine-after must be at least 1".to_string(),
+        ));
+    }
+    if parsed.quarantine_after.is_some() && parsed.history_file.is_none() {
+        return Err(Error::Config(
+            "--quarantine-after requires --history-file".to_string(),
+        ));
+    }
+    if !parsed.clear_quarantine.is_empty() && parsed.quarantine_after.is_none()
+    {
+        return Err(Error::Config(
+            "--clear-quarantine requires --quarantine-after".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Checks the `--tracker` flags: Jira credentials need `--tracker jira`,
+/// which in turn supports none of the Linear-specific features.
+fn validate_tracker(parsed: &ParsedArgs) -> Result<(), Error> {
+    if parsed.tracker != Tracker::Jira {
+        if has_jira_flags(parsed) {
+            return Err(Error::Config(
+                "--jira-base-url, --jira-api-token and --jira-email require --tracker jira"
+                    .to_string(),
+            ));
+        }
+        return Ok(());
+    }
+    reject_flags(
+        "--tracker jira",
+        &[
+            ("--linear-api-key", parsed.linear_api_key.is_some()),
+            ("--linear-org", parsed.linear_org.is_some()),
+            ("--org-map", !parsed.org_map.is_empty()),
+            ("--linear-api-url", parsed.linear_api_url.is_some()),
+            ("--linear-header", !parsed.linear_headers.is_empty()),
+            ("--linear-actor-token", parsed.linear_actor_token.is_some()),
+            ("--linear-resolve", !parsed.linear_resolve.is_empty()),
+            ("--linear-snapshot", parsed.linear_snapshot.is_some()),
+            ("--ticket-pattern", parsed.ticket_pattern.is_some()),
+            (
+                "--other-org-urls",
+                parsed.other_org_urls != OtherOrgUrls::Drop,
+            ),
+            ("--filter", parsed.filter.is_some()),
+            (
+                "--assignee/--creator",
+                parsed.assignee.is_some() || parsed.creator.is_some(),
+            ),
+            (
+                "--drop-unknown-teams",
+                parsed.unknown_teams != UnknownTeams::Warn,
+            ),
+            (
+                "--follow-duplicates",
+                parsed.duplicates != Duplicates::Ignore,
+            ),
+            ("--manifest", parsed.manifest.is_some()),
+            ("--approve", parsed.approve.is_some()),
+            ("--compare-manifest", parsed.compare_manifest.is_some()),
+            ("--queue-file", parsed.queue_file.is_some()),
+            ("--checkpoint", parsed.checkpoint.is_some()),
+            ("--journal", parsed.journal.is_some()),
+            ("--explain", parsed.explain.is_some()),
+            (
+                "--order-by-dependencies/--note-blocked",
+                parsed.dependencies != Dependencies::Ignore,
+            ),
+            ("--comment-*", has_comment_flags(parsed)),
+            ("--release-issue-team", parsed.release_issue_team.is_some()),
+            ("--linear-project", parsed.linear_project.is_some()),
+            ("--add-to-project", parsed.add_to_project.is_some()),
+            ("--add-label", parsed.add_label.is_some()),
+            ("--set-custom-field", parsed.custom_field.is_some()),
+            ("--linear-cycle", parsed.linear_cycle.is_some()),
+            (
+                "--attach-release",
+                parsed.release_attachment == ReleaseAttachment::Attach,
+            ),
+            ("--webhook-url/--webhook-secret", has_webhook_flags(parsed)),
+            ("--slack-webhook", parsed.slack_webhook.is_some()),
+            ("--state-route", !parsed.state_routes.is_empty()),
+            (
+                "--target-state/--target-state-type",
+                parsed.target_state.is_some()
+                    || parsed.target_state_type.is_some(),
+            ),
+            ("--concurrency", parsed.concurrency.is_some()),
+            ("--batch-lookups", parsed.lookups == Lookups::Batched),
+            ("--history-file", parsed.history_file.is_some()),
+            (
+                "--quiet-updates",
+                parsed.notifications != Notifications::Normal,
+            ),
+            ("--diff", parsed.dry_run_output == DryRunOutput::Diff),
+        ],
+    )
+}
+
+/// Checks that `--merged-after` isn't later than `--merged-before`.
+fn validate_merge_window(parsed: &ParsedArgs) -> Result<(), Error> {
+    let (Some(after), Some(before)) =
+        (&parsed.merged_after, &parsed.merged_before)
+    else {
+        return Ok(());
+    };
+    if utils::parse_timestamp(after)? > utils::parse_timestamp(before)? {
+        return Err(Error::Config(format!(
+            "--merged-after {after} is later than --merged-before {before}"
+        )));
+    }
+    Ok(())
+}
+
+/// Checks that an `--approve` run makes no change the manifest doesn't
+/// cover: the manifest records each ticket's state and label change, so
+/// approving it can't vouch for comments, attachments, project and cycle
+/// memberships, the release issue or webhook events.
+fn validate_approved_changes(parsed: &ParsedArgs) -> Result<(), Error> {
+    for (flag, given) in [
+        (
+            "--comment-release/--comment-template",
+            parsed.comment_release(),
+        ),
+        (
+            "--attach-release",
+            parsed.release_attachment == ReleaseAttachment::Attach,
+        ),
+        ("--add-to-project", parsed.add_to_project.is_some()),
+        ("--linear-cycle", parsed.linear_cycle.is_some()),
+        ("--release-issue-team", parsed.release_issue_team.is_some()),
+        (
+            "--note-blocked",
+            parsed.dependencies == Dependencies::OrderAndNote,
+        ),
+        ("--webhook-url", parsed.webhook_url.is_some()),
+    ] {
+        if given {
+            return Err(Error::Config(format!(
+                "{flag} cannot be combined with --approve: the manifest doesn't cover it"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the flags choosing how tickets are applied (dry-run, approval,
+/// queue) fit together.
+fn validate_run_mode(mode: Mode, parsed: &ParsedArgs) -> Result<(), Error> {
+    if parsed.manifest.is_some() && !parsed.dry_run {
+        return Err(Error::Config("--manifest requires --dry-run".to_string()));
+    }
+    if parsed.approve.is_some() && parsed.dry_run {
+        return Err(Error::Config(
+            "--approve cannot be combined with --dry-run".to_string(),
+        ));
+    }
+    if parsed.queue_file.is_some() && parsed.dry_run {
+        return Err(Error::Config(
+            "--queue-file cannot be combined with --dry-run".to_string(),
+        ));
+    }
+    if parsed.journal.is_some()
+        && parsed.dry_run
+        && !matches!(mode, Mode::Rollback)
+    {
+        return Err(Error::Config(
+            "--journal cannot be combined with --dry-run".to_string(),
+        ));
+    }
+    if parsed.queue_file.is_some() && parsed.approve.is_some() {
+        return Err(Error::Config(
+            "--queue-file cannot be combined with --approve".to_string(),
+        ));
+    }
+    if parsed.approve.is_some() {
+        validate_approved_changes(parsed)?;
+    }
+    if parsed.checkpoint.is_some() && parsed.dry_run {
+        return Err(Error::Config(
+            "--checkpoint cannot be combined with --dry-run".to_string(),
+        ));
+    }
+    if parsed.checkpoint.is_some() && parsed.queue_file.is_some() {
+        return Err(Error::Config(
+            "--checkpoint cannot be combined with --queue-file".to_string(),
+        ));
+    }
+    if parsed.dry_run_output == DryRunOutput::Diff {
+        if !parsed.dry_run {
+            return Err(Error::Config("--diff requires --dry-run".to_string()));
+        }
+        if parsed.output_format != OutputFormat::Text {
+            return Err(Error::Config(
+                "--diff cannot be combined with --output-format ndjson|json"
+                    .to_string(),
+            ));
+        }
+    }
+    if parsed.webhook_secret.is_some() && parsed.webhook_url.is_none() {
+        return Err(Error::Config(
+            "--webhook-secret requires --webhook-url".to_string(),
+        ));
+    }
+    if parsed.linear_snapshot.is_some()
+        && !parsed.dry_run
+        && !matches!(mode, Mode::Teams | Mode::Report | Mode::Verify)
+    {
+        return Err(Error::Config(
+            "--linear-snapshot requires --dry-run".to_string(),
+        ));
+    }
+    for (flag, given) in [
+        ("--linear-project", parsed.linear_project.is_some()),
+        ("--add-to-project", parsed.add_to_project.is_some()),
+        ("--add-label", parsed.add_label.is_some()),
+        ("--set-custom-field", parsed.custom_field.is_some()),
+        ("--linear-cycle", parsed.linear_cycle.is_some()),
+        (
+            "--attach-release",
+            parsed.release_attachment == ReleaseAttachment::Attach,
+        ),
+        (
+            "--follow-duplicates",
+            parsed.duplicates != Duplicates::Ignore,
+        ),
+    ] {
+        if given && parsed.linear_snapshot.is_some() {
+            return Err(Error::Config(format!(
+                "{flag} cannot be combined with --linear-snapshot"
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Checks the flags given to teams.
+fn validate_teams(parsed: &ParsedArgs) -> Result<(), Error> {
+    reject_extraction_flags("teams", parsed)?;
+    reject_flags(
+        "teams",
+        &[
+            ("--release-tag", parsed.release_tag.is_some()),
+            ("--tracker", parsed.tracker != Tracker::Linear),
+            ("--jira-*", has_jira_flags(parsed)),
+            ("--linear-actor-token", parsed.linear_actor_token.is_some()),
+            ("--dry-run", parsed.dry_run),
+            ("--filter", parsed.filter.is_some()),
+            (
+                "--assignee/--creator",
+                parsed.assignee.is_some() || parsed.creator.is_some(),
+            ),
+            (
+                "--drop-unknown-teams",
+                parsed.unknown_teams != UnknownTeams::Warn,
+            ),
+            (
+                "--follow-duplicates",
+                parsed.duplicates != Duplicates::Ignore,
+            ),
+            (
+                "--fail-on-partial",
+                parsed.partial_failure != PartialFailure::Succeed,
+            ),
+            ("--manifest", parsed.manifest.is_some()),
+            ("--approve", parsed.approve.is_some()),
+            ("--compare-manifest", parsed.compare_manifest.is_some()),
+            ("--queue-file", parsed.queue_file.is_some()),
+            ("--checkpoint", parsed.checkpoint.is_some()),
+            ("--journal", parsed.journal.is_some()),
+            (
+                "--order-by-dependencies/--note-blocked",
+                parsed.dependencies != Dependencies::Ignore,
+            ),
+            ("--locale", parsed.locale.is_some()),
+            ("--locale-dir", parsed.locale_dir.is_some()),
+            ("--url-format", parsed.url_format != UrlFormat::Url),
+            ("--diff", parsed.dry_run_output == DryRunOutput::Diff),
+            (
+                "--output-format",
+                parsed.output_format != OutputFormat::Text,
+            ),
+            (
+                "--github-output",
+                parsed.github_output == GithubOutput::Write,
+            ),
+            ("--comment-*", has_comment_flags(parsed)),
+            ("--release-issue-team", parsed.release_issue_team.is_some()),
+            ("--linear-project", parsed.linear_project.is_some()),
+            ("--add-to-project", parsed.add_to_project.is_some()),
+            ("--add-label", parsed.add_label.is_some()),
+            ("--set-custom-field", parsed.custom_field.is_some()),
+            ("--linear-cycle", parsed.linear_cycle.is_some()),
+            (
+                "--attach-release",
+                parsed.release_attachment == ReleaseAttachment::Attach,
+            ),
+            ("--webhook-url/--webhook-secret", has_webhook_flags(parsed)),
+            ("--slack-webhook", parsed.slack_webhook.is_some()),
+            ("--org-map", !parsed.org_map.is_empty()),
+            ("--state-route", !parsed.state_routes.is_empty()),
+            ("--concurrency", parsed.concurrency.is_some()),
+            ("--batch-lookups", parsed.lookups == Lookups::Batched),
+            ("--history-file", parsed.history_file.is_some()),
+            (
+                "--repeat-release",
+                parsed.repeat_release != RepeatRelease::Warn,
+            ),
+            (
+                "--quarantine-after/--clear-quarantine",
+                parsed.quarantine_after.is_some()
+                    || !parsed.clear_quarantine.is_empty(),
+            ),
+            (
+                "--quiet-updates",
+                parsed.notifications != Notifications::Normal,
+            ),
+            ("--commit-status", parsed.commit_status_url.is_some()),
+            ("--deployment-*", has_deployment_flags(parsed)),
+            ("--stdin-timeout", parsed.stdin_timeout_secs.is_some()),
+            ("--cache-dir", parsed.cache_dir.is_some()),
+            ("--rate-limit", parsed.rate_limit.is_some()),
+            ("--api-budget", parsed.api_budget.is_some()),
+        ],
+    )
+}
+
+/// Checks the flags given to rollback, which only reads the journal and
+/// moves its tickets back.
+fn validate_rollback(parsed: &ParsedArgs) -> Result<(), Error> {
+    if parsed.journal.is_none() {
+        return Err(Error::Config("rollback requires --journal".to_string()));
+    }
+    reject_extraction_flags("rollback", parsed)?;
+    reject_flags(
+        "rollback",
+        &[
+            ("--release-tag", parsed.release_tag.is_some()),
+            ("--tracker", parsed.tracker != Tracker::Linear),
+            ("--jira-*", has_jira_flags(parsed)),
+            ("--linear-actor-token", parsed.linear_actor_token.is_some()),
+            ("--update-all-statuses", parsed.update_all_statuses),
+            ("--from-states", !parsed.from_states.is_empty()),
+            (
+                "--target-state/--target-state-type",
+                parsed.target_state.is_some()
+                    || parsed.target_state_type.is_some(),
+            ),
+            ("--filter", parsed.filter.is_some()),
+            (
+                "--assignee/--creator",
+                parsed.assignee.is_some() || parsed.creator.is_some(),
+            ),
+            (
+                "--drop-unknown-teams",
+                parsed.unknown_teams != UnknownTeams::Warn,
+            ),
+            (
+                "--follow-duplicates",
+                parsed.duplicates != Duplicates::Ignore,
+            ),
+            (
+                "--fail-on-partial",
+                parsed.partial_failure != PartialFailure::Succeed,
+            ),
+            ("--manifest", parsed.manifest.is_some()),
+            ("--approve", parsed.approve.is_some()),
+            ("--compare-manifest", parsed.compare_manifest.is_some()),
+            ("--queue-file", parsed.queue_file.is_some()),
+            ("--checkpoint", parsed.checkpoint.is_some()),
+            (
+                "--order-by-dependencies/--note-blocked",
+                parsed.dependencies != Dependencies::Ignore,
+            ),
+            ("--locale", parsed.locale.is_some()),
+            ("--locale-dir", parsed.locale_dir.is_some()),
+            ("--url-format", parsed.url_format != UrlFormat::Url),
+            ("--diff", parsed.dry_run_output == DryRunOutput::Diff),
+            (
+                "--output-format",
+                parsed.output_format != OutputFormat::Text,
+            ),
+            (
+                "--github-output",
+                parsed.github_output == GithubOutput::Write,
+            ),
+            ("--comment-*", has_comment_flags(parsed)),
+            ("--release-issue-team", parsed.release_issue_team.is_some()),
+            ("--linear-project", parsed.linear_project.is_some()),
+            ("--add-to-project", parsed.add_to_project.is_some()),
+            ("--add-label", parsed.add_label.is_some()),
+            ("--set-custom-field", parsed.custom_field.is_some()),
+            ("--linear-cycle", parsed.linear_cycle.is_some()),
+            (
+                "--attach-release",
+                parsed.release_attachment == ReleaseAttachment::Attach,
+            ),
+            ("--webhook-url/--webhook-secret", has_webhook_flags(parsed)),
+            ("--slack-webhook", parsed.slack_webhook.is_some()),
+            ("--org-map", !parsed.org_map.is_empty()),
+            ("--state-route", !parsed.state_routes.is_empty()),
+            ("--concurrency", parsed.concurrency.is_some()),
+            ("--batch-lookups", parsed.lookups == Lookups::Batched),
+            ("--history-file", parsed.history_file.is_some()),
+            (
+                "--repeat-release",
+                parsed.repeat_release != RepeatRelease::Warn,
+            ),
+            (
+                "--quarantine-after/--clear-quarantine",
+                parsed.quarantine_after.is_some()
+                    || !parsed.clear_quarantine.is_empty(),
+            ),
+            (
+                "--quiet-updates",
+                parsed.notifications != Notifications::Normal,
+            ),
+            ("--commit-status", parsed.commit_status_url.is_some()),
+            ("--deployment-*", has_deployment_flags(parsed)),
+            ("--stdin-timeout", parsed.stdin_timeout_secs.is_some()),
+            ("--cache-dir", parsed.cache_dir.is_some()),
+            ("--rate-limit", parsed.rate_limit.is_some()),
+            ("--api-budget", parsed.api_budget.is_some()),
+        ],
+    )
+}
+
+/// Checks the flags given to selftest, which runs the update path on one
+/// throwaway issue of the `--team`.
+fn validate_selftest(parsed: &ParsedArgs) -> Result<(), Error> {
+    if parsed.selftest_team.is_none() {
+        return Err(Error::Config("selftest requires --team".to_string()));
+    }
+    reject_extraction_flags("selftest", parsed)?;
+    reject_flags(
+        "selftest",
+        &[
+            ("--release-tag", parsed.release_tag.is_some()),
+            ("--tracker", parsed.tracker != Tracker::Linear),
+            ("--jira-*", has_jira_flags(parsed)),
+            ("--update-all-statuses", parsed.update_all_statuses),
+            ("--from-states", !parsed.from_states.is_empty()),
+            ("--filter", parsed.filter.is_some()),
+            (
+                "--assignee/--creator",
+                parsed.assignee.is_some() || parsed.creator.is_some(),
+            ),
+            (
+                "--drop-unknown-teams",
+                parsed.unknown_teams != UnknownTeams::Warn,
+            ),
+            (
+                "--follow-duplicates",
+                parsed.duplicates != Duplicates::Ignore,
+            ),
+            (
+                "--fail-on-partial",
+                parsed.partial_failure != PartialFailure::Succeed,
+            ),
+            ("--manifest", parsed.manifest.is_some()),
+            ("--approve", parsed.approve.is_some()),
+            ("--compare-manifest", parsed.compare_manifest.is_some()),
+            ("--queue-file", parsed.queue_file.is_some()),
+            ("--checkpoint", parsed.checkpoint.is_some()),
+            ("--journal", parsed.journal.is_some()),
+            (
+                "--order-by-dependencies/--note-blocked",
+                parsed.dependencies != Dependencies::Ignore,
+            ),
+            ("--locale", parsed.locale.is_some()),
+            ("--locale-dir", parsed.locale_dir.is_some()),
+            ("--url-format", parsed.url_format != UrlFormat::Url),
+            ("--diff", parsed.dry_run_output == DryRunOutput::Diff),
+            (
+                "--output-format",
+                parsed.output_format != OutputFormat::Text,
+            ),
+            (
+                "--github-output",
+                parsed.github_output == GithubOutput::Write,
+            ),
+            ("--comment-*", has_comment_flags(parsed)),
+            ("--release-issue-team", parsed.release_issue_team.is_some()),
+            ("--linear-project", parsed.linear_project.is_some()),
+            ("--add-to-project", parsed.add_to_project.is_some()),
+            ("--add-label", parsed.add_label.is_some()),
+            ("--set-custom-field", parsed.custom_field.is_some()),
+            ("--linear-cycle", parsed.linear_cycle.is_some()),
+            (
+                "--attach-release",
+                parsed.release_attachment == ReleaseAttachment::Attach,
+            ),
+            ("--webhook-url/--webhook-secret", has_webhook_flags(parsed)),
+            ("--slack-webhook", parsed.slack_webhook.is_some()),
+            ("--org-map", !parsed.org_map.is_empty()),
+            ("--linear-snapshot", parsed.linear_snapshot.is_some()),
+            ("--state-route", !parsed.state_routes.is_empty()),
+            ("--concurrency", parsed.concurrency.is_some()),
+            ("--batch-lookups", parsed.lookups == Lookups::Batched),
+            ("--history-file", parsed.history_file.is_some()),
+            (
+                "--repeat-release",
+                parsed.repeat_release != RepeatRelease::Warn,
+            ),
+            (
+                "--quarantine-after/--clear-quarantine",
+                parsed.quarantine_after.is_some()
+                    || !parsed.clear_quarantine.is_empty(),
+            ),
+            (
+                "--quiet-updates",
+                parsed.notifications != Notifications::Normal,
+            ),
+            ("--commit-status", parsed.commit_status_url.is_some()),
+            ("--deployment-*", has_deployment_flags(parsed)),
+            ("--stdin-timeout", parsed.stdin_timeout_secs.is_some()),
+            ("--cache-dir", parsed.cache_dir.is_some()),
+            ("--rate-limit", parsed.rate_limit.is_some()),
+            ("--api-budget", parsed.api_budget.is_some()),
+        ],
+    )
+}
+
+/// Rejects flags that only make sense for extract-tickets (and the modes
+/// that run it).
+fn reject_extraction_flags(
+    mode: &str,
+    parsed: &ParsedArgs,
+) -> Result<(), Error> {
+    reject_flags(
+        mode,
+        &[
+            ("--forge", parsed.forge != Forge::GitHub),
+            ("--github-host", parsed.github_host.is_some()),
+            ("--github-api", parsed.github_access == GithubAccess::Api),
+            (
+                "--allow-unmerged",
+                parsed.merge_policy != MergePolicy::RequireMerged,
+            ),
+            (
+                "--merged-after/--merged-before",
+                parsed.merged_after.is_some() || parsed.merged_before.is_some(),
+            ),
+            ("--mapping-file", parsed.mapping_file.is_some()),
+            ("--max-comments", parsed.max_comments.is_some()),
+            ("--pr-label/--path-filter", parsed.pr_scope.is_set()),
+            ("--batch-prs", parsed.pr_fetches == PrFetches::Batched),
+            ("--prefix-map", !parsed.prefix_map.is_empty()),
+            ("--exclude-pattern", parsed.exclude_pattern.is_some()),
+            ("--ticket-pattern", parsed.ticket_pattern.is_some()),
+            (
+                "--warn-unlinked-prs",
+                parsed.unlinked_prs != UnlinkedPrs::Ignore,
+            ),
+            (
+                "--no-stack-detection",
+                parsed.stacked_prs != StackedPrs::Detect,
+            ),
+            (
+                "--other-org-urls",
+                parsed.other_org_urls != OtherOrgUrls::Drop,
+            ),
+            ("--trailer-keys", !parsed.trailer_keys.is_empty()),
+            ("--ignore-tickets/--ignore-prs", has_ignore_list(parsed)),
+            (
+                "--ignore-token/--no-default-ignores",
+                has_ignore_flags(parsed),
+            ),
+        ],
+    )
+}
+
+/// Checks the flags given to parse-notes, which reads a release or input
+/// files.
+fn validate_parse_notes(parsed: &ParsedArgs) -> Result<(), Error> {
+    if !parsed.input_sources.is_empty()
+        && (parsed.release_tag.is_some() || parsed.since_tag.is_some())
+    {
+        return Err(Error::Config(
+            "parse-notes reads a release or input files, not both".to_string(),
+        ));
+    }
+    reject_update_flags("parse-notes", parsed)?;
+    reject_flags(
+        "parse-notes",
+        &[
+            ("--linear-org", parsed.linear_org.is_some()),
+            (
+                "--allow-unmerged",
+                parsed.merge_policy != MergePolicy::RequireMerged,
+            ),
+            (
+                "--merged-after/--merged-before",
+                parsed.merged_after.is_some() || parsed.merged_before.is_some(),
+            ),
+            ("--mapping-file", parsed.mapping_file.is_some()),
+            ("--max-comments", parsed.max_comments.is_some()),
+            ("--pr-label/--path-filter", parsed.pr_scope.is_set()),
+            ("--batch-prs", parsed.pr_fetches == PrFetches::Batched),
+            ("--concurrency", parsed.concurrency.is_some()),
+            ("--prefix-map", !parsed.prefix_map.is_empty()),
+            ("--exclude-pattern", parsed.exclude_pattern.is_some()),
+            ("--ticket-pattern", parsed.ticket_pattern.is_some()),
+            ("--tracker", parsed.tracker != Tracker::Linear),
+            (
+                "--warn-unlinked-prs",
+                parsed.unlinked_prs != UnlinkedPrs::Ignore,
+            ),
+            (
+                "--no-stack-detection",
+                parsed.stacked_prs != StackedPrs::Detect,
+            ),
+            (
+                "--other-org-urls",
+                parsed.other_org_urls != OtherOrgUrls::Drop,
+            ),
+            ("--trailer-keys", !parsed.trailer_keys.is_empty()),
+            ("--ignore-tickets", !parsed.ignore_list.tickets.is_empty()),
+            (
+                "--ignore-token/--no-default-ignores",
+                has_ignore_flags(parsed),
+            ),
+            ("--commit-status", parsed.commit_status_url.is_some()),
+            ("--deployment-*", has_deployment_flags(parsed)),
+            (
+                "--stdin-timeout without input files",
+                parsed.stdin_timeout_secs.is_some()
+                    && parsed.input_sources.is_empty(),
+            ),
+            ("--rate-limit", parsed.rate_limit.is_some()),
+            ("--api-budget", parsed.api_budget.is_some()),
+        ],
+    )
+}
+
+/// Checks the flags given to extract-tickets.
+fn validate_extract_tickets(parsed: &ParsedArgs) -> Result<(), Error> {
+    if parsed.release_tag.is_some() {
+        return Err(Error::Config(
+            "extract-tickets does not accept --release-tag".to_string(),
+        ));
+    }
+    reject_update_flags("extract-tickets", parsed)?;
+    reject_flags(
+        "extract-tickets",
+        &[
+            ("--commit-status", parsed.commit_status_url.is_some()),
+            ("--deployment-*", has_deployment_flags(parsed)),
+        ],
+    )?;
+    if parsed.other_org_urls == OtherOrgUrls::Tag
+        && parsed.output_format == OutputFormat::Text
+    {
+        return Err(Error::Config(
+            "--other-org-urls tag requires --output-format ndjson or json"
+                .to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Checks the flags given to extract-commits, which reads the commits of a
+/// release instead of PRs.
+fn validate_extract_commits(parsed: &ParsedArgs) -> Result<(), Error> {
+    if parsed.release_tag.is_none() {
+        return Err(Error::Config(
+            "extract-commits requires --release-tag".to_string(),
+        ));
+    }
+    reject_update_flags("extract-commits", parsed)?;
+    reject_flags(
+        "extract-commits",
+        &[
+            ("--forge", parsed.forge != Forge::GitHub),
+            (
+                "--allow-unmerged",
+                parsed.merge_policy != MergePolicy::RequireMerged,
+            ),
+            (
+                "--merged-after/--merged-before",
+                parsed.merged_after.is_some() || parsed.merged_before.is_some(),
+            ),
+            ("--mapping-file", parsed.mapping_file.is_some()),
+            ("--max-comments", parsed.max_comments.is_some()),
+            ("--pr-label/--path-filter", parsed.pr_scope.is_set()),
+            ("--batch-prs", parsed.pr_fetches == PrFetches::Batched),
+            ("--concurrency", parsed.concurrency.is_some()),
+            (
+                "--warn-unlinked-prs",
+                parsed.unlinked_prs != UnlinkedPrs::Ignore,
+            ),
+            (
+                "--no-stack-detection",
+                parsed.stacked_prs != StackedPrs::Detect,
+            ),
+            ("--ignore-prs", !parsed.ignore_list.prs.is_empty()),
+            ("--commit-status", parsed.commit_status_url.is_some()),
+            ("--deployment-*", has_deployment_flags(parsed)),
+            ("--stdin-timeout", parsed.stdin_timeout_secs.is_some()),
+            ("--cache-dir", parsed.cache_dir.is_some()),
+            ("--rate-limit", parsed.rate_limit.is_some()),
+            ("--api-budget", parsed.api_budget.is_some()),
+        ],
+    )?;
+    if parsed.other_org_urls == OtherOrgUrls::Tag
+        && parsed.output_format == OutputFormat::Text
+    {
+        return Err(Error::Config(
+            "--other-org-urls tag requires --output-format ndjson or json"
+                .to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Checks the flags given to report, which runs parse-notes and
+/// extract-tickets and only reads from Linear.
+fn validate_report(parsed: &ParsedArgs) -> Result<(), Error> {
+    if parsed.release_tag.is_none() {
+        return Err(Error::Config("report requires --release-tag".to_string()));
+    }
+    if parsed.output_format == OutputFormat::Ndjson {
+        return Err(Error::Config(
+            "report supports --output-format text (Markdown) or json"
+                .to_string(),
+        ));
+    }
+    reject_flags(
+        "report",
+        &[
+            ("--tracker", parsed.tracker != Tracker::Linear),
+            (
+                "--other-org-urls tag",
+                parsed.other_org_urls != OtherOrgUrls::Drop,
+            ),
+            ("--jira-*", has_jira_flags(parsed)),
+            ("--linear-actor-token", parsed.linear_actor_token.is_some()),
+            ("--dry-run", parsed.dry_run),
+            ("--update-all-statuses", parsed.update_all_statuses),
+            ("--from-states", !parsed.from_states.is_empty()),
+            ("--filter", parsed.filter.is_some()),
+            (
+                "--assignee/--creator",
+                parsed.assignee.is_some() || parsed.creator.is_some(),
+            ),
+            (
+                "--drop-unknown-teams",
+                parsed.unknown_teams != UnknownTeams::Warn,
+            ),
+            (
+                "--follow-duplicates",
+                parsed.duplicates != Duplicates::Ignore,
+            ),
+            (
+                "--fail-on-partial",
+                parsed.partial_failure != PartialFailure::Succeed,
+            ),
+            ("--manifest", parsed.manifest.is_some()),
+            ("--approve", parsed.approve.is_some()),
+            ("--compare-manifest", parsed.compare_manifest.is_some()),
+            ("--queue-file", parsed.queue_file.is_some()),
+            ("--checkpoint", parsed.checkpoint.is_some()),
+            ("--journal", parsed.journal.is_some()),
+            (
+                "--order-by-dependencies/--note-blocked",
+                parsed.dependencies != Dependencies::Ignore,
+            ),
+            ("--locale", parsed.locale.is_some()),
+            ("--locale-dir", parsed.locale_dir.is_some()),
+            ("--url-format", parsed.url_format != UrlFormat::Url),
+            ("--diff", parsed.dry_run_output == DryRunOutput::Diff),
+            (
+                "--github-output",
+                parsed.github_output == GithubOutput::Write,
+            ),
+            ("--comment-*", has_comment_flags(parsed)),
+            ("--release-issue-team", parsed.release_issue_team.is_some()),
+            ("--linear-project", parsed.linear_project.is_some()),
+            ("--add-to-project", parsed.add_to_project.is_some()),
+            ("--add-label", parsed.add_label.is_some()),
+            ("--set-custom-field", parsed.custom_field.is_some()),
+            ("--linear-cycle", parsed.linear_cycle.is_some()),
+            (
+                "--attach-release",
+                parsed.release_attachment == ReleaseAttachment::Attach,
+            ),
+            ("--webhook-url/--webhook-secret", has_webhook_flags(parsed)),
+            ("--slack-webhook", parsed.slack_webhook.is_some()),
+            ("--org-map", !parsed.org_map.is_empty()),
+            ("--state-route", !parsed.state_routes.is_empty()),
+            (
+                "--target-state/--target-state-type",
+                parsed.target_state.is_some()
+                    || parsed.target_state_type.is_some(),
+            ),
+            ("--concurrency", parsed.concurrency.is_some()),
+            ("--history-file", parsed.history_file.is_some()),
+            (
+                "--repeat-release",
+                parsed.repeat_release != RepeatRelease::Warn,
+            ),
+            (
+                "--quarantine-after/--clear-quarantine",
+                parsed.quarantine_after.is_some()
+                    || !parsed.clear_quarantine.is_empty(),
+            ),
+            (
+                "--quiet-updates",
+                parsed.notifications != Notifications::Normal,
+            ),
+            ("--commit-status", parsed.commit_status_url.is_some()),
+            ("--deployment-*", has_deployment_flags(parsed)),
+            ("--stdin-timeout", parsed.stdin_timeout_secs.is_some()),
+        ],
+    )
+}
+
+/// Checks the flags given to verify, which, like report, reads the release
+/// and changes nothing.
+fn validate_verify(parsed: &ParsedArgs) -> Result<(), Error> {
+    if parsed.release_tag.is_none() {
+        return Err(Error::Config("verify requires --release-tag".to_string()));
+    }
+    reject_flags(
+        "verify",
+        &[
+            ("--tracker", parsed.tracker != Tracker::Linear),
+            (
+                "--other-org-urls tag",
+                parsed.other_org_urls != OtherOrgUrls::Drop,
+            ),
+            ("--jira-*", has_jira_flags(parsed)),
+            ("--linear-actor-token", parsed.linear_actor_token.is_some()),
+            ("--dry-run", parsed.dry_run),
+            ("--update-all-statuses", parsed.update_all_statuses),
+            ("--from-states", !parsed.from_states.is_empty()),
+            ("--filter", parsed.filter.is_some()),
+            (
+                "--assignee/--creator",
+                parsed.assignee.is_some() || parsed.creator.is_some(),
+            ),
+            (
+                "--drop-unknown-teams",
+                parsed.unknown_teams != UnknownTeams::Warn,
+            ),
+            (
+                "--follow-duplicates",
+                parsed.duplicates != Duplicates::Ignore,
+            ),
+            (
+                "--fail-on-partial",
+                parsed.partial_failure != PartialFailure::Succeed,
+            ),
+            ("--manifest", parsed.manifest.is_some()),
+            ("--approve", parsed.approve.is_some()),
+            ("--compare-manifest", parsed.compare_manifest.is_some()),
+            ("--queue-file", parsed.queue_file.is_some()),
+            ("--checkpoint", parsed.checkpoint.is_some()),
+            ("--journal", parsed.journal.is_some()),
+            (
+                "--order-by-dependencies/--note-blocked",
+                parsed.dependencies != Dependencies::Ignore,
+            ),
+            ("--locale", parsed.locale.is_some()),
+            ("--locale-dir", parsed.locale_dir.is_some()),
+            (
+                "--output-format",
+                parsed.output_format != OutputFormat::Text,
+            ),
+            (
+                "--github-output",
+                parsed.github_output == GithubOutput::Write,
+            ),
+            ("--diff", parsed.dry_run_output == DryRunOutput::Diff),
+            ("--comment-*", has_comment_flags(parsed)),
+            ("--release-issue-team", parsed.release_issue_team.is_some()),
+            ("--linear-project", parsed.linear_project.is_some()),
+            ("--add-to-project", parsed.add_to_project.is_some()),
+            ("--add-label", parsed.add_label.is_some()),
+            ("--set-custom-field", parsed.custom_field.is_some()),
+            ("--linear-cycle", parsed.linear_cycle.is_some()),
+            (
+                "--attach-release",
+                parsed.release_attachment == ReleaseAttachment::Attach,
+            ),
+            ("--webhook-url/--webhook-secret", has_webhook_flags(parsed)),
+            ("--slack-webhook", parsed.slack_webhook.is_some()),
+            ("--org-map", !parsed.org_map.is_empty()),
+            ("--state-route", !parsed.state_routes.is_empty()),
+            ("--concurrency", parsed.concurrency.is_some()),
+            ("--history-file", parsed.history_file.is_some()),
+            (
+                "--repeat-release",
+                parsed.repeat_release != RepeatRelease::Warn,
+            ),
+            (
+                "--quarantine-after/--clear-quarantine",
+                parsed.quarantine_after.is_some()
+                    || !parsed.clear_quarantine.is_empty(),
+            ),
+            (
+                "--quiet-updates",
+                parsed.notifications != Notifications::Normal,
+            ),
+            ("--commit-status", parsed.commit_status_url.is_some()),
+            ("--deployment-*", has_deployment_flags(parsed)),
+            ("--stdin-timeout", parsed.stdin_timeout_secs.is_some()),
+        ],
+    )
+}
+
+/// Checks the flags given to update-tickets.
+fn validate_update_tickets(parsed: &ParsedArgs) -> Result<(), Error> {
+    if parsed.comment_release && parsed.release_tag.is_none() {
+        return Err(Error::Config(
+            "--comment-release requires --release-tag".to_string(),
+        ));
+    }
+    if parsed.comment_template.is_some() && parsed.release_tag.is_none() {
+        return Err(Error::Config(
+            "--comment-template requires --release-tag".to_string(),
+        ));
+    }
+    if parsed.release_attachment == ReleaseAttachment::Attach
+        && parsed.release_tag.is_none()
+    {
+        return Err(Error::Config(
+            "--attach-release requires --release-tag".to_string(),
+        ));
+    }
+    if parsed.release_issue_team.is_some() && parsed.release_tag.is_none() {
+        return Err(Error::Config(
+            "--release-issue-team requires --release-tag".to_string(),
+        ));
+    }
+    if !parsed.state_routes.is_empty() && parsed.release_tag.is_none() {
+        return Err(Error::Config(
+            "--state-route requires --release-tag".to_string(),
+        ));
+    }
+    reject_flags(
+        "update-tickets",
+        &[
+            ("--forge", parsed.forge != Forge::GitHub),
+            ("--github-host", parsed.github_host.is_some()),
+            (
+                "--allow-unmerged",
+                parsed.merge_policy != MergePolicy::RequireMerged,
+            ),
+            (
+                "--merged-after/--merged-before",
+                parsed.merged_after.is_some() || parsed.merged_before.is_some(),
+            ),
+            ("--mapping-file", parsed.mapping_file.is_some()),
+            ("--max-comments", parsed.max_comments.is_some()),
+            ("--pr-label/--path-filter", parsed.pr_scope.is_set()),
+            ("--batch-prs", parsed.pr_fetches == PrFetches::Batched),
+            (
+                "--warn-unlinked-prs",
+                parsed.unlinked_prs != UnlinkedPrs::Ignore,
+            ),
+            (
+                "--no-stack-detection",
+                parsed.stacked_prs != StackedPrs::Detect,
+            ),
+            (
+                "--other-org-urls",
+                parsed.other_org_urls != OtherOrgUrls::Drop,
+            ),
+            ("--trailer-keys", !parsed.trailer_keys.is_empty()),
+            ("--ignore-tickets/--ignore-prs", has_ignore_list(parsed)),
+            (
+                "--ignore-token/--no-default-ignores",
+                has_ignore_flags(parsed),
+            ),
+            ("--commit-status", parsed.commit_status_url.is_some()),
+            ("--deployment-*", has_deployment_flags(parsed)),
+        ],
+    )
+}
+
+/// Rejects flags that only make sense for update-tickets (and the
+/// orchestrator, which forwards them).
+fn reject_update_flags(mode: &str, parsed: &ParsedArgs) -> Result<(), Error> {
+    reject_flags(
+        mode,
+        &[
+            ("--linear-api-key", parsed.linear_api_key.is_some()),
+            ("--org-map", !parsed.org_map.is_empty()),
+            ("--linear-api-url", parsed.linear_api_url.is_some()),
+            ("--linear-header", !parsed.linear_headers.is_empty()),
+            ("--linear-actor-token", parsed.linear_actor_token.is_some()),
+            ("--linear-resolve", !parsed.linear_resolve.is_empty()),
+            ("--linear-snapshot", parsed.linear_snapshot.is_some()),
+            ("--debug-dump", parsed.debug_dump.is_some()),
+            ("--jira-*", has_jira_flags(parsed)),
+            ("--update-all-statuses", parsed.update_all_statuses),
+            ("--from-states", !parsed.from_states.is_empty()),
+            ("--filter", parsed.filter.is_some()),
+            (
+                "--assignee/--creator",
+                parsed.assignee.is_some() || parsed.creator.is_some(),
+            ),
+            (
+                "--drop-unknown-teams",
+                parsed.unknown_teams != UnknownTeams::Warn,
+            ),
+            (
+                "--follow-duplicates",
+                parsed.duplicates != Duplicates::Ignore,
+            ),
+            (
+                "--fail-on-partial",
+                parsed.partial_failure != PartialFailure::Succeed,
+            ),
+            ("--manifest", parsed.manifest.is_some()),
+            ("--approve", parsed.approve.is_some()),
+            ("--compare-manifest", parsed.compare_manifest.is_some()),
+            ("--queue-file", parsed.queue_file.is_some()),
+            ("--checkpoint", parsed.checkpoint.is_some()),
+            ("--journal", parsed.journal.is_some()),
+            (
+                "--order-by-dependencies/--note-blocked",
+                parsed.dependencies != Dependencies::Ignore,
+            ),
+            ("--locale", parsed.locale.is_some()),
+            ("--locale-dir", parsed.locale_dir.is_some()),
+            ("--url-format", parsed.url_format != UrlFormat::Url),
+            ("--diff", parsed.dry_run_output == DryRunOutput::Diff),
+            (
+                "--github-output",
+                parsed.github_output == GithubOutput::Write,
+            ),
+            ("--comment-*", has_comment_flags(parsed)),
+            ("--release-issue-team", parsed.release_issue_team.is_some()),
+            ("--linear-project", parsed.linear_project.is_some()),
+            ("--add-to-project", parsed.add_to_project.is_some()),
+            ("--add-label", parsed.add_label.is_some()),
+            ("--set-custom-field", parsed.custom_field.is_some()),
+            ("--linear-cycle", parsed.linear_cycle.is_some()),
+            (
+                "--attach-release",
+                parsed.release_attachment == ReleaseAttachment::Attach,
+            ),
+            ("--webhook-url/--webhook-secret", has_webhook_flags(parsed)),
+            ("--slack-webhook", parsed.slack_webhook.is_some()),
+            ("--state-route", !parsed.state_routes.is_empty()),
+            (
+                "--target-state/--target-state-type",
+                parsed.target_state.is_some()
+                    || parsed.target_state_type.is_some(),
+            ),
+            ("--batch-lookups", parsed.lookups == Lookups::Batched),
+            ("--history-file", parsed.history_file.is_some()),
+            (
+                "--repeat-release",
+                parsed.repeat_release != RepeatRelease::Warn,
+            ),
+            (
+                "--quarantine-after/--clear-quarantine",
+                parsed.quarantine_after.is_some()
+                    || !parsed.clear_quarantine.is_empty(),
+            ),
+            (
+                "--quiet-updates",
+                parsed.notifications != Notifications::Normal,
+            ),
+        ],
+    )
+}
+
+fn has_ignore_flags(parsed: &ParsedArgs) -> bool {
+    !parsed.false_positives.builtin || !parsed.false_positives.extra.is_empty()
+}
+
+/// Whether `--ignore-tickets` or `--ignore-prs` was given.
+fn has_ignore_list(parsed: &ParsedArgs) -> bool {
+    !parsed.ignore_list.tickets.is_empty() || !parsed.ignore_list.prs.is_empty()
+}
+
+fn has_comment_flags(parsed: &ParsedArgs) -> bool {
+    parsed.comment_pacing.is_set()
+        || parsed.comment_release
+        || parsed.comment_template.is_some()
+}
+
+fn has_deployment_flags(parsed: &ParsedArgs) -> bool {
+    parsed.deployment.environment.is_some()
+        || parsed.deployment.url.is_some()
+        || parsed.deployment.timeout_secs.is_some()
+}
+
+fn has_webhook_flags(parsed: &ParsedArgs) -> bool {
+    parsed.webhook_url.is_some() || parsed.webhook_secret.is_some()
+}
+
+fn has_jira_flags(parsed: &ParsedArgs) -> bool {
+    parsed.jira.base_url.is_some()
+        || parsed.jira.api_token.is_some()
+        || parsed.jira.email.is_some()
+}
+
+/// Rejects every flag in `flags` that was given (`true`), naming them all.
+fn reject_flags(mode: &str, flags: &[(&str, bool)]) -> Result<(), Error> {
+    let given: Vec<&str> = flags
+        .iter()
+        .filter_map(|&(flag, present)| present.then_some(flag))
+        .collect();
+
+    if given.is_empty() {
+        return Ok(());
+    }
+    Err(Error::Config(format!(
+        "{mode} does not accept {}",
+        given.join(", ")
+    )))
+}
+
+fn handle_help(args: &[String]) {
+    let mode_arg = args.get(1).map(std::string::String::as_str);
+    match mode_arg {
+        Some("parse-notes") => print_parse_notes_help(),
+        Some("extract-tickets") => print_extract_tickets_help(),
+        Some("extract-commits") => print_extract_commits_help(),
+        Some("update-tickets") => print_update_tickets_help(),
+        Some("teams") => print_teams_help(),
+        Some("report") => print_report_help(),
+        Some("verify") => print_verify_help(),
+        Some("schema") => print_schema_help(),
+        Some("rollback") => print_rollback_help(),
+        Some("selftest") => print_selftest_help(),
+        Some("run") => print_run_help(),
+        Some("serve") => print_serve_help(),
+        _ => {
+            print_general_help();
+            print_logging_help();
+        }
+    }
+}
+
+fn print_general_help() {
+    println!(concat!(
+        "release-linear-ticket-update ",
+        env!("CARGO_PKG_VERSION"),
+        "\n",
+        "\n",
+        "A tool for automatically marking Linear tickets as completed when a GitHub release is published.\n",
+        "\n",
+        "USAGE:\n",
+        "    release-linear-ticket-update [MODE] [OPTIONS] [FILES...]\n",
+        "\n",
+        "MODES:\n",
+        "    parse-notes        Parse release notes to extract PR numbers\n",
+        "    extract-tickets    Extract Linear ticket IDs from PR content\n",
+        "    extract-commits    Extract Linear ticket IDs from the commits of a release (no PRs)\n",
+        "    update-tickets     Update Linear tickets to completed state\n",
+        "    teams              List Linear teams and their workflow states\n",
+        "    report             Report a release's PRs, tickets and ticket states (read-only)\n",
+        "    verify             Fail unless every ticket of a release is completed (read-only)\n",
+        "    schema             Print the JSON Schema of the machine outputs\n",
+        "    rollback           Move tickets back to the states a --journal run moved them out of\n",
+        "    selftest           Run the update path on a throwaway issue of a sandbox team\n",
+        "    run NAME           Run a pipeline of stages defined in the config file\n",
+        "    serve              Run the orchestrator for every release published to a GitHub webhook\n",
+        "    (default)          Run orchestrator mode (full pipeline)\n",
+        "\n",
+        "OPTIONS:\n",
+        "    --help, -h         Print this help message\n",
+        "    --version          Print version information\n",
+        "\n",
+        "    --release-tag TAG\n",
+        "            GitHub release tag (required for parse-notes, extract-commits, report, verify and orchestrator\n",
+        "            modes, and for --comment-release, --release-issue-team and --history-file)\n",
+        "\n",
+        "    --team KEY\n",
+        "            Sandbox team the selftest mode creates (and deletes) its throwaway issue in\n",
+        "\n",
+        "    --linear-api-key KEY\n",
+        "            Linear API authentication key (can also be set via LINEAR_API_KEY env var)\n",
+        "\n",
+        "    --linear-org ORG\n",
+        "            Linear organization identifier (can also be set via LINEAR_ORG env var)\n",
+        "\n",
+        "    --dry-run\n",
+        "            Preview changes without updating (parse-notes and extract-tickets: fetch their input\n",
+        "            and log counts instead of printing their output)\n",
+        "\n",
+        "    --update-all-statuses\n",
+        "            Update regardless of current Linear state (default: only \"Passing\")\n",
+        "\n",
+        "    --filter EXPR\n",
+        "            Only update tickets matching EXPR, e.g. 'team == \"ENG\" && !labels.contains(\"hold\")'\n",
+        "\n",
+        "    --assignee me|EMAIL, --creator me|EMAIL\n",
+        "            Only update tickets assigned to / created by this user (me: the API key's user)\n",
+        "\n",
+        "    --drop-unknown-teams\n",
+        "            Skip tickets whose team key isn't a team of the workspace (default: warn only)\n",
+        "\n",
+        "    --fail-on-partial\n",
+        "            Exit with status 4 if some tickets failed to update (default: exit 0)\n",
+        "\n",
+        "    --locale LOCALE, --locale-dir DIR\n",
+        "            Language of the end-of-run summary, loaded from the template file DIR/LOCALE.txt\n",
+        "            (default: built-in English; DIR: locales)\n",
+        "\n",
+        "    --output-format text|ndjson|json\n",
+        "            Print identifiers (update-tickets: tickets as per --url-format), one record per line,\n",
+        "            or a JSON array of the records at the end (default: text)\n",
+        "\n",
+        "    --url-format id|url|markdown\n",
+        "            How update-tickets prints processed tickets: ABC-123, its Linear URL, or a Markdown link\n",
+        "            (default: url)\n",
+        "\n",
+        "    --explain ABC-123|123|owner/repo#123\n",
+        "            Log why each stage did or didn't pass on this ticket or PR, as explain: lines (where it\n",
+        "            was found, the filters it passed or failed, the state decision). Not with --tracker jira\n",
+        "\n",
+        "    --stdin-timeout SECS\n",
+        "            When extract-tickets, update-tickets or parse-notes (with files or -) reads a terminal, give\n",
+        "            up if no input arrives within SECS (default: 30; 0 waits forever). Piped input is never timed out\n",
+        "\n",
+        "    --commit-status URL\n",
+        "            Orchestrator only: post a release/linear-sync commit status on the release commit, linking to URL\n",
+        "\n",
+        "    --deployment-environment ENV, --deployment-url URL\n",
+        "            Orchestrator only: before updating tickets, check that the release tag's commit was\n",
+        "            deployed to GitHub environment ENV, and/or that URL reports the release version\n",
+        "\n",
+        "    --deployment-timeout SECS\n",
+        "            Wait up to SECS for the deployment, checking every 30 seconds (default: check once)"
+    ));
+    print_extraction_help();
+    print_relations_help();
+    print_approval_help();
+    print_release_tracking_help();
+    print_tracker_help();
+    print_parallel_help();
+    print_config_file_help();
+    print_network_help();
+}
+
+fn print_logging_help() {
+    println!(concat!(
+        "\n",
+        "LOGGING OPTIONS:\n",
+        "    --quiet\n",
+        "            Log only failures and warnings, e.g. to keep CI logs short\n",
+        "\n",
+        "    -v, --verbose\n",
+        "            Also log per-ticket details: each ticket's current state, team and state lookups\n",
+        "\n",
+        "    -vv\n",
+        "            Also log every Linear GraphQL request and response (never the credentials)\n",
+        "\n",
+        "    --log-format text|json\n",
+        "            json: log one JSON object per line, with timestamp, level, stage, the ticket or PR\n",
+        "            being processed, and message, e.g. for a log aggregator (default: text)"
+    ));
+}
+
+fn print_relations_help() {
+    println!(concat!(
+        "\n",
+        "RELATION OPTIONS:\n",
+        "    --order-by-dependencies\n",
+        "            Update blockers before the tickets they block; report tickets completed with open blockers\n",
+        "\n",
+        "    --note-blocked\n",
+        "            Like --order-by-dependencies, and comment on blocked tickets when their blocker is completed\n",
+        "\n",
+        "    --follow-duplicates canonical|both\n",
+        "            Update the issue a duplicate ticket is marked a duplicate of instead of it, or both"
+    ));
+}
+
+fn print_extraction_help() {
+    println!(concat!(
+        "\n",
+        "EXTRACTION OPTIONS:\n",
+        "    --require-merged\n",
+        "            Skip tickets of PRs that were never merged (default)\n",
+        "\n",
+        "    --allow-unmerged\n",
+        "            Extract tickets from open, draft and closed-without-merge PRs too\n",
+        "\n",
+        "    --merged-after TIME, --merged-before TIME\n",
+        "            Only extract tickets from PRs merged within this window (ISO 8601, e.g. 2024-05-01 or\n",
+        "            2024-05-01T12:30:00Z; bounds included)\n",
+        "\n",
+        "    --mapping-file FILE\n",
+        "            Write which PR (and where in it) each ticket was found in to FILE as JSON\n",
+        "\n",
+        "    --max-comments N\n",
+        "            Scan at most the first N comments of each PR (default: all of them, fetched page by page\n",
+        "            when a PR has more than gh pr view returns; 0 skips comments)\n",
+        "\n",
+        "    --batch-prs\n",
+        "            Read all PR numbers first, then fetch the PRs 25 per GitHub GraphQL query instead of one\n",
+        "            gh pr view each\n",
+        "\n",
+        "    --pr-label LABEL, --path-filter GLOB\n",
+        "            Monorepos: only take tickets of PRs with one of these labels, or changing a file matching\n",
+        "            one of these globs (services/api/**); others are skipped and listed (repeatable)\n",
+        "\n",
+        "    --warn-unlinked-prs\n",
+        "            Log every PR without a Linear ticket reference and list them at the end\n",
+        "\n",
+        "    --no-stack-detection\n",
+        "            Also search commits, stack listings and merge(-queue) commits that belong to sibling PRs\n",
+        "            of a stack\n",
+        "\n",
+        "    --other-org-urls drop|tag\n",
+        "            Tickets referenced only by linear.app URLs of another workspace than --linear-org are\n",
+        "            left out (drop, the default) or output tagged with that workspace, for update-tickets to\n",
+        "            skip unless it is the ticket's --org-map workspace (tag; ndjson or json output only)\n",
+        "\n",
+        "    --prefix-map OLD=NEW\n",
+        "            Treat tickets of team key OLD as NEW-..., e.g. after a team key rename (repeatable)\n",
+        "\n",
+        "    --exclude-pattern REGEX\n",
+        "            Never extract or update tickets whose whole ID matches the extended regex REGEX,\n",
+        "            e.g. 'TMP-.*|SAND-.*' for sandbox teams\n",
+        "\n",
+        "    --ignore-token REGEX\n",
+        "            Never take tokens whose whole text matches the extended regex REGEX for tickets, on top\n",
+        "            of the built-in list of UTF-8, SHA-256, RFC-1234, ISO-8601, ... (repeatable)\n",
+        "\n",
+        "    --no-default-ignores\n",
+        "            Don't apply the built-in list, e.g. for a team whose key is one of its prefixes\n",
+        "\n",
+        "    --trailer-keys KEYS\n",
+        "            Comma-separated commit trailer keys whose values link tickets, e.g. 'Linear,Ticket'\n",
+        "            (default: Linear)\n",
+        "\n",
+        "    --previous-tag TAG\n",
+        "            extract-commits only: search the commits after TAG (default: the previous release's tag)\n",
+        "\n",
+        "    --commit-source github|git\n",
+        "            extract-commits only: list the commits from GitHub's comparison of the tags (github, the\n",
+        "            default) or with git log in the current clone (git)\n",
+        "\n",
+        "    --ignore-tickets LIST, --ignore-prs LIST\n",
+        "            Never extract these tickets (e.g. docs tickets), or take these PRs (e.g. reverts):\n",
+        "            a file with one per line ('#' comments), or else a comma-separated list\n",
+        "\n",
+        "    --ticket-pattern REGEX\n",
+        "            Extended regex of Linear ticket IDs, found by extract-tickets and required in full by\n",
+        "            update-tickets, e.g. '[A-Z]{{2,4}}-[0-9]+' (default: '[A-Z]{{3}}-[0-9]+')"
+    ));
+}
+
+fn print_approval_help() {
+    println!(concat!(
+        "\n",
+        "APPROVAL OPTIONS:\n",
+        "    --manifest FILE\n",
+        "            With --dry-run, write a hash-stamped manifest of intended changes to FILE\n",
+        "\n",
+        "    --approve HASH\n",
+        "            Plan all tickets first and apply only if the plan matches the approved manifest hash\n",
+        "\n",
+        "    --compare-manifest FILE\n",
+        "            After the run, report tickets whose state changed since the dry run that wrote FILE\n",
+        "\n",
+        "    --queue-file FILE\n",
+        "            Record the tickets still to process in FILE; if FILE exists, resume from it and ignore input\n",
+        "\n",
+        "    --checkpoint FILE\n",
+        "            Append every processed ticket to FILE; a re-run with the same FILE skips them\n",
+        "\n",
+        "    --journal FILE\n",
+        "            Append every attempted state change to FILE as JSON, for audits and the rollback mode"
+    ));
+}
+
+fn print_release_tracking_help() {
+    println!(concat!(
+        "\n",
+        "RELEASE TRACKING OPTIONS:\n",
+        "    --quiet-updates\n",
+        "            Plan every ticket first, then update them in batches and don't subscribe to commented tickets\n",
+        "\n",
+        "    --comment-release\n",
+        "            Comment the release on each completed ticket; skip tickets already marked for it\n",
+        "\n",
+        "    --comment-template FILE\n",
+        "            Like --comment-release, with the Markdown comment read from FILE; {{tag}}, {{release_url}},\n",
+        "            {{date}}, {{prs}}, {{pr_list}} and {{ticket}} are replaced with the release and the ticket's PR links\n",
+        "\n",
+        "    --release-issue-team KEY\n",
+        "            Create a \"Release TAG\" issue in team KEY with a checklist of the completed tickets\n",
+        "\n",
+        "    --linear-project PROJECT\n",
+        "            Only complete started tickets of the Linear project (name or slug ID) representing the\n",
+        "            release, and report its completion before and after\n",
+        "\n",
+        "    --add-to-project PROJECT\n",
+        "            Add every updated ticket to the Linear project (name or slug ID)\n",
+        "\n",
+        "    --add-label LABEL\n",
+        "            Apply the workspace label LABEL (e.g. released:v1.2.3) to every updated ticket,\n",
+        "            creating it if missing\n",
+        "\n",
+        "    --set-custom-field NAME=VALUE\n",
+        "            Set the label group NAME (Linear's custom fields) to its label VALUE on every\n",
+        "            updated ticket, in the mutation moving it; VALUE is created if missing\n",
+        "\n",
+        "    --linear-cycle CYCLE\n",
+        "            Add every updated ticket to its team's current, next or numbered cycle\n",
+        "\n",
+        "    --attach-release\n",
+        "            Attach the GitHub release to every updated ticket, and skip tickets it is already\n",
+        "            attached to\n",
+        "\n",
+        "    --webhook-url URL, --webhook-secret SECRET\n",
+        "            POST a ticket.released JSON event to URL for every updated ticket, signed with\n",
+        "            HMAC-SHA256 of SECRET (default: WEBHOOK_SECRET) in X-Release-Signature-256\n",
+        "\n",
+        "    --slack-webhook URL\n",
+        "            Post a summary of the updated and failed tickets to a Slack incoming webhook at the\n",
+        "            end of the run (default: SLACK_WEBHOOK_URL)\n",
+        "\n",
+        "    --state-route PATTERN=STATE\n",
+        "            Move tickets of releases whose tag matches PATTERN (* is a wildcard) to STATE instead of\n",
+        "            completing them, e.g. '*-rc*=In Staging' (repeatable; first match wins)\n",
+        "\n",
+        "    --target-state NAME, --target-state-type TYPE\n",
+        "            Move tickets to the workflow state named NAME (and/or the first of type TYPE, e.g.\n",
+        "            completed) instead of the Completed/Done state, unless a --state-route applies\n",
+        "\n",
+        "    --from-states STATES\n",
+        "            Update tickets in these comma-separated states (e.g. 'QA Done,Staged') instead of\n",
+        "            \"Passing\" (repeatable; --update-all-statuses still accepts any state)\n",
+        "\n",
+        "    --history-file FILE\n",
+        "            Append every processed ticket to FILE (shared across runs) and flag tickets an earlier\n",
+        "            release already completed\n",
+        "\n",
+        "    --repeat-release warn|fail\n",
+        "            With --history-file, only report such tickets (default) or also fail the run\n",
+        "\n",
+        "    --quarantine-after N\n",
+        "            With --history-file, quarantine tickets that failed in N runs in a row: they are\n",
+        "            listed at the end of every run and not retried\n",
+        "\n",
+        "    --clear-quarantine ID\n",
+        "            Retry quarantined ticket ID in this run, starting a new count of failed runs\n",
+        "            (repeatable)\n",
+        "\n",
+        "    --github-output\n",
+        "            In GitHub Actions, write the update-tickets counts and updated ticket URLs to\n",
+        "            $GITHUB_OUTPUT and a Markdown summary to $GITHUB_STEP_SUMMARY\n",
+        "\n",
+        "    --diff\n",
+        "            With --dry-run, print a table of every ticket (current -> target state, team, and\n",
+        "            why it would be skipped) instead of the URLs of the tickets that would be updated\n",
+        "\n",
+        "    --comment-interval MS\n",
+        "            Minimum delay between Linear comments (default: 1000)\n",
+        "\n",
+        "    --comment-batch-size N\n",
+        "            Pause after every N comments (default: 25)\n",
+        "\n",
+        "    --comment-batch-pause SECS\n",
+        "            Length of the pause between comment batches (default: 30)\n",
+        "\n",
+        "    --comment-progress FILE\n",
+        "            Record posted comments in FILE and skip them when re-run"
+    ));
+}
+
+fn print_tracker_help() {
+    println!(concat!(
+        "\n",
+        "TRACKER OPTIONS:\n",
+        "    --tracker linear|jira\n",
+        "            Issue tracker to complete tickets in (default: linear). With jira, extract-tickets finds\n",
+        "            Jira issue keys (e.g. PROJ-123) and update-tickets transitions them to a done status\n",
+        "\n",
+        "    --jira-base-url URL\n",
+        "            Jira site URL, e.g. https://acme.atlassian.net (can also be set via JIRA_BASE_URL env var)\n",
+        "\n",
+        "    --jira-api-token TOKEN\n",
+        "            Jira API token, sent as a bearer token (can also be set via JIRA_API_TOKEN env var)\n",
+        "\n",
+        "    --jira-email EMAIL\n",
+        "            Jira Cloud account email; the API token is then sent with Basic authentication\n",
+        "            (can also be set via JIRA_EMAIL env var)\n",
+        "\n",
+        "    --org-map TEAM=ORG:KEY\n",
+        "            Update-tickets and orchestrator modes: route TEAM's tickets to workspace ORG with API key\n",
+        "            KEY, or that of environment variable NAME for env:NAME (comma-separated or repeatable)"
+    ));
+}
+
+fn print_parallel_help() {
+    println!(concat!(
+        "\n",
+        "PARALLEL OPTIONS:\n",
+        "    --release-tag TAG (repeated)\n",
+        "            In orchestrator mode, process every given release, e.g. to backfill past releases\n",
+        "\n",
+        "    --since-tag TAG, --until-tag TAG\n",
+        "            Instead of --release-tag, process every release created after TAG, up to and including\n",
+        "            --until-tag (default: the latest), e.g. to catch up on hotfix releases (orchestrator;\n",
+        "            parse-notes reads the notes of them all)\n",
+        "\n",
+        "    --jobs N\n",
+        "            Number of releases the orchestrator processes concurrently (default: 4)\n",
+        "\n",
+        "    --in-process\n",
+        "            Run the orchestrator's stages on threads of this process, passing their records over\n",
+        "            channels, instead of spawning the binary for each (e.g. when it is run through a wrapper)\n",
+        "\n",
+        "    --concurrency N\n",
+        "            Number of PRs extract-tickets fetches, and tickets update-tickets looks up and updates,\n",
+        "            concurrently (default: 1); not with --order-by-dependencies, --note-blocked, --approve\n",
+        "            or --quiet-updates\n",
+        "\n",
+        "    --batch-lookups\n",
+        "            Read all input first, then look up tickets 25 per Linear query and workflow states in\n",
+        "            one query, instead of one query each per ticket\n",
+        "\n",
+        "    --cache-dir DIR\n",
+        "            Cache of GitHub PR responses and Linear workflow states shared by concurrent pipelines\n",
+        "            (default with several releases: a temporary directory removed afterwards)\n",
+        "\n",
+        "    --rate-limit N[/s]\n",
+        "            GitHub and Linear requests per second (a token bucket of N), across every process\n",
+        "            sharing the cache dir (default with several releases: 10)\n",
+        "\n",
+        "    --api-budget N\n",
+        "            Send at most N GitHub and Linear requests in the whole run, then stop and exit with\n",
+        "            status 3 (partial result)"
+    ));
+}
+
+fn print_config_file_help() {
+    println!(concat!(
+        "\n",
+        "CONFIG FILE OPTIONS:\n",
+        "    --config FILE\n",
+        "            Read default flags from the TOML file FILE (orchestrator mode; default:\n",
+        "            .release-linear.toml at the repository root, if present). Each key is a flag name,\n",
+        "            e.g. linear-org = \"acme\"; flags given on the command line take precedence\n",
+        "\n",
+        "    --no-config\n",
+        "            Don't read .release-linear.toml\n",
+        "\n",
+        "    [[pipeline.NAME]] tables of the config file define pipelines for the run mode\n",
+        "    (see run --help)"
+    ));
+}
+
+fn print_network_help() {
+    println!(concat!(
+        "\n",
+        "NETWORK OPTIONS:\n",
+        "    --forge FORGE\n",
+        "            github (default) or gitlab: read releases and merge requests (!123) through glab\n",
+        "\n",
+        "    --discover MODE\n",
+        "            Where parse-notes finds a release's PRs: notes (the release notes, the default),\n",
+        "            compare (the commits since the previous release) or both\n",
+        "\n",
+        "    --github-host HOST\n",
+        "            GitHub host used by gh, e.g. an Enterprise Server or internal gateway (sets GH_HOST)\n",
+        "\n",
+        "    --github-api\n",
+        "            Call the GitHub REST and GraphQL APIs with GITHUB_TOKEN (or GH_TOKEN) instead of gh, at\n",
+        "            https://HOST/api/v3 with --github-host, else GITHUB_API_URL, else api.github.com\n",
+        "\n",
+        "    --github-repo OWNER/REPO\n",
+        "            Repository of the release and of bare PR numbers with --github-api\n",
+        "            (default: GITHUB_REPOSITORY)\n",
+        "\n",
+        "    --linear-api-url URL\n",
+        "            Linear GraphQL endpoint (can also be set via LINEAR_API_URL env var)\n",
+        "            Requests are sent in-process; over HTTPS, curl is used instead in builds without\n",
+        "            the native-http feature\n",
+        "\n",
+        "    --linear-header 'NAME: VALUE'\n",
+        "            Extra HTTP header for Linear API requests (repeatable)\n",
+        "\n",
+        "    --linear-actor-token TOKEN\n",
+        "            Make Linear changes as the OAuth app (actor=application) this token belongs to, e.g. a\n",
+        "            \"Release Bot\", instead of the API key's user (can also be set via LINEAR_ACTOR_TOKEN)\n",
+        "\n",
+        "    --linear-resolve HOST:PORT:ADDRESS\n",
+        "            Resolve HOST:PORT to ADDRESS for Linear API requests without DNS (repeatable)\n",
+        "\n",
+        "    --linear-snapshot FILE\n",
+        "            Read issues, teams and workflow states from a JSON workspace snapshot instead of the\n",
+        "            Linear API (with --dry-run, and for teams)\n",
+        "\n",
+        "    --debug-dump FILE\n",
+        "            Append Linear and Jira API responses that aren't valid JSON to FILE, with the request\n",
+        "            they answered (update-tickets and teams)\n",
+        "\n",
+        "See 'release-linear-ticket-update <MODE> --help' for more information on a specific mode."
+    ));
+}
+
+fn print_parse_notes_help() {
+    println!(concat!(
+        "release-linear-ticket-update parse-notes\n",
+        "\n",
+        "Extracts Pull Request identifiers (123 or owner/repo#123) from release notes.\n",
         "\n",
         "USAGE:\n",
         "    release-linear-ticket-update parse-notes --release-tag <TAG>\n",
+        "    release-linear-ticket-update parse-notes --since-tag <TAG> [--until-tag <TAG>]\n",
+        "    release-linear-ticket-update parse-notes [FILES...]\n",
         "    echo \"...\" | release-linear-ticket-update parse-notes\n",
         "\n",
+        "ARGS:\n",
+        "    [FILES...]    Files of release notes (e.g. saved Markdown), read in order as one set of notes.\n",
+        "                  Reads from stdin if neither files nor a release tag are given, or if '-' is used.\n",
+        "\n",
         "OPTIONS:\n",
-        "    --release-tag <TAG>    GitHub release tag (required if not using stdin)\n",
+        "    --release-tag <TAG>    GitHub release tag (required if not using stdin or files)\n",
+        "    --since-tag <TAG>      Read the notes of every release created after TAG instead, oldest\n",
+        "                           first, and output each PR once\n",
+        "    --until-tag <TAG>      Last release read with --since-tag (default: the latest)\n",
+        "    --github-host <HOST>   GitHub host used by gh (sets GH_HOST)\n",
+        "    --github-api           Read the release with GITHUB_TOKEN from the REST API instead of gh\n",
+        "    --github-repo <REPO>   OWNER/REPO of the release with --github-api (default: GITHUB_REPOSITORY)\n",
+        "    --forge <FORGE>        github (default) or gitlab: read the GitLab release, and find MRs\n",
+        "                           (!123, group/project!123 or .../-/merge_requests/123)\n",
+        "    --discover <MODE>      notes (default), compare (PRs named by the commits since the previous\n",
+        "                           release: \"Merge pull request #123\" or \"... (#123)\") or both\n",
+        "    --output-format <FMT>  text (PR identifiers), ndjson (PR records) or json (array of them)\n",
+        "    --dry-run              Fetch and parse the notes, but only log how many PRs they reference\n",
+        "    --explain <PR>         Log whether the notes reference PR 123 (or owner/repo#123)\n",
+        "    --ignore-prs <LIST>    Leave out these PRs, e.g. reverts: a file with one per line, or\n",
+        "                           comma-separated (123,owner/repo#45)\n",
+        "    --cache-dir <DIR>      Cache the release notes in DIR, and read them from there when cached\n",
+        "    --stdin-timeout <SECS> With files or '-', give up if a terminal stdin sends nothing for SECS\n",
         "    --help, -h             Print this help message"
     ));
 }
@@ -483,10 +5406,95 @@ fn print_extract_tickets_help() {
         "    cat prs.txt | release-linear-ticket-update extract-tickets\n",
         "\n",
         "ARGS:\n",
-        "    [FILES...]    Files containing PR numbers (one per line). Reads from stdin if no files provided or if '-' is used.\n",
+        "    [FILES...]    Files containing PR identifiers (123, owner/repo#123, or ndjson PR records, one per line). Reads from stdin if no files provided or if '-' is used.\n",
         "\n",
         "OPTIONS:\n",
-        "    --help, -h    Print this help message"
+        "    --github-host <HOST>    GitHub host used by gh (sets GH_HOST)\n",
+        "    --github-api            Fetch PRs with GITHUB_TOKEN from the GitHub APIs instead of gh\n",
+        "    --github-repo <REPO>    OWNER/REPO of bare PR numbers with --github-api\n",
+        "                            (default: GITHUB_REPOSITORY)\n",
+        "    --forge <FORGE>         github (default) or gitlab: read merge requests with glab instead\n",
+        "    --tracker <TRACKER>     linear (default) or jira: find Jira issue keys like PROJ-123 instead\n",
+        "    --output-format <FMT>   text (ticket IDs), ndjson (ticket records) or json (array of them)\n",
+        "    --dry-run               Fetch every PR, but only log how many PRs and tickets were found;\n",
+        "                            fails at the end if any PR couldn't be fetched\n",
+        "    --require-merged        Skip tickets of PRs that were never merged (default)\n",
+        "    --allow-unmerged        Extract tickets from unmerged PRs too, logging each one\n",
+        "    --merged-after <TIME>   Skip PRs merged before TIME (2024-05-01 or 2024-05-01T12:30:00Z)\n",
+        "    --merged-before <TIME>  Skip PRs merged after TIME, e.g. when the release tag was cut\n",
+        "    --mapping-file <FILE>   Write the tickets found in each PR, and where, to FILE as JSON\n",
+        "    --max-comments <N>      Scan at most the first N comments of each PR (default: all; 0: none)\n",
+        "    --batch-prs             Read all input first, then fetch the PRs 25 per GitHub GraphQL query\n",
+        "    --pr-label <LABEL>      Skip PRs without this label (repeatable: any of them)\n",
+        "    --path-filter <GLOB>    Skip PRs changing no file matching GLOB, e.g. 'services/api/**'\n",
+        "                            (repeatable: any of them)\n",
+        "    --concurrency <N>       Fetch up to N PRs at a time, still output in input order (default: 1)\n",
+        "    --prefix-map <OLD=NEW>  Output OLD-123 references as NEW-123 (repeatable)\n",
+        "    --exclude-pattern <RE>  Drop tickets whose whole ID matches the extended regex RE, e.g. 'TMP-.*'\n",
+        "    --ticket-pattern <RE>   Extended regex of ticket IDs, e.g. '[A-Z]{{2,4}}-[0-9]+' (default: ABC-123)\n",
+        "    --warn-unlinked-prs     Log every PR without a Linear ticket and list them at the end\n",
+        "    --no-stack-detection    Also search text of sibling PRs (stacked or merge-queue commits)\n",
+        "    --linear-org <ORG>      Linear organization of the workspace (default: LINEAR_ORG), for\n",
+        "                            --other-org-urls\n",
+        "    --other-org-urls <MODE> Tickets referenced only by URLs of another workspace: drop (default,\n",
+        "                            logging each) or tag (ndjson/json records carry their \"org\")\n",
+        "    --ignore-token <RE>     Never take tokens matching RE in full for tickets (repeatable), besides\n",
+        "                            the built-in UTF-8, SHA-256, RFC-1234, ... list\n",
+        "    --no-default-ignores    Don't apply the built-in list of non-ticket tokens\n",
+        "    --trailer-keys <KEYS>   Comma-separated commit trailer keys linking tickets, e.g. 'Linear,Ticket'\n",
+        "                            (default: Linear), found along with fixes/closes/refs ABC-123\n",
+        "    --ignore-tickets <LIST> Drop these tickets, e.g. docs tickets: a file with one per line, or\n",
+        "                            comma-separated (ABC-1,ABC-2)\n",
+        "    --ignore-prs <LIST>     Skip these PRs without fetching them, e.g. reverts: a file with one\n",
+        "                            per line, or comma-separated (123,owner/repo#45)\n",
+        "    --stdin-timeout <SECS>  Give up if a terminal stdin sends nothing for SECS (default: 30, 0: never)\n",
+        "    --cache-dir <DIR>       Cache PR responses in DIR, shared with concurrent pipelines\n",
+        "    --rate-limit <N[/s]>    At most N GitHub requests per second (across all processes, with --cache-dir)\n",
+        "    --api-budget <N>        At most N GitHub requests (shared via --cache-dir), then stop and exit 3\n",
+        "    --explain <ID>          Log where ticket ABC-123 was found, or what PR 123 references, and\n",
+        "                            why it was skipped or dropped, if it was\n",
+        "    --help, -h              Print this help message"
+    ));
+}
+
+fn print_extract_commits_help() {
+    println!(concat!(
+        "release-linear-ticket-update extract-commits\n",
+        "\n",
+        "Finds Linear ticket IDs in the commit messages of a release, for repositories that merge without PRs.\n",
+        "\n",
+        "USAGE:\n",
+        "    release-linear-ticket-update extract-commits --release-tag <TAG> [--previous-tag <TAG>]\n",
+        "    release-linear-ticket-update extract-commits --release-tag <TAG> | release-linear-ticket-update update-tickets\n",
+        "\n",
+        "OPTIONS:\n",
+        "    --release-tag <TAG>     Release whose commits are searched (required)\n",
+        "    --previous-tag <TAG>    Search the commits after TAG (default: the tag of the release created\n",
+        "                            before; with --commit-source git, the nearest tag before the release)\n",
+        "    --commit-source <SRC>   github (default): the commits GitHub compares the tags with, through gh\n",
+        "                            or --github-api; git: git log PREVIOUS..TAG in the current clone\n",
+        "    --github-host <HOST>    GitHub host used by gh (sets GH_HOST)\n",
+        "    --github-api            List releases and commits with GITHUB_TOKEN instead of gh\n",
+        "    --github-repo <REPO>    OWNER/REPO with --github-api (default: GITHUB_REPOSITORY)\n",
+        "    --tracker <TRACKER>     linear (default) or jira: find Jira issue keys like PROJ-123 instead\n",
+        "    --output-format <FMT>   text (ticket IDs), ndjson (ticket records naming the commit) or json\n",
+        "                            (array of them)\n",
+        "    --dry-run               List and search the commits, but only log how many tickets were found\n",
+        "    --prefix-map <OLD=NEW>  Output OLD-123 references as NEW-123 (repeatable)\n",
+        "    --exclude-pattern <RE>  Drop tickets whose whole ID matches the extended regex RE, e.g. 'TMP-.*'\n",
+        "    --ticket-pattern <RE>   Extended regex of ticket IDs, e.g. '[A-Z]{{2,4}}-[0-9]+' (default: ABC-123)\n",
+        "    --linear-org <ORG>      Linear organization of the workspace (default: LINEAR_ORG), for\n",
+        "                            --other-org-urls\n",
+        "    --other-org-urls <MODE> Tickets referenced only by URLs of another workspace: drop (default,\n",
+        "                            logging each) or tag (ndjson/json records carry their \"org\")\n",
+        "    --ignore-token <RE>     Never take tokens matching RE in full for tickets (repeatable), besides\n",
+        "                            the built-in UTF-8, SHA-256, RFC-1234, ... list\n",
+        "    --no-default-ignores    Don't apply the built-in list of non-ticket tokens\n",
+        "    --trailer-keys <KEYS>   Comma-separated commit trailer keys linking tickets, e.g. 'Linear,Ticket'\n",
+        "                            (default: Linear), found along with fixes/closes/refs ABC-123\n",
+        "    --ignore-tickets <LIST> Drop these tickets, e.g. docs tickets: a file with one per line, or\n",
+        "                            comma-separated (ABC-1,ABC-2)\n",
+        "    --help, -h              Print this help message"
     ));
 }
 
@@ -500,7 +5508,7 @@ fn print_update_tickets_help() {
         "    release-linear-ticket-update update-tickets [OPTIONS] [FILES...]\n",
         "\n",
         "ARGS:\n",
-        "    [FILES...]    Files containing ticket IDs (one per line). Reads from stdin if no files provided or if '-' is used.\n",
+        "    [FILES...]    Files containing ticket IDs or ndjson ticket records (one per line). Reads from stdin if no files provided or if '-' is used.\n",
         "\n",
         "OPTIONS:\n",
         "    --linear-api-key <KEY>\n",
@@ -509,12 +5517,767 @@ fn print_update_tickets_help() {
         "    --linear-org <ORG>\n",
         "            Linear organization identifier\n",
         "\n",
+        "    --linear-actor-token <TOKEN>\n",
+        "            OAuth token of a Linear app installed with actor=application (or LINEAR_ACTOR_TOKEN):\n",
+        "            make changes as the app, e.g. \"Release Bot\", instead of the API key's user\n",
+        "\n",
         "    --dry-run\n",
-        "            Preview changes without updating\n",
+        "            Preview changes without updating, then log the requests the real run would send\n",
+        "\n",
+        "    --url-format <FMT>\n",
+        "            How processed tickets are printed: id (ABC-123), url (default) or markdown\n",
+        "            ([ABC-123](https://linear.app/...))\n",
+        "\n",
+        "    --output-format <FMT>\n",
+        "            text (tickets as per --url-format), ndjson (a result record per ticket: ticket, url,\n",
+        "            outcome, from/to state, source PR, error) or json (array of them, printed at the end)\n",
+        "\n",
+        "    --explain <ID>\n",
+        "            Log why ticket ABC-123 (or every ticket found in PR 123) was or wasn't updated: the\n",
+        "            checks it passed or failed and the state it was found in\n",
+        "\n",
+        "    --github-output\n",
+        "            Write step outputs (updated, already_completed, skipped, failed, urls) to $GITHUB_OUTPUT\n",
+        "            and a Markdown summary to $GITHUB_STEP_SUMMARY at the end of the run\n",
+        "\n",
+        "    --diff\n",
+        "            With --dry-run, print a table of every ticket at the end instead of the URLs of those\n",
+        "            that would be updated: its team, current state (and the state it would be moved\n",
+        "            to), and what would happen to it, e.g. why it would be skipped. Text output only\n"
+    ));
+    print_update_tickets_input_help();
+    print_update_tickets_selection_help();
+    println!(concat!(
+        "    --manifest <FILE>\n",
+        "            With --dry-run, write a hash-stamped manifest of intended changes to FILE\n",
+        "\n",
+        "    --approve <HASH>\n",
+        "            Plan all tickets first and apply only if the plan matches the approved manifest hash\n",
+        "\n",
+        "    --compare-manifest <FILE>\n",
+        "            After the run, report tickets whose state changed since the dry run that wrote FILE\n",
+        "            (e.g. closed by hand in between), planned tickets missing from the input, and\n",
+        "            tickets updated without being in the dry run\n"
+    ));
+    print_update_tickets_processing_help();
+    print_update_tickets_release_help();
+    print_update_tickets_pacing_help();
+    print_update_tickets_tracker_help();
+    println!(concat!(
+        "    --locale <LOCALE>\n",
+        "            Language of the end-of-run summary, loaded from DIR/LOCALE.txt (default: built-in English)\n",
+        "\n",
+        "    --locale-dir <DIR>\n",
+        "            Directory of locale template files (default: locales)\n",
+        "\n",
+        "    --linear-api-url <URL>\n",
+        "            Linear GraphQL endpoint (can also be set via LINEAR_API_URL env var)\n",
+        "            Requests are sent in-process; over HTTPS, curl is used instead in builds without\n",
+        "            the native-http feature\n",
+        "\n",
+        "    --linear-header <'NAME: VALUE'>\n",
+        "            Extra HTTP header for Linear API requests (repeatable)\n",
+        "\n",
+        "    --linear-resolve <HOST:PORT:ADDRESS>\n",
+        "            Resolve HOST:PORT to ADDRESS for Linear API requests without DNS (repeatable)\n",
+        "\n",
+        "    --linear-snapshot <FILE>\n",
+        "            With --dry-run, read tickets from a JSON workspace snapshot instead of the Linear API\n",
+        "\n",
+        "    --debug-dump <FILE>\n",
+        "            Append API responses that aren't valid JSON to FILE, with the request they answered\n",
+        "\n",
+        "    --cache-dir <DIR>\n",
+        "            Cache each team's completed workflow state in DIR, shared with concurrent pipelines\n",
+        "\n",
+        "    --rate-limit <N[/s]>\n",
+        "            At most N Linear requests per second (across all processes, with --cache-dir)\n",
         "\n",
+        "    --api-budget <N>\n",
+        "            Send at most N Linear requests (shared via --cache-dir); once used up, the remaining\n",
+        "            tickets fail without a request and the run exits with status 3 (partial result)\n",
+        "\n",
+        "    --help, -h    Print this help message"
+    ));
+}
+
+/// Processing order part of [`print_update_tickets_help`].
+fn print_update_tickets_processing_help() {
+    println!(concat!(
+        "    --quiet, -v, -vv\n",
+        "            Log only failures and warnings; also log each ticket's current state and lookups;\n",
+        "            also log every GraphQL request and response (never the credentials)\n",
+        "\n",
+        "    --log-format <FMT>\n",
+        "            text (default) or json: one JSON object per log line, with timestamp, level, stage,\n",
+        "            ticket and message\n",
+        "\n",
+        "    --queue-file <FILE>\n",
+        "            Read all input first and record the tickets still to process in FILE, removing each\n",
+        "            once processed (failed tickets stay). If FILE exists, resume from it and ignore input\n",
+        "\n",
+        "    --checkpoint <FILE>\n",
+        "            Append every ticket processed successfully to FILE. A re-run with the same FILE and\n",
+        "            input skips those tickets without a lookup; FILE is deleted once a run has no failures.\n",
+        "            Not with --dry-run or --queue-file\n",
+        "\n",
+        "    --journal <FILE>\n",
+        "            Append every attempted state change to FILE as a JSON line (time, ticket, old\n",
+        "            and new state, result, request ID), for audits and the rollback mode (not with\n",
+        "            --dry-run)\n",
+        "\n",
+        "    --fail-on-partial\n",
+        "            Exit with status 4 if some tickets failed to update, after processing the others\n",
+        "            (default: log them and exit 0)\n",
+        "\n",
+        "    --concurrency <N>\n",
+        "            Look up and update up to N tickets at a time (default: 1). Results and their logs are\n",
+        "            still reported in input order\n",
+        "\n",
+        "    --batch-lookups\n",
+        "            Read all input first, then look up the tickets 25 per query and their teams' workflow\n",
+        "            states in one query, instead of per ticket. Tickets a batch can't resolve are looked\n",
+        "            up on their own\n",
+        "\n",
+        "    --order-by-dependencies\n",
+        "            Update blockers before the tickets they block; report tickets completed with open blockers\n",
+        "\n",
+        "    --note-blocked\n",
+        "            Like --order-by-dependencies, and comment on blocked tickets when their blocker is completed\n"
+    ));
+}
+
+/// Input part of [`print_update_tickets_help`].
+fn print_update_tickets_input_help() {
+    println!(concat!(
+        "    --prefix-map <OLD=NEW>\n",
+        "            Update OLD-123 input tickets as NEW-123, e.g. after a team key rename (repeatable)\n",
+        "\n",
+        "    --exclude-pattern <REGEX>\n",
+        "            Skip input tickets whose whole ID matches the extended regex REGEX, e.g.\n",
+        "            'TMP-.*|SAND-.*' for sandbox teams (checked after --prefix-map)\n",
+        "\n",
+        "    --ticket-pattern <REGEX>\n",
+        "            Extended regex input ticket IDs must match in full, e.g. '[A-Z]{{2,4}}-[0-9]+' for 2- to\n",
+        "            4-letter team keys (default: 3 uppercase letters, a hyphen and a number)\n",
+        "\n",
+        "    --stdin-timeout <SECS>\n",
+        "            Give up if stdin is a terminal and no input arrives within SECS (default: 30; 0 waits\n",
+        "            forever). Piped input is never timed out\n"
+    ));
+}
+
+/// Ticket selection part of [`print_update_tickets_help`].
+fn print_update_tickets_selection_help() {
+    println!(concat!(
         "    --update-all-statuses\n",
         "            Update regardless of current Linear state\n",
         "\n",
-        "    --help, -h    Print this help message"
+        "    --from-states <STATES>\n",
+        "            Update tickets in these comma-separated states (e.g. 'QA Done,Staged') instead of\n",
+        "            \"Passing\" (repeatable; --update-all-statuses still accepts any state)\n",
+        "\n",
+        "    --filter EXPR\n",
+        "            Only update tickets matching EXPR. Fields: id, team, team_name, state,\n",
+        "            state_type, title, assignee, creator, labels. Operators: == != && || ! ( ).\n",
+        "            Methods: .contains(s), .starts_with(s), .ends_with(s)\n",
+        "\n",
+        "    --assignee <me|EMAIL>\n",
+        "            Only update tickets assigned to this user; me is the user of the API key\n",
+        "\n",
+        "    --creator <me|EMAIL>\n",
+        "            Only update tickets created by this user; with --assignee, tickets must match both\n",
+        "\n",
+        "    --drop-unknown-teams\n",
+        "            Skip input tickets whose team key isn't a team of the workspace, e.g. SHA-256 or\n",
+        "            ISO-8601 matched by the ticket pattern (default: warn about them and look them up)\n",
+        "\n",
+        "    --follow-duplicates <MODE>\n",
+        "            For input tickets marked as a duplicate of another issue, update that\n",
+        "            issue instead (canonical) or as well (both), so the ticket the work is\n",
+        "            tracked in gets closed. Costs one relations query per input ticket\n"
+    ));
+}
+
+/// Release tracking part of [`print_update_tickets_help`].
+fn print_update_tickets_release_help() {
+    println!(concat!(
+        "    --release-tag <TAG>\n",
+        "            Release the tickets belong to (required by --comment-release, --comment-template,\n",
+        "            --release-issue-team, --state-route and --history-file)\n",
+        "\n",
+        "    --comment-release\n",
+        "            Comment the release on each completed ticket, and skip tickets that\n",
+        "            already carry its marker (so re-runs for the same tag are no-ops)\n",
+        "\n",
+        "    --comment-template <FILE>\n",
+        "            Like --comment-release, but the comment is the Markdown FILE with {{tag}},\n",
+        "            {{release_url}} and {{date}} (looked up with gh, or the API with --github-api),\n",
+        "            {{prs}} (a Markdown list of the ticket's PR links, from ndjson input records),\n",
+        "            {{pr_list}} (the same links comma-separated) and {{ticket}} replaced\n",
+        "\n",
+        "    --quiet-updates\n",
+        "            Plan every ticket first, then move them in batches of up to 50 with\n",
+        "            issueBatchUpdate, and don't subscribe to tickets that get comments.\n",
+        "            Linear has no API switch to mute subscriber notifications.\n",
+        "\n",
+        "    --release-issue-team <KEY>\n",
+        "            Create a \"Release TAG\" issue in team KEY with a checklist of the\n",
+        "            completed tickets (skipped if it already exists)\n",
+        "\n",
+        "    --linear-project <PROJECT>\n",
+        "            Linear project (name or slug ID) representing the release: only\n",
+        "            complete tickets that belong to it and are in a started state, and\n",
+        "            report its completion percentage before and after the run\n",
+        "\n",
+        "    --add-to-project <PROJECT>\n",
+        "            Add every ticket moved by the run to the Linear project (name or\n",
+        "            slug ID), so the release's tickets are grouped in Linear\n",
+        "\n",
+        "    --add-label <LABEL>\n",
+        "            Apply the workspace label LABEL (e.g. released:v1.2.3) to every\n",
+        "            ticket moved by the run, creating the label if it doesn't exist\n",
+        "\n",
+        "    --set-custom-field <NAME=VALUE>\n",
+        "            Set a \"field\" of the moved tickets: the workspace label group NAME\n",
+        "            (e.g. Release) gets its label VALUE (created if missing), replacing\n",
+        "            the group's other labels, in the same mutation that moves the ticket\n",
+        "\n",
+        "    --linear-cycle <current|next|NUMBER>\n",
+        "            Add every ticket moved by the run to its team's active cycle, the\n",
+        "            next one, or the cycle with that number (teams without it are logged)\n",
+        "\n",
+        "    --attach-release\n",
+        "            With --release-tag, attach the GitHub release to every ticket moved\n",
+        "            by the run, and skip tickets it is already attached to, so re-runs\n",
+        "            only process tickets an earlier run didn't reach\n",
+        "\n",
+        "    --webhook-url <URL>\n",
+        "            POST a ticket.released JSON event (ticket, URL, states, release tag,\n",
+        "            PR) to URL for every ticket moved by the run; failures are logged\n",
+        "\n",
+        "    --webhook-secret <SECRET>\n",
+        "            Sign the events with HMAC-SHA256 of SECRET, sent as\n",
+        "            X-Release-Signature-256: sha256=HEX (default: WEBHOOK_SECRET)\n",
+        "\n",
+        "    --slack-webhook <URL>\n",
+        "            Post the run's counts and its updated and failed tickets (with links\n",
+        "            and errors) to a Slack incoming webhook at the end of the run\n",
+        "            (default: SLACK_WEBHOOK_URL); failures are logged\n",
+        "\n",
+        "    --state-route <PATTERN=STATE>\n",
+        "            For a release tag matching PATTERN (* matches anything), move tickets\n",
+        "            to the workflow state named STATE instead of completing them, e.g.\n",
+        "            '*-rc*=In Staging'. Tickets in a route's state are also eligible, so\n",
+        "            the final release completes them. Repeatable; the first match wins\n",
+        "\n",
+        "    --target-state <NAME>\n",
+        "            Move tickets to the workflow state named NAME (case-insensitive), e.g.\n",
+        "            \"Released\", instead of the Completed/Done state. A matching\n",
+        "            --state-route takes precedence. Every team of the input must have it\n",
+        "\n",
+        "    --target-state-type <TYPE>\n",
+        "            Only a state of TYPE (triage, backlog, unstarted, started, completed,\n",
+        "            canceled) is a target; without --target-state, the team's first state\n",
+        "            of TYPE is used\n",
+        "\n",
+        "    --history-file <FILE>\n",
+        "            Append every processed ticket (with the release and outcome) to FILE, and\n",
+        "            flag tickets that an earlier release's run already completed\n",
+        "\n",
+        "    --repeat-release <MODE>\n",
+        "            warn (default): only report such tickets; fail: also exit with an error\n",
+        "\n",
+        "    --quarantine-after <N>\n",
+        "            Quarantine tickets whose last N runs in the history file all failed: they\n",
+        "            are skipped, listed at the end of every run and not retried until cleared\n",
+        "\n",
+        "    --clear-quarantine <ID>\n",
+        "            Retry quarantined ticket ID, starting a new count of failed runs\n",
+        "            (repeatable)\n"
+    ));
+}
+
+/// Comment pacing part of [`print_update_tickets_help`].
+fn print_update_tickets_pacing_help() {
+    println!(concat!(
+        "    --comment-interval <MS>\n",
+        "            Minimum delay between Linear comments (default: 1000)\n",
+        "\n",
+        "    --comment-batch-size <N>\n",
+        "            Pause after every N comments (default: 25)\n",
+        "\n",
+        "    --comment-batch-pause <SECS>\n",
+        "            Length of the pause between comment batches (default: 30)\n",
+        "\n",
+        "    --comment-progress <FILE>\n",
+        "            Record posted comments in FILE and skip them when re-run\n"
+    ));
+}
+
+/// Jira part of [`print_update_tickets_help`].
+fn print_update_tickets_tracker_help() {
+    println!(concat!(
+        "    --tracker <TRACKER>\n",
+        "            linear (default) or jira: transition Jira issues to a done status instead. Jira\n",
+        "            supports --dry-run, --update-all-statuses, --from-states, --prefix-map,\n",
+        "            --url-format, --output-format, --github-output, --locale and --stdin-timeout\n",
+        "\n",
+        "    --jira-base-url <URL>, --jira-api-token <TOKEN>, --jira-email <EMAIL>\n",
+        "            Jira site URL and API token (JIRA_BASE_URL, JIRA_API_TOKEN); with an account email\n",
+        "            (JIRA_EMAIL), the token is sent with Basic authentication as Jira Cloud expects\n",
+        "\n",
+        "    --org-map <TEAM=ORG:KEY>\n",
+        "            Look up and update TEAM's tickets in workspace ORG, with API key KEY (env:NAME reads\n",
+        "            environment variable NAME); comma-separated or repeatable. Not with --batch-lookups,\n",
+        "            --linear-project, --add-to-project, --add-label, --set-custom-field,\n",
+        "            --attach-release, --assignee, --creator, --linear-actor-token, --linear-snapshot,\n",
+        "            --journal or the manifest flags\n",
+    ));
+}
+
+fn print_rollback_help() {
+    println!(concat!(
+        "release-linear-ticket-update rollback\n",
+        "\n",
+        "Moves the tickets an update-tickets run recorded with --journal back to the states\n",
+        "they were moved out of, last change first. Tickets whose state changed since are\n",
+        "left alone, so a rollback can be run again.\n",
+        "\n",
+        "USAGE:\n",
+        "    release-linear-ticket-update rollback --journal <FILE> [OPTIONS]\n",
+        "\n",
+        "OPTIONS:\n",
+        "    --journal <FILE>\n",
+        "            Journal written by update-tickets --journal (required)\n",
+        "\n",
+        "    --linear-api-key <KEY>, --linear-org <ORG>\n",
+        "            Linear API authentication key, and organization for ticket URLs\n",
+        "\n",
+        "    --dry-run\n",
+        "            Look the tickets up and print those that would be moved back, without moving them\n",
+        "\n",
+        "    --linear-api-url <URL>, --linear-header <'NAME: VALUE'>, --linear-resolve <HOST:PORT:ADDRESS>\n",
+        "            Linear endpoint overrides, as for update-tickets\n",
+        "\n",
+        "    --linear-snapshot <FILE>\n",
+        "            With --dry-run, read the tickets from a JSON workspace snapshot instead of the Linear API\n",
+        "\n",
+        "    --debug-dump <FILE>\n",
+        "            Append Linear API responses that aren't valid JSON to FILE\n",
+        "\n",
+        "    --help, -h\n",
+        "            Print this help message"
+    ));
+}
+
+fn print_selftest_help() {
+    println!(concat!(
+        "release-linear-ticket-update selftest\n",
+        "\n",
+        "Checks credentials and permissions by running every step of update-tickets on a\n",
+        "throwaway issue of a sandbox team: creates the issue, looks it up, looks up the team's\n",
+        "workflow, moves the issue to the completed (or --target-state) state, comments on it,\n",
+        "checks the result and deletes the issue. Fails naming the first step that failed; the\n",
+        "issue is deleted even then.\n",
+        "\n",
+        "USAGE:\n",
+        "    release-linear-ticket-update selftest --team <KEY> [OPTIONS]\n",
+        "\n",
+        "OPTIONS:\n",
+        "    --team <KEY>\n",
+        "            Key of the sandbox team to create the issue in (required)\n",
+        "\n",
+        "    --linear-api-key <KEY>, --linear-actor-token <TOKEN>\n",
+        "            Linear API authentication key, and the token mutations are made with, as for\n",
+        "            update-tickets\n",
+        "\n",
+        "    --target-state <NAME>, --target-state-type <TYPE>\n",
+        "            The state to move the issue to, as for update-tickets (default: Done/Completed)\n",
+        "\n",
+        "    --dry-run\n",
+        "            Only look up the team and its workflow, without creating an issue\n",
+        "\n",
+        "    --linear-api-url <URL>, --linear-header <'NAME: VALUE'>, --linear-resolve <HOST:PORT:ADDRESS>\n",
+        "            Linear endpoint overrides, as for update-tickets\n",
+        "\n",
+        "    --debug-dump <FILE>\n",
+        "            Append Linear API responses that aren't valid JSON to FILE\n",
+        "\n",
+        "    --help, -h\n",
+        "            Print this help message"
+    ));
+}
+
+fn print_run_help() {
+    println!(concat!(
+        "release-linear-ticket-update run\n",
+        "\n",
+        "Runs a pipeline defined in the config file: an ordered list of stages, each a mode\n",
+        "with its own flags. A stage reading records (extract-tickets after parse-notes,\n",
+        "update-tickets after extract-tickets) gets the previous stage's ndjson output; any\n",
+        "other stage runs once the stages before it have finished.\n",
+        "\n",
+        "    [[pipeline.staging]]\n",
+        "    stage = \"parse-notes\"\n",
+        "\n",
+        "    [[pipeline.staging]]\n",
+        "    stage = \"extract-tickets\"\n",
+        "\n",
+        "    [[pipeline.staging]]\n",
+        "    stage = \"update-tickets\"\n",
+        "    target-state = \"In Staging\"\n",
+        "\n",
+        "USAGE:\n",
+        "    release-linear-ticket-update run <NAME> [OPTIONS]\n",
+        "\n",
+        "OPTIONS:\n",
+        "    --config <FILE>\n",
+        "            Config file defining the pipeline (default: .release-linear.toml at the\n",
+        "            repository root)\n",
+        "\n",
+        "    --release-tag <TAG>\n",
+        "            Passed on to the parse-notes, update-tickets, report and verify stages, unless\n",
+        "            they set their own\n",
+        "\n",
+        "    --dry-run\n",
+        "            Passed on to the update-tickets and rollback stages\n",
+        "\n",
+        "    --help, -h\n",
+        "            Print this help message"
+    ));
+}
+
+fn print_serve_help() {
+    println!(concat!(
+        "release-linear-ticket-update serve\n",
+        "\n",
+        "Listens for GitHub release webhooks and runs the orchestrator for the tag of every\n",
+        "release published, one release at a time. Deliveries must be signed with the webhook's\n",
+        "secret (X-Hub-Signature-256); other events and release actions are ignored.\n",
+        "\n",
+        "USAGE:\n",
+        "    release-linear-ticket-update serve [OPTIONS] [ORCHESTRATOR OPTIONS]\n",
+        "\n",
+        "OPTIONS:\n",
+        "    --listen <ADDR>\n",
+        "            Address to listen on (default: 0.0.0.0:8080). Plain HTTP: put a TLS-terminating\n",
+        "            proxy in front of it\n",
+        "\n",
+        "    --github-webhook-secret <SECRET>\n",
+        "            Secret of the GitHub webhook (can also be set via GITHUB_WEBHOOK_SECRET env var;\n",
+        "            required)\n",
+        "\n",
+        "    --help, -h\n",
+        "            Print this help message\n",
+        "\n",
+        "Every other option is passed on to the orchestrator (see --help), except --release-tag,\n",
+        "--since-tag and --until-tag. With --github-repo, releases of other repositories are\n",
+        "ignored."
+    ));
+}
+
+fn print_teams_help() {
+    println!(concat!(
+        "release-linear-ticket-update teams\n",
+        "\n",
+        "Lists Linear teams with their workflow states, marking the state update-tickets\n",
+        "would complete tickets to and the states it would move tickets out of.\n",
+        "\n",
+        "USAGE:\n",
+        "    release-linear-ticket-update teams [OPTIONS]\n",
+        "\n",
+        "OPTIONS:\n",
+        "    --linear-api-key <KEY>\n",
+        "            Linear API authentication key\n",
+        "\n",
+        "    --update-all-statuses\n",
+        "            Mark every open state as eligible, as update-tickets would\n",
+        "\n",
+        "    --from-states <STATES>\n",
+        "            Mark these comma-separated states as eligible instead of \"Passing\"\n",
+        "\n",
+        "    --target-state <NAME>, --target-state-type <TYPE>\n",
+        "            Mark the state update-tickets would move tickets to with these flags, and flag\n",
+        "            teams that lack it\n",
+        "\n",
+        "    --linear-api-url <URL>, --linear-header <'NAME: VALUE'>, --linear-resolve <HOST:PORT:ADDRESS>\n",
+        "            Linear endpoint overrides, as for update-tickets\n",
+        "\n",
+        "    --linear-snapshot <FILE>\n",
+        "            Read teams and workflow states from a JSON workspace snapshot instead of the Linear API\n",
+        "\n",
+        "    --debug-dump <FILE>\n",
+        "            Append Linear API responses that aren't valid JSON to FILE\n",
+        "\n",
+        "    --help, -h\n",
+        "            Print this help message"
+    ));
+}
+
+fn print_report_help() {
+    println!(concat!(
+        "release-linear-ticket-update report\n",
+        "\n",
+        "Reports a release's PRs, the Linear tickets they reference and the tickets' current\n",
+        "workflow states, without changing anything. Runs parse-notes and extract-tickets, then\n",
+        "looks every ticket up in Linear.\n",
+        "\n",
+        "USAGE:\n",
+        "    release-linear-ticket-update report --release-tag <TAG> [OPTIONS]\n",
+        "\n",
+        "OPTIONS:\n",
+        "    --release-tag <TAG>\n",
+        "            GitHub release tag to report on (required)\n",
+        "\n",
+        "    --linear-api-key <KEY>, --linear-org <ORG>\n",
+        "            Linear API authentication key, and organization for ticket URLs (optional)\n",
+        "\n",
+        "    --output-format <FMT>\n",
+        "            text (a Markdown table, the default) or json (one report object)\n",
+        "\n",
+        "    --mapping-file <FILE>\n",
+        "            Also keep the per-PR ticket mapping extract-tickets writes in FILE\n",
+        "\n",
+        "    --forge, --github-host, --github-api, --github-repo, --allow-unmerged, --merged-after,\n",
+        "    --merged-before, --max-comments, --batch-prs, --prefix-map, --exclude-pattern,\n",
+        "    --ticket-pattern, --warn-unlinked-prs, --no-stack-detection, --ignore-token,\n",
+        "    --no-default-ignores, --trailer-keys, --ignore-tickets, --ignore-prs, --pr-label,\n",
+        "    --path-filter\n",
+        "            Passed to extract-tickets, as by the orchestrator\n",
+        "\n",
+        "    --discover <MODE>\n",
+        "            Passed to parse-notes, as by the orchestrator\n",
+        "\n",
+        "    --batch-lookups\n",
+        "            Look up the tickets 25 per Linear query\n",
+        "\n",
+        "    --linear-api-url <URL>, --linear-header <'NAME: VALUE'>, --linear-resolve <HOST:PORT:ADDRESS>\n",
+        "            Linear endpoint overrides, as for update-tickets\n",
+        "\n",
+        "    --linear-snapshot <FILE>\n",
+        "            Read tickets from a JSON workspace snapshot instead of the Linear API\n",
+        "\n",
+        "    --debug-dump <FILE>\n",
+        "            Append Linear API responses that aren't valid JSON to FILE\n",
+        "\n",
+        "    --cache-dir <DIR>, --rate-limit <N>, --api-budget <N>\n",
+        "            As for extract-tickets; the budget covers the Linear lookups too (shared via\n",
+        "            --cache-dir)\n",
+        "\n",
+        "    --help, -h\n",
+        "            Print this help message"
+    ));
+}
+
+fn print_verify_help() {
+    println!(concat!(
+        "release-linear-ticket-update verify\n",
+        "\n",
+        "Checks that every Linear ticket of a release is completed, without changing anything. Runs\n",
+        "parse-notes and extract-tickets, then looks every ticket up in Linear. Tickets that aren't in\n",
+        "a Done/Completed state (or the --target-state), or couldn't be looked up, are printed and the\n",
+        "run exits with status 1; otherwise nothing is printed.\n",
+        "\n",
+        "USAGE:\n",
+        "    release-linear-ticket-update verify --release-tag <TAG> [OPTIONS]\n",
+        "\n",
+        "OPTIONS:\n",
+        "    --release-tag <TAG>\n",
+        "            GitHub release tag to verify (required)\n",
+        "\n",
+        "    --linear-api-key <KEY>, --linear-org <ORG>\n",
+        "            Linear API authentication key and organization identifier\n",
+        "\n",
+        "    --target-state <NAME>, --target-state-type <TYPE>\n",
+        "            The state tickets must be in, as for update-tickets (default: Done/Completed)\n",
+        "\n",
+        "    --url-format <FMT>\n",
+        "            Print tickets as id, url (default) or markdown\n",
+        "\n",
+        "    --mapping-file <FILE>\n",
+        "            Also keep the per-PR ticket mapping extract-tickets writes in FILE\n",
+        "\n",
+        "    --forge, --github-host, --github-api, --github-repo, --allow-unmerged, --merged-after,\n",
+        "    --merged-before, --max-comments, --batch-prs, --prefix-map, --exclude-pattern,\n",
+        "    --ticket-pattern, --warn-unlinked-prs, --no-stack-detection, --ignore-token,\n",
+        "    --no-default-ignores, --trailer-keys, --ignore-tickets, --ignore-prs, --pr-label,\n",
+        "    --path-filter\n",
+        "            Passed to extract-tickets, as by the orchestrator\n",
+        "\n",
+        "    --discover <MODE>\n",
+        "            Passed to parse-notes, as by the orchestrator\n",
+        "\n",
+        "    --batch-lookups\n",
+        "            Look up the tickets 25 per Linear query\n",
+        "\n",
+        "    --linear-api-url <URL>, --linear-header <'NAME: VALUE'>, --linear-resolve <HOST:PORT:ADDRESS>\n",
+        "            Linear endpoint overrides, as for update-tickets\n",
+        "\n",
+        "    --linear-snapshot <FILE>\n",
+        "            Read tickets from a JSON workspace snapshot instead of the Linear API\n",
+        "\n",
+        "    --debug-dump <FILE>\n",
+        "            Append Linear API responses that aren't valid JSON to FILE\n",
+        "\n",
+        "    --cache-dir <DIR>, --rate-limit <N>, --api-budget <N>\n",
+        "            As for extract-tickets; the budget covers the Linear lookups too (shared via\n",
+        "            --cache-dir)\n",
+        "\n",
+        "    --help, -h\n",
+        "            Print this help message"
+    ));
+}
+
+fn print_schema_help() {
+    println!(concat!(
+        "release-linear-ticket-update schema\n",
+        "\n",
+        "Prints the JSON Schema (draft 2020-12) of the machine outputs: ndjson PR and ticket\n",
+        "records, the --mapping-file, the --manifest file and the json report. Each of them\n",
+        "carries the schema_version it conforms to.\n",
+        "\n",
+        "USAGE:\n",
+        "    release-linear-ticket-update schema\n",
+        "\n",
+        "OPTIONS:\n",
+        "    --help, -h\n",
+        "            Print this help message"
     ));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Config, Error> {
+        let args: Vec<String> = args.iter().map(ToString::to_string).collect();
+        Config::from_arg_list(&args)
+    }
+
+    #[test]
+    fn approve_rejects_changes_the_manifest_does_not_cover() -> Result<(), Error>
+    {
+        let approve =
+            ["update-tickets", "--release-tag", "v1.0.0", "--approve"];
+        let hash = "00".repeat(32);
+        parse(&[&approve[..], &[hash.as_str()]].concat())?;
+        for extra in [
+            &["--comment-release"][..],
+            &["--comment-template", "Released in {tag}"],
+            &["--attach-release"],
+            &["--add-to-project", "Q3"],
+            &["--linear-cycle", "current"],
+            &["--release-issue-team", "ENG"],
+            &["--note-blocked"],
+            &["--webhook-url", "https://example.com/hook"],
+        ] {
+            let args = [&approve[..], &[hash.as_str()], extra].concat();
+            let error = parse(&args).err();
+            assert!(
+                matches!(&error, Some(Error::Config(message)) if message.contains(extra[0])),
+                "{extra:?}: {error:?}"
+            );
+        }
+        Ok(())
+    }
+
+    fn rejects(args: &[&str]) -> bool {
+        matches!(parse(args), Err(Error::Config(_)))
+    }
+
+    #[test]
+    fn parses_jobs_and_rate_limit() -> Result<(), Error> {
+        let config = parse(&["--release-tag", "v1.0.0", "--jobs", "3"])?;
+        assert_eq!(config.jobs, Some(3));
+        for rate in ["10", "10/s"] {
+            let config =
+                parse(&["--release-tag", "v1.0.0", "--rate-limit", rate])?;
+            assert!(config.rate_limiter.is_some(), "{rate}");
+        }
+        assert!(rejects(&[
+            "--release-tag",
+            "v1.0.0",
+            "--rate-limit",
+            "fast"
+        ]));
+        assert!(rejects(&["extract-tickets", "--jobs", "2"]));
+        Ok(())
+    }
+
+    #[test]
+    fn parses_linear_endpoint_overrides() -> Result<(), Error> {
+        let config = parse(&[
+            "update-tickets",
+            "--linear-header",
+            "X-Trace: 1",
+            "--linear-resolve",
+            "api.linear.app:443:10.0.0.1",
+        ])?;
+        assert_eq!(config.linear_headers, ["X-Trace: 1"]);
+        assert_eq!(config.linear_resolve, ["api.linear.app:443:10.0.0.1"]);
+        assert!(rejects(&["update-tickets", "--linear-header", "X-Trace"]));
+        assert!(rejects(&[
+            "update-tickets",
+            "--linear-resolve",
+            "api.linear.app:10.0.0.1"
+        ]));
+        Ok(())
+    }
+
+    #[test]
+    fn parses_org_map() -> Result<(), Error> {
+        let config = parse(&[
+            "update-tickets",
+            "--org-map",
+            "OPS=acme-ops:env:OPS_KEY, SEC2=acme-sec:lin_api_x",
+        ])?;
+        let entries: Vec<_> = config.org_map.entries().collect();
+        assert_eq!(
+            entries,
+            ["OPS=acme-ops:env:OPS_KEY", "SEC2=acme-sec:lin_api_x"]
+        );
+        for invalid in ["OPS=acme-ops", "ops=acme-ops:key", "OPS=:key"] {
+            assert!(
+                rejects(&["update-tickets", "--org-map", invalid]),
+                "{invalid}"
+            );
+        }
+        assert!(rejects(&[
+            "update-tickets",
+            "--org-map",
+            "OPS=a:k",
+            "--org-map",
+            "OPS=b:k"
+        ]));
+        Ok(())
+    }
+
+    #[test]
+    fn parses_github_repo() -> Result<(), Error> {
+        let config = parse(&[
+            "extract-tickets",
+            "--github-api",
+            "--github-repo",
+            "acme/app",
+        ])?;
+        assert_eq!(config.github_repo.as_deref(), Some("acme/app"));
+        for invalid in ["acme", "acme/", "/app", "acme/app/x"] {
+            assert!(
+                rejects(&["extract-tickets", "--github-repo", invalid]),
+                "{invalid}"
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn parses_in_process() -> Result<(), Error> {
+        let config = parse(&["--release-tag", "v1.0.0", "--in-process"])?;
+        assert_eq!(config.stages, Stages::InProcess);
+        let config = parse(&["--release-tag", "v1.0.0"])?;
+        assert_eq!(config.stages, Stages::Spawned);
+        assert!(rejects(&["update-tickets", "--in-process"]));
+        Ok(())
+    }
+}
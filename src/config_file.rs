@@ -0,0 +1,334 @@
+//! `.release-linear.toml`: default flags for orchestrator mode.
+//!
+//! Long CI command lines can move into a config file at the repository root
+//! (or the file given with `--config`). Every key is the name of a flag
+//! without its leading dashes, and sets that flag unless it is given on the
+//! command line as well:
+//!
+//! ```toml
+//! # .release-linear.toml
+//! linear-org = "acme"
+//! target-state = "Released"
+//! concurrency = 4
+//! warn-unlinked-prs = true
+//! prefix-map = ["OLD=NEW", "WEB=FE"]
+//! ```
+//!
+//! - A string or number is the flag's value
+//! - `true` gives a switch such as `--dry-run`; `false` leaves it out
+//! - An array gives a repeatable flag once per element
+//! - `snake_case` keys are accepted too (`linear_org`)
+//!
+//! Only this subset of TOML is supported: one `key = value` per line, bare
+//! keys, `#` comments, and basic (`"..."`, JSON escapes) or literal
+//! (`'...'`) strings. Tables are rejected, since every key applies to the
+//! one run, except for pipeline stages.
+//!
+//! ## Pipelines
+//! Custom release flows are defined as pipelines of stages, run with
+//! `run NAME` (see [`crate::pipeline`]). Each `[[pipeline.NAME]]` table is
+//! the next stage of pipeline `NAME`: its `stage` key names the mode to run,
+//! and its other keys are that stage's flags, as above:
+//!
+//! ```toml
+//! [[pipeline.staging]]
+//! stage = "parse-notes"
+//!
+//! [[pipeline.staging]]
+//! stage = "extract-tickets"
+//! allow-unmerged = true
+//!
+//! [[pipeline.staging]]
+//! stage = "update-tickets"
+//! target-state = "In Staging"
+//!
+//! [[pipeline.staging]]
+//! stage = "report"
+//! ```
+//!
+//! As in TOML, keys after the first table header belong to the tables, so
+//! the orchestrator's flags come first in the file.
+
+use std::collections::HashSet;
+use std::fs;
+use std::process::Command;
+
+use crate::error::Error;
+use crate::json::{self, Value};
+
+/// Name of the config file looked up at the repository root.
+pub const DEFAULT_FILE_NAME: &str = ".release-linear.toml";
+
+/// Flags that can't be set from a config file.
+const COMMAND_LINE_ONLY: &[&str] = &["config", "no-config", "help", "version"];
+
+/// One key of the config file, as command-line flags.
+#[derive(Debug)]
+pub struct Entry {
+    /// The flag, e.g. `--linear-org`
+    pub flag: String,
+    /// The flag and its value(s), e.g. `["--linear-org", "acme"]`; empty for
+    /// a switch set to `false`
+    pub args: Vec<String>,
+}
+
+/// One `[[pipeline.NAME]]` table of the config file.
+#[derive(Debug)]
+pub struct StageEntry {
+    /// Mode the stage runs, e.g. `extract-tickets`
+    pub stage: String,
+    /// The stage's flags, in file order
+    pub args: Vec<String>,
+}
+
+/// The keys of a config file: the orchestrator's flags, and the stages of
+/// each pipeline, in file order.
+#[derive(Debug, Default)]
+struct File {
+    entries: Vec<Entry>,
+    pipelines: Vec<(String, Vec<StageEntry>)>,
+}
+
+/// Path of the default config file: [`DEFAULT_FILE_NAME`] at the root of
+/// the current git repository, or in the current directory outside one.
+pub fn default_path() -> String {
+    let root = Command::new("git")
+        .args(["rev-parse", "--show-toplevel"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|root| root.trim().to_string())
+        .filter(|root| !root.is_empty());
+    match root {
+        Some(root) => format!("{root}/{DEFAULT_FILE_NAME}"),
+        None => DEFAULT_FILE_NAME.to_string(),
+    }
+}
+
+/// Reads the orchestrator's flags from the config file at `path`, in file
+/// order.
+///
+/// # Errors
+/// Returns an error if the file can't be read, isn't in the supported TOML
+/// subset, or sets a key twice or a command-line-only flag.
+pub fn read(path: &str) -> Result<Vec<Entry>, Error> {
+    Ok(read_file(path)?.entries)
+}
+
+/// Reads the stages of pipeline `name` from the config file at `path`, in
+/// order.
+///
+/// # Errors
+/// Returns an error if the file can't be read or is invalid (see
+/// [`read`]), or doesn't define the pipeline.
+pub fn read_pipeline(path: &str, name: &str) -> Result<Vec<StageEntry>, Error> {
+    let file = read_file(path)?;
+    let names: Vec<&str> = file
+        .pipelines
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect();
+    if names.is_empty() {
+        return Err(Error::Config(format!("{path} defines no pipelines")));
+    }
+    if !names.contains(&name) {
+        return Err(Error::Config(format!(
+            "{path} defines no pipeline {name} (pipelines: {})",
+            names.join(", ")
+        )));
+    }
+    Ok(file
+        .pipelines
+        .into_iter()
+        .find(|(pipeline, _)| pipeline == name)
+        .map(|(_, stages)| stages)
+        .unwrap_or_default())
+}
+
+/// Reads and parses the config file at `path`.
+fn read_file(path: &str) -> Result<File, Error> {
+    let content = fs::read_to_string(path).map_err(|e| {
+        Error::io(format!("Failed to read config file {path}"), e)
+    })?;
+    let mut file = File::default();
+    // Entries of the current section: the top level or the current stage
+    let mut section: Vec<Entry> = Vec::new();
+    // Pipeline of the current stage, and the line of its header
+    let mut pipeline: Option<(String, usize)> = None;
+    let mut keys = HashSet::new();
+    for (idx, line) in content.lines().enumerate() {
+        let line = line.trim();
+        let at = |e: Error| e.context(format!("{path}:{}", idx + 1));
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('[') {
+            let name = parse_header(strip_comment(line).trim()).map_err(at)?;
+            close_section(&mut file, pipeline.take(), &mut section, path)?;
+            pipeline = Some((name, idx + 1));
+            keys.clear();
+            continue;
+        }
+        let entry = parse_line(line).map_err(at)?;
+        if !keys.insert(entry.flag.clone()) {
+            return Err(at(Error::Config(format!(
+                "{} is set more than once",
+                entry.flag
+            ))));
+        }
+        section.push(entry);
+    }
+    close_section(&mut file, pipeline, &mut section, path)?;
+    Ok(file)
+}
+
+/// Parses a `[[pipeline.NAME]]` header, returning `NAME`.
+fn parse_header(line: &str) -> Result<String, Error> {
+    let name = line
+        .strip_prefix("[[pipeline.")
+        .and_then(|rest| rest.strip_suffix("]]"))
+        .ok_or_else(|| {
+            Error::Config(
+                "tables are not supported, except [[pipeline.NAME]] stages"
+                    .to_string(),
+            )
+        })?;
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(Error::Config(format!("invalid pipeline name {name:?}")));
+    }
+    Ok(name.to_string())
+}
+
+/// Moves the entries read since the last header into `file`: as the
+/// orchestrator's flags before the first header, or else as the next stage
+/// of `pipeline` (whose header is on the given line of `path`).
+fn close_section(
+    file: &mut File,
+    pipeline: Option<(String, usize)>,
+    section: &mut Vec<Entry>,
+    path: &str,
+) -> Result<(), Error> {
+    let entries = std::mem::take(section);
+    let Some((pipeline, line)) = pipeline else {
+        file.entries = entries;
+        return Ok(());
+    };
+    let mut stage = None;
+    let mut args = Vec::new();
+    for entry in entries {
+        if entry.flag == "--stage" {
+            stage = entry.args.get(1).cloned();
+        } else {
+            args.extend(entry.args);
+        }
+    }
+    let stage = stage.ok_or_else(|| {
+        Error::Config(format!(
+            "{path}:{line}: this stage of pipeline {pipeline} needs a stage key, e.g. stage = \"extract-tickets\""
+        ))
+    })?;
+    let stage = StageEntry { stage, args };
+    match file
+        .pipelines
+        .iter_mut()
+        .find(|(name, _)| *name == pipeline)
+    {
+        Some((_, stages)) => stages.push(stage),
+        None => file.pipelines.push((pipeline, vec![stage])),
+    }
+    Ok(())
+}
+
+/// Parses one `key = value` line.
+fn parse_line(line: &str) -> Result<Entry, Error> {
+    let (key, value) = line
+        .split_once('=')
+        .ok_or_else(|| Error::Config("expected key = value".to_string()))?;
+    let key = key.trim();
+    if key.is_empty()
+        || !key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(Error::Config(format!("invalid key {key:?}")));
+    }
+    let name = key.replace('_', "-");
+    if COMMAND_LINE_ONLY.contains(&name.as_str()) {
+        return Err(Error::Config(format!(
+            "{key} can only be given on the command line"
+        )));
+    }
+    let flag = format!("--{name}");
+
+    let value = strip_comment(value).trim();
+    let mut args = Vec::new();
+    match parse_value(value)? {
+        Value::Bool(true) => args.push(flag.clone()),
+        Value::Bool(false) => {}
+        Value::Array(items) => {
+            for item in &items {
+                args.push(flag.clone());
+                args.push(scalar(item).ok_or_else(|| {
+                    Error::Config(format!(
+                        "{key} must be an array of strings or numbers"
+                    ))
+                })?);
+            }
+        }
+        value => {
+            args.push(flag.clone());
+            args.push(scalar(&value).ok_or_else(|| {
+                Error::Config(format!(
+                    "{key} must be a string, number, boolean or array"
+                ))
+            })?);
+        }
+    }
+    Ok(Entry { flag, args })
+}
+
+/// Parses a value: a literal string, or anything JSON and TOML write alike
+/// (basic strings, numbers, booleans, arrays of them).
+fn parse_value(value: &str) -> Result<Value, Error> {
+    if let Some(rest) = value.strip_prefix('\'') {
+        return rest
+            .strip_suffix('\'')
+            .filter(|literal| !literal.contains('\''))
+            .map(|literal| Value::String(literal.to_string()))
+            .ok_or_else(|| {
+                Error::Config(format!("unterminated literal string {value}"))
+            });
+    }
+    json::parse(value)
+        .map_err(|e| Error::Config(format!("invalid value {value}: {e}")))
+}
+
+/// A string or number as a flag value.
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) | Value::Number(s) => Some(s.clone()),
+        _ => None,
+    }
+}
+
+/// Cuts a trailing `# comment` off a value, leaving `#` inside strings.
+fn strip_comment(value: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+    for (idx, c) in value.char_indices() {
+        match quote {
+            Some('"') if escaped => escaped = false,
+            Some('"') if c == '\\' => escaped = true,
+            Some(q) if c == q => quote = None,
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '#' => return &value[..idx],
+            Some(_) | None => {}
+        }
+    }
+    value
+}
@@ -0,0 +1,119 @@
+//! Linear cycle updated tickets are added to (`--linear-cycle`).
+//!
+//! Cycles belong to a team, so `--linear-cycle CYCLE` is resolved once per
+//! team of the updated tickets: `current` is the team's active cycle, `next`
+//! the one after it, and a number is the team's cycle with that number. A
+//! team without such a cycle is logged, and its tickets aren't added to any.
+
+use std::fmt;
+
+use crate::error::Error;
+use crate::utils::{self, LinearApi};
+
+/// Which of a team's cycles to add tickets to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cycle {
+    /// The team's active cycle
+    Current,
+    /// The cycle after the active one
+    Next,
+    /// The team's cycle with this number
+    Number(u64),
+}
+
+impl Cycle {
+    /// Parses a `--linear-cycle` value: `current`, `next` or a cycle number.
+    ///
+    /// # Errors
+    /// Returns an error for any other value.
+    pub fn parse(value: &str) -> Result<Self, Error> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "current" => Ok(Self::Current),
+            "next" => Ok(Self::Next),
+            number => number.parse().map(Self::Number).map_err(|_| {
+                Error::Config(format!(
+                    "Invalid --linear-cycle value: {value} (expected current, next or a cycle number)"
+                ))
+            }),
+        }
+    }
+
+    /// The `cycles` filter selecting this cycle of a team.
+    fn filter(self) -> String {
+        match self {
+            Self::Current => "{ isActive: { eq: true } }".to_string(),
+            Self::Next => "{ isNext: { eq: true } }".to_string(),
+            Self::Number(number) => format!("{{ number: {{ eq: {number} }} }}"),
+        }
+    }
+}
+
+impl fmt::Display for Cycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Current => write!(f, "current"),
+            Self::Next => write!(f, "next"),
+            Self::Number(number) => write!(f, "{number}"),
+        }
+    }
+}
+
+/// A team's cycle, as found by [`find`].
+#[derive(Debug, Clone)]
+pub struct TeamCycle {
+    pub id: String,
+    pub number: String,
+    /// Empty for cycles without a name
+    pub name: String,
+}
+
+impl fmt::Display for TeamCycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.name.is_empty() {
+            write!(f, "cycle {}", self.number)
+        } else {
+            write!(f, "cycle {} ({})", self.number, self.name)
+        }
+    }
+}
+
+/// Finds the `cycle` of the team with ID `team_id`.
+///
+/// # GraphQL Query
+/// ```graphql
+/// query($teamId: String!) {
+///   team(id: $teamId) {
+///     cycles(filter: <cycle filter>, first: 1) {
+///       nodes { id number name }
+///     }
+///   }
+/// }
+/// ```
+///
+/// # Returns
+/// `None` if the team has no such cycle.
+///
+/// # Errors
+/// Returns an error if the request fails.
+pub fn find(
+    cycle: Cycle,
+    team_id: &str,
+    linear: &LinearApi,
+) -> Result<Option<TeamCycle>, Error> {
+    let query = format!(
+        r#"{{"query": "query($teamId: String!) {{ team(id: $teamId) {{ cycles(filter: {}, first: 1) {{ nodes {{ id number name }} }} }} }}", "variables": {{"teamId": {}}}}}"#,
+        cycle.filter(),
+        utils::json_string(team_id)
+    );
+    let response = utils::graphql_request(&query, linear)?;
+    utils::ensure_no_graphql_errors(&response)?;
+    Ok(response
+        .get("data.team.cycles.nodes")
+        .items()
+        .first()
+        .map(|node| TeamCycle {
+            id: node.get("id").str_or_empty().to_string(),
+            number: node.get("number").to_string(),
+            name: node.get("name").str_or_empty().to_string(),
+        }))
+}
@@ -0,0 +1,236 @@
+//! Waiting for the release to be live (`--deployment-environment`,
+//! `--deployment-url`).
+//!
+//! A published release isn't necessarily deployed yet. With either flag, the
+//! orchestrator checks a deployment marker before starting the pipeline, so
+//! tickets are only closed once the release is actually live:
+//! - `--deployment-environment ENV`: the latest GitHub deployment of the
+//!   release tag's commit to environment `ENV` has a `success` status
+//! - `--deployment-url URL`: `URL` (e.g. a health or version endpoint)
+//!   answers with a 2xx status and a body naming the release version (the
+//!   tag, with or without its leading `v`)
+//!
+//! By default the markers are checked once, and a release that isn't live
+//! fails the run. With `--deployment-timeout SECS`, they are checked every
+//! [`POLL_INTERVAL`] until they pass or `SECS` have elapsed. A URL that
+//! can't be reached counts as not live yet rather than as an error, since
+//! the service may be restarting. In dry-run, the markers are checked once
+//! and the result is only logged.
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::commit_status;
+use crate::config::Config;
+use crate::error::Error;
+use crate::exit_status;
+use crate::github_api::GitHub;
+use crate::json::{self, Value};
+use crate::utils;
+
+const NAME: &str = "orchestrator";
+
+/// Delay between checks while waiting for the deployment.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The deployment markers of a release (`--deployment-environment`,
+/// `--deployment-url`), and how long to wait for them.
+#[derive(Debug, Default)]
+pub struct Deployment {
+    /// GitHub environment the release must be deployed to
+    pub environment: Option<String>,
+    /// Endpoint that must report the release version
+    pub url: Option<String>,
+    /// How long to wait for the deployment, in seconds
+    /// (`--deployment-timeout`); checked once without it
+    pub timeout_secs: Option<u64>,
+}
+
+macro_rules! log {
+    ($fmt:expr) => {
+        utils::log(NAME, format_args!($fmt));
+    };
+}
+
+/// Waits until release `tag` is live, as far as the configured deployment
+/// markers tell. Returns immediately if none are configured.
+///
+/// # Errors
+/// Returns an error if the release isn't live within the
+/// `--deployment-timeout` (or, without it, at the first check), or the
+/// GitHub deployment can't be looked up.
+pub fn wait(config: &Config, tag: &str) -> Result<(), Error> {
+    if config.deployment.environment.is_none()
+        && config.deployment.url.is_none()
+    {
+        return Ok(());
+    }
+    let timeout =
+        Duration::from_secs(config.deployment.timeout_secs.unwrap_or(0));
+    let started = Instant::now();
+    loop {
+        let Some(reason) = not_live(config, tag)? else {
+            log!("release {tag} is live");
+            return Ok(());
+        };
+        if config.dry_run {
+            log!(
+                "release {tag} isn't live yet ({reason}); a real run would wait for it before updating tickets"
+            );
+            return Ok(());
+        }
+        if started.elapsed() + POLL_INTERVAL > timeout {
+            return Err(Error::GitHub(format!(
+                "Release {tag} isn't live: {reason}"
+            )));
+        }
+        let interval = POLL_INTERVAL.as_secs();
+        log!(
+            "release {tag} isn't live yet ({reason}); checking again in {interval}s"
+        );
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Checks the configured deployment markers of release `tag`.
+///
+/// # Returns
+/// Why the release isn't live, or `None` if every marker passed.
+///
+/// # Errors
+/// Returns an error if the GitHub deployment can't be looked up.
+fn not_live(config: &Config, tag: &str) -> Result<Option<String>, Error> {
+    if let Some(ref environment) = config.deployment.environment
+        && let Some(reason) =
+            environment_not_live(tag, environment, &config.github()?)?
+    {
+        return Ok(Some(reason));
+    }
+    if let Some(ref url) = config.deployment.url
+        && let Some(reason) = url_not_live(tag, url)
+    {
+        return Ok(Some(reason));
+    }
+    Ok(None)
+}
+
+/// Checks the latest deployment of the commit of `tag` to `environment`.
+///
+/// # Returns
+/// Why it doesn't count as live, or `None` if its latest status is
+/// `success`.
+///
+/// # Errors
+/// Returns an error if the commit or the deployment can't be looked up.
+fn environment_not_live(
+    tag: &str,
+    environment: &str,
+    github: &GitHub,
+) -> Result<Option<String>, Error> {
+    let sha = commit_status::tag_commit(tag, github)?;
+    let deployments = repo_get(
+        &format!(
+            "deployments?sha={sha}&environment={}&per_page=1",
+            utils::percent_encode(environment)
+        ),
+        github,
+    )
+    .map_err(|e| {
+        e.context(format!(
+            "Failed to look up the deployments of {sha} to {environment}"
+        ))
+    })?;
+    let Some(deployment) = deployments.items().first() else {
+        return Ok(Some(format!("{sha} has no deployment to {environment}")));
+    };
+    let id = deployment.get("id").to_string();
+    let statuses =
+        repo_get(&format!("deployments/{id}/statuses?per_page=1"), github)
+            .map_err(|e| {
+                e.context(format!(
+                    "Failed to look up the status of deployment {id}"
+                ))
+            })?;
+    Ok(match statuses.items().first().map(|s| s.get("state")) {
+        Some(state) if state.as_str() == Some("success") => None,
+        Some(state) => Some(format!(
+            "its deployment to {environment} is {}",
+            state.str_or_empty()
+        )),
+        None => {
+            Some(format!("its deployment to {environment} has no status yet"))
+        }
+    })
+}
+
+/// Gets `path` under the current repository's REST endpoint (e.g.
+/// `deployments?sha=...`).
+///
+/// # Errors
+/// Returns an error if `gh` (or the API) fails or the response isn't JSON.
+fn repo_get(path: &str, github: &GitHub) -> Result<Value, Error> {
+    let github_host = match github {
+        GitHub::Cli(host) => host.as_deref(),
+        GitHub::Api(api) => {
+            let (owner, name) = api.current_repo()?;
+            return api.rest(
+                "GET",
+                &format!("repos/{owner}/{name}/{path}"),
+                None,
+            );
+        }
+    };
+    let output = utils::gh_command(github_host)
+        .args(["api", &format!("repos/{{owner}}/{{repo}}/{path}")])
+        .output()
+        .map_err(|e| {
+            exit_status::spawn_error("Failed to execute gh command", e)
+        })?;
+    if !output.status.success() {
+        return Err(Error::GitHub(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    let body = String::from_utf8(output.stdout)
+        .map_err(|e| Error::command(format!("Invalid UTF-8 from gh: {e}")))?;
+    json::parse(&body).map_err(|e| e.context("Invalid JSON from gh"))
+}
+
+/// Checks that `url` answers with a 2xx status and names the version of
+/// `tag`.
+///
+/// # Returns
+/// Why it doesn't count as live, or `None` if it does.
+fn url_not_live(tag: &str, url: &str) -> Option<String> {
+    let response =
+        match utils::http_request("GET", url, &[], &[], None, Error::Other) {
+            Ok(response) => response,
+            Err(e) => {
+                return Some(format!("{url} can't be reached: {e}"));
+            }
+        };
+    if !(200..300).contains(&response.status) {
+        let status = response.status;
+        return Some(format!("{url} answered HTTP {status}"));
+    }
+    let version = tag.strip_prefix(['v', 'V']).unwrap_or(tag);
+    if mentions_version(&response.body, version) {
+        None
+    } else {
+        let excerpt = utils::excerpt(response.body.trim());
+        Some(format!("{url} doesn't report version {version}: {excerpt}"))
+    }
+}
+
+/// Whether `body` names `version` on its own, e.g. `"version": "1.2.3"` or
+/// `v1.2.3`, but not `1.2.30` or `1.2.3-rc1`.
+fn mentions_version(body: &str, version: &str) -> bool {
+    body.match_indices(version).any(|(start, _)| {
+        let before = body[..start].chars().next_back();
+        let after = body[start + version.len()..].chars().next();
+        !before.is_some_and(|c| {
+            c == '.' || (c.is_alphanumeric() && !matches!(c, 'v' | 'V'))
+        }) && !after
+            .is_some_and(|c| c.is_alphanumeric() || matches!(c, '.' | '-'))
+    })
+}
@@ -0,0 +1,79 @@
+//! Dry-run diff of update-tickets (`--diff`).
+//!
+//! A dry run normally prints the URLs of the tickets it would update, which
+//! says nothing about where they are now or why the others would be left
+//! alone. With `--dry-run --diff`, update-tickets instead prints a table of
+//! every input ticket once the run is done, for release managers to review
+//! before approving the change:
+//!
+//! ```text
+//! TICKET  TEAM  STATE                RESULT
+//! ENG-2   ENG   Passing → completed  update
+//! ENG-1   ENG   Done                 already completed
+//! ENG-3   ENG   Todo                 skip: its state isn't one tickets are moved out of (...)
+//! OPS-9   -     -                    fail: Entity not found
+//! ```
+//!
+//! Rows are in processing order. The target state is the one the run moves
+//! tickets to: `completed` (the team's Done/Completed state), or the
+//! `--state-route`, `--target-state` or `--target-state-type` state.
+
+/// One ticket of the table.
+#[derive(Debug)]
+pub struct Row {
+    pub ticket: String,
+    /// Team key, if the ticket was looked up
+    pub team: Option<String>,
+    /// Current workflow state, if the ticket was looked up
+    pub from_state: Option<String>,
+    /// State the ticket would be moved to, if it would be updated
+    pub to_state: Option<String>,
+    /// What would happen to it, e.g. `update` or `skip: it is archived`
+    pub result: String,
+}
+
+/// The table of a dry run, filled as tickets are processed.
+#[derive(Debug, Default)]
+pub struct Diff {
+    rows: Vec<Row>,
+}
+
+impl Diff {
+    pub fn push(&mut self, row: Row) {
+        self.rows.push(row);
+    }
+
+    /// Prints the table on stdout, with a header and aligned columns.
+    pub fn print(&self) {
+        let header = ["TICKET", "TEAM", "STATE", "RESULT"].map(String::from);
+        let lines: Vec<[String; 4]> = std::iter::once(header)
+            .chain(self.rows.iter().map(|row| {
+                let state = match (&row.from_state, &row.to_state) {
+                    (Some(from), Some(to)) => format!("{from} → {to}"),
+                    (Some(from), None) => from.clone(),
+                    (None, _) => "-".to_string(),
+                };
+                [
+                    row.ticket.clone(),
+                    row.team.clone().unwrap_or_else(|| "-".to_string()),
+                    state,
+                    row.result.clone(),
+                ]
+            }))
+            .collect();
+        let mut widths = [0; 3];
+        for line in &lines {
+            for (width, cell) in widths.iter_mut().zip(line) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        for [ticket, team, state, result] in &lines {
+            println!(
+                "{ticket:<w0$}  {team:<w1$}  {state:<w2$}  {result}",
+                w0 = widths[0],
+                w1 = widths[1],
+                w2 = widths[2]
+            );
+        }
+    }
+}
@@ -0,0 +1,158 @@
+//! The crate's error type.
+//!
+//! Every fallible function returns an [`Error`], whose variant tells what
+//! failed:
+//! - [`Error::Config`]: invalid command line or config file
+//! - [`Error::Credentials`]: a tracker credential (or the `--github-api`
+//!   token) is missing, or was rejected
+//! - [`Error::Io`]: a file (or stdin, stdout) couldn't be read or written
+//! - [`Error::ExternalCommand`]: `gh`, `glab`, `curl`, `grep`, `sha256sum`
+//!   or `git` couldn't be run, or failed
+//! - [`Error::GitHub`]: GitHub (or GitLab) failed or refused a request
+//! - [`Error::LinearApi`]: the Linear (or Jira) API failed or refused a
+//!   request
+//! - [`Error::GraphQl`]: a GraphQL API answered with `errors`
+//! - [`Error::Partial`]: some tickets failed to update (`--fail-on-partial`)
+//! - [`Error::Stage`]: a pipeline stage run as a process exited with a
+//!   failure class
+//! - [`Error::Other`]: anything else, e.g. invalid input records
+//!
+//! An error passed up with [`Error::context`] keeps its cause, so the
+//! variant of the failure is still found with [`Error::root`], and the exit
+//! status with [`crate::exit_status::of`]. The `Display` of an error is the
+//! whole chain, as printed after `Error: `:
+//!
+//! ```text
+//! Failed to get PR #12: GitHub API returned HTTP 404 to GET ...: Not Found
+//! ```
+//!
+//! `source()` gives the cause (the `io::Error` of [`Error::Io`] and
+//! [`Error::ExternalCommand`], the inner error of [`Error::Context`]). Since
+//! the `Display` already includes every cause, a reporter walking the chain
+//! should print only the outermost error.
+
+use std::fmt;
+use std::io;
+
+/// A failure, with its cause.
+#[derive(Debug)]
+pub enum Error {
+    /// The command line or config file is invalid
+    Config(String),
+    /// A tracker credential (or the `--github-api` token) is missing, or was
+    /// rejected
+    Credentials(String),
+    /// Reading or writing a file failed
+    Io { context: String, source: io::Error },
+    /// An external command couldn't be spawned (`source`), or failed
+    ExternalCommand {
+        message: String,
+        source: Option<io::Error>,
+    },
+    /// A GitHub (or GitLab) request failed
+    GitHub(String),
+    /// A Linear (or Jira) API request failed
+    LinearApi(String),
+    /// A GraphQL API (`api`, e.g. `Linear API`) answered with errors
+    GraphQl { api: &'static str, messages: String },
+    /// Some tickets failed to update (`--fail-on-partial`)
+    Partial(String),
+    /// A pipeline stage run as a process failed, exiting with the status
+    /// `code` of a failure class (see [`crate::exit_status`])
+    Stage { message: String, code: i32 },
+    /// Any other failure
+    Other(String),
+    /// `context` failed because of `source`
+    Context { context: String, source: Box<Error> },
+}
+
+impl Error {
+    /// An [`Error::Io`] of `context`, e.g. `Failed to read FILE`.
+    pub fn io(context: impl Into<String>, source: io::Error) -> Self {
+        Self::Io {
+            context: context.into(),
+            source,
+        }
+    }
+
+    /// An [`Error::ExternalCommand`] that ran, but failed with `message`.
+    pub fn command(message: impl Into<String>) -> Self {
+        Self::ExternalCommand {
+            message: message.into(),
+            source: None,
+        }
+    }
+
+    /// An [`Error::GraphQl`] of `api` answering with `messages`.
+    #[must_use]
+    pub fn graphql(api: &'static str, messages: &str) -> Self {
+        Self::GraphQl {
+            api,
+            messages: messages.trim().to_string(),
+        }
+    }
+
+    /// This error as the cause of `context`, e.g. `Failed to get PR #12`.
+    #[must_use]
+    pub fn context(self, context: impl Into<String>) -> Self {
+        Self::Context {
+            context: context.into(),
+            source: Box::new(self),
+        }
+    }
+
+    /// The error at the end of the chain of contexts, whose variant tells
+    /// what failed.
+    #[must_use]
+    pub fn root(&self) -> &Self {
+        match self {
+            Self::Context { source, .. } => source.root(),
+            other => other,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Config(message)
+            | Self::Credentials(message)
+            | Self::GitHub(message)
+            | Self::LinearApi(message)
+            | Self::Partial(message)
+            | Self::Other(message)
+            | Self::Stage { message, .. }
+            | Self::ExternalCommand {
+                message,
+                source: None,
+            } => f.write_str(message),
+            Self::ExternalCommand {
+                message,
+                source: Some(source),
+            } => write!(f, "{message}: {source}"),
+            Self::Io { context, source } => write!(f, "{context}: {source}"),
+            Self::GraphQl { api, messages } => {
+                write!(f, "{api} returned errors: {messages}")
+            }
+            Self::Context { context, source } => {
+                write!(f, "{context}: {source}")
+            }
+        }
+    }
+}
+
+/// The `Display` of an error already includes its causes, so `source()` is
+/// for callers that inspect the chain, e.g. for an [`io::ErrorKind`].
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. }
+            | Self::ExternalCommand {
+                source: Some(source),
+                ..
+            } => Some(source),
+            Self::Context { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,135 @@
+//! Request estimate of a dry run.
+//!
+//! Before a large backfill, operators need to know how hard the real run
+//! would hit each API and its rate limits. A dry run sends the same lookups
+//! as the real run but none of its changes, so at the end update-tickets
+//! logs the requests the real run would send, per provider:
+//!
+//! ```text
+//! update-tickets  : The real run would send about:
+//! update-tickets  :   Linear: 43 queries, 24 mutations
+//! update-tickets  :   GitHub: 1 request
+//! update-tickets  :   webhook: 12 requests
+//! ```
+//!
+//! Queries are the requests the dry run sent (counted as they are sent, see
+//! [`count`]) plus the lookups it skips, such as each team's target workflow
+//! state; mutations are the changes it logged instead of making. Retries,
+//! rate-limit waits and tickets changing in between aren't accounted for.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::utils;
+
+/// An API requests are sent to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Provider {
+    Linear,
+    GitHub,
+    Jira,
+    /// The `--webhook-url` endpoint
+    Webhook,
+    /// The `--slack-webhook` endpoint
+    Slack,
+}
+
+impl Provider {
+    const ALL: [Self; 5] = [
+        Self::Linear,
+        Self::GitHub,
+        Self::Jira,
+        Self::Webhook,
+        Self::Slack,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Linear => "Linear",
+            Self::GitHub => "GitHub",
+            Self::Jira => "Jira",
+            Self::Webhook => "webhook",
+            Self::Slack => "Slack",
+        }
+    }
+}
+
+/// Whether a request reads or changes something.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Request {
+    Query,
+    Mutation,
+}
+
+/// Requests sent by this process so far, per [`Provider`].
+static SENT: [AtomicU64; 5] = [const { AtomicU64::new(0) }; 5];
+
+/// Counts a request sent to `provider`.
+pub fn count(provider: Provider) {
+    SENT[provider as usize].fetch_add(1, Ordering::Relaxed);
+}
+
+/// Requests a dry run left out, filled in as tickets are processed.
+#[derive(Debug, Default)]
+pub struct Estimate {
+    queries: [AtomicU64; 5],
+    mutations: [AtomicU64; 5],
+}
+
+impl Estimate {
+    /// Records `count` requests to `provider` that the real run would send.
+    pub fn add(&self, provider: Provider, request: Request, count: u64) {
+        let counts = match request {
+            Request::Query => &self.queries,
+            Request::Mutation => &self.mutations,
+        };
+        counts[provider as usize].fetch_add(count, Ordering::Relaxed);
+    }
+
+    /// Logs the requests of the real run per provider, under log prefix
+    /// `name`: those sent so far and those left out.
+    pub fn report(&self, name: &str) {
+        let lines: Vec<String> = Provider::ALL
+            .into_iter()
+            .filter_map(|provider| {
+                let idx = provider as usize;
+                let queries = SENT[idx].load(Ordering::Relaxed)
+                    + self.queries[idx].load(Ordering::Relaxed);
+                let mutations = self.mutations[idx].load(Ordering::Relaxed);
+                let counts = match (provider, queries, mutations) {
+                    (_, 0, 0) => return None,
+                    (
+                        Provider::GitHub | Provider::Webhook | Provider::Slack,
+                        _,
+                        _,
+                    ) => plural(queries + mutations, "request"),
+                    _ => format!(
+                        "{}, {}",
+                        plural(queries, "query"),
+                        plural(mutations, "mutation")
+                    ),
+                };
+                Some(format!("  {}: {counts}", provider.name()))
+            })
+            .collect();
+        if lines.is_empty() {
+            utils::log(
+                name,
+                format_args!("The real run would send no API requests"),
+            );
+            return;
+        }
+        utils::log(name, format_args!("The real run would send about:"));
+        for line in lines {
+            utils::log(name, format_args!("{line}"));
+        }
+    }
+}
+
+/// `count` followed by `noun`, pluralized (`1 query`, `2 queries`).
+fn plural(count: u64, noun: &str) -> String {
+    match (count, noun.strip_suffix('y')) {
+        (1, _) => format!("1 {noun}"),
+        (_, Some(stem)) => format!("{count} {stem}ies"),
+        (_, None) => format!("{count} {noun}s"),
+    }
+}
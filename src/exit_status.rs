@@ -0,0 +1,89 @@
+//! Exit codes by failure class.
+//!
+//! The classes a caller such as CI may want to tell apart are told by the
+//! error a run fails with ([`of`]), at the root of its chain of contexts:
+//! - [`CONFIG`]: invalid command line or config file ([`Error::Config`])
+//! - [`ApiBudget::EXHAUSTED_EXIT_CODE`]: the `--api-budget` ran out (told by
+//!   the run's budget, not the error)
+//! - [`PARTIAL`]: some tickets failed (only with `--fail-on-partial`;
+//!   [`Error::Partial`])
+//! - [`TOOL_MISSING`]: an external command (`gh`, `glab`, `curl`, `grep`,
+//!   `sha256sum`, `git`) isn't installed (an [`Error::ExternalCommand`] that
+//!   couldn't be spawned for that reason)
+//! - [`CREDENTIALS`]: a tracker credential is missing or was rejected
+//!   ([`Error::Credentials`])
+//!
+//! Any other error exits with [`FAILURE`]. The orchestrator passes on the
+//! class of a stage that exited with one ([`Error::Stage`]). Since the class
+//! is part of each run's result, runs in one process, such as those of
+//! `serve` or of a library embedding the pipeline, don't affect each other's.
+//!
+//! [`ApiBudget::EXHAUSTED_EXIT_CODE`]: crate::cache::ApiBudget::EXHAUSTED_EXIT_CODE
+
+use std::io;
+use std::process::ExitStatus;
+
+use crate::error::Error;
+
+/// Any failure without a class of its own.
+pub const FAILURE: i32 = 1;
+
+/// The command line (or config file) is invalid.
+pub const CONFIG: i32 = 2;
+
+/// Every ticket was processed, but some failed (`--fail-on-partial`).
+pub const PARTIAL: i32 = 4;
+
+/// An external command isn't installed.
+pub const TOOL_MISSING: i32 = 5;
+
+/// A tracker credential (or the `--github-api` token) is missing, or was
+/// rejected.
+pub const CREDENTIALS: i32 = 6;
+
+/// Codes a stage's exit status is passed on with.
+const CLASSES: [i32; 4] = [CONFIG, PARTIAL, TOOL_MISSING, CREDENTIALS];
+
+/// The code a run failing with `error` exits with.
+#[must_use]
+pub fn of(error: &Error) -> i32 {
+    match error.root() {
+        Error::Config(_) => CONFIG,
+        Error::Credentials(_) => CREDENTIALS,
+        Error::Partial(_) => PARTIAL,
+        Error::ExternalCommand {
+            source: Some(source),
+            ..
+        } if source.kind() == io::ErrorKind::NotFound => TOOL_MISSING,
+        Error::Stage { code, .. } => *code,
+        _ => FAILURE,
+    }
+}
+
+/// The error of a pipeline stage that exited with `status`: an
+/// [`Error::Stage`] passing on its failure class, if it exited with one,
+/// otherwise an [`Error::Other`] of `message`.
+#[must_use]
+pub fn stage_error(status: ExitStatus, message: String) -> Error {
+    match status.code().filter(|code| CLASSES.contains(code)) {
+        Some(code) => Error::Stage { message, code },
+        None => Error::Other(message),
+    }
+}
+
+/// An [`Error::Credentials`] of `message`.
+#[must_use]
+pub fn credentials(message: &str) -> Error {
+    Error::Credentials(message.to_string())
+}
+
+/// `error` from running a command, as an [`Error::ExternalCommand`] of
+/// `message` (e.g. `Failed to execute gh command`); a command that doesn't
+/// exist exits with [`TOOL_MISSING`].
+#[must_use]
+pub fn spawn_error(message: &str, error: io::Error) -> Error {
+    Error::ExternalCommand {
+        message: message.to_string(),
+        source: Some(error),
+    }
+}
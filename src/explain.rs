@@ -0,0 +1,129 @@
+//! Decision traces for one ticket or PR (`--explain`).
+//!
+//! With `--explain ABC-123` (a ticket) or `--explain 123` (a PR; also `#123`
+//! or `owner/repo#123`), each stage logs what it decided about the subject,
+//! as `explain:` lines among its usual logs:
+//! - parse-notes: whether the PR is in the release notes
+//! - extract-tickets: whether the PR was fetched and skipped (unmerged,
+//!   outside the merge window), which tickets it references and where, and
+//!   which of them were dropped (`--exclude-pattern`, false positives) or
+//!   already output for an earlier PR
+//! - update-tickets: whether the ticket was dropped without a lookup, and
+//!   otherwise the state it was found in and what was done with it (for a
+//!   PR, every ticket found in it)
+//!
+//! A stage that never came across the subject says so at the end, so the
+//! trace shows where it left the pipeline. The orchestrator forwards
+//! `--explain` to every stage, so `grep 'explain:'` over its log answers
+//! "why did (or didn't) it close ABC-123?".
+
+use std::fmt;
+
+use crate::error::Error;
+use crate::protocol::{PrRef, SkipReason};
+use crate::utils;
+
+/// The ticket or PR to explain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Subject {
+    /// A ticket ID (`ABC-123`)
+    Ticket(String),
+    /// A PR (`123`, `#123` or `owner/repo#123`)
+    Pr(PrRef),
+}
+
+impl Subject {
+    /// Parses an `--explain` value: a PR if it is a number (optionally after
+    /// `#` or `owner/repo#`), otherwise a ticket ID.
+    ///
+    /// # Errors
+    /// Returns an error if the value is neither a PR identifier nor of the
+    /// form `KEY-123`.
+    pub fn parse(value: &str) -> Result<Self, Error> {
+        let value = value.trim();
+        if let Some(pr) = value.strip_prefix('#') {
+            return PrRef::parse(pr).map(Self::Pr);
+        }
+        if value.contains('#') || value.bytes().all(|b| b.is_ascii_digit()) {
+            return PrRef::parse(value).map(Self::Pr);
+        }
+        let valid_ticket =
+            value.split_once('-').is_some_and(|(key, number)| {
+                !key.is_empty()
+                    && key
+                        .bytes()
+                        .all(|b| b.is_ascii_alphanumeric() || b == b'_')
+                    && !number.is_empty()
+                    && number.bytes().all(|b| b.is_ascii_digit())
+            });
+        if !valid_ticket {
+            return Err(Error::Config(format!(
+                "Invalid --explain value: {value} (expected a ticket ID like ABC-123 or a PR like 123 or owner/repo#123)"
+            )));
+        }
+        Ok(Self::Ticket(value.to_ascii_uppercase()))
+    }
+
+    /// The subject as an `--explain` argument, for forwarding to a stage.
+    pub fn arg(&self) -> String {
+        match self {
+            Self::Ticket(ticket) => ticket.clone(),
+            Self::Pr(pr) => pr.to_text(),
+        }
+    }
+
+    /// Whether the subject is ticket `ticket`.
+    pub fn is_ticket(&self, ticket: &str) -> bool {
+        matches!(self, Self::Ticket(subject) if subject == ticket)
+    }
+
+    /// Whether the subject is PR `pr`.
+    pub fn is_pr(&self, pr: &PrRef) -> bool {
+        matches!(self, Self::Pr(subject) if subject == pr)
+    }
+}
+
+impl fmt::Display for Subject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ticket(ticket) => write!(f, "{ticket}"),
+            Self::Pr(pr) => write!(f, "PR {pr}"),
+        }
+    }
+}
+
+/// Logs one `explain:` line of stage `stage`.
+pub fn note(stage: &str, message: fmt::Arguments<'_>) {
+    utils::log(stage, format_args!("explain: {message}"));
+}
+
+/// Why update-tickets skipped a ticket, in words.
+pub fn skip_reason(reason: SkipReason) -> &'static str {
+    match reason {
+        SkipReason::Excluded => "it matches --exclude-pattern",
+        SkipReason::UnknownTeam => {
+            "its team key isn't a team of the workspace (--drop-unknown-teams)"
+        }
+        SkipReason::Quarantined => {
+            "it failed in its last runs and is quarantined (--quarantine-after)"
+        }
+        SkipReason::Checkpointed => {
+            "an earlier run with this --checkpoint processed it"
+        }
+        SkipReason::Archived => "it is archived",
+        SkipReason::Filtered => "it doesn't match --filter",
+        SkipReason::NotInProject => "it isn't in the --linear-project",
+        SkipReason::OtherUser => {
+            "it isn't assigned to the --assignee or created by the --creator"
+        }
+        SkipReason::AlreadyInTarget => {
+            "it is already in the state it would be moved to"
+        }
+        SkipReason::IneligibleState => {
+            "its state isn't one tickets are moved out of (see --from-states, --update-all-statuses)"
+        }
+        SkipReason::OtherOrg => {
+            "its URLs are of another Linear workspace (--other-org-urls)"
+        }
+    }
+}
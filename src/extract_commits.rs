@@ -0,0 +1,230 @@
+//! Extract Linear ticket IDs from the commits of a release
+//! (`extract-commits`).
+//!
+//! Some repositories merge without PRs, pushing commits straight to the
+//! release branch, so their release notes name no PR for extract-tickets to
+//! read. `extract-commits` instead lists the commits between the previous
+//! release and `--release-tag`, and searches their messages for tickets
+//! like extract-tickets searches a PR's commits: the headline
+//! (`commit_headline`), the rest of the message (`commit_body`) and its
+//! ticket links, trailers like `Linear: ABC-123` and magic words like
+//! `Fixes ABC-123` (`commit_trailer`). Its output feeds update-tickets like
+//! that of extract-tickets: ticket IDs, or ticket records naming the commit
+//! each ticket was first found in (see [`crate::protocol`]).
+//!
+//! The ticket flags of extract-tickets apply: `--ticket-pattern`,
+//! `--tracker jira`, `--prefix-map`, `--exclude-pattern`, `--ignore-tickets`,
+//! the non-ticket tokens, `--trailer-keys` and `--other-org-urls`.
+//!
+//! ## Range
+//! The commits are those reachable from `--release-tag` but not from
+//! `--previous-tag`. Without `--previous-tag`, it is the tag of the release
+//! created before the release (see [`crate::release_range`]), or with
+//! `--commit-source git`, the nearest tag before the release tag's commit
+//! (`git describe --tags --abbrev=0 TAG^`). Commits are searched oldest
+//! first, and each ticket is output once.
+//!
+//! ## Commit Source
+//! - `github` (the default): the commits GitHub compares the tags with
+//!   (`gh api --paginate repos/{owner}/{repo}/compare/PREV...TAG`, or the
+//!   same REST endpoint with `--github-api`; see [`crate::compare`])
+//! - `git`: `git log PREV..TAG` in the current directory, which must be a
+//!   clone with both tags (in GitHub Actions, check out with
+//!   `fetch-depth: 0`)
+
+use std::collections::HashSet;
+use std::process::Command;
+
+use crate::compare::{self, Commit};
+use crate::config::Config;
+use crate::error::Error;
+use crate::exit_status;
+use crate::extract_tickets;
+use crate::protocol::RecordWriter;
+use crate::release_range;
+use crate::utils;
+
+const NAME: &str = "extract-commits";
+
+macro_rules! log {
+    ($fmt:expr) => {
+        utils::log(NAME, format_args!($fmt));
+    };
+}
+
+/// The commits extract-commits searches (`--previous-tag`,
+/// `--commit-source`).
+#[derive(Debug, Clone, Default)]
+pub struct CommitRange {
+    /// Tag the range starts after, if not the previous release's
+    pub previous_tag: Option<String>,
+    /// Where the commits are listed from
+    pub source: CommitSource,
+}
+
+impl CommitRange {
+    /// Whether `--previous-tag` or `--commit-source` was given.
+    #[must_use]
+    pub fn is_set(&self) -> bool {
+        self.previous_tag.is_some() || self.source != CommitSource::GitHub
+    }
+}
+
+/// Where extract-commits lists commits from (`--commit-source`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CommitSource {
+    /// GitHub's comparison of the two tags (the default)
+    #[default]
+    GitHub,
+    /// `git log` in the current directory
+    Git,
+}
+
+impl CommitSource {
+    /// Parses a `--commit-source` value.
+    ///
+    /// # Errors
+    /// Returns an error unless `value` is `github` or `git`.
+    pub fn parse(value: &str) -> Result<Self, Error> {
+        match value {
+            "github" => Ok(Self::GitHub),
+            "git" => Ok(Self::Git),
+            other => Err(Error::Config(format!(
+                "Invalid --commit-source {other}: expected github or git"
+            ))),
+        }
+    }
+}
+
+/// Runs the extract-commits mode to find Linear tickets in the commits of
+/// a release.
+///
+/// # Output
+/// Prints Linear ticket IDs (or ndjson ticket records) to stdout, one per
+/// line, deduplicated (or, with `--output-format json`, an array of ticket
+/// records at the end), in order of discovery. With `--dry-run`, nothing is
+/// printed.
+///
+/// # Process
+/// 1. Find the previous tag, unless `--previous-tag` names it
+/// 2. List the commits between it and `--release-tag`
+/// 3. Search each commit message for ticket references, oldest first
+///
+/// # Errors
+/// Returns an error if the previous tag can't be found, the commits can't be
+/// listed, or grep fails.
+pub fn run(config: &Config) -> Result<(), Error> {
+    let Some(ref tag) = config.release_tag else {
+        return Err(Error::Config(
+            "extract-commits requires --release-tag".to_string(),
+        ));
+    };
+    let previous = match config.commit_range.previous_tag {
+        Some(ref previous) => previous.clone(),
+        None => previous_tag(config, tag)?,
+    };
+    log!("reading the commits of {previous}..{tag}");
+    let commits = match config.commit_range.source {
+        CommitSource::GitHub => {
+            compare::commits(&config.github()?, &previous, tag)?
+        }
+        CommitSource::Git => git_commits(&previous, tag)?,
+    };
+    let count = commits.len();
+    log!("found {count} commit(s)");
+
+    let mut seen = HashSet::new();
+    let mut output =
+        RecordWriter::new(config.output_format, config.output.clone());
+    for commit in &commits {
+        let records = extract_tickets::tickets_for_commit(
+            config,
+            &commit.sha,
+            &commit.message,
+        )?;
+        for record in records {
+            if !seen.insert(record.ticket.clone()) || config.dry_run {
+                continue;
+            }
+            output.write(Some(&record.ticket), || &record)?;
+        }
+    }
+
+    let tickets = seen.len();
+    if config.dry_run {
+        log!(
+            "dry run: {count} commit(s) referencing {tickets} ticket(s), none output"
+        );
+    } else {
+        output.finish();
+    }
+    log!("done");
+    if tickets == 0 {
+        log!("no changes made");
+    }
+    Ok(())
+}
+
+/// The tag the commits of release `tag` start after: the previous release,
+/// or with `--commit-source git`, the nearest tag before `tag`.
+///
+/// # Errors
+/// Returns an error if there is no such tag.
+fn previous_tag(config: &Config, tag: &str) -> Result<String, Error> {
+    let previous = match config.commit_range.source {
+        CommitSource::GitHub => release_range::previous(config, tag),
+        CommitSource::Git => {
+            git(&["describe", "--tags", "--abbrev=0", &format!("{tag}^")])
+                .map(|output| output.trim().to_string())
+        }
+    };
+    previous.map_err(|e| {
+        e.context(format!(
+            "Failed to find the tag before {tag}; name it with --previous-tag"
+        ))
+    })
+}
+
+/// The commits of `git log PREVIOUS..TAG`, oldest first.
+///
+/// # Errors
+/// Returns an error if `git` fails, e.g. a tag isn't in the clone.
+fn git_commits(previous: &str, tag: &str) -> Result<Vec<Commit>, Error> {
+    // The SHA ends in a unit separator, each commit in a record separator.
+    let log = git(&[
+        "log",
+        "--reverse",
+        "--format=%H%x1f%B%x1e",
+        &format!("{previous}..{tag}"),
+        "--",
+    ])?;
+    Ok(log
+        .split('\u{1e}')
+        .filter_map(|commit| {
+            let (sha, message) = commit.trim_start().split_once('\u{1f}')?;
+            Some(Commit {
+                sha: sha.to_string(),
+                message: message.trim_end().to_string(),
+            })
+        })
+        .collect())
+}
+
+/// Runs `git ARGS`, returning its stdout.
+///
+/// # Errors
+/// Returns an error if `git` can't be run or fails.
+fn git(args: &[&str]) -> Result<String, Error> {
+    let output = Command::new("git").args(args).output().map_err(|e| {
+        exit_status::spawn_error("Failed to execute git command", e)
+    })?;
+    if !output.status.success() {
+        return Err(Error::command(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    String::from_utf8(output.stdout)
+        .map_err(|e| Error::command(format!("Invalid UTF-8 from git: {e}")))
+}
@@ -7,69 +7,329 @@
 //! - PR comments
 //! - Commit message headlines
 //! - Commit message bodies
+//! - Ticket links of commit messages: trailers and magic words
 //!
 //! Supported ticket formats:
 //! - Ticket ID: `ABC-123`
 //! - Full URL: `https://linear.app/org/issue/ABC-123`
+//!
+//! `--ticket-pattern REGEX` replaces the Linear ticket ID pattern, for teams
+//! whose keys aren't 3 letters long (e.g. `[A-Z]{2,4}-[0-9]+`).
+//!
+//! A PR may link a ticket of another Linear workspace by its full URL (e.g.
+//! a vendor's `https://linear.app/vendor/issue/ENG-12`), whose bare ID would
+//! then be updated in the wrong workspace. With the organization known
+//! (`--linear-org` or `LINEAR_ORG`), tickets referenced only by URLs of
+//! other organizations are dropped and logged; `--other-org-urls tag`
+//! outputs them anyway, their records carrying the URL's `org`, for
+//! update-tickets to skip unless it is the ticket's `--org-map` workspace.
+//!
+//! Commit messages (squash-merge commits in particular) often link tickets
+//! only in trailers like `Linear: ABC-123`, or with a magic word like
+//! `Fixes ABC-123`. Those links are searched once more as the
+//! `commit_trailer` source, with the confidence of the PR body: the values
+//! of trailers whose key is one of `--trailer-keys` (by default `Linear`),
+//! and the words following `fix`, `close`, `resolve`, `ref` and their
+//! inflections (`fixes`, `closed`, `refs`, ...), case-insensitive.
+//!
+//! With `--tracker jira`, Jira issue keys are extracted instead: project keys
+//! of any length, which may contain digits and underscores (`PROJ-123`,
+//! `AB2_X-7`, or `https://acme.atlassian.net/browse/PROJ-123`).
+//!
+//! With `--output-format ndjson`, each ticket is written as a record carrying
+//! the PR it was found in and how confident the match is; `--output-format
+//! json` prints an array of them at the end (see [`crate::protocol`]).
+//!
+//! Release notes occasionally reference PRs that were never merged (drafts,
+//! or PRs closed without merging). By default their tickets are skipped and
+//! the PRs are listed at the end; `--allow-unmerged` extracts them anyway.
+//!
+//! `--merged-after` / `--merged-before` restrict extraction to PRs merged
+//! within a time window, so a PR merged after the tag was cut that slipped
+//! into the release notes doesn't close its tickets as shipped. PRs outside
+//! the window are skipped and listed at the end like unmerged PRs.
+//!
+//! In a monorepo, `--pr-label` and `--path-filter` restrict extraction to
+//! the PRs of one service: those carrying a label, or changing files under a
+//! path (see [`crate::scope`]). The other PRs are skipped and listed at the
+//! end like unmerged PRs.
+//!
+//! PRs that never got linked to a Linear ticket otherwise vanish from the
+//! pipeline; `--warn-unlinked-prs` logs each of them as it is processed and
+//! lists them all at the end.
+//!
+//! With `--prefix-map OLD=NEW`, references to a renamed team key are output
+//! under the new key. Tickets whose (remapped) ID matches the
+//! `--exclude-pattern` extended regex in full, e.g. `TMP-.*|SAND-.*` for
+//! sandbox teams, are dropped as if the PR never referenced them.
+//! So are the tickets of `--ignore-tickets` (e.g. docs tickets), while the
+//! PRs of `--ignore-prs` (e.g. reverts) are skipped without being fetched.
+//!
+//! Some tokens match the ticket pattern but are never tickets: encodings,
+//! hashes and standards like `UTF-8`, `SHA-256`, `RFC-7231` or `ISO-8601`.
+//! A built-in list of them is dropped (and logged) like excluded tickets;
+//! `--ignore-token REGEX` adds to it, and `--no-default-ignores` turns it
+//! off for a team whose key happens to be on it.
+//!
+//! PRs of a stack (Graphite, ghstack) or landed through a merge queue often
+//! carry text of their sibling PRs: commits squash-merged from the PR below
+//! (`Title (#122)`), merge-queue and branch merge commits, and stack
+//! listings in the body or a bot comment. That text is left out (and
+//! logged), so a sibling's tickets aren't attributed to this PR;
+//! `--no-stack-detection` searches everything.
+//!
+//! With `--forge gitlab`, the input identifiers are GitLab merge requests
+//! (`123` in the current project, `group/project#123` in another), fetched
+//! with `glab` (see [`crate::gitlab`]): their title, description,
+//! non-system notes and commit messages are searched like a PR's.
+//!
+//! An input line that isn't a PR identifier or PR record is logged and
+//! skipped, so one malformed line doesn't end the run; input without a
+//! single PR fails.
+//!
+//! With `--batch-prs`, all input is read first and the PRs are fetched
+//! [`github::BATCH_SIZE`] per `gh api graphql` query (see [`crate::github`])
+//! instead of one `gh pr view` each, at the cost of streaming.
+//!
+//! With `--concurrency N`, up to N PRs are fetched at once on worker
+//! threads as input streams in. Each PR's tickets (and log lines) are still
+//! output in input order, once it and every PR before it are done, so the
+//! output is the same as that of a sequential run.
+//!
+//! With `--github-api`, PRs are fetched from the GitHub APIs with a token
+//! instead of with `gh` (see [`crate::github_api`]).
+//!
+//! With `--mapping-file FILE`, the tickets found in each PR and the places
+//! they were found (title, body, comment, commit) are also written to FILE as
+//! JSON, so provenance survives even when stdout is a flat ticket stream.
+//!
+//! With `--api-budget N`, at most N PRs are fetched from GitHub (a paginated
+//! comment thread counts as one more request; cached PRs are free). Once the
+//! budget is used up, the remaining PRs are skipped and listed at the end,
+//! and the process exits with [`ApiBudget::EXHAUSTED_EXIT_CODE`].
+//!
+//! With `--dry-run`, every PR is fetched and searched as usual, but no
+//! tickets are written to stdout: the numbers of PRs and tickets are logged
+//! instead. A PR that can't be fetched doesn't stop the dry run; every such
+//! PR is listed at the end and the run fails, so a pre-flight job sees all
+//! of them at once.
+//!
+//! With `--explain`, the fetch, merge checks and references of the subject
+//! PR, or of every PR referencing the subject ticket, are logged (see
+//! [`crate::explain`]).
 
-use std::collections::HashSet;
-use std::process::Command;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, mpsc};
+use std::thread;
 
-use crate::config::Config;
-use crate::utils;
+use crate::cache::{ApiBudget, Cache, RateLimiter};
+use crate::config::{
+    Config, Forge, MergePolicy, OtherOrgUrls, PrFetches, PrefixMap, StackedPrs,
+    Tracker, UnlinkedPrs,
+};
+use crate::error::Error;
+use crate::explain::{self, Subject};
+use crate::github;
+use crate::github_api::GitHub;
+use crate::gitlab;
+use crate::json::{self, Value};
+use crate::protocol::{
+    Confidence, Input, Origin, PrRef, RecordWriter, SCHEMA_VERSION,
+    TicketRecord,
+};
+use crate::scope::ScopeFacts;
+use crate::utils::{self, NoInputTimeout};
 
 const NAME: &str = "extract-tickets";
 const TICKET_PATTERN: &str = r"[A-Z]{3}-[0-9]+";
+/// Prefix of Linear issue URLs up to the ticket ID, matched along with it to
+/// tell which workspace a URL points to.
+const LINEAR_URL_PATTERN: &str = r"linear\.app/[A-Za-z0-9_-]+/issue/";
+/// Trailer keys of commit messages whose values link tickets, unless
+/// `--trailer-keys` is given.
+const DEFAULT_TRAILER_KEYS: &[&str] = &["Linear"];
+/// Words linking the tickets that follow them in a commit message
+/// (`Fixes ABC-123`), case-insensitive.
+const MAGIC_WORDS: &[&str] = &[
+    "close",
+    "closes",
+    "closed",
+    "fix",
+    "fixes",
+    "fixed",
+    "resolve",
+    "resolves",
+    "resolved",
+    "ref",
+    "refs",
+    "references",
+];
+/// Jira issue keys (`--tracker jira`).
+const JIRA_TICKET_PATTERN: &str = r"[A-Z][A-Z0-9_]+-[0-9]+";
+/// Most comments `gh pr view --json comments` returns. PRs with that many
+/// have their comments fetched again, page by page, with `gh api`.
+const GH_VIEW_COMMENT_LIMIT: usize = 100;
+/// Member added to a PR response whose comments were fetched page by page,
+/// so a cached response isn't paginated again.
+const COMPLETE_COMMENTS_KEY: &str = "commentsComplete";
 
 macro_rules! log {
+    ($level:ident, $fmt:expr) => {
+        utils::log_at(utils::LogLevel::$level, NAME, format_args!($fmt));
+    };
     ($fmt:expr) => {
         utils::log(NAME, format_args!($fmt));
     };
 }
 
+macro_rules! explain {
+    ($fmt:expr) => {
+        explain::note(NAME, format_args!($fmt));
+    };
+}
+
 /// Runs the extract-tickets mode to find Linear tickets in PRs.
 ///
 /// # Input
-/// Reads PR numbers from input sources (stdin or files), one per line.
+/// Reads PR identifiers from input sources (stdin or files), one per line.
+/// Each identifier is either a bare PR number (`123`, resolved against the
+/// current repository), a repo-qualified reference (`owner/repo#123`), or an
+/// ndjson PR record.
 ///
 /// # Output
-/// Prints Linear ticket IDs to stdout, one per line, deduplicated.
-/// Outputs immediately in order of discovery (no sorting or buffering).
+/// Prints Linear ticket IDs (or ndjson ticket records) to stdout, one per
+/// line, deduplicated (or, with `--output-format json`, an array of ticket
+/// records at the end). Each PR's tickets are output as soon as that PR is
+/// processed, in order of discovery. With `--dry-run`, nothing is printed.
 ///
 /// # Process
-/// For each PR number:
-/// 1. Fetch PR data from GitHub (title, body, comments, commits)
-/// 2. Search all text content for Linear ticket references
-/// 3. Deduplicate and output
+/// For each PR identifier:
+/// 1. Fetch PR data from GitHub (merge status, title, body, comments,
+///    commits)
+/// 2. Skip the PR if it isn't merged (unless `--allow-unmerged`), was
+///    merged outside the `--merged-after`/`--merged-before` window, or is
+///    outside the `--pr-label`/`--path-filter` scope
+/// 3. Search all text content for Linear ticket references
+/// 4. Deduplicate and output
 ///
 /// # Errors
 /// Returns an error if:
 /// - Input sources cannot be read
-/// - GitHub CLI fails to fetch PR data
-/// - PR number is invalid or inaccessible
-pub fn run(config: &Config) -> Result<(), String> {
+/// - GitHub CLI fails to fetch PR data (with `--dry-run`, once every PR has
+///   been tried)
+/// - PR identifier is invalid or inaccessible
+pub fn run(config: &Config) -> Result<(), Error> {
     // Track seen ticket IDs to avoid duplicates across all PRs
     let mut seen_tickets = HashSet::new();
     let mut any_output = false;
+    let mut skipped = SkippedPrs::default();
+    let mut fetched = 0;
+    let mut mapping = Vec::new();
+    let mut output =
+        RecordWriter::new(config.output_format, config.output.clone());
+    let mut subject_seen = false;
+    let cache = config.cache()?;
+    let limiter = config.rate_limiter.clone();
+    let github = config.github()?;
+    let window = MergeWindow::from_config(config)?;
+    let pattern = ticket_pattern(config);
+    let org = match config.tracker {
+        Tracker::Linear => config.linear_org(),
+        Tracker::Jira => None,
+    };
+    let org = org.as_deref();
+
+    log!("reading PR identifiers from input");
+    let no_input = NoInputTimeout {
+        secs: config.stdin_timeout_secs,
+        expected: "PR numbers",
+    };
+    // With --batch-prs, all input is read and fetched up front.
+    let prs = match config.pr_fetches {
+        PrFetches::Single => None,
+        PrFetches::Batched => Some(read_all_prs(config, no_input)?),
+    };
+    let prefetched = prs.as_deref().map_or_else(HashMap::new, |prs| {
+        prefetch(config, prs, cache.as_ref(), limiter.as_ref(), &github)
+    });
 
-    // Process PR numbers as they arrive from input (streaming).
-    log!("reading PR numbers from input");
-    utils::for_each_input_line(&config.input_sources, |line| {
-        let pr_num = line.trim();
-        if pr_num.is_empty() {
+    let fetch = Fetch {
+        cache: cache.as_ref(),
+        prefetched: &prefetched,
+        limiter: limiter.as_ref(),
+        github: &github,
+    };
+    let mut process = |InputPr {
+                           pr,
+                           fetched: result,
+                       }| {
+        subject_seen |= explains_pr(config, &pr);
+        let Some(pull_request) = fetch_pr(config, &pr, result, &mut skipped)?
+        else {
             return Ok(());
-        }
+        };
+        fetched += 1;
 
-        log!("processing PR #{pr_num}");
+        let check = check_merge(config, &pr, &pull_request, &window)?;
+        subject_seen |= explain_skipped_pr(config, &pr, &pull_request, &check)?;
+        if skipped.excluded(&pr, &pull_request, check) {
+            return Ok(());
+        }
 
-        // Fetch all text content from the PR
-        let pr_text = get_pr_text(pr_num)?;
+        let mut references = find_references(
+            &pull_request.sources,
+            &pattern,
+            &config.prefix_map,
+        )?;
+        let origin = Origin::Pr(pr.clone());
+        subject_seen |= exclude_references(config, &origin, &mut references)?;
+        subject_seen |=
+            other_org_references(config, &origin, org, &mut references);
+        subject_seen |=
+            explain_references(config, &pr, &references, &seen_tickets);
+        if references.is_empty() && config.unlinked_prs == UnlinkedPrs::Warn {
+            let title = pull_request.title();
+            log!(
+                Warn,
+                "warning: PR {pr} ({title}) references no Linear ticket"
+            );
+            skipped.unlinked.push(format!("{pr} ({title})"));
+        }
+        if config.mapping_file.is_some() {
+            mapping.push(mapping_entry(&pr, pull_request.merged, &references));
+        }
 
         // Find and output Linear ticket IDs immediately
-        find_and_output_tickets(&pr_text, &mut seen_tickets, &mut any_output)?;
+        for record in find_tickets(&origin, &references, &mut seen_tickets) {
+            if config.dry_run {
+                continue;
+            }
+            output.write(Some(&record.ticket), || &record)?;
+            any_output = true;
+        }
 
         Ok(())
-    })?;
+    };
+    for_each_pr(config, prs, &fetch, no_input, &mut process)?;
+    if !config.dry_run {
+        output.finish();
+    }
+
+    skipped.report();
+
+    if let Some(ref path) = config.mapping_file {
+        write_mapping(path, &mapping)?;
+        log!("wrote ticket mapping to {path}");
+    }
+
+    explain_missing(config, subject_seen, fetched);
+
+    if config.dry_run {
+        skipped.check_dry_run(fetched, seen_tickets.len())?;
+    }
 
     log!("done");
     if !any_output {
@@ -79,21 +339,715 @@ pub fn run(config: &Config) -> Result<(), String> {
     Ok(())
 }
 
-/// Fetches all relevant text content from a GitHub PR.
+/// The tickets referenced by PR `pr`, as the extract-tickets mode finds
+/// them, in order of discovery: none if the PR is in `--ignore-prs`, isn't
+/// merged (unless `--allow-unmerged`), was merged outside the
+/// `--merged-after`/`--merged-before` window or is outside the
+/// `--pr-label`/`--path-filter` scope.
+///
+/// # Errors
+/// Returns an error if the PR can't be fetched or its merge time can't be
+/// parsed.
+pub fn tickets_for_pr(
+    config: &Config,
+    pr: &PrRef,
+) -> Result<Vec<TicketRecord>, Error> {
+    if config.ignore_list.ignores_pr(pr) {
+        return Ok(Vec::new());
+    }
+    let cache = config.cache()?;
+    let fetch = Fetch {
+        cache: cache.as_ref(),
+        prefetched: &HashMap::new(),
+        limiter: config.rate_limiter.as_ref(),
+        github: &config.github()?,
+    };
+    let pull_request = get_pr(config, pr, &fetch)?;
+    let window = MergeWindow::from_config(config)?;
+    let check = check_merge(config, pr, &pull_request, &window)?;
+    if !matches!(check, MergeCheck::Include) {
+        return Ok(Vec::new());
+    }
+    let org = match config.tracker {
+        Tracker::Linear => config.linear_org(),
+        Tracker::Jira => None,
+    };
+    let mut references = find_references(
+        &pull_request.sources,
+        &ticket_pattern(config),
+        &config.prefix_map,
+    )?;
+    let origin = Origin::Pr(pr.clone());
+    exclude_references(config, &origin, &mut references)?;
+    other_org_references(config, &origin, org.as_deref(), &mut references);
+    Ok(find_tickets(&origin, &references, &mut HashSet::new()))
+}
+
+/// The tickets referenced by the `message` of commit `sha`, as the
+/// extract-commits mode finds them, in order of discovery: in its headline
+/// (`commit_headline`), the rest of it (`commit_body`) and its ticket links
+/// (`commit_trailer`, see [`trailer_text`]), searched like a PR's commits.
+///
+/// # Errors
+/// Returns an error if grep fails.
+pub fn tickets_for_commit(
+    config: &Config,
+    sha: &str,
+    message: &str,
+) -> Result<Vec<TicketRecord>, Error> {
+    let (headline, body) = message.split_once('\n').unwrap_or((message, ""));
+    let sources = [
+        TextSource {
+            name: "commit_headline",
+            confidence: Confidence::Medium,
+            text: headline.to_string(),
+        },
+        TextSource {
+            name: "commit_body",
+            confidence: Confidence::Medium,
+            text: body.trim().to_string(),
+        },
+        TextSource {
+            name: "commit_trailer",
+            confidence: Confidence::High,
+            text: trailer_text(&[message], &config.trailer_keys),
+        },
+    ];
+    let org = match config.tracker {
+        Tracker::Linear => config.linear_org(),
+        Tracker::Jira => None,
+    };
+    let mut references =
+        find_references(&sources, &ticket_pattern(config), &config.prefix_map)?;
+    let origin = Origin::Commit(sha.to_string());
+    exclude_references(config, &origin, &mut references)?;
+    other_org_references(config, &origin, org.as_deref(), &mut references);
+    Ok(find_tickets(&origin, &references, &mut HashSet::new()))
+}
+
+/// The ticket ID pattern of the tracker: [`TICKET_PATTERN`] or the
+/// `--ticket-pattern`, optionally preceded by a Linear issue URL
+/// ([`LINEAR_URL_PATTERN`]), or [`JIRA_TICKET_PATTERN`].
+fn ticket_pattern(config: &Config) -> String {
+    match config.tracker {
+        Tracker::Linear => {
+            let pattern =
+                config.ticket_pattern.as_deref().unwrap_or(TICKET_PATTERN);
+            format!("({LINEAR_URL_PATTERN})?({pattern})")
+        }
+        Tracker::Jira => JIRA_TICKET_PATTERN.to_string(),
+    }
+}
+
+/// Where [`get_pr`] finds a PR's response before fetching it.
+struct Fetch<'a> {
+    /// Responses cached in `--cache-dir`
+    cache: Option<&'a Cache>,
+    /// Responses fetched in bulk, by [`cache_key`] (`--batch-prs`)
+    prefetched: &'a HashMap<String, String>,
+    limiter: Option<&'a RateLimiter>,
+    /// How GitHub PRs are fetched (`--github-api`)
+    github: &'a GitHub,
+}
+
+/// An input PR and the result of fetching it with [`get_pr`].
+struct InputPr {
+    pr: PrRef,
+    fetched: Result<PullRequest, Error>,
+}
+
+/// Input lines read as PRs, counted, and the first that isn't one.
+///
+/// A line that isn't a PR identifier (or PR record) is logged and skipped,
+/// so one malformed line doesn't end the run; only input without a single
+/// PR fails (see [`PrInputs::finish`]).
+#[derive(Default)]
+struct PrInputs {
+    prs: usize,
+    malformed: Option<Error>,
+}
+
+impl PrInputs {
+    /// The PR of `input`, or `None` for a blank line or one that isn't a PR,
+    /// which is logged.
+    fn read(&mut self, input: &Input) -> Option<PrRef> {
+        match input.pr() {
+            Ok(pr) => {
+                self.prs += usize::from(pr.is_some());
+                pr
+            }
+            Err(e) => {
+                log!(Warn, "warning: skipping input: {e}");
+                self.malformed.get_or_insert(e);
+                None
+            }
+        }
+    }
+
+    /// Checks that some input was a PR, once input has ended.
+    ///
+    /// # Errors
+    /// Returns the error of the first input that isn't a PR if none is.
+    fn finish(self) -> Result<(), Error> {
+        match self.malformed {
+            Some(e) if self.prs == 0 => {
+                Err(e.context("No input line is a PR identifier"))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Hands every input PR to `process` once it is fetched, in input order:
+/// the PRs read up front (`--batch-prs`), or else those of the input as it
+/// streams in, fetched on worker threads with `--concurrency`. Inputs that
+/// aren't PRs are skipped (see [`PrInputs`]).
+///
+/// # Errors
+/// Returns the first error of reading input, [`read_pr`] or `process`, or
+/// an error if no input is a PR.
+fn for_each_pr(
+    config: &Config,
+    prs: Option<Vec<PrRef>>,
+    fetch: &Fetch<'_>,
+    no_input: NoInputTimeout<'_>,
+    process: &mut dyn FnMut(InputPr) -> Result<(), Error>,
+) -> Result<(), Error> {
+    let mut process = |input: InputPr| {
+        utils::with_log_subject(Subject::Pr(input.pr.clone()), || {
+            process(input)
+        })
+    };
+    let mut process_pr =
+        |pr: PrRef| read_pr(config, pr, fetch).map_or(Ok(()), &mut process);
+    match (prs, config.concurrency) {
+        (Some(prs), _) => prs.into_iter().try_for_each(process_pr),
+        (None, Some(workers)) if workers > 1 => {
+            fetch_concurrently(config, workers, fetch, no_input, &mut process)
+        }
+        // Process PR numbers as they arrive from input (streaming).
+        (None, _) => {
+            let mut inputs = PrInputs::default();
+            utils::for_each_input(&config.input_sources, no_input, |input| {
+                inputs.read(&input).map_or(Ok(()), &mut process_pr)
+            })?;
+            inputs.finish()
+        }
+    }
+}
+
+/// Fetches an input PR, or `None` for an `--ignore-prs` PR.
+///
+/// A failed fetch is returned along with the PR, for [`fetch_pr`].
+fn read_pr(config: &Config, pr: PrRef, fetch: &Fetch<'_>) -> Option<InputPr> {
+    if config.ignore_list.ignores_pr(&pr) {
+        log!("skipping PR {pr} (--ignore-prs)");
+        return None;
+    }
+    // Fetch merge status and all text content from the PR
+    let fetched = utils::with_log_subject(Subject::Pr(pr.clone()), || {
+        log!("processing PR {pr}");
+        get_pr(config, &pr, fetch)
+    });
+    Some(InputPr { pr, fetched })
+}
+
+/// An input PR fetched on a worker thread (`--concurrency`).
+struct FetchedPr {
+    read: Option<InputPr>,
+    /// Lines logged while fetching the PR
+    logs: Vec<String>,
+}
+
+/// Fetches the input PRs on `workers` threads as they arrive
+/// (`--concurrency`), with [`read_pr`].
+///
+/// Only fetching runs concurrently. Each PR is passed to `process` on this
+/// thread in input order, its log lines printed along with it, as soon as
+/// every earlier PR is done, so output (and ticket deduplication) doesn't
+/// depend on timing.
+///
+/// # Errors
+/// Returns the first error of reading input or `process`, or an error if no
+/// input is a PR. Input after such an error is no longer fetched, and PRs
+/// fetched already aren't processed.
+fn fetch_concurrently(
+    config: &Config,
+    workers: usize,
+    fetch: &Fetch<'_>,
+    no_input: NoInputTimeout<'_>,
+    process: &mut dyn FnMut(InputPr) -> Result<(), Error>,
+) -> Result<(), Error> {
+    let (job_tx, job_rx) = mpsc::channel::<(usize, PrRef)>();
+    let job_rx = Mutex::new(job_rx);
+    let (done_tx, done_rx) = mpsc::channel::<(usize, FetchedPr)>();
+    let failed = AtomicBool::new(false);
+    let log_settings = utils::log_settings();
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            let done_tx = done_tx.clone();
+            let (job_rx, failed) = (&job_rx, &failed);
+            scope.spawn(move || {
+                utils::with_log_settings(log_settings, || {
+                    while let Some((seq, pr)) = next_pr(job_rx) {
+                        let (read, logs) = if failed.load(Ordering::Relaxed) {
+                            (None, Vec::new())
+                        } else {
+                            utils::capture_logs(|| read_pr(config, pr, fetch))
+                        };
+                        let fetched = FetchedPr { read, logs };
+                        if done_tx.send((seq, fetched)).is_err() {
+                            break;
+                        }
+                    }
+                });
+            });
+        }
+        drop(done_tx);
+
+        // Dropping `job_tx` once input ends lets idle workers exit.
+        let failed = &failed;
+        let reader = scope.spawn(move || {
+            let mut seq = 0;
+            let mut inputs = PrInputs::default();
+            utils::with_log_settings(log_settings, || {
+                utils::for_each_input(
+                    &config.input_sources,
+                    no_input,
+                    |input| {
+                        // Sending only fails if every worker is gone, and
+                        // then nothing more can be fetched anyway.
+                        if !failed.load(Ordering::Relaxed)
+                            && let Some(pr) = inputs.read(&input)
+                        {
+                            let _ = job_tx.send((seq, pr));
+                            seq += 1;
+                        }
+                        Ok(())
+                    },
+                )
+            })
+            .and_then(|()| inputs.finish())
+        });
+
+        let mut result = Ok(());
+        let mut pending = BTreeMap::new();
+        let mut next = 0;
+        for (seq, fetched) in done_rx {
+            pending.insert(seq, fetched);
+            while let Some(fetched) = pending.remove(&next) {
+                next += 1;
+                if result.is_err() {
+                    continue;
+                }
+                utils::print_logs(&fetched.logs);
+                result = fetched.read.map_or(Ok(()), &mut *process);
+                if result.is_err() {
+                    failed.store(true, Ordering::Relaxed);
+                }
+            }
+        }
+        let read = reader.join().unwrap_or_else(|_| {
+            Err(Error::Other("Reading input failed".to_string()))
+        });
+        result.and(read)
+    })
+}
+
+/// Takes the next input PR, or `None` once input has ended.
+fn next_pr(
+    prs: &Mutex<mpsc::Receiver<(usize, PrRef)>>,
+) -> Option<(usize, PrRef)> {
+    prs.lock().ok()?.recv().ok()
+}
+
+/// The PR fetched by [`get_pr`] (`fetched`), or `None` if the `--api-budget` ran out
+/// before it could be, or fetching it failed in a `--dry-run`; the PR is
+/// then added to `skipped`. Later PRs may still be cached, so the caller
+/// keeps going.
+///
+/// # Errors
+/// Returns an error if fetching the PR failed for any other reason.
+fn fetch_pr(
+    config: &Config,
+    pr: &PrRef,
+    fetched: Result<PullRequest, Error>,
+    skipped: &mut SkippedPrs,
+) -> Result<Option<PullRequest>, Error> {
+    let budget = config.api_budget.as_ref();
+    let skip = match fetched {
+        Ok(pull_request) => return Ok(Some(pull_request)),
+        Err(_) if budget.is_some_and(ApiBudget::exhausted) => {
+            skipped.unfetched.push(pr.to_string());
+            "the --api-budget was used up"
+        }
+        Err(e) if config.dry_run => {
+            log!(Warn, "{e}");
+            skipped.inaccessible.push(pr.to_string());
+            "fetching it failed"
+        }
+        Err(e) => return Err(e),
+    };
+    if explains_pr(config, pr) {
+        explain!(
+            "PR {pr} was not fetched ({skip}), so its tickets are missing"
+        );
+    }
+    Ok(None)
+}
+
+/// PRs whose tickets weren't extracted, listed at the end of the run.
+#[derive(Default)]
+struct SkippedPrs {
+    unmerged: Vec<String>,
+    /// Merged outside the `--merged-after`/`--merged-before` window
+    outside_window: Vec<String>,
+    /// Outside the `--pr-label`/`--path-filter` scope
+    out_of_scope: Vec<String>,
+    /// Processed, but referencing no ticket (`--warn-unlinked-prs`)
+    unlinked: Vec<String>,
+    /// Not fetched once the `--api-budget` was used up
+    unfetched: Vec<String>,
+    /// Failed to fetch (`--dry-run` only; otherwise the run stops)
+    inaccessible: Vec<String>,
+}
+
+impl SkippedPrs {
+    /// Records a PR that [`check_merge`] excludes, returning whether it
+    /// does.
+    fn excluded(
+        &mut self,
+        pr: &PrRef,
+        pull_request: &PullRequest,
+        check: MergeCheck,
+    ) -> bool {
+        match check {
+            MergeCheck::Include => return false,
+            MergeCheck::Unmerged(status) => {
+                self.unmerged.push(format!("{pr} ({status})"));
+            }
+            MergeCheck::OutsideWindow => {
+                let merged_at = &pull_request.merged_at;
+                self.outside_window
+                    .push(format!("{pr} (merged {merged_at})"));
+            }
+            MergeCheck::OutOfScope(reason) => {
+                self.out_of_scope.push(format!("{pr} ({reason})"));
+            }
+        }
+        true
+    }
+
+    fn report(&self) {
+        report_prs(&self.unmerged, |count| {
+            format!(
+                "skipped {count} unmerged PR(s) (use --allow-unmerged to include them):"
+            )
+        });
+        report_prs(&self.outside_window, |count| {
+            format!(
+                "skipped {count} PR(s) merged outside the --merged-after/--merged-before window:"
+            )
+        });
+        report_prs(&self.out_of_scope, |count| {
+            format!(
+                "skipped {count} PR(s) outside the --pr-label/--path-filter scope:"
+            )
+        });
+        report_prs(&self.unlinked, |count| {
+            format!("{count} PR(s) without a Linear ticket:")
+        });
+        report_prs(&self.unfetched, |count| {
+            format!(
+                "skipped {count} PR(s) after the --api-budget was used up; their tickets are missing:"
+            )
+        });
+        report_prs(&self.inaccessible, |count| {
+            format!("{count} PR(s) could not be fetched:")
+        });
+    }
+
+    /// Logs the counts of a `--dry-run`.
+    ///
+    /// # Errors
+    /// Returns an error if any PR couldn't be fetched.
+    fn check_dry_run(
+        &self,
+        fetched: usize,
+        tickets: usize,
+    ) -> Result<(), Error> {
+        log!(
+            "dry run: fetched {fetched} PR(s) referencing {tickets} ticket(s), none output"
+        );
+        if self.inaccessible.is_empty() {
+            return Ok(());
+        }
+        Err(Error::GitHub(format!(
+            "{} PR(s) could not be fetched",
+            self.inaccessible.len()
+        )))
+    }
+}
+
+/// Logs an end-of-run list of PRs under a header built from their count, if
+/// there are any.
+fn report_prs(prs: &[String], header: impl Fn(usize) -> String) {
+    if prs.is_empty() {
+        return;
+    }
+    let header = header(prs.len());
+    log!("{header}");
+    for pr in prs {
+        log!("  {pr}");
+    }
+}
+
+/// The `--merged-after` / `--merged-before` window, in Unix seconds.
+struct MergeWindow {
+    after: Option<i64>,
+    before: Option<i64>,
+}
+
+impl MergeWindow {
+    /// # Errors
+    /// Returns an error if a bound isn't a valid timestamp.
+    fn from_config(config: &Config) -> Result<Self, Error> {
+        let parse = |value: &Option<String>| {
+            value.as_deref().map(utils::parse_timestamp).transpose()
+        };
+        Ok(Self {
+            after: parse(&config.merged_after)?,
+            before: parse(&config.merged_before)?,
+        })
+    }
+
+    fn is_unbounded(&self) -> bool {
+        self.after.is_none() && self.before.is_none()
+    }
+
+    /// Whether a merge at `merged_at` falls within the window (bounds
+    /// included).
+    fn contains(&self, merged_at: i64) -> bool {
+        self.after.is_none_or(|after| merged_at >= after)
+            && self.before.is_none_or(|before| merged_at <= before)
+    }
+}
+
+/// Whether a PR's tickets are extracted, judged by its merge status and
+/// scope.
+enum MergeCheck {
+    Include,
+    /// Not merged, with `--require-merged` (why it doesn't count as merged)
+    Unmerged(String),
+    /// Merged outside the `--merged-after`/`--merged-before` window
+    OutsideWindow,
+    /// Outside the `--pr-label`/`--path-filter` scope (why)
+    OutOfScope(String),
+}
+
+/// Checks a PR's merge status and time against the `--require-merged`
+/// policy and `window`, then its `--pr-label`/`--path-filter` scope,
+/// logging PRs that are skipped or only included because of
+/// `--allow-unmerged`.
+///
+/// # Errors
+/// Returns an error if the PR's `mergedAt` can't be parsed.
+fn check_merge(
+    config: &Config,
+    pr: &PrRef,
+    pull_request: &PullRequest,
+    window: &MergeWindow,
+) -> Result<MergeCheck, Error> {
+    if let Some(status) = pull_request.unmerged_status() {
+        // Unmerged PRs have no merge time to check against the window.
+        match config.merge_policy {
+            MergePolicy::RequireMerged => {
+                log!("PR {pr} is not merged ({status}), skipping");
+                return Ok(MergeCheck::Unmerged(status));
+            }
+            MergePolicy::AllowUnmerged => {
+                log!("PR {pr} is not merged ({status}), including anyway");
+            }
+        }
+    } else if !window.is_unbounded() {
+        let merged_at = &pull_request.merged_at;
+        let timestamp = utils::parse_timestamp(merged_at)
+            .map_err(|e| e.context(format!("Invalid mergedAt of PR {pr}")))?;
+        if !window.contains(timestamp) {
+            log!(
+                "PR {pr} was merged at {merged_at}, outside the merge window, skipping"
+            );
+            return Ok(MergeCheck::OutsideWindow);
+        }
+    }
+
+    if let Some(reason) = config.pr_scope.excludes(&pull_request.scope) {
+        log!(
+            "PR {pr} is outside the --pr-label/--path-filter scope ({reason}), skipping"
+        );
+        return Ok(MergeCheck::OutOfScope(reason));
+    }
+    Ok(MergeCheck::Include)
+}
+
+/// Whether `pr` is the `--explain` subject.
+fn explains_pr(config: &Config, pr: &PrRef) -> bool {
+    config
+        .explain
+        .as_ref()
+        .is_some_and(|subject| subject.is_pr(pr))
+}
+
+/// Whether `origin` is a PR that is the `--explain` subject.
+fn explains_origin(config: &Config, origin: &Origin) -> bool {
+    matches!(origin, Origin::Pr(pr) if explains_pr(config, pr))
+}
+
+/// With `--explain`, logs why a PR [`check_merge`] excludes is skipped, if
+/// it is the subject or references the subject ticket.
+///
+/// # Returns
+/// Whether the subject came up.
+///
+/// # Errors
+/// Returns an error if grep fails.
+fn explain_skipped_pr(
+    config: &Config,
+    pr: &PrRef,
+    pull_request: &PullRequest,
+    check: &MergeCheck,
+) -> Result<bool, Error> {
+    let Some(ref subject) = config.explain else {
+        return Ok(false);
+    };
+    let reason = match check {
+        MergeCheck::Include => return Ok(false),
+        MergeCheck::Unmerged(status) => {
+            format!("it is not merged ({status}; see --allow-unmerged)")
+        }
+        MergeCheck::OutsideWindow => format!(
+            "it was merged at {}, outside the --merged-after/--merged-before window",
+            pull_request.merged_at
+        ),
+        MergeCheck::OutOfScope(reason) => format!(
+            "it is outside the --pr-label/--path-filter scope ({reason})"
+        ),
+    };
+    match subject {
+        Subject::Pr(_) if subject.is_pr(pr) => {
+            explain!("PR {pr} is skipped: {reason}");
+            Ok(true)
+        }
+        Subject::Pr(_) => Ok(false),
+        Subject::Ticket(ticket) => {
+            let references = find_references(
+                &pull_request.sources,
+                &ticket_pattern(config),
+                &config.prefix_map,
+            )?;
+            if !references.iter().any(|r| r.ticket == *ticket) {
+                return Ok(false);
+            }
+            explain!(
+                "{ticket} is referenced by PR {pr}, but the PR is skipped: {reason}"
+            );
+            Ok(true)
+        }
+    }
+}
+
+/// With `--explain`, logs where a PR's remaining references to the subject
+/// were found and whether they are output: every reference of the subject
+/// PR, or the subject ticket in any PR. Must be called before the PR's
+/// tickets are marked `seen`.
+///
+/// # Returns
+/// Whether the subject came up.
+fn explain_references(
+    config: &Config,
+    pr: &PrRef,
+    references: &[Reference<'_>],
+    seen: &HashSet<String>,
+) -> bool {
+    let Some(ref subject) = config.explain else {
+        return false;
+    };
+    let whole_pr = subject.is_pr(pr);
+    if whole_pr && references.is_empty() {
+        explain!("PR {pr} references no ticket");
+    }
+    let mut explained = whole_pr;
+    for reference in references
+        .iter()
+        .filter(|reference| whole_pr || subject.is_ticket(&reference.ticket))
+    {
+        explained = true;
+        let ticket = &reference.ticket;
+        let sources: Vec<&str> =
+            reference.sources.iter().map(|source| source.name).collect();
+        let sources = sources.join(", ");
+        let fate = if seen.contains(ticket) {
+            "already passed on for an earlier PR"
+        } else if config.dry_run {
+            "not passed on (--dry-run)"
+        } else {
+            "passed on to update-tickets"
+        };
+        explain!("PR {pr} references {ticket} (in {sources}): {fate}");
+    }
+    explained
+}
+
+/// With `--explain`, logs that the subject never came up in this run, unless
+/// it was `seen`.
+fn explain_missing(config: &Config, seen: bool, fetched: usize) {
+    if seen {
+        return;
+    }
+    match config.explain {
+        Some(Subject::Pr(ref pr)) if config.ignore_list.ignores_pr(pr) => {
+            explain!("PR {pr} is in --ignore-prs, so it is never fetched");
+        }
+        Some(Subject::Pr(ref pr)) => {
+            explain!("PR {pr} is not in the input (see parse-notes)");
+        }
+        Some(Subject::Ticket(ref ticket)) => {
+            explain!(
+                "{ticket} is not referenced by any of the {fetched} PR(s) fetched"
+            );
+        }
+        None => {}
+    }
+}
+
+/// Fetches the merge status and all relevant text content of a GitHub PR
+/// (or, with `--forge gitlab`, a GitLab merge request).
 ///
 /// # Arguments
-/// * `pr_num` - The pull request number to fetch
+/// * `config` - The `--github-host` override, `--max-comments` limit,
+///   `--api-budget`, stack detection, `--trailer-keys`, forge and
+///   `--pr-label`/`--path-filter` scope
+/// * `pr` - The pull request to fetch (`--repo` is passed to `gh` for
+///   repo-qualified references)
 ///
 /// # Returns
-/// A string containing all searchable text from the PR, with sections
-/// separated by double newlines.
+/// The PR's state, draft flag and merge status, plus its searchable text,
+/// one entry per text source (in the order below), each tagged with its name
+/// and match confidence, and the labels and changed files the
+/// `--pr-label`/`--path-filter` scope needs (see
+/// [`crate::scope::PrScope::fetch`]).
 ///
 /// # Text Sources
-/// - PR title
-/// - PR body
-/// - All comment bodies
-/// - All commit message headlines
-/// - All commit message bodies
+/// - PR title (high confidence)
+/// - PR body (high)
+/// - All comment bodies (low), or the first `--max-comments` of them
+/// - All commit message headlines (medium)
+/// - All commit message bodies (medium)
+/// - The ticket links of the commit messages (high; see [`trailer_text`])
+///
+/// With [`StackedPrs::Detect`], text of sibling PRs is left out (see
+/// [`text_sources`]).
 ///
 /// # Errors
 /// Returns an error if:
@@ -103,91 +1057,900 @@ pub fn run(config: &Config) -> Result<(), String> {
 /// - JSON parsing fails
 ///
 /// # Implementation
-/// Uses `gh pr view <num> --json` to fetch structured data, then
-/// uses `jq` to extract text fields.
-fn get_pr_text(pr_num: &str) -> Result<String, String> {
-    // Fetch PR data as JSON
-    let output = Command::new("gh")
-        .args([
-            "pr",
-            "view",
-            pr_num,
-            "--json",
-            "title,body,comments,commits",
-        ])
-        .output()
-        .map_err(|e| format!("Failed to execute gh command: {e}"))?;
+/// Uses `gh pr view <num> --json` to fetch structured data (or the response
+/// cached in `--cache-dir`, or prefetched with `--batch-prs`; see [`Fetch`]),
+/// then parses out the text fields. Comment threads
+/// longer than `gh pr view` returns are completed with [`complete_comments`]
+/// first, unless `--max-comments` doesn't reach past them. Each `gh` call
+/// claims one request from the `--api-budget`. A GitLab merge request is
+/// fetched complete, in the same shape, by [`gitlab::fetch_mr_json`].
+fn get_pr(
+    config: &Config,
+    pr: &PrRef,
+    fetch: &Fetch<'_>,
+) -> Result<PullRequest, Error> {
+    let Fetch {
+        cache,
+        prefetched,
+        limiter,
+        github,
+    } = *fetch;
+    let budget = config.api_budget.as_ref();
+    let max_comments = config.max_comments;
+    let namespace = config.forge.name();
+    let cache_key = cache_key(pr);
+    let cached = cache.and_then(|cache| cache.get(namespace, &cache_key));
+    let fresh = cached.is_none();
+    let json_output = if let Some(cached) = cached {
+        log!(Verbose, "using cached PR {pr}");
+        cached
+    } else if let Some(response) = prefetched.get(&cache_key) {
+        response.clone()
+    } else if config.forge == Forge::GitLab {
+        gitlab::fetch_mr_json(pr, limiter, budget, max_comments)?
+    } else {
+        fetch_pr_json(pr, github, limiter, budget, max_comments)?
+    };
+
+    let mut response = json::parse(&json_output)
+        .map_err(|e| e.context(format!("Invalid response for PR {pr}")))?;
+    let completed = config.forge == Forge::GitHub
+        && complete_comments(
+            pr,
+            github,
+            limiter,
+            budget,
+            max_comments,
+            &mut response,
+        )?;
+    if let Some(cache) = cache {
+        if completed {
+            cache.put(namespace, &cache_key, &response.to_string())?;
+        } else if fresh {
+            cache.put(namespace, &cache_key, &json_output)?;
+        }
+    }
+
+    let state = response.get("state").str_or_empty().to_lowercase();
+    let is_draft = response.get("isDraft").as_bool() == Some(true);
+    let merged_at = response.get("mergedAt").str_or_empty().to_string();
+
+    let comments = response.get("comments").items();
+    let scanned =
+        max_comments.map_or(comments.len(), |max| max.min(comments.len()));
+    if scanned < comments.len() {
+        log!(
+            "scanning only the first {scanned} comments of PR {pr} (--max-comments)"
+        );
+    }
+
+    let sources = text_sources(
+        pr,
+        &response,
+        &comments[..scanned],
+        config.stacked_prs,
+        &config.trailer_keys,
+    );
+    let scope = config.pr_scope.fetch(pr, github, cache, limiter, budget)?;
+
+    Ok(PullRequest {
+        state,
+        is_draft,
+        merged: !merged_at.is_empty(),
+        merged_at,
+        sources,
+        scope,
+    })
+}
+
+/// Key of a PR's response in the cache and among prefetched responses:
+/// `123` or `owner/repo#123`.
+fn cache_key(pr: &PrRef) -> String {
+    match pr.repo {
+        Some(ref repo) => format!("{repo}#{}", pr.number),
+        None => pr.number.clone(),
+    }
+}
+
+/// Reads every input PR before any is processed (`--batch-prs`), skipping
+/// blank lines and inputs that aren't PRs (see [`PrInputs`]).
+///
+/// # Errors
+/// Returns an error if input sources cannot be read, or no input is a PR.
+fn read_all_prs(
+    config: &Config,
+    no_input: NoInputTimeout<'_>,
+) -> Result<Vec<PrRef>, Error> {
+    let mut prs = Vec::new();
+    let mut inputs = PrInputs::default();
+    utils::for_each_input(&config.input_sources, no_input, |input| {
+        prs.extend(inputs.read(&input));
+        Ok(())
+    })?;
+    inputs.finish()?;
+    Ok(prs)
+}
+
+/// With `--batch-prs`, fetches the PRs of `prs` that aren't cached with
+/// [`github::fetch_prs`], returning their responses by [`cache_key`].
+fn prefetch(
+    config: &Config,
+    prs: &[PrRef],
+    cache: Option<&Cache>,
+    limiter: Option<&RateLimiter>,
+    github: &GitHub,
+) -> HashMap<String, String> {
+    let namespace = config.forge.name();
+    let mut keys = HashSet::new();
+    let prs: Vec<PrRef> = prs
+        .iter()
+        .filter(|pr| !config.ignore_list.ignores_pr(pr))
+        .filter(|pr| {
+            let key = cache_key(pr);
+            cache.is_none_or(|cache| cache.get(namespace, &key).is_none())
+                && keys.insert(key)
+        })
+        .cloned()
+        .collect();
+    if prs.is_empty() {
+        return HashMap::new();
+    }
+    github::fetch_prs(
+        &prs,
+        github,
+        limiter,
+        config.api_budget.as_ref(),
+        config.max_comments,
+    )
+    .into_iter()
+    .map(|(pr, response)| (cache_key(&pr), response))
+    .collect()
+}
+
+/// Splits a PR response into its searchable text sources (see [`get_pr`]),
+/// scanning only `comments`.
+///
+/// With [`StackedPrs::Detect`], text that belongs to other PRs is left out
+/// first (see [`own_commits`], [`strip_stack_lines`] and
+/// [`is_stack_comment`]).
+fn text_sources(
+    pr: &PrRef,
+    response: &Value,
+    comments: &[Value],
+    stacked_prs: StackedPrs,
+    trailer_keys: &[String],
+) -> Vec<TextSource> {
+    let detect = stacked_prs == StackedPrs::Detect;
+    let mut body = response.get("body").str_or_empty().to_string();
+    let mut comments: Vec<&Value> = comments.iter().collect();
+    let mut commits: Vec<&Value> =
+        response.get("commits").items().iter().collect();
+    if detect {
+        body = strip_stack_lines(pr, &body);
+        comments.retain(|comment| {
+            let stack = is_stack_comment(comment.get("body").str_or_empty());
+            if stack {
+                log!(Verbose, "PR {pr}: skipping a Graphite stack comment");
+            }
+            !stack
+        });
+        commits = own_commits(pr, commits);
+    }
+
+    // Text of every element of a list, one per line. Missing fields
+    // contribute no text.
+    let list_text = |items: &[&Value], field: &str| {
+        items
+            .iter()
+            .filter_map(|item| item.get(field).as_str())
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    let source = |name, confidence, text| TextSource {
+        name,
+        confidence,
+        text,
+    };
+    let messages: Vec<&str> = commits
+        .iter()
+        .flat_map(|commit| {
+            [
+                commit.get("messageHeadline").str_or_empty(),
+                commit.get("messageBody").str_or_empty(),
+            ]
+        })
+        .collect();
+    vec![
+        source(
+            "title",
+            Confidence::High,
+            response.get("title").str_or_empty().to_string(),
+        ),
+        source("body", Confidence::High, body),
+        source("comment", Confidence::Low, list_text(&comments, "body")),
+        source(
+            "commit_headline",
+            Confidence::Medium,
+            list_text(&commits, "messageHeadline"),
+        ),
+        source(
+            "commit_body",
+            Confidence::Medium,
+            list_text(&commits, "messageBody"),
+        ),
+        source(
+            "commit_trailer",
+            Confidence::High,
+            trailer_text(&messages, trailer_keys),
+        ),
+    ]
+}
+
+/// The parts of commit `messages` that link tickets, one per line: the
+/// values of trailers whose key is one of `trailer_keys` (or
+/// [`DEFAULT_TRAILER_KEYS`]), e.g. `Linear: ABC-123`, and the words following
+/// one of [`MAGIC_WORDS`], e.g. `Fixes ABC-123, ABC-124 and ABC-125`.
+///
+/// Only those parts are searched for tickets, so `Fix the ENG-12 importer`
+/// links nothing.
+fn trailer_text(messages: &[&str], trailer_keys: &[String]) -> String {
+    let is_trailer_key = |key: &str| {
+        if trailer_keys.is_empty() {
+            DEFAULT_TRAILER_KEYS
+                .iter()
+                .any(|known| known.eq_ignore_ascii_case(key))
+        } else {
+            trailer_keys
+                .iter()
+                .any(|known| known.eq_ignore_ascii_case(key))
+        }
+    };
+    let mut links = Vec::new();
+    for line in messages.iter().flat_map(|message| message.lines()) {
+        if let Some((key, value)) = line.split_once(':')
+            && is_trailer_key(key.trim())
+        {
+            links.push(value.trim());
+            continue;
+        }
+        let mut words = line.split_whitespace().peekable();
+        while let Some(word) = words.next() {
+            let word = word.trim_matches(|c: char| !c.is_ascii_alphanumeric());
+            if !MAGIC_WORDS
+                .iter()
+                .any(|magic| magic.eq_ignore_ascii_case(word))
+            {
+                continue;
+            }
+            // The next word, and more after a comma or "and"
+            while let Some(linked) = words.next() {
+                links.push(linked);
+                let more = linked.ends_with(',')
+                    || words
+                        .next_if(|next| {
+                            next.eq_ignore_ascii_case("and") || *next == "&"
+                        })
+                        .is_some();
+                if !more {
+                    break;
+                }
+            }
+        }
+    }
+    links.join("\n")
+}
+
+/// The commits of `pr` that belong to it, logging the others: merge-queue
+/// commits (`gh-readonly-queue/...`), merges of another branch or PR, and
+/// commits squash-merged from another PR (`Title (#122)`), which a stacked
+/// PR picks up when it is rebased onto a landed sibling.
+fn own_commits<'a>(pr: &PrRef, commits: Vec<&'a Value>) -> Vec<&'a Value> {
+    commits
+        .into_iter()
+        .filter(|commit| {
+            let headline = commit.get("messageHeadline").str_or_empty();
+            let Some(reason) = foreign_commit(&pr.number, headline) else {
+                return true;
+            };
+            log!(Verbose, "PR {pr}: skipping {reason}: {headline}");
+            false
+        })
+        .collect()
+}
+
+/// Why a commit with this headline doesn't belong to PR `number`, if it
+/// doesn't.
+fn foreign_commit(number: &str, headline: &str) -> Option<String> {
+    if headline.contains("gh-readonly-queue/") {
+        return Some("merge-queue commit".to_string());
+    }
+    if let Some(rest) = headline.strip_prefix("Merge pull request #") {
+        let merged = leading_digits(rest);
+        if !merged.is_empty() && merged != number {
+            return Some(format!("merge of PR #{merged}"));
+        }
+    }
+    if headline.starts_with("Merge branch ")
+        || headline.starts_with("Merge remote-tracking branch ")
+    {
+        return Some("merge of another branch".to_string());
+    }
+    // Squash merges are titled `Title (#123)`.
+    let squashed = headline
+        .trim_end()
+        .strip_suffix(')')
+        .and_then(|rest| rest.rsplit_once("(#"))
+        .map(|(_, digits)| digits)
+        .filter(|digits| {
+            !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+        });
+    match squashed {
+        Some(other) if other != number => {
+            Some(format!("commit of PR #{other}"))
+        }
+        _ => None,
+    }
+}
+
+/// Removes the lines of a PR body that list other PRs of its stack, as
+/// Graphite and ghstack write them (`* **#122**`, `* __->__ #123`,
+/// `- https://github.com/acme/app/pull/122`): list items that start with a
+/// reference to another PR.
+fn strip_stack_lines(pr: &PrRef, body: &str) -> String {
+    let mut skipped = 0;
+    let kept: Vec<&str> = body
+        .lines()
+        .filter(|line| {
+            let stack = is_stack_line(&pr.number, line);
+            skipped += usize::from(stack);
+            !stack
+        })
+        .collect();
+    if skipped > 0 {
+        log!(
+            Verbose,
+            "PR {pr}: skipping {skipped} stack line(s) of the body"
+        );
+    }
+    kept.join("\n")
+}
+
+/// Whether `line` is a list item starting with a reference to a PR other
+/// than `number`.
+fn is_stack_line(number: &str, line: &str) -> bool {
+    let Some(item) = line.trim_start().strip_prefix(['-', '*', '+']) else {
+        return false;
+    };
+    let item = item.trim_start_matches([' ', '*', '_', '-', '>', '[', '`']);
+    let referenced = if let Some(rest) = item.strip_prefix('#') {
+        leading_digits(rest)
+    } else if item.starts_with("https://")
+        && let Some((_, rest)) = item.split_once("/pull/")
+    {
+        leading_digits(rest)
+    } else {
+        return false;
+    };
+    !referenced.is_empty() && referenced != number
+}
+
+/// Whether a comment is Graphite's stack listing.
+fn is_stack_comment(body: &str) -> bool {
+    body.contains("managed by Graphite") || body.contains("app.graphite.dev")
+}
+
+fn leading_digits(text: &str) -> &str {
+    let end = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    &text[..end]
+}
+
+/// Fetches a PR's `gh pr view --json` response (with `--github-api`, one
+/// built from the API; see [`crate::github_api::GithubApi::pr_json`]). With
+/// `--max-comments 0`, comments aren't requested at all.
+///
+/// # Errors
+/// Returns an error if the budget is used up, `gh` (or the API) fails or
+/// the response isn't valid UTF-8.
+fn fetch_pr_json(
+    pr: &PrRef,
+    github: &GitHub,
+    limiter: Option<&RateLimiter>,
+    budget: Option<&ApiBudget>,
+    max_comments: Option<usize>,
+) -> Result<String, Error> {
+    if let Some(budget) = budget {
+        budget.spend()?;
+    }
+    if let Some(limiter) = limiter {
+        limiter.wait()?;
+    }
+    let github_host = match github {
+        GitHub::Cli(host) => host.as_deref(),
+        GitHub::Api(api) => return api.pr_json(pr, max_comments),
+    };
+    let fields = if max_comments == Some(0) {
+        "state,isDraft,mergedAt,title,body,commits"
+    } else {
+        "state,isDraft,mergedAt,title,body,comments,commits"
+    };
+    let mut command = utils::gh_command(github_host);
+    command.args(["pr", "view", &pr.number, "--json", fields]);
+    if let Some(ref repo) = pr.repo {
+        command.args(["--repo", repo]);
+    }
+    let output = utils::gh_output(&mut command, limiter)?;
 
     if !output.status.success() {
-        return Err(format!("Failed to get PR #{pr_num}"));
+        return Err(Error::GitHub(format!("Failed to get PR {pr}")));
     }
 
-    let json_output = String::from_utf8(output.stdout)
-        .map_err(|e| format!("Invalid UTF-8 from gh: {e}"))?;
+    String::from_utf8(output.stdout)
+        .map_err(|e| Error::command(format!("Invalid UTF-8 from gh: {e}")))
+}
 
-    macro_rules! jq {
-        ($query:literal) => {
-            utils::run_jq(&json_output, $query)?
-        };
+/// Replaces the comments of a `gh pr view` response with the PR's complete
+/// comment thread, when `gh pr view` may have cut it short.
+///
+/// `gh pr view` returns at most [`GH_VIEW_COMMENT_LIMIT`] comments; a
+/// response with that many is completed from the issue comments REST
+/// endpoint (`gh api --paginate`), unless `max_comments` wouldn't scan past
+/// the comments already there. The response is then marked with
+/// [`COMPLETE_COMMENTS_KEY`].
+///
+/// # Returns
+/// Whether the comments were replaced (so the cached response should be
+/// too).
+///
+/// # Errors
+/// Returns an error if the budget is used up, or `gh api` fails or returns
+/// unparseable comments.
+fn complete_comments(
+    pr: &PrRef,
+    github: &GitHub,
+    limiter: Option<&RateLimiter>,
+    budget: Option<&ApiBudget>,
+    max_comments: Option<usize>,
+    response: &mut Value,
+) -> Result<bool, Error> {
+    let fetched = response.get("comments").items().len();
+    if fetched < GH_VIEW_COMMENT_LIMIT
+        || max_comments.is_some_and(|max| max <= fetched)
+        || response.get(COMPLETE_COMMENTS_KEY).as_bool() == Some(true)
+    {
+        return Ok(false);
+    }
+    log!(
+        Verbose,
+        "PR {pr} has at least {fetched} comments, fetching all of them"
+    );
+    let comments = fetch_all_comments(pr, github, limiter, budget)?;
+    let count = comments.len();
+    log!(Verbose, "fetched {count} comments of PR {pr}");
+    if let Value::Object(members) = response {
+        members.retain(|(name, _)| {
+            name != "comments" && name != COMPLETE_COMMENTS_KEY
+        });
+        members.push(("comments".to_string(), Value::Array(comments)));
+        members.push((COMPLETE_COMMENTS_KEY.to_string(), Value::Bool(true)));
     }
+    Ok(true)
+}
 
-    // Extract text fields from JSON using jq
-    // Each field may return empty string if not present
-    let text_parts = [
-        jq!(r#".title // """#),
-        jq!(r#".body // """#),
-        jq!(".comments[]?.body // empty"),
-        jq!(".commits[]?.messageHeadline // empty"),
-        jq!(".commits[]?.messageBody // empty"),
-    ];
+/// Fetches every comment of a PR with `gh api --paginate` (or, with
+/// `--github-api`, [`crate::github_api::GithubApi::comments`]), as `{"body": ...}` objects like
+/// those of `gh pr view --json comments`.
+///
+/// # Errors
+/// Returns an error if the budget is used up, `gh` (or the API) fails or a
+/// comment body can't be parsed.
+fn fetch_all_comments(
+    pr: &PrRef,
+    github: &GitHub,
+    limiter: Option<&RateLimiter>,
+    budget: Option<&ApiBudget>,
+) -> Result<Vec<Value>, Error> {
+    if let Some(budget) = budget {
+        budget.spend()?;
+    }
+    if let Some(limiter) = limiter {
+        limiter.wait()?;
+    }
+    let github_host = match github {
+        GitHub::Cli(host) => host.as_deref(),
+        GitHub::Api(api) => return api.comments(pr),
+    };
+    // gh fills in `{owner}/{repo}` from the current repository.
+    let repo = pr.repo.as_deref().unwrap_or("{owner}/{repo}");
+    let endpoint =
+        format!("repos/{repo}/issues/{}/comments?per_page=100", pr.number);
+    // One JSON string per line, whatever the bodies contain.
+    let output = utils::gh_output(
+        utils::gh_command(github_host).args([
+            "api",
+            "--paginate",
+            &endpoint,
+            "--jq",
+            ".[].body | @json",
+        ]),
+        limiter,
+    )?;
+    if !output.status.success() {
+        return Err(Error::GitHub(format!(
+            "Failed to get the comments of PR {pr}"
+        )));
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let body = json::parse(line).map_err(|e| {
+                e.context(format!("Invalid comment of PR {pr} from gh api"))
+            })?;
+            Ok(Value::Object(vec![("body".to_string(), body)]))
+        })
+        .collect()
+}
 
-    // Combine all text parts with double newlines for separation
-    Ok(text_parts.join("\n\n"))
+/// A PR as fetched by `get_pr`.
+struct PullRequest {
+    /// Lowercased GitHub state (`open`, `closed` or `merged`)
+    state: String,
+    is_draft: bool,
+    /// Whether `mergedAt` is set
+    merged: bool,
+    /// `mergedAt` (e.g. `2024-05-01T12:30:00Z`), empty if not merged
+    merged_at: String,
+    sources: Vec<TextSource>,
+    /// Labels and changed files, for `--pr-label`/`--path-filter`
+    scope: ScopeFacts,
 }
 
-/// Finds Linear ticket IDs in text and outputs them immediately.
+impl PullRequest {
+    /// The PR title, for log messages.
+    fn title(&self) -> &str {
+        self.sources
+            .iter()
+            .find(|source| source.name == "title")
+            .map_or("", |source| source.text.trim())
+    }
+
+    /// Describes why the PR doesn't count as merged, or `None` if it is.
+    fn unmerged_status(&self) -> Option<String> {
+        if self.merged {
+            return None;
+        }
+        Some(match (self.is_draft, self.state.as_str()) {
+            (true, _) => "draft".to_string(),
+            (false, "closed") => "closed without merging".to_string(),
+            (false, state) => state.to_string(),
+        })
+    }
+}
+
+/// One searchable piece of a PR.
+struct TextSource {
+    /// Source name reported in ticket records
+    name: &'static str,
+    /// Confidence of a ticket reference found here
+    confidence: Confidence,
+    text: String,
+}
+
+/// Every reference to one ticket within a PR.
+struct Reference<'a> {
+    ticket: String,
+    /// Text sources mentioning the ticket, in discovery order
+    sources: Vec<&'a TextSource>,
+    /// Organizations of the Linear URLs of the ticket, in discovery order
+    url_orgs: Vec<String>,
+    /// Whether the ticket is also mentioned by its bare ID
+    bare: bool,
+    /// The other workspace the ticket is tagged with (`--other-org-urls tag`)
+    org: Option<String>,
+}
+
+impl Reference<'_> {
+    /// The organization of the first URL of the ticket, if it is referenced
+    /// only by URLs and none of them is of workspace `own`.
+    fn other_org(&self, own: Option<&str>) -> Option<&str> {
+        if self.bare
+            || self
+                .url_orgs
+                .iter()
+                .any(|org| own.is_some_and(|own| org.eq_ignore_ascii_case(own)))
+        {
+            return None;
+        }
+        self.url_orgs.first().map(String::as_str)
+    }
+}
+
+/// Finds all Linear ticket IDs referenced by a PR.
 ///
 /// # Arguments
-/// * `text` - The text to search for ticket references
-/// * `seen` - `HashSet` to track already-output tickets (prevents duplicates)
+/// * `sources` - The PR's text, as returned by `get_pr`
+/// * `pattern` - Ticket ID pattern of the tracker ([`TICKET_PATTERN`] or
+///   the `--ticket-pattern`, or [`JIRA_TICKET_PATTERN`])
+/// * `prefix_map` - `--prefix-map` team key renames, applied to every match
+///   (so `OLD-1` and `NEW-1` count as the same ticket)
 ///
-/// # Output
-/// Prints each unique ticket ID to stdout as soon as it's discovered.
-/// No sorting or buffering - outputs in order of discovery.
+/// # Returns
+/// One entry per distinct ticket, in order of discovery across `sources`,
+/// listing every source that mentions it.
 ///
 /// # Supported Formats
 /// - Ticket ID: `ABC-123`
-/// - Full URL: `https://linear.app/org/issue/ABC-123` (ID extracted from URL)
+/// - Full URL: `https://linear.app/org/issue/ABC-123` (ID extracted from URL,
+///   `org` recorded in [`Reference::url_orgs`])
 ///
 /// # Pattern
 /// Ticket IDs must match the pattern: 3 uppercase ASCII letters, hyphen, one or
-/// more digits. Examples: `HIP-123`, `ENG-42`, `BUG-007`
+/// more digits. Examples: `HIP-123`, `ENG-42`, `BUG-007`. `--ticket-pattern`
+/// replaces this, e.g. `[A-Z]{2,4}-[0-9]+` for `OPS-12` and `PLAT-999`. Jira issue keys
+/// start with an uppercase letter followed by one or more uppercase letters,
+/// digits or underscores, e.g. `PROJ-123`.
 ///
 /// # Implementation
-/// 1. Uses grep to find all ticket ID matches anywhere in the text
-/// 2. Outputs each new ticket ID immediately (checks `HashSet`)
-fn find_and_output_tickets(
-    text: &str,
-    seen: &mut HashSet<String>,
-    any_output: &mut bool,
-) -> Result<(), String> {
-    let id_matches = utils::run_grep(text, TICKET_PATTERN)?;
-    id_matches
-        .lines()
-        .filter_map(|id| {
-            let trimmed = id.trim();
-            if trimmed.is_empty() {
-                None
-            } else {
-                Some(trimmed)
+/// Uses grep to find all ticket ID matches in each text source.
+fn find_references<'a>(
+    sources: &'a [TextSource],
+    pattern: &str,
+    prefix_map: &PrefixMap,
+) -> Result<Vec<Reference<'a>>, Error> {
+    let mut references: Vec<Reference<'_>> = Vec::new();
+    for source in sources {
+        let id_matches = utils::run_grep(&source.text, pattern)?;
+        for found in id_matches
+            .lines()
+            .map(str::trim)
+            .filter(|found| !found.is_empty())
+        {
+            // `linear.app/ORG/issue/ID` for a URL, else the bare ID
+            let (url_org, id) = match found
+                .strip_prefix("linear.app/")
+                .and_then(|url| url.split_once("/issue/"))
+            {
+                Some((org, id)) => (Some(org), id),
+                None => (None, found),
+            };
+            let id = prefix_map.apply(id);
+            let index = references
+                .iter()
+                .position(|r| r.ticket == id)
+                .unwrap_or_else(|| {
+                    references.push(Reference {
+                        ticket: id,
+                        sources: Vec::new(),
+                        url_orgs: Vec::new(),
+                        bare: false,
+                        org: None,
+                    });
+                    references.len() - 1
+                });
+            let reference = &mut references[index];
+            if !reference
+                .sources
+                .iter()
+                .any(|seen| seen.name == source.name)
+            {
+                reference.sources.push(source);
             }
-        })
-        .for_each(|id| {
-            if seen.insert(id.to_string()) {
-                println!("{id}");
-                *any_output = true;
+            match url_org {
+                Some(org) if !reference.url_orgs.iter().any(|o| o == org) => {
+                    reference.url_orgs.push(org.to_string());
+                }
+                Some(_) => {}
+                None => reference.bare = true,
             }
-        });
+        }
+    }
 
-    Ok(())
+    Ok(references)
+}
+
+/// Drops the references of `origin` whose ticket is a known false positive
+/// (see [`crate::config::FalsePositives`]), matches `--exclude-pattern` or is
+/// in `--ignore-tickets`, logging each.
+///
+/// # Returns
+/// Whether the `--explain` ticket was among those dropped.
+///
+/// # Errors
+/// Returns an error if grep fails.
+fn exclude_references(
+    config: &Config,
+    origin: &Origin,
+    references: &mut Vec<Reference<'_>>,
+) -> Result<bool, Error> {
+    let mut explained = false;
+    let checks = [
+        (config.false_positives.pattern(), "not a ticket"),
+        (config.exclude_pattern.clone(), "--exclude-pattern"),
+    ];
+    for (pattern, reason) in checks {
+        let Some(pattern) = pattern else {
+            continue;
+        };
+        if references.is_empty() {
+            return Ok(explained);
+        }
+        let tickets: Vec<&str> =
+            references.iter().map(|r| r.ticket.as_str()).collect();
+        let excluded = utils::grep_whole_lines(&tickets.join("\n"), &pattern)?;
+        for ticket in &excluded {
+            explained |= log_excluded(config, origin, ticket, reason);
+        }
+        references.retain(|reference| !excluded.contains(&reference.ticket));
+    }
+    references.retain(|reference| {
+        let ignored = config.ignore_list.ignores_ticket(&reference.ticket);
+        if ignored {
+            explained |= log_excluded(
+                config,
+                origin,
+                &reference.ticket,
+                "--ignore-tickets",
+            );
+        }
+        !ignored
+    });
+    Ok(explained)
+}
+
+/// Logs that `ticket`, referenced by `origin`, is excluded for `reason`.
+///
+/// # Returns
+/// Whether `ticket` is the `--explain` subject.
+fn log_excluded(
+    config: &Config,
+    origin: &Origin,
+    ticket: &str,
+    reason: &str,
+) -> bool {
+    log!("excluding {ticket} referenced by {origin} ({reason})");
+    let is_subject = config
+        .explain
+        .as_ref()
+        .is_some_and(|subject| subject.is_ticket(ticket));
+    if is_subject || explains_origin(config, origin) {
+        explain!("{ticket} is referenced by {origin}, but dropped ({reason})");
+    }
+    is_subject
+}
+
+/// Drops the references to tickets of another Linear workspace than `org`
+/// (see [`Reference::other_org`]), logging each, or with `--other-org-urls
+/// tag` tags them with that workspace.
+///
+/// # Returns
+/// Whether the `--explain` ticket was among those dropped or tagged.
+fn other_org_references(
+    config: &Config,
+    origin: &Origin,
+    org: Option<&str>,
+    references: &mut Vec<Reference<'_>>,
+) -> bool {
+    let mut explained = false;
+    let policy = config.other_org_urls;
+    references.retain_mut(|reference| {
+        let Some(other) = reference.other_org(org).map(String::from) else {
+            return true;
+        };
+        let ticket = &reference.ticket;
+        let fate = match (policy, org) {
+            (OtherOrgUrls::Tag, _) => format!("tagged with workspace {other}"),
+            (OtherOrgUrls::Drop, Some(own)) => format!(
+                "dropped: only linked by URLs of workspace {other}, not {own}"
+            ),
+            (OtherOrgUrls::Drop, None) => return true,
+        };
+        log!("{ticket} referenced by {origin} is {fate} (--other-org-urls)");
+        let is_subject = config
+            .explain
+            .as_ref()
+            .is_some_and(|subject| subject.is_ticket(ticket));
+        explained |= is_subject;
+        if is_subject || explains_origin(config, origin) {
+            explain!("{ticket} is referenced by {origin}, but {fate}");
+        }
+        reference.org = Some(other);
+        policy == OtherOrgUrls::Tag
+    });
+    explained
+}
+
+/// Builds the records of a PR's (or commit's) tickets that haven't been seen
+/// yet.
+///
+/// # Arguments
+/// * `origin` - The PR (or commit) the references belong to
+/// * `references` - The PR's references, as returned by `find_references`
+/// * `seen` - `HashSet` to track already-output tickets (prevents duplicates)
+///
+/// # Returns
+/// One record per new ticket, in order of discovery. Each record carries the
+/// strongest confidence with which the PR references the ticket (the first
+/// such source on ties).
+fn find_tickets(
+    origin: &Origin,
+    references: &[Reference<'_>],
+    seen: &mut HashSet<String>,
+) -> Vec<TicketRecord> {
+    references
+        .iter()
+        .filter(|reference| seen.insert(reference.ticket.clone()))
+        .filter_map(|reference| {
+            let strongest = reference
+                .sources
+                .iter()
+                .min_by_key(|source| Reverse(source.confidence))?;
+            Some(TicketRecord {
+                ticket: reference.ticket.clone(),
+                origin: origin.clone(),
+                confidence: strongest.confidence,
+                source: strongest.name,
+                org: reference.org.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Renders one PR's entry of the `--mapping-file` JSON array.
+///
+/// Unlike stdout, the mapping lists every ticket the PR references, including
+/// tickets already output for an earlier PR.
+fn mapping_entry(
+    pr: &PrRef,
+    merged: bool,
+    references: &[Reference<'_>],
+) -> String {
+    let tickets: Vec<String> = references
+        .iter()
+        .map(|reference| {
+            let sources: Vec<String> = reference
+                .sources
+                .iter()
+                .map(|source| utils::json_string(source.name))
+                .collect();
+            let org =
+                reference.org.as_deref().map_or_else(String::new, |org| {
+                    format!(",\"org\":{}", utils::json_string(org))
+                });
+            format!(
+                r#"{{"ticket":{},"sources":[{}]{org}}}"#,
+                utils::json_string(&reference.ticket),
+                sources.join(",")
+            )
+        })
+        .collect();
+
+    let mut entry = format!("{{\"pr\":{}", pr.number);
+    if let Some(ref repo) = pr.repo {
+        let _ = write!(entry, ",\"repo\":{}", utils::json_string(repo));
+    }
+    let _ = write!(
+        entry,
+        ",\"merged\":{merged},\"tickets\":[{}],\"schema_version\":{SCHEMA_VERSION}}}",
+        tickets.join(",")
+    );
+    entry
+}
+
+/// Writes the `--mapping-file` JSON array.
+///
+/// # Errors
+/// Returns an error if the file cannot be written.
+fn write_mapping(path: &str, entries: &[String]) -> Result<(), Error> {
+    let mut json = String::from("[");
+    for (idx, entry) in entries.iter().enumerate() {
+        json.push_str(if idx == 0 { "\n  " } else { ",\n  " });
+        json.push_str(entry);
+    }
+    json.push_str("\n]\n");
+    std::fs::write(path, json).map_err(|e| {
+        Error::io(format!("Failed to write mapping file {path}"), e)
+    })
 }
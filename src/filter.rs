@@ -0,0 +1,664 @@
+//! Inline ticket filter expressions.
+//!
+//! This module implements the small expression language accepted by
+//! `--filter`. Expressions are evaluated once per ticket against the metadata
+//! fetched from Linear, so users can express arbitrary selection logic without
+//! a bespoke flag for every field.
+//!
+//! ## Syntax
+//! ```text
+//! team == "ENG" && state != "Canceled" && !labels.contains("hold")
+//! ```
+//!
+//! - Fields: `id`, `team`, `team_name`, `state`, `state_type`, `title`,
+//!   `assignee`, `creator`, `labels`
+//! - Literals: `"double quoted"` strings, `true`, `false`
+//! - Operators: `==`, `!=`, `&&`, `||`, `!`, parentheses
+//! - Methods: `.contains(s)` (list membership or substring),
+//!   `.starts_with(s)`, `.ends_with(s)`
+//!
+//! Comparisons are exact (case-sensitive). Missing values (e.g. an unassigned
+//! ticket's `assignee`) evaluate to the empty string.
+//!
+//! Expressions are type-checked when parsed, so a filter that compares a
+//! list or calls a method on the wrong kind of value is rejected before any
+//! ticket is touched. Negations, parentheses and method arguments may nest
+//! at most [`MAX_DEPTH`] levels deep.
+
+use std::fmt;
+
+use crate::error::Error;
+
+/// Field names that can be referenced in a filter expression.
+pub const FIELDS: &[&str] = &[
+    "id",
+    "team",
+    "team_name",
+    "state",
+    "state_type",
+    "title",
+    "assignee",
+    "creator",
+    "labels",
+];
+
+/// A value produced while evaluating a filter expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    /// A string field or literal
+    Str(String),
+    /// A list field (e.g. `labels`)
+    List(Vec<String>),
+    /// A boolean result or literal
+    Bool(bool),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Str(_) => write!(f, "string"),
+            Self::List(_) => write!(f, "list"),
+            Self::Bool(_) => write!(f, "boolean"),
+        }
+    }
+}
+
+/// The type of a value, known before the expression is evaluated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Type {
+    Str,
+    List,
+    Bool,
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Str => write!(f, "string"),
+            Self::List => write!(f, "list"),
+            Self::Bool => write!(f, "boolean"),
+        }
+    }
+}
+
+/// A parsed and type-checked `--filter` expression.
+#[derive(Debug, Clone)]
+pub struct Filter {
+    source: String,
+    expr: Expr,
+}
+
+impl Filter {
+    /// Parses a filter expression.
+    ///
+    /// # Errors
+    /// Returns an error if the expression is syntactically invalid,
+    /// references an unknown field, applies an operator to values of the
+    /// wrong type, or does not evaluate to a boolean.
+    pub fn parse(source: &str) -> Result<Self, Error> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            depth: 0,
+        };
+        let expr = parser.parse_or()?;
+        if let Some(token) = parser.peek() {
+            return Err(Error::Config(format!(
+                "Unexpected {token} in filter expression"
+            )));
+        }
+        match check(&expr)? {
+            Type::Bool => Ok(Self {
+                source: source.to_string(),
+                expr,
+            }),
+            other => Err(Error::Config(format!(
+                "Filter must evaluate to a boolean, got {other}"
+            ))),
+        }
+    }
+
+    /// Returns the expression as it was given on the command line.
+    #[must_use]
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Evaluates the filter for a single ticket.
+    ///
+    /// # Arguments
+    /// * `resolve` - Looks up the value of a field (one of [`FIELDS`]) for the
+    ///   ticket being evaluated
+    ///
+    /// # Errors
+    /// Returns an error if `resolve` returns a value of a different type than
+    /// the field was checked against in [`Filter::parse`].
+    pub fn matches<F>(&self, resolve: &F) -> Result<bool, Error>
+    where
+        F: Fn(&str) -> Value,
+    {
+        match eval(&self.expr, resolve)? {
+            Value::Bool(result) => Ok(result),
+            other => Err(Error::Config(format!(
+                "Filter must evaluate to a boolean, got {other}"
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Field(String),
+    Literal(Value),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Eq(Box<Expr>, Box<Expr>),
+    Ne(Box<Expr>, Box<Expr>),
+    Method(Box<Expr>, Method, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Method {
+    Contains,
+    StartsWith,
+    EndsWith,
+}
+
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Contains => write!(f, ".contains()"),
+            Self::StartsWith => write!(f, ".starts_with()"),
+            Self::EndsWith => write!(f, ".ends_with()"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    LParen,
+    RParen,
+    Dot,
+    Not,
+    And,
+    Or,
+    Eq,
+    Ne,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ident(name) => write!(f, "'{name}'"),
+            Self::Str(value) => write!(f, "\"{value}\""),
+            Self::LParen => write!(f, "'('"),
+            Self::RParen => write!(f, "')'"),
+            Self::Dot => write!(f, "'.'"),
+            Self::Not => write!(f, "'!'"),
+            Self::And => write!(f, "'&&'"),
+            Self::Or => write!(f, "'||'"),
+            Self::Eq => write!(f, "'=='"),
+            Self::Ne => write!(f, "'!='"),
+        }
+    }
+}
+
+/// Deepest nesting of `!`, parentheses and method arguments accepted by
+/// [`Filter::parse`], like [`crate::json::MAX_DEPTH`]: it keeps a filter
+/// like `!!!...` from overflowing the stack of the recursive parser.
+pub const MAX_DEPTH: usize = 128;
+
+const UNTERMINATED: &str = "Unterminated string in filter expression";
+
+fn tokenize(source: &str) -> Result<Vec<Token>, Error> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '.' => Token::Dot,
+            '!' if chars.next_if_eq(&'=').is_some() => Token::Ne,
+            '!' => Token::Not,
+            '=' if chars.next_if_eq(&'=').is_some() => Token::Eq,
+            '&' if chars.next_if_eq(&'&').is_some() => Token::And,
+            '|' if chars.next_if_eq(&'|').is_some() => Token::Or,
+            '"' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(escaped) => value.push(escaped),
+                            None => {
+                                return Err(Error::Config(
+                                    UNTERMINATED.to_string(),
+                                ));
+                            }
+                        },
+                        Some(other) => value.push(other),
+                        None => {
+                            return Err(Error::Config(
+                                UNTERMINATED.to_string(),
+                            ));
+                        }
+                    }
+                }
+                Token::Str(value)
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut name = String::from(c);
+                while let Some(next) = chars.next_if(|next| {
+                    next.is_ascii_alphanumeric() || *next == '_'
+                }) {
+                    name.push(next);
+                }
+                Token::Ident(name)
+            }
+            other => {
+                return Err(Error::Config(format!(
+                    "Unexpected character '{other}' in filter expression"
+                )));
+            }
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// Negations, parentheses and method arguments currently open
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, expected: &Token) -> bool {
+        if self.peek() == Some(expected) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, expected: &Token) -> Result<(), Error> {
+        if self.eat(expected) {
+            return Ok(());
+        }
+        match self.peek() {
+            Some(found) => Err(Error::Config(format!(
+                "Expected {expected} in filter expression, found {found}"
+            ))),
+            None => Err(Error::Config(format!(
+                "Expected {expected} at end of filter expression"
+            ))),
+        }
+    }
+
+    /// Parses a nested expression with `parse`, one level deeper.
+    fn nested(
+        &mut self,
+        parse: fn(&mut Self) -> Result<Expr, Error>,
+    ) -> Result<Expr, Error> {
+        if self.depth == MAX_DEPTH {
+            return Err(Error::Config(format!(
+                "Filter expression nested more than {MAX_DEPTH} levels deep"
+            )));
+        }
+        self.depth += 1;
+        let expr = parse(self);
+        self.depth -= 1;
+        expr
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, Error> {
+        let mut lhs = self.parse_and()?;
+        while self.eat(&Token::Or) {
+            let rhs = self.parse_and()?;
+            lhs = Expr::Or(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, Error> {
+        let mut lhs = self.parse_unary()?;
+        while self.eat(&Token::And) {
+            let rhs = self.parse_unary()?;
+            lhs = Expr::And(Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, Error> {
+        if self.eat(&Token::Not) {
+            let inner = self.nested(Self::parse_unary)?;
+            return Ok(Expr::Not(Box::new(inner)));
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, Error> {
+        let lhs = self.parse_postfix()?;
+        if self.eat(&Token::Eq) {
+            let rhs = self.parse_postfix()?;
+            return Ok(Expr::Eq(Box::new(lhs), Box::new(rhs)));
+        }
+        if self.eat(&Token::Ne) {
+            let rhs = self.parse_postfix()?;
+            return Ok(Expr::Ne(Box::new(lhs), Box::new(rhs)));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_postfix(&mut self) -> Result<Expr, Error> {
+        let mut expr = self.parse_primary()?;
+        while self.eat(&Token::Dot) {
+            let method = match self.next() {
+                Some(Token::Ident(name)) => match name.as_str() {
+                    "contains" => Method::Contains,
+                    "starts_with" => Method::StartsWith,
+                    "ends_with" => Method::EndsWith,
+                    _ => {
+                        return Err(Error::Config(format!(
+                            "Unknown method '{name}' in filter expression"
+                        )));
+                    }
+                },
+                _ => {
+                    return Err(Error::Config(
+                        "Expected method name after '.' in filter expression"
+                            .to_string(),
+                    ));
+                }
+            };
+            self.expect(&Token::LParen)?;
+            let arg = self.nested(Self::parse_or)?;
+            self.expect(&Token::RParen)?;
+            expr = Expr::Method(Box::new(expr), method, Box::new(arg));
+        }
+        Ok(expr)
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, Error> {
+        match self.next() {
+            Some(Token::LParen) => {
+                let expr = self.nested(Self::parse_or)?;
+                self.expect(&Token::RParen)?;
+                Ok(expr)
+            }
+            Some(Token::Str(value)) => Ok(Expr::Literal(Value::Str(value))),
+            Some(Token::Ident(name)) => match name.as_str() {
+                "true" => Ok(Expr::Literal(Value::Bool(true))),
+                "false" => Ok(Expr::Literal(Value::Bool(false))),
+                field if FIELDS.contains(&field) => Ok(Expr::Field(name)),
+                _ => Err(Error::Config(format!(
+                    "Unknown field '{name}' in filter expression (expected one of: {})",
+                    FIELDS.join(", ")
+                ))),
+            },
+            Some(token) => Err(Error::Config(format!(
+                "Unexpected {token} in filter expression"
+            ))),
+            None => Err(Error::Config(
+                "Unexpected end of filter expression".to_string(),
+            )),
+        }
+    }
+}
+
+/// Returns the type of a field: `labels` is a list, everything else a string.
+fn field_type(name: &str) -> Type {
+    if name == "labels" {
+        Type::List
+    } else {
+        Type::Str
+    }
+}
+
+/// Infers the type of an expression, rejecting operators applied to values
+/// of the wrong type.
+fn check(expr: &Expr) -> Result<Type, Error> {
+    match expr {
+        Expr::Field(name) => Ok(field_type(name)),
+        Expr::Literal(Value::Str(_)) => Ok(Type::Str),
+        Expr::Literal(Value::List(_)) => Ok(Type::List),
+        Expr::Literal(Value::Bool(_)) => Ok(Type::Bool),
+        Expr::Not(inner) => {
+            check_bool(inner)?;
+            Ok(Type::Bool)
+        }
+        Expr::And(lhs, rhs) | Expr::Or(lhs, rhs) => {
+            check_bool(lhs)?;
+            check_bool(rhs)?;
+            Ok(Type::Bool)
+        }
+        Expr::Eq(lhs, rhs) | Expr::Ne(lhs, rhs) => {
+            match (check(lhs)?, check(rhs)?) {
+                (Type::List, _) | (_, Type::List) => Err(Error::Config(
+                    "Lists cannot be compared with == or != (use .contains())"
+                        .to_string(),
+                )),
+                (lhs, rhs) if lhs != rhs => Err(Error::Config(format!(
+                    "Cannot compare a {lhs} with a {rhs} in filter expression"
+                ))),
+                _ => Ok(Type::Bool),
+            }
+        }
+        Expr::Method(target, method, arg) => {
+            let target = check(target)?;
+            if check(arg)? != Type::Str {
+                return Err(Error::Config(
+                    "Filter method arguments must be strings".to_string(),
+                ));
+            }
+            match (method, target) {
+                (Method::Contains, Type::List | Type::Str)
+                | (Method::StartsWith | Method::EndsWith, Type::Str) => {
+                    Ok(Type::Bool)
+                }
+                (method, target) => Err(Error::Config(format!(
+                    "Cannot call {method} on a {target} in filter expression"
+                ))),
+            }
+        }
+    }
+}
+
+fn check_bool(expr: &Expr) -> Result<(), Error> {
+    match check(expr)? {
+        Type::Bool => Ok(()),
+        other => Err(Error::Config(format!(
+            "Expected a boolean in filter expression, got {other}"
+        ))),
+    }
+}
+
+fn eval<F>(expr: &Expr, resolve: &F) -> Result<Value, Error>
+where
+    F: Fn(&str) -> Value,
+{
+    match expr {
+        Expr::Field(name) => Ok(resolve(name)),
+        Expr::Literal(value) => Ok(value.clone()),
+        Expr::Not(inner) => Ok(Value::Bool(!eval_bool(inner, resolve)?)),
+        Expr::And(lhs, rhs) => Ok(Value::Bool(
+            eval_bool(lhs, resolve)? && eval_bool(rhs, resolve)?,
+        )),
+        Expr::Or(lhs, rhs) => Ok(Value::Bool(
+            eval_bool(lhs, resolve)? || eval_bool(rhs, resolve)?,
+        )),
+        Expr::Eq(lhs, rhs) => Ok(Value::Bool(eval_equal(lhs, rhs, resolve)?)),
+        Expr::Ne(lhs, rhs) => Ok(Value::Bool(!eval_equal(lhs, rhs, resolve)?)),
+        Expr::Method(target, method, arg) => {
+            let target = eval(target, resolve)?;
+            let Value::Str(arg) = eval(arg, resolve)? else {
+                return Err(Error::Config(
+                    "Filter method arguments must be strings".to_string(),
+                ));
+            };
+            let result = match (method, &target) {
+                (Method::Contains, Value::List(items)) => items.contains(&arg),
+                (Method::Contains, Value::Str(s)) => s.contains(&arg),
+                (Method::StartsWith, Value::Str(s)) => s.starts_with(&arg),
+                (Method::EndsWith, Value::Str(s)) => s.ends_with(&arg),
+                (method, target) => {
+                    return Err(Error::Config(format!(
+                        "Cannot call {method} on a {target} in filter expression"
+                    )));
+                }
+            };
+            Ok(Value::Bool(result))
+        }
+    }
+}
+
+fn eval_bool<F>(expr: &Expr, resolve: &F) -> Result<bool, Error>
+where
+    F: Fn(&str) -> Value,
+{
+    match eval(expr, resolve)? {
+        Value::Bool(value) => Ok(value),
+        other => Err(Error::Config(format!(
+            "Expected a boolean in filter expression, got {other}"
+        ))),
+    }
+}
+
+fn eval_equal<F>(lhs: &Expr, rhs: &Expr, resolve: &F) -> Result<bool, Error>
+where
+    F: Fn(&str) -> Value,
+{
+    match (eval(lhs, resolve)?, eval(rhs, resolve)?) {
+        (Value::List(_), _) | (_, Value::List(_)) => Err(Error::Config(
+            "Lists cannot be compared with == or != (use .contains())"
+                .to_string(),
+        )),
+        (lhs, rhs) => Ok(lhs == rhs),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A ticket of team ENG in state "Passing", labeled `bug` and `ui`.
+    fn ticket(name: &str) -> Value {
+        match name {
+            "team" => Value::Str("ENG".to_string()),
+            "state" => Value::Str("Passing".to_string()),
+            "title" => Value::Str("Fix the \"login\" page".to_string()),
+            "labels" => Value::List(vec!["bug".to_string(), "ui".to_string()]),
+            _ => Value::Str(String::new()),
+        }
+    }
+
+    fn matches(source: &str) -> Result<bool, Error> {
+        Filter::parse(source)?.matches(&ticket)
+    }
+
+    #[test]
+    fn evaluates_expressions() -> Result<(), Error> {
+        assert!(matches(r#"team == "ENG""#)?);
+        assert!(matches(r#"team != "OPS" && state == "Passing""#)?);
+        assert!(matches(r#"team == "OPS" || labels.contains("bug")"#)?);
+        assert!(matches(r#"!(labels.contains("hold"))"#)?);
+        assert!(matches(
+            r#"title.starts_with("Fix") && title.ends_with("page")"#
+        )?);
+        assert!(matches(r#"title.contains("\"login\"")"#)?);
+        assert!(matches(r#"assignee == """#)?);
+        assert!(!matches(r#"labels.contains("bu")"#)?);
+        assert!(matches("true && !false")?);
+        Ok(())
+    }
+
+    #[test]
+    fn binds_and_tighter_than_or() -> Result<(), Error> {
+        assert!(matches(r#"team == "ENG" || team == "OPS" && false"#)?);
+        assert!(!matches(r#"(team == "ENG" || team == "OPS") && false"#)?);
+        Ok(())
+    }
+
+    #[test]
+    fn keeps_its_source() -> Result<(), Error> {
+        let source = r#"team == "ENG""#;
+        assert_eq!(Filter::parse(source)?.source(), source);
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_syntax_errors() {
+        for invalid in [
+            "",
+            "team ==",
+            r#"team == "ENG"#,
+            r#"team = "ENG""#,
+            r#"(team == "ENG""#,
+            r#"team == "ENG")"#,
+            r#"team.lower("x")"#,
+            r#"project == "x""#,
+            r#"team == "ENG" &"#,
+        ] {
+            assert!(Filter::parse(invalid).is_err(), "{invalid} should fail");
+        }
+    }
+
+    #[test]
+    fn rejects_type_errors() {
+        for invalid in [
+            "team",
+            "labels",
+            "team == true",
+            r#"labels == "bug""#,
+            "!team",
+            "team && true",
+            r#"labels.starts_with("b")"#,
+            "team.contains(labels)",
+            "team.contains(true)",
+            r#"team.contains("E").contains("x")"#,
+        ] {
+            assert!(Filter::parse(invalid).is_err(), "{invalid} should fail");
+        }
+    }
+
+    #[test]
+    fn limits_nesting_depth() -> Result<(), Error> {
+        let parens = |depth| "(".repeat(depth) + "true" + &")".repeat(depth);
+        let negations = |depth| "!".repeat(depth) + "true";
+        let arguments = |depth| {
+            "team.contains(".repeat(depth) + r#""E""# + &")".repeat(depth)
+        };
+        Filter::parse(&parens(MAX_DEPTH))?;
+        Filter::parse(&negations(MAX_DEPTH))?;
+        for too_deep in [
+            parens(MAX_DEPTH + 1),
+            negations(MAX_DEPTH + 1),
+            arguments(MAX_DEPTH + 1),
+            "!".repeat(100_000) + "true",
+            "(".repeat(100_000),
+        ] {
+            assert!(
+                matches!(Filter::parse(&too_deep), Err(Error::Config(message)) if message.contains("levels deep")),
+                "{}...",
+                &too_deep[..10]
+            );
+        }
+        Ok(())
+    }
+}
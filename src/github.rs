@@ -0,0 +1,260 @@
+//! GitHub pull requests in bulk, through `gh api graphql`.
+//!
+//! extract-tickets fetches each PR with `gh pr view` as it reads it. With
+//! `--batch-prs`, it reads every PR first and fetches those not cached
+//! [`BATCH_SIZE`] per query instead, as aliased `pullRequest` fields:
+//!
+//! ```graphql
+//! query($owner: String!, $repo: String!) {
+//!   p0: repository(owner: $owner, name: $repo) { pullRequest(number: 12) { ... } }
+//!   p1: repository(owner: "acme", name: "other") { pullRequest(number: 3) { ... } }
+//! }
+//! ```
+//!
+//! `$owner` and `$repo` are the current repository, filled in by `gh` (or,
+//! with `--github-api`, sent as variables; see [`crate::github_api`]). Each
+//! PR is rewritten into the shape of a `gh pr view --json` response, so
+//! caching and ticket extraction treat it like one.
+//!
+//! GitHub answers for the PRs it finds even when others in the query don't
+//! resolve. Those, the PRs of a query that fails, and PRs with more commits
+//! than one query returns are left to `gh pr view`. A PR with more comments
+//! is completed page by page, as after `gh pr view`.
+
+use crate::cache::{ApiBudget, RateLimiter};
+use crate::error::Error;
+use crate::github_api::GitHub;
+use crate::json::{self, Value};
+use crate::protocol::PrRef;
+use crate::utils;
+
+const NAME: &str = "extract-tickets";
+
+/// PRs fetched per query. Each brings up to [`PAGE_SIZE`] comments and
+/// commits, which keeps a query well within GitHub's node limit.
+pub const BATCH_SIZE: usize = 25;
+
+/// Most comments or commits a GraphQL connection returns at once.
+pub const PAGE_SIZE: usize = 100;
+
+macro_rules! log {
+    ($level:ident, $fmt:expr) => {
+        utils::log_at(utils::LogLevel::$level, NAME, format_args!($fmt));
+    };
+    ($fmt:expr) => {
+        utils::log(NAME, format_args!($fmt));
+    };
+}
+
+/// Fetches `prs` [`BATCH_SIZE`] per query, as `gh pr view --json
+/// state,isDraft,mergedAt,title,body,comments,commits` responses (without
+/// `comments` for `--max-comments 0`). Every query claims one request from
+/// `budget`.
+///
+/// Failures are logged rather than returned: the PRs missing from the result
+/// are fetched one by one on first use.
+pub fn fetch_prs(
+    prs: &[PrRef],
+    github: &GitHub,
+    limiter: Option<&RateLimiter>,
+    budget: Option<&ApiBudget>,
+    max_comments: Option<usize>,
+) -> Vec<(PrRef, String)> {
+    let mut fetched = Vec::new();
+    let mut queries = 0;
+    for batch in prs.chunks(BATCH_SIZE) {
+        if budget.is_some_and(ApiBudget::exhausted) {
+            break;
+        }
+        queries += 1;
+        match query_batch(batch, github, limiter, budget, max_comments) {
+            Ok(responses) => fetched.extend(responses),
+            Err(e) => {
+                let count = batch.len();
+                log!(
+                    Warn,
+                    "Batched fetch of {count} PRs failed, will fetch them alone: {e}"
+                );
+            }
+        }
+    }
+    let count = fetched.len();
+    let total = prs.len();
+    log!(
+        Verbose,
+        "fetched {count} of {total} PRs in {queries} batched queries"
+    );
+    fetched
+}
+
+/// Queries `batch` as aliased `pullRequest` fields (see the module docs),
+/// returning the PRs that resolved.
+///
+/// # Errors
+/// Returns an error if the budget is used up, or `gh` (or the API) fails
+/// without a JSON response.
+fn query_batch(
+    batch: &[PrRef],
+    github: &GitHub,
+    limiter: Option<&RateLimiter>,
+    budget: Option<&ApiBudget>,
+    max_comments: Option<usize>,
+) -> Result<Vec<(PrRef, String)>, Error> {
+    let without_comments = max_comments == Some(0);
+    let fields = pr_fields(without_comments);
+    let mut current_repo = false;
+    let mut selections = Vec::new();
+    let mut queried = Vec::new();
+    for pr in batch {
+        let repository = match pr.repo.as_deref().map(|repo| repo.split('/')) {
+            None => {
+                current_repo = true;
+                "repository(owner: $owner, name: $repo)".to_string()
+            }
+            Some(mut parts) => match (parts.next(), parts.next(), parts.next())
+            {
+                (Some(owner), Some(name), None) => format!(
+                    "repository(owner: {}, name: {})",
+                    utils::json_string(owner),
+                    utils::json_string(name)
+                ),
+                // Not a GitHub repository; `gh pr view` reports it.
+                _ => continue,
+            },
+        };
+        let idx = queried.len();
+        selections.push(format!(
+            "p{idx}: {repository} {{ pullRequest(number: {}) {{ {fields} }} }}",
+            pr.number
+        ));
+        queried.push(pr);
+    }
+    if queried.is_empty() {
+        return Ok(Vec::new());
+    }
+    let params = if current_repo {
+        "($owner: String!, $repo: String!)"
+    } else {
+        ""
+    };
+    let query = format!("query{params} {{ {} }}", selections.join(" "));
+
+    if let Some(budget) = budget {
+        budget.spend()?;
+    }
+    if let Some(limiter) = limiter {
+        limiter.wait()?;
+    }
+    let response = match github {
+        GitHub::Cli(host) => {
+            gh_graphql(&query, host.as_deref(), current_repo, limiter)?
+        }
+        GitHub::Api(api) => {
+            let variables = if current_repo {
+                let (owner, name) = api.current_repo()?;
+                vec![
+                    ("owner", Value::String(owner.to_string())),
+                    ("repo", Value::String(name.to_string())),
+                ]
+            } else {
+                Vec::new()
+            };
+            api.graphql(&query, variables)?
+        }
+    };
+
+    Ok(queried
+        .into_iter()
+        .enumerate()
+        .filter_map(|(idx, pr)| {
+            let pull_request = response.get(&format!("data.p{idx}.pullRequest"));
+            if pull_request.is_null() {
+                return None;
+            }
+            let total_commits = pull_request.get("commits.totalCount");
+            if matches!(total_commits, Value::Number(total)
+                if total.parse::<usize>().is_ok_and(|total| total > PAGE_SIZE))
+            {
+                log!(Verbose, "PR {pr} has more than {PAGE_SIZE} commits, fetching it alone");
+                return None;
+            }
+            Some((pr.clone(), view_response(pull_request, without_comments)))
+        })
+        .collect())
+}
+
+/// Sends `query` with `gh api graphql`, filling in the current repository
+/// as `$owner` and `$repo` if `current_repo`, and pausing `limiter` while
+/// GitHub rate limits it.
+///
+/// # Errors
+/// Returns an error if `gh` fails without a JSON response.
+fn gh_graphql(
+    query: &str,
+    github_host: Option<&str>,
+    current_repo: bool,
+    limiter: Option<&RateLimiter>,
+) -> Result<Value, Error> {
+    let mut command = utils::gh_command(github_host);
+    command.args(["api", "graphql", "-f", &format!("query={query}")]);
+    if current_repo {
+        command.args(["-F", "owner={owner}", "-F", "repo={repo}"]);
+    }
+    let output = utils::gh_output(&mut command, limiter)?;
+    // gh exits with an error when any PR didn't resolve, but still prints
+    // the others.
+    json::parse(&String::from_utf8_lossy(&output.stdout))
+        .ok()
+        .filter(|response| !response.get("data").is_null())
+        .ok_or_else(|| {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            Error::GitHub(
+                stderr.lines().next().unwrap_or("").trim().to_string(),
+            )
+        })
+}
+
+/// The `pullRequest` fields a PR is queried with, up to [`PAGE_SIZE`]
+/// comments (none if `without_comments`) and commits.
+pub fn pr_fields(without_comments: bool) -> String {
+    let comments = if without_comments {
+        ""
+    } else {
+        "comments(first: 100) { nodes { body } }"
+    };
+    format!(
+        "state isDraft mergedAt title body {comments} commits(first: 100) {{ totalCount nodes {{ commit {{ messageHeadline messageBody }} }} }}"
+    )
+}
+
+/// Rewrites a GraphQL `pullRequest` into a `gh pr view --json` response.
+pub fn view_response(pull_request: &Value, without_comments: bool) -> String {
+    let mut members: Vec<(String, Value)> =
+        ["state", "isDraft", "mergedAt", "title", "body"]
+            .into_iter()
+            .map(|name| (name.to_string(), pull_request.get(name).clone()))
+            .collect();
+    if !without_comments {
+        members.push((
+            "comments".to_string(),
+            pull_request.get("comments.nodes").clone(),
+        ));
+    }
+    let commits = pull_request
+        .get("commits.nodes")
+        .items()
+        .iter()
+        .map(|node| {
+            let commit = node.get("commit");
+            Value::Object(vec![
+                (
+                    "messageHeadline".to_string(),
+                    commit.get("messageHeadline").clone(),
+                ),
+                ("messageBody".to_string(), commit.get("messageBody").clone()),
+            ])
+        })
+        .collect();
+    members.push(("commits".to_string(), Value::Array(commits)));
+    Value::Object(members).to_string()
+}
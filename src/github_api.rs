@@ -0,0 +1,477 @@
+//! GitHub without `gh`, through its REST and GraphQL APIs (`--github-api`).
+//!
+//! Every GitHub request goes through the `gh` CLI by default, which finds
+//! the host, credentials and current repository itself. With
+//! `--github-api`, requests are sent directly instead (with the built-in
+//! client of [`crate::http`]), so images without `gh` can run every mode:
+//! - The token is `GITHUB_TOKEN` (or `GH_TOKEN`), sent as a bearer token
+//! - The current repository is `--github-repo OWNER/REPO` or
+//!   `GITHUB_REPOSITORY`; only PRs without a repository need it
+//! - The APIs are at `https://HOST/api/v3` and `https://HOST/api/graphql`
+//!   with `--github-host HOST`, otherwise at `GITHUB_API_URL` and
+//!   `GITHUB_GRAPHQL_URL` (both set in GitHub Actions), and otherwise at
+//!   `api.github.com`
+//!
+//! Each request stands in for a `gh` command:
+//! - `gh release view`: REST `repos/OWNER/REPO/releases/tags/TAG`
+//! - `gh api --paginate .../releases`: REST `repos/OWNER/REPO/releases`,
+//!   page by page
+//! - `gh api --paginate .../compare/BASE...HEAD`: the same REST endpoint,
+//!   page by page
+//! - `gh pr view`: the GraphQL fields of a `--batch-prs` query (see
+//!   [`crate::github`]), with commits past the first 100 from REST
+//!   `pulls/N/commits`
+//! - `gh api --paginate`: the same REST endpoint, page by page
+//!
+//! Responses are rewritten into the shape of the `gh` output they replace,
+//! so caching and ticket extraction don't tell them apart.
+
+use std::env;
+
+use crate::error::Error;
+use crate::estimate::{self, Provider};
+use crate::exit_status;
+use crate::github;
+use crate::json::{self, Value};
+use crate::protocol::PrRef;
+use crate::utils;
+
+/// REST API root of github.com.
+const DEFAULT_API_URL: &str = "https://api.github.com";
+
+/// Items requested per page of a REST list endpoint (GitHub's maximum).
+const PER_PAGE: usize = 100;
+
+/// How GitHub is reached.
+#[derive(Debug, Clone)]
+pub enum GitHub {
+    /// Through `gh`, with the `--github-host` override (the default)
+    Cli(Option<String>),
+    /// Through the APIs, with a token (`--github-api`)
+    Api(GithubApi),
+}
+
+/// GitHub API endpoints and credentials, as described in the module docs.
+#[derive(Debug, Clone)]
+pub struct GithubApi {
+    token: String,
+    /// REST API root, without a trailing slash
+    api_url: String,
+    graphql_url: String,
+    /// The current repository (`OWNER/REPO`)
+    repo: Option<String>,
+}
+
+impl GithubApi {
+    /// Finds the token, endpoints and current repository (see the module
+    /// docs), for GitHub host `host` and repository `repo` if given.
+    ///
+    /// # Errors
+    /// Returns an error if there is no token, or the current repository
+    /// isn't of the form `OWNER/REPO`.
+    pub fn new(host: Option<&str>, repo: Option<&str>) -> Result<Self, Error> {
+        let token = ["GITHUB_TOKEN", "GH_TOKEN"]
+            .into_iter()
+            .find_map(|name| {
+                env::var(name).ok().filter(|token| !token.trim().is_empty())
+            })
+            .ok_or_else(|| {
+                exit_status::credentials(
+                    "GITHUB_TOKEN (or GH_TOKEN) not provided; --github-api needs a token",
+                )
+            })?;
+        let (api_url, graphql_url) = if let Some(host) = host {
+            (
+                format!("https://{host}/api/v3"),
+                format!("https://{host}/api/graphql"),
+            )
+        } else {
+            let api_url = env::var("GITHUB_API_URL")
+                .ok()
+                .filter(|url| !url.trim().is_empty())
+                .map_or_else(
+                    || DEFAULT_API_URL.to_string(),
+                    |url| url.trim_end_matches('/').to_string(),
+                );
+            // GitHub Enterprise Server serves GraphQL next to `/api/v3`.
+            let graphql_url = env::var("GITHUB_GRAPHQL_URL")
+                .ok()
+                .filter(|url| !url.trim().is_empty())
+                .unwrap_or_else(|| match api_url.strip_suffix("/v3") {
+                    Some(base) => format!("{base}/graphql"),
+                    None => format!("{api_url}/graphql"),
+                });
+            (api_url, graphql_url)
+        };
+        let repo = repo
+            .map(String::from)
+            .or_else(|| env::var("GITHUB_REPOSITORY").ok())
+            .filter(|repo| !repo.trim().is_empty());
+        if let Some(ref repo) = repo
+            && split_repo(repo).is_none()
+        {
+            return Err(Error::GitHub(format!(
+                "Invalid GitHub repository {repo}: expected OWNER/REPO"
+            )));
+        }
+        Ok(Self {
+            token,
+            api_url,
+            graphql_url,
+            repo,
+        })
+    }
+
+    /// The current repository, as owner and name.
+    ///
+    /// # Errors
+    /// Returns an error if neither `--github-repo` nor `GITHUB_REPOSITORY`
+    /// names one.
+    pub fn current_repo(&self) -> Result<(&str, &str), Error> {
+        self.repo.as_deref().and_then(split_repo).ok_or_else(|| {
+            Error::Config("--github-api needs --github-repo OWNER/REPO (or GITHUB_REPOSITORY) for releases and PRs of the current repository".to_string())
+        })
+    }
+
+    /// `repo`, or the current repository if `None`.
+    fn repo_or_current<'a>(
+        &'a self,
+        repo: Option<&'a str>,
+    ) -> Result<(&'a str, &'a str), Error> {
+        match repo {
+            Some(repo) => split_repo(repo).ok_or_else(|| {
+                Error::Other(format!(
+                    "Invalid GitHub repository {repo}: expected OWNER/REPO"
+                ))
+            }),
+            None => self.current_repo(),
+        }
+    }
+
+    /// Sends a request with the token, returning the response body.
+    ///
+    /// # Errors
+    /// Returns an error if the request fails or GitHub answers with an
+    /// error status (a 401 exits with [`exit_status::CREDENTIALS`]).
+    fn send(
+        &self,
+        method: &str,
+        url: &str,
+        body: Option<&str>,
+    ) -> Result<String, Error> {
+        let headers = [
+            format!("Authorization: Bearer {}", self.token),
+            "Accept: application/vnd.github+json".to_string(),
+            "Content-Type: application/json".to_string(),
+            "X-GitHub-Api-Version: 2022-11-28".to_string(),
+            concat!(
+                "User-Agent: release-linear-ticket-update/",
+                env!("CARGO_PKG_VERSION")
+            )
+            .to_string(),
+        ];
+        estimate::count(Provider::GitHub);
+        let response = utils::retry_rate_limited(
+            "GitHub",
+            None,
+            || {
+                utils::http_request(
+                    method,
+                    url,
+                    &headers,
+                    &[],
+                    body,
+                    Error::GitHub,
+                )
+            },
+            utils::is_rate_limited,
+        )
+        .map_err(|e| e.context(format!("GitHub {method} {url} failed")))?;
+        if !(200..300).contains(&response.status) {
+            let status = response.status;
+            let message = format!(
+                "GitHub API returned HTTP {status} to {method} {url}: {}",
+                utils::excerpt(response.body.trim())
+            );
+            return Err(if status == 401 {
+                Error::Credentials(message)
+            } else {
+                Error::GitHub(message)
+            });
+        }
+        Ok(response.body)
+    }
+
+    /// Sends a REST request to `endpoint` (e.g. `repos/OWNER/REPO/...`),
+    /// returning the parsed response.
+    ///
+    /// # Errors
+    /// Returns an error if the request fails or the response isn't JSON.
+    pub fn rest(
+        &self,
+        method: &str,
+        endpoint: &str,
+        body: Option<&Value>,
+    ) -> Result<Value, Error> {
+        let url = format!("{}/{endpoint}", self.api_url);
+        let body = body.map(Value::to_string);
+        let response = self.send(method, &url, body.as_deref())?;
+        json::parse(&response).map_err(|e| {
+            e.context(format!(
+                "Invalid GitHub API response to {method} {endpoint}"
+            ))
+        })
+    }
+
+    /// Gets every item of REST list `endpoint`, [`PER_PAGE`] per request.
+    ///
+    /// # Errors
+    /// Returns an error if any page can't be fetched.
+    fn rest_pages(&self, endpoint: &str) -> Result<Vec<Value>, Error> {
+        let separator = if endpoint.contains('?') { '&' } else { '?' };
+        let mut items = Vec::new();
+        for page in 1.. {
+            let response = self.rest(
+                "GET",
+                &format!(
+                    "{endpoint}{separator}per_page={PER_PAGE}&page={page}"
+                ),
+                None,
+            )?;
+            let count = response.items().len();
+            items.extend(response.items().iter().cloned());
+            if count < PER_PAGE {
+                break;
+            }
+        }
+        Ok(items)
+    }
+
+    /// Sends a GraphQL query with `variables`, returning the response.
+    /// Like `gh api graphql`, the response may hold `errors` next to the
+    /// `data` that did resolve.
+    ///
+    /// # Errors
+    /// Returns an error if the request fails, or the response has no `data`.
+    pub fn graphql(
+        &self,
+        query: &str,
+        variables: Vec<(&str, Value)>,
+    ) -> Result<Value, Error> {
+        let body = Value::Object(vec![
+            ("query".to_string(), Value::String(query.to_string())),
+            (
+                "variables".to_string(),
+                Value::Object(
+                    variables
+                        .into_iter()
+                        .map(|(name, value)| (name.to_string(), value))
+                        .collect(),
+                ),
+            ),
+        ]);
+        let response =
+            self.send("POST", &self.graphql_url, Some(&body.to_string()))?;
+        let response = json::parse(&response)
+            .map_err(|e| e.context("Invalid GitHub GraphQL response"))?;
+        if response.get("data").is_null() {
+            let messages = response.error_messages().join("; ");
+            return Err(Error::graphql("GitHub API", &messages));
+        }
+        Ok(response)
+    }
+
+    /// Gets release `tag` of the current repository, as the REST release
+    /// object (`body`, `html_url`, `published_at`, ...).
+    ///
+    /// # Errors
+    /// Returns an error if there is no current repository or no such
+    /// release.
+    pub fn release(&self, tag: &str) -> Result<Value, Error> {
+        let (owner, name) = self.current_repo()?;
+        self.rest(
+            "GET",
+            &format!(
+                "repos/{owner}/{name}/releases/tags/{}",
+                utils::percent_encode(tag)
+            ),
+            None,
+        )
+    }
+
+    /// Gets every release of the current repository, newest first, as REST
+    /// release objects (`tag_name`, `draft`, ...).
+    ///
+    /// # Errors
+    /// Returns an error if there is no current repository or a page can't
+    /// be fetched.
+    pub fn releases(&self) -> Result<Vec<Value>, Error> {
+        let (owner, name) = self.current_repo()?;
+        self.rest_pages(&format!("repos/{owner}/{name}/releases"))
+    }
+
+    /// Gets every commit between releases `base` and `head` of the current
+    /// repository, as REST commit objects (`sha`, `commit.message`, ...),
+    /// [`PER_PAGE`] per request.
+    ///
+    /// # Errors
+    /// Returns an error if there is no current repository or a page can't
+    /// be fetched.
+    pub fn compare_commits(
+        &self,
+        base: &str,
+        head: &str,
+    ) -> Result<Vec<Value>, Error> {
+        let (owner, name) = self.current_repo()?;
+        let endpoint = format!(
+            "repos/{owner}/{name}/compare/{}...{}",
+            utils::percent_encode(base),
+            utils::percent_encode(head)
+        );
+        let mut commits = Vec::new();
+        for page in 1.. {
+            let response = self.rest(
+                "GET",
+                &format!("{endpoint}?per_page={PER_PAGE}&page={page}"),
+                None,
+            )?;
+            let count = response.get("commits").items().len();
+            commits.extend_from_slice(response.get("commits").items());
+            if count < PER_PAGE {
+                break;
+            }
+        }
+        Ok(commits)
+    }
+
+    /// Fetches `pr` as a `gh pr view --json
+    /// state,isDraft,mergedAt,title,body,comments,commits` response
+    /// (without `comments` for `--max-comments 0`).
+    ///
+    /// # Errors
+    /// Returns an error if the PR can't be found or fetched.
+    pub fn pr_json(
+        &self,
+        pr: &PrRef,
+        max_comments: Option<usize>,
+    ) -> Result<String, Error> {
+        let (owner, name) = self.repo_or_current(pr.repo.as_deref())?;
+        let without_comments = max_comments == Some(0);
+        let query = format!(
+            "query($owner: String!, $repo: String!, $number: Int!) {{ repository(owner: $owner, name: $repo) {{ pullRequest(number: $number) {{ {} }} }} }}",
+            github::pr_fields(without_comments)
+        );
+        let response = self
+            .graphql(
+                &query,
+                vec![
+                    ("owner", Value::String(owner.to_string())),
+                    ("repo", Value::String(name.to_string())),
+                    ("number", Value::Number(pr.number.clone())),
+                ],
+            )
+            .map_err(|e| e.context(format!("Failed to get PR {pr}")))?;
+        let mut pull_request =
+            response.get("data.repository.pullRequest").clone();
+        if pull_request.is_null() {
+            let messages = response.error_messages().join("; ");
+            return Err(Error::GitHub(format!(
+                "Failed to get PR {pr}: {messages}"
+            )));
+        }
+        let total_commits = pull_request.get("commits.totalCount");
+        if matches!(total_commits, Value::Number(total)
+            if total.parse::<usize>().is_ok_and(|total| total > github::PAGE_SIZE))
+        {
+            let commits = self.commits(owner, name, &pr.number)?;
+            if let Value::Object(ref mut members) = pull_request {
+                members.retain(|(name, _)| name != "commits");
+                members.push((
+                    "commits".to_string(),
+                    Value::Object(vec![(
+                        "nodes".to_string(),
+                        Value::Array(commits),
+                    )]),
+                ));
+            }
+        }
+        Ok(github::view_response(&pull_request, without_comments))
+    }
+
+    /// Gets every commit of PR `number` of `owner/name`, as GraphQL commit
+    /// nodes (`{"commit": {"messageHeadline": ..., "messageBody": ...}}`).
+    fn commits(
+        &self,
+        owner: &str,
+        name: &str,
+        number: &str,
+    ) -> Result<Vec<Value>, Error> {
+        let commits = self.rest_pages(&format!(
+            "repos/{owner}/{name}/pulls/{number}/commits"
+        ))?;
+        Ok(commits
+            .iter()
+            .map(|commit| {
+                let message = commit.get("commit.message").str_or_empty();
+                let (headline, body) =
+                    message.split_once('\n').unwrap_or((message, ""));
+                Value::Object(vec![(
+                    "commit".to_string(),
+                    Value::Object(vec![
+                        (
+                            "messageHeadline".to_string(),
+                            Value::String(headline.trim_end().to_string()),
+                        ),
+                        (
+                            "messageBody".to_string(),
+                            Value::String(body.trim().to_string()),
+                        ),
+                    ]),
+                )])
+            })
+            .collect())
+    }
+
+    /// Gets every item of REST list `endpoint` of repository `repo` (or the
+    /// current one), e.g. `pulls/12/files`, as `gh api --paginate` would.
+    ///
+    /// # Errors
+    /// Returns an error if any page can't be fetched.
+    pub fn repo_pages(
+        &self,
+        repo: Option<&str>,
+        endpoint: &str,
+    ) -> Result<Vec<Value>, Error> {
+        let (owner, name) = self.repo_or_current(repo)?;
+        self.rest_pages(&format!("repos/{owner}/{name}/{endpoint}"))
+    }
+
+    /// Gets every comment of `pr`, as `{"body": ...}` objects like those of
+    /// `gh pr view --json comments`.
+    ///
+    /// # Errors
+    /// Returns an error if any page of comments can't be fetched.
+    pub fn comments(&self, pr: &PrRef) -> Result<Vec<Value>, Error> {
+        let (owner, name) = self.repo_or_current(pr.repo.as_deref())?;
+        let comments = self.rest_pages(&format!(
+            "repos/{owner}/{name}/issues/{}/comments",
+            pr.number
+        ))?;
+        Ok(comments
+            .iter()
+            .map(|comment| {
+                Value::Object(vec![(
+                    "body".to_string(),
+                    comment.get("body").clone(),
+                )])
+            })
+            .collect())
+    }
+}
+
+/// Splits `OWNER/REPO` into owner and name.
+fn split_repo(repo: &str) -> Option<(&str, &str)> {
+    repo.split_once('/').filter(|(owner, name)| {
+        !owner.is_empty() && !name.is_empty() && !name.contains('/')
+    })
+}
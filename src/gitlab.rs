@@ -0,0 +1,227 @@
+//! GitLab releases and merge requests, through the `glab` CLI.
+//!
+//! parse-notes and extract-tickets read from GitHub with `gh` by default.
+//! With `--forge gitlab`, they go through `glab api` instead, which finds
+//! the host and credentials as any `glab` command does (the repository's
+//! remote, `GITLAB_HOST`, `GITLAB_TOKEN`):
+//! - Release notes are the `description` of `projects/:id/releases/TAG`
+//! - Releases of a `--since-tag` range are listed from `projects/:id/releases`
+//! - A merge request is read from `projects/:id/merge_requests/N` with its
+//!   notes and commits, and rewritten into the shape of a `gh pr view --json`
+//!   response, so caching and ticket extraction treat it like a GitHub PR
+//!
+//! `:id` is the project of the current repository. A repo-qualified
+//! reference (`group/project!123`, passed between stages as
+//! `group/project#123`) names another project by its full path.
+
+use std::process::Command;
+
+use crate::cache::{ApiBudget, RateLimiter};
+use crate::error::Error;
+use crate::exit_status;
+use crate::json::{self, Value};
+use crate::protocol::PrRef;
+use crate::utils;
+
+/// Items requested per page of a list endpoint (GitLab's maximum).
+const PER_PAGE: usize = 100;
+
+/// Fetches the release notes of release `tag` of the current project.
+///
+/// # Errors
+/// Returns an error if `glab` fails (e.g. there is no such release) or
+/// returns invalid JSON.
+pub fn release_notes(tag: &str) -> Result<String, Error> {
+    let endpoint =
+        format!("projects/:id/releases/{}", utils::percent_encode(tag));
+    let release = api(&endpoint, None, None).map_err(|e| {
+        e.context(format!("Failed to get release notes for tag {tag}"))
+    })?;
+    Ok(release.get("description").str_or_empty().to_string())
+}
+
+/// The full path of the current project, e.g. `group/project`.
+///
+/// # Errors
+/// Returns an error if `glab` fails or returns invalid JSON.
+pub fn current_project() -> Result<String, Error> {
+    let project = api("projects/:id", None, None)
+        .map_err(|e| e.context("Failed to get the current project"))?;
+    Ok(project
+        .get("path_with_namespace")
+        .str_or_empty()
+        .to_string())
+}
+
+/// The tags of the releases of the current project, newest first, without
+/// upcoming releases (whose release date is still ahead).
+///
+/// # Errors
+/// Returns an error if `glab` fails or returns invalid JSON.
+pub fn release_tags() -> Result<Vec<String>, Error> {
+    let releases = pages("projects/:id/releases", &|endpoint| {
+        api(endpoint, None, None)
+    })
+    .map_err(|e| e.context("Failed to list releases"))?;
+    Ok(releases
+        .iter()
+        .filter(|release| {
+            release.get("upcoming_release").as_bool() != Some(true)
+        })
+        .map(|release| release.get("tag_name").str_or_empty().to_string())
+        .collect())
+}
+
+/// Fetches merge request `mr` with its notes and commits, as a
+/// `gh pr view --json state,isDraft,mergedAt,title,body,comments,commits`
+/// response.
+///
+/// System notes ("changed the description", "mentioned in commit ...") are
+/// left out, and the others are listed oldest first. With
+/// `--max-comments 0`, notes aren't requested at all. Every page of notes
+/// and commits is one more request, claimed from `budget`.
+///
+/// # Errors
+/// Returns an error if the budget is used up, or `glab` fails or returns
+/// invalid JSON.
+pub fn fetch_mr_json(
+    mr: &PrRef,
+    limiter: Option<&RateLimiter>,
+    budget: Option<&ApiBudget>,
+    max_comments: Option<usize>,
+) -> Result<String, Error> {
+    let base = format!("projects/{}/merge_requests/{}", project(mr), mr.number);
+    let request = |endpoint: &str| {
+        api(endpoint, limiter, budget)
+            .map_err(|e| e.context(format!("Failed to get MR {mr}")))
+    };
+
+    let merge_request = request(&base)?;
+    let comments = if max_comments == Some(0) {
+        Vec::new()
+    } else {
+        pages(
+            &format!("{base}/notes?sort=asc&order_by=created_at"),
+            &request,
+        )?
+        .iter()
+        .filter(|note| note.get("system").as_bool() != Some(true))
+        .map(|note| object(vec![("body", note.get("body").clone())]))
+        .collect()
+    };
+    let commits = pages(&format!("{base}/commits"), &request)?
+        .iter()
+        .map(|commit| {
+            let message = commit.get("message").str_or_empty();
+            let (headline, body) =
+                message.split_once('\n').unwrap_or((message, ""));
+            object(vec![
+                ("messageHeadline", string(headline.trim_end())),
+                ("messageBody", string(body.trim())),
+            ])
+        })
+        .collect();
+
+    // gh reports OPEN, CLOSED or MERGED; a locked MR is still being merged.
+    let state = match merge_request.get("state").str_or_empty() {
+        "merged" => "MERGED",
+        "closed" => "CLOSED",
+        _ => "OPEN",
+    };
+    let response = object(vec![
+        ("state", string(state)),
+        (
+            "isDraft",
+            Value::Bool(merge_request.get("draft").as_bool() == Some(true)),
+        ),
+        (
+            "mergedAt",
+            string(merge_request.get("merged_at").str_or_empty()),
+        ),
+        ("title", string(merge_request.get("title").str_or_empty())),
+        (
+            "body",
+            string(merge_request.get("description").str_or_empty()),
+        ),
+        ("comments", Value::Array(comments)),
+        ("commits", Value::Array(commits)),
+    ]);
+    Ok(response.to_string())
+}
+
+/// Every item of a list endpoint, requesting page after page until one
+/// comes back short.
+fn pages(
+    endpoint: &str,
+    request: &impl Fn(&str) -> Result<Value, Error>,
+) -> Result<Vec<Value>, Error> {
+    let separator = if endpoint.contains('?') { '&' } else { '?' };
+    let mut items = Vec::new();
+    for page in 1.. {
+        let response = request(&format!(
+            "{endpoint}{separator}per_page={PER_PAGE}&page={page}"
+        ))?;
+        let count = response.items().len();
+        items.extend_from_slice(response.items());
+        if count < PER_PAGE {
+            break;
+        }
+    }
+    Ok(items)
+}
+
+/// Sends one `glab api` request, after claiming it from `budget` and
+/// waiting for `limiter`.
+///
+/// # Errors
+/// Returns an error if the budget is used up, `glab` fails or the response
+/// isn't JSON.
+fn api(
+    endpoint: &str,
+    limiter: Option<&RateLimiter>,
+    budget: Option<&ApiBudget>,
+) -> Result<Value, Error> {
+    if let Some(budget) = budget {
+        budget.spend()?;
+    }
+    if let Some(limiter) = limiter {
+        limiter.wait()?;
+    }
+    let output = Command::new("glab")
+        .args(["api", endpoint])
+        .output()
+        .map_err(|e| {
+            exit_status::spawn_error("Failed to execute glab command", e)
+        })?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = stderr.lines().next().unwrap_or("").trim();
+        return Err(Error::GitHub(format!(
+            "glab api {endpoint} failed: {reason}"
+        )));
+    }
+    let text = String::from_utf8(output.stdout)
+        .map_err(|e| Error::command(format!("Invalid UTF-8 from glab: {e}")))?;
+    json::parse(&text).map_err(|e| e.context("Invalid glab response"))
+}
+
+/// The project of `mr` in an API path: `:id` (the current project) or its
+/// URL-encoded full path.
+fn project(mr: &PrRef) -> String {
+    mr.repo
+        .as_deref()
+        .map_or_else(|| ":id".to_string(), utils::percent_encode)
+}
+
+fn object(members: Vec<(&str, Value)>) -> Value {
+    Value::Object(
+        members
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect(),
+    )
+}
+
+fn string(value: &str) -> Value {
+    Value::String(value.to_string())
+}
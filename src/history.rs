@@ -0,0 +1,196 @@
+//! Run history store (`--history-file`).
+//!
+//! update-tickets appends one line per processed ticket to the history file,
+//! so later runs can see what earlier runs, and earlier releases, did with a
+//! ticket. The file is shared across runs (e.g. cached between CI jobs) and
+//! only ever appended to.
+//!
+//! ## Repeat Releases
+//! A ticket completed in the current release that an earlier release's run
+//! already completed usually means a cherry-pick, or release notes that
+//! picked up unrelated PRs. [`History::earlier_releases`] finds those
+//! releases so the run can flag the ticket.
+//!
+//! ## Quarantine
+//! A ticket that fails in every run (deleted, or in a team the API key can't
+//! reach) otherwise fails again each release. With `--quarantine-after N`,
+//! a ticket whose last N runs all failed it ([`History::failed_runs`]) is
+//! quarantined: skipped and listed at the end of every run until
+//! `--clear-quarantine` records a `cleared` line for it, which starts a new
+//! count.
+//!
+//! ## File Format
+//! One processed ticket per line, tab-separated:
+//! `RUN<TAB>RELEASE<TAB>TICKET<TAB>OUTCOME`, where `RUN` is the Unix time
+//! (in seconds) the run started, identifying the run, and `OUTCOME` is one of
+//! `updated`, `moved`, `already_completed`, `already_released`, `skipped`,
+//! `failed` or `cleared`. `moved` means a `--state-route` moved the ticket to a state
+//! short of completion (e.g. for a release candidate), so it doesn't count
+//! as completed by that release.
+//! Lines are appended as tickets are processed, so the file is valid even if
+//! the run is killed.
+
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::Error;
+
+/// Outcomes meaning the ticket was completed in the entry's release.
+const COMPLETED_OUTCOMES: [&str; 3] =
+    ["updated", "already_completed", "already_released"];
+
+/// Outcome of a ticket whose quarantine was cleared (`--clear-quarantine`).
+pub const CLEARED: &str = "cleared";
+
+/// One processed ticket recorded by an earlier run.
+#[derive(Debug, Clone)]
+struct Entry {
+    run: u64,
+    release: String,
+    ticket: String,
+    outcome: String,
+}
+
+/// The history of earlier runs, plus where to record this run.
+#[derive(Debug)]
+pub struct History {
+    path: String,
+    /// Start time of this run, recorded as its `RUN` column
+    run: u64,
+    /// Release processed by this run
+    release: String,
+    /// Entries of earlier runs, in file order
+    entries: Vec<Entry>,
+}
+
+impl History {
+    /// Loads the history file for a run processing `release`. A missing file
+    /// is an empty history.
+    ///
+    /// # Errors
+    /// Returns an error if the file exists but can't be read, or has a
+    /// malformed line.
+    pub fn load(path: &str, release: &str) -> Result<Self, Error> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+            Err(e) => {
+                return Err(Error::io(
+                    format!("Failed to read history file {path}"),
+                    e,
+                ));
+            }
+        };
+
+        let mut entries = Vec::new();
+        for (number, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let Some(entry) = parse_entry(line) else {
+                let number = number + 1;
+                return Err(Error::Other(format!(
+                    "Invalid line {number} in history file {path}: expected RUN, RELEASE, TICKET and OUTCOME"
+                )));
+            };
+            entries.push(entry);
+        }
+
+        let run = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        Ok(Self {
+            path: path.to_string(),
+            run,
+            release: release.to_string(),
+            entries,
+        })
+    }
+
+    /// Releases other than this run's that completed `ticket`, in the order
+    /// they were first recorded.
+    pub fn earlier_releases(&self, ticket: &str) -> Vec<&str> {
+        let mut releases: Vec<&str> = Vec::new();
+        for entry in &self.entries {
+            if entry.ticket == ticket
+                && entry.release != self.release
+                && COMPLETED_OUTCOMES.contains(&entry.outcome.as_str())
+                && !releases.contains(&entry.release.as_str())
+            {
+                releases.push(&entry.release);
+            }
+        }
+        releases
+    }
+
+    /// The number of runs in a row, most recent first, that failed `ticket`.
+    /// Runs of several releases started in the same second count once per
+    /// release.
+    pub fn failed_runs(&self, ticket: &str) -> usize {
+        let mut runs: Vec<(u64, &str)> = Vec::new();
+        for entry in self.entries.iter().rev() {
+            if entry.ticket != ticket {
+                continue;
+            }
+            if entry.outcome != "failed" {
+                break;
+            }
+            let run = (entry.run, entry.release.as_str());
+            if !runs.contains(&run) {
+                runs.push(run);
+            }
+        }
+        runs.len()
+    }
+
+    /// Starts a new count of failed runs for `ticket` in this run, as a
+    /// [`CLEARED`] line would.
+    pub fn clear(&mut self, ticket: &str) {
+        self.entries.push(Entry {
+            run: self.run,
+            release: self.release.clone(),
+            ticket: ticket.to_string(),
+            outcome: CLEARED.to_string(),
+        });
+    }
+
+    /// Appends this run's outcome for `ticket` to the history file.
+    ///
+    /// # Errors
+    /// Returns an error if the file can't be written.
+    pub fn record(&self, ticket: &str, outcome: &str) -> Result<(), Error> {
+        let path = &self.path;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| {
+                Error::io(format!("Failed to open history file {path}"), e)
+            })?;
+        writeln!(file, "{}\t{}\t{ticket}\t{outcome}", self.run, self.release)
+            .map_err(|e| {
+                Error::io(format!("Failed to write history file {path}"), e)
+            })
+    }
+}
+
+/// Parses one `RUN<TAB>RELEASE<TAB>TICKET<TAB>OUTCOME` line.
+fn parse_entry(line: &str) -> Option<Entry> {
+    let mut fields = line.split('\t');
+    let (Some(run), Some(release), Some(ticket), Some(outcome), None) = (
+        fields.next(),
+        fields.next(),
+        fields.next(),
+        fields.next(),
+        fields.next(),
+    ) else {
+        return None;
+    };
+    Some(Entry {
+        run: run.parse().ok()?,
+        release: release.to_string(),
+        ticket: ticket.to_string(),
+        outcome: outcome.to_string(),
+    })
+}
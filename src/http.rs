@@ -0,0 +1,666 @@
+//! Built-in HTTP/1.1 client for the tracker, GitHub and webhook endpoints.
+//!
+//! Requests are sent in-process, so minimal containers without `curl` can
+//! run the tool. `https://` URLs are served over TLS with rustls when the
+//! crate is built with the `native-http` feature (the default), trusting
+//! Mozilla's root certificates and those of the system's CA bundle
+//! (`SSL_CERT_FILE`, or the usual bundle paths) for internal CAs. A build
+//! without the feature (`--no-default-features`) bundles no TLS library, and
+//! sends `https://` requests through `curl` instead (see
+//! [`crate::utils::http_request`]).
+//!
+//! The client sends a single request per connection (`Connection: close`) and
+//! understands `Content-Length` and `chunked` response bodies. It honours
+//! `--linear-resolve`, and tunnels `https://` requests through the proxy of
+//! `https_proxy` (or `HTTPS_PROXY`, an `http://[USER:PASSWORD@]HOST:PORT`
+//! URL) unless `no_proxy` lists the host; plain `http://` requests don't use
+//! a proxy. Connecting gives up after [`CONNECT_TIMEOUT`], and a connection
+//! that stalls for [`IO_TIMEOUT`] fails the request. Responses larger than
+//! [`MAX_RESPONSE_BYTES`] are refused.
+
+use std::env;
+use std::fmt::Write as _;
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use crate::error::Error;
+use crate::utils;
+
+/// Longest wait for a TCP connection to a host (or its proxy).
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Longest wait for a connection to accept or deliver any data, once
+/// connected.
+pub const IO_TIMEOUT: Duration = Duration::from_mins(2);
+
+/// Largest response accepted from a tracker API, in bytes (16 MiB).
+///
+/// The tool's queries return at most a few hundred KB; a larger response is
+/// a misbehaving gateway, and is refused rather than buffered and parsed.
+pub const MAX_RESPONSE_BYTES: usize = 16 * 1024 * 1024;
+
+/// An HTTP response.
+#[derive(Debug)]
+pub struct Response {
+    /// Status code (e.g. `200`)
+    pub status: u16,
+    pub body: String,
+}
+
+/// Scheme of a URL the client sends requests to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scheme {
+    Http,
+    Https,
+}
+
+impl Scheme {
+    fn default_port(self) -> u16 {
+        match self {
+            Self::Http => 80,
+            Self::Https => 443,
+        }
+    }
+}
+
+/// A parsed `http[s]://host[:port]/path` URL.
+struct Url<'a> {
+    scheme: Scheme,
+    /// Host name or address, without the brackets of an IPv6 address
+    host: &'a str,
+    port: u16,
+    /// Path and query, starting with `/`
+    path: &'a str,
+}
+
+/// A connection a request is sent over: plain TCP, or TLS over it.
+trait Stream: Read + Write {}
+
+impl<T: Read + Write> Stream for T {}
+
+/// Whether the built-in client can send requests to `url`: any `http://`
+/// URL, and `https://` URLs when built with the `native-http` feature.
+#[must_use]
+pub fn supports(url: &str) -> bool {
+    url.starts_with("http://")
+        || (cfg!(feature = "native-http") && url.starts_with("https://"))
+}
+
+/// Sends a request, with a JSON body if one is given.
+///
+/// # Arguments
+/// * `method` - HTTP method (e.g. `GET`, `POST`)
+/// * `url` - `http://` (or `https://`) URL to send the request to
+/// * `headers` - Request headers, each as `Name: value`
+/// * `resolve` - Static resolutions as `HOST:PORT:ADDRESS`; a matching entry
+///   connects to `ADDRESS` instead of resolving `HOST`
+/// * `body` - Request body, if any
+/// * `invalid` - Error of a response that is too large or can't be parsed,
+///   naming the API that sent it, e.g. [`Error::LinearApi`]
+///
+/// # Errors
+/// Returns an error if the URL isn't a supported URL, the connection or TLS
+/// handshake fails or times out, or the response can't be parsed. Non-2xx
+/// statuses are returned as a [`Response`], not as an error.
+pub fn send(
+    method: &str,
+    url: &str,
+    headers: &[String],
+    resolve: &[String],
+    body: Option<&str>,
+    invalid: fn(String) -> Error,
+) -> Result<Response, Error> {
+    let parsed = parse_url(url)?;
+    let mut stream = connect(&parsed, resolve)
+        .map_err(|e| Error::io(format!("Failed to connect to {url}"), e))?;
+
+    let host = if parsed.port == parsed.scheme.default_port() {
+        bracketed(parsed.host)
+    } else {
+        format!("{}:{}", bracketed(parsed.host), parsed.port)
+    };
+    let mut request =
+        format!("{method} {} HTTP/1.1\r\nHost: {host}\r\n", parsed.path);
+    for header in headers {
+        request.push_str(header);
+        request.push_str("\r\n");
+    }
+    if let Some(body) = body {
+        let _ = write!(request, "Content-Length: {}\r\n", body.len());
+    }
+    let _ = write!(
+        request,
+        "Connection: close\r\n\r\n{}",
+        body.unwrap_or_default()
+    );
+    stream
+        .write_all(request.as_bytes())
+        .and_then(|()| stream.flush())
+        .map_err(|e| {
+            Error::io(format!("Failed to send request to {url}"), e)
+        })?;
+
+    let raw = read_response(&mut stream).map_err(|e| {
+        Error::io(format!("Failed to read response from {url}"), e)
+    })?;
+    if raw.len() > MAX_RESPONSE_BYTES {
+        return Err(invalid(format!(
+            "Response from {url} exceeds {MAX_RESPONSE_BYTES} bytes"
+        )));
+    }
+    parse_response(&raw)
+        .map_err(|e| invalid(format!("Invalid response from {url}: {e}")))
+}
+
+/// Parses an `http[s]://host[:port]/path` URL, whose host may be an IPv6
+/// address in brackets (`http://[::1]:8080/`).
+fn parse_url(url: &str) -> Result<Url<'_>, Error> {
+    let (scheme, rest) = if let Some(rest) = url.strip_prefix("http://") {
+        (Scheme::Http, rest)
+    } else if let Some(rest) = url.strip_prefix("https://") {
+        (Scheme::Https, rest)
+    } else {
+        return Err(Error::Config(format!(
+            "Not an http:// or https:// URL: {url}"
+        )));
+    };
+    let (authority, path) = match rest.find('/') {
+        Some(idx) => rest.split_at(idx),
+        None => (rest, "/"),
+    };
+    let (host, port) = if let Some(rest) = authority.strip_prefix('[') {
+        let (host, after) = rest.split_once(']').ok_or_else(|| {
+            Error::Config(format!("Unterminated IPv6 address in URL: {url}"))
+        })?;
+        let port = match after {
+            "" => None,
+            after => Some(after.strip_prefix(':').ok_or_else(|| {
+                Error::Config(format!("Invalid port in URL: {url}"))
+            })?),
+        };
+        (host, port)
+    } else {
+        match authority.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        }
+    };
+    let port = match port {
+        Some(port) => port.parse::<u16>().map_err(|_| {
+            Error::Config(format!("Invalid port in URL: {url}"))
+        })?,
+        None => scheme.default_port(),
+    };
+    if host.is_empty() {
+        return Err(Error::Config(format!("Missing host in URL: {url}")));
+    }
+    Ok(Url {
+        scheme,
+        host,
+        port,
+        path,
+    })
+}
+
+/// `host` as written in a `Host` header or `CONNECT` request, with an IPv6
+/// address in brackets.
+fn bracketed(host: &str) -> String {
+    if host.contains(':') {
+        format!("[{host}]")
+    } else {
+        host.to_string()
+    }
+}
+
+/// Connects to the host of `url`: directly (or to its `resolve` address),
+/// or for `https://` through the `https_proxy`, over TLS for `https://`.
+fn connect(url: &Url<'_>, resolve: &[String]) -> io::Result<Box<dyn Stream>> {
+    let proxy = match url.scheme {
+        Scheme::Http => None,
+        Scheme::Https => https_proxy(url.host),
+    };
+    let tcp = if let Some(ref proxy) = proxy {
+        tunnel(proxy, url.host, url.port)?
+    } else {
+        let address = resolve
+            .iter()
+            .find_map(|entry| resolved_address(entry, url.host, url.port))
+            .unwrap_or(url.host);
+        tcp_connect(address, url.port)?
+    };
+    match url.scheme {
+        Scheme::Http => Ok(Box::new(tcp)),
+        Scheme::Https => tls::wrap(tcp, url.host),
+    }
+}
+
+/// Opens a TCP connection to `host`:`port`, trying each of its addresses
+/// for at most [`CONNECT_TIMEOUT`], with [`IO_TIMEOUT`] on reads and writes.
+fn tcp_connect(host: &str, port: u16) -> io::Result<TcpStream> {
+    let mut failure = None;
+    for address in (host, port).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&address, CONNECT_TIMEOUT) {
+            Ok(stream) => {
+                stream.set_read_timeout(Some(IO_TIMEOUT))?;
+                stream.set_write_timeout(Some(IO_TIMEOUT))?;
+                return Ok(stream);
+            }
+            Err(e) => failure = Some(e),
+        }
+    }
+    Err(failure.unwrap_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{host} has no address"),
+        )
+    }))
+}
+
+/// The address of a `HOST:PORT:ADDRESS` resolve entry, if it's for
+/// `host`:`port`.
+fn resolved_address<'a>(
+    entry: &'a str,
+    host: &str,
+    port: u16,
+) -> Option<&'a str> {
+    let mut fields = entry.splitn(3, ':');
+    let (entry_host, entry_port, address) =
+        (fields.next()?, fields.next()?, fields.next()?);
+    (entry_host == host && entry_port.parse::<u16>().ok()? == port)
+        .then(|| address.trim_start_matches('[').trim_end_matches(']'))
+}
+
+/// The proxy `https://` requests to `host` go through: `https_proxy` (or
+/// `HTTPS_PROXY`), unless `host` matches an entry of `no_proxy` (or
+/// `NO_PROXY`): `*`, the host, or a domain it is under.
+fn https_proxy(host: &str) -> Option<String> {
+    let variable = |name: &str| {
+        env::var(name)
+            .or_else(|_| env::var(name.to_ascii_uppercase()))
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+    };
+    let proxy = variable("https_proxy")?;
+    let host = host.to_ascii_lowercase();
+    let bypassed = variable("no_proxy").is_some_and(|list| {
+        list.split(',').any(|entry| {
+            let domain = entry.trim().trim_start_matches('.').to_lowercase();
+            domain == "*"
+                || (!domain.is_empty()
+                    && (host == domain
+                        || host.ends_with(&format!(".{domain}"))))
+        })
+    });
+    (!bypassed).then_some(proxy)
+}
+
+/// Opens a `CONNECT` tunnel to `host`:`port` through `proxy`, an
+/// `http://[USER:PASSWORD@]HOST[:PORT]` URL (port 80 by default).
+fn tunnel(proxy: &str, host: &str, port: u16) -> io::Result<TcpStream> {
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid https_proxy {proxy}: expected http://HOST:PORT"),
+        )
+    };
+    let authority = proxy
+        .strip_prefix("http://")
+        .unwrap_or(proxy)
+        .trim_end_matches('/');
+    let (credentials, address) = match authority.rsplit_once('@') {
+        Some((credentials, address)) => (Some(credentials), address),
+        None => (None, authority),
+    };
+    let (proxy_host, proxy_port) = match address.rsplit_once(':') {
+        Some((proxy_host, proxy_port)) => {
+            (proxy_host, proxy_port.parse().map_err(|_| invalid())?)
+        }
+        None => (address, 80),
+    };
+    if proxy_host.is_empty() {
+        return Err(invalid());
+    }
+
+    let mut stream = tcp_connect(proxy_host, proxy_port)?;
+    let target = format!("{}:{port}", bracketed(host));
+    let mut request =
+        format!("CONNECT {target} HTTP/1.1\r\nHost: {target}\r\n");
+    if let Some(credentials) = credentials {
+        let _ = write!(
+            request,
+            "Proxy-Authorization: Basic {}\r\n",
+            utils::base64_encode(credentials)
+        );
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes())?;
+
+    // Read the proxy's answer byte by byte, leaving what follows it (the
+    // server's side of the tunnel) unread.
+    let mut head = Vec::new();
+    let mut byte = [0];
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() > 8 * 1024 || stream.read(&mut byte)? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("proxy {address} closed the CONNECT tunnel"),
+            ));
+        }
+        head.push(byte[0]);
+    }
+    let head = String::from_utf8_lossy(&head);
+    let status_line = head.lines().next().unwrap_or_default();
+    if status_line
+        .split_whitespace()
+        .nth(1)
+        .is_some_and(|code| code.len() == 3 && code.starts_with('2'))
+    {
+        Ok(stream)
+    } else {
+        Err(io::Error::other(format!(
+            "proxy {address} refused to tunnel to {target}: {status_line}"
+        )))
+    }
+}
+
+/// Reads a response from `stream` until it is complete, or the connection
+/// closes, or more than [`MAX_RESPONSE_BYTES`] are received.
+fn read_response(stream: &mut dyn Stream) -> io::Result<Vec<u8>> {
+    let mut raw = Vec::new();
+    let mut buffer = [0; 16 * 1024];
+    loop {
+        let read = match stream.read(&mut buffer) {
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            // A TLS server closing without `close_notify` ends a response
+            // the way closing a plain connection does, if only closing the
+            // connection ends its body. Otherwise the response was cut
+            // short.
+            Err(e)
+                if e.kind() == io::ErrorKind::UnexpectedEof
+                    && ends_at_close(&raw) =>
+            {
+                0
+            }
+            Err(e) => return Err(e),
+        };
+        if read == 0 {
+            return Ok(raw);
+        }
+        raw.extend_from_slice(&buffer[..read]);
+        if raw.len() > MAX_RESPONSE_BYTES || is_complete(&raw) {
+            return Ok(raw);
+        }
+    }
+}
+
+/// Whether the body of the response starting with `raw` ends when the
+/// connection closes: it has neither a `Content-Length` nor a `chunked`
+/// body.
+fn ends_at_close(raw: &[u8]) -> bool {
+    parse_head(raw)
+        .is_ok_and(|head| !head.chunked && head.content_length.is_none())
+}
+
+/// Whether `raw` holds a whole response: its headers, and as many body bytes
+/// as its `Content-Length` or the last chunk of a `chunked` body. Other
+/// bodies end when the connection closes.
+fn is_complete(raw: &[u8]) -> bool {
+    let Ok(head) = parse_head(raw) else {
+        return false;
+    };
+    let body = &raw[head.body_start..];
+    if head.chunked {
+        // The last chunk (and any trailers) end in an empty line.
+        body.ends_with(b"\r\n\r\n") && decode_chunked(body).is_ok()
+    } else {
+        head.content_length
+            .is_some_and(|length| body.len() >= length)
+    }
+}
+
+/// The status line and headers of a response.
+struct Head {
+    status: u16,
+    chunked: bool,
+    content_length: Option<usize>,
+    /// Offset of the body in the response
+    body_start: usize,
+}
+
+/// Parses the status line and headers of a response, or tells what's wrong
+/// with them.
+fn parse_head(raw: &[u8]) -> Result<Head, String> {
+    let header_end = raw
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .ok_or("no end of headers")?;
+    let head = std::str::from_utf8(&raw[..header_end])
+        .map_err(|_| "headers are not UTF-8".to_string())?;
+    let mut lines = head.split("\r\n");
+
+    // Status line: `HTTP/1.1 200 OK`
+    let status = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or("no status code")?;
+
+    let mut chunked = false;
+    let mut content_length = None;
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("transfer-encoding") {
+            chunked = value.eq_ignore_ascii_case("chunked");
+        } else if name.eq_ignore_ascii_case("content-length") {
+            content_length = value.parse::<usize>().ok();
+        }
+    }
+    Ok(Head {
+        status,
+        chunked,
+        content_length,
+        body_start: header_end + 4,
+    })
+}
+
+/// Parses a complete HTTP/1.1 response, or tells what's wrong with it.
+fn parse_response(raw: &[u8]) -> Result<Response, String> {
+    let head = parse_head(raw)?;
+    let rest = &raw[head.body_start..];
+    let body = if head.chunked {
+        decode_chunked(rest)?
+    } else {
+        let length = head.content_length.unwrap_or(rest.len());
+        rest.get(..length)
+            .ok_or_else(|| {
+                format!("body is truncated: {} of {length} bytes", rest.len())
+            })?
+            .to_vec()
+    };
+    let body =
+        String::from_utf8(body).map_err(|_| "body is not UTF-8".to_string())?;
+    Ok(Response {
+        status: head.status,
+        body,
+    })
+}
+
+/// Decodes a `Transfer-Encoding: chunked` body, or tells what's wrong with
+/// it.
+fn decode_chunked(mut rest: &[u8]) -> Result<Vec<u8>, String> {
+    let mut body = Vec::new();
+    loop {
+        let line_end = rest
+            .windows(2)
+            .position(|window| window == b"\r\n")
+            .ok_or("truncated chunk size")?;
+        let size_line = std::str::from_utf8(&rest[..line_end])
+            .map_err(|_| "invalid chunk size".to_string())?;
+        // Chunk extensions (`;name=value`) are ignored.
+        let size_hex = size_line.split(';').next().unwrap_or("").trim();
+        let size = usize::from_str_radix(size_hex, 16)
+            .map_err(|_| format!("invalid chunk size {size_hex}"))?;
+        rest = &rest[line_end + 2..];
+        if size == 0 {
+            return Ok(body);
+        }
+        let chunk = rest.get(..size).ok_or("truncated chunk")?;
+        body.extend_from_slice(chunk);
+        rest = rest.get(size + 2..).ok_or("truncated chunk")?;
+    }
+}
+
+/// TLS over a connection, with rustls (the `native-http` feature).
+#[cfg(feature = "native-http")]
+mod tls {
+    use std::io;
+    use std::net::TcpStream;
+    use std::path::Path;
+    use std::sync::{Arc, OnceLock};
+
+    use rustls::pki_types::pem::PemObject;
+    use rustls::pki_types::{CertificateDer, ServerName};
+    use rustls::{ClientConfig, ClientConnection, RootCertStore, StreamOwned};
+
+    use super::Stream;
+
+    /// CA bundles of common distributions, the first one found trusted
+    /// unless `SSL_CERT_FILE` names one.
+    const CA_BUNDLES: [&str; 4] = [
+        "/etc/ssl/certs/ca-certificates.crt",
+        "/etc/pki/tls/certs/ca-bundle.crt",
+        "/etc/ssl/ca-bundle.pem",
+        "/etc/ssl/cert.pem",
+    ];
+
+    /// Starts a TLS session with `host` over `tcp`. The handshake happens
+    /// on the first write.
+    pub fn wrap(tcp: TcpStream, host: &str) -> io::Result<Box<dyn Stream>> {
+        let name = ServerName::try_from(host.to_string())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let connection = ClientConnection::new(client_config()?, name)
+            .map_err(io::Error::other)?;
+        Ok(Box::new(StreamOwned::new(connection, tcp)))
+    }
+
+    /// The TLS settings of every connection, built on first use.
+    fn client_config() -> io::Result<Arc<ClientConfig>> {
+        static CONFIG: OnceLock<Arc<ClientConfig>> = OnceLock::new();
+        if let Some(config) = CONFIG.get() {
+            return Ok(Arc::clone(config));
+        }
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let config = ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .map_err(io::Error::other)?
+            .with_root_certificates(root_store())
+            .with_no_client_auth();
+        Ok(Arc::clone(CONFIG.get_or_init(|| Arc::new(config))))
+    }
+
+    /// Mozilla's root certificates, and those of `SSL_CERT_FILE` or the
+    /// system's CA bundle. Certificates that can't be parsed are skipped.
+    fn root_store() -> RootCertStore {
+        let mut roots = RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        };
+        let bundle = std::env::var("SSL_CERT_FILE").ok().or_else(|| {
+            CA_BUNDLES
+                .iter()
+                .find(|path| Path::new(path).is_file())
+                .map(|path| (*path).to_string())
+        });
+        if let Some(certs) =
+            bundle.and_then(|path| CertificateDer::pem_file_iter(path).ok())
+        {
+            roots.add_parsable_certificates(certs.filter_map(Result::ok));
+        }
+        roots
+    }
+}
+
+/// Without the `native-http` feature, `https://` URLs go through `curl` (see
+/// [`supports`]).
+#[cfg(not(feature = "native-http"))]
+mod tls {
+    use std::io;
+    use std::net::TcpStream;
+
+    use super::Stream;
+
+    pub fn wrap(_tcp: TcpStream, _host: &str) -> io::Result<Box<dyn Stream>> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "built without the native-http feature",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_chunked_bodies() -> Result<(), String> {
+        let body =
+            decode_chunked(b"5\r\nhello\r\n7;ext=1\r\n, world\r\n0\r\n\r\n")?;
+        assert_eq!(body, b"hello, world");
+        assert!(decode_chunked(b"5\r\nhel").is_err());
+        assert!(decode_chunked(b"5\r\nhello\r\n").is_err());
+        assert!(decode_chunked(b"zz\r\nhello\r\n0\r\n\r\n").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn parses_responses() -> Result<(), String> {
+        let response = parse_response(
+            b"HTTP/1.1 201 Created\r\nContent-Length: 2\r\n\r\nok",
+        )?;
+        assert_eq!((response.status, response.body.as_str()), (201, "ok"));
+
+        let response = parse_response(
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n2\r\nok\r\n0\r\n\r\n",
+        )?;
+        assert_eq!(response.body, "ok");
+
+        // Without a Content-Length, the body runs to the end.
+        let response = parse_response(b"HTTP/1.1 200 OK\r\n\r\nall of it")?;
+        assert_eq!(response.body, "all of it");
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_truncated_responses() {
+        let truncated = b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nshort";
+        assert!(parse_response(truncated).is_err());
+        assert!(!ends_at_close(truncated));
+        assert!(ends_at_close(b"HTTP/1.1 200 OK\r\n\r\nshort"));
+        assert!(parse_response(b"HTTP/1.1 200 OK\r\n").is_err());
+    }
+
+    #[test]
+    fn parses_urls() -> Result<(), Error> {
+        let url = parse_url("https://api.linear.app/graphql")?;
+        assert_eq!(
+            (url.scheme, url.host, url.port, url.path),
+            (Scheme::Https, "api.linear.app", 443, "/graphql")
+        );
+        let url = parse_url("http://localhost:8080")?;
+        assert_eq!((url.host, url.port, url.path), ("localhost", 8080, "/"));
+        let url = parse_url("http://[::1]/hook")?;
+        assert_eq!((url.host, url.port, url.path), ("::1", 80, "/hook"));
+        let url = parse_url("http://[fe80::1]:8080/")?;
+        assert_eq!((url.host, url.port), ("fe80::1", 8080));
+        assert_eq!(bracketed(url.host), "[fe80::1]");
+        assert!(parse_url("http://[::1/").is_err());
+        assert!(parse_url("http://[::1]x/").is_err());
+        assert!(parse_url("http://host:port/").is_err());
+        assert!(parse_url("ftp://host/").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,583 @@
+//! Jira backend of update-tickets (`--tracker jira`).
+//!
+//! Transitions Jira issues (e.g. `PROJ-123`) to a done status through the
+//! Jira REST API (v2, which Jira Cloud and Data Center both serve), reading
+//! the same input as the Linear backend: ticket IDs or ndjson ticket records,
+//! one per line.
+//!
+//! ## Process
+//! For each issue key:
+//! 1. Query the issue's status (`GET /rest/api/2/issue/KEY?fields=status`)
+//! 2. Skip if its status is in the `done` category
+//! 3. Skip unless its status name is "Passing" or one of the `--from-states`
+//!    (or `--update-all-statuses`)
+//! 4. Find a transition to a status in the `done` category, preferring one
+//!    whose name contains "Done" or "Completed"
+//!    (`GET /rest/api/2/issue/KEY/transitions`)
+//! 5. Perform it (unless dry-run)
+//!
+//! Jira has no per-team completed state to look up: which statuses an issue
+//! can reach depends on its workflow, so the transition is chosen per issue.
+//!
+//! ## Authentication
+//! `--jira-base-url` (or `JIRA_BASE_URL`) is the site URL, e.g.
+//! `https://acme.atlassian.net`. `--jira-api-token` (or `JIRA_API_TOKEN`) is
+//! sent as a bearer token (a Data Center personal access token), or, with
+//! `--jira-email` (or `JIRA_EMAIL`), with Basic authentication as Jira Cloud
+//! expects.
+//!
+//! ## Output
+//! As with Linear: processed issues are printed as their browse URL
+//! (`BASE/browse/KEY`), ID or Markdown link per `--url-format`, or as result
+//! records with `--output-format ndjson|json`, followed by the localized
+//! summary. With `--github-output`, the counts and the updated issues' URLs
+//! are also written for GitHub Actions (see [`crate::action_outputs`]).
+
+use std::time::{Duration, Instant};
+
+use crate::action_outputs::{self, RunResults};
+use crate::cache::{ApiBudget, RateLimiter};
+use crate::config::{Config, GithubOutput, UrlFormat};
+use crate::error::Error;
+use crate::estimate::{self, Estimate, Provider, Request};
+use crate::explain::Subject;
+use crate::json;
+use crate::locale::Messages;
+use crate::protocol::{
+    Input, PrRef, RecordWriter, SkipReason, TicketInput, UpdateRecord,
+};
+use crate::tickets;
+use crate::update_tickets;
+use crate::utils::{self, NoInputTimeout};
+
+const NAME: &str = "update-tickets";
+
+/// `statusCategory.key` of Jira's done statuses.
+const DONE_CATEGORY: &str = "done";
+
+macro_rules! log {
+    ($level:ident, $fmt:expr) => {
+        utils::log_at(utils::LogLevel::$level, NAME, format_args!($fmt));
+    };
+    ($fmt:expr) => {
+        utils::log(NAME, format_args!($fmt));
+    };
+}
+
+/// The Jira site and credentials given as flags, each falling back to its
+/// environment variable (see [`Config::jira_api`]).
+#[derive(Debug, Default)]
+pub struct JiraCredentials {
+    /// Site URL (`--jira-base-url`)
+    pub base_url: Option<String>,
+    /// API token (`--jira-api-token`)
+    pub api_token: Option<String>,
+    /// Account email for Basic authentication (`--jira-email`)
+    pub email: Option<String>,
+}
+
+/// Connection settings for the Jira REST API.
+#[derive(Debug, Clone)]
+pub struct JiraApi {
+    /// Site URL without a trailing slash, e.g. `https://acme.atlassian.net`
+    pub base_url: String,
+    /// Value of the `Authorization` header (`Bearer ...` or `Basic ...`)
+    pub authorization: String,
+    /// Rate limiter shared with concurrent pipelines (`--rate-limit`)
+    pub limiter: Option<RateLimiter>,
+    /// Request budget of the run (`--api-budget`)
+    pub budget: Option<ApiBudget>,
+    /// File that unparseable responses are appended to (`--debug-dump`)
+    pub debug_dump: Option<String>,
+}
+
+impl JiraApi {
+    /// Sends a request to `BASE/rest/api/2/PATH`.
+    ///
+    /// # Returns
+    /// The parsed JSON response; `Null` for an empty body (e.g. `204 No
+    /// Content` after a transition).
+    ///
+    /// # Errors
+    /// Returns an error if the request budget is used up (nothing is sent),
+    /// the request fails, the response status isn't 2xx (the error carries
+    /// the status and response body), or the body isn't valid JSON (it's
+    /// then appended to `debug_dump`, if set).
+    fn request(
+        &self,
+        method: &str,
+        path: &str,
+        body: Option<&str>,
+    ) -> Result<json::Value, Error> {
+        if let Some(ref budget) = self.budget {
+            budget.spend()?;
+        }
+        if let Some(ref limiter) = self.limiter {
+            limiter.wait()?;
+        }
+
+        let headers = [
+            "Accept: application/json".to_string(),
+            "Content-Type: application/json".to_string(),
+            format!("Authorization: {}", self.authorization),
+        ];
+        let url = format!("{}/rest/api/2/{path}", self.base_url);
+        estimate::count(Provider::Jira);
+        let response = utils::retry_rate_limited(
+            "Jira",
+            self.limiter.as_ref(),
+            || {
+                utils::http_request(
+                    method,
+                    &url,
+                    &headers,
+                    &[],
+                    body,
+                    Error::LinearApi,
+                )
+            },
+            utils::is_rate_limited,
+        )
+        .map_err(|e| e.context(format!("Jira {method} {path} failed")))?;
+        match response.status {
+            200..300 => {}
+            404 => {
+                return Err(Error::LinearApi(format!(
+                    "{} (or not visible with this API token)",
+                    tickets::NOT_FOUND
+                )));
+            }
+            status => {
+                let body = response.body.trim();
+                let message =
+                    format!("Jira API returned HTTP {status}: {body}");
+                return Err(if status == 401 {
+                    Error::Credentials(message)
+                } else {
+                    Error::LinearApi(message)
+                });
+            }
+        }
+        if response.body.trim().is_empty() {
+            return Ok(json::Value::Null);
+        }
+        json::parse(&response.body).map_err(|e| {
+            let request = format!("{method} {path}");
+            let dumped = utils::dump_response(
+                self.debug_dump.as_deref(),
+                &request,
+                &response.body,
+            );
+            Error::LinearApi(format!(
+                "Invalid Jira API response to {request}: {e} (response starts: {}){dumped}",
+                utils::excerpt(&response.body)
+            ))
+        })
+    }
+
+    /// The issue's browse URL, e.g. `https://acme.atlassian.net/browse/PROJ-1`.
+    fn browse_url(&self, issue_key: &str) -> String {
+        format!("{}/browse/{issue_key}", self.base_url)
+    }
+}
+
+/// What happened to a single issue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    /// The issue was transitioned to done (or would be, in dry-run)
+    Updated,
+    /// The issue's status was already in the done category
+    AlreadyCompleted,
+    /// The issue was excluded or wasn't in an eligible status
+    Skipped(SkipReason),
+}
+
+impl Outcome {
+    /// The outcome's name in result records.
+    fn name(self) -> &'static str {
+        match self {
+            Outcome::Updated => "updated",
+            Outcome::AlreadyCompleted => "already_completed",
+            Outcome::Skipped(_) => "skipped",
+        }
+    }
+}
+
+/// The result of processing one issue.
+struct Processed {
+    outcome: Outcome,
+    /// Status name when the issue was looked up
+    from_state: String,
+    /// Status the issue was (or would be) transitioned to
+    to_state: Option<String>,
+}
+
+/// Issue counts and output accumulated over the run.
+struct RunState {
+    output: RecordWriter,
+    updated: usize,
+    already_completed: usize,
+    skipped: usize,
+    failed: usize,
+    /// Of the failed issues, those that don't exist (or aren't visible)
+    not_found: usize,
+    any_output: bool,
+    /// Browse URLs of the updated issues, for `--github-output`
+    updated_urls: Vec<String>,
+}
+
+/// Runs update-tickets against Jira.
+///
+/// # Input
+/// Reads Jira issue keys (or ndjson ticket records) from input sources
+/// (stdin or files), one per line, applying `--prefix-map`.
+///
+/// # Output
+/// Prints processed issues to stdout, as update-tickets does for Linear (see
+/// the module documentation), then logs the summary.
+///
+/// # Errors
+/// Returns an error if:
+/// - `JIRA_BASE_URL` or `JIRA_API_TOKEN` is not provided
+/// - The locale templates can't be loaded
+/// - Input sources cannot be read
+///
+/// Individual issues that fail are logged to stderr and counted, without
+/// stopping the run; with `--fail-on-partial`, the run then fails if any
+/// did.
+pub fn run(config: &Config) -> Result<(), Error> {
+    let started = Instant::now();
+    let jira = config.jira_api()?;
+    let messages =
+        Messages::load(config.locale.as_deref(), config.locale_dir.as_deref())?;
+
+    if config.dry_run {
+        log!(
+            "Dry-run mode enabled. The following issues would be transitioned to Done:"
+        );
+    }
+
+    let mut state = RunState {
+        output: RecordWriter::new(config.output_format, config.output.clone()),
+        updated: 0,
+        already_completed: 0,
+        skipped: 0,
+        failed: 0,
+        not_found: 0,
+        any_output: false,
+        updated_urls: Vec::new(),
+    };
+    log!("reading ticket IDs from input");
+    let no_input = NoInputTimeout {
+        secs: config.stdin_timeout_secs,
+        expected: "ticket IDs",
+    };
+    utils::for_each_input(&config.input_sources, no_input, |input| {
+        let Some((issue_key, pr)) = read_issue_key(config, &input) else {
+            return Ok(());
+        };
+        let subject = Subject::Ticket(issue_key.clone());
+        utils::with_log_subject(subject, || {
+            process_issue(config, &jira, &issue_key, pr, &mut state)
+        })
+    })?;
+    state.output.finish();
+
+    log!("done");
+    report_summary(config, &messages, &state, started.elapsed());
+    if config.dry_run {
+        // One transition per issue that would be moved
+        let estimate = Estimate::default();
+        estimate.add(Provider::Jira, Request::Mutation, state.updated as u64);
+        estimate.report(NAME);
+    }
+    if config.github_output == GithubOutput::Write {
+        action_outputs::write(&RunResults {
+            release_tag: config.release_tag.as_deref(),
+            dry_run: config.dry_run,
+            updated: state.updated,
+            already_completed: state.already_completed,
+            skipped: state.skipped,
+            failed: state.failed,
+            urls: state.updated_urls,
+        })?;
+    }
+    update_tickets::check_failures(config, state.failed)
+}
+
+/// Parses one input line into an issue key, logging invalid input and
+/// applying `--prefix-map` and `--exclude-pattern`.
+///
+/// # Returns
+/// The key and the PR named by a ticket record, or `None` for blank lines
+/// and invalid keys (which are logged to stderr).
+fn read_issue_key(
+    config: &Config,
+    input: &Input,
+) -> Option<(String, Option<PrRef>)> {
+    match input.ticket() {
+        Ok(None) => None,
+        Ok(Some(TicketInput { ticket, pr, .. }))
+            if is_valid_issue_key(&ticket) =>
+        {
+            let remapped = config.prefix_map.apply(&ticket);
+            if remapped != ticket {
+                log!(Verbose, "remapped {ticket} to {remapped} (--prefix-map)");
+            }
+            Some((remapped, pr))
+        }
+        Ok(Some(_)) => {
+            log!(
+                Warn,
+                "Invalid input {input}: Expected Jira issue key like PROJ-123"
+            );
+            None
+        }
+        Err(e) => {
+            log!(Warn, "Invalid input {input}: {e}");
+            None
+        }
+    }
+}
+
+/// Processes one issue: prints it (or its result record) and counts its
+/// outcome, logging failures.
+///
+/// # Errors
+/// Returns an error if the issue's result can't be written.
+fn process_issue(
+    config: &Config,
+    jira: &JiraApi,
+    issue_key: &str,
+    pr: Option<PrRef>,
+    state: &mut RunState,
+) -> Result<(), Error> {
+    let url = jira.browse_url(issue_key);
+    if update_tickets::exclude_ticket(config, issue_key) {
+        state.skipped += 1;
+        return state.output.write(None, || UpdateRecord {
+            ticket: issue_key.to_string(),
+            url,
+            outcome: "skipped",
+            skip_reason: Some(SkipReason::Excluded),
+            from_state: None,
+            to_state: None,
+            pr,
+            error: None,
+            dry_run: config.dry_run,
+        });
+    }
+    log!("processing {url}");
+
+    let result = complete_issue(config, jira, issue_key);
+    let print = match result {
+        Ok(ref processed) => {
+            match processed.outcome {
+                Outcome::Updated => {
+                    state.updated += 1;
+                    state.updated_urls.push(url.clone());
+                }
+                Outcome::AlreadyCompleted => state.already_completed += 1,
+                Outcome::Skipped(_) => state.skipped += 1,
+            }
+            // In dry-run, only issues that would be updated are printed.
+            match processed.outcome {
+                Outcome::Updated => true,
+                Outcome::AlreadyCompleted => !config.dry_run,
+                Outcome::Skipped(_) => false,
+            }
+        }
+        Err(ref e) => {
+            log!(Warn, "Failed to update {url}: {e}");
+            log!(Warn, "{url}");
+            state.failed += 1;
+            if e.root().to_string().starts_with(tickets::NOT_FOUND) {
+                state.not_found += 1;
+            }
+            false
+        }
+    };
+    state.any_output |= print;
+
+    let text = print.then(|| output_line(config, jira, issue_key));
+    state.output.write(text.as_deref(), || {
+        let (outcome, from_state, to_state, error) = match result {
+            Ok(ref processed) => (
+                Some(processed.outcome),
+                Some(processed.from_state.clone()),
+                processed.to_state.clone(),
+                None,
+            ),
+            Err(e) => (None, None, None, Some(e.to_string())),
+        };
+        UpdateRecord {
+            ticket: issue_key.to_string(),
+            url,
+            outcome: outcome.map_or("failed", Outcome::name),
+            skip_reason: match outcome {
+                Some(Outcome::Skipped(reason)) => Some(reason),
+                _ => None,
+            },
+            from_state,
+            to_state,
+            pr,
+            error,
+            dry_run: config.dry_run,
+        }
+    })
+}
+
+/// Transitions a single issue to done, if it's eligible.
+///
+/// # Errors
+/// Returns an error if a Jira API request fails, or the issue's workflow
+/// offers no transition to a done status.
+fn complete_issue(
+    config: &Config,
+    jira: &JiraApi,
+    issue_key: &str,
+) -> Result<Processed, Error> {
+    let issue =
+        jira.request("GET", &format!("issue/{issue_key}?fields=status"), None)?;
+    let status = issue.get("fields.status");
+    let from_state = status.get("name").str_or_empty().to_string();
+    let skip = |outcome| Processed {
+        outcome,
+        from_state: from_state.clone(),
+        to_state: None,
+    };
+
+    if status.get("statusCategory.key").as_str() == Some(DONE_CATEGORY) {
+        log!("{issue_key} is already completed ({from_state})");
+        return Ok(skip(Outcome::AlreadyCompleted));
+    }
+    if !config.update_all_statuses && !config.is_source_state(&from_state) {
+        let eligible = config.source_states_label();
+        log!("skipping {issue_key}: status is {from_state}, not {eligible}");
+        return Ok(skip(Outcome::Skipped(SkipReason::IneligibleState)));
+    }
+
+    let transitions =
+        jira.request("GET", &format!("issue/{issue_key}/transitions"), None)?;
+    let (transition_id, to_state) = find_done_transition(&transitions)
+        .ok_or_else(|| {
+            Error::LinearApi(format!(
+                "No transition from {from_state} to a done status"
+            ))
+        })?;
+
+    if !config.dry_run {
+        let body = format!(
+            r#"{{"transition":{{"id":{}}}}}"#,
+            utils::json_string(&transition_id)
+        );
+        jira.request(
+            "POST",
+            &format!("issue/{issue_key}/transitions"),
+            Some(&body),
+        )?;
+    }
+    Ok(Processed {
+        outcome: Outcome::Updated,
+        from_state,
+        to_state: Some(to_state),
+    })
+}
+
+/// Picks the transition to a done status, preferring one whose target
+/// status name contains "Done" or "Completed".
+///
+/// # Returns
+/// The transition's ID and target status name, or `None` if no transition
+/// leads to a done status.
+fn find_done_transition(transitions: &json::Value) -> Option<(String, String)> {
+    let done: Vec<&json::Value> = transitions
+        .get("transitions")
+        .items()
+        .iter()
+        .filter(|transition| {
+            transition.get("to.statusCategory.key").as_str()
+                == Some(DONE_CATEGORY)
+        })
+        .collect();
+    let transition = done
+        .iter()
+        .find(|transition| {
+            update_tickets::matches_completed_state(
+                transition.get("to.name").str_or_empty(),
+            )
+        })
+        .or_else(|| done.first())?;
+    Some((
+        transition.get("id").as_str()?.to_string(),
+        transition.get("to.name").str_or_empty().to_string(),
+    ))
+}
+
+/// Formats a processed issue for stdout according to `--url-format`.
+fn output_line(config: &Config, jira: &JiraApi, issue_key: &str) -> String {
+    match config.url_format {
+        UrlFormat::Id => issue_key.to_string(),
+        UrlFormat::Url => jira.browse_url(issue_key),
+        UrlFormat::Markdown => {
+            format!("[{issue_key}]({})", jira.browse_url(issue_key))
+        }
+    }
+}
+
+/// Logs the localized end-of-run summary of issue counts and the run's
+/// `elapsed` time.
+fn report_summary(
+    config: &Config,
+    messages: &Messages,
+    state: &RunState,
+    elapsed: Duration,
+) {
+    let updated_key = if config.dry_run {
+        "summary.would_update"
+    } else {
+        "summary.updated"
+    };
+
+    let processed =
+        state.updated + state.already_completed + state.skipped + state.failed;
+
+    let header = messages.render("summary.header", &[]);
+    log!("{header}");
+    for (key, count) in [
+        ("summary.processed", processed),
+        (updated_key, state.updated),
+        ("summary.already_completed", state.already_completed),
+        ("summary.skipped", state.skipped),
+        ("summary.not_found", state.not_found),
+        ("summary.failed", state.failed - state.not_found),
+    ] {
+        let count = count.to_string();
+        let line = messages.render(key, &[("count", count.as_str())]);
+        log!("  {line}");
+    }
+
+    if !state.any_output {
+        let no_changes = messages.render("summary.no_changes", &[]);
+        log!("{no_changes}");
+    }
+    let elapsed = utils::format_elapsed(elapsed);
+    let elapsed = messages.render("summary.elapsed", &[("elapsed", &elapsed)]);
+    log!("{elapsed}");
+}
+
+/// Whether `input` is a Jira issue key: a project key (an uppercase letter
+/// followed by uppercase letters, digits or underscores), a hyphen, and the
+/// issue number.
+fn is_valid_issue_key(input: &str) -> bool {
+    let Some((project, number)) = input.split_once('-') else {
+        return false;
+    };
+    let mut project_chars = project.bytes();
+    project_chars.next().is_some_and(|b| b.is_ascii_uppercase())
+        && project.len() >= 2
+        && project_chars
+            .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit() || b == b'_')
+        && !number.is_empty()
+        && number.bytes().all(|b| b.is_ascii_digit())
+}
@@ -0,0 +1,224 @@
+//! Audit journal of ticket state changes (`--journal`), for audits and
+//! `rollback`.
+//!
+//! With `--journal FILE`, update-tickets appends a line to `FILE` for every
+//! state change it attempts, moved or failed, recording the state it moved
+//! the ticket out of. Compliance audits read it to tell which run closed a
+//! ticket, and the `rollback` mode reads it back to move each moved ticket to
+//! its earlier state again, undoing a run against the wrong release tag.
+//!
+//! ## File Format
+//! One attempted change per line, as a JSON object, in the order they were
+//! attempted:
+//!
+//! ```json
+//! {"timestamp":"2024-05-01T10:00:00Z","ticket":"ENG-2","from_state_id":"...","from_state":"Passing",
+//!  "to_state_id":"...","to_state":"completed","request_id":"18f2a6c1b20-3f1c-1","result":"moved"}
+//! ```
+//!
+//! `ticket` is the ticket's current identifier, `to_state` names the target
+//! of the run (`completed`, or the `--state-route`, `--target-state` or
+//! `--target-state-type`), and `result` is `moved` or `failed`, with the
+//! failure in `error`. `request_id` is the ID of the mutation, sent as its
+//! `X-Request-Id` header so the line can be matched with gateway or API logs;
+//! tickets moved in one `--quiet-updates` batch share it.
+//!
+//! Lines are appended as changes are attempted, so the file is valid even if
+//! the run is killed, and runs sharing a file append to it. Journals written
+//! before the JSON format, one moved ticket per line as
+//! `TICKET<TAB>FROM_STATE_ID<TAB>TO_STATE_ID<TAB>FROM_STATE_NAME`, are still
+//! read.
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::Error;
+use crate::json;
+use crate::utils;
+
+/// Mutations sent by this process, numbering their request IDs.
+static REQUESTS: AtomicU64 = AtomicU64::new(0);
+
+/// How an attempted state change ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// The ticket was moved
+    Moved,
+    /// The mutation failed, with this error
+    Failed(String),
+}
+
+/// One state change attempted by update-tickets.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    /// When the change was attempted (`YYYY-MM-DDTHH:MM:SSZ`; empty in old
+    /// journals)
+    pub timestamp: String,
+    /// Linear issue identifier (e.g. `ABC-123`)
+    pub ticket: String,
+    /// Workflow state the ticket was moved out of
+    pub from_state_id: String,
+    /// Workflow state the ticket was moved to
+    pub to_state_id: String,
+    /// Name of the state the ticket was moved out of
+    pub from_state: String,
+    /// Name of the run's target (empty in old journals)
+    pub to_state: String,
+    /// How the change ended (`result`)
+    pub outcome: Outcome,
+    /// ID the mutation was sent with, if known
+    pub request_id: Option<String>,
+}
+
+impl Entry {
+    /// Renders the entry as a journal line, without its newline.
+    fn to_json(&self) -> String {
+        let mut line = String::from("{");
+        for (name, value) in [
+            ("timestamp", Some(&self.timestamp)),
+            ("ticket", Some(&self.ticket)),
+            ("from_state_id", Some(&self.from_state_id)),
+            ("from_state", Some(&self.from_state)),
+            ("to_state_id", Some(&self.to_state_id)),
+            ("to_state", Some(&self.to_state)),
+            ("request_id", self.request_id.as_ref()),
+        ] {
+            if let Some(value) = value {
+                if line.len() > 1 {
+                    line.push(',');
+                }
+                line.push('"');
+                line.push_str(name);
+                line.push_str("\":");
+                line.push_str(&utils::json_string(value));
+            }
+        }
+        line.push_str(",\"result\":");
+        match self.outcome {
+            Outcome::Moved => line.push_str("\"moved\""),
+            Outcome::Failed(ref error) => {
+                line.push_str("\"failed\",\"error\":");
+                line.push_str(&utils::json_string(error));
+            }
+        }
+        line.push('}');
+        line
+    }
+}
+
+/// A new ID for a mutation: the time in milliseconds, the process ID and a
+/// count, e.g. `18f2a6c1b20-3f1c-1`, unique across runs sharing a journal.
+pub fn request_id() -> String {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis());
+    let count = REQUESTS.fetch_add(1, Ordering::Relaxed) + 1;
+    format!("{millis:x}-{:x}-{count}", std::process::id())
+}
+
+/// Appends `entry` to the journal at `path`.
+///
+/// # Errors
+/// Returns an error if the file can't be written.
+pub fn record(path: &str, entry: &Entry) -> Result<(), Error> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| Error::io(format!("Failed to open journal {path}"), e))?;
+    writeln!(file, "{}", entry.to_json())
+        .map_err(|e| Error::io(format!("Failed to write journal {path}"), e))
+}
+
+/// Reads every entry of the journal at `path`, in file order.
+///
+/// # Errors
+/// Returns an error if the file can't be read or has a malformed line.
+pub fn read(path: &str) -> Result<Vec<Entry>, Error> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| Error::io(format!("Failed to read journal {path}"), e))?;
+    let mut entries = Vec::new();
+    for (number, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = if line.trim_start().starts_with('{') {
+            parse_json_entry(line)
+        } else {
+            parse_entry(line)
+        };
+        let Some(entry) = entry else {
+            let number = number + 1;
+            return Err(Error::Other(format!(
+                "Invalid line {number} in journal {path}: expected a JSON entry with ticket, from_state_id, to_state_id and result"
+            )));
+        };
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// Parses one JSON journal line.
+fn parse_json_entry(line: &str) -> Option<Entry> {
+    let value = json::parse(line).ok()?;
+    let field = |name: &str| value.get(name).str_or_empty().to_string();
+    let outcome = match value.get("result").as_str()? {
+        "moved" => Outcome::Moved,
+        "failed" => Outcome::Failed(field("error")),
+        _ => return None,
+    };
+    let entry = Entry {
+        timestamp: field("timestamp"),
+        ticket: field("ticket"),
+        from_state_id: field("from_state_id"),
+        to_state_id: field("to_state_id"),
+        from_state: field("from_state"),
+        to_state: field("to_state"),
+        outcome,
+        request_id: value.get("request_id").as_str().map(String::from),
+    };
+    [&entry.ticket, &entry.from_state_id, &entry.to_state_id]
+        .iter()
+        .all(|field| !field.is_empty())
+        .then_some(entry)
+}
+
+/// Parses one `TICKET<TAB>FROM_STATE_ID<TAB>TO_STATE_ID<TAB>FROM_STATE_NAME`
+/// line of an old journal.
+fn parse_entry(line: &str) -> Option<Entry> {
+    let mut fields = line.split('\t');
+    let (
+        Some(ticket),
+        Some(from_state_id),
+        Some(to_state_id),
+        Some(from_state),
+        None,
+    ) = (
+        fields.next(),
+        fields.next(),
+        fields.next(),
+        fields.next(),
+        fields.next(),
+    )
+    else {
+        return None;
+    };
+    if [ticket, from_state_id, to_state_id]
+        .iter()
+        .any(|field| field.is_empty())
+    {
+        return None;
+    }
+    Some(Entry {
+        timestamp: String::new(),
+        ticket: ticket.to_string(),
+        from_state_id: from_state_id.to_string(),
+        to_state_id: to_state_id.to_string(),
+        from_state: from_state.to_string(),
+        to_state: String::new(),
+        outcome: Outcome::Moved,
+        request_id: None,
+    })
+}
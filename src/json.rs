@@ -0,0 +1,453 @@
+//! Built-in JSON parser for Linear and GitHub responses and stage records.
+//!
+//! Parsing in-process rather than running `jq` per field avoids dozens of
+//! process spawns per ticket, and means `jq` doesn't need to be installed.
+//!
+//! Lookups are forgiving the way jq's are: a missing key, or indexing into
+//! something that isn't an object, yields `null` rather than an error, so
+//! `value.get("data.issue.id")` reads like jq's `.data.issue.id`.
+
+use std::fmt;
+
+use crate::error::Error;
+use crate::utils;
+
+/// Shared `null` returned by lookups that find nothing.
+static NULL: Value = Value::Null;
+
+/// A parsed JSON value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    /// A number, kept as written so it round-trips exactly
+    Number(String),
+    String(String),
+    Array(Vec<Value>),
+    /// An object's members, in document order
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// The value at a dotted path of object keys (e.g. `data.issue.id`), or
+    /// `null` if any part of the path is missing.
+    pub fn get(&self, path: &str) -> &Value {
+        path.split('.').fold(self, |value, key| match value {
+            Value::Object(members) => members
+                .iter()
+                .find(|(name, _)| name == key)
+                .map_or(&NULL, |(_, member)| member),
+            _ => &NULL,
+        })
+    }
+
+    /// The elements of an array; empty for anything else (like jq's `[]?`).
+    pub fn items(&self) -> &[Value] {
+        match self {
+            Value::Array(items) => items,
+            _ => &[],
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// The string, or `""` for anything else (like jq's `. // ""`).
+    pub fn str_or_empty(&self) -> &str {
+        self.as_str().unwrap_or("")
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+
+    /// The `message` of every entry in a GraphQL response's `errors` array.
+    pub fn error_messages(&self) -> Vec<&str> {
+        self.get("errors")
+            .items()
+            .iter()
+            .filter_map(|error| error.get("message").as_str())
+            .collect()
+    }
+}
+
+/// Serializes the value as compact JSON.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => f.write_str("null"),
+            Value::Bool(b) => write!(f, "{b}"),
+            Value::Number(n) => f.write_str(n),
+            Value::String(s) => f.write_str(&utils::json_string(s)),
+            Value::Array(items) => {
+                f.write_str("[")?;
+                for (idx, item) in items.iter().enumerate() {
+                    if idx > 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                f.write_str("]")
+            }
+            Value::Object(members) => {
+                f.write_str("{")?;
+                for (idx, (name, member)) in members.iter().enumerate() {
+                    if idx > 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{}:{member}", utils::json_string(name))?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+/// Nests `value` under a dotted path of object keys, e.g. `data.issue` for
+/// `{"data": {"issue": value}}`.
+pub fn nest(path: &str, value: Value) -> Value {
+    path.rsplit('.').fold(value, |value, key| {
+        Value::Object(vec![(key.to_string(), value)])
+    })
+}
+
+/// Parses a JSON document.
+///
+/// # Errors
+/// Returns an error if `input` isn't a single valid JSON value (surrounding
+/// whitespace is allowed), or nests arrays and objects more than
+/// [`MAX_DEPTH`] deep.
+pub fn parse(input: &str) -> Result<Value, Error> {
+    let mut parser = Parser {
+        bytes: input.as_bytes(),
+        pos: 0,
+        depth: 0,
+    };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.pos != parser.bytes.len() {
+        return Err(parser.error("trailing characters"));
+    }
+    Ok(value)
+}
+
+/// Deepest nesting of arrays and objects accepted by [`parse`].
+///
+/// Real responses nest a handful of levels; the limit keeps a malformed or
+/// hostile one from overflowing the stack of the recursive parser.
+pub const MAX_DEPTH: usize = 128;
+
+/// Recursive-descent parser over the document's bytes.
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    /// Arrays and objects currently open
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> Error {
+        Error::Other(format!("Invalid JSON at byte {}: {message}", self.pos))
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self
+            .bytes
+            .get(self.pos)
+            .is_some_and(|b| matches!(b, b' ' | b'\t' | b'\n' | b'\r'))
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    /// Consumes `literal` if the input continues with it.
+    fn eat(&mut self, literal: &str) -> bool {
+        let matched = self.bytes[self.pos..].starts_with(literal.as_bytes());
+        if matched {
+            self.pos += literal.len();
+        }
+        matched
+    }
+
+    fn value(&mut self) -> Result<Value, Error> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{' | b'[') if self.depth == MAX_DEPTH => Err(self
+                .error(&format!("nested more than {MAX_DEPTH} levels deep"))),
+            Some(b'{') => self.nested(Self::object),
+            Some(b'[') => self.nested(Self::array),
+            Some(b'"') => self.string().map(Value::String),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            _ if self.eat("null") => Ok(Value::Null),
+            _ if self.eat("true") => Ok(Value::Bool(true)),
+            _ if self.eat("false") => Ok(Value::Bool(false)),
+            Some(_) => Err(self.error("expected a value")),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn nested(
+        &mut self,
+        parse: fn(&mut Self) -> Result<Value, Error>,
+    ) -> Result<Value, Error> {
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn object(&mut self) -> Result<Value, Error> {
+        self.pos += 1;
+        let mut members = Vec::new();
+        self.skip_whitespace();
+        if self.eat("}") {
+            return Ok(Value::Object(members));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some(b'"') {
+                return Err(self.error("expected an object key"));
+            }
+            let name = self.string()?;
+            self.skip_whitespace();
+            if !self.eat(":") {
+                return Err(self.error("expected ':'"));
+            }
+            members.push((name, self.value()?));
+            self.skip_whitespace();
+            if self.eat("}") {
+                return Ok(Value::Object(members));
+            }
+            if !self.eat(",") {
+                return Err(self.error("expected ',' or '}'"));
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, Error> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.eat("]") {
+            return Ok(Value::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.skip_whitespace();
+            if self.eat("]") {
+                return Ok(Value::Array(items));
+            }
+            if !self.eat(",") {
+                return Err(self.error("expected ',' or ']'"));
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, Error> {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|b| b.is_ascii_digit() || b"+-.eE".contains(&b))
+        {
+            self.pos += 1;
+        }
+        let lexeme = std::str::from_utf8(&self.bytes[start..self.pos])
+            .map_err(|_| self.error("invalid number"))?;
+        if !is_number(lexeme) {
+            return Err(self.error("invalid number"));
+        }
+        Ok(Value::Number(lexeme.to_string()))
+    }
+
+    /// Parses a string literal, starting at its opening quote.
+    fn string(&mut self) -> Result<String, Error> {
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            // Copy the run of plain characters up to the next quote or
+            // escape in one go; the input is UTF-8, and both delimiters are
+            // ASCII, so the run is too.
+            let start = self.pos;
+            while self.peek().is_some_and(|b| b != b'"' && b != b'\\') {
+                self.pos += 1;
+            }
+            out.push_str(
+                std::str::from_utf8(&self.bytes[start..self.pos])
+                    .map_err(|_| self.error("invalid UTF-8"))?,
+            );
+            match self.peek() {
+                Some(b'"') => {
+                    self.pos += 1;
+                    return Ok(out);
+                }
+                Some(_) => {
+                    self.pos += 1;
+                    self.escape(&mut out)?;
+                }
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    /// Decodes the escape sequence after a backslash.
+    fn escape(&mut self, out: &mut String) -> Result<(), Error> {
+        let Some(b) = self.peek() else {
+            return Err(self.error("unterminated string"));
+        };
+        self.pos += 1;
+        let c = match b {
+            b'"' => '"',
+            b'\\' => '\\',
+            b'/' => '/',
+            b'b' => '\u{8}',
+            b'f' => '\u{c}',
+            b'n' => '\n',
+            b'r' => '\r',
+            b't' => '\t',
+            b'u' => {
+                let unit = self.hex4()?;
+                // A high surrogate is followed by `\uDC00`-`\uDFFF` for the
+                // low half of the pair.
+                let code = if (0xD800..0xDC00).contains(&unit) {
+                    if !self.eat("\\u") {
+                        return Err(self.error("unpaired surrogate"));
+                    }
+                    let low = self.hex4()?;
+                    if !(0xDC00..0xE000).contains(&low) {
+                        return Err(self.error("unpaired surrogate"));
+                    }
+                    0x10000 + ((unit - 0xD800) << 10) + (low - 0xDC00)
+                } else {
+                    unit
+                };
+                char::from_u32(code)
+                    .ok_or_else(|| self.error("invalid \\u escape"))?
+            }
+            _ => return Err(self.error("invalid escape")),
+        };
+        out.push(c);
+        Ok(())
+    }
+
+    fn hex4(&mut self) -> Result<u32, Error> {
+        let digits = self
+            .bytes
+            .get(self.pos..self.pos + 4)
+            .filter(|digits| digits.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+            .ok_or_else(|| self.error("invalid \\u escape"))?;
+        self.pos += 4;
+        Ok(digits)
+    }
+}
+
+/// Whether `lexeme` is a number in JSON's grammar: an optional `-`, an
+/// integer part without leading zeros, then an optional fraction and
+/// exponent, each with at least one digit.
+fn is_number(lexeme: &str) -> bool {
+    fn digits(s: &str) -> usize {
+        s.bytes().take_while(u8::is_ascii_digit).count()
+    }
+    let rest = lexeme.strip_prefix('-').unwrap_or(lexeme);
+    let int = digits(rest);
+    if int == 0 || (int > 1 && rest.starts_with('0')) {
+        return false;
+    }
+    let mut rest = &rest[int..];
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let count = digits(fraction);
+        if count == 0 {
+            return false;
+        }
+        rest = &fraction[count..];
+    }
+    if let Some(exponent) = rest.strip_prefix(['e', 'E']) {
+        let exponent = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+        let count = digits(exponent);
+        if count == 0 {
+            return false;
+        }
+        rest = &exponent[count..];
+    }
+    rest.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_nested_documents() -> Result<(), Error> {
+        let value = parse(r#" {"data":{"issue":{"id":"1","labels":[]}}} "#)?;
+        assert_eq!(value.get("data.issue.id").as_str(), Some("1"));
+        assert!(value.get("data.issue.labels").items().is_empty());
+        assert!(value.get("data.missing.id").is_null());
+        Ok(())
+    }
+
+    #[test]
+    fn decodes_escapes() -> Result<(), Error> {
+        let value = parse(r#""a\"b\\c\/d\b\f\n\r\t\u00e9""#)?;
+        assert_eq!(value.as_str(), Some("a\"b\\c/d\u{8}\u{c}\n\r\t\u{e9}"));
+        for invalid in [r#""\x""#, r#""\u12""#, r#""\u+123""#, r#""abc"#] {
+            assert!(parse(invalid).is_err(), "{invalid} should be rejected");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn decodes_surrogate_pairs() -> Result<(), Error> {
+        assert_eq!(parse(r#""\ud83d\ude80""#)?.as_str(), Some("\u{1f680}"));
+        for unpaired in [r#""\ud83d""#, r#""\ud83dx""#, r#""\ud83d\u0041""#] {
+            assert!(parse(unpaired).is_err(), "{unpaired} should be rejected");
+        }
+        assert!(parse(r#""\ude80""#).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn limits_nesting_depth() {
+        let nested = |depth| "[".repeat(depth) + &"]".repeat(depth);
+        assert!(parse(&nested(MAX_DEPTH)).is_ok());
+        assert!(parse(&nested(MAX_DEPTH + 1)).is_err());
+        assert!(parse(&"[".repeat(100_000)).is_err());
+    }
+
+    #[test]
+    fn keeps_numbers_as_written() -> Result<(), Error> {
+        for number in ["0", "-0", "12", "1.50", "-2.5e-3", "1E+10", "0.0"] {
+            assert_eq!(parse(number)?, Value::Number(number.to_string()));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_invalid_numbers() {
+        for invalid in ["01", "-01", "00", "+1", "1.", ".5", "1e", "1e+", "-"] {
+            assert!(parse(invalid).is_err(), "{invalid} should be rejected");
+        }
+    }
+
+    #[test]
+    fn rejects_trailing_characters() {
+        assert!(parse("{} {}").is_err());
+        assert!(parse("[1,]").is_err());
+        assert!(parse(r#"{"a":1,}"#).is_err());
+    }
+}
@@ -0,0 +1,374 @@
+//! Labels applied to updated tickets (`--add-label`).
+//!
+//! With `--add-label NAME`, update-tickets applies the label `NAME` (e.g.
+//! `released:v1.2.3`) to every ticket it moves, so released work can be
+//! found with a label filter in Linear, even in workflows without a released
+//! state. The label is looked up once, before any ticket is processed, among
+//! the workspace labels (ignoring case); if there is none, the run creates it
+//! as a workspace label, and a dry run only logs that it would. Labels of a
+//! single team aren't used, since they can't be applied to other teams'
+//! tickets.
+//!
+//! The label is added with `issueAddLabel`, which keeps the ticket's other
+//! labels. Tickets that already carry it are left as they are.
+//!
+//! ## Custom Fields
+//! Linear issues have no custom fields; the Linear way to give issues a
+//! field such as "Release" is a workspace label group, whose labels are the
+//! field's values and of which an issue carries at most one. With
+//! `--set-custom-field NAME=VALUE`, update-tickets looks up the label group
+//! `NAME` once (an error if the workspace has none), creates its label
+//! `VALUE` if missing (a dry run only logs that it would), and sets it in
+//! the same `issueUpdate` (or `issueBatchUpdate`) that moves each ticket,
+//! removing the group's other labels the ticket carries.
+
+use std::fmt;
+
+use crate::error::Error;
+use crate::json::Value;
+use crate::utils::{self, LinearApi};
+
+/// The label applied to updated tickets.
+#[derive(Debug)]
+pub struct Label {
+    /// Name of the label, as given
+    pub name: String,
+    /// UUID of the label (`None` in a dry run that would create it)
+    pub id: Option<String>,
+}
+
+impl Label {
+    /// Whether `labels` (label names of a ticket) include this label.
+    pub fn is_on(&self, labels: &[String]) -> bool {
+        labels
+            .iter()
+            .any(|label| label.eq_ignore_ascii_case(&self.name))
+    }
+
+    /// Adds the label to the issue with UUID `issue_id`.
+    ///
+    /// # GraphQL Mutation
+    /// ```graphql
+    /// mutation($id: String!, $labelId: String!) {
+    ///   issueAddLabel(id: $id, labelId: $labelId) { success }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the label wasn't created (dry run) or the
+    /// mutation returns `success: false`.
+    pub fn apply(
+        &self,
+        issue_id: &str,
+        linear: &LinearApi,
+    ) -> Result<(), Error> {
+        let Some(ref label_id) = self.id else {
+            return Err(Error::Other(format!(
+                "Label {} was not created",
+                self.name
+            )));
+        };
+        let query = format!(
+            r#"{{"query": "mutation($id: String!, $labelId: String!) {{ issueAddLabel(id: $id, labelId: $labelId) {{ success }} }}", "variables": {{"id": {}, "labelId": {}}}}}"#,
+            utils::json_string(issue_id),
+            utils::json_string(label_id)
+        );
+        let response = utils::graphql_request(&query, linear)?;
+        if response.get("data.issueAddLabel.success").as_bool() == Some(true) {
+            Ok(())
+        } else {
+            Err(Error::LinearApi(format!(
+                "Adding the label failed: {response}"
+            )))
+        }
+    }
+}
+
+/// A `--set-custom-field NAME=VALUE`: label `VALUE` of label group `NAME`.
+#[derive(Debug, Clone)]
+pub struct CustomField {
+    pub name: String,
+    pub value: String,
+}
+
+impl CustomField {
+    /// Parses a `--set-custom-field` value, `NAME=VALUE`.
+    ///
+    /// # Errors
+    /// Returns an error if the name or value is missing.
+    pub fn parse(spec: &str) -> Result<Self, Error> {
+        spec.split_once('=')
+            .map(|(name, value)| (name.trim(), value.trim()))
+            .filter(|(name, value)| !name.is_empty() && !value.is_empty())
+            .map(|(name, value)| Self {
+                name: name.to_string(),
+                value: value.to_string(),
+            })
+            .ok_or_else(|| {
+                Error::Config(format!(
+                    "Invalid --set-custom-field {spec}: expected NAME=VALUE"
+                ))
+            })
+    }
+}
+
+impl fmt::Display for CustomField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} = {}", self.name, self.value)
+    }
+}
+
+/// A custom field's value label, as looked up for the run.
+#[derive(Debug)]
+pub struct FieldValue {
+    pub field: CustomField,
+    /// UUID of the value label (`None` in a dry run that would create it)
+    pub id: Option<String>,
+    /// The group's other labels, as UUID and name
+    others: Vec<(String, String)>,
+}
+
+impl FieldValue {
+    /// A value of `group`, found among its labels by name (ignoring case),
+    /// or `id` if given.
+    pub fn new(field: &CustomField, group: Group, id: Option<String>) -> Self {
+        let (values, others): (Vec<_>, Vec<_>) = group
+            .labels
+            .into_iter()
+            .partition(|(_, name)| name.eq_ignore_ascii_case(&field.value));
+        Self {
+            field: field.clone(),
+            id: id.or_else(|| values.into_iter().next().map(|(id, _)| id)),
+            others,
+        }
+    }
+
+    /// The label change setting the value on tickets carrying the label
+    /// names of `tickets`: adds the value and removes the group's other
+    /// labels any of them carry.
+    pub fn change<'a>(
+        &self,
+        tickets: impl IntoIterator<Item = &'a [String]>,
+    ) -> LabelChange {
+        let mut removed = Vec::new();
+        for labels in tickets {
+            for (id, name) in &self.others {
+                if labels.iter().any(|label| label == name)
+                    && !removed.contains(id)
+                {
+                    removed.push(id.clone());
+                }
+            }
+        }
+        LabelChange {
+            added: self.id.iter().cloned().collect(),
+            removed,
+        }
+    }
+}
+
+impl FieldValue {
+    /// The change setting the value on a ticket carrying the label names
+    /// `labels`, as label names: the value, unless the ticket carries it,
+    /// and the group's other labels it carries.
+    pub fn named_change(
+        &self,
+        labels: &[String],
+    ) -> (Vec<String>, Vec<String>) {
+        let value = &self.field.value;
+        let added =
+            (!labels.iter().any(|label| label.eq_ignore_ascii_case(value)))
+                .then(|| value.clone())
+                .into_iter()
+                .collect();
+        let removed = self
+            .others
+            .iter()
+            .filter(|(_, name)| labels.contains(name))
+            .map(|(_, name)| name.clone())
+            .collect();
+        (added, removed)
+    }
+}
+
+/// Labels added to and removed from issues along with a state change.
+#[derive(Debug, Default)]
+pub struct LabelChange {
+    /// UUIDs of the labels added
+    pub added: Vec<String>,
+    /// UUIDs of the labels removed
+    pub removed: Vec<String>,
+}
+
+impl LabelChange {
+    /// The `addedLabelIds` and `removedLabelIds` fields of an
+    /// `IssueUpdateInput` for the change, each preceded by `, ` (empty if
+    /// nothing changes).
+    pub fn input_fields(&self) -> String {
+        let mut fields = String::new();
+        for (name, ids) in [
+            ("addedLabelIds", &self.added),
+            ("removedLabelIds", &self.removed),
+        ] {
+            if ids.is_empty() {
+                continue;
+            }
+            let ids: Vec<String> =
+                ids.iter().map(|id| utils::json_string(id)).collect();
+            fields.push_str(", \"");
+            fields.push_str(name);
+            fields.push_str("\": [");
+            fields.push_str(&ids.join(", "));
+            fields.push(']');
+        }
+        fields
+    }
+}
+
+/// A workspace label group.
+#[derive(Debug)]
+pub struct Group {
+    pub id: String,
+    /// The group's labels, as UUID and name
+    pub labels: Vec<(String, String)>,
+}
+
+/// Finds the workspace label group named `name` (ignoring case).
+///
+/// # GraphQL Query
+/// ```graphql
+/// query($name: String!) {
+///   issueLabels(filter: { name: { eqIgnoreCase: $name } }, first: 50) {
+///     nodes { id isGroup team { id } children(first: 250) { nodes { id name } } }
+///   }
+/// }
+/// ```
+///
+/// # Returns
+/// The group, or `None` if no workspace label group has the name.
+///
+/// # Errors
+/// Returns an error if the query fails.
+pub fn find_group(
+    name: &str,
+    linear: &LinearApi,
+) -> Result<Option<Group>, Error> {
+    let query = format!(
+        r#"{{"query": "query($name: String!) {{ issueLabels(filter: {{ name: {{ eqIgnoreCase: $name }} }}, first: 50) {{ nodes {{ id isGroup team {{ id }} children(first: 250) {{ nodes {{ id name }} }} }} }} }}", "variables": {{"name": {}}}}}"#,
+        utils::json_string(name)
+    );
+    let response = utils::graphql_request(&query, linear)?;
+    ensure_no_errors(
+        &response,
+        &format!("Failed to look up label group {name}"),
+    )?;
+    let nodes = response.get("data.issueLabels.nodes").items();
+    let Some(group) = nodes.iter().find(|node| {
+        node.get("isGroup").as_bool() == Some(true)
+            && node.get("team.id").as_str().is_none()
+    }) else {
+        return Ok(None);
+    };
+    Ok(group.get("id").as_str().map(|id| Group {
+        id: id.to_string(),
+        labels: group
+            .get("children.nodes")
+            .items()
+            .iter()
+            .filter_map(|label| {
+                Some((
+                    label.get("id").as_str()?.to_string(),
+                    label.get("name").str_or_empty().to_string(),
+                ))
+            })
+            .collect(),
+    }))
+}
+
+/// Finds the workspace label named `name` (ignoring case), other than a
+/// label group.
+///
+/// # GraphQL Query
+/// ```graphql
+/// query($name: String!) {
+///   issueLabels(filter: { name: { eqIgnoreCase: $name } }, first: 50) {
+///     nodes { id isGroup team { id } }
+///   }
+/// }
+/// ```
+///
+/// # Returns
+/// The label's UUID, or `None` if no workspace label has the name.
+///
+/// # Errors
+/// Returns an error if the query fails.
+pub fn find(name: &str, linear: &LinearApi) -> Result<Option<String>, Error> {
+    let query = format!(
+        r#"{{"query": "query($name: String!) {{ issueLabels(filter: {{ name: {{ eqIgnoreCase: $name }} }}, first: 50) {{ nodes {{ id isGroup team {{ id }} }} }} }}", "variables": {{"name": {}}}}}"#,
+        utils::json_string(name)
+    );
+    let response = utils::graphql_request(&query, linear)?;
+    ensure_no_errors(&response, &format!("Failed to look up label {name}"))?;
+    Ok(response
+        .get("data.issueLabels.nodes")
+        .items()
+        .iter()
+        .find(|node| {
+            node.get("isGroup").as_bool() != Some(true)
+                && node.get("team.id").as_str().is_none()
+        })
+        .and_then(|node| node.get("id").as_str())
+        .map(String::from))
+}
+
+/// Creates the workspace label `name`, in the label group with UUID
+/// `parent` if given.
+///
+/// # GraphQL Mutation
+/// ```graphql
+/// mutation($input: IssueLabelCreateInput!) {
+///   issueLabelCreate(input: $input) { success issueLabel { id } }
+/// }
+/// ```
+/// with the name (and `parentId`) as the input.
+///
+/// # Returns
+/// The new label's UUID.
+///
+/// # Errors
+/// Returns an error if the mutation fails.
+pub fn create(
+    name: &str,
+    parent: Option<&str>,
+    linear: &LinearApi,
+) -> Result<String, Error> {
+    let parent = parent.map_or_else(String::new, |parent| {
+        format!(r#", "parentId": {}"#, utils::json_string(parent))
+    });
+    let query = format!(
+        r#"{{"query": "mutation($input: IssueLabelCreateInput!) {{ issueLabelCreate(input: $input) {{ success issueLabel {{ id }} }} }}", "variables": {{"input": {{"name": {}{parent}}}}}}}"#,
+        utils::json_string(name)
+    );
+    let response = utils::graphql_request(&query, linear)?;
+    let context = format!("Failed to create label {name}");
+    ensure_no_errors(&response, &context)?;
+    response
+        .get("data.issueLabelCreate.issueLabel.id")
+        .as_str()
+        .filter(|_| {
+            response.get("data.issueLabelCreate.success").as_bool()
+                == Some(true)
+        })
+        .map(String::from)
+        .ok_or_else(|| Error::LinearApi(format!("{context}: {response}")))
+}
+
+/// Fails with `context` if `response` has GraphQL errors.
+fn ensure_no_errors(response: &Value, context: &str) -> Result<(), Error> {
+    let messages = response.error_messages().join("\n");
+    if messages.trim().is_empty() {
+        Ok(())
+    } else {
+        Err(Error::LinearApi(format!("{context}: {}", messages.trim())))
+    }
+}
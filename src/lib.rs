@@ -0,0 +1,189 @@
+//! Release Linear Ticket Update
+//!
+//! A tool for automatically marking Linear tickets as completed when a GitHub
+//! release is published.
+//!
+//! ## Overview
+//!
+//! This tool processes GitHub release notes to find associated Pull Requests,
+//! extracts Linear ticket IDs from those PRs, and marks them as completed in
+//! Linear. It can be used as individual pipeline commands or as a single
+//! orchestrated workflow.
+//!
+//! ## Modes
+//!
+//! - **parse-notes**: Extract PR numbers from release notes
+//! - **extract-tickets**: Find Linear tickets in PRs
+//! - **extract-commits**: Find Linear tickets in the commits of a release,
+//!   for repositories that merge without PRs
+//! - **update-tickets**: Mark Linear tickets as completed (or, with
+//!   `--tracker jira`, transition Jira issues to done)
+//! - **teams**: List Linear teams and their workflow states
+//! - **report**: Report a release's PRs, their tickets and the tickets'
+//!   current states, without changing anything
+//! - **verify**: Check that every ticket of a release is completed, without
+//!   changing anything
+//! - **schema**: Print the JSON Schema of the machine outputs
+//! - **rollback**: Move tickets back to the states an update-tickets run
+//!   with `--journal` moved them out of
+//! - **selftest**: Run the update path on a throwaway issue of a sandbox
+//!   team, to check credentials and permissions
+//! - **orchestrator**: Run the complete pipeline
+//! - **run**: Run a pipeline of stages defined in the config file
+//! - **serve**: Listen for GitHub release webhooks and run the orchestrator
+//!   for every release published
+//!
+//! ## Library
+//!
+//! The binary is a thin CLI over this crate, so other Rust tools can run the
+//! pipeline stages without spawning it. A [`Config`] is built from the same
+//! flags the command line takes ([`Config::from_arg_list`]), and each stage
+//! is a function of it:
+//! - [`parse_notes::extract_pr_numbers`]: the PRs referenced by release
+//!   notes
+//! - [`extract_tickets::tickets_for_pr`]: the tickets referenced by a PR
+//! - [`update_tickets::update`]: moves tickets to the completed state
+//!
+//! A [`Config`] can also be built with [`Config::new`], every flag at its
+//! default, and its fields set directly.
+//!
+//! [`run`] runs a whole mode, as the binary does. The stages log to stderr
+//! like the CLI, as the config of their run says (see [`with_logging`]);
+//! the external commands below must be installed all the same.
+//!
+//! ## External Dependencies
+//!
+//! This tool delegates to external commands rather than bundling libraries:
+//! - `gh` (GitHub CLI) - for accessing GitHub API, unless `--github-api`
+//!   reaches it with `GITHUB_TOKEN` instead
+//! - `glab` (GitLab CLI) - for GitLab releases and merge requests, with
+//!   `--forge gitlab`
+//! - `curl` - for HTTPS requests, only when built without the `native-http`
+//!   feature; by default they are sent in-process, over rustls
+//! - `grep` - for pattern matching
+//! - `git` - for listing commits with `extract-commits --commit-source git`
+//! - `sha256sum` - for hashing dry-run manifests, and webhook signatures
+//!   when built without the `native-http` feature
+//!
+//! Errors are [`Error`]s, whose message names each step that failed, down
+//! to the cause.
+#![forbid(clippy::allow_attributes)]
+#![forbid(clippy::complexity)]
+#![forbid(clippy::correctness)]
+#![forbid(clippy::expect_used)]
+#![forbid(clippy::pedantic)]
+#![forbid(clippy::perf)]
+#![forbid(clippy::style)]
+#![forbid(clippy::suspicious)]
+#![forbid(clippy::unwrap_used)]
+#![forbid(future_incompatible)]
+#![forbid(unsafe_code)]
+
+mod action_outputs;
+mod attachments;
+pub mod cache;
+mod capabilities;
+mod checkpoint;
+mod comments;
+mod commit_status;
+mod compare;
+pub mod config;
+mod config_file;
+mod cycle;
+mod deployment;
+mod diff;
+pub mod error;
+mod estimate;
+pub mod exit_status;
+mod explain;
+mod extract_commits;
+pub mod extract_tickets;
+mod filter;
+mod github;
+mod github_api;
+mod gitlab;
+mod history;
+mod http;
+mod jira;
+mod journal;
+mod json;
+mod labels;
+mod locale;
+mod manifest;
+mod orchestrator;
+pub mod parse_notes;
+mod pipeline;
+mod project;
+pub mod protocol;
+mod queue;
+mod relations;
+mod release_issue;
+mod release_range;
+mod report;
+mod rollback;
+mod schema;
+mod scope;
+mod selftest;
+mod serve;
+mod slack;
+mod snapshot;
+mod teams;
+mod tickets;
+pub mod update_tickets;
+mod utils;
+mod verify;
+mod webhook;
+
+pub use config::Config;
+pub use error::Error;
+
+use config::{Mode, Tracker};
+
+/// Runs the mode of `config`, as the binary does, logging as `config` says
+/// (see [`with_logging`]).
+///
+/// # Errors
+/// Returns the error the mode failed with.
+pub fn run(config: &Config) -> Result<(), Error> {
+    with_logging(config, || run_mode(config))
+}
+
+/// Runs `f` with what it logs on this thread logged as `config` says
+/// (`--quiet`, `-v`, `-vv`, `--log-format`), e.g. to call a stage function
+/// or [`print_error`]. Otherwise lines are logged at the default level, as
+/// text.
+pub fn with_logging<R>(config: &Config, f: impl FnOnce() -> R) -> R {
+    utils::with_log_settings(config.log_settings(), f)
+}
+
+/// Runs the mode of `config`.
+fn run_mode(config: &Config) -> Result<(), Error> {
+    match config.mode {
+        Mode::ExtractTickets => extract_tickets::run(config),
+        Mode::ExtractCommits => extract_commits::run(config),
+        Mode::Orchestrator => orchestrator::run(config),
+        Mode::ParseNotes => parse_notes::run(config),
+        Mode::UpdateTickets => match config.tracker {
+            Tracker::Linear => update_tickets::run(config),
+            Tracker::Jira => jira::run(config),
+        },
+        Mode::Teams => teams::run(config),
+        Mode::Report => report::run(config),
+        Mode::Verify => verify::run(config),
+        Mode::Rollback => rollback::run(config),
+        Mode::Selftest => selftest::run(config),
+        Mode::Run => pipeline::run(config),
+        Mode::Serve => serve::run(config),
+        Mode::Schema => {
+            schema::run();
+            Ok(())
+        }
+    }
+}
+
+/// Prints the error that ended the run to stderr, as the binary does:
+/// `Error: MESSAGE`, or with `--log-format json` (see [`with_logging`]) a
+/// log record of level `error`.
+pub fn print_error(error: &dyn std::fmt::Display) {
+    utils::log_error(&error.to_string());
+}
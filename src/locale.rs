@@ -0,0 +1,112 @@
+//! Localized end-of-run summary and report text.
+//!
+//! Messages are `key = text` lines in a template file, with `{name}`
+//! placeholders. The English template (`locales/en.txt`) is compiled into the
+//! binary; `--locale LOCALE` loads `<LOCALE>.txt` from `--locale-dir`
+//! (default `locales`) on top of it, so translations only need the keys they
+//! change.
+
+use std::collections::HashMap;
+
+use crate::error::Error;
+
+/// The built-in English template.
+const DEFAULT_TEMPLATE: &str = include_str!("../locales/en.txt");
+
+/// Default directory searched for `<LOCALE>.txt` template files.
+pub const DEFAULT_LOCALE_DIR: &str = "locales";
+
+/// A set of localized messages keyed by message name.
+#[derive(Debug, Clone)]
+pub struct Messages {
+    messages: HashMap<String, String>,
+}
+
+impl Messages {
+    /// Loads messages for `locale`, falling back to English per key.
+    ///
+    /// # Arguments
+    /// * `locale` - Locale name (e.g. `de`, `pt-BR`); `None` uses English
+    /// * `dir` - Directory containing `<LOCALE>.txt` (default `locales`)
+    ///
+    /// For `en`, a missing template file is not an error (the built-in
+    /// template is used).
+    ///
+    /// # Errors
+    /// Returns an error if the template file can't be read, has a malformed
+    /// line, or defines a key the built-in template doesn't have.
+    pub fn load(
+        locale: Option<&str>,
+        dir: Option<&str>,
+    ) -> Result<Self, Error> {
+        let mut messages = parse(DEFAULT_TEMPLATE, "built-in template")?;
+        let Some(locale) = locale else {
+            return Ok(Self { messages });
+        };
+
+        let path =
+            format!("{}/{locale}.txt", dir.unwrap_or(DEFAULT_LOCALE_DIR));
+        let template = match std::fs::read_to_string(&path) {
+            Ok(template) => template,
+            Err(e)
+                if e.kind() == std::io::ErrorKind::NotFound
+                    && locale == "en" =>
+            {
+                return Ok(Self { messages });
+            }
+            Err(e) => {
+                return Err(Error::io(
+                    format!("Failed to read locale template {path}"),
+                    e,
+                ));
+            }
+        };
+
+        for (key, text) in parse(&template, &path)? {
+            if !messages.contains_key(&key) {
+                return Err(Error::Other(format!(
+                    "Unknown message key {key} in {path}"
+                )));
+            }
+            messages.insert(key, text);
+        }
+        Ok(Self { messages })
+    }
+
+    /// Renders message `key`, substituting `{name}` placeholders from `vars`.
+    ///
+    /// Unknown keys render as the key itself.
+    pub fn render(&self, key: &str, vars: &[(&str, &str)]) -> String {
+        let Some(text) = self.messages.get(key) else {
+            return key.to_string();
+        };
+        vars.iter().fold(text.clone(), |text, (name, value)| {
+            text.replace(&format!("{{{name}}}"), value)
+        })
+    }
+}
+
+/// Parses `key = text` lines, ignoring blank lines and `#` comments.
+///
+/// Only the single space after `=` is stripped, so values may keep leading
+/// indentation.
+fn parse(
+    template: &str,
+    source: &str,
+) -> Result<HashMap<String, String>, Error> {
+    let mut messages = HashMap::new();
+    for (number, line) in template.lines().enumerate() {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        let Some((key, text)) = line.split_once('=') else {
+            let number = number + 1;
+            return Err(Error::Other(format!(
+                "Invalid line {number} in {source}: expected 'key = text'"
+            )));
+        };
+        let text = text.strip_prefix(' ').unwrap_or(text);
+        messages.insert(key.trim().to_string(), text.trim_end().to_string());
+    }
+    Ok(messages)
+}
@@ -1,30 +1,26 @@
 //! Release Linear Ticket Update
 //!
-//! A tool for automatically marking Linear tickets as completed when a GitHub
-//! release is published.
+//! The command line of the crate: parses the arguments into a [`Config`],
+//! runs its mode and exits with the status of the outcome. See the library
+//! docs for the modes and what they need.
 //!
-//! ## Overview
+//! ## Exit Status
 //!
-//! This tool processes GitHub release notes to find associated Pull Requests,
-//! extracts Linear ticket IDs from those PRs, and marks them as completed in
-//! Linear. It can be used as individual pipeline commands or as a single
-//! orchestrated workflow.
+//! - `0` - success
+//! - `1` - failure
+//! - `2` - invalid command line or config file
+//! - `3` - the `--api-budget` ran out: the requests made were completed, but
+//!   the rest of the input wasn't processed
+//! - `4` - every ticket was processed, but some failed to update (only with
+//!   `--fail-on-partial`; otherwise the run succeeds)
+//! - `5` - an external command (`gh`, `glab`, `curl`, `grep`, `sha256sum`,
+//!   `git`) is not installed
+//! - `6` - a tracker credential (or the `--github-api` token) is missing, or
+//!   was rejected
 //!
-//! ## Modes
-//!
-//! - **parse-notes**: Extract PR numbers from release notes
-//! - **extract-tickets**: Find Linear tickets in PRs
-//! - **update-tickets**: Mark Linear tickets as completed
-//! - **orchestrator**: Run the complete pipeline
-//!
-//! ## External Dependencies
-//!
-//! This tool delegates to external commands rather than bundling libraries:
-//! - `gh` (GitHub CLI) - for accessing GitHub API
-//! - `curl` - for HTTP requests to Linear API
-//! - `grep` - for pattern matching
-//! - `jq` - for JSON manipulation
-
+//! See [`exit_status`]. The message printed after `Error: ` (with
+//! `--log-format json`, the `message` of the final `error` record) names
+//! each step that failed, down to the cause.
 #![forbid(clippy::allow_attributes)]
 #![forbid(clippy::complexity)]
 #![forbid(clippy::correctness)]
@@ -37,14 +33,9 @@
 #![forbid(future_incompatible)]
 #![forbid(unsafe_code)]
 
-mod config;
-mod extract_tickets;
-mod orchestrator;
-mod parse_notes;
-mod update_tickets;
-mod utils;
-
-use config::{Config, Mode};
+use release_linear_ticket_update::Config;
+use release_linear_ticket_update::cache::ApiBudget;
+use release_linear_ticket_update::exit_status;
 
 fn main() {
     // Parse command-line arguments into configuration
@@ -52,22 +43,38 @@ fn main() {
         Ok(config) => config,
         Err(error) => {
             // Print errors to stderr and exit with failure code
-            eprintln!("Error: {error}");
-            std::process::exit(1);
+            release_linear_ticket_update::print_error(&error);
+            std::process::exit(exit_status::CONFIG);
         }
     };
 
     // Dispatch to the appropriate mode handler
-    let result = match config.mode {
-        Mode::ExtractTickets => extract_tickets::run(&config),
-        Mode::Orchestrator => orchestrator::run(&config),
-        Mode::ParseNotes => parse_notes::run(&config),
-        Mode::UpdateTickets => update_tickets::run(&config),
-    };
+    let result = release_linear_ticket_update::run(&config);
+
+    let exhausted = config
+        .api_budget
+        .as_ref()
+        .filter(|budget| budget.exhausted());
 
     // Handle any errors from mode execution
+    let print_error = |error: &dyn std::fmt::Display| {
+        release_linear_ticket_update::with_logging(&config, || {
+            release_linear_ticket_update::print_error(error);
+        });
+    };
     if let Err(error) = result {
-        eprintln!("Error: {error}");
-        std::process::exit(1);
+        print_error(&error);
+        std::process::exit(if exhausted.is_some() {
+            ApiBudget::EXHAUSTED_EXIT_CODE
+        } else {
+            exit_status::of(&error)
+        });
+    }
+    if let Some(budget) = exhausted {
+        print_error(&format!(
+            "stopped after the API budget of {} requests (--api-budget); the result is partial",
+            budget.limit()
+        ));
+        std::process::exit(ApiBudget::EXHAUSTED_EXIT_CODE);
     }
 }
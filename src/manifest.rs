@@ -0,0 +1,251 @@
+//! Hash-stamped manifests of intended ticket changes.
+//!
+//! A dry-run with `--manifest FILE` records exactly which tickets would be
+//! updated. A later run with `--approve HASH` re-plans the same input and
+//! refuses to apply anything unless the fresh plan hashes to the approved
+//! value, so an approval step (e.g. a GitHub Environment reviewer) can be sure
+//! that what was reviewed is what gets executed.
+//!
+//! The manifest records state and label changes only. Flags with other side
+//! effects (release comments, attachments, project and cycle memberships, the
+//! release issue, webhook events) are rejected with `--approve`, and so is a
+//! plan in which any ticket couldn't be planned.
+//!
+//! ## Format
+//! The canonical manifest is compact JSON with changes sorted by ticket ID,
+//! each with the state it is moved to and the labels added and removed with
+//! the move (`--add-label`, `--set-custom-field`), so approving a manifest
+//! approves where every ticket goes:
+//! ```text
+//! {"version":2,"org":"acme","changes":[{"ticket":"ABC-123","team_id":"...","from":"Passing","to":"Done","to_state_id":"...","add_labels":["released:v1.2.3"],"remove_labels":[]}]}
+//! ```
+//! The file written to disk wraps it together with its SHA-256 hash:
+//! ```text
+//! {"sha256":"<hex>","manifest":<canonical manifest>,"schema_version":1}
+//! ```
+//! The hash is computed over the canonical manifest bytes exactly as they
+//! appear in the file. `schema_version` (see [`crate::schema`]) is kept out
+//! of the canonical manifest, so it doesn't affect the hash.
+//!
+//! A real run with `--compare-manifest FILE` reads the changes back to report
+//! what changed between the dry run and the run.
+
+use crate::error::Error;
+use crate::json;
+use crate::protocol::SCHEMA_VERSION;
+use crate::utils;
+
+/// Manifest format version, bumped whenever the canonical form changes.
+const VERSION: u32 = 2;
+
+/// A single intended ticket transition.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    /// Linear issue identifier (e.g. `ABC-123`)
+    pub ticket: String,
+    /// Linear team ID owning the issue
+    pub team_id: String,
+    /// Workflow state name at planning time
+    pub from_state: String,
+    /// Name of the workflow state the ticket is moved to, as found in its
+    /// team's workflow (with `--linear-snapshot`, which carries no
+    /// workflows, the target as given, e.g. `completed`)
+    pub to_state: String,
+    /// ID of the workflow state the ticket is moved to (empty with
+    /// `--linear-snapshot`)
+    pub to_state_id: String,
+    /// Names of the labels added with the move, sorted
+    pub added_labels: Vec<String>,
+    /// Names of the labels removed with the move, sorted
+    pub removed_labels: Vec<String>,
+}
+
+/// Renders the canonical manifest for a set of changes.
+///
+/// Entries are sorted and deduplicated by ticket ID so the result doesn't
+/// depend on input order.
+pub fn render(org: &str, mut entries: Vec<Entry>) -> String {
+    entries.sort_by(|a, b| a.ticket.cmp(&b.ticket));
+    entries.dedup_by(|a, b| a.ticket == b.ticket);
+
+    let changes: Vec<String> = entries
+        .iter()
+        .map(|entry| {
+            format!(
+                r#"{{"ticket":{},"team_id":{},"from":{},"to":{},"to_state_id":{},"add_labels":{},"remove_labels":{}}}"#,
+                utils::json_string(&entry.ticket),
+                utils::json_string(&entry.team_id),
+                utils::json_string(&entry.from_state),
+                utils::json_string(&entry.to_state),
+                utils::json_string(&entry.to_state_id),
+                json_strings(&entry.added_labels),
+                json_strings(&entry.removed_labels),
+            )
+        })
+        .collect();
+
+    format!(
+        r#"{{"version":{VERSION},"org":{},"changes":[{}]}}"#,
+        utils::json_string(org),
+        changes.join(",")
+    )
+}
+
+/// Renders `values` as a JSON array of strings.
+fn json_strings(values: &[String]) -> String {
+    let values: Vec<String> = values
+        .iter()
+        .map(|value| utils::json_string(value))
+        .collect();
+    format!("[{}]", values.join(","))
+}
+
+/// Computes the hash that `--approve` is checked against.
+///
+/// # Errors
+/// Returns an error if `sha256sum` fails.
+pub fn hash(canonical: &str) -> Result<String, Error> {
+    utils::sha256_hex(canonical)
+}
+
+/// Reads the changes recorded in a manifest written by [`write`].
+///
+/// # Errors
+/// Returns an error if the file cannot be read, isn't a manifest, or has an
+/// unsupported version.
+pub fn read(path: &str) -> Result<Vec<Entry>, Error> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| Error::io(format!("Failed to read manifest {path}"), e))?;
+    let file = json::parse(&contents)
+        .map_err(|e| e.context(format!("Invalid manifest {path}")))?;
+    let manifest = file.get("manifest");
+    let version = manifest.get("version").to_string();
+    if version != VERSION.to_string() {
+        return Err(Error::Other(format!(
+            "Invalid manifest {path}: unsupported version {version}"
+        )));
+    }
+    Ok(manifest
+        .get("changes")
+        .items()
+        .iter()
+        .map(|change| Entry {
+            ticket: change.get("ticket").str_or_empty().to_string(),
+            team_id: change.get("team_id").str_or_empty().to_string(),
+            from_state: change.get("from").str_or_empty().to_string(),
+            to_state: change.get("to").str_or_empty().to_string(),
+            to_state_id: change.get("to_state_id").str_or_empty().to_string(),
+            added_labels: strings(change.get("add_labels")),
+            removed_labels: strings(change.get("remove_labels")),
+        })
+        .collect())
+}
+
+/// The strings of JSON array `value`.
+fn strings(value: &json::Value) -> Vec<String> {
+    value
+        .items()
+        .iter()
+        .map(|item| item.str_or_empty().to_string())
+        .collect()
+}
+
+/// Writes a hash-stamped manifest to `path`.
+///
+/// # Returns
+/// The manifest hash.
+///
+/// # Errors
+/// Returns an error if hashing fails or the file cannot be written.
+pub fn write(
+    path: &str,
+    org: &str,
+    entries: Vec<Entry>,
+) -> Result<String, Error> {
+    let canonical = render(org, entries);
+    let hash = hash(&canonical)?;
+    std::fs::write(
+        path,
+        format!(
+            r#"{{"sha256":"{hash}","manifest":{canonical},"schema_version":{SCHEMA_VERSION}}}"#
+        ) + "\n",
+    )
+    .map_err(|e| Error::io(format!("Failed to write manifest {path}"), e))?;
+    Ok(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(ticket: &str, to_state: &str, added_labels: &[&str]) -> Entry {
+        Entry {
+            ticket: ticket.to_string(),
+            team_id: "team-1".to_string(),
+            from_state: "Passing".to_string(),
+            to_state: to_state.to_string(),
+            to_state_id: format!("state-{to_state}"),
+            added_labels: added_labels
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            removed_labels: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn renders_canonical_form() {
+        assert_eq!(
+            render("acme", vec![entry("ABC-1", "Done", &["released"])]),
+            concat!(
+                r#"{"version":2,"org":"acme","changes":[{"ticket":"ABC-1","#,
+                r#""team_id":"team-1","from":"Passing","to":"Done","#,
+                r#""to_state_id":"state-Done","add_labels":["released"],"#,
+                r#""remove_labels":[]}]}"#
+            )
+        );
+    }
+
+    /// A manifest approved with an earlier build must still be accepted, so
+    /// the hash of a given plan only changes along with [`VERSION`].
+    #[test]
+    fn hash_is_stable() -> Result<(), Error> {
+        let canonical = render(
+            "acme",
+            vec![
+                entry("ABC-2", "Done", &[]),
+                entry("ABC-1", "Done", &["released"]),
+            ],
+        );
+        assert_eq!(
+            hash(&canonical)?,
+            "94a5c1ed9056c4f410e575357933128fbc4cbde1e78c6f88bddee1bebffd33d9"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn hash_ignores_input_order_and_duplicates() -> Result<(), Error> {
+        let (a, b) = (entry("ABC-1", "Done", &[]), entry("ABC-2", "Done", &[]));
+        let forward = render("acme", vec![a.clone(), b.clone()]);
+        let backward = render("acme", vec![b.clone(), a.clone(), b]);
+        assert_eq!(hash(&forward)?, hash(&backward)?);
+        Ok(())
+    }
+
+    #[test]
+    fn hash_covers_target_and_labels() -> Result<(), Error> {
+        let planned = hash(&render("acme", vec![entry("ABC-1", "Done", &[])]))?;
+        for changed in [
+            entry("ABC-1", "Shipped", &[]),
+            entry("ABC-1", "Done", &["released"]),
+        ] {
+            assert_ne!(hash(&render("acme", vec![changed]))?, planned);
+        }
+        assert_ne!(
+            hash(&render("other", vec![entry("ABC-1", "Done", &[])]))?,
+            planned
+        );
+        Ok(())
+    }
+}
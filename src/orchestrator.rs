@@ -6,13 +6,14 @@
 //!
 //! ## Pipeline
 //! ```text
-//! parse-notes --release-tag TAG
-//!     | (stdout)
+//! parse-notes --release-tag TAG --output-format ndjson
+//!     | (stdout, PR records)
 //!     v
-//! extract-tickets
-//!     | (stdout)
+//! extract-tickets --output-format ndjson
+//!     | (stdout, ticket records)
 //!     v
-//! update-tickets --linear-api-key KEY --linear-org ORG [--dry-run]
+//! update-tickets --linear-api-key KEY --linear-org ORG [--dry-run] [--output-format FMT]
+//!     (or --tracker jira --jira-base-url URL --jira-api-token TOKEN)
 //!     | (stdout/stderr)
 //!     v
 //! Output to parent process
@@ -21,19 +22,119 @@
 //! ## Implementation
 //! Instead of calling internal functions, this mode spawns the binary
 //! as separate processes. This ensures consistency with standalone usage
-//! and makes the pipeline composable.
+//! and makes the pipeline composable. Stages exchange ndjson records (see
+//! [`crate::protocol`]) so context such as the originating PR isn't lost
+//! between them.
+//!
+//! Spawning [`env::current_exe`] breaks when the binary is run through a
+//! wrapper or renamed. With `--in-process`, each stage runs on a thread of
+//! the orchestrator instead, configured from the same arguments, and passes
+//! its records to the next one over a channel, still line by line.
+//!
+//! ## Several Releases
+//! Given `--release-tag` more than once (e.g. to backfill past releases), the
+//! orchestrator runs one pipeline per release, up to `--jobs` at a time. All
+//! pipelines share a cache directory (see [`crate::cache`]) for GitHub PR
+//! responses and Linear workflow states, and a rate limiter keeping their
+//! combined GitHub and Linear requests under `--rate-limit` per second.
+//!
+//! ## Request Budget
+//! With `--api-budget N`, the stages of every pipeline draw from one count
+//! kept in the cache directory (a temporary one if `--cache-dir` isn't
+//! given). A stage that runs out exits with
+//! [`ApiBudget::EXHAUSTED_EXIT_CODE`]; its release is reported as partial
+//! rather than failed, and the orchestrator exits with the same status.
 
 use std::env;
-use std::process::{Command, Stdio};
+use std::fs;
+use std::io;
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread;
+use std::time::Instant;
+
+use crate::cache::{ApiBudget, Cache, RateLimiter};
+use crate::commit_status::{self, State};
+use crate::config::{
+    Config, Dependencies, Discover, DryRunOutput, Duplicates, Forge,
+    GithubAccess, GithubOutput, InputSource, Lookups, MergePolicy,
+    Notifications, OtherOrgUrls, OutputFormat, PartialFailure, PrFetches,
+    ReleaseAttachment, RepeatRelease, StackedPrs, Stages, Tracker,
+    UnknownTeams, UnlinkedPrs, UrlFormat,
+};
+use crate::deployment;
+use crate::error::Error;
+use crate::exit_status;
+use crate::protocol::{Output, PrRef};
+use crate::release_range;
+use crate::utils::{self, LogFormat};
+
+const NAME: &str = "orchestrator";
+
+/// Default of `--jobs`.
+const DEFAULT_JOBS: usize = 4;
+
+/// Default of `--rate-limit` when running several releases.
+const DEFAULT_PARALLEL_RATE_LIMIT: u32 = 10;
 
-use crate::config::Config;
+macro_rules! log {
+    ($level:ident, $fmt:expr) => {
+        utils::log_at(utils::LogLevel::$level, NAME, format_args!($fmt));
+    };
+    ($fmt:expr) => {
+        utils::log(NAME, format_args!($fmt));
+    };
+}
+
+/// Cache directory, rate limit and request budget forwarded to the stages
+/// of every pipeline.
+#[derive(Debug, Default)]
+struct Shared {
+    cache_dir: Option<String>,
+    rate_limit: Option<u32>,
+    api_budget: Option<ApiBudget>,
+}
+
+impl Shared {
+    /// The `--cache-dir`, `--rate-limit` and `--api-budget` arguments of a
+    /// stage.
+    fn args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(ref dir) = self.cache_dir {
+            args.extend(["--cache-dir".to_string(), dir.clone()]);
+        }
+        if let Some(rate_limit) = self.rate_limit {
+            args.extend(["--rate-limit".to_string(), rate_limit.to_string()]);
+        }
+        if let Some(ref budget) = self.api_budget {
+            args.extend([
+                "--api-budget".to_string(),
+                budget.limit().to_string(),
+            ]);
+        }
+        args
+    }
+}
+
+/// How far a release's pipeline got.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Completion {
+    /// Every stage processed all of its input
+    Complete,
+    /// A stage stopped early because the `--api-budget` ran out
+    Partial,
+}
 
 /// Runs the orchestrator mode to execute the complete pipeline.
 ///
 /// # Required Configuration
-/// - `config.release_tag` - The GitHub release tag to process
+/// - `config.release_tag` - The GitHub release tag to process (or
+///   `config.release_range`, see [Several Releases](#several-releases))
 /// - `LINEAR_API_KEY` (from config or environment)
 /// - `LINEAR_ORG` (from config or environment)
+/// - With `--tracker jira`, `JIRA_BASE_URL` and `JIRA_API_TOKEN` (and
+///   optionally `JIRA_EMAIL`) instead of the Linear credentials
 ///
 /// # Pipeline Stages
 /// 1. **parse-notes**: Extracts PR numbers from release notes
@@ -46,117 +147,985 @@ use crate::config::Config;
 /// 3. Spawns `update-tickets` subprocess, piping from extract-tickets
 /// 4. Waits for completion and forwards output to parent
 ///
+/// With `--in-process`, the stages run on threads of this process instead,
+/// connected by channels.
+///
 /// # Dry-Run Support
 /// If `config.dry_run` is true, passes `--dry-run` flag to update-tickets
 /// stage.
 ///
 /// # Workflow State Filtering
 /// By default, tickets are only updated if their current state name is
-/// "Passing". If `config.update_all_statuses` is true, passes
-/// `--update-all-statuses` to update-tickets.
+/// "Passing", or one of `config.from_states` (forwarded as `--from-states`).
+/// If `config.update_all_statuses` is true, passes `--update-all-statuses`
+/// to update-tickets. A `--filter` expression is forwarded unchanged.
+///
+/// # Approval Workflow
+/// `--manifest FILE` (with `--dry-run`), `--approve HASH` and
+/// `--compare-manifest FILE` are forwarded to update-tickets, as are `--order-by-dependencies` and `--note-blocked`.
+/// `--forge` and `--github-host` are forwarded to parse-notes and
+/// extract-tickets (as is `--discover` to parse-notes, and
+/// `--allow-unmerged`, `--merged-after`, `--merged-before`,
+/// `--warn-unlinked-prs`, `--no-stack-detection`, `--mapping-file`,
+/// `--max-comments`, `--pr-label`, `--path-filter`, `--prefix-map` and
+/// `--exclude-pattern` to extract-tickets, whose output is then already
+/// remapped and filtered), and the
+/// Linear endpoint overrides (`--linear-api-url`, `--linear-header`,
+/// `--linear-resolve`), `--linear-snapshot` and `--debug-dump` to
+/// update-tickets, as are
+/// `--locale`, `--locale-dir`, `--url-format` and the `--comment-*` pacing
+/// flags. With `--comment-release` (or `--comment-template`, also forwarded),
+/// `--release-issue-team`, `--attach-release`, `--state-route`,
+/// `--webhook-url`, `--slack-webhook` or `--history-file` (all forwarded, with `--webhook-secret`, along with `--repeat-release`, `--quarantine-after` and
+/// `--clear-quarantine`), the release tag is passed to
+/// update-tickets too.
+/// `--quiet-updates`, `--github-output`, `--diff`, `--queue-file`, `--checkpoint`,
+/// `--journal`, `--add-to-project`, `--add-label`, `--set-custom-field`,
+/// `--linear-cycle` and `--follow-duplicates`
+/// are forwarded to update-tickets.
+/// `--explain` is forwarded to every stage, so each traces its decisions
+/// about the ticket or PR (see [`crate::explain`]), as are `--quiet`, `-v`,
+/// `-vv` and `--log-format`.
+/// `--tracker jira` is forwarded to extract-tickets and, with the Jira
+/// credentials, to update-tickets.
+///
+/// # Commit Status
+/// With `--commit-status URL` (and without `--dry-run`), a
+/// `release/linear-sync` status is posted on the release tag's commit:
+/// `pending` before the pipeline starts, then `success` or `failure`, each
+/// linking to `URL`. See [`crate::commit_status`].
+///
+/// # Deployment
+/// With `--deployment-environment` or `--deployment-url`, the pipeline only
+/// starts once the release is live (waiting up to `--deployment-timeout`),
+/// so tickets aren't closed for a release that was published but not
+/// deployed. See [`crate::deployment`].
 ///
 /// # Output
 /// - Forwards stdout from update-tickets to parent stdout
 /// - Forwards stderr from update-tickets to parent stderr
 ///
+/// # Several Releases
+/// With more than one `--release-tag`, runs a pipeline per release, up to
+/// `--jobs` (default: [`DEFAULT_JOBS`]) at a time. Without `--cache-dir`, the
+/// pipelines share a temporary cache directory that is removed afterwards;
+/// `--rate-limit` defaults to [`DEFAULT_PARALLEL_RATE_LIMIT`]. A failed
+/// release doesn't stop the others. With `--since-tag`, the releases are
+/// those of the range (see [`crate::release_range`]), oldest first, and a
+/// range holding a single release runs like one `--release-tag`.
+///
+/// # Request Budget
+/// With `--api-budget`, the stages share a cache directory (a temporary one
+/// if needed) holding the request count. If a stage runs out, the budget of
+/// `config` is marked exhausted, so the process exits with
+/// [`ApiBudget::EXHAUSTED_EXIT_CODE`].
+///
 /// # Errors
 /// Returns an error if:
-/// - `--release-tag` is not provided
-/// - `LINEAR_API_KEY` or `LINEAR_ORG` cannot be determined
+/// - `--release-tag` (or `--since-tag`) is not provided
+/// - The releases of the `--since-tag` range can't be listed
+/// - `LINEAR_API_KEY` or `LINEAR_ORG` (or, with `--tracker jira`,
+///   `JIRA_BASE_URL` or `JIRA_API_TOKEN`) cannot be determined
+/// - The cache directory cannot be created
 /// - Any subprocess fails to spawn
 /// - Pipe redirection fails
-/// - The pipeline exits with non-zero status
-pub fn run(config: &Config) -> Result<(), String> {
+/// - The pipeline exits with non-zero status (for any release)
+/// - The commit status cannot be posted
+/// - The release isn't deployed within the `--deployment-timeout`
+pub fn run(config: &Config) -> Result<(), Error> {
     // Validate required configuration
-    let release_tag = config.release_tag.as_ref().ok_or_else(|| {
-        "Orchestrator mode requires --release-tag flag".to_string()
-    })?;
+    if config.release_tag.is_none() && config.release_range.is_none() {
+        return Err(Error::Config(
+            "Orchestrator mode requires --release-tag (or --since-tag)"
+                .to_string(),
+        ));
+    }
+
+    // Get the tracker's credentials from config or environment
+    let credentials = tracker_credentials(config)?;
+
+    let tags = match config.release_range {
+        Some(ref range) => release_range::tags(config, range)?,
+        None => config.release_tags.clone(),
+    };
+    let Some(release_tag) = tags.first() else {
+        let since = config
+            .release_range
+            .as_ref()
+            .map_or("", |range| &range.since);
+        log!("no releases after {since}, nothing to do");
+        return Ok(());
+    };
+    let several = tags.len() > 1;
+    let temp_cache_dir = (config.cache_dir.is_none()
+        && (several
+            || config.rate_limiter.is_some()
+            || config.api_budget.is_some()))
+    .then(|| {
+        env::temp_dir()
+            .join(format!(
+                "release-linear-ticket-update-{}",
+                std::process::id()
+            ))
+            .to_string_lossy()
+            .into_owned()
+    });
+    let shared = Shared {
+        cache_dir: config.cache_dir.clone().or_else(|| temp_cache_dir.clone()),
+        rate_limit: config
+            .rate_limiter
+            .as_ref()
+            .map(RateLimiter::requests_per_sec)
+            .or(several.then_some(DEFAULT_PARALLEL_RATE_LIMIT)),
+        api_budget: config.api_budget.clone(),
+    };
+    if let Some(ref dir) = shared.cache_dir {
+        Cache::open(dir)?;
+    }
+
+    let result = if several {
+        run_releases(config, &tags, &credentials, &shared)
+    } else {
+        run_release(config, release_tag, &credentials, &shared).map(drop)
+    };
+
+    if let Some(dir) = temp_cache_dir
+        && let Err(e) = fs::remove_dir_all(&dir)
+    {
+        log!(Warn, "Failed to remove temporary cache dir {dir}: {e}");
+    }
+    result
+}
+
+/// The update-tickets arguments carrying the tracker's credentials (with the
+/// `--linear-actor-token`, if any), resolved up front so a missing one fails
+/// before any stage is spawned.
+///
+/// # Errors
+/// Returns an error if `LINEAR_API_KEY` or `LINEAR_ORG` (with `--tracker
+/// jira`: `JIRA_BASE_URL` or `JIRA_API_TOKEN`) cannot be determined.
+fn tracker_credentials(config: &Config) -> Result<Vec<String>, Error> {
+    Ok(match config.tracker {
+        Tracker::Linear => {
+            let mut args = vec![
+                "--linear-api-key".to_string(),
+                config.get_linear_api_key()?,
+                "--linear-org".to_string(),
+                config.get_linear_org()?,
+            ];
+            if let Some(token) = config.get_linear_actor_token() {
+                args.extend(["--linear-actor-token".to_string(), token]);
+            }
+            args
+        }
+        Tracker::Jira => {
+            let mut args = vec![
+                "--tracker".to_string(),
+                config.tracker.name().to_string(),
+                "--jira-base-url".to_string(),
+                config.get_jira_base_url()?,
+                "--jira-api-token".to_string(),
+                config.get_jira_api_token()?,
+            ];
+            if let Some(email) = config.get_jira_email() {
+                args.extend(["--jira-email".to_string(), email]);
+            }
+            args
+        }
+    })
+}
+
+/// Takes the next unprocessed release of `tags` (the index `next`) and runs
+/// its pipeline, until none are left.
+///
+/// # Returns
+/// The indexes of the releases whose pipeline succeeded.
+fn release_worker(
+    config: &Config,
+    tags: &[String],
+    next: &AtomicUsize,
+    credentials: &[String],
+    shared: &Shared,
+) -> Vec<usize> {
+    let mut succeeded = Vec::new();
+    loop {
+        let idx = next.fetch_add(1, Ordering::Relaxed);
+        let Some(tag) = tags.get(idx) else {
+            break;
+        };
+        log!("release {tag}: started");
+        match run_release(config, tag, credentials, shared) {
+            Ok(Completion::Complete) => {
+                log!("release {tag}: done");
+                succeeded.push(idx);
+            }
+            Ok(Completion::Partial) => {
+                log!(
+                    Warn,
+                    "release {tag}: stopped early, the --api-budget ran out"
+                );
+                succeeded.push(idx);
+            }
+            Err(e) => {
+                log!(Warn, "release {tag}: {e}");
+            }
+        }
+    }
+    succeeded
+}
+
+/// Runs a pipeline per release, up to `--jobs` at a time, then logs how many
+/// releases were done and how long they took.
+///
+/// # Errors
+/// Returns an error listing the releases whose pipeline failed.
+fn run_releases(
+    config: &Config,
+    tags: &[String],
+    credentials: &[String],
+    shared: &Shared,
+) -> Result<(), Error> {
+    let count = tags.len();
+    let jobs = config.jobs.unwrap_or(DEFAULT_JOBS).min(count);
+    log!("processing {count} releases, {jobs} at a time");
+    let started = Instant::now();
+
+    // Workers take the next unprocessed release until none are left.
+    let next = AtomicUsize::new(0);
+    let log_settings = utils::log_settings();
+    let succeeded: Vec<usize> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..jobs)
+            .map(|_| {
+                scope.spawn(|| {
+                    utils::with_log_settings(log_settings, || {
+                        release_worker(config, tags, &next, credentials, shared)
+                    })
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap_or_default())
+            .collect()
+    });
+
+    let failed: Vec<&str> = tags
+        .iter()
+        .enumerate()
+        .filter(|(idx, _)| !succeeded.contains(idx))
+        .map(|(_, tag)| tag.as_str())
+        .collect();
+    let done = succeeded.len();
+    let elapsed = utils::format_elapsed(started.elapsed());
+    log!("{done} of {count} release(s) done in {elapsed}");
+    if failed.is_empty() {
+        return Ok(());
+    }
+    Err(Error::Other(format!(
+        "{} of {count} release(s) failed: {}",
+        failed.len(),
+        failed.join(", ")
+    )))
+}
+
+/// Runs the pipeline for one release, wrapped in its commit status, once
+/// the release is deployed. A partial run is posted as a failure, since some
+/// tickets weren't processed.
+///
+/// # Errors
+/// Returns an error if the release isn't deployed in time, the pipeline
+/// fails or the commit status cannot be posted.
+fn run_release(
+    config: &Config,
+    release_tag: &str,
+    credentials: &[String],
+    shared: &Shared,
+) -> Result<Completion, Error> {
+    let status_target = config
+        .commit_status_url
+        .as_deref()
+        .filter(|_| !config.dry_run);
+    let Some(target_url) = status_target else {
+        deployment::wait(config, release_tag)?;
+        return run_pipeline(config, release_tag, credentials, shared);
+    };
+
+    let github = config.github()?;
+    let sha = commit_status::tag_commit(release_tag, &github)?;
+    commit_status::post(&sha, State::Pending, target_url, &github)?;
+
+    let result = deployment::wait(config, release_tag)
+        .and_then(|()| run_pipeline(config, release_tag, credentials, shared));
+    let state = if matches!(result, Ok(Completion::Complete)) {
+        State::Success
+    } else {
+        State::Failure
+    };
+    let posted = commit_status::post(&sha, state, target_url, &github);
+
+    // A pipeline failure is the more useful error to report.
+    let completion = result?;
+    posted.map(|()| completion)
+}
+
+/// Runs the three pipeline stages and waits for all of them: as processes
+/// of the binary, or with `--in-process` on threads of this one.
+///
+/// A stage running out of `--api-budget` makes the run
+/// [`Completion::Partial`] and marks the shared budget exhausted.
+///
+/// # Errors
+/// Returns an error if a stage fails to start, or fails for any other
+/// reason than running out of `--api-budget`.
+fn run_pipeline(
+    config: &Config,
+    release_tag: &str,
+    credentials: &[String],
+    shared: &Shared,
+) -> Result<Completion, Error> {
+    let stage_args = stage_args(config, release_tag, credentials, shared);
+    match config.stages {
+        Stages::Spawned => spawn_pipeline(&stage_args, shared),
+        Stages::InProcess => run_in_process(&stage_args, shared),
+    }
+}
+
+/// The arguments of the parse-notes, extract-tickets and update-tickets
+/// stages of the pipeline of `release_tag`, mode first.
+fn stage_args(
+    config: &Config,
+    release_tag: &str,
+    credentials: &[String],
+    shared: &Shared,
+) -> [Vec<String>; 3] {
+    let mut forge_args = Vec::new();
+    if config.forge != Forge::GitHub {
+        forge_args.extend(["--forge".to_string(), config.forge.name().into()]);
+    }
+    if let Some(ref host) = config.github_host {
+        forge_args.extend(["--github-host".to_string(), host.clone()]);
+    }
+    let diagnostic_args = diagnostic_args(config);
+    let github_args = github_args(config);
 
-    // Get Linear credentials from config or environment
-    let linear_api_key = config.get_linear_api_key()?;
-    let linear_org = config.get_linear_org()?;
+    // Stage 1: Parse release notes to extract PR numbers
+    // parse-notes --release-tag <TAG> --output-format ndjson
+    let mut parse_args = vec![
+        "parse-notes".to_string(),
+        "--release-tag".to_string(),
+        release_tag.to_string(),
+        "--output-format".to_string(),
+        "ndjson".to_string(),
+    ];
+    parse_args.extend(forge_args.iter().cloned());
+    parse_args.extend(discover_args(config));
+    parse_args.extend(github_args.iter().cloned());
+    parse_args.extend(diagnostic_args.iter().cloned());
+    if let Some(ref dir) = shared.cache_dir {
+        parse_args.extend(["--cache-dir".to_string(), dir.clone()]);
+    }
+
+    // Stage 2: Extract Linear tickets from PRs, read from parse-notes
+    // extract-tickets --output-format ndjson
+    let mut extract_args = vec![
+        "extract-tickets".to_string(),
+        "--output-format".to_string(),
+        "ndjson".to_string(),
+    ];
+    extract_args.extend(forge_args);
+    extract_args.extend(github_args);
+    extract_args.extend(diagnostic_args);
+    extract_args.extend(extract_tickets_args(config));
+    if let Some(ref path) = config.mapping_file {
+        extract_args.extend(["--mapping-file".to_string(), path.clone()]);
+    }
+    extract_args.extend(shared.args());
 
+    // Stage 3: Update Linear tickets to completed state, read from
+    // extract-tickets
+    // update-tickets --linear-api-key <KEY> --linear-org <ORG> [--dry-run]
+    let mut update_args = update_tickets_args(config, release_tag, credentials);
+    update_args.extend(shared.args());
+
+    [parse_args, extract_args, update_args]
+}
+
+/// Spawns the stages as processes of the binary, each reading the stdout of
+/// the one before, and waits for all of them.
+///
+/// A stage exiting with [`ApiBudget::EXHAUSTED_EXIT_CODE`] makes the run
+/// [`Completion::Partial`] and marks the shared budget exhausted.
+///
+/// # Errors
+/// Returns an error if a stage fails to spawn or exits with any other
+/// non-zero status.
+fn spawn_pipeline(
+    stage_args: &[Vec<String>; 3],
+    shared: &Shared,
+) -> Result<Completion, Error> {
+    let [parse_args, extract_args, update_args] = stage_args;
     // Get path to current executable for spawning subprocesses
     let exe_path = env::current_exe()
-        .map_err(|e| format!("Failed to get current executable path: {e}"))?;
+        .map_err(|e| Error::io("Failed to get current executable path", e))?;
 
-    // Stage 1: Parse release notes to extract PR numbers
-    // Spawns: release-linear-ticket-update parse-notes --release-tag <TAG>
     let mut parse_cmd = Command::new(&exe_path)
-        .args(["parse-notes", "--release-tag", release_tag])
+        .args(parse_args)
         .stdout(Stdio::piped()) // Capture stdout for piping to next stage
         .spawn()
-        .map_err(|e| format!("Failed to spawn parse-notes: {e}"))?;
+        .map_err(|e| Error::io("Failed to spawn parse-notes", e))?;
 
-    let parse_stdout = parse_cmd
-        .stdout
-        .take()
-        .ok_or_else(|| "Failed to capture parse-notes stdout".to_string())?;
+    let parse_stdout = parse_cmd.stdout.take().ok_or_else(|| {
+        Error::Other("Failed to capture parse-notes stdout".to_string())
+    })?;
 
-    // Stage 2: Extract Linear tickets from PRs
-    // Spawns: release-linear-ticket-update extract-tickets
-    // Reads from parse-notes stdout
     let mut extract_cmd = Command::new(&exe_path)
-        .arg("extract-tickets")
+        .args(extract_args)
         .stdin(parse_stdout)
         .stdout(Stdio::piped()) // Capture stdout for piping to next stage
         .spawn()
-        .map_err(|e| format!("Failed to spawn extract-tickets: {e}"))?;
+        .map_err(|e| Error::io("Failed to spawn extract-tickets", e))?;
 
     let extract_stdout = extract_cmd.stdout.take().ok_or_else(|| {
-        "Failed to capture extract-tickets stdout".to_string()
+        Error::Other("Failed to capture extract-tickets stdout".to_string())
     })?;
 
-    // Stage 3: Update Linear tickets to completed state
-    // Build arguments dynamically to include --dry-run if needed
-    let mut update_args = vec![
-        "update-tickets",
-        "--linear-api-key",
-        &linear_api_key,
-        "--linear-org",
-        &linear_org,
-    ];
-
-    // Add --dry-run flag if in preview mode
-    if config.dry_run {
-        update_args.push("--dry-run");
-    }
-
-    if config.update_all_statuses {
-        update_args.push("--update-all-statuses");
-    }
-
-    // Spawns: release-linear-ticket-update update-tickets --linear-api-key
-    // <KEY> --linear-org <ORG> [--dry-run] Reads from extract-tickets
-    // stdout
     let mut update_child = Command::new(&exe_path)
-        .args(&update_args)
+        .args(update_args)
         .stdin(extract_stdout)
         .stdout(Stdio::inherit())
         .stderr(Stdio::inherit())
         .spawn()
-        .map_err(|e| format!("Failed to spawn update-tickets: {e}"))?;
+        .map_err(|e| Error::io("Failed to spawn update-tickets", e))?;
 
     // Important: wait on *every* stage so failures don't get masked by a
     // successful last stage.
     let update_status = update_child
         .wait()
-        .map_err(|e| format!("Failed to wait for update-tickets: {e}"))?;
+        .map_err(|e| Error::io("Failed to wait for update-tickets", e))?;
 
     let extract_status = extract_cmd
         .wait()
-        .map_err(|e| format!("Failed to wait for extract-tickets: {e}"))?;
+        .map_err(|e| Error::io("Failed to wait for extract-tickets", e))?;
 
     let parse_status = parse_cmd
         .wait()
-        .map_err(|e| format!("Failed to wait for parse-notes: {e}"))?;
+        .map_err(|e| Error::io("Failed to wait for parse-notes", e))?;
+
+    completion(&[parse_status, extract_status, update_status], shared)
+}
+
+/// Runs the stages on threads of this process (`--in-process`), each
+/// reading the lines the one before writes to a channel as they come, and
+/// waits for all of them. Each stage's configuration is parsed from its
+/// arguments, as a spawned stage's would be.
+///
+/// # Errors
+/// Returns an error if a stage's arguments are invalid, or a stage fails
+/// for any other reason than running out of `--api-budget`, with the error
+/// of the first such stage (and so its failure class).
+fn run_in_process(
+    stage_args: &[Vec<String>; 3],
+    shared: &Shared,
+) -> Result<Completion, Error> {
+    let mut stages = Vec::new();
+    let mut input = None;
+    for (idx, args) in stage_args.iter().enumerate() {
+        let mut stage = Config::from_arg_list(args)?;
+        if let Some(lines) = input.take() {
+            stage.input_sources =
+                vec![InputSource::Channel(Arc::new(Mutex::new(lines)))];
+        }
+        if idx + 1 < stage_args.len() {
+            let (sender, lines) = mpsc::channel();
+            stage.output = Output::Channel(sender);
+            input = Some(lines);
+        }
+        stages.push((args[0].as_str(), stage));
+    }
+
+    let results: Vec<_> = thread::scope(|scope| {
+        let running: Vec<_> = stages
+            .into_iter()
+            .map(|(name, stage)| {
+                // Moved in, so the stage's output channel closes (ending the
+                // next stage's input) as soon as it finishes.
+                let running = scope.spawn(move || {
+                    let result = crate::run(&stage);
+                    let exhausted = stage
+                        .api_budget
+                        .as_ref()
+                        .is_some_and(ApiBudget::exhausted);
+                    (result, exhausted)
+                });
+                (name, running)
+            })
+            .collect();
+        running
+            .into_iter()
+            .map(|(name, running)| {
+                let (result, exhausted) = running.join().unwrap_or_else(|_| {
+                    (Err(Error::Other("Stage panicked".to_string())), false)
+                });
+                (name, result, exhausted)
+            })
+            .collect()
+    });
+
+    // A stage stopped by the next one ending isn't what failed: the next
+    // stage's error (or exhausted budget) decides.
+    let any_exhausted = results.iter().any(|(_, _, exhausted)| *exhausted);
+    let mut failure = None;
+    for (name, result, exhausted) in results {
+        if let Err(e) = result {
+            if exhausted || failure.is_some() || stopped_by_next_stage(&e) {
+                log!(Warn, "{name}: {e}");
+            } else {
+                failure = Some(e.context(name));
+            }
+        }
+    }
+    if let Some(e) = failure {
+        return Err(e.context("Pipeline failed"));
+    }
+    if !any_exhausted {
+        return Ok(Completion::Complete);
+    }
+    if let Some(ref budget) = shared.api_budget {
+        budget.mark_exhausted();
+    }
+    Ok(Completion::Partial)
+}
+
+/// Whether an in-process stage failed only because the next stage stopped
+/// reading its records (see [`crate::protocol::RecordWriter::write`]).
+fn stopped_by_next_stage(e: &Error) -> bool {
+    matches!(
+        e.root(),
+        Error::Io { source, .. } if source.kind() == io::ErrorKind::BrokenPipe
+    )
+}
+
+/// How far a pipeline got, from the exit statuses of its stages.
+///
+/// # Errors
+/// Returns an error if a stage failed for any reason other than running out
+/// of `--api-budget`, with the failure class of the first stage that exited
+/// with one (see [`crate::exit_status`]).
+fn completion(
+    statuses: &[ExitStatus],
+    shared: &Shared,
+) -> Result<Completion, Error> {
+    let exhausted = |status: &ExitStatus| {
+        status.code() == Some(ApiBudget::EXHAUSTED_EXIT_CODE)
+    };
+    if statuses
+        .iter()
+        .any(|status| !status.success() && !exhausted(status))
+    {
+        return Err(statuses
+            .iter()
+            .map(|&status| {
+                exit_status::stage_error(status, "Pipeline failed".to_string())
+            })
+            .find(|error| matches!(error, Error::Stage { .. }))
+            .unwrap_or_else(|| Error::Other("Pipeline failed".to_string())));
+    }
+    if !statuses.iter().any(exhausted) {
+        return Ok(Completion::Complete);
+    }
+    if let Some(ref budget) = shared.api_budget {
+        budget.mark_exhausted();
+    }
+    Ok(Completion::Partial)
+}
 
-    if !parse_status.success()
-        || !extract_status.success()
-        || !update_status.success()
+/// The extract-tickets flags forwarded from `config` (`--tracker`,
+/// `--allow-unmerged`, `--merged-after`, `--merged-before`,
+/// `--warn-unlinked-prs`, `--no-stack-detection`, `--ignore-token`,
+/// `--no-default-ignores`, `--max-comments`, `--batch-prs` or else
+/// `--concurrency`, `--prefix-map`, `--exclude-pattern`, `--ticket-pattern`,
+/// `--linear-org`, `--other-org-urls`, `--trailer-keys`, `--ignore-tickets`,
+/// `--ignore-prs`, `--pr-label`, `--path-filter`), shared with the report
+/// mode.
+///
+/// With `--org-map`, tickets of other workspaces' URLs are always tagged
+/// rather than dropped: only update-tickets knows which of those workspaces
+/// are mapped.
+pub fn extract_tickets_args(config: &Config) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut flag = |name: &str, value: Option<&str>| {
+        args.push(name.to_string());
+        args.extend(value.map(String::from));
+    };
+
+    if config.tracker != Tracker::Linear {
+        flag("--tracker", Some(config.tracker.name()));
+    }
+
+    if config.merge_policy == MergePolicy::AllowUnmerged {
+        flag("--allow-unmerged", None);
+    }
+
+    for (name, value) in [
+        ("--merged-after", &config.merged_after),
+        ("--merged-before", &config.merged_before),
+        ("--exclude-pattern", &config.exclude_pattern),
+        ("--ticket-pattern", &config.ticket_pattern),
+    ] {
+        if let Some(value) = value {
+            flag(name, Some(value));
+        }
+    }
+
+    if config.unlinked_prs == UnlinkedPrs::Warn {
+        flag("--warn-unlinked-prs", None);
+    }
+
+    if config.stacked_prs == StackedPrs::Ignore {
+        flag("--no-stack-detection", None);
+    }
+
+    if !config.trailer_keys.is_empty() {
+        flag("--trailer-keys", Some(&config.trailer_keys.join(",")));
+    }
+
+    // Forwarded as lists, so the stage doesn't read the files again.
+    let ignore_list = &config.ignore_list;
+    if !ignore_list.tickets().is_empty() {
+        flag("--ignore-tickets", Some(&ignore_list.tickets().join(",")));
+    }
+    if !ignore_list.prs().is_empty() {
+        let prs: Vec<String> =
+            ignore_list.prs().iter().map(PrRef::to_text).collect();
+        flag("--ignore-prs", Some(&prs.join(",")));
+    }
+
+    if let Some(ref org) = config.linear_org {
+        flag("--linear-org", Some(org));
+    }
+
+    if config.other_org_urls == OtherOrgUrls::Tag || !config.org_map.is_empty()
     {
-        return Err("Pipeline failed".to_string());
+        flag("--other-org-urls", Some("tag"));
+    }
+
+    if !config.false_positives.builtin() {
+        flag("--no-default-ignores", None);
+    }
+
+    for pattern in config.false_positives.extra() {
+        flag("--ignore-token", Some(pattern));
+    }
+
+    if let Some(max) = config.max_comments {
+        flag("--max-comments", Some(&max.to_string()));
+    }
+
+    if config.pr_fetches == PrFetches::Batched {
+        flag("--batch-prs", None);
+    } else if let Some(concurrency) = config.concurrency {
+        flag("--concurrency", Some(&concurrency.to_string()));
+    }
+
+    for entry in config.prefix_map.entries() {
+        flag("--prefix-map", Some(&entry));
+    }
+
+    for label in &config.pr_scope.labels {
+        flag("--pr-label", Some(label));
+    }
+    for glob in &config.pr_scope.paths {
+        flag("--path-filter", Some(glob));
+    }
+    args
+}
+
+/// Whether the update-tickets flags forwarded from `config` use the release
+/// tag (to comment, record, attach or announce the release, or route its
+/// tickets).
+fn needs_release_tag(config: &Config) -> bool {
+    config.comment_release
+        || config.release_issue_team.is_some()
+        || config.release_attachment == ReleaseAttachment::Attach
+        || config.history_file.is_some()
+        || config.webhook_url.is_some()
+        || config.slack_webhook.is_some()
+        || !config.state_routes.is_empty()
+}
+
+/// Builds the update-tickets arguments, forwarding the orchestrator's
+/// update-related flags.
+fn update_tickets_args(
+    config: &Config,
+    release_tag: &str,
+    credentials: &[String],
+) -> Vec<String> {
+    let mut args = vec!["update-tickets".to_string()];
+    args.extend_from_slice(credentials);
+    let mut flag = |name: &str, value: Option<&str>| {
+        args.push(name.to_string());
+        args.extend(value.map(String::from));
+    };
+
+    if needs_release_tag(config) {
+        flag("--release-tag", Some(release_tag));
+    }
+
+    if config.comment_release {
+        flag("--comment-release", None);
+    }
+
+    if config.notifications == Notifications::Quiet {
+        flag("--quiet-updates", None);
+    }
+
+    if config.lookups == Lookups::Batched {
+        flag("--batch-lookups", None);
+    }
+
+    if let Some(concurrency) = config.concurrency {
+        flag("--concurrency", Some(&concurrency.to_string()));
+    }
+
+    if let Some(ref field) = config.custom_field {
+        let spec = format!("{}={}", field.name, field.value);
+        flag("--set-custom-field", Some(&spec));
+    }
+
+    if let Some(cycle) = config.linear_cycle {
+        flag("--linear-cycle", Some(&cycle.to_string()));
+    }
+
+    if config.release_attachment == ReleaseAttachment::Attach {
+        flag("--attach-release", None);
+    }
+
+    for route in config.state_routes.entries() {
+        flag("--state-route", Some(&route));
     }
 
-    Ok(())
+    // Add --dry-run flag if in preview mode
+    if config.dry_run {
+        flag("--dry-run", None);
+    }
+
+    if config.update_all_statuses {
+        flag("--update-all-statuses", None);
+    }
+
+    if config.unknown_teams == UnknownTeams::Drop {
+        flag("--drop-unknown-teams", None);
+    }
+
+    match config.duplicates {
+        Duplicates::Ignore => {}
+        Duplicates::Canonical => flag("--follow-duplicates", Some("canonical")),
+        Duplicates::Both => flag("--follow-duplicates", Some("both")),
+    }
+
+    if config.partial_failure == PartialFailure::Fail {
+        flag("--fail-on-partial", None);
+    }
+
+    for (name, value) in [
+        ("--release-issue-team", &config.release_issue_team),
+        ("--comment-template", &config.comment_template),
+        ("--linear-project", &config.linear_project),
+        ("--add-to-project", &config.add_to_project),
+        ("--add-label", &config.add_label),
+        ("--target-state", &config.target_state),
+        ("--target-state-type", &config.target_state_type),
+        ("--ticket-pattern", &config.ticket_pattern),
+        ("--linear-snapshot", &config.linear_snapshot),
+        ("--debug-dump", &config.debug_dump),
+        ("--manifest", &config.manifest),
+        ("--compare-manifest", &config.compare_manifest),
+        ("--approve", &config.approve),
+        ("--queue-file", &config.queue_file),
+        ("--checkpoint", &config.checkpoint),
+        ("--journal", &config.journal),
+        ("--locale", &config.locale),
+        ("--locale-dir", &config.locale_dir),
+        ("--webhook-url", &config.webhook_url),
+        ("--webhook-secret", &config.webhook_secret),
+        ("--slack-webhook", &config.slack_webhook),
+    ] {
+        if let Some(value) = value {
+            flag(name, Some(value));
+        }
+    }
+
+    for entry in config.org_map.entries() {
+        flag("--org-map", Some(&entry));
+    }
+
+    match config.dependencies {
+        Dependencies::Ignore => {}
+        Dependencies::Order => flag("--order-by-dependencies", None),
+        Dependencies::OrderAndNote => flag("--note-blocked", None),
+    }
+
+    args.extend(selection_args(config));
+    args.extend(output_args(config));
+    args.extend(endpoint_args(config));
+    // For the --comment-template release lookup
+    args.extend(github_args(config));
+    args.extend(history_args(config));
+    args.extend(comment_pacing_args(config));
+    args.extend(diagnostic_args(config));
+    args
+}
+
+/// The ticket selection flags of `config` (`--from-states`, `--filter`,
+/// `--assignee`, `--creator`), as update-tickets arguments.
+fn selection_args(config: &Config) -> Vec<String> {
+    let mut args = Vec::new();
+    if !config.from_states.is_empty() {
+        args.extend([
+            "--from-states".to_string(),
+            config.from_states.join(","),
+        ]);
+    }
+    if let Some(ref filter) = config.filter {
+        args.extend(["--filter".to_string(), filter.source().to_string()]);
+    }
+    for (name, value) in [
+        ("--assignee", &config.assignee),
+        ("--creator", &config.creator),
+    ] {
+        if let Some(value) = value {
+            args.extend([name.to_string(), value.clone()]);
+        }
+    }
+    args
+}
+
+/// The Linear endpoint overrides of `config` (`--linear-api-url`,
+/// `--linear-header`, `--linear-resolve`), as update-tickets arguments.
+fn endpoint_args(config: &Config) -> Vec<String> {
+    let mut args = Vec::new();
+    if let Some(ref url) = config.linear_api_url {
+        args.extend(["--linear-api-url".to_string(), url.clone()]);
+    }
+    for header in &config.linear_headers {
+        args.extend(["--linear-header".to_string(), header.clone()]);
+    }
+    for entry in &config.linear_resolve {
+        args.extend(["--linear-resolve".to_string(), entry.clone()]);
+    }
+    args
+}
+
+/// The GitHub access flags of `config` (`--github-api`, `--github-repo`),
+/// as arguments of every stage. The token is inherited from the
+/// environment.
+pub fn github_args(config: &Config) -> Vec<String> {
+    let mut args = Vec::new();
+    if config.github_access == GithubAccess::Api {
+        args.push("--github-api".to_string());
+    }
+    if let Some(ref repo) = config.github_repo {
+        args.extend(["--github-repo".to_string(), repo.clone()]);
+    }
+    args
+}
+
+/// The PR discovery strategy of `config` (`--discover`), as arguments of
+/// parse-notes.
+pub fn discover_args(config: &Config) -> Vec<String> {
+    if config.discover == Discover::Notes {
+        return Vec::new();
+    }
+    vec!["--discover".to_string(), config.discover.name().to_string()]
+}
+
+/// The `--explain` subject, log level (`--quiet`, `-v`, `-vv`) and
+/// `--log-format` of `config`, as arguments of every stage.
+fn diagnostic_args(config: &Config) -> Vec<String> {
+    let mut args: Vec<String> = config
+        .explain
+        .iter()
+        .flat_map(|subject| ["--explain".to_string(), subject.arg()])
+        .chain(config.log_level.flag().map(String::from))
+        .collect();
+    if config.log_format != LogFormat::Text {
+        args.extend([
+            "--log-format".to_string(),
+            config.log_format.name().to_string(),
+        ]);
+    }
+    args
+}
+
+/// The flags shaping update-tickets' output (`--output-format`,
+/// `--github-output`, `--diff`, `--url-format`), as arguments.
+fn output_args(config: &Config) -> Vec<String> {
+    let mut args = Vec::new();
+    if config.output_format != OutputFormat::Text {
+        args.extend([
+            "--output-format".to_string(),
+            config.output_format.name().to_string(),
+        ]);
+    }
+    if config.github_output == GithubOutput::Write {
+        args.push("--github-output".to_string());
+    }
+    if config.dry_run_output == DryRunOutput::Diff {
+        args.push("--diff".to_string());
+    }
+    let url_format = match config.url_format {
+        UrlFormat::Url => None,
+        UrlFormat::Id => Some("id"),
+        UrlFormat::Markdown => Some("markdown"),
+    };
+    if let Some(url_format) = url_format {
+        args.extend(["--url-format".to_string(), url_format.to_string()]);
+    }
+    args
+}
+
+/// The run history flags of `config`, as update-tickets arguments.
+fn history_args(config: &Config) -> Vec<String> {
+    let Some(ref history_file) = config.history_file else {
+        return Vec::new();
+    };
+    let mut args = vec!["--history-file".to_string(), history_file.clone()];
+    if config.repeat_release == RepeatRelease::Fail {
+        args.extend(["--repeat-release".to_string(), "fail".to_string()]);
+    }
+    if let Some(after) = config.quarantine_after {
+        args.extend(["--quarantine-after".to_string(), after.to_string()]);
+    }
+    for ticket in &config.clear_quarantine {
+        args.extend(["--clear-quarantine".to_string(), ticket.clone()]);
+    }
+    args
+}
+
+/// The `--comment-*` pacing flags of `config`, as update-tickets arguments.
+fn comment_pacing_args(config: &Config) -> Vec<String> {
+    let pacing = &config.comment_pacing;
+    let mut args = Vec::new();
+    for (name, value) in [
+        (
+            "--comment-interval",
+            pacing.interval_ms.map(|ms| ms.to_string()),
+        ),
+        (
+            "--comment-batch-size",
+            pacing.batch_size.map(|n| n.to_string()),
+        ),
+        (
+            "--comment-batch-pause",
+            pacing.batch_pause_secs.map(|secs| secs.to_string()),
+        ),
+    ] {
+        if let Some(value) = value {
+            args.extend([name.to_string(), value]);
+        }
+    }
+    if let Some(ref progress) = pacing.progress {
+        args.extend(["--comment-progress".to_string(), progress.clone()]);
+    }
+    args
 }
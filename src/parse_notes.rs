@@ -1,21 +1,127 @@
 //! Parse release notes to extract Pull Request numbers.
 //!
 //! This module implements the `parse-notes` mode which extracts PR numbers from
-//! GitHub release notes. It supports two input patterns:
+//! GitHub release notes. It supports three input patterns:
 //! - Short format: `#123`
+//! - Repo-qualified format: `owner/repo#123`
 //! - Full URL format: `https://github.com/owner/repo/pull/123`
 //!
-//! The output is deduplicated PR numbers (one per line), printed immediately
-//! as they are discovered. No sorting or buffering to minimize latency.
+//! With `--forge gitlab`, the notes are those of the GitLab release (read
+//! with `glab`), and merge request references are matched instead:
+//! - Short format: `!123`
+//! - Project-qualified format: `group/project!123` (output as
+//!   `group/project#123`)
+//! - Full URL format: `https://gitlab.com/group/project/-/merge_requests/123`
+//!   (output as `group/project#123`)
+//!
+//! `#123` is a GitLab issue, not a merge request, so it isn't matched.
+//!
+//! With `--github-api`, GitHub release notes are read from the REST API
+//! instead of with `gh` (see [`crate::github_api`]).
+//!
+//! The output is deduplicated PR identifiers (one per line), printed
+//! immediately as they are discovered. No sorting or buffering to minimize
+//! latency.
+//!
+//! ## Normalization
+//! Hand-written notes are normalized line by line before matching:
+//! - CRLF (and lone CR) line endings are treated as plain line breaks
+//! - Full-width characters such as `＃１２３` (U+FF01 to U+FF5E) are folded
+//!   to their ASCII counterparts, and the ideographic space to a space
+//! - A reference split by a hard wrap is joined back together: a line whose
+//!   last word ends in `#` or `/` continues on the next line, as does one
+//!   ending in `owner/repo` (or a URL) when the next line starts with `#123`
+//!   or `/pull/123`
+//!
+//! Only the joined line is held back, so output still streams.
+//!
+//! ## PR Identifier Format
+//! Identifiers passed to `extract-tickets` are either a bare PR number (`123`,
+//! resolved against the current repository) or a repo-qualified reference
+//! (`owner/repo#123`) for PRs that live in another repository. A full URL is
+//! output repo-qualified too (`https://github.com/acme/lib/pull/7` as
+//! `acme/lib#7`), so extract-tickets fetches the PR from the repository it
+//! names. A URL or reference naming the current repository is output as the
+//! bare number instead, so `#7` and its URL are one PR (the current
+//! repository is found with `gh repo view`, `glab` or `--github-repo` the
+//! first time a reference names a repository). With
+//! `--output-format ndjson` they are written as PR records instead, and with
+//! `--output-format json` as an array of them once every PR is found (see
+//! [`crate::protocol`]).
+//!
+//! ## Cache
+//! With `--cache-dir`, the release notes of a tag are cached under
+//! `releases/<forge>-<tag>` once fetched, so repeated (dry) runs against the
+//! same release read them from disk instead of asking GitHub or GitLab
+//! again. The notes are still streamed as they are fetched. A cached entry
+//! is never refreshed; remove it to pick up edited release notes.
+//!
+//! ## Input Files
+//! Without a release, the notes are read from stdin, or from the files given
+//! as arguments (`-` for stdin), e.g. release notes saved as Markdown. They
+//! are read in order, as the other stages read theirs (see
+//! [`utils::for_each_input_line`]), and parsed as one set of notes: a PR
+//! referenced by several files is output once.
+//!
+//! ## Release Range
+//! With `--since-tag` (and `--until-tag`), the notes of every release of the
+//! range are read in turn, oldest first (see [`crate::release_range`]). A PR
+//! referenced by several of them is output once.
+//!
+//! ## Compare
+//! Hand-written notes can miss PRs. With `--discover compare`, the PRs are
+//! those named by the commits between the previous release and the release
+//! instead (see [`crate::compare`]); with `--discover both`, the PRs of the
+//! commits are output after those of the notes, each PR once.
+//!
+//! ## Ignored PRs
+//! PRs of `--ignore-prs` (e.g. reverts) are left out of the output, each
+//! logged the first time the notes reference it.
+//!
+//! ## Dry Run
+//! With `--dry-run`, the release notes are fetched and parsed as usual, but
+//! nothing is written to stdout: only the number of PRs found is logged. A
+//! pre-flight job can check that the release exists and references PRs
+//! without running the rest of the pipeline.
+//!
+//! ## Explain
+//! With `--explain` of a PR, whether the release notes reference it is
+//! logged (see [`crate::explain`]).
 
+use std::cell::OnceCell;
 use std::collections::HashSet;
-use std::io::{self, BufRead};
-use std::process::{Command, Stdio};
+use std::io::{self, BufRead, Read, Write};
+use std::process::{ChildStdin, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
 
-use crate::config::Config;
-use crate::utils;
+use crate::cache::Cache;
+use crate::compare;
+use crate::config::{Config, Discover, Forge, IgnoreList};
+use crate::error::Error;
+use crate::exit_status;
+use crate::explain::{self, Subject};
+use crate::github_api::GitHub;
+use crate::gitlab;
+use crate::protocol::{PrRef, RecordWriter};
+use crate::release_range;
+use crate::utils::{self, LogLevel, NoInputTimeout};
 
 const NAME: &str = "parse-notes";
+/// Namespace of release notes in the `--cache-dir` cache.
+const CACHE_NAMESPACE: &str = "releases";
+/// PR references in GitHub release notes.
+const GITHUB_PATTERN: &str = concat!(
+    r"([A-Za-z0-9-]+/[A-Za-z0-9_.-]+#[0-9]+",
+    r"|#[0-9]+",
+    r"|https://github\.com/[^/]+/[^/]+/pull/[0-9]+)"
+);
+/// Merge request references in GitLab release notes (`--forge gitlab`).
+const GITLAB_PATTERN: &str = concat!(
+    r"([A-Za-z0-9_.-]+(/[A-Za-z0-9_.-]+)+![0-9]+",
+    r"|![0-9]+",
+    r"|https://[^/[:space:]]+(/[A-Za-z0-9_.-]+)+/-/merge_requests/[0-9]+)"
+);
 
 macro_rules! log {
     ($fmt:expr) => {
@@ -23,31 +129,88 @@ macro_rules! log {
     };
 }
 
+macro_rules! explain {
+    ($fmt:expr) => {
+        explain::note(NAME, format_args!($fmt));
+    };
+}
+
 /// Runs the parse-notes mode to extract PR numbers from release notes.
 ///
 /// # Input Sources
 /// - If `config.release_tag` is set, fetches release notes from GitHub using
-///   `gh` CLI
+///   `gh` CLI or, with `--github-api`, the REST API (with `--forge gitlab`,
+///   from GitLab using `glab`), or reads them from the `--cache-dir` cache
+/// - With `--since-tag`, does so for every release of the range, oldest
+///   first (see [`crate::release_range`])
+/// - With `--discover compare` (or `both`), takes the PRs of the commits
+///   since the previous release instead of (or besides) those of the notes
+/// - With input files (or `-`), reads release notes from them, in order
 /// - Otherwise, reads release notes from stdin
 ///
 /// # Output
-/// Prints PR numbers to stdout, one per line, deduplicated.
-/// Outputs immediately in order of discovery (no sorting or buffering).
+/// Prints PR identifiers (`123` or `owner/repo#123`) to stdout, one per line,
+/// deduplicated. Outputs immediately in order of discovery (no sorting or buffering).
+/// With `--dry-run`, nothing is printed and the number of PRs is logged
+/// instead.
 ///
 /// # Errors
 /// Returns an error if:
-/// - GitHub CLI fails to fetch release notes
-/// - Release notes contain invalid UTF-8
-/// - stdin cannot be read
-pub fn run(config: &Config) -> Result<(), String> {
-    let mut seen = HashSet::new();
-    let any_output = if let Some(ref tag) = config.release_tag {
-        log!("streaming release notes for tag {tag}");
-        stream_pr_numbers_from_release(tag, &mut seen)?
-    } else {
+/// - GitHub (or GitLab) CLI or the GitHub API fails to fetch release notes
+/// - The release notes cannot be read (invalid UTF-8 is replaced, not an
+///   error, except in input files)
+/// - grep fails
+pub fn run(config: &Config) -> Result<(), Error> {
+    let mut seen = Seen::new(config);
+    // No output at all with --dry-run.
+    let mut output = (!config.dry_run).then(|| {
+        RecordWriter::new(config.output_format, config.output.clone())
+    });
+    let cache = config.cache()?;
+    let any_output = if let Some(ref range) = config.release_range {
+        let tags = release_range::tags(config, range)?;
+        if tags.is_empty() {
+            let since = &range.since;
+            log!("no releases after {since}");
+        } else {
+            let count = tags.len();
+            let listed = tags.join(" ");
+            log!("{count} release(s) in the range: {listed}");
+        }
+        let mut any_output = false;
+        for tag in &tags {
+            any_output |= stream_release(
+                config,
+                tag,
+                cache.as_ref(),
+                &mut output,
+                &mut seen,
+            )?;
+        }
+        any_output
+    } else if let Some(ref tag) = config.release_tag {
+        stream_release(config, tag, cache.as_ref(), &mut output, &mut seen)?
+    } else if config.input_sources.is_empty() {
         log!("streaming release notes from stdin");
-        stream_pr_numbers_from_stdin(&mut seen)?
+        stream_pr_numbers_from_stdin(
+            config.forge,
+            &mut output,
+            &config.ignore_list,
+            &mut seen,
+        )?
+    } else {
+        let count = config.input_sources.len();
+        log!("reading release notes from {count} input(s)");
+        stream_pr_numbers_from_inputs(config, &mut output, &mut seen)?
     };
+    if let Some(ref mut output) = output {
+        output.finish();
+    }
+    if config.dry_run {
+        let count = seen.len();
+        log!("dry run: found {count} PR(s) in the release notes, none output");
+    }
+    explain_notes(config, &seen);
     if any_output {
         log!("done");
     } else {
@@ -58,91 +221,706 @@ pub fn run(config: &Config) -> Result<(), String> {
     Ok(())
 }
 
+/// Streams the PR identifiers of release `tag`, found as `--discover`
+/// says: in its notes, the commits since the previous release, or both.
+///
+/// # Errors
+/// Returns an error if the release notes can't be fetched or cached, or
+/// the commits can't be compared.
+fn stream_release(
+    config: &Config,
+    tag: &str,
+    cache: Option<&Cache>,
+    output: &mut Option<RecordWriter>,
+    seen: &mut Seen<'_>,
+) -> Result<bool, Error> {
+    let mut any_output = false;
+    if config.discover.notes() {
+        any_output |= stream_notes(config, tag, cache, output, seen)?;
+    }
+    if config.discover.compare() {
+        any_output |= stream_compared(config, tag, output, seen)?;
+    }
+    Ok(any_output)
+}
+
+/// Outputs the PRs named by the commits between the release before `tag`
+/// and `tag` (see [`crate::compare`]), past those already `seen`.
+///
+/// # Errors
+/// Returns an error if the previous release can't be found or the commits
+/// can't be compared.
+fn stream_compared(
+    config: &Config,
+    tag: &str,
+    output: &mut Option<RecordWriter>,
+    seen: &mut Seen<'_>,
+) -> Result<bool, Error> {
+    let previous = release_range::previous(config, tag)?;
+    log!("comparing the commits of {previous}...{tag}");
+    let prs = compare::merged_prs(&config.github()?, &previous, tag)?;
+    let before = seen.len();
+    let mut any_output = false;
+    let mut ignored = HashSet::new();
+    for pr in &prs {
+        any_output |=
+            emit_pr(pr, &config.ignore_list, &mut ignored, output, seen)?;
+    }
+    let found = seen.len() - before;
+    log!("the commits name {found} PR(s) not found before");
+    Ok(any_output)
+}
+
+/// Streams the PR identifiers of the notes of release `tag` (from the
+/// cache, if there), caching the notes once fetched.
+///
+/// # Errors
+/// Returns an error if the release notes can't be fetched or cached.
+fn stream_notes(
+    config: &Config,
+    tag: &str,
+    cache: Option<&Cache>,
+    output: &mut Option<RecordWriter>,
+    seen: &mut Seen<'_>,
+) -> Result<bool, Error> {
+    let forge = config.forge;
+    let cached = cache
+        .and_then(|cache| cache.get(CACHE_NAMESPACE, &cache_key(forge, tag)));
+    match (forge, cached) {
+        (forge, Some(notes)) => {
+            log!("using cached release notes for tag {tag}");
+            stream_pr_numbers_from_reader(
+                io::Cursor::new(notes.into_bytes()),
+                forge,
+                output,
+                &config.ignore_list,
+                seen,
+            )
+        }
+        (Forge::GitHub, None) => {
+            log!("streaming release notes for tag {tag}");
+            stream_pr_numbers_from_release(
+                tag,
+                &config.github()?,
+                cache,
+                output,
+                &config.ignore_list,
+                seen,
+            )
+        }
+        (Forge::GitLab, None) => {
+            log!("reading GitLab release notes for tag {tag}");
+            // glab api returns the release as one JSON document.
+            let notes = gitlab::release_notes(tag)?;
+            if let Some(cache) = cache {
+                cache.put(
+                    CACHE_NAMESPACE,
+                    &cache_key(Forge::GitLab, tag),
+                    &notes,
+                )?;
+            }
+            stream_pr_numbers_from_reader(
+                io::Cursor::new(notes.into_bytes()),
+                Forge::GitLab,
+                output,
+                &config.ignore_list,
+                seen,
+            )
+        }
+    }
+}
+
+/// With `--explain` of a PR, logs whether the release notes reference it
+/// (see [`crate::explain`]).
+fn explain_notes(config: &Config, seen: &Seen<'_>) {
+    let Some(Subject::Pr(ref pr)) = config.explain else {
+        return;
+    };
+    let source = match config.discover {
+        Discover::Notes => "the release notes",
+        Discover::Compare => "the commits",
+        Discover::Both => "the release notes or commits",
+    };
+    let notes = match (&config.release_tag, &config.release_range) {
+        (Some(tag), _) => format!("{source} of {tag}"),
+        (None, Some(range)) => {
+            format!("{source} of the releases after {}", range.since)
+        }
+        (None, None) => source.to_string(),
+    };
+    if config.ignore_list.ignores_pr(pr) {
+        explain!("PR {pr} is in --ignore-prs, so it is never output");
+    } else if seen.contains(pr) {
+        explain!("PR {pr} is referenced by {notes}");
+    } else {
+        explain!(
+            "PR {pr} is not referenced by {notes}, so none of its tickets are considered"
+        );
+    }
+}
+
+/// Key of the release notes of `tag` in the cache: `github-v1.2.3`.
+fn cache_key(forge: Forge, tag: &str) -> String {
+    format!("{}-{tag}", forge.name())
+}
+
+/// Reader that keeps a copy of everything read from `inner`, so streamed
+/// release notes can be cached once complete.
+struct Tee<R> {
+    inner: R,
+    copy: Arc<Mutex<Vec<u8>>>,
+}
+
+impl<R: Read> Read for Tee<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        if let Ok(mut copy) = self.copy.lock() {
+            copy.extend_from_slice(&buf[..read]);
+        }
+        Ok(read)
+    }
+}
+
+/// Streams the GitHub release notes of `tag` to the PR matcher, caching them
+/// in `cache` once they are all read.
+///
+/// # Errors
+/// Returns an error if the release notes can't be fetched or cached.
 fn stream_pr_numbers_from_release(
     tag: &str,
-    seen: &mut HashSet<String>,
-) -> Result<bool, String> {
+    github: &GitHub,
+    cache: Option<&Cache>,
+    output: &mut Option<RecordWriter>,
+    ignore_list: &IgnoreList,
+    seen: &mut Seen<'_>,
+) -> Result<bool, Error> {
+    let key = cache_key(Forge::GitHub, tag);
+    let github_host = match github {
+        GitHub::Cli(host) => host.as_deref(),
+        GitHub::Api(api) => {
+            // The API returns the release as one JSON document.
+            let release = api.release(tag).map_err(|e| {
+                e.context(format!("Failed to get release notes for tag {tag}"))
+            })?;
+            let notes = release.get("body").str_or_empty().to_string();
+            if let Some(cache) = cache {
+                cache.put(CACHE_NAMESPACE, &key, &notes)?;
+            }
+            return stream_pr_numbers_from_reader(
+                io::Cursor::new(notes.into_bytes()),
+                Forge::GitHub,
+                output,
+                ignore_list,
+                seen,
+            );
+        }
+    };
     // We stream `gh` output into `grep` so this stage can start emitting PR
     // numbers immediately.
-    let mut gh_child = Command::new("gh")
+    let mut gh_child = utils::gh_command(github_host)
         .args(["release", "view", tag, "--json", "body", "--jq", ".body"])
         .stdout(Stdio::piped())
         .stderr(Stdio::inherit())
         .spawn()
-        .map_err(|e| format!("Failed to execute gh command: {e}"))?;
+        .map_err(|e| {
+            exit_status::spawn_error("Failed to execute gh command", e)
+        })?;
 
-    let gh_stdout = gh_child
-        .stdout
-        .take()
-        .ok_or_else(|| "Failed to capture gh stdout".to_string())?;
+    let gh_stdout = gh_child.stdout.take().ok_or_else(|| {
+        Error::Other("Failed to capture gh stdout".to_string())
+    })?;
 
-    let any_output =
-        stream_pr_numbers_from_reader(Stdio::from(gh_stdout), seen)?;
+    let copy = Arc::new(Mutex::new(Vec::new()));
+    let any_output = if cache.is_some() {
+        let notes = Tee {
+            inner: gh_stdout,
+            copy: Arc::clone(&copy),
+        };
+        stream_pr_numbers_from_reader(
+            notes,
+            Forge::GitHub,
+            output,
+            ignore_list,
+            seen,
+        )?
+    } else {
+        stream_pr_numbers_from_reader(
+            gh_stdout,
+            Forge::GitHub,
+            output,
+            ignore_list,
+            seen,
+        )?
+    };
     let status = gh_child
         .wait()
-        .map_err(|e| format!("Failed to wait for gh: {e}"))?;
+        .map_err(|e| Error::io("Failed to wait for gh", e))?;
     if !status.success() {
-        return Err(format!("Failed to get release notes for tag {tag}"));
+        return Err(Error::GitHub(format!(
+            "Failed to get release notes for tag {tag}"
+        )));
+    }
+    if let Some(cache) = cache {
+        let notes = copy.lock().map_err(|_| {
+            Error::Other("Release notes copy poisoned".to_string())
+        })?;
+        cache.put(CACHE_NAMESPACE, &key, &String::from_utf8_lossy(&notes))?;
     }
     Ok(any_output)
 }
 
 fn stream_pr_numbers_from_stdin(
-    seen: &mut HashSet<String>,
-) -> Result<bool, String> {
-    stream_pr_numbers_from_reader(Stdio::inherit(), seen)
+    forge: Forge,
+    output: &mut Option<RecordWriter>,
+    ignore_list: &IgnoreList,
+    seen: &mut Seen<'_>,
+) -> Result<bool, Error> {
+    stream_pr_numbers_from_reader(io::stdin(), forge, output, ignore_list, seen)
+}
+
+/// Streams the PR identifiers of the release notes in the input files of
+/// `config` (and `-`), read in order as one set of notes.
+///
+/// # Errors
+/// Returns an error if an input can't be read or grep fails.
+fn stream_pr_numbers_from_inputs(
+    config: &Config,
+    output: &mut Option<RecordWriter>,
+    seen: &mut Seen<'_>,
+) -> Result<bool, Error> {
+    let sources = config.input_sources.clone();
+    let no_input = NoInputTimeout {
+        secs: config.stdin_timeout_secs,
+        expected: "release notes",
+    };
+    let notes = move |grep: ChildStdin| {
+        let mut notes = Normalizer::new(grep);
+        utils::for_each_input_line(&sources, no_input, |line| {
+            notes.push(line)
+        })?;
+        notes.finish()
+    };
+    let mut any_output = false;
+    let mut ignored = HashSet::new();
+    for_each_pr_match(notes, config.forge, |pr| {
+        any_output |=
+            emit_pr(pr, &config.ignore_list, &mut ignored, output, seen)?;
+        Ok(())
+    })?;
+    Ok(any_output)
 }
 
 fn stream_pr_numbers_from_reader(
-    stdin: Stdio,
-    seen: &mut HashSet<String>,
-) -> Result<bool, String> {
+    notes: impl Read + Send + 'static,
+    forge: Forge,
+    output: &mut Option<RecordWriter>,
+    ignore_list: &IgnoreList,
+    seen: &mut Seen<'_>,
+) -> Result<bool, Error> {
+    let mut any_output = false;
+    let mut ignored = HashSet::new();
+    let notes = move |grep| write_normalized(notes, grep);
+    for_each_pr_match(notes, forge, |pr| {
+        any_output |= emit_pr(pr, ignore_list, &mut ignored, output, seen)?;
+        Ok(())
+    })?;
+    Ok(any_output)
+}
+
+/// The PRs referenced by release `notes` of `forge`, deduplicated, in order
+/// of discovery. The notes are normalized as described in the module docs.
+///
+/// # Errors
+/// Returns an error if grep fails.
+pub fn extract_pr_numbers(
+    notes: &str,
+    forge: Forge,
+) -> Result<Vec<PrRef>, Error> {
+    let mut seen = HashSet::new();
+    let mut prs = Vec::new();
+    let notes = io::Cursor::new(notes.as_bytes().to_vec());
+    let notes = move |grep| write_normalized(notes, grep);
+    for_each_pr_match(notes, forge, |pr| {
+        if seen.insert(pr.to_text()) {
+            prs.push(pr.clone());
+        }
+        Ok(())
+    })?;
+    Ok(prs)
+}
+
+/// Calls `on_pr` with every PR reference of the notes `write_notes` writes
+/// to grep, duplicates included, as grep matches them.
+///
+/// # Errors
+/// Returns an error if the notes can't be read, grep fails or `on_pr`
+/// fails, which stops grep.
+fn for_each_pr_match(
+    write_notes: impl FnOnce(ChildStdin) -> Result<(), Error> + Send + 'static,
+    forge: Forge,
+    mut on_pr: impl FnMut(&PrRef) -> Result<(), Error>,
+) -> Result<(), Error> {
     // Single pass over the input, emitting matches in discovery order.
-    // We let grep do the heavy lifting for matching, then we normalize output
-    // to raw PR numbers.
-    let pattern = r"(#[0-9]+|https://github\.com/[^/]+/[^/]+/pull/[0-9]+)";
+    // The notes are normalized on their way into grep, which does the heavy
+    // lifting for matching; we then normalize its output to PR identifiers.
+    let pattern = match forge {
+        Forge::GitHub => GITHUB_PATTERN,
+        Forge::GitLab => GITLAB_PATTERN,
+    };
 
     let mut grep_child = Command::new("grep")
         .args(["-oE", pattern])
-        .stdin(stdin)
+        .stdin(Stdio::piped())
         .stdout(Stdio::piped())
         .stderr(Stdio::inherit())
         .spawn()
-        .map_err(|e| format!("Failed to spawn grep: {e}"))?;
+        .map_err(|e| exit_status::spawn_error("Failed to spawn grep", e))?;
 
-    let grep_stdout = grep_child
-        .stdout
-        .take()
-        .ok_or_else(|| "Failed to capture grep stdout".to_string())?;
+    let grep_stdin = grep_child.stdin.take().ok_or_else(|| {
+        Error::Other("Failed to capture grep stdin".to_string())
+    })?;
+    // Written from another thread, so grep's output is read as it comes.
+    let writer = thread::spawn(move || write_notes(grep_stdin));
+
+    let grep_stdout = grep_child.stdout.take().ok_or_else(|| {
+        Error::Other("Failed to capture grep stdout".to_string())
+    })?;
 
-    let mut any_output = false;
     let reader = io::BufReader::new(grep_stdout);
     for line_result in reader.lines() {
         let matched = line_result
-            .map_err(|e| format!("Failed to read grep output: {e}"))?;
-        // `grep -oE` returns the matched substring; normalize it to a PR number
-        // and dedupe.
-        if let Some(num) = matched.strip_prefix('#') {
-            if seen.insert(num.to_string()) {
-                println!("{num}");
-                any_output = true;
-            }
-            continue;
-        }
-        if let Some(num) = matched.rsplit('/').next()
-            && seen.insert(num.to_string())
+            .map_err(|e| Error::io("Failed to read grep output", e))?;
+        // `grep -oE` returns the matched substring; normalize it to a PR
+        // identifier and dedupe.
+        if let Some(pr) = normalize_pr_match(&matched)
+            && let Err(e) = on_pr(&pr)
         {
-            println!("{num}");
-            any_output = true;
+            // The writer fails once grep is gone, and is left to end.
+            let _ = grep_child.kill();
+            let _ = grep_child.wait();
+            return Err(e);
         }
     }
-
+    writer.join().map_err(|_| {
+        Error::Other("Release notes writer panicked".to_string())
+    })??;
     let status = grep_child
         .wait()
-        .map_err(|e| format!("Failed to wait for grep: {e}"))?;
+        .map_err(|e| Error::io("Failed to wait for grep", e))?;
     if status.success() || status.code() == Some(1) {
-        return Ok(any_output);
+        return Ok(());
+    }
+
+    Err(Error::command("grep failed"))
+}
+
+/// Outputs `pr` unless it was output before (it is in `seen`) or is in
+/// `--ignore-prs`, which is logged once per PR (tracked in `ignored`).
+/// Returns whether it was output.
+///
+/// # Errors
+/// Returns an error if the next stage stopped reading.
+fn emit_pr(
+    pr: &PrRef,
+    ignore_list: &IgnoreList,
+    ignored: &mut HashSet<String>,
+    output: &mut Option<RecordWriter>,
+    seen: &mut Seen<'_>,
+) -> Result<bool, Error> {
+    let pr = &seen.local(pr);
+    if ignore_list.ignores_pr(pr) {
+        if ignored.insert(pr.to_text()) {
+            log!("skipping PR {pr} (--ignore-prs)");
+        }
+        return Ok(false);
+    }
+    if seen.prs.insert(pr.to_text())
+        && let Some(output) = output
+    {
+        output.write(Some(&pr.to_text()), || pr)?;
+        return Ok(true);
+    }
+    Ok(false)
+}
+
+/// The PRs output so far, by their text form.
+///
+/// A reference naming the current repository (e.g. the URL of one of its
+/// PRs) stands for the bare number, so `#7` and its URL are one PR. The
+/// current repository is looked up the first time a reference names a
+/// repository (see [`current_repo`]).
+struct Seen<'a> {
+    config: &'a Config,
+    prs: HashSet<String>,
+    /// `owner/repo` of the current repository, once looked up (`None` if
+    /// it couldn't be)
+    current_repo: OnceCell<Option<String>>,
+}
+
+impl<'a> Seen<'a> {
+    fn new(config: &'a Config) -> Self {
+        Self {
+            config,
+            prs: HashSet::new(),
+            current_repo: OnceCell::new(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.prs.len()
+    }
+
+    /// Whether `pr` was output.
+    fn contains(&self, pr: &PrRef) -> bool {
+        let pr = self.local(pr);
+        self.prs.contains(&pr.to_text())
     }
 
-    Err("grep failed".to_string())
+    /// `pr`, as a bare number if its repository is the current one.
+    fn local(&self, pr: &PrRef) -> PrRef {
+        let Some(ref repo) = pr.repo else {
+            return pr.clone();
+        };
+        let current = self.current_repo.get_or_init(|| {
+            current_repo(self.config)
+                .inspect_err(|e| {
+                    utils::log_at(
+                        LogLevel::Warn,
+                        NAME,
+                        format_args!(
+                            "PRs of the current repository may be output twice: {e}"
+                        ),
+                    );
+                })
+                .ok()
+        });
+        if current
+            .as_deref()
+            .is_some_and(|current| current.eq_ignore_ascii_case(repo))
+        {
+            PrRef {
+                repo: None,
+                number: pr.number.clone(),
+            }
+        } else {
+            pr.clone()
+        }
+    }
+}
+
+/// `owner/repo` of the current repository (with `--forge gitlab`, the full
+/// path of the current project).
+///
+/// # Errors
+/// Returns an error if `gh` (or `glab`, or with `--github-api`,
+/// `--github-repo`) doesn't name it.
+fn current_repo(config: &Config) -> Result<String, Error> {
+    if config.forge == Forge::GitLab {
+        return gitlab::current_project();
+    }
+    let github_host = match config.github()? {
+        GitHub::Cli(host) => host,
+        GitHub::Api(api) => {
+            let (owner, repo) = api.current_repo()?;
+            return Ok(format!("{owner}/{repo}"));
+        }
+    };
+    let output = utils::gh_command(github_host.as_deref())
+        .args(["repo", "view", "--json", "nameWithOwner"])
+        .args(["--jq", ".nameWithOwner"])
+        .output()
+        .map_err(|e| {
+            exit_status::spawn_error("Failed to execute gh command", e)
+        })?;
+    let repo = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || repo.is_empty() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::GitHub(match stderr.trim() {
+            "" => "gh repo view named no current repository".to_string(),
+            reason => {
+                format!("Failed to find the current repository: {reason}")
+            }
+        }));
+    }
+    Ok(repo)
+}
+
+/// Copies `notes` to `out` line by line, normalized as described in the
+/// module docs.
+///
+/// # Errors
+/// Returns an error if `notes` can't be read or `out` can't be written.
+fn write_normalized(notes: impl Read, out: impl Write) -> Result<(), Error> {
+    let mut reader = io::BufReader::new(notes);
+    let mut raw = Vec::new();
+    let mut normalizer = Normalizer::new(out);
+    loop {
+        raw.clear();
+        let read = reader
+            .read_until(b'\n', &mut raw)
+            .map_err(|e| Error::io("Failed to read release notes", e))?;
+        if read == 0 {
+            break;
+        }
+        let text = String::from_utf8_lossy(&raw);
+        normalizer.push(text.trim_end_matches('\n'))?;
+    }
+    normalizer.finish()
+}
+
+/// Writes lines of release notes to `out`, normalized as described in the
+/// module docs.
+struct Normalizer<W> {
+    out: W,
+    /// The last line pushed, held back in case the next one continues it.
+    pending: Option<String>,
+}
+
+impl<W: Write> Normalizer<W> {
+    fn new(out: W) -> Self {
+        Self { out, pending: None }
+    }
+
+    /// Adds a line of the notes, without its line break.
+    ///
+    /// # Errors
+    /// Returns an error if `out` can't be written.
+    fn push(&mut self, text: &str) -> Result<(), Error> {
+        let text = text.trim_end_matches('\r');
+        for line in text.split('\r').map(fold_full_width) {
+            self.pending = match self.pending.take() {
+                Some(prev) if continues_on_next_line(&prev, &line) => {
+                    Some(format!("{}{}", prev.trim_end(), line.trim_start()))
+                }
+                Some(prev) => {
+                    write_line(&mut self.out, &prev)?;
+                    Some(line)
+                }
+                None => Some(line),
+            };
+        }
+        Ok(())
+    }
+
+    /// Writes the line held back, if any.
+    ///
+    /// # Errors
+    /// Returns an error if `out` can't be written.
+    fn finish(mut self) -> Result<(), Error> {
+        match self.pending.take() {
+            Some(prev) => write_line(&mut self.out, &prev),
+            None => Ok(()),
+        }
+    }
+}
+
+fn write_line(out: &mut impl Write, line: &str) -> Result<(), Error> {
+    writeln!(out, "{line}")
+        .and_then(|()| out.flush())
+        .map_err(|e| Error::io("Failed to write to grep stdin", e))
+}
+
+/// Folds full-width ASCII variants (`＃`, `１２３`, `／`, ...) and the
+/// ideographic space to ASCII.
+fn fold_full_width(line: &str) -> String {
+    line.chars()
+        .map(|c| match c {
+            '\u{FF01}'..='\u{FF5E}' => {
+                char::from_u32(u32::from(c) - 0xFEE0).unwrap_or(c)
+            }
+            '\u{3000}' => ' ',
+            _ => c,
+        })
+        .collect()
+}
+
+/// Whether a hard wrap between `line` and `next` split a PR reference.
+fn continues_on_next_line(line: &str, next: &str) -> bool {
+    let Some(last_word) = line.split_whitespace().next_back() else {
+        return false;
+    };
+    let next = next.trim_start();
+    if last_word.ends_with(['#', '/']) {
+        return next.starts_with(|c: char| c.is_ascii_alphanumeric());
+    }
+    let starts_reference = next.starts_with('/')
+        || next
+            .strip_prefix('#')
+            .is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_digit()));
+    starts_reference && last_word.contains('/')
+}
+
+/// Normalizes a raw grep match into a PR reference.
+///
+/// # Returns
+/// - `#123` -> `123`
+/// - `owner/repo#123` -> `owner/repo#123` (kept qualified so extract-tickets
+///   fetches it from the right repository)
+/// - `https://github.com/owner/repo/pull/123` -> `owner/repo#123` (likewise,
+///   rather than PR 123 of the current repository)
+/// - `!123` -> `123`, `group/project!123` -> `group/project#123` and
+///   `https://gitlab.com/group/project/-/merge_requests/123` ->
+///   `group/project#123` (GitLab)
+///
+/// A reference to the current repository is made bare when it is output
+/// (see [`Seen`]).
+fn normalize_pr_match(matched: &str) -> Option<PrRef> {
+    if let Some(num) = matched.strip_prefix(['#', '!']) {
+        return PrRef::parse(num).ok();
+    }
+    if !matched.starts_with("https://")
+        && let Some((project, num)) = matched.split_once('!')
+    {
+        return PrRef::parse(&format!("{project}#{num}")).ok();
+    }
+    PrRef::parse(matched).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pr(text: &str) -> Result<PrRef, Error> {
+        PrRef::parse(text)
+    }
+
+    #[test]
+    fn normalizes_matches() -> Result<(), Error> {
+        assert_eq!(normalize_pr_match("#12"), Some(pr("12")?));
+        assert_eq!(normalize_pr_match("!12"), Some(pr("12")?));
+        assert_eq!(normalize_pr_match("acme/lib#7"), Some(pr("acme/lib#7")?));
+        assert_eq!(
+            normalize_pr_match("https://github.com/acme/lib/pull/7"),
+            Some(pr("acme/lib#7")?)
+        );
+        assert_eq!(
+            normalize_pr_match("group/sub/project!3"),
+            Some(pr("group/sub/project#3")?)
+        );
+        assert_eq!(
+            normalize_pr_match(
+                "https://gitlab.com/group/project/-/merge_requests/3"
+            ),
+            Some(pr("group/project#3")?)
+        );
+        assert_eq!(normalize_pr_match("#x"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn references_to_the_current_repository_are_bare() -> Result<(), Error> {
+        let config = Config::from_arg_list(&["parse-notes".to_string()])?;
+        let mut seen = Seen::new(&config);
+        seen.current_repo = OnceCell::from(Some("Acme/App".to_string()));
+        let url = normalize_pr_match("https://github.com/acme/app/pull/7");
+        assert_eq!(url.map(|url| seen.local(&url)), Some(pr("7")?));
+        assert_eq!(seen.local(&pr("acme/app#7")?), pr("7")?);
+        assert_eq!(seen.local(&pr("acme/lib#7")?), pr("acme/lib#7")?);
+        assert_eq!(seen.local(&pr("7")?), pr("7")?);
+
+        seen.prs.insert("7".to_string());
+        assert!(seen.contains(&pr("acme/app#7")?));
+        assert!(!seen.contains(&pr("acme/lib#7")?));
+        Ok(())
+    }
 }
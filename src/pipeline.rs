@@ -0,0 +1,149 @@
+//! Config-driven pipelines (`run NAME`).
+//!
+//! The orchestrator always runs parse-notes, extract-tickets and
+//! update-tickets. Teams with other release flows define their own pipelines
+//! in the config file instead (see [`crate::config_file`]), as an ordered
+//! list of stages with per-stage flags, and run one with
+//! `run NAME [--release-tag TAG] [--dry-run]`.
+//!
+//! ## Stages
+//! Like the orchestrator, `run` spawns the binary once per stage. A stage
+//! that reads tickets or PRs (extract-tickets after parse-notes,
+//! update-tickets after extract-tickets or extract-commits) gets the
+//! previous stage's ndjson records on its stdin, and the two run
+//! concurrently. Any other stage (e.g. `report` after update-tickets) starts
+//! once the stages before it have finished successfully, so it sees their
+//! changes. A reading stage at the start of the pipeline reads the run's own
+//! stdin.
+//!
+//! `--release-tag` is passed on to the stages that take one (parse-notes,
+//! extract-commits, update-tickets, report, verify) and `--dry-run` to
+//! update-tickets and rollback, unless the stage sets them itself. Every
+//! stage's flags are checked before the first stage starts, as if it were
+//! run on its own.
+//!
+//! The run stops at the first failed stage, with the failure class of that
+//! stage (see [`crate::exit_status`]).
+
+use std::env;
+use std::process::{Child, Command, Stdio};
+
+use crate::config::Config;
+use crate::error::Error;
+use crate::exit_status;
+use crate::utils;
+
+const NAME: &str = "run";
+
+macro_rules! log {
+    ($fmt:expr) => {
+        utils::log(NAME, format_args!($fmt));
+    };
+}
+
+/// A pipeline defined in the config file, ready to run.
+#[derive(Debug)]
+pub struct Pipeline {
+    pub name: String,
+    pub stages: Vec<Stage>,
+}
+
+/// Where a stage reads its input from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StageInput {
+    /// The run's stdin, once the stages before it have finished
+    Own,
+    /// The previous stage's ndjson output
+    Piped,
+}
+
+/// One stage of a [`Pipeline`].
+#[derive(Debug)]
+pub struct Stage {
+    /// Mode the stage runs, e.g. `extract-tickets`
+    pub mode: String,
+    /// The stage's flags, including those passed on from `run`
+    pub args: Vec<String>,
+    /// Where the stage reads its input from
+    pub input: StageInput,
+}
+
+/// Runs the `run` pipeline of `config`, stage by stage.
+///
+/// # Errors
+/// Returns an error if a stage can't be spawned or fails.
+pub fn run(config: &Config) -> Result<(), Error> {
+    let Some(ref pipeline) = config.pipeline else {
+        return Err(Error::Config("run requires a pipeline name".to_string()));
+    };
+    let exe_path = env::current_exe()
+        .map_err(|e| Error::io("Failed to get current executable path", e))?;
+    let name = &pipeline.name;
+    let count = pipeline.stages.len();
+    log!("running pipeline {name} ({count} stage(s))");
+
+    // Stages running concurrently, each reading the previous one's output
+    let mut running: Vec<(&str, Child)> = Vec::new();
+    for (idx, stage) in pipeline.stages.iter().enumerate() {
+        let mode = stage.mode.as_str();
+        let mut command = Command::new(&exe_path);
+        command.arg(mode).args(&stage.args);
+        match stage.input {
+            StageInput::Piped => {
+                let stdout = running
+                    .last_mut()
+                    .and_then(|(_, child)| child.stdout.take())
+                    .ok_or_else(|| {
+                        Error::Other(format!(
+                            "Failed to capture the output piped to {mode}"
+                        ))
+                    })?;
+                command.stdin(stdout);
+            }
+            StageInput::Own => wait_all(name, &mut running)?,
+        }
+        if pipeline
+            .stages
+            .get(idx + 1)
+            .is_some_and(|next| next.input == StageInput::Piped)
+        {
+            command.stdout(Stdio::piped());
+        }
+        let number = idx + 1;
+        log!("stage {number}/{count}: {mode}");
+        let child = command
+            .spawn()
+            .map_err(|e| Error::io(format!("Failed to spawn {mode}"), e))?;
+        running.push((mode, child));
+    }
+    wait_all(name, &mut running)?;
+    log!("pipeline {name} done");
+    Ok(())
+}
+
+/// Waits for every running stage, last first, so a failure isn't masked by
+/// a successful later stage.
+///
+/// # Errors
+/// Returns an error naming the first stage that failed, with its failure
+/// class.
+fn wait_all(name: &str, running: &mut Vec<(&str, Child)>) -> Result<(), Error> {
+    let mut statuses = Vec::new();
+    for (mode, mut child) in running.drain(..).rev() {
+        let status = child
+            .wait()
+            .map_err(|e| Error::io(format!("Failed to wait for {mode}"), e))?;
+        statuses.push((mode, status));
+    }
+    let Some((mode, status)) = statuses
+        .into_iter()
+        .rev()
+        .find(|(_, status)| !status.success())
+    else {
+        return Ok(());
+    };
+    Err(exit_status::stage_error(
+        status,
+        format!("Pipeline {name} failed at {mode}"),
+    ))
+}
@@ -0,0 +1,181 @@
+//! Linear project representing the release (`--linear-project`).
+//!
+//! Some teams model each release as a Linear project. With
+//! `--linear-project PROJECT`, update-tickets only completes tickets of the
+//! release that belong to that project and are in a started state, and
+//! reports the project's completion before and after the run.
+//!
+//! `PROJECT` is the project's name (case-insensitive) or its slug ID, the
+//! suffix of its URL (`https://linear.app/<org>/project/<name>-<slug ID>`).
+//!
+//! `--add-to-project PROJECT` names a project the same way; update-tickets
+//! adds the tickets it moves to it (see [`find_project`]).
+//!
+//! ## Progress
+//! Progress counts the project's issues, excluding canceled ones, the way
+//! Linear's project view does. Estimates aren't taken into account.
+
+use std::collections::HashMap;
+
+use crate::error::Error;
+use crate::utils::{self, LinearApi};
+
+/// Issues fetched per page when listing the project's issues.
+const PAGE_SIZE: usize = 250;
+
+/// A Linear project and the workflow state type of each of its issues.
+#[derive(Debug)]
+pub struct Project {
+    pub name: String,
+    /// Workflow state type (e.g. `started`) by issue UUID
+    issues: HashMap<String, String>,
+}
+
+/// Completion of a project's issues.
+#[derive(Debug, Clone, Copy)]
+pub struct Progress {
+    /// Issues in a completed state
+    pub completed: usize,
+    /// Issues that aren't canceled
+    pub total: usize,
+}
+
+impl Progress {
+    /// Completed issues as a whole percentage of the total (0 for an empty
+    /// project).
+    pub fn percent(self) -> usize {
+        (self.completed * 100).checked_div(self.total).unwrap_or(0)
+    }
+}
+
+impl Project {
+    /// Looks up the project by name or slug ID and lists its issues.
+    ///
+    /// # Errors
+    /// Returns an error if a request fails, or no project (or more than one)
+    /// matches `project`.
+    pub fn load(project: &str, linear: &LinearApi) -> Result<Self, Error> {
+        let (id, name) = find_project(project, linear)?;
+        let issues = list_issues(&id, linear)?;
+        Ok(Self { name, issues })
+    }
+
+    /// Whether the issue with UUID `issue_id` belongs to the project.
+    pub fn contains(&self, issue_id: &str) -> bool {
+        self.issues.contains_key(issue_id)
+    }
+
+    /// The project's completion as of loading it.
+    pub fn progress(&self) -> Progress {
+        let mut progress = Progress {
+            completed: 0,
+            total: 0,
+        };
+        for state_type in self.issues.values() {
+            match state_type.as_str() {
+                "canceled" => {}
+                "completed" => {
+                    progress.completed += 1;
+                    progress.total += 1;
+                }
+                _ => progress.total += 1,
+            }
+        }
+        progress
+    }
+}
+
+/// Finds the project whose name or slug ID is `project`.
+///
+/// # GraphQL Query
+/// ```graphql
+/// query($project: String!) {
+///   projects(filter: { or: [
+///     { name: { eqIgnoreCase: $project } },
+///     { slugId: { eq: $project } }
+///   ] }, first: 2) {
+///     nodes { id name }
+///   }
+/// }
+/// ```
+///
+/// # Returns
+/// The project's ID and name.
+///
+/// # Errors
+/// Returns an error if the request fails, or not exactly one project
+/// matches.
+pub fn find_project(
+    project: &str,
+    linear: &LinearApi,
+) -> Result<(String, String), Error> {
+    let query = format!(
+        r#"{{"query": "query($project: String!) {{ projects(filter: {{ or: [{{ name: {{ eqIgnoreCase: $project }} }}, {{ slugId: {{ eq: $project }} }}] }}, first: 2) {{ nodes {{ id name }} }} }}", "variables": {{"project": {}}}}}"#,
+        utils::json_string(project)
+    );
+    let response = utils::graphql_request(&query, linear)?;
+    utils::ensure_no_graphql_errors(&response)?;
+    let matches: Vec<(&str, &str)> = response
+        .get("data.projects.nodes")
+        .items()
+        .iter()
+        .map(|node| {
+            (
+                node.get("id").str_or_empty(),
+                node.get("name").str_or_empty(),
+            )
+        })
+        .collect();
+    match matches.as_slice() {
+        [(id, name)] => Ok(((*id).to_string(), (*name).to_string())),
+        [] => Err(Error::LinearApi(format!(
+            "No Linear project named {project}"
+        ))),
+        _ => Err(Error::LinearApi(format!(
+            "More than one Linear project matches {project}; pass its slug ID instead"
+        ))),
+    }
+}
+
+/// Lists the state type of every issue in the project, a page at a time.
+///
+/// # GraphQL Query
+/// ```graphql
+/// query($projectId: String!, $after: String) {
+///   project(id: $projectId) {
+///     issues(first: 250, after: $after) {
+///       nodes { id state { type } }
+///       pageInfo { hasNextPage endCursor }
+///     }
+///   }
+/// }
+/// ```
+///
+/// # Errors
+/// Returns an error if a request fails.
+fn list_issues(
+    project_id: &str,
+    linear: &LinearApi,
+) -> Result<HashMap<String, String>, Error> {
+    let mut issues = HashMap::new();
+    let mut after = "null".to_string();
+    loop {
+        let query = format!(
+            r#"{{"query": "query($projectId: String!, $after: String) {{ project(id: $projectId) {{ issues(first: {PAGE_SIZE}, after: $after) {{ nodes {{ id state {{ type }} }} pageInfo {{ hasNextPage endCursor }} }} }} }}", "variables": {{"projectId": {}, "after": {after}}}}}"#,
+            utils::json_string(project_id)
+        );
+        let response = utils::graphql_request(&query, linear)?;
+        utils::ensure_no_graphql_errors(&response)?;
+        let page = response.get("data.project.issues");
+        for node in page.get("nodes").items() {
+            issues.insert(
+                node.get("id").str_or_empty().to_string(),
+                node.get("state.type").str_or_empty().to_string(),
+            );
+        }
+        if page.get("pageInfo.hasNextPage").as_bool() != Some(true) {
+            return Ok(issues);
+        }
+        after = page.get("pageInfo.endCursor").to_string();
+    }
+}
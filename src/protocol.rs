@@ -0,0 +1,752 @@
+//! Line protocol between pipeline stages.
+//!
+//! Stages exchange one record per line. Two formats are accepted on input,
+//! and can be mixed line by line:
+//! - **text** - the bare identifier (`123`, `owner/repo#123`, `ABC-123`)
+//! - **ndjson** - one JSON object per line (any line starting with `{`),
+//!   carrying the context the bare identifier loses
+//!
+//! Which format a stage writes is chosen with `--output-format`; the
+//! orchestrator always uses ndjson between stages. `--output-format json`
+//! prints the same records as one JSON array once the stage finishes, for
+//! consumers that want a single document; stages don't read it back.
+//!
+//! ## Records
+//! PR record (parse-notes output, extract-tickets input):
+//! ```json
+//! {"pr":123,"repo":"owner/repo","schema_version":1}
+//! ```
+//! `repo` is omitted for PRs in the current repository.
+//!
+//! Ticket record (extract-tickets and extract-commits output, update-tickets
+//! input):
+//! ```json
+//! {"ticket":"ABC-123","pr":123,"repo":"owner/repo","confidence":"high","source":"title","schema_version":1}
+//! ```
+//! `pr`/`repo` identify the PR the ticket was first found in; `confidence`
+//! and `source` describe the strongest reference to it in that PR.
+//! extract-commits writes the same record with the SHA of the commit the
+//! ticket was first found in instead of a PR:
+//! ```json
+//! {"ticket":"ABC-123","commit":"3f1c2a9e...","confidence":"medium","source":"commit_headline","schema_version":1}
+//! ```
+//! With `--other-org-urls tag`, a ticket referenced only by Linear URLs of
+//! another workspace than `--linear-org` also carries `"org"`, that
+//! workspace's organization; update-tickets skips it unless it is the
+//! ticket's workspace.
+//!
+//! Update record (update-tickets output):
+//! ```json
+//! {"ticket":"ABC-123","url":"https://linear.app/acme/issue/ABC-123","outcome":"updated","from":"Passing","to":"completed","pr":123,"schema_version":1}
+//! ```
+//! `outcome` is one of `updated`, `moved`, `already_completed`,
+//! `already_released`, `skipped` (with `skip_reason`, see [`SkipReason`]) or
+//! `failed` (with `error`). `from` is the ticket's state when it was looked
+//! up, `to` the state it was (or, with `"dry_run":true`, would be) moved to.
+//! `pr`/`repo` are carried over from the ticket record the ticket was read
+//! from, if any.
+//!
+//! ## Schema
+//! Every record written carries `"schema_version"`
+//! ([`SCHEMA_VERSION`]); the JSON Schema of all machine outputs is printed by
+//! the `schema` mode (see [`crate::schema`]). Records without it are still
+//! accepted on input.
+//!
+//! ## In-Process Stages
+//! With `--in-process`, the orchestrator runs the stages on threads instead
+//! of processes. Records are then passed over channels already typed: a
+//! stage writes each [`Record`] to its [`Output`] rather than printing it,
+//! and the next stage reads it from an [`InputSource::Channel`] without
+//! parsing it back.
+//!
+//! Either way, a stage parses each line of input once, into an [`Input`].
+//!
+//! [`InputSource::Channel`]: crate::config::InputSource::Channel
+
+use std::fmt::{self, Write as _};
+use std::io;
+use std::sync::mpsc::Sender;
+
+use crate::config::OutputFormat;
+use crate::error::Error;
+use crate::json;
+use crate::utils;
+
+/// Version of the JSON schema of all machine outputs (stage records, the
+/// `--mapping-file` and the `--manifest` file). Bumped on any incompatible
+/// change; new optional fields don't bump it.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Why update-tickets skipped a ticket (`skip_reason` of an update record).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    /// The ID matches `--exclude-pattern`; it was never looked up
+    Excluded,
+    /// The ID's team key isn't a team of the workspace
+    /// (`--drop-unknown-teams`); it was never looked up
+    UnknownTeam,
+    /// The ticket failed in too many runs in a row (`--quarantine-after`);
+    /// it was never looked up
+    Quarantined,
+    /// An earlier run with the same `--checkpoint` processed the ticket; it
+    /// was never looked up
+    Checkpointed,
+    /// The ticket is archived
+    Archived,
+    /// The ticket doesn't match `--filter`
+    Filtered,
+    /// The ticket isn't in the `--linear-project` project
+    NotInProject,
+    /// The ticket isn't assigned to the `--assignee` or wasn't created by
+    /// the `--creator`
+    OtherUser,
+    /// The ticket is already in the state it would be moved to
+    AlreadyInTarget,
+    /// The ticket's state isn't one tickets are moved out of (e.g. not
+    /// "Passing", without `--update-all-statuses`)
+    IneligibleState,
+    /// The ticket record is tagged with another workspace's organization
+    /// than the ticket's (`--other-org-urls tag`); it was never looked up
+    OtherOrg,
+}
+
+impl SkipReason {
+    /// The reason's name in update records.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            SkipReason::Excluded => "excluded",
+            SkipReason::UnknownTeam => "unknown_team",
+            SkipReason::Quarantined => "quarantined",
+            SkipReason::Checkpointed => "checkpointed",
+            SkipReason::Archived => "archived",
+            SkipReason::Filtered => "filtered",
+            SkipReason::NotInProject => "not_in_project",
+            SkipReason::OtherUser => "other_user",
+            SkipReason::AlreadyInTarget => "already_in_target",
+            SkipReason::IneligibleState => "ineligible_state",
+            SkipReason::OtherOrg => "other_org",
+        }
+    }
+}
+
+/// A pull request reference, optionally qualified with its repository.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PrRef {
+    /// `owner/repo` (for GitLab, `group/subgroup/project`) if the PR lives
+    /// outside the current repository
+    pub repo: Option<String>,
+    /// The pull request number
+    pub number: String,
+}
+
+impl PrRef {
+    /// Parses a PR identifier in text form.
+    ///
+    /// # Accepted Formats
+    /// - `123` - PR in the current repository
+    /// - `owner/repo#123` - PR in another repository
+    /// - `group/subgroup/project#123` - GitLab MR in a nested project
+    /// - `https://github.com/owner/repo/pull/123` and
+    ///   `https://gitlab.com/group/project/-/merge_requests/123` - the same,
+    ///   as URLs (read as `owner/repo#123` and `group/project#123`; the host
+    ///   is the one the forge CLI talks to)
+    ///
+    /// # Errors
+    /// Returns an error if the number is not numeric or the repository is not
+    /// of the form `owner/repo` (or a longer path).
+    pub fn parse(input: &str) -> Result<Self, Error> {
+        if let Some(path) = input
+            .strip_prefix("https://")
+            .or_else(|| input.strip_prefix("http://"))
+        {
+            let qualified = path.split_once('/').and_then(|(_, path)| {
+                let (repo, number) = path
+                    .rsplit_once("/-/merge_requests/")
+                    .or_else(|| path.rsplit_once("/pull/"))?;
+                Some(format!("{repo}#{number}"))
+            });
+            return match qualified {
+                Some(qualified) => Self::parse(&qualified),
+                None => Err(Error::Other(format!("Invalid PR URL: {input}"))),
+            };
+        }
+        let (repo, number) = match input.split_once('#') {
+            Some((repo, number)) => (Some(repo), number),
+            None => (None, input),
+        };
+        if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(Error::Other(format!(
+                "Invalid PR identifier: {input}"
+            )));
+        }
+        if let Some(repo) = repo {
+            let valid_repo = repo.contains('/')
+                && repo.split('/').all(|segment| !segment.is_empty());
+            if !valid_repo {
+                return Err(Error::Other(format!(
+                    "Invalid PR repository in: {input}"
+                )));
+            }
+        }
+        Ok(Self {
+            repo: repo.map(String::from),
+            number: number.to_string(),
+        })
+    }
+
+    /// Parses an input line in either text or ndjson form.
+    ///
+    /// # Errors
+    /// Returns an error if the line is not a valid PR identifier or record.
+    pub fn parse_line(line: &str) -> Result<Self, Error> {
+        if !is_record(line) {
+            return Self::parse(line);
+        }
+        let record = json::parse(line)
+            .map_err(|e| e.context(format!("Invalid PR record {line}")))?;
+        Self::from_record(&record)
+    }
+
+    /// Reads the PR of a parsed record (`pr` and `repo`).
+    ///
+    /// # Errors
+    /// Returns an error if the record names no valid PR.
+    pub fn from_record(record: &json::Value) -> Result<Self, Error> {
+        // `pr` is a number, but a string is accepted too.
+        let number = match record.get("pr") {
+            json::Value::Number(number) => number.clone(),
+            other => other.str_or_empty().to_string(),
+        };
+        let repo = record.get("repo").str_or_empty();
+        let text = if repo.is_empty() {
+            number.clone()
+        } else {
+            format!("{repo}#{number}")
+        };
+        Self::parse(&text)
+    }
+
+    /// Renders the text form (`123` or `owner/repo#123`).
+    #[must_use]
+    pub fn to_text(&self) -> String {
+        match self.repo {
+            Some(ref repo) => format!("{repo}#{}", self.number),
+            None => self.number.clone(),
+        }
+    }
+}
+
+impl OutputRecord for PrRef {
+    fn to_record(&self) -> String {
+        let mut record = format!("{{\"pr\":{}", self.number);
+        if let Some(ref repo) = self.repo {
+            record.push_str(",\"repo\":");
+            record.push_str(&utils::json_string(repo));
+        }
+        let _ = write!(record, ",\"schema_version\":{SCHEMA_VERSION}}}");
+        record
+    }
+
+    fn to_input(&self) -> Option<Record> {
+        Some(Record::Pr(self.clone()))
+    }
+}
+
+impl fmt::Display for PrRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.repo {
+            Some(ref repo) => write!(f, "{repo}#{}", self.number),
+            None => write!(f, "#{}", self.number),
+        }
+    }
+}
+
+/// How strongly a PR references a ticket, by where the reference appears.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Confidence {
+    /// Mentioned only in a PR comment
+    Low,
+    /// Mentioned in a commit message
+    Medium,
+    /// Mentioned in the PR title or body
+    High,
+}
+
+impl Confidence {
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+        }
+    }
+}
+
+/// Where a ticket was found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    /// A pull request (extract-tickets)
+    Pr(PrRef),
+    /// A commit, by its full SHA (extract-commits)
+    Commit(String),
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pr(pr) => write!(f, "PR {pr}"),
+            Self::Commit(sha) => {
+                write!(f, "commit {}", sha.get(..7).unwrap_or(sha))
+            }
+        }
+    }
+}
+
+/// A Linear ticket found in a pull request (or, with extract-commits, a
+/// commit).
+#[derive(Debug, Clone)]
+pub struct TicketRecord {
+    /// Linear ticket ID (e.g. `ABC-123`)
+    pub ticket: String,
+    /// The PR (or commit) the ticket was found in
+    pub origin: Origin,
+    /// Strength of the strongest reference in that PR
+    pub confidence: Confidence,
+    /// Where that reference was found (`title`, `body`, `comment`,
+    /// `commit_headline`, `commit_body`, `commit_trailer`)
+    pub source: &'static str,
+    /// Organization of the other Linear workspace the ticket's URLs point
+    /// to (`--other-org-urls tag`)
+    pub org: Option<String>,
+}
+
+impl OutputRecord for TicketRecord {
+    fn to_record(&self) -> String {
+        let mut record =
+            format!("{{\"ticket\":{}", utils::json_string(&self.ticket));
+        match self.origin {
+            Origin::Pr(ref pr) => {
+                let _ = write!(record, ",\"pr\":{}", pr.number);
+                if let Some(ref repo) = pr.repo {
+                    record.push_str(",\"repo\":");
+                    record.push_str(&utils::json_string(repo));
+                }
+            }
+            Origin::Commit(ref sha) => {
+                record.push_str(",\"commit\":");
+                record.push_str(&utils::json_string(sha));
+            }
+        }
+        record.push_str(",\"confidence\":");
+        record.push_str(&utils::json_string(self.confidence.as_str()));
+        record.push_str(",\"source\":");
+        record.push_str(&utils::json_string(self.source));
+        if let Some(ref org) = self.org {
+            record.push_str(",\"org\":");
+            record.push_str(&utils::json_string(org));
+        }
+        let _ = write!(record, ",\"schema_version\":{SCHEMA_VERSION}}}");
+        record
+    }
+
+    fn to_input(&self) -> Option<Record> {
+        Some(Record::Ticket(TicketInput {
+            ticket: self.ticket.clone(),
+            pr: match self.origin {
+                Origin::Pr(ref pr) => Some(pr.clone()),
+                Origin::Commit(_) => None,
+            },
+            org: self.org.clone(),
+        }))
+    }
+}
+
+/// A ticket read from stage input: a bare ID, or the fields of a ticket
+/// record a later stage uses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TicketInput {
+    /// Ticket ID as read (e.g. `ABC-123`)
+    pub ticket: String,
+    /// The PR the ticket was found in
+    pub pr: Option<PrRef>,
+    /// Organization of the other Linear workspace the ticket's URLs point
+    /// to (`--other-org-urls tag`)
+    pub org: Option<String>,
+}
+
+impl TicketInput {
+    /// Parses an input line in either text or ndjson form.
+    ///
+    /// # Errors
+    /// Returns an error if an ndjson record can't be parsed or has no
+    /// `ticket`.
+    pub fn parse_line(line: &str) -> Result<Self, Error> {
+        if !is_record(line) {
+            return Ok(Self {
+                ticket: line.to_string(),
+                pr: None,
+                org: None,
+            });
+        }
+        let record = json::parse(line)
+            .map_err(|e| e.context(format!("Invalid ticket record {line}")))?;
+        let ticket = record.get("ticket").str_or_empty().trim();
+        if ticket.is_empty() {
+            return Err(Error::Other(format!(
+                "Ticket record has no ticket: {line}"
+            )));
+        }
+        let org = record.get("org").str_or_empty().trim();
+        Ok(Self {
+            ticket: ticket.to_string(),
+            pr: PrRef::from_record(&record).ok(),
+            org: (!org.is_empty()).then(|| org.to_string()),
+        })
+    }
+}
+
+/// A record passed from one stage of an in-process pipeline to the next.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Record {
+    /// A PR (parse-notes output)
+    Pr(PrRef),
+    /// A ticket (extract-tickets and extract-commits output)
+    Ticket(TicketInput),
+}
+
+/// One item of stage input, as read from its source. Displayed as the line,
+/// or a record's text form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Input {
+    /// A line of stdin or a file, in text or ndjson form
+    Line(String),
+    /// A record of the previous stage of an in-process pipeline
+    Record(Record),
+}
+
+impl Input {
+    /// The PR this input names, or `None` for a blank line.
+    ///
+    /// # Errors
+    /// Returns an error if the input is not a valid PR identifier or record.
+    pub fn pr(&self) -> Result<Option<PrRef>, Error> {
+        match self {
+            Self::Line(line) => {
+                let line = line.trim();
+                if line.is_empty() {
+                    return Ok(None);
+                }
+                PrRef::parse_line(line).map(Some)
+            }
+            Self::Record(Record::Pr(pr)) => Ok(Some(pr.clone())),
+            Self::Record(Record::Ticket(ticket)) => {
+                ticket.pr.clone().map(Some).ok_or_else(|| {
+                    Error::Other(format!(
+                        "Ticket record {} names no PR",
+                        ticket.ticket
+                    ))
+                })
+            }
+        }
+    }
+
+    /// The ticket this input names, or `None` for a blank line.
+    ///
+    /// # Errors
+    /// Returns an error if an ndjson record can't be parsed or has no
+    /// `ticket`.
+    pub fn ticket(&self) -> Result<Option<TicketInput>, Error> {
+        match self {
+            Self::Line(line) => {
+                let line = line.trim();
+                if line.is_empty() {
+                    return Ok(None);
+                }
+                TicketInput::parse_line(line).map(Some)
+            }
+            Self::Record(Record::Ticket(ticket)) => Ok(Some(ticket.clone())),
+            Self::Record(Record::Pr(pr)) => Err(Error::Other(format!(
+                "PR record has no ticket: {}",
+                pr.to_text()
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Line(line) => write!(f, "{}", line.trim()),
+            Self::Record(Record::Pr(pr)) => write!(f, "{}", pr.to_text()),
+            Self::Record(Record::Ticket(ticket)) => {
+                write!(f, "{}", ticket.ticket)
+            }
+        }
+    }
+}
+
+/// A record a stage writes: rendered as ndjson, or handed to the next stage
+/// of an in-process pipeline as it is.
+pub trait OutputRecord {
+    /// Renders the ndjson record.
+    fn to_record(&self) -> String;
+
+    /// The record as the next stage of an in-process pipeline reads it, or
+    /// `None` if no stage reads it.
+    fn to_input(&self) -> Option<Record> {
+        None
+    }
+}
+
+impl<T: OutputRecord> OutputRecord for &T {
+    fn to_record(&self) -> String {
+        (**self).to_record()
+    }
+
+    fn to_input(&self) -> Option<Record> {
+        (**self).to_input()
+    }
+}
+
+/// The result of processing a ticket in update-tickets.
+#[derive(Debug, Clone)]
+pub struct UpdateRecord {
+    /// Linear ticket ID as read from input (e.g. `ABC-123`)
+    pub ticket: String,
+    /// The ticket's Linear URL
+    pub url: String,
+    /// What happened to the ticket (`updated`, `moved`, ..., `failed`)
+    pub outcome: &'static str,
+    /// Why the ticket was skipped, for `skipped`
+    pub skip_reason: Option<SkipReason>,
+    /// Workflow state name when the ticket was looked up
+    pub from_state: Option<String>,
+    /// Workflow state the ticket was moved to
+    pub to_state: Option<String>,
+    /// The PR the ticket was found in, from its ticket record
+    pub pr: Option<PrRef>,
+    /// Why processing failed
+    pub error: Option<String>,
+    /// Whether this was a dry run
+    pub dry_run: bool,
+}
+
+impl OutputRecord for UpdateRecord {
+    fn to_record(&self) -> String {
+        let mut record = format!(
+            "{{\"ticket\":{},\"url\":{},\"outcome\":{}",
+            utils::json_string(&self.ticket),
+            utils::json_string(&self.url),
+            utils::json_string(self.outcome)
+        );
+        if let Some(reason) = self.skip_reason {
+            let _ = write!(record, ",\"skip_reason\":\"{}\"", reason.name());
+        }
+        for (name, value) in [
+            ("from", &self.from_state),
+            ("to", &self.to_state),
+            ("error", &self.error),
+        ] {
+            if let Some(value) = value {
+                let _ =
+                    write!(record, ",\"{name}\":{}", utils::json_string(value));
+            }
+        }
+        if let Some(ref pr) = self.pr {
+            let _ = write!(record, ",\"pr\":{}", pr.number);
+            if let Some(ref repo) = pr.repo {
+                record.push_str(",\"repo\":");
+                record.push_str(&utils::json_string(repo));
+            }
+        }
+        if self.dry_run {
+            record.push_str(",\"dry_run\":true");
+        }
+        let _ = write!(record, ",\"schema_version\":{SCHEMA_VERSION}}}");
+        record
+    }
+}
+
+/// Where a stage writes its output lines.
+#[derive(Debug, Clone, Default)]
+pub enum Output {
+    /// Standard output (the default)
+    #[default]
+    Stdout,
+    /// The next stage of an in-process pipeline (`--in-process`), which
+    /// gets the records typed
+    Channel(Sender<Record>),
+}
+
+/// Writes a stage's output in the `--output-format` it was asked for.
+#[derive(Debug, Default)]
+pub struct RecordWriter {
+    format: OutputFormat,
+    output: Output,
+    /// Records held back for the array (`json`)
+    pending: Vec<String>,
+}
+
+impl RecordWriter {
+    #[must_use]
+    pub fn new(format: OutputFormat, output: Output) -> Self {
+        Self {
+            format,
+            output,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Writes one result: `text` (if any) in text format, otherwise the
+    /// record, straight away (`ndjson`) or once the stage finishes (`json`).
+    /// The next stage of an in-process pipeline gets the record itself,
+    /// whatever the format.
+    ///
+    /// # Errors
+    /// Returns a broken pipe [`Error::Io`] if the next stage of an in-process
+    /// pipeline stopped reading, so this stage stops too, as a process
+    /// writing to a closed pipe would.
+    pub fn write<R: OutputRecord>(
+        &mut self,
+        text: Option<&str>,
+        record: impl FnOnce() -> R,
+    ) -> Result<(), Error> {
+        if let Output::Channel(ref sender) = self.output {
+            if let Some(input) = record().to_input() {
+                sender.send(input).map_err(|_| {
+                    Error::io(
+                        "The next stage stopped reading",
+                        io::ErrorKind::BrokenPipe.into(),
+                    )
+                })?;
+            }
+            return Ok(());
+        }
+        match self.format {
+            OutputFormat::Text => {
+                if let Some(text) = text {
+                    println!("{text}");
+                }
+            }
+            OutputFormat::Ndjson => println!("{}", record().to_record()),
+            OutputFormat::Json => self.pending.push(record().to_record()),
+        }
+        Ok(())
+    }
+
+    /// Prints the array of records with `json` (`[]` if there were none).
+    pub fn finish(&mut self) {
+        if self.format != OutputFormat::Json
+            || matches!(self.output, Output::Channel(_))
+        {
+            return;
+        }
+        if self.pending.is_empty() {
+            println!("[]");
+        } else {
+            println!("[\n{}\n]", self.pending.join(",\n"));
+        }
+        self.pending.clear();
+    }
+}
+
+/// Whether an input line is an ndjson record rather than a bare identifier.
+fn is_record(line: &str) -> bool {
+    line.starts_with('{')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pr(repo: Option<&str>, number: &str) -> PrRef {
+        PrRef {
+            repo: repo.map(String::from),
+            number: number.to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_pr_identifiers() -> Result<(), Error> {
+        assert_eq!(PrRef::parse("123")?, pr(None, "123"));
+        assert_eq!(PrRef::parse("acme/lib#7")?, pr(Some("acme/lib"), "7"));
+        assert_eq!(
+            PrRef::parse("group/sub/project#7")?,
+            pr(Some("group/sub/project"), "7")
+        );
+        assert_eq!(
+            PrRef::parse("https://github.com/acme/lib/pull/7")?,
+            pr(Some("acme/lib"), "7")
+        );
+        assert_eq!(
+            PrRef::parse(
+                "https://gitlab.com/group/sub/project/-/merge_requests/7"
+            )?,
+            pr(Some("group/sub/project"), "7")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_invalid_pr_identifiers() {
+        for input in [
+            "",
+            "abc",
+            "#",
+            "12a",
+            "-1",
+            "lib#7",
+            "acme/#7",
+            "/lib#7",
+            "acme//lib#7",
+            "acme/lib#",
+            "https://github.com/acme/lib/issues/7",
+            "https://github.com/acme/lib/pull/x",
+        ] {
+            assert!(PrRef::parse(input).is_err(), "{input:?}");
+        }
+    }
+
+    #[test]
+    fn pr_records_round_trip() -> Result<(), Error> {
+        for written in [pr(None, "12"), pr(Some("acme/lib"), "7")] {
+            let line = Input::Line(written.to_record());
+            assert_eq!(line.pr()?, Some(written.clone()));
+            let passed = written.to_input().map(Input::Record);
+            assert_eq!(
+                passed.map(|input| input.pr()).transpose()?,
+                Some(Some(written))
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn ticket_records_round_trip() -> Result<(), Error> {
+        let origins = [
+            Origin::Pr(pr(None, "12")),
+            Origin::Pr(pr(Some("acme/lib"), "7")),
+            Origin::Commit("3f1c2a9e".to_string()),
+        ];
+        for (origin, org) in
+            origins.into_iter().zip([None, Some("acme-ops"), None])
+        {
+            let written = TicketRecord {
+                ticket: "ABC-123".to_string(),
+                origin,
+                confidence: Confidence::High,
+                source: "title",
+                org: org.map(String::from),
+            };
+            let read = Input::Line(written.to_record()).ticket()?;
+            let passed = written.to_input().map(Input::Record);
+            assert_eq!(
+                passed.map(|input| input.ticket()).transpose()?,
+                Some(read)
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,136 @@
+//! Resumable update queue (`--queue-file`).
+//!
+//! With `--queue-file FILE`, update-tickets reads all input up front and
+//! records the ticket IDs in `FILE` before updating anything. Each ticket is
+//! removed from the file once it has been processed successfully, so if the
+//! run crashes or is cancelled, `FILE` holds exactly the unprocessed
+//! remainder. Failed tickets stay in the file to be retried.
+//!
+//! When `FILE` already exists (and isn't empty), the next run resumes from it
+//! and ignores its input, so it doesn't matter whether the original input
+//! came from stdin, files or the orchestrator. The file is deleted once every
+//! ticket has been processed.
+//!
+//! ## File Format
+//! One ticket ID per line, in processing order. The file is rewritten
+//! through a temporary file and renamed into place, so it is never left
+//! half-written.
+
+use std::fs;
+use std::io::ErrorKind;
+
+use crate::error::Error;
+
+/// The tickets of a run that haven't been processed successfully yet.
+#[derive(Debug)]
+pub struct Queue {
+    path: String,
+    pending: Vec<String>,
+}
+
+impl Queue {
+    /// Reads the queue left by an earlier run.
+    ///
+    /// # Returns
+    /// The queue, or `None` if `path` doesn't exist or is empty.
+    ///
+    /// # Errors
+    /// Returns an error if `path` exists but can't be read.
+    pub fn load(path: &str) -> Result<Option<Self>, Error> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                return Ok(None);
+            }
+            Err(e) => {
+                return Err(Error::io(
+                    format!("Failed to read queue file {path}"),
+                    e,
+                ));
+            }
+        };
+
+        let pending: Vec<String> = contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(String::from)
+            .collect();
+        if pending.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            path: path.to_string(),
+            pending,
+        }))
+    }
+
+    /// Creates a queue of `issue_ids` and writes it to `path`.
+    ///
+    /// # Errors
+    /// Returns an error if the file can't be written.
+    pub fn create(path: &str, issue_ids: &[String]) -> Result<Self, Error> {
+        let queue = Self {
+            path: path.to_string(),
+            pending: issue_ids.to_vec(),
+        };
+        queue.save()?;
+        Ok(queue)
+    }
+
+    /// Path of the queue file.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The queued ticket IDs, in processing order.
+    pub fn issue_ids(&self) -> &[String] {
+        &self.pending
+    }
+
+    /// Removes a successfully processed ticket from the queue file.
+    ///
+    /// # Errors
+    /// Returns an error if the file can't be rewritten.
+    pub fn complete(&mut self, issue_id: &str) -> Result<(), Error> {
+        let before = self.pending.len();
+        self.pending.retain(|pending| pending != issue_id);
+        if self.pending.len() == before {
+            return Ok(());
+        }
+        self.save()
+    }
+
+    /// Deletes the queue file if every ticket was processed.
+    ///
+    /// # Returns
+    /// The number of tickets left in the queue (to be retried).
+    ///
+    /// # Errors
+    /// Returns an error if the file can't be deleted.
+    pub fn finish(self) -> Result<usize, Error> {
+        if !self.pending.is_empty() {
+            return Ok(self.pending.len());
+        }
+        fs::remove_file(&self.path).map_err(|e| {
+            Error::io(format!("Failed to remove queue file {}", self.path), e)
+        })?;
+        Ok(0)
+    }
+
+    /// Writes the pending tickets to a temporary file and renames it over the
+    /// queue file.
+    fn save(&self) -> Result<(), Error> {
+        let tmp_path = format!("{}.tmp", self.path);
+        let mut contents = self.pending.join("\n");
+        if !contents.is_empty() {
+            contents.push('\n');
+        }
+        fs::write(&tmp_path, contents).map_err(|e| {
+            Error::io(format!("Failed to write queue file {tmp_path}"), e)
+        })?;
+        fs::rename(&tmp_path, &self.path).map_err(|e| {
+            Error::io(format!("Failed to replace queue file {}", self.path), e)
+        })
+    }
+}
@@ -0,0 +1,160 @@
+//! Blocked-by and duplicate relations between Linear issues.
+//!
+//! Used by update-tickets' `--order-by-dependencies` mode to update blockers
+//! before the tickets they block, and to report tickets that get completed
+//! while one of their blockers is still open, and by `--follow-duplicates`
+//! to find the issue a ticket is marked a duplicate of.
+//!
+//! ## Linear Relation Semantics
+//! A relation of type `blocks` from issue A to issue B means "A blocks B".
+//! For a given issue, its `relations` are the ones it is the source of (the
+//! issues it blocks) and its `inverseRelations` are the ones pointing at it
+//! (its blockers).
+//!
+//! Marking issue A as a duplicate of issue B creates a relation of type
+//! `duplicate` from A to B, so B is among A's `relations`.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::error::Error;
+use crate::json::Value;
+use crate::utils::{self, LinearApi};
+
+/// A related issue and its current state.
+#[derive(Debug, Clone)]
+pub struct RelatedIssue {
+    /// Linear issue identifier (e.g. `ABC-123`)
+    pub identifier: String,
+    /// Workflow state name
+    pub state_name: String,
+    /// Workflow state type (e.g. `started`, `completed`, `canceled`)
+    pub state_type: String,
+}
+
+impl RelatedIssue {
+    /// Whether the issue is still open (not completed or canceled).
+    pub fn is_open(&self) -> bool {
+        !matches!(self.state_type.as_str(), "completed" | "canceled")
+    }
+}
+
+/// The `blocks` relations of a single issue.
+#[derive(Debug, Clone, Default)]
+pub struct Relations {
+    /// Issues blocking this one
+    pub blockers: Vec<RelatedIssue>,
+    /// Issues this one blocks
+    pub blocks: Vec<RelatedIssue>,
+}
+
+/// Issue fields selecting the `blocks` relations, for inclusion in an
+/// `issue(id:)` query.
+pub const GRAPHQL_FIELDS: &str = "relations { nodes { type relatedIssue { identifier state { name type } } } } inverseRelations { nodes { type issue { identifier state { name type } } } }";
+
+/// Parses the `blocks` relations out of an `issue(id:)` response that
+/// selected [`GRAPHQL_FIELDS`].
+pub fn parse(response: &Value) -> Relations {
+    let issue = response.get("data.issue");
+    Relations {
+        blockers: related_issues(issue.get("inverseRelations"), "issue"),
+        blocks: related_issues(issue.get("relations"), "relatedIssue"),
+    }
+}
+
+/// The issues of the `blocks` relations in a relation connection, where
+/// `field` is the relation's field holding the other issue.
+fn related_issues(connection: &Value, field: &str) -> Vec<RelatedIssue> {
+    connection
+        .get("nodes")
+        .items()
+        .iter()
+        .filter(|relation| relation.get("type").as_str() == Some("blocks"))
+        .map(|relation| {
+            let issue = relation.get(field);
+            RelatedIssue {
+                identifier: issue.get("identifier").str_or_empty().to_string(),
+                state_name: issue.get("state.name").str_or_empty().to_string(),
+                state_type: issue.get("state.type").str_or_empty().to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Looks up the issue `issue_id` is marked a duplicate of.
+///
+/// # GraphQL Query
+/// ```graphql
+/// query($id: String!) {
+///   issue(id: $id) {
+///     relations { nodes { type relatedIssue { identifier } } }
+///   }
+/// }
+/// ```
+///
+/// # Returns
+/// The identifier of the duplicated issue, or `None` if `issue_id` isn't a
+/// duplicate.
+///
+/// # Errors
+/// Returns an error if the query fails or the issue doesn't exist.
+pub fn duplicate_of(
+    issue_id: &str,
+    linear: &LinearApi,
+) -> Result<Option<String>, Error> {
+    let query = format!(
+        r#"{{"query": "query($id: String!) {{ issue(id: $id) {{ relations {{ nodes {{ type relatedIssue {{ identifier }} }} }} }} }}", "variables": {{"id": {}}}}}"#,
+        utils::json_string(issue_id)
+    );
+    let response = utils::graphql_request(&query, linear)?;
+    utils::ensure_no_graphql_errors(&response)?;
+    let issue = response.get("data.issue");
+    if issue.is_null() {
+        return Err(Error::LinearApi(format!("Issue {issue_id} not found")));
+    }
+    Ok(issue
+        .get("relations.nodes")
+        .items()
+        .iter()
+        .find(|relation| relation.get("type").as_str() == Some("duplicate"))
+        .and_then(|relation| relation.get("relatedIssue.identifier").as_str())
+        .map(str::to_string))
+}
+
+/// Orders tickets so that blockers come before the tickets they block.
+///
+/// Only relations between tickets in `ids` are considered. Among tickets whose
+/// blockers have all been placed, input order is preserved. Tickets involved
+/// in a blocking cycle are appended in input order.
+///
+/// # Returns
+/// The ordered ticket IDs and the IDs that were part of a cycle.
+pub fn order(
+    ids: &[String],
+    relations: &HashMap<String, Relations>,
+) -> (Vec<String>, Vec<String>) {
+    let in_release: HashSet<&str> = ids.iter().map(String::as_str).collect();
+    let mut pending: Vec<&String> = ids.iter().collect();
+    let mut placed: HashSet<&str> = HashSet::new();
+    let mut ordered = Vec::with_capacity(ids.len());
+
+    loop {
+        let ready = pending.iter().position(|id| {
+            relations.get(*id).is_none_or(|rel| {
+                rel.blockers.iter().all(|blocker| {
+                    let blocker = blocker.identifier.as_str();
+                    !in_release.contains(blocker) || placed.contains(blocker)
+                })
+            })
+        });
+        let Some(index) = ready else {
+            break;
+        };
+        let id = pending.remove(index);
+        placed.insert(id);
+        ordered.push(id.clone());
+    }
+
+    let cyclic: Vec<String> = pending.into_iter().cloned().collect();
+    ordered.extend(cyclic.iter().cloned());
+    (ordered, cyclic)
+}
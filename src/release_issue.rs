@@ -0,0 +1,194 @@
+//! Release tracking issue (`--release-issue-team`).
+//!
+//! After update-tickets has processed a release, it can create a
+//! `Release <TAG>` issue in a Linear team whose description is a checklist of
+//! every ticket completed in the release. This gives the release itself a
+//! Linear artifact that PMs can subscribe to.
+//!
+//! If the team already has an issue with that title (e.g. on a re-run), it is
+//! left untouched and no second issue is created.
+
+use std::fmt::Write as _;
+
+use crate::error::Error;
+use crate::json::Value;
+use crate::utils::{self, LinearApi};
+
+const NAME: &str = "update-tickets";
+
+macro_rules! log {
+    ($fmt:expr) => {
+        utils::log(NAME, format_args!($fmt));
+    };
+}
+
+/// A ticket listed in the release checklist.
+#[derive(Debug, Clone)]
+pub struct ChecklistItem {
+    pub identifier: String,
+    pub title: String,
+}
+
+/// Creates the `Release <TAG>` tracking issue in team `team_key`.
+///
+/// # Process
+/// 1. Skips if no ticket was completed in the release
+/// 2. In dry-run mode, only logs what would be created
+/// 3. Looks up the team ID by key
+/// 4. Skips if the team already has an issue titled `Release <TAG>`
+/// 5. Creates the issue with the checklist as its description
+///
+/// # Errors
+/// Returns an error if the team doesn't exist or a Linear request fails.
+pub fn create(
+    team_key: &str,
+    tag: &str,
+    items: &[ChecklistItem],
+    dry_run: bool,
+    linear: &LinearApi,
+) -> Result<(), Error> {
+    let title = format!("Release {tag}");
+    if items.is_empty() {
+        log!("No tickets completed in {tag}, not creating \"{title}\"");
+        return Ok(());
+    }
+
+    let count = items.len();
+    if dry_run {
+        log!(
+            "Would create \"{title}\" in team {team_key} listing {count} ticket(s)"
+        );
+        return Ok(());
+    }
+
+    let team_id = find_team_id(team_key, linear)?;
+    if let Some(existing) = find_existing(team_key, &title, linear)? {
+        log!("Release issue {existing} (\"{title}\") already exists, skipping");
+        return Ok(());
+    }
+
+    let description = render_checklist(tag, items);
+    let identifier = create_issue(&team_id, &title, &description, linear)?;
+    log!("Created release issue {identifier} listing {count} ticket(s)");
+    Ok(())
+}
+
+/// Renders the issue description: a checked Markdown checklist of the
+/// release's tickets. Linear links the identifiers automatically.
+fn render_checklist(tag: &str, items: &[ChecklistItem]) -> String {
+    let mut description = format!("Tickets completed in release {tag}:\n\n");
+    for item in items {
+        let _ =
+            writeln!(description, "- [x] {} {}", item.identifier, item.title);
+    }
+    description
+}
+
+/// Looks up a team's ID by its key.
+///
+/// # GraphQL Query
+/// ```graphql
+/// query($key: String!) {
+///   teams(filter: { key: { eq: $key } }) { nodes { id } }
+/// }
+/// ```
+///
+/// # Errors
+/// Returns an error if the request fails or no team has that key.
+pub fn find_team_id(
+    team_key: &str,
+    linear: &LinearApi,
+) -> Result<String, Error> {
+    let query = format!(
+        r#"{{"query": "query($key: String!) {{ teams(filter: {{ key: {{ eq: $key }} }}) {{ nodes {{ id }} }} }}", "variables": {{"key": {}}}}}"#,
+        utils::json_string(team_key)
+    );
+    let response = utils::graphql_request(&query, linear)?;
+    utils::ensure_no_graphql_errors(&response)?;
+    let team_id = first_node(&response, "data.teams.nodes", "id");
+    if team_id.is_empty() {
+        return Err(Error::LinearApi(format!(
+            "No Linear team with key {team_key}"
+        )));
+    }
+    Ok(team_id.to_string())
+}
+
+/// Finds an issue in the team with exactly the given title.
+///
+/// # GraphQL Query
+/// ```graphql
+/// query($key: String!, $title: String!) {
+///   issues(filter: { team: { key: { eq: $key } }, title: { eq: $title } },
+///          first: 1) {
+///     nodes { identifier }
+///   }
+/// }
+/// ```
+fn find_existing(
+    team_key: &str,
+    title: &str,
+    linear: &LinearApi,
+) -> Result<Option<String>, Error> {
+    let query = format!(
+        r#"{{"query": "query($key: String!, $title: String!) {{ issues(filter: {{ team: {{ key: {{ eq: $key }} }}, title: {{ eq: $title }} }}, first: 1) {{ nodes {{ identifier }} }} }}", "variables": {{"key": {}, "title": {}}}}}"#,
+        utils::json_string(team_key),
+        utils::json_string(title)
+    );
+    let response = utils::graphql_request(&query, linear)?;
+    utils::ensure_no_graphql_errors(&response)?;
+    let identifier = first_node(&response, "data.issues.nodes", "identifier");
+    Ok((!identifier.is_empty()).then(|| identifier.to_string()))
+}
+
+/// The string `field` of the first node of the connection at `path`, or `""`
+/// if there is none.
+fn first_node<'a>(response: &'a Value, path: &str, field: &str) -> &'a str {
+    response
+        .get(path)
+        .items()
+        .first()
+        .map_or("", |node| node.get(field).str_or_empty())
+}
+
+/// Creates an issue, returning its identifier.
+///
+/// # GraphQL Mutation
+/// ```graphql
+/// mutation($teamId: String!, $title: String!, $description: String!) {
+///   issueCreate(input: {
+///     teamId: $teamId, title: $title, description: $description
+///   }) {
+///     success
+///     issue { identifier }
+///   }
+/// }
+/// ```
+///
+/// # Errors
+/// Returns an error if the request fails or the mutation returns
+/// `success: false`.
+pub fn create_issue(
+    team_id: &str,
+    title: &str,
+    description: &str,
+    linear: &LinearApi,
+) -> Result<String, Error> {
+    let query = format!(
+        r#"{{"query": "mutation($teamId: String!, $title: String!, $description: String!) {{ issueCreate(input: {{ teamId: $teamId, title: $title, description: $description }}) {{ success issue {{ identifier }} }} }}", "variables": {{"teamId": {}, "title": {}, "description": {}}}}}"#,
+        utils::json_string(team_id),
+        utils::json_string(title),
+        utils::json_string(description)
+    );
+    let response = utils::graphql_request(&query, linear)?;
+    utils::ensure_no_graphql_errors(&response)?;
+
+    let result = response.get("data.issueCreate");
+    if result.get("success").as_bool() == Some(true) {
+        Ok(result.get("issue.identifier").str_or_empty().to_string())
+    } else {
+        Err(Error::LinearApi(format!(
+            "Issue creation failed: {response}"
+        )))
+    }
+}
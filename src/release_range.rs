@@ -0,0 +1,149 @@
+//! Releases between two tags (`--since-tag`, `--until-tag`).
+//!
+//! Teams cutting frequent hotfix releases catch up on their tickets in one
+//! run by naming a range instead of every `--release-tag`:
+//!
+//! ```text
+//! --since-tag v1.2.0 --until-tag v1.2.5  ->  v1.2.1 v1.2.2 ... v1.2.5
+//! --since-tag v1.2.0                     ->  every release after v1.2.0
+//! ```
+//!
+//! The releases of the current repository are listed newest first, in the
+//! order they were created (`gh api --paginate repos/{owner}/{repo}/releases`,
+//! the same endpoint with `--github-api`, or `glab api projects/:id/releases`
+//! with `--forge gitlab`). The range holds the releases created after
+//! `--since-tag`, up to and including `--until-tag` (by default, the latest
+//! release), oldest first. Draft (and upcoming GitLab) releases have no
+//! published notes, so they are never part of it.
+//!
+//! The same list gives the release before a release, which
+//! `--discover compare` compares it with (see [`crate::compare`]).
+
+use crate::config::{Config, Forge, ReleaseRange};
+use crate::error::Error;
+use crate::exit_status;
+use crate::github_api::GitHub;
+use crate::gitlab;
+use crate::utils;
+
+/// The tags of the releases in `range`, oldest first (empty when no release
+/// came after `--since-tag`).
+///
+/// # Errors
+/// Returns an error if the releases can't be listed, a tag of the range
+/// isn't a listed release, or `--since-tag` is newer than `--until-tag`.
+pub fn tags(
+    config: &Config,
+    range: &ReleaseRange,
+) -> Result<Vec<String>, Error> {
+    select(&listed(config)?, &range.since, range.until.as_deref())
+}
+
+/// The tag of the release created before release `tag`.
+///
+/// # Errors
+/// Returns an error if the releases can't be listed, `tag` isn't a listed
+/// release, or it is the first release.
+pub fn previous(config: &Config, tag: &str) -> Result<String, Error> {
+    let listed = listed(config)?;
+    let position =
+        listed
+            .iter()
+            .position(|listed| listed == tag)
+            .ok_or_else(|| {
+                Error::GitHub(format!("{tag} is not a published release"))
+            })?;
+    listed.get(position + 1).cloned().ok_or_else(|| {
+        Error::GitHub(format!("{tag} is the first release, none came before"))
+    })
+}
+
+/// The tags of the published releases of the current repository, newest
+/// first.
+///
+/// # Errors
+/// Returns an error if the releases can't be listed.
+fn listed(config: &Config) -> Result<Vec<String>, Error> {
+    match config.forge {
+        Forge::GitHub => github_release_tags(&config.github()?),
+        Forge::GitLab => gitlab::release_tags(),
+    }
+}
+
+/// Picks the releases after `since`, up to `until` (or the latest), from
+/// `listed` (newest first), oldest first.
+///
+/// # Errors
+/// Returns an error if `since` or `until` isn't listed, or `since` is newer
+/// than `until`.
+fn select(
+    listed: &[String],
+    since: &str,
+    until: Option<&str>,
+) -> Result<Vec<String>, Error> {
+    let position = |flag: &str, tag: &str| {
+        listed.iter().position(|listed| listed == tag).ok_or_else(|| {
+            Error::GitHub(format!(
+                "{flag} {tag} is not a published release ({} release(s) listed)",
+                listed.len()
+            ))
+        })
+    };
+    let end = position("--since-tag", since)?;
+    let start = match until {
+        Some(until) => position("--until-tag", until)?,
+        None => 0,
+    };
+    if start > end {
+        let until = until.unwrap_or_default();
+        return Err(Error::GitHub(format!(
+            "--since-tag {since} is newer than --until-tag {until}"
+        )));
+    }
+    Ok(listed[start..end].iter().rev().cloned().collect())
+}
+
+/// The tags of the published releases of the current GitHub repository,
+/// newest first.
+///
+/// # Errors
+/// Returns an error if `gh` (or the API) fails to list them.
+fn github_release_tags(github: &GitHub) -> Result<Vec<String>, Error> {
+    let github_host = match github {
+        GitHub::Cli(host) => host.as_deref(),
+        GitHub::Api(api) => {
+            let releases = api
+                .releases()
+                .map_err(|e| e.context("Failed to list releases"))?;
+            return Ok(releases
+                .iter()
+                .filter(|release| release.get("draft").as_bool() != Some(true))
+                .map(|release| {
+                    release.get("tag_name").str_or_empty().to_string()
+                })
+                .collect());
+        }
+    };
+    let output = utils::gh_command(github_host)
+        .args(["api", "--paginate", "repos/{owner}/{repo}/releases"])
+        .args(["--jq", ".[] | select(.draft | not) | .tag_name"])
+        .output()
+        .map_err(|e| {
+            exit_status::spawn_error("Failed to execute gh command", e)
+        })?;
+
+    if !output.status.success() {
+        return Err(Error::GitHub(format!(
+            "Failed to list releases: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    let tags = String::from_utf8(output.stdout)
+        .map_err(|e| Error::command(format!("Invalid UTF-8 from gh: {e}")))?;
+    Ok(tags
+        .lines()
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(String::from)
+        .collect())
+}
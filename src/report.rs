@@ -0,0 +1,452 @@
+//! Release report: which PRs a release contains, the tickets they reference
+//! and the tickets' current states.
+//!
+//! This module implements the `report` mode, a read-only audit for release
+//! managers to run before the real update. It runs the parse-notes and
+//! extract-tickets stages like the orchestrator does, reads the per-PR ticket
+//! mapping extract-tickets writes (see `--mapping-file`), and looks every
+//! ticket up in Linear (or the `--linear-snapshot`). Nothing is changed in
+//! Linear or GitHub.
+//!
+//! ## Output
+//! With the default `--output-format text`, a Markdown document: a table of
+//! every PR with its tickets, their titles and current workflow states,
+//! followed by the number of tickets in each state. PRs referencing no
+//! ticket get a row of their own, so gaps in the changelog stand out.
+//!
+//! With `--output-format json`, a single JSON object:
+//! ```json
+//! {"release":"v1.2.3","prs":[{"pr":123,"repo":"owner/repo","merged":true,"tickets":[{"ticket":"ABC-1","url":"https://linear.app/acme/issue/ABC-1","title":"Fix login","state":"Passing","state_type":"started"}]}],"schema_version":1}
+//! ```
+//! `url` is present when the Linear org is known; a ticket that couldn't be
+//! looked up has an `error` instead of its title and state.
+//!
+//! PRs extract-tickets skips (unmerged, outside the merge window) aren't in
+//! the mapping, and so aren't reported; they are listed in its log.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::process::{Command, Stdio};
+
+use crate::cache::ApiBudget;
+use crate::config::{Config, Forge, Lookups, OutputFormat};
+use crate::error::Error;
+use crate::exit_status;
+use crate::json;
+use crate::orchestrator;
+use crate::protocol::{PrRef, SCHEMA_VERSION};
+use crate::tickets::TicketStore;
+use crate::update_tickets;
+use crate::utils;
+
+const NAME: &str = "report";
+
+macro_rules! log {
+    ($level:ident, $fmt:expr) => {
+        utils::log_at(utils::LogLevel::$level, NAME, format_args!($fmt));
+    };
+    ($fmt:expr) => {
+        utils::log(NAME, format_args!($fmt));
+    };
+}
+
+/// A PR of the release and the tickets it references.
+pub struct PrEntry {
+    pub pr: PrRef,
+    pub merged: bool,
+    pub tickets: Vec<String>,
+}
+
+/// A ticket's current state, or why it couldn't be looked up.
+enum TicketState {
+    Found {
+        title: String,
+        state: String,
+        state_type: String,
+    },
+    Failed(String),
+}
+
+/// Runs the report mode, printing the report to stdout.
+///
+/// # Errors
+/// Returns an error if:
+/// - `--release-tag` or `LINEAR_API_KEY` is not provided
+/// - The snapshot can't be read
+/// - parse-notes or extract-tickets fail (other than by running out of
+///   `--api-budget`)
+/// - The mapping file can't be read or parsed
+///
+/// Tickets that can't be looked up are reported as such rather than failing
+/// the run.
+pub fn run(config: &Config) -> Result<(), Error> {
+    let release_tag = config.release_tag.as_deref().ok_or_else(|| {
+        Error::Config("report requires --release-tag".to_string())
+    })?;
+    let linear = config.linear_api()?;
+    let org = config.linear_org();
+    let snapshot = config.snapshot()?;
+
+    let prs = release_prs(config, release_tag)?;
+
+    if let Some(ref snapshot) = snapshot {
+        let path = snapshot.path();
+        log!("reading tickets from snapshot {path}");
+    }
+    let mut store = TicketStore::new(false, false, snapshot);
+    let issue_ids = ticket_ids(&prs);
+    let count = issue_ids.len();
+    log!("looking up {count} tickets");
+    if config.lookups == Lookups::Batched {
+        store.prefetch(&issue_ids, &linear);
+    }
+    let states: BTreeMap<String, TicketState> = issue_ids
+        .into_iter()
+        .map(|issue_id| {
+            let state = match store.get(&issue_id, &linear) {
+                Ok(ticket) => TicketState::Found {
+                    title: ticket.title.clone(),
+                    state: ticket.state_name.clone(),
+                    state_type: ticket.state_type.clone(),
+                },
+                Err(e) => {
+                    log!(Warn, "Failed to look up {issue_id}: {e}");
+                    TicketState::Failed(e.to_string())
+                }
+            };
+            (issue_id, state)
+        })
+        .collect();
+
+    let report = Report {
+        release_tag,
+        org: org.as_deref(),
+        prs: &prs,
+        states: &states,
+    };
+    match config.output_format {
+        OutputFormat::Json => println!("{}", report.to_json()),
+        _ => print!("{}", report.to_markdown()),
+    }
+    log!("done");
+    Ok(())
+}
+
+/// The release's PRs and the tickets they reference, from the mapping
+/// extract-tickets writes to `--mapping-file` (or a temporary file).
+///
+/// # Errors
+/// Returns an error if parse-notes or extract-tickets fail (other than by
+/// running out of `--api-budget`), or the mapping can't be read or parsed.
+pub fn release_prs(
+    config: &Config,
+    release_tag: &str,
+) -> Result<Vec<PrEntry>, Error> {
+    let (mapping_path, temporary) = if let Some(ref path) = config.mapping_file
+    {
+        (path.clone(), false)
+    } else {
+        let path = env::temp_dir().join(format!(
+            "release-linear-ticket-update-report-{}.json",
+            std::process::id()
+        ));
+        (path.to_string_lossy().into_owned(), true)
+    };
+    let extracted = extract_mapping(config, release_tag, &mapping_path);
+    let prs = extracted.and_then(|()| read_mapping(&mapping_path));
+    if temporary && let Err(e) = fs::remove_file(&mapping_path) {
+        log!(
+            Warn,
+            "Failed to remove temporary mapping file {mapping_path}: {e}"
+        );
+    }
+    prs
+}
+
+/// The tickets referenced by `prs`, without duplicates, in PR order.
+pub fn ticket_ids(prs: &[PrEntry]) -> Vec<String> {
+    let mut issue_ids: Vec<String> = Vec::new();
+    for ticket in prs.iter().flat_map(|entry| &entry.tickets) {
+        if !issue_ids.contains(ticket) {
+            issue_ids.push(ticket.clone());
+        }
+    }
+    issue_ids
+}
+
+/// Runs parse-notes and extract-tickets for the release, with extract-tickets
+/// writing its per-PR ticket mapping to `mapping_path`. Its ticket output is
+/// discarded.
+///
+/// A stage exiting with [`ApiBudget::EXHAUSTED_EXIT_CODE`] still leaves a
+/// (partial) mapping; the budget of `config` is then marked exhausted, so the
+/// process exits with the same status after reporting.
+///
+/// # Errors
+/// Returns an error if a stage fails to spawn or exits with any other
+/// non-zero status.
+fn extract_mapping(
+    config: &Config,
+    release_tag: &str,
+    mapping_path: &str,
+) -> Result<(), Error> {
+    let exe_path = env::current_exe()
+        .map_err(|e| Error::io("Failed to get current executable path", e))?;
+    let mut forge_args: Vec<&str> = Vec::new();
+    if config.forge != Forge::GitHub {
+        forge_args.extend(["--forge", config.forge.name()]);
+    }
+    if let Some(ref host) = config.github_host {
+        forge_args.extend(["--github-host", host]);
+    }
+
+    let mut parse_cmd = Command::new(&exe_path)
+        .args(["parse-notes", "--release-tag", release_tag])
+        .args(["--output-format", "ndjson"])
+        .args(&forge_args)
+        .args(orchestrator::discover_args(config))
+        .args(orchestrator::github_args(config))
+        .args(
+            config
+                .cache_dir
+                .iter()
+                .flat_map(|dir| ["--cache-dir", dir.as_str()]),
+        )
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| Error::io("Failed to spawn parse-notes", e))?;
+    let parse_stdout = parse_cmd.stdout.take().ok_or_else(|| {
+        Error::Other("Failed to capture parse-notes stdout".to_string())
+    })?;
+
+    let mut extract_cmd = Command::new(&exe_path)
+        .args(["extract-tickets", "--output-format", "ndjson"])
+        .args(&forge_args)
+        .args(orchestrator::github_args(config))
+        .args(orchestrator::extract_tickets_args(config))
+        .args(["--mapping-file", mapping_path])
+        .args(shared_args(config))
+        .stdin(parse_stdout)
+        .stdout(Stdio::null())
+        .spawn()
+        .map_err(|e| Error::io("Failed to spawn extract-tickets", e))?;
+
+    let extract_status = extract_cmd
+        .wait()
+        .map_err(|e| Error::io("Failed to wait for extract-tickets", e))?;
+    let parse_status = parse_cmd
+        .wait()
+        .map_err(|e| Error::io("Failed to wait for parse-notes", e))?;
+
+    let exhausted = |code| code == Some(ApiBudget::EXHAUSTED_EXIT_CODE);
+    for status in [parse_status, extract_status] {
+        if status.success() {
+            continue;
+        }
+        if !exhausted(status.code()) {
+            return Err(exit_status::stage_error(
+                status,
+                "Extracting the release's tickets failed".to_string(),
+            ));
+        }
+        if let Some(ref budget) = config.api_budget {
+            budget.mark_exhausted();
+        }
+    }
+    Ok(())
+}
+
+/// The `--cache-dir`, `--rate-limit` and `--api-budget` arguments forwarded
+/// to extract-tickets.
+fn shared_args(config: &Config) -> Vec<String> {
+    let mut args = Vec::new();
+    if let Some(ref dir) = config.cache_dir {
+        args.extend(["--cache-dir".to_string(), dir.clone()]);
+    }
+    if let Some(ref limiter) = config.rate_limiter {
+        let rate_limit = limiter.requests_per_sec();
+        args.extend(["--rate-limit".to_string(), rate_limit.to_string()]);
+    }
+    if let Some(ref budget) = config.api_budget {
+        args.extend(["--api-budget".to_string(), budget.limit().to_string()]);
+    }
+    args
+}
+
+/// Reads the `--mapping-file` array written by extract-tickets.
+///
+/// # Errors
+/// Returns an error if the file can't be read or an entry is malformed.
+fn read_mapping(path: &str) -> Result<Vec<PrEntry>, Error> {
+    let contents = fs::read_to_string(path).map_err(|e| {
+        Error::io(format!("Failed to read mapping file {path}"), e)
+    })?;
+    let mapping = json::parse(&contents)
+        .map_err(|e| e.context(format!("Invalid mapping file {path}")))?;
+    mapping
+        .items()
+        .iter()
+        .map(|entry| {
+            let pr = PrRef::from_record(entry)?;
+            let tickets = entry
+                .get("tickets")
+                .items()
+                .iter()
+                .filter_map(|ticket| ticket.get("ticket").as_str())
+                .map(String::from)
+                .collect();
+            Ok(PrEntry {
+                pr,
+                merged: entry.get("merged").as_bool() == Some(true),
+                tickets,
+            })
+        })
+        .collect()
+}
+
+/// Everything the report shows.
+struct Report<'a> {
+    release_tag: &'a str,
+    /// Linear organization, for ticket URLs
+    org: Option<&'a str>,
+    prs: &'a [PrEntry],
+    states: &'a BTreeMap<String, TicketState>,
+}
+
+impl Report<'_> {
+    fn url(&self, ticket: &str) -> Option<String> {
+        self.org.map(|org| update_tickets::issue_url(org, ticket))
+    }
+
+    /// Renders the Markdown report.
+    fn to_markdown(&self) -> String {
+        let mut out = format!("# Release {}\n\n", self.release_tag);
+        if self.prs.is_empty() {
+            out.push_str("No PRs with tickets were found.\n");
+            return out;
+        }
+        out.push_str("| PR | Ticket | Title | State |\n");
+        out.push_str("|----|--------|-------|-------|\n");
+        for entry in self.prs {
+            let pr = if entry.merged {
+                entry.pr.to_string()
+            } else {
+                format!("{} (unmerged)", entry.pr)
+            };
+            if entry.tickets.is_empty() {
+                let _ = writeln!(out, "| {pr} | | _no tickets_ | |");
+            }
+            for ticket in &entry.tickets {
+                let link = match self.url(ticket) {
+                    Some(url) => format!("[{ticket}]({url})"),
+                    None => ticket.clone(),
+                };
+                let (title, state) = match self.states.get(ticket) {
+                    Some(TicketState::Found {
+                        title,
+                        state,
+                        state_type,
+                    }) => (title.clone(), format!("{state} ({state_type})")),
+                    Some(TicketState::Failed(error)) => {
+                        (format!("_lookup failed: {error}_"), String::new())
+                    }
+                    None => (String::new(), String::new()),
+                };
+                let _ = writeln!(
+                    out,
+                    "| {pr} | {link} | {} | {} |",
+                    escape_cell(&title),
+                    escape_cell(&state)
+                );
+            }
+        }
+
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for state in self.states.values() {
+            let name = match state {
+                TicketState::Found { state, .. } => state.as_str(),
+                TicketState::Failed(_) => "lookup failed",
+            };
+            *counts.entry(name).or_default() += 1;
+        }
+        let _ = write!(out, "\n{} tickets:", self.states.len());
+        for (name, count) in counts {
+            let _ = write!(out, "\n- {name}: {count}");
+        }
+        out.push('\n');
+        out
+    }
+
+    /// Renders the JSON report (see the module docs).
+    fn to_json(&self) -> String {
+        let prs: Vec<String> = self
+            .prs
+            .iter()
+            .map(|entry| {
+                let tickets: Vec<String> = entry
+                    .tickets
+                    .iter()
+                    .map(|ticket| self.ticket_json(ticket))
+                    .collect();
+                let mut json = format!("{{\"pr\":{}", entry.pr.number);
+                if let Some(ref repo) = entry.pr.repo {
+                    let _ =
+                        write!(json, ",\"repo\":{}", utils::json_string(repo));
+                }
+                let _ = write!(
+                    json,
+                    ",\"merged\":{},\"tickets\":[{}]}}",
+                    entry.merged,
+                    tickets.join(",")
+                );
+                json
+            })
+            .collect();
+        format!(
+            "{{\"release\":{},\"prs\":[{}],\"schema_version\":{SCHEMA_VERSION}}}",
+            utils::json_string(self.release_tag),
+            prs.join(",")
+        )
+    }
+
+    fn ticket_json(&self, ticket: &str) -> String {
+        let mut json = format!("{{\"ticket\":{}", utils::json_string(ticket));
+        if let Some(url) = self.url(ticket) {
+            let _ = write!(json, ",\"url\":{}", utils::json_string(&url));
+        }
+        match self.states.get(ticket) {
+            Some(TicketState::Found {
+                title,
+                state,
+                state_type,
+            }) => {
+                for (name, value) in [
+                    ("title", title),
+                    ("state", state),
+                    ("state_type", state_type),
+                ] {
+                    let _ = write!(
+                        json,
+                        ",\"{name}\":{}",
+                        utils::json_string(value)
+                    );
+                }
+            }
+            Some(TicketState::Failed(error)) => {
+                let _ =
+                    write!(json, ",\"error\":{}", utils::json_string(error));
+            }
+            None => {}
+        }
+        json.push('}');
+        json
+    }
+}
+
+/// Escapes a Markdown table cell: pipes would end the cell, and line breaks
+/// the row.
+fn escape_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace(['\n', '\r'], " ")
+}
@@ -0,0 +1,159 @@
+//! Move tickets back to the states an update-tickets run moved them out of.
+//!
+//! This module implements the `rollback` mode, which undoes a run that wrote
+//! a `--journal` (e.g. one started with the wrong release tag). The journal's
+//! moves are undone last first: a ticket still in the state the run moved
+//! it to is moved back to the state it came from. Entries of failed moves
+//! are skipped.
+//!
+//! A ticket whose state changed since (moved on by hand, or already rolled
+//! back) is left alone and logged, so a rollback can safely be run again. A
+//! ticket moved by several journaled runs goes back through each of their
+//! entries in turn, ending in the state before the first.
+//!
+//! ## Output
+//! The URL of every ticket moved back is printed to stdout. With
+//! `--dry-run`, tickets are looked up and the URLs of those that would be
+//! moved back are printed, but nothing is changed.
+
+use crate::config::Config;
+use crate::error::Error;
+use crate::explain::Subject;
+use crate::journal::{self, Entry, Outcome};
+use crate::labels::LabelChange;
+use crate::tickets::TicketStore;
+use crate::update_tickets;
+use crate::utils::{self, LinearApi};
+
+const NAME: &str = "rollback";
+
+macro_rules! log {
+    ($level:ident, $fmt:expr) => {
+        utils::log_at(utils::LogLevel::$level, NAME, format_args!($fmt));
+    };
+    ($fmt:expr) => {
+        utils::log(NAME, format_args!($fmt));
+    };
+}
+
+/// Ticket counts reported at the end of the rollback.
+#[derive(Debug, Default)]
+struct Tally {
+    rolled_back: usize,
+    changed: usize,
+    failed: usize,
+}
+
+/// Runs the rollback mode.
+///
+/// # Errors
+/// Returns an error if:
+/// - `LINEAR_API_KEY` or `LINEAR_ORG` is not provided
+/// - The journal can't be read
+/// - Any ticket could not be moved back (after trying every other one)
+pub fn run(config: &Config) -> Result<(), Error> {
+    let linear = config.linear_api()?;
+    let org = config.get_linear_org()?;
+    let path = config.journal.as_deref().ok_or_else(|| {
+        Error::Config("rollback requires --journal".to_string())
+    })?;
+    let mut entries = journal::read(path)?;
+    entries.retain(|entry| entry.outcome == Outcome::Moved);
+    let count = entries.len();
+    log!("rolling back {count} change(s) recorded in {path}");
+    if config.dry_run {
+        log!("Dry-run mode enabled. The following issues would be moved back:");
+    }
+
+    let mut tickets = TicketStore::new(false, false, config.snapshot()?);
+    let mut tally = Tally::default();
+    for entry in entries.iter().rev() {
+        let subject = Subject::Ticket(entry.ticket.clone());
+        let result = utils::with_log_subject(subject, || {
+            roll_back(config, &mut tickets, entry, &linear)
+        });
+        match result {
+            Ok(true) => {
+                tally.rolled_back += 1;
+                println!("{}", update_tickets::issue_url(&org, &entry.ticket));
+            }
+            Ok(false) => tally.changed += 1,
+            Err(e) => {
+                let ticket = &entry.ticket;
+                log!(Warn, "Failed to roll back {ticket}: {e}");
+                tally.failed += 1;
+            }
+        }
+    }
+
+    let Tally {
+        rolled_back,
+        changed,
+        failed,
+    } = tally;
+    let verb = if config.dry_run {
+        "would be moved back"
+    } else {
+        "moved back"
+    };
+    log!("done");
+    log!("  {rolled_back} ticket(s) {verb}");
+    log!("  {changed} ticket(s) left alone, their state changed since");
+    log!("  {failed} ticket(s) failed");
+    if failed > 0 {
+        return Err(Error::Other(format!(
+            "{failed} ticket(s) could not be rolled back"
+        )));
+    }
+    Ok(())
+}
+
+/// Moves the ticket of `entry` back to the state it was moved out of, if it
+/// is still in the state it was moved to.
+///
+/// # Returns
+/// Whether the ticket was (or, in dry-run, would be) moved back.
+///
+/// # Errors
+/// Returns an error if the ticket can't be looked up or the update fails.
+fn roll_back(
+    config: &Config,
+    tickets: &mut TicketStore,
+    entry: &Entry,
+    linear: &LinearApi,
+) -> Result<bool, Error> {
+    let Entry {
+        ticket: issue_id,
+        from_state_id,
+        to_state_id,
+        from_state,
+        ..
+    } = entry;
+    let mut ticket = tickets.get(issue_id, linear)?.clone();
+    if ticket.state_id != *to_state_id {
+        let state_name = &ticket.state_name;
+        log!(
+            Warn,
+            "Issue {issue_id} is in {state_name}, no longer in the state the run moved it to, leaving it"
+        );
+        return Ok(false);
+    }
+
+    if config.dry_run {
+        log!("Would move {issue_id} back to {from_state}");
+    } else {
+        let response = update_tickets::update_issue_state(
+            &ticket.identifier,
+            from_state_id,
+            &LabelChange::default(),
+            linear,
+        )?;
+        utils::ensure_no_graphql_errors(&response)?;
+        log!("Moved {issue_id} back to {from_state}");
+    }
+    // An earlier journal entry of the same ticket starts from this state.
+    ticket.state_id.clone_from(from_state_id);
+    ticket.state_name.clone_from(from_state);
+    tickets.insert(issue_id, ticket);
+    Ok(true)
+}
@@ -0,0 +1,244 @@
+//! JSON Schema of the machine outputs (`schema` mode).
+//!
+//! Downstream consumers validate against this schema rather than against
+//! whatever the current release happens to print. It covers:
+//! - `pr_record` / `ticket_record` / `update_record` - ndjson stage records
+//!   (see [`crate::protocol`]); `--output-format json` prints an array of
+//!   them
+//! - `mapping_file` - the extract-tickets `--mapping-file` array
+//! - `manifest_file` - the update-tickets `--manifest` file (see
+//!   [`crate::manifest`])
+//! - `report` - the `report` mode's `--output-format json` document (see
+//!   [`crate::report`])
+//!
+//! Every record, mapping entry, manifest file and report carries `schema_version`,
+//! and the schema document states the version it describes.
+//!
+//! ## Compatibility
+//! Adding an optional field keeps the version; removing or renaming a field,
+//! or changing its type or meaning, bumps
+//! [`crate::protocol::SCHEMA_VERSION`]. Objects allow additional properties,
+//! so consumers should ignore fields they don't know.
+
+use crate::protocol::SCHEMA_VERSION;
+
+/// The schema document, with `@VERSION@` standing for [`SCHEMA_VERSION`].
+const TEMPLATE: &str = r##"{
+  "$schema": "https://json-schema.org/draft/2020-12/schema",
+  "$id": "https://github.com/mkpro118/release-linear-ticket-update/schema/v@VERSION@.json",
+  "title": "release-linear-ticket-update machine outputs",
+  "schema_version": @VERSION@,
+  "$defs": {
+    "schema_version": {
+      "description": "Version of this schema the document conforms to",
+      "const": @VERSION@
+    },
+    "pr_number": {
+      "type": "integer",
+      "minimum": 1
+    },
+    "repo": {
+      "description": "owner/repo of a PR outside the current repository; omitted for the current repository",
+      "type": "string",
+      "pattern": "^[^/]+/[^/]+$"
+    },
+    "source": {
+      "enum": ["title", "body", "comment", "commit_headline", "commit_body", "commit_trailer"]
+    },
+    "pr_record": {
+      "description": "parse-notes ndjson output, extract-tickets input",
+      "type": "object",
+      "required": ["pr", "schema_version"],
+      "properties": {
+        "pr": { "$ref": "#/$defs/pr_number" },
+        "repo": { "$ref": "#/$defs/repo" },
+        "schema_version": { "$ref": "#/$defs/schema_version" }
+      }
+    },
+    "ticket_record": {
+      "description": "extract-tickets and extract-commits ndjson output, update-tickets input",
+      "type": "object",
+      "required": ["ticket", "confidence", "source", "schema_version"],
+      "oneOf": [{ "required": ["pr"] }, { "required": ["commit"] }],
+      "properties": {
+        "ticket": { "type": "string" },
+        "pr": { "$ref": "#/$defs/pr_number" },
+        "repo": { "$ref": "#/$defs/repo" },
+        "commit": {
+          "description": "SHA of the commit the ticket was found in, for extract-commits",
+          "type": "string",
+          "pattern": "^[0-9a-f]{40}$"
+        },
+        "confidence": { "enum": ["high", "medium", "low"] },
+        "source": { "$ref": "#/$defs/source" },
+        "org": { "type": "string" },
+        "schema_version": { "$ref": "#/$defs/schema_version" }
+      }
+    },
+    "update_record": {
+      "description": "update-tickets ndjson output",
+      "type": "object",
+      "required": ["ticket", "url", "outcome", "schema_version"],
+      "properties": {
+        "ticket": { "type": "string" },
+        "url": { "type": "string" },
+        "outcome": {
+          "enum": ["updated", "moved", "already_completed", "already_released", "skipped", "failed"]
+        },
+        "skip_reason": {
+          "description": "Why a skipped ticket was skipped",
+          "enum": ["excluded", "unknown_team", "quarantined", "checkpointed", "archived", "filtered", "not_in_project", "other_user", "already_in_target", "ineligible_state", "other_org"]
+        },
+        "from": {
+          "description": "Workflow state when the ticket was looked up",
+          "type": "string"
+        },
+        "to": {
+          "description": "Workflow state the ticket was moved to: a --state-route or --target-state state (or the --target-state-type), or completed",
+          "type": "string"
+        },
+        "pr": { "$ref": "#/$defs/pr_number" },
+        "repo": { "$ref": "#/$defs/repo" },
+        "error": { "type": "string" },
+        "dry_run": {
+          "description": "Present (true) in dry runs, where nothing was actually changed",
+          "const": true
+        },
+        "schema_version": { "$ref": "#/$defs/schema_version" }
+      }
+    },
+    "mapping_file": {
+      "description": "extract-tickets --mapping-file",
+      "type": "array",
+      "items": {
+        "type": "object",
+        "required": ["pr", "merged", "tickets", "schema_version"],
+        "properties": {
+          "pr": { "$ref": "#/$defs/pr_number" },
+          "repo": { "$ref": "#/$defs/repo" },
+          "merged": { "type": "boolean" },
+          "tickets": {
+            "type": "array",
+            "items": {
+              "type": "object",
+              "required": ["ticket", "sources"],
+              "properties": {
+                "ticket": { "type": "string" },
+                "sources": {
+                  "type": "array",
+                  "items": { "$ref": "#/$defs/source" }
+                },
+                "org": { "type": "string" }
+              }
+            }
+          },
+          "schema_version": { "$ref": "#/$defs/schema_version" }
+        }
+      }
+    },
+    "manifest_file": {
+      "description": "update-tickets --manifest",
+      "type": "object",
+      "required": ["sha256", "manifest", "schema_version"],
+      "properties": {
+        "sha256": {
+          "description": "SHA-256 of the manifest exactly as it appears in the file",
+          "type": "string",
+          "pattern": "^[0-9a-f]{64}$"
+        },
+        "manifest": {
+          "type": "object",
+          "required": ["version", "org", "changes"],
+          "properties": {
+            "version": { "type": "integer" },
+            "org": { "type": "string" },
+            "changes": {
+              "type": "array",
+              "items": {
+                "type": "object",
+                "required": ["ticket", "team_id", "from"],
+                "properties": {
+                  "ticket": { "type": "string" },
+                  "team_id": { "type": "string" },
+                  "from": { "type": "string" },
+                  "to": {
+                    "description": "Name of the workflow state the ticket is moved to",
+                    "type": "string"
+                  },
+                  "to_state_id": {
+                    "description": "Workflow state the ticket is moved to (empty with --linear-snapshot)",
+                    "type": "string"
+                  },
+                  "add_labels": {
+                    "description": "Labels added with the move",
+                    "type": "array",
+                    "items": { "type": "string" }
+                  },
+                  "remove_labels": {
+                    "description": "Labels removed with the move",
+                    "type": "array",
+                    "items": { "type": "string" }
+                  }
+                }
+              }
+            }
+          }
+        },
+        "schema_version": { "$ref": "#/$defs/schema_version" }
+      }
+    },
+    "report": {
+      "description": "report --output-format json",
+      "type": "object",
+      "required": ["release", "prs", "schema_version"],
+      "properties": {
+        "release": { "type": "string" },
+        "prs": {
+          "type": "array",
+          "items": {
+            "type": "object",
+            "required": ["pr", "merged", "tickets"],
+            "properties": {
+              "pr": { "$ref": "#/$defs/pr_number" },
+              "repo": { "$ref": "#/$defs/repo" },
+              "merged": { "type": "boolean" },
+              "tickets": {
+                "type": "array",
+                "items": {
+                  "type": "object",
+                  "required": ["ticket"],
+                  "properties": {
+                    "ticket": { "type": "string" },
+                    "url": {
+                      "description": "Present when the Linear org is known",
+                      "type": "string"
+                    },
+                    "title": { "type": "string" },
+                    "state": {
+                      "description": "Current workflow state name",
+                      "type": "string"
+                    },
+                    "state_type": { "type": "string" },
+                    "error": {
+                      "description": "Why the ticket couldn't be looked up; title and state are then absent",
+                      "type": "string"
+                    }
+                  }
+                }
+              }
+            }
+          }
+        },
+        "schema_version": { "$ref": "#/$defs/schema_version" }
+      }
+    }
+  }
+}"##;
+
+/// Prints the schema document to stdout.
+pub fn run() {
+    println!(
+        "{}",
+        TEMPLATE.replace("@VERSION@", &SCHEMA_VERSION.to_string())
+    );
+}
@@ -0,0 +1,273 @@
+//! The PRs of one part of a monorepo (`--pr-label`, `--path-filter`).
+//!
+//! A monorepo's services are often released separately, each from its own
+//! tags, while their release notes can still reference PRs of the others.
+//! With `--pr-label LABEL`, extract-tickets only takes the tickets of PRs
+//! carrying the label (ignoring case); with `--path-filter GLOB`, only those
+//! of PRs changing a file that matches the glob. Both are repeatable, any
+//! label or glob matching, and given together, a PR must pass both. The
+//! other PRs are skipped and listed at the end, like unmerged PRs.
+//!
+//! ## Globs
+//! A glob is matched against the paths of the files a PR changes (and, for a
+//! renamed file, its old path), from the repository root: `*` matches any
+//! run of characters within a directory, `?` any one character, and `**`
+//! any number of directories. A glob also matches the files under the
+//! directories it matches, so `services/api` is `services/api/**`.
+//!
+//! ## Fetching
+//! A PR's labels and changed files are only fetched for the filters given,
+//! through `gh api --paginate` (`repos/OWNER/REPO/issues/N/labels` and
+//! `.../pulls/N/files`; the same REST endpoints with `--github-api`). Each
+//! list claims one request of the `--api-budget`, and with `--cache-dir`, it
+//! is cached under `github-labels/` or `github-files/` like the PR itself.
+//! GitHub lists at most 3000 files of a PR.
+
+use crate::cache::{ApiBudget, Cache, RateLimiter};
+use crate::error::Error;
+use crate::github_api::GitHub;
+use crate::protocol::PrRef;
+use crate::utils;
+
+const NAME: &str = "extract-tickets";
+
+macro_rules! log {
+    ($level:ident, $fmt:expr) => {
+        utils::log_at(utils::LogLevel::$level, NAME, format_args!($fmt));
+    };
+}
+
+/// The PRs extract-tickets takes tickets from, by label and changed path.
+#[derive(Debug, Clone, Default)]
+pub struct PrScope {
+    /// `--pr-label` labels, any of which a PR must carry
+    pub labels: Vec<String>,
+    /// `--path-filter` globs, any of which a changed file must match
+    pub paths: Vec<String>,
+}
+
+/// The labels and changed files of a PR, as far as the scope needs them.
+#[derive(Debug, Default)]
+pub struct ScopeFacts {
+    /// Label names (empty without `--pr-label`)
+    labels: Vec<String>,
+    /// Changed paths (empty without `--path-filter`)
+    files: Vec<String>,
+}
+
+impl PrScope {
+    /// Whether `--pr-label` or `--path-filter` was given.
+    #[must_use]
+    pub fn is_set(&self) -> bool {
+        !self.labels.is_empty() || !self.paths.is_empty()
+    }
+
+    /// Fetches the labels and changed files of `pr` the filters need (from
+    /// `cache`, if there), as described in the module docs.
+    ///
+    /// # Errors
+    /// Returns an error if the budget is used up, or `gh` (or the API)
+    /// fails.
+    pub fn fetch(
+        &self,
+        pr: &PrRef,
+        github: &GitHub,
+        cache: Option<&Cache>,
+        limiter: Option<&RateLimiter>,
+        budget: Option<&ApiBudget>,
+    ) -> Result<ScopeFacts, Error> {
+        let list = |kind: List| {
+            let key = pr.to_text();
+            if let Some(cached) =
+                cache.and_then(|cache| cache.get(kind.namespace(), &key))
+            {
+                let name = kind.name();
+                log!(Verbose, "using cached {name} of PR {pr}");
+                return Ok(cached.lines().map(String::from).collect());
+            }
+            let items = fetch_list(pr, kind, github, limiter, budget)?;
+            if let Some(cache) = cache {
+                cache.put(kind.namespace(), &key, &items.join("\n"))?;
+            }
+            Ok::<_, Error>(items)
+        };
+        Ok(ScopeFacts {
+            labels: if self.labels.is_empty() {
+                Vec::new()
+            } else {
+                list(List::Labels)?
+            },
+            files: if self.paths.is_empty() {
+                Vec::new()
+            } else {
+                list(List::Files)?
+            },
+        })
+    }
+
+    /// Why a PR with `facts` is out of scope, or `None` if it is in scope.
+    #[must_use]
+    pub fn excludes(&self, facts: &ScopeFacts) -> Option<String> {
+        if !self.labels.is_empty()
+            && !facts.labels.iter().any(|label| {
+                self.labels
+                    .iter()
+                    .any(|want| want.eq_ignore_ascii_case(label))
+            })
+        {
+            return Some(format!("no label {}", self.labels.join(" or ")));
+        }
+        if !self.paths.is_empty()
+            && !facts.files.iter().any(|file| {
+                self.paths.iter().any(|glob| path_matches(glob, file))
+            })
+        {
+            return Some(format!(
+                "changes nothing in {}",
+                self.paths.join(" or ")
+            ));
+        }
+        None
+    }
+}
+
+/// A list of a PR fetched for the scope.
+#[derive(Debug, Clone, Copy)]
+enum List {
+    Labels,
+    Files,
+}
+
+impl List {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Labels => "labels",
+            Self::Files => "changed files",
+        }
+    }
+
+    /// Namespace of the list in the `--cache-dir` cache.
+    fn namespace(self) -> &'static str {
+        match self {
+            Self::Labels => "github-labels",
+            Self::Files => "github-files",
+        }
+    }
+
+    /// REST endpoint of the list, under `repos/OWNER/REPO/`.
+    fn endpoint(self, number: &str) -> String {
+        match self {
+            Self::Labels => format!("issues/{number}/labels"),
+            Self::Files => format!("pulls/{number}/files"),
+        }
+    }
+}
+
+/// Fetches list `kind` of `pr`: label names, or changed paths (with the old
+/// paths of renamed files).
+///
+/// # Errors
+/// Returns an error if the budget is used up, or `gh` (or the API) fails.
+fn fetch_list(
+    pr: &PrRef,
+    kind: List,
+    github: &GitHub,
+    limiter: Option<&RateLimiter>,
+    budget: Option<&ApiBudget>,
+) -> Result<Vec<String>, Error> {
+    if let Some(budget) = budget {
+        budget.spend()?;
+    }
+    if let Some(limiter) = limiter {
+        limiter.wait()?;
+    }
+    let endpoint = kind.endpoint(&pr.number);
+    let name = kind.name();
+    let github_host = match github {
+        GitHub::Cli(host) => host.as_deref(),
+        GitHub::Api(api) => {
+            let items = api.repo_pages(pr.repo.as_deref(), &endpoint)?;
+            return Ok(items
+                .iter()
+                .flat_map(|item| match kind {
+                    List::Labels => vec![item.get("name")],
+                    List::Files => {
+                        vec![
+                            item.get("filename"),
+                            item.get("previous_filename"),
+                        ]
+                    }
+                })
+                .filter_map(|value| value.as_str().map(String::from))
+                .collect());
+        }
+    };
+    // gh fills in `{owner}/{repo}` from the current repository.
+    let repo = pr.repo.as_deref().unwrap_or("{owner}/{repo}");
+    let jq = match kind {
+        List::Labels => ".[].name",
+        List::Files => ".[] | .filename, (.previous_filename // empty)",
+    };
+    let output = utils::gh_output(
+        utils::gh_command(github_host).args([
+            "api",
+            "--paginate",
+            &format!("repos/{repo}/{endpoint}?per_page=100"),
+            "--jq",
+            jq,
+        ]),
+        limiter,
+    )?;
+    if !output.status.success() {
+        return Err(Error::GitHub(format!(
+            "Failed to get the {name} of PR {pr}"
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+/// Whether `path` matches `glob` (see the module docs).
+fn path_matches(glob: &str, path: &str) -> bool {
+    let glob: Vec<&str> = glob
+        .trim_start_matches("./")
+        .split('/')
+        .filter(|part| !part.is_empty())
+        .collect();
+    let path: Vec<&str> = path.split('/').collect();
+    segments_match(&glob, &path)
+}
+
+/// Whether the glob segments `glob` match the leading segments of `path`.
+fn segments_match(glob: &[&str], path: &[&str]) -> bool {
+    match glob.split_first() {
+        // The rest of the path is under a matched directory.
+        None => true,
+        Some((&"**", rest)) => {
+            (0..=path.len()).any(|skip| segments_match(rest, &path[skip..]))
+        }
+        Some((part, rest)) => path.split_first().is_some_and(|(name, path)| {
+            let part: Vec<char> = part.chars().collect();
+            let name: Vec<char> = name.chars().collect();
+            segment_matches(&part, &name) && segments_match(rest, path)
+        }),
+    }
+}
+
+/// Whether file or directory name `name` matches the glob segment `part`
+/// (`*` and `?`).
+fn segment_matches(part: &[char], name: &[char]) -> bool {
+    match (part.split_first(), name.split_first()) {
+        (None, _) => name.is_empty(),
+        (Some(('*', rest)), _) => {
+            (0..=name.len()).any(|skip| segment_matches(rest, &name[skip..]))
+        }
+        (Some((c, rest)), Some((first, name))) if *c == '?' || c == first => {
+            segment_matches(rest, name)
+        }
+        (Some(_), _) => false,
+    }
+}
@@ -0,0 +1,230 @@
+//! End-to-end smoke test against a sandbox team (`selftest`).
+//!
+//! A dry run only reads from Linear, so a key without write access or an
+//! actor token missing a scope only shows up on the first real release.
+//! `selftest --team SANDBOX` runs every step of update-tickets on a
+//! throwaway issue of a designated sandbox team instead:
+//!
+//! 1. create the issue (`issueCreate`)
+//! 2. look it up, as update-tickets looks up each ticket
+//! 3. look up the team's workflow and pick the state tickets are moved to
+//!    (a Done/Completed state, or the `--target-state`)
+//! 4. move the issue there (`issueUpdate`)
+//! 5. comment on it (`commentCreate`)
+//! 6. look it up again, checking its state and the comment
+//! 7. delete it (`issueDelete`, which moves it to Linear's trash)
+//!
+//! Each step is logged as it passes. The issue is deleted even if a step
+//! after its creation fails, and the run fails naming the first failed step.
+//! With `--dry-run`, only the team and its workflow are looked up, and no
+//! issue is created.
+
+use crate::comments;
+use crate::config::Config;
+use crate::error::Error;
+use crate::labels::LabelChange;
+use crate::release_issue;
+use crate::tickets::TicketStore;
+use crate::update_tickets;
+use crate::utils::{self, LinearApi};
+
+const NAME: &str = "selftest";
+
+/// Title of the throwaway issue.
+const TITLE: &str = "release-linear-ticket-update selftest";
+
+/// Description of the throwaway issue.
+const DESCRIPTION: &str = "Created by `release-linear-ticket-update selftest` to check its credentials and permissions. It is deleted at the end of the test; if it wasn't, it is safe to delete.";
+
+/// Body of the comment posted on the throwaway issue.
+const COMMENT: &str = "Comment by `release-linear-ticket-update selftest`, checking comment permissions.";
+
+macro_rules! log {
+    ($level:ident, $fmt:expr) => {
+        utils::log_at(utils::LogLevel::$level, NAME, format_args!($fmt));
+    };
+    ($fmt:expr) => {
+        utils::log(NAME, format_args!($fmt));
+    };
+}
+
+/// The workflow state the throwaway issue is moved to.
+struct TargetState {
+    id: String,
+    name: String,
+}
+
+/// Runs the selftest mode.
+///
+/// # Errors
+/// Returns an error if `LINEAR_API_KEY` is not provided, or naming the first
+/// step that failed (see the module documentation).
+pub fn run(config: &Config) -> Result<(), Error> {
+    let team_key = config
+        .selftest_team
+        .as_deref()
+        .ok_or_else(|| Error::Config("selftest requires --team".to_string()))?;
+    let linear = config.linear_api()?;
+
+    let team_id = release_issue::find_team_id(team_key, &linear)
+        .map_err(|e| failed("team lookup", e))?;
+    log!("ok: team lookup: found team {team_key}");
+    let target = target_state(config, &team_id, &linear)
+        .map_err(|e| failed("workflow lookup", e))?;
+    let target_name = &target.name;
+    log!("ok: workflow lookup: tickets would be moved to {target_name}");
+    if config.dry_run {
+        log!("Dry-run mode enabled, not creating a test issue");
+        log!("self-test passed");
+        return Ok(());
+    }
+
+    let issue_id =
+        release_issue::create_issue(&team_id, TITLE, DESCRIPTION, &linear)
+            .map_err(|e| failed("issue creation", e))?;
+    log!("ok: issue creation: created {issue_id}");
+    let result = exercise(&issue_id, &target, &linear);
+    let deleted = delete_issue(&issue_id, &linear);
+    match deleted {
+        Ok(()) => {
+            log!("ok: cleanup: deleted {issue_id}");
+        }
+        Err(ref e) => {
+            log!(Warn, "Failed to delete {issue_id}; delete it by hand: {e}");
+        }
+    }
+    result?;
+    deleted.map_err(|e| failed("cleanup", e))?;
+    log!("self-test passed");
+    Ok(())
+}
+
+/// Runs the update-tickets steps on the throwaway issue `issue_id`: state
+/// query, state update, comment and verification.
+///
+/// # Errors
+/// Returns an error naming the step that failed.
+fn exercise(
+    issue_id: &str,
+    target: &TargetState,
+    linear: &LinearApi,
+) -> Result<(), Error> {
+    let mut tickets = TicketStore::new(false, false, None);
+    let ticket = tickets
+        .get(issue_id, linear)
+        .map_err(|e| failed("state query", e))?;
+    let state = &ticket.state_name;
+    log!("ok: state query: {issue_id} is in {state}");
+
+    update_tickets::update_issue_state(
+        issue_id,
+        &target.id,
+        &LabelChange::default(),
+        linear,
+    )
+    .and_then(|response| utils::ensure_no_graphql_errors(&response))
+    .map_err(|e| failed("state update", e))?;
+    let target_name = &target.name;
+    log!("ok: state update: moved {issue_id} to {target_name}");
+
+    comments::create_comment(issue_id, COMMENT, false, linear)
+        .map_err(|e| failed("comment", e))?;
+    log!("ok: comment: commented on {issue_id}");
+
+    // A new store, so the issue is queried again rather than read back from
+    // the first lookup.
+    let mut tickets = TicketStore::new(false, true, None);
+    let ticket = tickets
+        .get(issue_id, linear)
+        .map_err(|e| failed("verification", e))?;
+    if ticket.state_id != target.id {
+        let state = &ticket.state_name;
+        return Err(failed(
+            "verification",
+            Error::Other(format!(
+                "{issue_id} is in {state}, not {target_name}"
+            )),
+        ));
+    }
+    if !ticket.comments.iter().any(|body| body.trim() == COMMENT) {
+        return Err(failed(
+            "verification",
+            Error::Other(format!("{issue_id} doesn't have the test comment")),
+        ));
+    }
+    log!("ok: verification: {issue_id} is in {target_name}, with the comment");
+    Ok(())
+}
+
+/// The state of team `team_id` that update-tickets would move tickets to:
+/// the first one [`update_tickets::matches_target_state`] the
+/// `--target-state` and `--target-state-type` (by default, a Done/Completed
+/// state).
+///
+/// # Errors
+/// Returns an error if the workflow can't be fetched or no state matches.
+fn target_state(
+    config: &Config,
+    team_id: &str,
+    linear: &LinearApi,
+) -> Result<TargetState, Error> {
+    let response = update_tickets::get_workflow_states(team_id, linear)?;
+    utils::ensure_no_graphql_errors(&response)?;
+    let states = response.get("data.team.states.nodes").items();
+    states
+        .iter()
+        .find(|state| {
+            update_tickets::matches_target_state(
+                state.get("name").str_or_empty(),
+                state.get("type").str_or_empty(),
+                config.target_state.as_deref(),
+                config.target_state_type.as_deref(),
+            )
+        })
+        .map(|state| TargetState {
+            id: state.get("id").str_or_empty().to_string(),
+            name: state.get("name").str_or_empty().to_string(),
+        })
+        .filter(|state| !state.id.is_empty())
+        .ok_or_else(|| {
+            let available: Vec<&str> = states
+                .iter()
+                .map(|state| state.get("name").str_or_empty())
+                .collect();
+            Error::Other(format!(
+                "no state of the team matches (states: {})",
+                available.join(", ")
+            ))
+        })
+}
+
+/// Deletes an issue (Linear moves it to the trash).
+///
+/// # GraphQL Mutation
+/// ```graphql
+/// mutation($issueId: String!) {
+///   issueDelete(id: $issueId) { success }
+/// }
+/// ```
+///
+/// # Errors
+/// Returns an error if the request fails or the mutation returns
+/// `success: false`.
+fn delete_issue(issue_id: &str, linear: &LinearApi) -> Result<(), Error> {
+    let query = format!(
+        r#"{{"query": "mutation($issueId: String!) {{ issueDelete(id: $issueId) {{ success }} }}", "variables": {{"issueId": {}}}}}"#,
+        utils::json_string(issue_id)
+    );
+    let response = utils::graphql_request(&query, linear)?;
+    utils::ensure_no_graphql_errors(&response)?;
+    if response.get("data.issueDelete.success").as_bool() == Some(true) {
+        Ok(())
+    } else {
+        Err(Error::LinearApi(format!("Delete failed: {response}")))
+    }
+}
+
+/// The error of a failed `step`.
+fn failed(step: &str, error: Error) -> Error {
+    error.context(format!("selftest failed at {step}"))
+}
@@ -0,0 +1,594 @@
+//! Release webhook server (`serve`).
+//!
+//! Rather than a CI workflow per repository, the tool can run as a small
+//! always-on service: `serve` listens for GitHub `release` webhooks and runs
+//! the orchestrator for the tag of every release published:
+//!
+//! ```text
+//! POST / (X-GitHub-Event: release, "action": "published", "tag_name": "v1.2.3")
+//!     -> 202 queued release v1.2.3
+//!     -> orchestrator --release-tag v1.2.3 [FLAGS...]
+//! ```
+//!
+//! Every delivery must carry GitHub's `X-Hub-Signature-256: sha256=HEX`
+//! header, the HMAC-SHA256 of the body keyed with the webhook's secret
+//! (`--github-webhook-secret` or `GITHUB_WEBHOOK_SECRET`); the server doesn't
+//! start without one, and answers `401` to unsigned or wrongly signed
+//! requests. `ping` events are answered, and other events, other release
+//! actions and (with `--github-repo`) releases of other repositories are
+//! acknowledged and ignored.
+//!
+//! ## Runs
+//! Releases are queued and processed one at a time, in the order they were
+//! published, each by a run of the orchestrator with the flags `serve` was
+//! given (and the config file, read again for every run). A failed run is
+//! logged with the exit status it would have as a command, and doesn't stop
+//! the server. A release delivered twice (e.g. redelivered from the
+//! webhook's settings) is processed twice, which leaves the tickets the
+//! first run completed as they are.
+//!
+//! ## Connections
+//! The server speaks plain HTTP/1.1, one request per connection; put it
+//! behind a TLS-terminating proxy to expose it. Connections are answered on
+//! a thread each, up to [`MAX_CONNECTIONS`] at a time (more get `503`), so a
+//! slow client doesn't hold up the others. A request must be sent in full
+//! within [`REQUEST_DEADLINE`], with a request line of at most
+//! [`MAX_REQUEST_LINE_BYTES`] and at most [`MAX_HEADERS`] headers of
+//! [`MAX_HEADER_BYTES`] in all, before its signature is checked.
+
+use std::env;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::cache::ApiBudget;
+use crate::config::Config;
+use crate::error::Error;
+use crate::exit_status;
+use crate::json;
+use crate::utils;
+
+const NAME: &str = "serve";
+
+/// Address the server listens on without `--listen`.
+pub const DEFAULT_LISTEN: &str = "0.0.0.0:8080";
+
+/// Largest webhook body accepted, in bytes (5 MiB).
+///
+/// A release event is a few tens of KB, even with long release notes.
+const MAX_BODY_BYTES: usize = 5 * 1024 * 1024;
+
+/// How long a client may take to send its whole request.
+const REQUEST_DEADLINE: Duration = Duration::from_secs(10);
+
+/// Largest request line accepted, in bytes.
+const MAX_REQUEST_LINE_BYTES: usize = 8 * 1024;
+
+/// Largest size of a request's headers together, in bytes.
+///
+/// GitHub's deliveries send about 1 KB of headers.
+const MAX_HEADER_BYTES: usize = 64 * 1024;
+
+/// Most headers a request may have.
+const MAX_HEADERS: usize = 100;
+
+/// Most connections answered at a time.
+const MAX_CONNECTIONS: usize = 64;
+
+macro_rules! log {
+    ($level:ident, $fmt:expr) => {
+        utils::log_at(utils::LogLevel::$level, NAME, format_args!($fmt));
+    };
+    ($fmt:expr) => {
+        utils::log(NAME, format_args!($fmt));
+    };
+}
+
+/// The settings of `serve`.
+#[derive(Debug)]
+pub struct Server {
+    /// Address to listen on (`--listen`)
+    pub listen: String,
+    /// Secret the webhook deliveries are signed with
+    /// (`--github-webhook-secret`)
+    pub secret: Option<String>,
+    /// The orchestrator flags every release is processed with
+    pub args: Vec<String>,
+    /// Repository whose releases are processed (`--github-repo`), if given
+    pub repository: Option<String>,
+}
+
+impl Server {
+    /// The orchestrator configuration processing release `tag`.
+    ///
+    /// # Errors
+    /// Returns an error if the flags (or the config file) are invalid.
+    pub fn release_config(&self, tag: &str) -> Result<Config, Error> {
+        let mut args = vec!["--release-tag".to_string(), tag.to_string()];
+        args.extend(self.args.iter().cloned());
+        Config::from_arg_list(&args)
+    }
+
+    /// Gets the webhook secret from config or environment variable
+    /// (`--github-webhook-secret`, then `GITHUB_WEBHOOK_SECRET`).
+    ///
+    /// # Errors
+    /// Returns an error if neither is set.
+    fn secret(&self) -> Result<String, Error> {
+        self.secret
+            .clone()
+            .or_else(|| env::var("GITHUB_WEBHOOK_SECRET").ok())
+            .filter(|secret| !secret.is_empty())
+            .ok_or_else(|| {
+                exit_status::credentials(
+                    "serve requires a webhook secret via --github-webhook-secret or GITHUB_WEBHOOK_SECRET",
+                )
+            })
+    }
+}
+
+/// A webhook delivery.
+struct Request {
+    method: String,
+    /// Headers, with lowercase names
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    /// The value of header `name` (lowercase), if sent.
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Runs the webhook server of `config` until the process is stopped.
+///
+/// # Process
+/// 1. Listens on the `--listen` address
+/// 2. Answers each delivery on a thread of its own, queueing the tag of
+///    every published release
+/// 3. Processes the queued releases one at a time on a worker thread, each
+///    with a run of the orchestrator
+///
+/// # Errors
+/// Returns an error if no webhook secret is configured or the address
+/// can't be listened on.
+pub fn run(config: &Config) -> Result<(), Error> {
+    let Some(ref server) = config.server else {
+        return Err(Error::Config("serve requires its settings".to_string()));
+    };
+    let secret = server.secret()?;
+    let listener = TcpListener::bind(&server.listen).map_err(|e| {
+        Error::io(format!("Failed to listen on {}", server.listen), e)
+    })?;
+    let address = &server.listen;
+    log!("listening for GitHub release webhooks on {address}");
+
+    let (queue, releases) = mpsc::channel();
+    let connections = AtomicUsize::new(0);
+    let log_settings = utils::log_settings();
+    thread::scope(|scope| {
+        scope.spawn(move || process_releases(server, releases));
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    log!(Warn, "failed to accept a connection: {e}");
+                    continue;
+                }
+            };
+            let Some(slot) = Slot::take(&connections) else {
+                refuse(stream);
+                continue;
+            };
+            let (secret, queue) = (&secret, queue.clone());
+            scope.spawn(move || {
+                let _slot = slot;
+                utils::with_log_settings(log_settings, || {
+                    handle(stream, server, secret, &queue);
+                });
+            });
+        }
+    });
+    Ok(())
+}
+
+/// One of the [`MAX_CONNECTIONS`] connections answered at a time, given
+/// back when dropped.
+struct Slot<'a>(&'a AtomicUsize);
+
+impl<'a> Slot<'a> {
+    /// Takes a slot of `connections` (the number in use), if one is free.
+    fn take(connections: &'a AtomicUsize) -> Option<Self> {
+        connections
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |used| {
+                (used < MAX_CONNECTIONS).then_some(used + 1)
+            })
+            .ok()
+            .map(|_| Self(connections))
+    }
+}
+
+impl Drop for Slot<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Processes the releases sent to `releases`, one at a time, until the
+/// server stops, logging the exit status of each failed run.
+fn process_releases(server: &Server, releases: Receiver<String>) {
+    for tag in releases {
+        log!("processing release {tag}");
+        let config = match server.release_config(&tag) {
+            Ok(config) => config,
+            Err(e) => {
+                let status = exit_status::of(&e);
+                log!(
+                    Warn,
+                    "release {tag} failed with exit status {status}: {e}"
+                );
+                continue;
+            }
+        };
+        let result = crate::run(&config);
+        let exhausted = config
+            .api_budget
+            .as_ref()
+            .filter(|budget| budget.exhausted());
+        match (result, exhausted) {
+            (Ok(()), None) => {
+                log!("release {tag} processed");
+            }
+            (Ok(()), Some(budget)) => {
+                let limit = budget.limit();
+                let status = ApiBudget::EXHAUSTED_EXIT_CODE;
+                log!(
+                    Warn,
+                    "release {tag} stopped after the API budget of {limit} requests (--api-budget) with exit status {status}; the result is partial"
+                );
+            }
+            (Err(e), exhausted) => {
+                let status = if exhausted.is_some() {
+                    ApiBudget::EXHAUSTED_EXIT_CODE
+                } else {
+                    exit_status::of(&e)
+                };
+                log!(
+                    Warn,
+                    "release {tag} failed with exit status {status}: {e}"
+                );
+            }
+        }
+    }
+}
+
+/// Answers one connection, queueing its release if it is one to process.
+fn handle(
+    mut stream: TcpStream,
+    server: &Server,
+    secret: &str,
+    queue: &Sender<String>,
+) {
+    let peer = stream
+        .peer_addr()
+        .map_or_else(|_| "unknown peer".to_string(), |addr| addr.to_string());
+    let (status, message) = match read_request(&stream) {
+        Ok(request) => respond(&request, server, secret, queue),
+        Err(e) => (400, e.to_string()),
+    };
+    log!("{peer}: {status} {message}");
+    if let Err(e) = write_response(&mut stream, status, &message) {
+        log!(Warn, "{peer}: failed to send the response: {e}");
+    }
+}
+
+/// Answers a connection over [`MAX_CONNECTIONS`] with `503`, without
+/// reading its request.
+fn refuse(mut stream: TcpStream) {
+    let peer = stream
+        .peer_addr()
+        .map_or_else(|_| "unknown peer".to_string(), |addr| addr.to_string());
+    let message = format!("more than {MAX_CONNECTIONS} connections at a time");
+    log!(Warn, "{peer}: 503 {message}");
+    let result = stream
+        .set_write_timeout(Some(REQUEST_DEADLINE))
+        .and_then(|()| write_response(&mut stream, 503, &message));
+    if let Err(e) = result {
+        log!(Warn, "{peer}: failed to send the response: {e}");
+    }
+}
+
+/// Writes a plain-text response of `status` with `message` as its body.
+fn write_response(
+    stream: &mut TcpStream,
+    status: u16,
+    message: &str,
+) -> io::Result<()> {
+    let reason = match status {
+        200 => "OK",
+        202 => "Accepted",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        405 => "Method Not Allowed",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    };
+    let body = format!("{message}\n");
+    let response = format!(
+        "HTTP/1.1 {status} {reason}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes())
+}
+
+/// Decides what to do with a delivery.
+///
+/// # Returns
+/// The HTTP status and message to answer with.
+fn respond(
+    request: &Request,
+    server: &Server,
+    secret: &str,
+    queue: &Sender<String>,
+) -> (u16, String) {
+    if request.method != "POST" {
+        return (405, "webhooks must be POSTed".to_string());
+    }
+    match verify_signature(request, secret) {
+        Ok(true) => {}
+        Ok(false) => {
+            return (401, "invalid or missing X-Hub-Signature-256".to_string());
+        }
+        Err(e) => return (500, format!("failed to check the signature: {e}")),
+    }
+    let event = request.header("x-github-event").unwrap_or_default();
+    if event == "ping" {
+        return (200, "pong".to_string());
+    }
+    if event != "release" {
+        return (200, format!("ignored {event} event"));
+    }
+    let payload = match String::from_utf8(request.body.clone())
+        .map_err(|e| Error::Other(e.to_string()))
+        .and_then(|body| json::parse(&body))
+    {
+        Ok(payload) => payload,
+        Err(e) => return (400, format!("invalid payload: {e}")),
+    };
+    let action = payload.get("action").str_or_empty();
+    if action != "published" {
+        return (200, format!("ignored release {action} event"));
+    }
+    let repository = payload.get("repository.full_name").str_or_empty();
+    if let Some(ref expected) = server.repository
+        && !repository.eq_ignore_ascii_case(expected)
+    {
+        return (200, format!("ignored release of {repository}"));
+    }
+    let tag = payload.get("release.tag_name").str_or_empty();
+    if tag.is_empty() {
+        return (400, "release has no tag_name".to_string());
+    }
+    if queue.send(tag.to_string()).is_err() {
+        return (500, "release queue closed".to_string());
+    }
+    (202, format!("queued release {tag}"))
+}
+
+/// Checks the `X-Hub-Signature-256` header of a delivery against the
+/// HMAC-SHA256 of its body keyed with `secret`.
+///
+/// # Returns
+/// Whether the header is present and matches.
+///
+/// # Errors
+/// Returns an error if the HMAC can't be computed (see
+/// [`utils::hmac_sha256_hex`]).
+fn verify_signature(request: &Request, secret: &str) -> Result<bool, Error> {
+    let Some(signature) = request
+        .header("x-hub-signature-256")
+        .and_then(|value| value.strip_prefix("sha256="))
+    else {
+        return Ok(false);
+    };
+    let expected = utils::hmac_sha256_hex(secret.as_bytes(), &request.body)?;
+    let signature = signature.to_ascii_lowercase();
+    // Compared in full, so the time taken doesn't tell how much matched.
+    Ok(expected.len() == signature.len()
+        && expected
+            .bytes()
+            .zip(signature.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0)
+}
+
+/// A connection read until a deadline: every read waits at most until
+/// then, and fails with [`io::ErrorKind::TimedOut`] once it has passed.
+struct Deadline<'a> {
+    stream: &'a TcpStream,
+    until: Instant,
+}
+
+impl Read for Deadline<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let timed_out = || {
+            io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("request not sent within {REQUEST_DEADLINE:?}"),
+            )
+        };
+        let left = self.until.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return Err(timed_out());
+        }
+        self.stream.set_read_timeout(Some(left))?;
+        self.stream.read(buf).map_err(|e| match e.kind() {
+            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => timed_out(),
+            _ => e,
+        })
+    }
+}
+
+/// Reads a line of at most `limit` bytes (with its line ending).
+///
+/// # Returns
+/// The line, or `None` if it is longer.
+///
+/// # Errors
+/// Returns an error naming `what` if the line can't be read, or isn't
+/// UTF-8.
+fn read_line(
+    reader: &mut impl BufRead,
+    limit: usize,
+    what: &str,
+) -> Result<Option<String>, Error> {
+    let mut line = Vec::new();
+    reader
+        .take(limit as u64)
+        .read_until(b'\n', &mut line)
+        .map_err(|e| Error::io(format!("Failed to read the {what}"), e))?;
+    if line.len() == limit && !line.ends_with(b"\n") {
+        return Ok(None);
+    }
+    String::from_utf8(line)
+        .map(Some)
+        .map_err(|_| Error::Other(format!("{what} is not valid UTF-8")))
+}
+
+/// Reads a request: its request line, headers and `Content-Length` body.
+///
+/// # Errors
+/// Returns an error if the request is malformed, too large, or not sent
+/// within [`REQUEST_DEADLINE`].
+fn read_request(stream: &TcpStream) -> Result<Request, Error> {
+    stream
+        .set_write_timeout(Some(REQUEST_DEADLINE))
+        .map_err(|e| Error::io("Failed to set the write timeout", e))?;
+    let mut reader = BufReader::new(Deadline {
+        stream,
+        until: Instant::now() + REQUEST_DEADLINE,
+    });
+    let line = read_line(&mut reader, MAX_REQUEST_LINE_BYTES, "request line")?
+        .ok_or_else(|| {
+            Error::Other(format!(
+                "request line is longer than {MAX_REQUEST_LINE_BYTES} bytes"
+            ))
+        })?;
+    let method = line
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    if method.is_empty() {
+        return Err(Error::Other("empty request".to_string()));
+    }
+
+    let mut headers = Vec::new();
+    let mut header_bytes = 0;
+    loop {
+        let line = read_line(
+            &mut reader,
+            MAX_HEADER_BYTES - header_bytes,
+            "request headers",
+        )?
+        .ok_or_else(|| {
+            Error::Other(format!(
+                "request headers are larger than {MAX_HEADER_BYTES} bytes"
+            ))
+        })?;
+        header_bytes += line.len();
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if headers.len() == MAX_HEADERS {
+            return Err(Error::Other(format!(
+                "more than {MAX_HEADERS} headers"
+            )));
+        }
+        let (name, value) = header.split_once(':').ok_or_else(|| {
+            Error::Other(format!("malformed header: {header}"))
+        })?;
+        headers.push((name.trim().to_ascii_lowercase(), value.trim().into()));
+    }
+
+    let mut request = Request {
+        method,
+        headers,
+        body: Vec::new(),
+    };
+    let length = match request.header("content-length") {
+        Some(length) => length.parse::<usize>().map_err(|_| {
+            Error::Other(format!("invalid Content-Length: {length}"))
+        })?,
+        None => 0,
+    };
+    if length > MAX_BODY_BYTES {
+        return Err(Error::Other(format!(
+            "body of {length} bytes is larger than {MAX_BODY_BYTES}"
+        )));
+    }
+    request.body.resize(length, 0);
+    reader
+        .read_exact(&mut request.body)
+        .map_err(|e| Error::io("Failed to read the request body", e))?;
+    Ok(request)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The example delivery of GitHub's webhook validation docs.
+    const SECRET: &str = "It's a Secret to Everybody";
+    const BODY: &str = "Hello, World!";
+    const SIGNATURE: &str = "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17";
+
+    fn delivery(signature: Option<&str>, body: &str) -> Request {
+        Request {
+            method: "POST".to_string(),
+            headers: signature
+                .map(|signature| {
+                    ("x-hub-signature-256".to_string(), signature.to_string())
+                })
+                .into_iter()
+                .collect(),
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn accepts_valid_signature() -> Result<(), Error> {
+        assert!(verify_signature(&delivery(Some(SIGNATURE), BODY), SECRET)?);
+        let uppercase = format!("sha256={}", SIGNATURE[7..].to_uppercase());
+        assert!(verify_signature(&delivery(Some(&uppercase), BODY), SECRET)?);
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_invalid_signatures() -> Result<(), Error> {
+        let truncated = &SIGNATURE[..SIGNATURE.len() - 2];
+        let sha1 = SIGNATURE.replacen("sha256=", "sha1=", 1);
+        for (signature, body, secret) in [
+            (None, BODY, SECRET),
+            (Some(SIGNATURE), "Hello, World?", SECRET),
+            (Some(SIGNATURE), BODY, "another secret"),
+            (Some(truncated), BODY, SECRET),
+            (Some(&SIGNATURE[7..]), BODY, SECRET),
+            (Some(sha1.as_str()), BODY, SECRET),
+            (Some("sha256="), BODY, SECRET),
+        ] {
+            let request = delivery(signature, body);
+            assert!(!verify_signature(&request, secret)?, "{signature:?}");
+        }
+        Ok(())
+    }
+}
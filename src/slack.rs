@@ -0,0 +1,137 @@
+//! Slack summary of an update-tickets run (`--slack-webhook`).
+//!
+//! With `--slack-webhook URL` (or `SLACK_WEBHOOK_URL`), update-tickets posts
+//! one message to a Slack incoming webhook at the end of the run, so the
+//! team sees what a release changed without re-parsing the tool's output:
+//!
+//! ```text
+//! Linear tickets for v1.2.3
+//! Updated: 2 · Already completed: 1 · Skipped: 0 · Failed: 1
+//! Updated
+//! • ENG-1
+//! • ENG-2
+//! Failed
+//! • ENG-3: Entity not found
+//! ```
+//!
+//! Each ticket links to Linear. Lists longer than [`MAX_LISTED`] are cut
+//! short with a count of the rest. The message is posted once, without
+//! retries; a failed post is logged and doesn't fail the run. In dry-run, the
+//! message is only logged.
+
+use std::fmt::Write as _;
+
+use crate::error::Error;
+use crate::estimate::{self, Provider};
+use crate::utils;
+
+/// Most tickets listed per section of the message.
+pub const MAX_LISTED: usize = 50;
+
+/// A ticket of the summary, with its Linear URL.
+#[derive(Debug)]
+pub struct Ticket<'a> {
+    pub id: &'a str,
+    pub url: String,
+}
+
+/// Results of an update-tickets run, as posted to Slack.
+#[derive(Debug)]
+pub struct Summary<'a> {
+    /// Release the run was for (`--release-tag`), for the heading
+    pub release_tag: Option<&'a str>,
+    /// Tickets updated, in processing order
+    pub updated: Vec<Ticket<'a>>,
+    pub already_completed: usize,
+    pub skipped: usize,
+    /// Tickets that failed, with their errors, in processing order
+    pub failed: Vec<(Ticket<'a>, &'a str)>,
+}
+
+impl Summary<'_> {
+    /// Renders the message text in Slack's `mrkdwn` format.
+    pub fn text(&self) -> String {
+        let mut text = String::from("*Linear tickets");
+        if let Some(tag) = self.release_tag {
+            let _ = write!(text, " for {}", escape(tag));
+        }
+        text.push('*');
+        let _ = write!(
+            text,
+            "\nUpdated: {} · Already completed: {} · Skipped: {} · Failed: {}",
+            self.updated.len(),
+            self.already_completed,
+            self.skipped,
+            self.failed.len()
+        );
+        let updated_lines: Vec<String> =
+            self.updated.iter().map(Ticket::link).collect();
+        push_section(&mut text, "Updated", &updated_lines);
+        let failed_lines: Vec<String> = self
+            .failed
+            .iter()
+            .map(|(ticket, error)| {
+                format!("{}: {}", ticket.link(), escape(error))
+            })
+            .collect();
+        push_section(&mut text, "Failed", &failed_lines);
+        text
+    }
+
+    /// Posts the message to the incoming webhook `url`.
+    ///
+    /// # Errors
+    /// Returns an error if the request fails or Slack doesn't answer with a
+    /// 2xx status.
+    pub fn post(&self, url: &str) -> Result<(), Error> {
+        let body = format!("{{\"text\":{}}}", utils::json_string(&self.text()));
+        let headers = ["Content-Type: application/json".to_string()];
+        estimate::count(Provider::Slack);
+        let response = utils::http_request(
+            "POST",
+            url,
+            &headers,
+            &[],
+            Some(&body),
+            Error::Other,
+        )?;
+        if (200..300).contains(&response.status) {
+            Ok(())
+        } else {
+            let status = response.status;
+            let excerpt = utils::excerpt(response.body.trim());
+            Err(Error::Other(format!(
+                "Slack answered HTTP {status}: {excerpt}"
+            )))
+        }
+    }
+}
+
+impl Ticket<'_> {
+    /// The ticket ID linked to its URL (`<URL|ENG-1>`).
+    fn link(&self) -> String {
+        format!("<{}|{}>", self.url, escape(self.id))
+    }
+}
+
+/// Appends a `heading` section listing `lines` (at most [`MAX_LISTED`]),
+/// unless there are none.
+fn push_section(text: &mut String, heading: &str, lines: &[String]) {
+    if lines.is_empty() {
+        return;
+    }
+    let _ = write!(text, "\n*{heading}*");
+    for line in lines.iter().take(MAX_LISTED) {
+        let _ = write!(text, "\n• {line}");
+    }
+    if lines.len() > MAX_LISTED {
+        let _ = write!(text, "\n… and {} more", lines.len() - MAX_LISTED);
+    }
+}
+
+/// Escapes the characters Slack's `mrkdwn` reserves (`&`, `<`, `>`).
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,118 @@
+//! Offline Linear workspace snapshot (`--linear-snapshot`).
+//!
+//! A snapshot is a JSON export of a workspace's issues and teams. Dry runs of
+//! update-tickets and the teams report can read it instead of querying the
+//! Linear API, so large previews don't consume API quota (and work without
+//! network access).
+//!
+//! ## File Format
+//! ```json
+//! {
+//!   "issues": [
+//!     {
+//!       "id": "…", "identifier": "ENG-1", "title": "…", "archivedAt": null,
+//!       "previousIdentifiers": ["OLD-1"],
+//!       "team": { "id": "…", "key": "ENG", "name": "Engineering" },
+//!       "state": { "id": "…", "name": "Passing", "type": "started" },
+//!       "assignee": { "email": "…" }, "creator": { "email": "…" },
+//!       "labels": { "nodes": [{ "name": "…" }] }
+//!     }
+//!   ],
+//!   "teams": [
+//!     {
+//!       "key": "ENG", "name": "Engineering",
+//!       "states": { "nodes": [{ "name": "Done", "type": "completed" }] }
+//!     }
+//!   ]
+//! }
+//! ```
+//! Issues and teams have the shape of Linear's GraphQL `Issue` and `Team`
+//! nodes with the fields update-tickets queries, so a snapshot can be
+//! exported with the same GraphQL selections. Issues may also carry the
+//! `relations`/`inverseRelations` (see [`crate::relations::GRAPHQL_FIELDS`])
+//! and `comments` used by `--order-by-dependencies` and `--comment-release`;
+//! when they're missing, the ticket is treated as having none.
+
+use std::collections::HashMap;
+
+use crate::error::Error;
+use crate::json::{self, Value};
+
+/// A loaded workspace snapshot.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    path: String,
+    /// Issue nodes by current and previous identifier
+    issues: HashMap<String, Value>,
+    /// The `teams` array
+    teams: Value,
+}
+
+impl Snapshot {
+    /// Reads and indexes a snapshot file.
+    ///
+    /// # Errors
+    /// Returns an error if the file can't be read or isn't a snapshot.
+    pub fn load(path: &str) -> Result<Self, Error> {
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            Error::io(format!("Failed to read snapshot {path}"), e)
+        })?;
+        let snapshot = json::parse(&contents)
+            .map_err(|e| e.context(format!("Invalid snapshot {path}")))?;
+        let is_list = |field| {
+            matches!(snapshot.get(field), Value::Array(_) | Value::Null)
+        };
+        if !matches!(snapshot, Value::Object(_))
+            || !is_list("issues")
+            || !is_list("teams")
+        {
+            return Err(Error::Other(format!(
+                "Invalid snapshot {path}: expected an object with issues and teams arrays"
+            )));
+        }
+
+        // Previous identifiers first, so that an identifier that is some
+        // issue's current one wins, like it does with the live API.
+        let mut issues = HashMap::new();
+        let nodes = snapshot.get("issues").items();
+        for issue in nodes {
+            for previous in issue.get("previousIdentifiers").items() {
+                if let Some(previous) = previous.as_str() {
+                    issues.insert(previous.to_string(), issue.clone());
+                }
+            }
+        }
+        for issue in nodes {
+            let identifier = issue.get("identifier").str_or_empty();
+            issues.insert(identifier.to_string(), issue.clone());
+        }
+        let teams = match snapshot.get("teams") {
+            Value::Null => Value::Array(Vec::new()),
+            teams => teams.clone(),
+        };
+
+        Ok(Self {
+            path: path.to_string(),
+            issues,
+            teams,
+        })
+    }
+
+    /// Path the snapshot was loaded from.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The issue whose current or previous identifier is `issue_id`, shaped
+    /// like an `issue(id:)` response (`{"data": {"issue": ...}}`).
+    pub fn issue_response(&self, issue_id: &str) -> Option<Value> {
+        let issue = self.issues.get(issue_id)?;
+        Some(json::nest("data.issue", issue.clone()))
+    }
+
+    /// The teams, shaped like a `teams` query response
+    /// (`{"data": {"teams": {"nodes": [...]}}}`).
+    pub fn teams_response(&self) -> Value {
+        json::nest("data.teams.nodes", self.teams.clone())
+    }
+}
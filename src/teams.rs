@@ -0,0 +1,219 @@
+//! List Linear teams and their workflow states.
+//!
+//! This module implements the `teams` mode, a read-only report for checking
+//! how update-tickets would treat each team before running it on a real
+//! release.
+//!
+//! ## Output
+//! For every team, its key and name followed by its workflow states in
+//! Linear's order. Each state is marked with:
+//! - `completed` - the state update-tickets would move tickets to (`target`
+//!   with `--target-state` or `--target-state-type`, given as they would be
+//!   to update-tickets)
+//! - `eligible` - states update-tickets would move tickets out of ("Passing"
+//!   or the `--from-states`, or every open state with `--update-all-statuses`)
+//!
+//! Teams with no matching completed (or target) state are flagged, since
+//! updates for their tickets would fail.
+
+use std::fmt::Write as _;
+
+use crate::config::Config;
+use crate::error::Error;
+use crate::json::Value;
+use crate::update_tickets;
+use crate::utils::{self, LinearApi};
+
+const NAME: &str = "teams";
+
+macro_rules! log {
+    ($fmt:expr) => {
+        utils::log(NAME, format_args!($fmt));
+    };
+}
+
+/// A workflow state of a team.
+struct WorkflowState {
+    name: String,
+    state_type: String,
+    /// Whether update-tickets would move tickets to this state, if it's the
+    /// team's first such state (see [`update_tickets::matches_target_state`])
+    matches_target: bool,
+    /// Whether update-tickets would move tickets out of this state
+    eligible: bool,
+}
+
+/// A team and its workflow states, in Linear's order.
+struct Team {
+    key: String,
+    name: String,
+    states: Vec<WorkflowState>,
+}
+
+/// Runs the teams mode, printing the report to stdout.
+///
+/// # Errors
+/// Returns an error if:
+/// - `LINEAR_API_KEY` is not provided
+/// - The snapshot can't be read
+/// - The Linear API request fails or returns GraphQL errors
+pub fn run(config: &Config) -> Result<(), Error> {
+    let linear = config.linear_api()?;
+
+    let response = if let Some(snapshot) = config.snapshot()? {
+        let path = snapshot.path();
+        log!("reading teams from snapshot {path}");
+        snapshot.teams_response()
+    } else {
+        log!("fetching teams");
+        fetch_teams_response(&linear)?
+    };
+    let teams = parse_teams(&response, config);
+    let count = teams.len();
+    log!("found {count} teams");
+
+    let target = match (&config.target_state, &config.target_state_type) {
+        (None, None) => "completed",
+        _ => "target",
+    };
+    print!("{}", render(&teams, target));
+    Ok(())
+}
+
+/// Fetches every team with its workflow states.
+///
+/// # Returns
+/// The raw `teams` response, parsed with [`parse_teams`].
+///
+/// # GraphQL Query
+/// ```graphql
+/// query {
+///   teams(first: 250) {
+///     nodes { key name states { nodes { name type } } }
+///   }
+/// }
+/// ```
+fn fetch_teams_response(linear: &LinearApi) -> Result<Value, Error> {
+    let query = r#"{"query": "query { teams(first: 250) { nodes { key name states(first: 250) { nodes { name type } } } } }"}"#;
+    let response = utils::graphql_request(query, linear)?;
+    utils::ensure_no_graphql_errors(&response)?;
+    Ok(response)
+}
+
+/// Keys of every team of the workspace (or the snapshot, with
+/// `--linear-snapshot`).
+///
+/// # GraphQL Query
+/// ```graphql
+/// query {
+///   teams(first: 250) { nodes { key } }
+/// }
+/// ```
+///
+/// # Errors
+/// Returns an error if the snapshot can't be read, or the Linear API request
+/// fails or returns GraphQL errors.
+pub fn team_keys(
+    config: &Config,
+    linear: &LinearApi,
+) -> Result<Vec<String>, Error> {
+    let response = if let Some(snapshot) = config.snapshot()? {
+        snapshot.teams_response()
+    } else {
+        let query =
+            r#"{"query": "query { teams(first: 250) { nodes { key } } }"}"#;
+        let response = utils::graphql_request(query, linear)?;
+        utils::ensure_no_graphql_errors(&response)?;
+        response
+    };
+    Ok(response
+        .get("data.teams.nodes")
+        .items()
+        .iter()
+        .map(|team| team.get("key").str_or_empty().to_string())
+        .filter(|key| !key.is_empty())
+        .collect())
+}
+
+/// Parses a `teams` response into teams with their workflow states, matched
+/// against the target and eligible states of `config`.
+fn parse_teams(response: &Value, config: &Config) -> Vec<Team> {
+    response
+        .get("data.teams.nodes")
+        .items()
+        .iter()
+        .map(|team| Team {
+            key: team.get("key").str_or_empty().to_string(),
+            name: team.get("name").str_or_empty().to_string(),
+            states: team
+                .get("states.nodes")
+                .items()
+                .iter()
+                .map(|state| {
+                    let name = state.get("name").str_or_empty();
+                    let state_type = state.get("type").str_or_empty();
+                    WorkflowState {
+                        name: name.to_string(),
+                        state_type: state_type.to_string(),
+                        matches_target: update_tickets::matches_target_state(
+                            name,
+                            state_type,
+                            config.target_state.as_deref(),
+                            config.target_state_type.as_deref(),
+                        ),
+                        eligible: is_eligible(name, config),
+                    }
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// Renders the report, marking the target state (as `target`, e.g.
+/// "completed") and the eligible states.
+fn render(teams: &[Team], target: &str) -> String {
+    let mut out = String::new();
+    for team in teams {
+        let _ = writeln!(out, "{} ({})", team.key, team.name);
+
+        let width = team
+            .states
+            .iter()
+            .map(|state| state.name.len())
+            .max()
+            .unwrap_or(0);
+        // update-tickets picks the first matching state.
+        let completed = team.states.iter().position(|s| s.matches_target);
+        for (index, state) in team.states.iter().enumerate() {
+            let mark = if completed == Some(index) {
+                format!("  <- {target}")
+            } else if state.eligible {
+                "  <- eligible".to_string()
+            } else {
+                String::new()
+            };
+            let line = format!(
+                "  {:<width$}  {:<9}{mark}",
+                state.name, state.state_type
+            );
+            let _ = writeln!(out, "{}", line.trim_end());
+        }
+
+        if completed.is_none() {
+            let _ = writeln!(
+                out,
+                "  (no {target} state matches; updates for this team would fail)"
+            );
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Whether update-tickets would move a ticket out of the state `name`.
+fn is_eligible(name: &str, config: &Config) -> bool {
+    if update_tickets::state_is_done_or_completed(name) {
+        return false;
+    }
+    config.update_all_statuses || config.is_source_state(name)
+}
@@ -0,0 +1,478 @@
+//! Per-run Linear ticket metadata store.
+//!
+//! Every feature that needs a ticket's metadata (filtering, planning,
+//! dependency ordering, reporting, comments) reads it from a [`TicketStore`],
+//! which queries Linear once per ticket and keeps the result for the rest of
+//! the run.
+//!
+//! ## Lookup
+//! Tickets are looked up with `issue(id:)`. If Linear doesn't resolve the
+//! identifier (e.g. an older ticket whose team key was renamed), the store
+//! falls back to `searchIssues` and accepts the issue whose current or
+//! previous identifier matches exactly.
+//!
+//! If the Linear API has no `searchIssues` (see [`crate::capabilities`]),
+//! there is no fallback and such tickets fail.
+//!
+//! With a workspace [`Snapshot`] (`--linear-snapshot`), tickets are read from
+//! the snapshot instead, and Linear is never queried.
+//!
+//! ## Batched Lookup
+//! With `--batch-lookups`, [`TicketStore::prefetch`] fetches many tickets in
+//! one query, as aliased `issue(id:)` fields (`t0: issue(id: $i0) { ... }`),
+//! [`BATCH_SIZE`] at a time. Linear fails the whole query if any identifier
+//! doesn't resolve, so a failed batch is split in half and retried; tickets
+//! that still fail on their own are left to the regular lookup (and its
+//! search fallback).
+
+use std::collections::HashMap;
+
+use crate::error::Error;
+use crate::json::{self, Value};
+use crate::relations::{self, Relations};
+use crate::snapshot::Snapshot;
+use crate::utils::{self, LinearApi};
+
+const NAME: &str = "update-tickets";
+
+/// Start of the error of a ticket that doesn't exist, which the end-of-run
+/// summary counts apart from other failures.
+pub const NOT_FOUND: &str = "Issue not found";
+
+/// Issue fields fetched for every ticket.
+const ISSUE_FIELDS: &str = "id identifier title archivedAt team { id key name } state { id name type } assignee { email } creator { email } labels { nodes { name } }";
+
+/// Comment fields fetched when the store looks for release markers.
+const COMMENT_FIELDS: &str = "comments(first: 100) { nodes { body } }";
+
+/// Tickets fetched per batched query (`--batch-lookups`). Small enough to
+/// stay well within Linear's query complexity limit with comments and
+/// relations included.
+const BATCH_SIZE: usize = 25;
+
+macro_rules! log {
+    ($level:ident, $fmt:expr) => {
+        utils::log_at(utils::LogLevel::$level, NAME, format_args!($fmt));
+    };
+    ($fmt:expr) => {
+        utils::log(NAME, format_args!($fmt));
+    };
+}
+
+/// Metadata of a single Linear ticket.
+#[derive(Debug, Clone)]
+pub struct Ticket {
+    /// Linear's UUID for the issue
+    pub id: String,
+    /// Current identifier (differs from the looked-up ID after a rename)
+    pub identifier: String,
+    pub title: String,
+    pub team_id: String,
+    pub team_key: String,
+    pub team_name: String,
+    /// Workflow state ID
+    pub state_id: String,
+    /// Workflow state name (e.g. `Passing`)
+    pub state_name: String,
+    /// Workflow state type (e.g. `started`, `completed`)
+    pub state_type: String,
+    /// Assignee email (empty if unassigned)
+    pub assignee: String,
+    /// Creator email (empty for tickets created by an integration)
+    pub creator: String,
+    pub labels: Vec<String>,
+    /// Whether the issue is archived (`archivedAt` is set)
+    pub archived: bool,
+    /// Blocked-by relations, if the store fetches them
+    pub relations: Option<Relations>,
+    /// Comment bodies, if the store fetches them (empty otherwise)
+    pub comments: Vec<String>,
+}
+
+/// Caches ticket metadata for the duration of a run.
+#[derive(Debug, Default)]
+pub struct TicketStore {
+    /// Whether to fetch blocked-by relations along with the metadata
+    with_relations: bool,
+    /// Whether to fetch comment bodies along with the metadata
+    with_comments: bool,
+    /// Snapshot to read tickets from instead of querying Linear
+    snapshot: Option<Snapshot>,
+    /// Whether the issue search fallback is unavailable
+    no_search: bool,
+    /// Tickets by the ID they were looked up with
+    tickets: HashMap<String, Ticket>,
+}
+
+impl TicketStore {
+    /// Creates an empty store. With `with_relations` / `with_comments`, every
+    /// lookup also fetches the ticket's blocked-by relations / comments in the
+    /// same query. With a `snapshot`, tickets are read from it instead.
+    pub fn new(
+        with_relations: bool,
+        with_comments: bool,
+        snapshot: Option<Snapshot>,
+    ) -> Self {
+        Self {
+            with_relations,
+            with_comments,
+            snapshot,
+            no_search: false,
+            tickets: HashMap::new(),
+        }
+    }
+
+    /// Turns off the issue search fallback, for Linear APIs without
+    /// `searchIssues`.
+    pub fn disable_search(&mut self) {
+        self.no_search = true;
+    }
+
+    /// Returns the metadata of `issue_id`, querying Linear on first use.
+    ///
+    /// # Errors
+    /// Returns an error if the query fails or no issue matches.
+    pub fn get(
+        &mut self,
+        issue_id: &str,
+        linear: &LinearApi,
+    ) -> Result<&Ticket, Error> {
+        if !self.tickets.contains_key(issue_id) {
+            let ticket = self.fetch(issue_id, linear)?;
+            if ticket.identifier != issue_id {
+                let identifier = &ticket.identifier;
+                log!(Verbose, "Issue {issue_id} is now {identifier}");
+            }
+            self.tickets.insert(issue_id.to_string(), ticket);
+        }
+        self.tickets.get(issue_id).ok_or_else(|| {
+            Error::Other(format!("Issue {issue_id} missing from store"))
+        })
+    }
+
+    /// A store fetching the same fields from the same source, starting with
+    /// the tickets fetched so far (e.g. by [`TicketStore::prefetch`]), for a
+    /// worker thread.
+    pub fn fork(&self) -> Self {
+        Self {
+            with_relations: self.with_relations,
+            with_comments: self.with_comments,
+            snapshot: self.snapshot.clone(),
+            no_search: self.no_search,
+            tickets: self.tickets.clone(),
+        }
+    }
+
+    /// Fetches the metadata of every ticket not yet in the store with batched
+    /// queries (see the module docs). Snapshot stores read tickets locally
+    /// and skip this.
+    ///
+    /// Failures are logged rather than returned: tickets that couldn't be
+    /// prefetched are looked up one by one on first use.
+    pub fn prefetch(&mut self, issue_ids: &[String], linear: &LinearApi) {
+        if self.snapshot.is_some() {
+            return;
+        }
+        let mut missing: Vec<&str> = Vec::new();
+        for issue_id in issue_ids {
+            if !self.tickets.contains_key(issue_id)
+                && !missing.contains(&issue_id.as_str())
+            {
+                missing.push(issue_id);
+            }
+        }
+        if missing.is_empty() {
+            return;
+        }
+
+        let total = missing.len();
+        let mut queries = 0;
+        for batch in missing.chunks(BATCH_SIZE) {
+            self.fetch_batch(batch, linear, &mut queries);
+        }
+        let fetched = missing
+            .iter()
+            .filter(|issue_id| self.tickets.contains_key(**issue_id))
+            .count();
+        log!(
+            Verbose,
+            "fetched {fetched} of {total} tickets in {queries} batched queries"
+        );
+    }
+
+    /// Fetches `batch` in one query, splitting it in half on failure.
+    fn fetch_batch(
+        &mut self,
+        batch: &[&str],
+        linear: &LinearApi,
+        queries: &mut usize,
+    ) {
+        *queries += 1;
+        let result = self.query_batch(batch, linear);
+        match result {
+            Ok(tickets) => {
+                for (issue_id, ticket) in batch.iter().zip(tickets) {
+                    if ticket.identifier != *issue_id {
+                        let identifier = &ticket.identifier;
+                        log!(Verbose, "Issue {issue_id} is now {identifier}");
+                    }
+                    self.tickets.insert((*issue_id).to_string(), ticket);
+                }
+            }
+            Err(_) if batch.len() > 1 => {
+                let (first, second) = batch.split_at(batch.len() / 2);
+                self.fetch_batch(first, linear, queries);
+                self.fetch_batch(second, linear, queries);
+            }
+            Err(e) => {
+                let issue_id = batch[0];
+                log!(
+                    Warn,
+                    "Batched lookup of {issue_id} failed, will retry alone: {e}"
+                );
+            }
+        }
+    }
+
+    /// Queries `batch` as aliased `issue(id:)` fields.
+    ///
+    /// # GraphQL Query
+    /// ```graphql
+    /// query($i0: String!, $i1: String!) {
+    ///   t0: issue(id: $i0) { <same fields as issue(id:)> }
+    ///   t1: issue(id: $i1) { ... }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the query fails, reports errors, or lacks any of
+    /// the issues.
+    fn query_batch(
+        &self,
+        batch: &[&str],
+        linear: &LinearApi,
+    ) -> Result<Vec<Ticket>, Error> {
+        let fields = self.fields();
+        let mut params = Vec::new();
+        let mut selections = Vec::new();
+        let mut variables = Vec::new();
+        for (idx, issue_id) in batch.iter().enumerate() {
+            params.push(format!("$i{idx}: String!"));
+            selections
+                .push(format!("t{idx}: issue(id: $i{idx}) {{ {fields} }}"));
+            variables
+                .push(format!("\"i{idx}\": {}", utils::json_string(issue_id)));
+        }
+        let query = format!(
+            r#"{{"query": "query({}) {{ {} }}", "variables": {{{}}}}}"#,
+            params.join(", "),
+            selections.join(" "),
+            variables.join(", ")
+        );
+        let response = utils::graphql_request(&query, linear)?;
+        utils::ensure_no_graphql_errors(&response)?;
+        (0..batch.len())
+            .map(|idx| {
+                let issue = response.get(&format!("data.t{idx}"));
+                self.parse(&json::nest("data.issue", issue.clone()))
+            })
+            .collect()
+    }
+
+    /// Adds metadata fetched by another store (see [`TicketStore::fork`]).
+    pub fn insert(&mut self, issue_id: &str, ticket: Ticket) {
+        self.tickets.insert(issue_id.to_string(), ticket);
+    }
+
+    /// Every ticket fetched so far, with the ID it was looked up with.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Ticket)> {
+        self.tickets
+            .iter()
+            .map(|(issue_id, ticket)| (issue_id.as_str(), ticket))
+    }
+
+    /// Returns already-fetched metadata without querying Linear.
+    pub fn cached(&self, issue_id: &str) -> Option<&Ticket> {
+        self.tickets.get(issue_id)
+    }
+
+    fn fields(&self) -> String {
+        let mut fields = ISSUE_FIELDS.to_string();
+        if self.with_relations {
+            fields.push(' ');
+            fields.push_str(relations::GRAPHQL_FIELDS);
+        }
+        if self.with_comments {
+            fields.push(' ');
+            fields.push_str(COMMENT_FIELDS);
+        }
+        fields
+    }
+
+    /// Queries a ticket by identifier, falling back to issue search (or reads
+    /// it from the snapshot).
+    ///
+    /// # GraphQL Query
+    /// ```graphql
+    /// query($issueId: String!) {
+    ///   issue(id: $issueId) {
+    ///     id
+    ///     identifier
+    ///     title
+    ///     archivedAt
+    ///     team { id key name }
+    ///     state { id name type }
+    ///     assignee { email }
+    ///     creator { email }
+    ///     labels { nodes { name } }
+    ///     # with relations: see relations::GRAPHQL_FIELDS
+    ///     # with comments: comments(first: 100) { nodes { body } }
+    ///   }
+    /// }
+    /// ```
+    fn fetch(
+        &self,
+        issue_id: &str,
+        linear: &LinearApi,
+    ) -> Result<Ticket, Error> {
+        if let Some(ref snapshot) = self.snapshot {
+            let response =
+                snapshot.issue_response(issue_id).ok_or_else(|| {
+                    Error::LinearApi(format!(
+                        "{NOT_FOUND} in snapshot {}",
+                        snapshot.path()
+                    ))
+                })?;
+            return self.parse(&response);
+        }
+
+        let fields = self.fields();
+        let query = format!(
+            r#"{{"query": "query($issueId: String!) {{ issue(id: $issueId) {{ {fields} }} }}", "variables": {{"issueId": {}}}}}"#,
+            utils::json_string(issue_id)
+        );
+        let mut response = utils::graphql_request(&query, linear)?;
+        if issue_not_found(&response) {
+            if self.no_search {
+                return Err(Error::LinearApi(format!(
+                    "{NOT_FOUND} (issue search is unavailable)"
+                )));
+            }
+            log!(
+                Verbose,
+                "Issue {issue_id} not found by identifier, searching"
+            );
+            response = self
+                .search(issue_id, linear)?
+                .ok_or_else(|| Error::LinearApi(NOT_FOUND.to_string()))?;
+        }
+        utils::ensure_no_graphql_errors(&response)?;
+        self.parse(&response)
+    }
+
+    /// Searches for an issue whose current or previous identifier is
+    /// `issue_id`.
+    ///
+    /// # Returns
+    /// The match reshaped like an `issue(id:)` response
+    /// (`{"data": {"issue": ...}}`), or `None` if no issue matches exactly.
+    ///
+    /// # GraphQL Query
+    /// ```graphql
+    /// query($term: String!) {
+    ///   searchIssues(term: $term, first: 10) {
+    ///     nodes { previousIdentifiers <same fields as issue(id:)> }
+    ///   }
+    /// }
+    /// ```
+    fn search(
+        &self,
+        issue_id: &str,
+        linear: &LinearApi,
+    ) -> Result<Option<Value>, Error> {
+        let fields = self.fields();
+        let query = format!(
+            r#"{{"query": "query($term: String!) {{ searchIssues(term: $term, first: 10) {{ nodes {{ previousIdentifiers {fields} }} }} }}", "variables": {{"term": {}}}}}"#,
+            utils::json_string(issue_id)
+        );
+        let response = utils::graphql_request(&query, linear)?;
+        utils::ensure_no_graphql_errors(&response)?;
+
+        // Search is fuzzy; only accept an exact (current or previous) match.
+        let issue =
+            response.get("data.searchIssues.nodes").items().iter().find(
+                |node| {
+                    node.get("identifier").as_str() == Some(issue_id)
+                        || node
+                            .get("previousIdentifiers")
+                            .items()
+                            .iter()
+                            .any(|previous| previous.as_str() == Some(issue_id))
+                },
+            );
+        Ok(issue.map(|issue| json::nest("data.issue", issue.clone())))
+    }
+
+    /// Parses an `issue(id:)` response into a [`Ticket`].
+    fn parse(&self, response: &Value) -> Result<Ticket, Error> {
+        let issue = response.get("data.issue");
+        if !matches!(issue, Value::Object(_)) {
+            return Err(Error::LinearApi(
+                "Unexpected issue response".to_string(),
+            ));
+        }
+        let field = |path| issue.get(path).str_or_empty().to_string();
+
+        let labels = issue
+            .get("labels.nodes")
+            .items()
+            .iter()
+            .filter_map(|label| label.get("name").as_str())
+            .map(String::from)
+            .collect();
+
+        let relations = self.with_relations.then(|| relations::parse(response));
+
+        let comments = if self.with_comments {
+            issue
+                .get("comments.nodes")
+                .items()
+                .iter()
+                .filter_map(|comment| comment.get("body").as_str())
+                .map(String::from)
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        Ok(Ticket {
+            id: field("id"),
+            identifier: field("identifier"),
+            title: field("title"),
+            team_id: field("team.id"),
+            team_key: field("team.key"),
+            team_name: field("team.name"),
+            state_id: field("state.id"),
+            state_name: field("state.name"),
+            state_type: field("state.type"),
+            assignee: field("assignee.email"),
+            creator: field("creator.email"),
+            labels,
+            archived: !issue.get("archivedAt").is_null(),
+            relations,
+            comments,
+        })
+    }
+}
+
+/// Whether an issue response means the identifier didn't resolve.
+///
+/// Linear reports an unknown identifier either as a `null` issue or as an
+/// "Entity not found" GraphQL error.
+fn issue_not_found(response: &Value) -> bool {
+    response.get("data.issue").is_null()
+        && response
+            .error_messages()
+            .iter()
+            .all(|message| message.to_lowercase().contains("not found"))
+}
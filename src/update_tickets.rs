@@ -6,29 +6,639 @@
 //!
 //! ## Process
 //! For each ticket ID:
-//! 1. Query current state from Linear API
-//! 2. Skip if already completed (Done/Completed)
-//! 3. Skip unless current state name is "Passing"
-//! 4. Find the team's completed state ID
-//! 5. Update ticket to completed state (unless dry-run)
+//! 1. Query the ticket's metadata from Linear API (falling back to issue search
+//!    for identifiers Linear no longer resolves directly, e.g. after a team key
+//!    rename). Metadata is fetched once per run and shared by filtering,
+//!    planning and dependency ordering (see [`crate::tickets`]).
+//! 2. Skip if it's archived, or doesn't match the `--filter` expression (if
+//!    any)
+//! 3. Skip if already completed (Done/Completed)
+//! 4. Skip unless current state name is "Passing" (or one of the
+//!    `--from-states`)
+//! 5. Find the team's completed state ID
+//! 6. Update ticket to completed state (unless dry-run)
+//!
+//! Ticket IDs are remapped with `--prefix-map OLD=NEW` as they are read, so
+//! references to a renamed team key are updated (and printed) under the new
+//! key. Tickets whose (remapped) ID matches the `--exclude-pattern` extended
+//! regex in full are dropped from the input, and never looked up; they are
+//! counted as skipped at the end of the run.
+//!
+//! The workspace's team keys are listed once at the start, and a warning is
+//! logged for each input team key that isn't one of them: such IDs are
+//! usually false positives of the ticket pattern, like `SHA-256` or
+//! `ISO-8601`. With `--drop-unknown-teams`, their tickets are dropped (and
+//! counted as skipped) like excluded ones.
+//!
+//! With `--follow-duplicates canonical|both`, the relations of every input
+//! ticket are looked up as it is read, and a ticket marked as a duplicate of
+//! another issue is replaced by that issue (`canonical`), or followed by it
+//! (`both`), so the issue the work is tracked in gets closed. Chains of
+//! duplicates are followed to their end, and each followed issue is
+//! processed once per run. A failed lookup is logged and the input ticket
+//! processed as is.
+//!
+//! With `--output-format ndjson|json`, the result record of a skipped ticket
+//! says why in its `skip_reason` (see [`SkipReason`]).
+//!
+//! ## Dependency Ordering
+//! With `--order-by-dependencies`, blockers in the same release are updated
+//! before the tickets they block, and tickets completed while a blocker is
+//! still open are reported. Relations are fetched in the same query as the
+//! rest of the ticket's metadata. `--note-blocked` additionally comments on blocked
+//! tickets when their blocker is completed.
+//!
+//! ## Release Comments
+//! With `--comment-release`, each completed ticket gets a comment naming the
+//! release (`--release-tag`) with a hidden marker. Tickets that already carry
+//! the marker are skipped before anything else happens to them, so re-running
+//! the orchestrator for the same tag neither queries workflow states nor
+//! prints those tickets again. `--comment-template FILE` sets the comment
+//! text, with the release URL and the PRs the input records name for the
+//! ticket (see [`CommentTemplate`]).
+//!
+//! ## Release Issue
+//! With `--release-issue-team KEY`, a `Release <TAG>` issue listing every
+//! completed ticket is created in team `KEY` at the end of the run (see
+//! [`crate::release_issue`]).
+//!
+//! ## Project Releases
+//! With `--linear-project PROJECT`, the release is modelled as a Linear
+//! project: only tickets of the project are completed, and a ticket is
+//! eligible when its state is of the `started` type rather than named
+//! "Passing". The project's completion is logged before and after the run
+//! (see [`crate::project`]).
+//!
+//! ## Grouping in Linear
+//! With `--add-to-project PROJECT` and/or `--linear-cycle CYCLE`, every
+//! ticket the run moves is also added to that project, and to its team's
+//! cycle (see [`crate::cycle`]), with one more `issueUpdate`; dry runs log
+//! what would be added. A failure is logged without failing the ticket.
+//!
+//! ## Release Labels
+//! With `--add-label LABEL`, every ticket the run moves also gets the
+//! workspace label `LABEL` (e.g. `released:v1.2.3`), which is created before
+//! the first ticket if it doesn't exist (see [`crate::labels`]). Tickets
+//! already carrying it are left alone, and a failure is logged without
+//! failing the ticket.
+//!
+//! With `--set-custom-field NAME=VALUE`, the label `VALUE` of the workspace
+//! label group `NAME` (Linear's stand-in for a custom field, e.g. a
+//! "Release" group) is set in the same mutation that moves each ticket,
+//! replacing the group's other labels on it (see [`crate::labels`]).
+//!
+//! ## Release Attachments
+//! With `--attach-release`, every ticket the run moves also gets the GitHub
+//! release of `--release-tag` as a Linear attachment, and tickets the release
+//! is already attached to are skipped like those carrying the release marker
+//! (see [`crate::attachments`]). Attachment failures are logged without
+//! failing the ticket.
+//!
+//! ## Webhooks
+//! With `--webhook-url URL`, every ticket the run moves is also announced to
+//! `URL` with a `ticket.released` JSON event, signed with `--webhook-secret`
+//! if given (see [`crate::webhook`]). Dry runs log the events instead, and
+//! failed deliveries are logged without failing the ticket.
+//!
+//! With `--slack-webhook URL` (or `SLACK_WEBHOOK_URL`), a summary of the run
+//! (its counts, and the updated and failed tickets) is posted to a Slack
+//! incoming webhook at the end (see [`crate::slack`]).
+//!
+//! ## Target State
+//! Tickets are moved to the team's first state named like
+//! [`COMPLETED_STATE_NAMES`]. `--target-state NAME` moves them to the state
+//! named `NAME` instead, e.g. "Released", and `--target-state-type TYPE`
+//! only accepts a state of that type (alone, it picks the team's first state
+//! of the type). A matching `--state-route` takes precedence over
+//! `--target-state`. A team without such a state fails its tickets; dry runs
+//! look the state up too, so a misspelt name shows up before the real run.
+//!
+//! ## Batched Lookups
+//! Workflow state IDs are looked up once per team and run (and shared
+//! through `--cache-dir`). With `--batch-lookups`, all input is read up
+//! front, and the tickets and their teams' workflow states are fetched with
+//! a few batched queries before any ticket is processed (see
+//! [`TicketStore::prefetch`]).
+//!
+//! ## Resumable Queue
+//! With `--queue-file FILE`, all input is read up front and the ticket IDs
+//! are recorded in `FILE`; each ticket is removed once processed
+//! successfully. A later run with the same `--queue-file` resumes with
+//! exactly the unprocessed (or failed) remainder, ignoring its input (see
+//! [`crate::queue`]).
+//!
+//! ## Checkpoint
+//! With `--checkpoint FILE`, every ticket processed successfully is appended
+//! to `FILE`. A re-run with the same `--checkpoint` and input skips those
+//! tickets without a lookup, so only the failed and unreached ones are
+//! processed again. The file is deleted once a run ends without failures
+//! (see [`crate::checkpoint`]).
+//!
+//! ## Run History
+//! With `--history-file FILE` (and `--release-tag`), every processed ticket
+//! is appended to `FILE` with the release and its outcome (see
+//! [`crate::history`]). Tickets completed in this release that an earlier
+//! release's run already completed are flagged as they are processed and
+//! listed at the end; with `--repeat-release fail` the run then exits with an
+//! error.
+//!
+//! With `--quarantine-after N`, tickets that failed in each of their last N
+//! runs are quarantined: they are skipped without a lookup and listed at the
+//! end of every run, as are the tickets this run's failures quarantine, until
+//! `--clear-quarantine ID` retries one.
+//!
+//! ## Journal
+//! With `--journal FILE`, every attempted state change is appended to `FILE`
+//! as a JSON line: when, the ticket, the state it was moved out of and into,
+//! whether it moved, and the ID of the mutation, which is sent as its
+//! `X-Request-Id` header. Audits read it, and the `rollback` mode moves the
+//! moved tickets back (see [`crate::journal`] and [`crate::rollback`]).
+//!
+//! ## Dry-Run Delta
+//! With `--compare-manifest FILE`, the run is compared against the manifest
+//! an earlier dry run wrote with `--manifest FILE`. After the summary, it
+//! lists planned tickets found in a different state than the dry run saw,
+//! planned tickets it didn't process, and tickets it updated that weren't
+//! planned, so changes made between preview and apply are visible.
+//!
+//! ## Quiet Updates
+//! Linear's API has no way to suppress subscriber notifications for state
+//! changes. With `--quiet-updates`, update-tickets does what it can: it plans
+//! every ticket first, then moves them with `issueBatchUpdate` (up to
+//! [`QUIET_BATCH_SIZE`] per request, grouped by team) instead of one mutation
+//! per ticket, and posts comments with `doNotSubscribeToIssue`.
+//!
+//! ## Explain
+//! With `--explain`, the remapping, dropping and outcome of the subject
+//! ticket, or of every ticket record naming the subject PR, are logged (see
+//! [`crate::explain`]).
+//!
+//! ## Bot Identity
+//! With `--linear-actor-token` (or `LINEAR_ACTOR_TOKEN`), every mutation is
+//! sent with that OAuth token of an app installed with `actor=application`,
+//! so Linear attributes the state changes, comments and release issue to the
+//! app rather than to the API key's user (see [`utils::graphql_request`]).
+//!
+//! ## Multiple Workspaces
+//! With `--org-map TEAM=ORG:KEY`, tickets of team `TEAM` are looked up,
+//! moved and commented on in workspace `ORG` with that workspace's API key,
+//! and printed with its URLs; other tickets use `--linear-org`. Mapped team
+//! keys count as known teams.
+//!
+//! ## Linear API Features
+//! Before processing, the Linear API's schema is probed for optional
+//! features (batch updates, issue search, unsubscribed comments); any it
+//! lacks are turned off and logged rather than failing mid-run (see
+//! [`crate::capabilities`]).
+//!
+//! ## Summary
+//! At the end of the run, counts of updated, already-completed, skipped and
+//! failed tickets are logged. The text comes from the locale templates (see
+//! `--locale`).
+//!
+//! ## Output
+//! Each processed ticket is printed to stdout as its Linear URL, or as its
+//! identifier or a Markdown link with `--url-format id|markdown`. With
+//! `--output-format ndjson|json`, every ticket (skipped and failed ones too)
+//! gets a result record instead (see [`crate::protocol`]).
 //!
 //! ## Dry-Run Mode
 //! When `--dry-run` is enabled:
 //! - Queries ticket state but skips mutation
 //! - Outputs only tickets that would be updated
 //! - Suppresses output for already-completed tickets
+//! - Ends with an estimate of the real run's requests (see
+//!   [`crate::estimate`])
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, mpsc};
+use std::thread;
+use std::time::Instant;
 
-use crate::config::Config;
-use crate::utils;
+use crate::action_outputs::{self, RunResults};
+use crate::attachments::AttachedRelease;
+use crate::cache::Cache;
+use crate::capabilities::Capabilities;
+use crate::checkpoint::Checkpoint;
+use crate::comments::{self, CommentTemplate, Commenter, Posted};
+use crate::config::{
+    Config, Dependencies, DryRunOutput, Duplicates, GithubOutput, Lookups,
+    Notifications, OutputFormat, PartialFailure, ReleaseAttachment,
+    RepeatRelease, UnknownTeams, UrlFormat,
+};
+use crate::cycle::{self, TeamCycle};
+use crate::diff::{self, Diff};
+use crate::error::Error;
+use crate::estimate::{Estimate, Provider, Request};
+use crate::explain::{self, Subject};
+use crate::filter::{Filter, Value};
+use crate::history::{self, History};
+use crate::journal;
+use crate::json;
+use crate::labels::{self, CustomField, FieldValue, Label, LabelChange};
+use crate::locale::Messages;
+use crate::manifest;
+use crate::project::{self, Progress, Project};
+use crate::protocol::{
+    Input, PrRef, RecordWriter, SkipReason, TicketInput, UpdateRecord,
+};
+use crate::queue::Queue;
+use crate::relations::{self, RelatedIssue, Relations};
+use crate::release_issue::{self, ChecklistItem};
+use crate::slack;
+use crate::teams;
+use crate::tickets::{self, Ticket, TicketStore};
+use crate::utils::{self, LinearApi, NoInputTimeout};
+use crate::webhook::{self, TICKET_RELEASED, Webhook};
 
 const NAME: &str = "update-tickets";
 
+/// Case-insensitive substrings of the names of workflow states tickets are
+/// moved to. The first state whose name contains one is used.
+pub const COMPLETED_STATE_NAMES: [&str; 2] = ["completed", "done"];
+
+/// Maximum number of issues moved by one `issueBatchUpdate` with
+/// `--quiet-updates`.
+const QUIET_BATCH_SIZE: usize = 50;
+
 macro_rules! log {
+    ($level:ident, $fmt:expr) => {
+        utils::log_at(utils::LogLevel::$level, NAME, format_args!($fmt));
+    };
     ($fmt:expr) => {
         utils::log(NAME, format_args!($fmt));
     };
 }
 
+macro_rules! explain {
+    ($fmt:expr) => {
+        explain::note(NAME, format_args!($fmt));
+    };
+}
+
+/// Per-run settings shared by every ticket.
+struct Context<'a> {
+    config: &'a Config,
+    /// Linear organization identifier (for output URLs)
+    org: String,
+    /// Linear API connection settings
+    linear: LinearApi,
+    /// Workspaces of the `--org-map` teams, by team key
+    workspaces: HashMap<String, Workspace>,
+    /// Cache shared with concurrent pipelines (`--cache-dir`)
+    cache: Option<Cache>,
+    /// Email of the `--assignee` tickets must be assigned to
+    assignee: Option<String>,
+    /// Email of the `--creator` tickets must be created by
+    creator: Option<String>,
+    /// Localized summary and report text
+    messages: Messages,
+    /// Release tag and its comment marker, with `--comment-release`
+    release: Option<(String, String)>,
+    /// Release comment text, with `--comment-template`
+    comment_template: Option<CommentTemplate>,
+    /// Project representing the release, with `--linear-project`
+    project: Option<Project>,
+    /// ID and name of the project updated tickets are added to
+    /// (`--add-to-project`)
+    add_to_project: Option<(String, String)>,
+    /// Label applied to updated tickets (`--add-label`)
+    label: Option<Label>,
+    /// Value label set on updated tickets (`--set-custom-field`)
+    custom_field: Option<FieldValue>,
+    /// `--linear-cycle` of each team, by team ID, as looked up (`None` for
+    /// teams without it)
+    cycles: Mutex<HashMap<String, Option<TeamCycle>>>,
+    /// Release attached to updated tickets, with `--attach-release`
+    attachment: Option<AttachedRelease>,
+    /// Endpoint moved tickets are announced to, with `--webhook-url`
+    webhook: Option<Webhook>,
+    /// Requests the dry run leaves out, for its estimate of the real run
+    estimate: Estimate,
+    /// Name of the workflow state tickets are moved to instead of the
+    /// completed state: that of the `--state-route` the release tag matches,
+    /// or else the `--target-state`
+    route: Option<String>,
+    /// PRs each ticket was found in, from the ticket records read (for
+    /// `--output-format ndjson|json`, `--comment-template` and `--explain`)
+    sources: Mutex<HashMap<String, Vec<PrRef>>>,
+    /// Whether the `--explain` subject came up
+    explained: AtomicBool,
+    /// Input tickets dropped by `--exclude-pattern`, `--drop-unknown-teams`,
+    /// `--quarantine-after` or `--checkpoint`, counted as skipped (with a result record, in
+    /// ndjson/json output) at the end
+    dropped: Mutex<Vec<(String, SkipReason)>>,
+    /// Keys of the workspace's teams, to flag input tickets of other teams
+    /// (`None` if they couldn't be listed)
+    team_keys: Option<HashSet<String>>,
+    /// Unknown team keys already warned about
+    unknown_teams: Mutex<HashSet<String>>,
+    /// Issues already followed to from a duplicate (`--follow-duplicates`)
+    followed: Mutex<HashSet<String>>,
+    /// Optional Linear API features available to this run
+    capabilities: Capabilities,
+    /// Target workflow states looked up in this run, by cache key (see
+    /// [`resolve_target_state`])
+    target_states: Mutex<HashMap<String, TargetState>>,
+    /// Outcomes of earlier runs (`--history-file`)
+    history: Option<History>,
+    /// Tickets processed by earlier runs (`--checkpoint`)
+    checkpoint: Option<Checkpoint>,
+    /// When the run started, for the elapsed time in the summary
+    started: Instant,
+}
+
+impl<'a> Context<'a> {
+    /// Sets up the run: Linear credentials, the `--linear-project` and
+    /// `--add-to-project` projects, the `--add-label` label, the `--attach-release` release, the `--assignee` and `--creator`, the
+    /// workspace's team keys, the API's optional features and the per-run
+    /// settings.
+    ///
+    /// # Errors
+    /// Returns an error if the credentials are missing, or an input such as
+    /// the locale or project can't be loaded.
+    fn new(config: &'a Config) -> Result<Self, Error> {
+        let started = Instant::now();
+        // Get Linear API key from config or environment
+        let linear = config.linear_api()?;
+        let org = config.get_linear_org()?;
+        let project = config
+            .linear_project
+            .as_deref()
+            .map(|project| Project::load(project, &linear))
+            .transpose()?;
+        let add_to_project = config
+            .add_to_project
+            .as_deref()
+            .map(|project| project::find_project(project, &linear))
+            .transpose()?;
+        let label = load_label(config, &linear)?;
+        let custom_field = config
+            .custom_field
+            .as_ref()
+            .map(|field| load_custom_field(field, config.dry_run, &linear))
+            .transpose()?;
+        let attachment = load_attachment(config, &linear)?;
+        let (assignee, creator) = resolve_users(config, &linear)?;
+        let workspaces = org_map_workspaces(config, &linear)?;
+        let team_keys = known_team_keys(config, &linear)?.map(|mut keys| {
+            keys.extend(workspaces.keys().cloned());
+            keys
+        });
+        let history = load_history(config)?;
+        let checkpoint = load_checkpoint(config)?;
+        // A snapshot run never reaches the API.
+        let capabilities = if config.linear_snapshot.is_some() {
+            Capabilities::default()
+        } else {
+            Capabilities::probe(&linear)
+        };
+        Ok(Self {
+            config,
+            linear,
+            workspaces,
+            cache: config.cache()?,
+            org,
+            assignee,
+            creator,
+            messages: Messages::load(
+                config.locale.as_deref(),
+                config.locale_dir.as_deref(),
+            )?,
+            release: config
+                .release_tag
+                .as_ref()
+                .filter(|_| config.comment_release)
+                .map(|tag| (tag.clone(), comments::release_marker(tag))),
+            comment_template: config
+                .comment_template
+                .as_deref()
+                .zip(config.release_tag.as_deref())
+                .map(|(path, tag)| {
+                    CommentTemplate::load(path, tag, &config.github()?)
+                })
+                .transpose()?,
+            project,
+            add_to_project,
+            label,
+            custom_field,
+            cycles: Mutex::default(),
+            attachment,
+            webhook: Webhook::from_config(config),
+            estimate: Estimate::default(),
+            route: state_route(config).or_else(|| config.target_state.clone()),
+            sources: Mutex::default(),
+            explained: AtomicBool::new(false),
+            dropped: Mutex::default(),
+            team_keys,
+            unknown_teams: Mutex::default(),
+            followed: Mutex::default(),
+            capabilities,
+            target_states: Mutex::default(),
+            history,
+            checkpoint,
+            started,
+        })
+    }
+
+    /// Linear API settings of the workspace of team `team_key`: its
+    /// `--org-map` workspace, or else the `--linear-org` one.
+    fn linear_of_team(&self, team_key: &str) -> &LinearApi {
+        self.workspaces
+            .get(team_key)
+            .map_or(&self.linear, |workspace| &workspace.linear)
+    }
+
+    /// Linear API settings of the workspace of ticket `issue_id`.
+    fn linear_for(&self, issue_id: &str) -> &LinearApi {
+        self.linear_of_team(team_key(issue_id))
+    }
+
+    /// Organization of the workspace of ticket `issue_id`, for its URL.
+    fn org_for(&self, issue_id: &str) -> &str {
+        self.workspaces
+            .get(team_key(issue_id))
+            .map_or(&self.org, |workspace| &workspace.org)
+    }
+}
+
+/// A Linear workspace that some teams' tickets live in (`--org-map`).
+struct Workspace {
+    /// Linear organization identifier (for output URLs)
+    org: String,
+    /// Linear API connection settings, with the workspace's API key
+    linear: LinearApi,
+}
+
+/// Team key of ticket `issue_id`, e.g. `ENG` for `ENG-123`.
+fn team_key(issue_id: &str) -> &str {
+    issue_id.split_once('-').map_or(issue_id, |(key, _)| key)
+}
+
+/// Connects to the workspace of every `--org-map` team, by team key. They
+/// share the endpoint, headers, rate limiter and budget of `linear`.
+///
+/// # Errors
+/// Returns an error if a workspace's API key can't be read.
+fn org_map_workspaces(
+    config: &Config,
+    linear: &LinearApi,
+) -> Result<HashMap<String, Workspace>, Error> {
+    config
+        .org_map
+        .routes()
+        .iter()
+        .map(|route| {
+            let workspace = Workspace {
+                org: route.org.clone(),
+                linear: LinearApi {
+                    api_key: route.api_key()?,
+                    actor_token: None,
+                    ..linear.clone()
+                },
+            };
+            Ok((route.team.clone(), workspace))
+        })
+        .collect()
+}
+
+/// Mutable bookkeeping accumulated while processing tickets.
+#[derive(Default)]
+struct RunState {
+    /// Metadata of every ticket looked up in this run
+    tickets: TicketStore,
+    /// Whether any ticket was printed to stdout (in text format)
+    any_output: bool,
+    /// Result records (`--output-format ndjson|json`)
+    output: RecordWriter,
+    /// Table of every ticket, printed at the end (`--diff`)
+    diff: Option<Diff>,
+    /// Manifest entries for tickets that would be updated (dry-run)
+    planned: Vec<manifest::Entry>,
+    /// Tickets updated (or that would be updated) in this run
+    updated: HashSet<String>,
+    /// Tickets completed in the release (updated or already completed), in
+    /// processing order
+    completed: Vec<String>,
+    /// Blocked-by relations, when ordering by dependencies
+    relations: HashMap<String, Relations>,
+    /// `(ticket, blocker)` pairs where a ticket was completed while its
+    /// blocker was still open
+    open_blockers: Vec<(String, RelatedIssue)>,
+    /// Per-outcome ticket counts for the end-of-run summary
+    tally: Tally,
+    /// Paced poster for `--note-blocked` comments
+    commenter: Commenter,
+    /// Tickets not yet processed successfully (`--queue-file`)
+    queue: Option<Queue>,
+    /// `(ticket, releases)` for tickets an earlier release already completed
+    repeat_releases: Vec<(String, String)>,
+    /// `(ticket, error)` for tickets that failed, in processing order
+    failures: Vec<(String, String)>,
+    /// Tickets whose failure in this run quarantines them
+    /// (`--quarantine-after`)
+    newly_quarantined: Vec<String>,
+    /// Tickets of the `--linear-project` project updated in this run
+    project_updated: usize,
+    /// Changes planned by the dry run (`--compare-manifest`)
+    preview: Option<Vec<manifest::Entry>>,
+}
+
+impl RunState {
+    fn new(ctx: &Context<'_>) -> Result<Self, Error> {
+        let config = ctx.config;
+        let mut tickets = TicketStore::new(
+            config.dependencies != Dependencies::Ignore,
+            config.comment_release,
+            config.snapshot()?,
+        );
+        if !ctx.capabilities.issue_search {
+            tickets.disable_search();
+        }
+        Ok(Self {
+            tickets,
+            commenter: Commenter::from_config(config, ctx.capabilities)?,
+            output: RecordWriter::new(
+                config.output_format,
+                config.output.clone(),
+            ),
+            diff: (config.dry_run_output == DryRunOutput::Diff)
+                .then(Diff::default),
+            preview: config
+                .compare_manifest
+                .as_deref()
+                .map(manifest::read)
+                .transpose()?,
+            ..Self::default()
+        })
+    }
+}
+
+/// Ticket counts reported in the end-of-run summary.
+#[derive(Debug, Default)]
+struct Tally {
+    updated: usize,
+    already_completed: usize,
+    already_released: usize,
+    skipped: usize,
+    /// Of the skipped tickets, those quarantined (`--quarantine-after`)
+    quarantined: usize,
+    /// Of the skipped tickets, those an earlier run processed
+    /// (`--checkpoint`)
+    checkpointed: usize,
+    failed: usize,
+    /// Of the failed tickets, those that don't exist
+    not_found: usize,
+}
+
+/// The decision made for a single ticket after querying its current state.
+enum Plan {
+    /// The ticket is eligible and should be moved to the completed state
+    Update(manifest::Entry),
+    /// The ticket is already in a Done/Completed state
+    AlreadyCompleted,
+    /// The ticket already carries this release's marker
+    AlreadyReleased,
+    /// The ticket is filtered out or not in an eligible state
+    Skip(SkipReason),
+}
+
+/// What happened to a single ticket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The ticket was moved to the completed state (or would be, in dry-run)
+    Updated,
+    /// The ticket was already in a Done/Completed state
+    AlreadyCompleted,
+    /// The ticket already carries this release's marker
+    AlreadyReleased,
+    /// The ticket was filtered out or not in an eligible state
+    Skipped(SkipReason),
+}
+
+/// What [`update`] did to a ticket.
+#[derive(Debug)]
+pub struct TicketUpdate {
+    /// The ticket ID, e.g. `ABC-123`
+    pub ticket: String,
+    /// Its outcome, or why it failed
+    pub result: Result<Outcome, Error>,
+}
+
+impl Outcome {
+    /// The outcome's name in the `--history-file`.
+    fn history_name(self) -> &'static str {
+        match self {
+            Outcome::Updated => "updated",
+            Outcome::AlreadyCompleted => "already_completed",
+            Outcome::AlreadyReleased => "already_released",
+            Outcome::Skipped(_) => "skipped",
+        }
+    }
+}
+
 /// Runs the update-tickets mode to mark Linear tickets as completed.
 ///
 /// # Input
@@ -40,6 +650,8 @@ macro_rules! log {
 /// - **Dry-run mode**: Prints only tickets that would be updated (not already
 ///   completed)
 /// - **Errors**: Prints error messages and failed ticket URLs to stderr
+/// - **`--output-format ndjson|json`**: Prints a result record per ticket
+///   instead
 ///
 /// # Dry-Run Mode
 /// If `config.dry_run` is true:
@@ -47,222 +659,2938 @@ macro_rules! log {
 ///   would be marked as Done or Completed:"
 /// - Queries each ticket's state but skips the update mutation
 /// - Only outputs URLs for tickets that would be updated
+/// - With `--manifest FILE`, writes the hash-stamped manifest of intended
+///   changes to `FILE`
+///
+/// # Dependency Ordering
+/// With `--order-by-dependencies` (or `--note-blocked`), all input is read
+/// first and tickets are processed so that blockers are updated before the
+/// tickets they block. Tickets completed while a blocker is still open are
+/// reported at the end.
+///
+/// # Approval Mode
+/// If `config.approve` is set, all input is read and planned before anything
+/// is updated. The plan's manifest hash must equal the approved hash,
+/// otherwise no ticket is touched.
 ///
 /// # Errors
 /// Returns an error if:
 /// - `LINEAR_API_KEY` is not provided
 /// - `LINEAR_ORG` is not provided
 /// - Input sources cannot be read
+/// - The manifest cannot be written, or doesn't match `--approve`
 /// - Individual ticket updates may fail (logged to stderr, doesn't stop
-///   processing)
-pub fn run(config: &Config) -> Result<(), String> {
-    // Get Linear API key from config or environment
-    let linear_api_key = config.get_linear_api_key()?;
-    let linear_org = config.get_linear_org()?;
+///   processing); with `--fail-on-partial`, the run then fails if any did
+pub fn run(config: &Config) -> Result<(), Error> {
+    let ctx = Context::new(config)?;
+    report_project(&ctx, "project.before", None);
+
+    if let Some(ref approved_hash) = config.approve {
+        return run_planned(&ctx, Some(approved_hash));
+    }
+    if config.notifications == Notifications::Quiet && !config.dry_run {
+        return run_planned(&ctx, None);
+    }
+
+    if let Some(ref path) = config.linear_snapshot {
+        log!("reading tickets from snapshot {path} instead of the Linear API");
+    }
 
     // Print dry-run header if in preview mode
-    if config.dry_run {
+    if config.dry_run && has_custom_target(&ctx) {
+        let target = target_state(&ctx);
+        log!(
+            "Dry-run mode enabled. The following issues would be moved to {target}:"
+        );
+    } else if config.dry_run {
         log!(
             "Dry-run mode enabled. The following issues would be marked as Done or Completed:"
         );
     }
 
-    let mut any_output = false;
+    let mut state = RunState::new(&ctx)?;
+
+    if config.dependencies == Dependencies::Ignore
+        && config.queue_file.is_none()
+        && config.lookups == Lookups::Single
+    {
+        log!("reading ticket IDs from input");
+        // Process tickets as they arrive from input (streaming), so an
+        // upstream stage can keep the pipeline flowing and we can start
+        // updating tickets immediately.
+        let read_input =
+            |submit: &mut dyn FnMut(String) -> Result<(), Error>| {
+                utils::for_each_input(
+                    &config.input_sources,
+                    no_input_timeout(config),
+                    |input| {
+                        read_issue_ids(&ctx, &input)
+                            .into_iter()
+                            .try_for_each(&mut *submit)
+                    },
+                )
+            };
+        process_tickets(&ctx, &mut state, read_input)?;
+    } else {
+        let mut issue_ids = queued_issue_ids(&ctx, &mut state)?;
+        prefetch(&ctx, &issue_ids, &mut state);
+        if config.dependencies != Dependencies::Ignore {
+            issue_ids = order_by_dependencies(&ctx, &issue_ids, &mut state);
+        }
+        process_tickets(&ctx, &mut state, |submit| {
+            issue_ids.into_iter().try_for_each(submit)
+        })?;
+        if config.dependencies != Dependencies::Ignore {
+            report_open_blockers(&ctx, &state);
+        }
+    }
+    record_dropped(&ctx, &mut state)?;
+    explain_missing(&ctx);
+    finish_queue(&mut state)?;
+    finish_checkpoint(&ctx, &state)?;
+    finish_output(&mut state);
+
+    if config.dry_run
+        && let Some(ref path) = config.manifest
+    {
+        let hash = manifest::write(
+            path,
+            &ctx.org,
+            std::mem::take(&mut state.planned),
+        )?;
+        log!("wrote manifest {path} (sha256 {hash})");
+        log!("re-run without --dry-run and with --approve {hash} to apply");
+    }
+
+    create_release_issue(&ctx, &state)?;
+    report_repeat_releases(&ctx, &state);
+    report_quarantine(&ctx, &state);
+
+    log!("done");
+    report_summary(&ctx, &state);
+    notify_slack(&ctx, &state);
+    report_estimate(&ctx, &state);
+    write_github_output(&ctx, &state)?;
+    report_delta(&ctx, &state);
+    report_project_after(&ctx, &state);
+
+    check_repeat_releases(&ctx, &state)?;
+    check_failures(config, state.tally.failed)
+}
+
+/// Moves the tickets `issue_ids` as the update-tickets mode does (or, with
+/// `--dry-run`, only looks them up), returning the outcome of each, in
+/// order. Tickets matching `--exclude-pattern` (and the like) are skipped
+/// without a lookup.
+///
+/// Only the tickets' states are changed: what follows an update in the
+/// update-tickets mode (`--comment-release`, `--attach-release`,
+/// `--webhook-url`, `--journal`, ...) and the end-of-run reports are left to
+/// the caller.
+///
+/// # Errors
+/// Returns an error if the run can't be set up, e.g. the Linear credentials
+/// are missing; a ticket that fails is returned with its error.
+pub fn update(
+    config: &Config,
+    issue_ids: &[String],
+) -> Result<Vec<TicketUpdate>, Error> {
+    let ctx = Context::new(config)?;
+    let mut state = RunState::new(&ctx)?;
+    Ok(issue_ids
+        .iter()
+        .map(|issue_id| {
+            let result = match drop_reason(&ctx, issue_id) {
+                Some(reason) => Ok(Outcome::Skipped(reason)),
+                None => {
+                    update_single_ticket(&ctx, &mut state.tickets, issue_id)
+                        .map(|(outcome, _)| outcome)
+                }
+            };
+            TicketUpdate {
+                ticket: issue_id.clone(),
+                result,
+            }
+        })
+        .collect())
+}
+
+/// Plans every input ticket, then applies the plan.
+///
+/// With `approved_hash` (`--approve`), the plan is verified against the
+/// approved manifest hash before anything is applied. With `--quiet-updates`,
+/// the updates are applied in batches.
+///
+/// # Errors
+/// Returns an error (without updating anything) if, with `approved_hash`,
+/// a ticket can't be planned or the current plan's manifest hash differs.
+fn run_planned(
+    ctx: &Context<'_>,
+    approved_hash: Option<&str>,
+) -> Result<(), Error> {
+    let mut state = RunState::new(ctx)?;
+    let mut issue_ids = queued_issue_ids(ctx, &mut state)?;
+    prefetch(ctx, &issue_ids, &mut state);
+    if ctx.config.dependencies != Dependencies::Ignore {
+        issue_ids = order_by_dependencies(ctx, &issue_ids, &mut state);
+    }
+
+    // Phase 1: plan every ticket without mutating anything.
+    let mut plans = Vec::new();
+    let mut unplanned = 0;
+    for issue_id in issue_ids {
+        let url = issue_url(ctx.org_for(&issue_id), &issue_id);
+        log!("planning {url}");
+        match plan_ticket(ctx, &mut state.tickets, &issue_id) {
+            Ok(plan) => plans.push((issue_id, plan)),
+            Err(e) => {
+                unplanned += 1;
+                record_outcome(ctx, &issue_id, Err(e), &mut state)?;
+            }
+        }
+    }
+
+    if let Some(approved_hash) = approved_hash {
+        // A ticket that couldn't be planned is missing from the hash, so
+        // the plan isn't the one that was approved.
+        if unplanned > 0 {
+            return Err(Error::Other(format!(
+                "{unplanned} ticket(s) could not be planned; refusing to apply"
+            )));
+        }
+        verify_approval(ctx, &plans, approved_hash)?;
+    }
+
+    // Phase 2: apply exactly the planned changes.
+    if ctx.config.notifications == Notifications::Quiet
+        && ctx.capabilities.batch_update
+    {
+        let results = apply_batched(ctx, &plans, &state.tickets);
+        for ((issue_id, _), result) in plans.iter().zip(results) {
+            record_outcome(ctx, issue_id, result, &mut state)?;
+        }
+    } else {
+        for (issue_id, plan) in plans {
+            let result = apply_plan(ctx, &issue_id, &plan, &state.tickets);
+            record_outcome(ctx, &issue_id, result, &mut state)?;
+        }
+    }
+    if ctx.config.dependencies != Dependencies::Ignore {
+        report_open_blockers(ctx, &state);
+    }
+
+    record_dropped(ctx, &mut state)?;
+    explain_missing(ctx);
+    finish_queue(&mut state)?;
+    finish_checkpoint(ctx, &state)?;
+    finish_output(&mut state);
+    create_release_issue(ctx, &state)?;
+    report_repeat_releases(ctx, &state);
+    report_quarantine(ctx, &state);
+
+    log!("done");
+    report_summary(ctx, &state);
+    notify_slack(ctx, &state);
+    report_estimate(ctx, &state);
+    write_github_output(ctx, &state)?;
+    report_delta(ctx, &state);
+    report_project_after(ctx, &state);
+
+    check_repeat_releases(ctx, &state)?;
+    check_failures(ctx.config, state.tally.failed)
+}
+
+/// Checks the plan's manifest hash against `--approve`.
+///
+/// # Errors
+/// Returns an error if the hashes differ.
+fn verify_approval(
+    ctx: &Context<'_>,
+    plans: &[(String, Plan)],
+    approved_hash: &str,
+) -> Result<(), Error> {
+    let entries: Vec<manifest::Entry> = plans
+        .iter()
+        .filter_map(|(_, plan)| match plan {
+            Plan::Update(entry) => Some(entry.clone()),
+            Plan::AlreadyCompleted | Plan::AlreadyReleased | Plan::Skip(_) => {
+                None
+            }
+        })
+        .collect();
+    let hash = manifest::hash(&manifest::render(&ctx.org, entries))?;
+    if !hash.eq_ignore_ascii_case(approved_hash) {
+        return Err(Error::Other(format!(
+            "Manifest hash mismatch: approved {approved_hash}, current plan is {hash}; refusing to apply"
+        )));
+    }
+    log!("manifest hash {hash} matches approval, applying");
+    Ok(())
+}
+
+/// Reads the tickets to process up front.
+///
+/// With `--queue-file`, resumes from an existing queue file (ignoring input),
+/// or records all input in a new one.
+///
+/// # Errors
+/// Returns an error if the input or queue file cannot be read, or the queue
+/// file cannot be written.
+fn queued_issue_ids(
+    ctx: &Context<'_>,
+    state: &mut RunState,
+) -> Result<Vec<String>, Error> {
+    let Some(ref path) = ctx.config.queue_file else {
+        return collect_issue_ids(ctx);
+    };
+
+    if let Some(queue) = Queue::load(path)? {
+        let issue_ids = queue.issue_ids().to_vec();
+        let count = issue_ids.len();
+        log!("resuming {count} queued ticket(s) from {path}, ignoring input");
+        state.queue = Some(queue);
+        return Ok(issue_ids);
+    }
+
+    let issue_ids = collect_issue_ids(ctx)?;
+    state.queue = Some(Queue::create(path, &issue_ids)?);
+    let count = issue_ids.len();
+    log!("queued {count} ticket(s) in {path}");
+    Ok(issue_ids)
+}
+
+/// Removes the queue file once every ticket has been processed, or reports
+/// how many are left for a retry.
+///
+/// # Errors
+/// Returns an error if the queue file cannot be removed.
+fn finish_queue(state: &mut RunState) -> Result<(), Error> {
+    let Some(queue) = state.queue.take() else {
+        return Ok(());
+    };
+    let path = queue.path().to_string();
+    match queue.finish()? {
+        0 => {
+            log!("all queued tickets processed, removed {path}");
+        }
+        left => {
+            log!(
+                Warn,
+                "{left} ticket(s) left in {path}; re-run with --queue-file {path} to retry"
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Loads the `--checkpoint` file, logging how many tickets an earlier run
+/// left in it.
+///
+/// # Errors
+/// Returns an error if the file can't be read.
+fn load_checkpoint(config: &Config) -> Result<Option<Checkpoint>, Error> {
+    let Some(ref path) = config.checkpoint else {
+        return Ok(None);
+    };
+    let checkpoint = Checkpoint::load(path)?;
+    let count = checkpoint.count();
+    if count > 0 {
+        log!(
+            "resuming from {path}: {count} ticket(s) already processed, skipping them"
+        );
+    }
+    Ok(Some(checkpoint))
+}
+
+/// Removes the checkpoint file once a run ends without failures, or reports
+/// how to retry the failed tickets.
+///
+/// # Errors
+/// Returns an error if the checkpoint file cannot be removed.
+fn finish_checkpoint(ctx: &Context<'_>, state: &RunState) -> Result<(), Error> {
+    let Some(ref checkpoint) = ctx.checkpoint else {
+        return Ok(());
+    };
+    let path = checkpoint.path();
+    let failed = state.tally.failed;
+    if failed == 0 {
+        checkpoint.finish()?;
+        log!("all tickets processed, removed {path}");
+    } else {
+        log!(
+            Warn,
+            "{failed} ticket(s) failed; re-run with the same input and --checkpoint {path} to retry them"
+        );
+    }
+    Ok(())
+}
+
+/// How long to wait for ticket IDs on a terminal stdin.
+fn no_input_timeout(config: &Config) -> NoInputTimeout<'static> {
+    NoInputTimeout {
+        secs: config.stdin_timeout_secs,
+        expected: "ticket IDs",
+    }
+}
+
+/// Reads all input up front, returning unique ticket IDs in input order.
+///
+/// # Errors
+/// Returns an error if the input sources cannot be read.
+fn collect_issue_ids(ctx: &Context<'_>) -> Result<Vec<String>, Error> {
+    let mut issue_ids = Vec::new();
+
+    log!("reading ticket IDs from input");
+    utils::for_each_input(
+        &ctx.config.input_sources,
+        no_input_timeout(ctx.config),
+        |input| {
+            for issue_id in read_issue_ids(ctx, &input) {
+                if !issue_ids.contains(&issue_id) {
+                    issue_ids.push(issue_id);
+                }
+            }
+            Ok(())
+        },
+    )?;
+
+    Ok(issue_ids)
+}
+
+/// Parses one input line into the ticket IDs to process, logging invalid
+/// input and applying `--prefix-map`, `--exclude-pattern`,
+/// `--drop-unknown-teams`, `--quarantine-after`, `--checkpoint` and
+/// `--follow-duplicates`, and skipping ticket records tagged with another
+/// workspace than the ticket's (`--other-org-urls tag`).
+/// The PR named by a ticket record is remembered for the ticket's result
+/// record.
+///
+/// # Returns
+/// Nothing for blank lines, invalid ticket IDs (which are logged to stderr),
+/// excluded, quarantined, checkpointed and other workspaces' tickets and,
+/// with `--drop-unknown-teams`, tickets of unknown teams. With
+/// `--follow-duplicates`, the issue a ticket duplicates replaces it or
+/// follows it.
+fn read_issue_ids(ctx: &Context<'_>, input: &Input) -> Vec<String> {
+    let ticket = match input.ticket() {
+        Ok(Some(ticket)) => parse_issue_id(ctx.config, &ticket.ticket)
+            .map(|issue_id| (issue_id, ticket)),
+        Ok(None) => return Vec::new(),
+        Err(e) => Err(e),
+    };
+    match ticket {
+        Ok((issue_id, TicketInput { pr, org, .. })) => {
+            let remapped = ctx.config.prefix_map.apply(&issue_id);
+            if let Some(pr) = pr
+                && (ctx.config.output_format != OutputFormat::Text
+                    || ctx.comment_template.is_some()
+                    || ctx.config.explain.is_some())
+                && let Ok(mut sources) = ctx.sources.lock()
+            {
+                let prs = sources.entry(remapped.clone()).or_default();
+                if !prs.contains(&pr) {
+                    prs.push(pr);
+                }
+            }
+            if remapped != issue_id {
+                log!(
+                    Verbose,
+                    "remapped {issue_id} to {remapped} (--prefix-map)"
+                );
+                if explained(ctx, &remapped) {
+                    explain!("{issue_id} is read as {remapped} (--prefix-map)");
+                }
+            }
+            let reason = match org {
+                Some(org)
+                    if !org.eq_ignore_ascii_case(ctx.org_for(&remapped)) =>
+                {
+                    log!(
+                        "skipping {remapped}: its URLs are of Linear workspace {org} (--other-org-urls)"
+                    );
+                    Some(SkipReason::OtherOrg)
+                }
+                _ => drop_reason(ctx, &remapped),
+            };
+            if let Some(reason) = reason {
+                if explained(ctx, &remapped) {
+                    let why = explain::skip_reason(reason);
+                    explain!("{remapped} is dropped without a lookup: {why}");
+                }
+                drop_ticket(ctx, remapped, reason);
+                return Vec::new();
+            }
+            follow_duplicates(ctx, remapped)
+        }
+        Err(e) => {
+            log!(Warn, "Invalid input {input}: {e}");
+            log!(Warn, "{input}");
+            Vec::new()
+        }
+    }
+}
+
+/// The tickets to process for input ticket `issue_id` with
+/// `--follow-duplicates`: the issue it is marked a duplicate of (at the end
+/// of a chain of duplicates) instead of it or after it, unless that issue was
+/// already followed to or is dropped (see [`drop_reason`]). The issue gets
+/// the PRs the input ticket was found in.
+fn follow_duplicates(ctx: &Context<'_>, issue_id: String) -> Vec<String> {
+    if ctx.config.duplicates == Duplicates::Ignore {
+        return vec![issue_id];
+    }
+    let mut chain = vec![issue_id.clone()];
+    loop {
+        let last = &chain[chain.len() - 1];
+        match relations::duplicate_of(last, ctx.linear_for(last)) {
+            Ok(Some(next)) if !chain.contains(&next) => chain.push(next),
+            Ok(_) => break,
+            Err(e) => {
+                log!(Warn, "Failed to look up duplicates of {last}: {e}");
+                break;
+            }
+        }
+    }
+    let Some(canonical) = chain.pop().filter(|last| *last != issue_id) else {
+        return vec![issue_id];
+    };
+
+    let (verb, mut issue_ids) = match ctx.config.duplicates {
+        Duplicates::Canonical => ("instead", Vec::new()),
+        _ => ("as well", vec![issue_id.clone()]),
+    };
+    log!(
+        "{issue_id} is a duplicate of {canonical}; updating {canonical} {verb} (--follow-duplicates)"
+    );
+    if explained(ctx, &issue_id) {
+        explain!(
+            "{issue_id} is a duplicate of {canonical}, which is updated {verb} (--follow-duplicates)"
+        );
+    }
+    if let Ok(mut sources) = ctx.sources.lock()
+        && let Some(prs) = sources.get(&issue_id).cloned()
+    {
+        let canonical_prs = sources.entry(canonical.clone()).or_default();
+        for pr in prs {
+            if !canonical_prs.contains(&pr) {
+                canonical_prs.push(pr);
+            }
+        }
+    }
+    let first = ctx
+        .followed
+        .lock()
+        .is_ok_and(|mut followed| followed.insert(canonical.clone()));
+    if !first {
+        log!("{canonical} was already followed to; not updating it again");
+        return issue_ids;
+    }
+    if let Some(reason) = drop_reason(ctx, &canonical) {
+        drop_ticket(ctx, canonical, reason);
+        return issue_ids;
+    }
+    issue_ids.push(canonical);
+    issue_ids
+}
+
+/// Why an input ticket is dropped without a lookup, if it is: it matches
+/// `--exclude-pattern`, is of an unknown team (with `--drop-unknown-teams`),
+/// is quarantined or is in the `--checkpoint`.
+fn drop_reason(ctx: &Context<'_>, issue_id: &str) -> Option<SkipReason> {
+    if exclude_ticket(ctx.config, issue_id) {
+        return Some(SkipReason::Excluded);
+    }
+    if unknown_team(ctx, issue_id)
+        && ctx.config.unknown_teams == UnknownTeams::Drop
+    {
+        return Some(SkipReason::UnknownTeam);
+    }
+    if quarantined(ctx, issue_id) {
+        return Some(SkipReason::Quarantined);
+    }
+    if ctx
+        .checkpoint
+        .as_ref()
+        .is_some_and(|checkpoint| checkpoint.contains(issue_id))
+    {
+        return Some(SkipReason::Checkpointed);
+    }
+    None
+}
+
+/// Whether `issue_id` is to be explained (`--explain`): it is the subject
+/// ticket, or was found in the subject PR. Remembers that the subject came
+/// up.
+fn explained(ctx: &Context<'_>, issue_id: &str) -> bool {
+    let explained = match ctx.config.explain {
+        None => false,
+        Some(Subject::Ticket(ref ticket)) => ticket == issue_id,
+        Some(Subject::Pr(ref pr)) => ctx.sources.lock().is_ok_and(|sources| {
+            sources.get(issue_id).is_some_and(|prs| prs.contains(pr))
+        }),
+    };
+    if explained {
+        ctx.explained.store(true, Ordering::Relaxed);
+    }
+    explained
+}
+
+/// With `--explain`, logs what was decided about `issue_id` if it is to be
+/// explained.
+fn explain_outcome(
+    ctx: &Context<'_>,
+    issue_id: &str,
+    result: &Result<Outcome, Error>,
+    state: &RunState,
+) {
+    if !explained(ctx, issue_id) {
+        return;
+    }
+    let found_in = state
+        .tickets
+        .cached(issue_id)
+        .map_or_else(String::new, |ticket| {
+            format!(" (in {})", ticket.state_name)
+        });
+    match result {
+        Err(e) => {
+            explain!("{issue_id} failed: {e}");
+        }
+        Ok(Outcome::Updated) => {
+            let target = target_state(ctx);
+            let verb = if ctx.config.dry_run {
+                "would be moved"
+            } else {
+                "is moved"
+            };
+            explain!("{issue_id}{found_in} {verb} to {target}");
+        }
+        Ok(Outcome::AlreadyCompleted) => {
+            explain!("{issue_id}{found_in} is already completed");
+        }
+        Ok(Outcome::AlreadyReleased) => {
+            explain!(
+                "{issue_id}{found_in} already carries the marker or attachment of this release"
+            );
+        }
+        Ok(Outcome::Skipped(reason)) => {
+            let why = explain::skip_reason(*reason);
+            explain!("{issue_id}{found_in} is skipped: {why}");
+        }
+    }
+}
+
+/// With `--explain`, logs that the subject never came up in this run.
+fn explain_missing(ctx: &Context<'_>) {
+    let Some(ref subject) = ctx.config.explain else {
+        return;
+    };
+    if ctx.explained.load(Ordering::Relaxed) {
+        return;
+    }
+    match subject {
+        Subject::Ticket(_) => {
+            explain!("{subject} is not in the input (see extract-tickets)");
+        }
+        Subject::Pr(_) => {
+            explain!(
+                "no ticket of {subject} is in the input (see extract-tickets)"
+            );
+        }
+    }
+}
+
+/// Remembers an input ticket that won't be looked up, for
+/// [`record_dropped`].
+fn drop_ticket(ctx: &Context<'_>, issue_id: String, reason: SkipReason) {
+    if let Ok(mut dropped) = ctx.dropped.lock()
+        && !dropped
+            .iter()
+            .any(|(dropped_id, _)| *dropped_id == issue_id)
+    {
+        dropped.push((issue_id, reason));
+    }
+}
+
+/// Writes a skipped result record (and counts a skipped ticket) for every
+/// input ticket `--exclude-pattern`, `--drop-unknown-teams`,
+/// `--quarantine-after`, `--checkpoint` or an other workspace's `org` tag
+/// dropped, after the processed ones.
+///
+/// # Errors
+/// Returns an error if a record can't be written.
+fn record_dropped(
+    ctx: &Context<'_>,
+    state: &mut RunState,
+) -> Result<(), Error> {
+    let dropped = ctx
+        .dropped
+        .lock()
+        .map(|dropped| dropped.clone())
+        .unwrap_or_default();
+    for (issue_id, reason) in dropped {
+        state.tally.skipped += 1;
+        match reason {
+            SkipReason::Quarantined => state.tally.quarantined += 1,
+            SkipReason::Checkpointed => state.tally.checkpointed += 1,
+            _ => {}
+        }
+        let outcome = Outcome::Skipped(reason);
+        write_record(ctx, &issue_id, Ok(outcome), false, state)?;
+    }
+    Ok(())
+}
+
+/// Whether the team key of `issue_id` isn't a team of the workspace, which
+/// usually means the ID is a false positive of the ticket pattern (such as
+/// `SHA-256` or `ISO-8601`). Each unknown key is logged once.
+fn unknown_team(ctx: &Context<'_>, issue_id: &str) -> bool {
+    let Some(ref team_keys) = ctx.team_keys else {
+        return false;
+    };
+    let key = issue_id.split_once('-').map_or(issue_id, |(key, _)| key);
+    if team_keys.contains(key) {
+        return false;
+    }
+    if let Ok(mut warned) = ctx.unknown_teams.lock()
+        && warned.insert(key.to_string())
+    {
+        if ctx.config.unknown_teams == UnknownTeams::Drop {
+            log!(
+                "skipping {key}- tickets such as {issue_id}: no team {key} in the workspace (--drop-unknown-teams)"
+            );
+        } else {
+            log!(
+                Warn,
+                "Warning: no team {key} in the workspace for {issue_id}; likely not a ticket (skip such IDs with --drop-unknown-teams)"
+            );
+        }
+    }
+    true
+}
+
+/// Lists the workspace's team keys for [`unknown_team`].
+///
+/// # Returns
+/// `None` (with a warning) if the teams can't be listed or the snapshot has
+/// none, so no ticket is flagged.
+///
+/// # Errors
+/// Returns an error if the teams can't be listed with
+/// `--drop-unknown-teams`, which can't be honored without them.
+fn known_team_keys(
+    config: &Config,
+    linear: &LinearApi,
+) -> Result<Option<HashSet<String>>, Error> {
+    match teams::team_keys(config, linear) {
+        Ok(keys) if keys.is_empty() => {
+            log!(
+                Warn,
+                "Warning: no teams listed; not checking ticket team keys"
+            );
+            Ok(None)
+        }
+        Ok(keys) => Ok(Some(keys.into_iter().collect())),
+        Err(e) if config.unknown_teams == UnknownTeams::Drop => {
+            Err(e.context("Failed to list teams for --drop-unknown-teams"))
+        }
+        Err(e) => {
+            log!(
+                Warn,
+                "Warning: could not list teams to check ticket team keys: {e}"
+            );
+            Ok(None)
+        }
+    }
+}
+
+/// Whether `ticket` matches `--exclude-pattern`, logging it if so. A ticket
+/// that can't be checked is excluded too, so a denylisted ticket is never
+/// updated.
+#[must_use]
+pub fn exclude_ticket(config: &Config, ticket: &str) -> bool {
+    match config.is_excluded(ticket) {
+        Ok(false) => false,
+        Ok(true) => {
+            log!("excluding {ticket} (--exclude-pattern)");
+            true
+        }
+        Err(e) => {
+            log!(
+                Warn,
+                "excluding {ticket}: could not check --exclude-pattern: {e}"
+            );
+            true
+        }
+    }
+}
+
+/// With `--batch-lookups`, fetches every ticket into the store with batched
+/// queries, then the target workflow state of each of their teams in one
+/// query (see [`TicketStore::prefetch`] and [`prefetch_target_states`]).
+fn prefetch(ctx: &Context<'_>, issue_ids: &[String], state: &mut RunState) {
+    if ctx.config.lookups != Lookups::Batched {
+        return;
+    }
+    state.tickets.prefetch(issue_ids, &ctx.linear);
+    if ctx.config.dry_run {
+        return;
+    }
+    let mut team_ids: Vec<&str> = Vec::new();
+    for (_, ticket) in state.tickets.iter() {
+        if !team_ids.contains(&ticket.team_id.as_str()) {
+            team_ids.push(&ticket.team_id);
+        }
+    }
+    if let Err(e) = prefetch_target_states(ctx, &team_ids) {
+        log!(
+            Warn,
+            "Batched workflow state lookup failed, looking up per team: {e}"
+        );
+    }
+}
+
+/// Looks up the target workflow state of every team in `team_ids` not yet
+/// known, in one query of aliased `team(id:)` fields, and keeps them for
+/// [`resolve_target_state`].
+///
+/// # GraphQL Query
+/// ```graphql
+/// query($t0: String!, $t1: String!) {
+///   t0: team(id: $t0) { states { nodes { id name type } } }
+///   t1: team(id: $t1) { ... }
+/// }
+/// ```
+///
+/// # Errors
+/// Returns an error if the query fails or reports errors. Teams without a
+/// matching state are left to the per-team lookup (which reports it).
+fn prefetch_target_states(
+    ctx: &Context<'_>,
+    team_ids: &[&str],
+) -> Result<(), Error> {
+    let team_ids: Vec<&str> = team_ids
+        .iter()
+        .copied()
+        .filter(|team_id| known_target_state(ctx, team_id).is_none())
+        .collect();
+    if team_ids.is_empty() {
+        return Ok(());
+    }
+
+    let mut params = Vec::new();
+    let mut selections = Vec::new();
+    let mut variables = Vec::new();
+    for (idx, team_id) in team_ids.iter().enumerate() {
+        params.push(format!("$t{idx}: String!"));
+        selections.push(format!(
+            "t{idx}: team(id: $t{idx}) {{ states {{ nodes {{ id name type }} }} }}"
+        ));
+        variables.push(format!("\"t{idx}\": {}", utils::json_string(team_id)));
+    }
+    let query = format!(
+        r#"{{"query": "query({}) {{ {} }}", "variables": {{{}}}}}"#,
+        params.join(", "),
+        selections.join(" "),
+        variables.join(", ")
+    );
+    let response = utils::graphql_request(&query, &ctx.linear)?;
+    utils::ensure_no_graphql_errors(&response)?;
+
+    let count = team_ids.len();
+    log!(
+        Verbose,
+        "fetched workflow states of {count} team(s) in one query"
+    );
+    for (idx, team_id) in team_ids.iter().enumerate() {
+        let team = response.get(&format!("data.t{idx}"));
+        let states = json::nest("data.team", team.clone());
+        if let Ok(target) = find_target_state(ctx, &states) {
+            remember_target_state(ctx, team_id, &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Fetches every ticket (with its blocked-by relations) into the store and
+/// orders them so blockers come first. Relations are also kept in `state` for
+/// notes and reporting.
+fn order_by_dependencies(
+    ctx: &Context<'_>,
+    issue_ids: &[String],
+    state: &mut RunState,
+) -> Vec<String> {
+    let count = issue_ids.len();
+    log!("fetching blocked-by relations for {count} tickets");
+    for issue_id in issue_ids {
+        match state.tickets.get(issue_id, ctx.linear_for(issue_id)) {
+            Ok(ticket) => {
+                let relations = ticket.relations.clone().unwrap_or_default();
+                state.relations.insert(issue_id.clone(), relations);
+            }
+            Err(e) => {
+                log!(Warn, "Failed to fetch relations for {issue_id}: {e}");
+            }
+        }
+    }
+
+    let (ordered, cyclic) = relations::order(issue_ids, &state.relations);
+    if !cyclic.is_empty() {
+        let cyclic = cyclic.join(", ");
+        log!(
+            Warn,
+            "Blocking cycle between {cyclic}; processing them in input order"
+        );
+    }
+    ordered
+}
+
+/// Processes every ticket `feed` submits, one at a time or, with
+/// `--concurrency`, on a pool of worker threads.
+///
+/// # Errors
+/// Returns `feed`'s error (e.g. unreadable input), tickets submitted before
+/// it still being processed, or the error of a ticket whose record can't be
+/// written, which stops processing.
+fn process_tickets(
+    ctx: &Context<'_>,
+    state: &mut RunState,
+    feed: impl FnOnce(
+        &mut dyn FnMut(String) -> Result<(), Error>,
+    ) -> Result<(), Error>
+    + Send,
+) -> Result<(), Error> {
+    match ctx.config.concurrency {
+        Some(workers) if workers > 1 => {
+            process_concurrently(ctx, workers, state, feed)
+        }
+        _ => feed(&mut |issue_id| process_ticket(ctx, &issue_id, state)),
+    }
+}
+
+/// A ticket processed on a worker thread (`--concurrency`).
+struct Processed {
+    issue_id: String,
+    result: Result<(Outcome, Option<manifest::Entry>), Error>,
+    /// The ticket's metadata, if it could be fetched
+    ticket: Option<Ticket>,
+    /// Lines logged while processing the ticket
+    logs: Vec<String>,
+}
+
+/// Looks up and updates tickets on `workers` threads as `feed` submits them
+/// (`--concurrency`).
+///
+/// Only the Linear lookups and updates run concurrently. Results are recorded
+/// (URLs printed, comments posted, history written) on this thread in
+/// submission order, each ticket's log lines printed along with them, as soon
+/// as every earlier ticket is done, so output doesn't depend on timing.
+///
+/// # Errors
+/// Returns `feed`'s error, tickets submitted before it still being
+/// processed, or the error of a ticket whose record can't be written, after
+/// which no more results are recorded.
+fn process_concurrently(
+    ctx: &Context<'_>,
+    workers: usize,
+    state: &mut RunState,
+    feed: impl FnOnce(
+        &mut dyn FnMut(String) -> Result<(), Error>,
+    ) -> Result<(), Error>
+    + Send,
+) -> Result<(), Error> {
+    let (job_tx, job_rx) = mpsc::channel::<(usize, String)>();
+    let job_rx = Mutex::new(job_rx);
+    let (done_tx, done_rx) = mpsc::channel::<(usize, Processed)>();
+    let template = state.tickets.fork();
+    let log_settings = utils::log_settings();
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            let done_tx = done_tx.clone();
+            let (job_rx, template) = (&job_rx, &template);
+            scope.spawn(move || {
+                utils::with_log_settings(log_settings, || {
+                    let mut tickets = template.fork();
+                    while let Some((seq, issue_id)) = next_job(job_rx) {
+                        let processed =
+                            process_job(ctx, &mut tickets, issue_id);
+                        if done_tx.send((seq, processed)).is_err() {
+                            break;
+                        }
+                    }
+                });
+            });
+        }
+        drop(done_tx);
+
+        // Dropping `job_tx` once input ends lets idle workers exit.
+        let reader = scope.spawn(move || {
+            let mut seq = 0;
+            utils::with_log_settings(log_settings, || {
+                feed(&mut |issue_id| {
+                    // Sending only fails if every worker is gone, and then
+                    // nothing more can be processed anyway.
+                    let _ = job_tx.send((seq, issue_id));
+                    seq += 1;
+                    Ok(())
+                })
+            })
+        });
+
+        // Once a record can't be written, dropping `done_rx` stops the
+        // workers.
+        let mut result = Ok(());
+        let mut pending = BTreeMap::new();
+        let mut next = 0;
+        'recording: for (seq, processed) in done_rx {
+            pending.insert(seq, processed);
+            while let Some(processed) = pending.remove(&next) {
+                result = record_processed(ctx, processed, state);
+                if result.is_err() {
+                    break 'recording;
+                }
+                next += 1;
+            }
+        }
+        let read = reader.join().unwrap_or_else(|_| {
+            Err(Error::Other("Reading input failed".to_string()))
+        });
+        result.and(read)
+    })
+}
+
+/// Processes ticket `issue_id` on a worker thread, capturing what it logs.
+fn process_job(
+    ctx: &Context<'_>,
+    tickets: &mut TicketStore,
+    issue_id: String,
+) -> Processed {
+    let subject = Subject::Ticket(issue_id.clone());
+    let (result, logs) = utils::capture_logs(|| {
+        utils::with_log_subject(subject, || {
+            let url = issue_url(ctx.org_for(&issue_id), &issue_id);
+            log!("processing {url}");
+            update_single_ticket(ctx, tickets, &issue_id)
+        })
+    });
+    Processed {
+        ticket: tickets.cached(&issue_id).cloned(),
+        issue_id,
+        result,
+        logs,
+    }
+}
+
+/// Takes the next submitted ticket, or `None` once input has ended.
+fn next_job(
+    jobs: &Mutex<mpsc::Receiver<(usize, String)>>,
+) -> Option<(usize, String)> {
+    jobs.lock().ok()?.recv().ok()
+}
+
+/// Records a ticket processed on a worker thread, after printing what it
+/// logged.
+///
+/// # Errors
+/// Returns an error if the ticket's record can't be written.
+fn record_processed(
+    ctx: &Context<'_>,
+    processed: Processed,
+    state: &mut RunState,
+) -> Result<(), Error> {
+    utils::print_logs(&processed.logs);
+    let issue_id = processed.issue_id;
+    if let Some(ticket) = processed.ticket {
+        state.tickets.insert(&issue_id, ticket);
+    }
+    let result = processed.result.map(|(outcome, entry)| {
+        state.planned.extend(entry);
+        outcome
+    });
+    utils::with_log_subject(Subject::Ticket(issue_id.clone()), || {
+        record_outcome(ctx, &issue_id, result, state)
+    })
+}
+
+/// Processes a single ticket, printing its URL on success and logging
+/// failures to stderr.
+///
+/// # Errors
+/// Returns an error if the ticket's record can't be written.
+fn process_ticket(
+    ctx: &Context<'_>,
+    issue_id: &str,
+    state: &mut RunState,
+) -> Result<(), Error> {
+    utils::with_log_subject(Subject::Ticket(issue_id.to_string()), || {
+        let url = issue_url(ctx.org_for(issue_id), issue_id);
+        log!("processing {url}");
+
+        let result = update_single_ticket(ctx, &mut state.tickets, issue_id)
+            .map(|(outcome, entry)| {
+                state.planned.extend(entry);
+                outcome
+            });
+        record_outcome(ctx, issue_id, result, state)
+    })
+}
+
+/// Records the result of processing a ticket: prints its URL, remembers it
+/// for dependency reporting, and logs failures.
+///
+/// # Errors
+/// Returns an error if the ticket's record can't be written; the rest of
+/// its result is still recorded.
+fn record_outcome(
+    ctx: &Context<'_>,
+    issue_id: &str,
+    result: Result<Outcome, Error>,
+    state: &mut RunState,
+) -> Result<(), Error> {
+    explain_outcome(ctx, issue_id, &result, state);
+    let url = issue_url(ctx.org_for(issue_id), issue_id);
+    let outcome = match result {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            // Log error to stderr and output failed URL to stderr
+            log!(Warn, "Failed to update {url}: {e}");
+            log!(Warn, "{url}");
+            state.tally.failed += 1;
+            if e.root().to_string().starts_with(tickets::NOT_FOUND) {
+                state.tally.not_found += 1;
+            }
+            track_history(ctx, issue_id, None, state);
+            state.failures.push((issue_id.to_string(), e.to_string()));
+            return write_record(ctx, issue_id, Err(e), false, state);
+        }
+    };
+    track_history(ctx, issue_id, Some(outcome), state);
+
+    match outcome {
+        Outcome::Updated => state.tally.updated += 1,
+        Outcome::AlreadyCompleted => state.tally.already_completed += 1,
+        Outcome::AlreadyReleased => state.tally.already_released += 1,
+        Outcome::Skipped(_) => state.tally.skipped += 1,
+    }
+
+    // In dry-run, only tickets that would be updated are printed.
+    let print = match outcome {
+        Outcome::Updated => true,
+        Outcome::AlreadyCompleted => !ctx.config.dry_run,
+        Outcome::AlreadyReleased | Outcome::Skipped(_) => false,
+    };
+    state.any_output |= print;
+    let written = write_record(ctx, issue_id, Ok(outcome), print, state);
+
+    if matches!(outcome, Outcome::Updated | Outcome::AlreadyCompleted) {
+        state.completed.push(issue_id.to_string());
+    }
+
+    if outcome == Outcome::Updated {
+        if let Some(ref project) = ctx.project
+            && state
+                .tickets
+                .cached(issue_id)
+                .is_some_and(|ticket| project.contains(&ticket.id))
+        {
+            state.project_updated += 1;
+        }
+        state.updated.insert(issue_id.to_string());
+        link_release(ctx, issue_id, state);
+        label_release(ctx, issue_id, state);
+        attach_release(ctx, issue_id, state);
+        send_webhook(ctx, issue_id, state);
+        comment_release(ctx, issue_id, state);
+        handle_relations_after_update(ctx, issue_id, state);
+    }
+
+    if let Some(ref mut queue) = state.queue
+        && let Err(e) = queue.complete(issue_id)
+    {
+        log!(Warn, "{e}");
+    }
+    if let Some(ref checkpoint) = ctx.checkpoint
+        && let Err(e) = checkpoint.record(issue_id)
+    {
+        log!(Warn, "{e}");
+    }
+    written
+}
+
+/// With `--add-to-project` or `--linear-cycle`, adds an updated ticket to
+/// the project and its team's cycle (or logs that it would, in dry-run).
+/// Failures are logged and don't fail the ticket.
+fn link_release(ctx: &Context<'_>, issue_id: &str, state: &RunState) {
+    if ctx.add_to_project.is_none() && ctx.config.linear_cycle.is_none() {
+        return;
+    }
+    let Some(ticket) = state.tickets.cached(issue_id) else {
+        return;
+    };
+    let cycle = team_cycle(ctx, ticket);
+    let mut targets = Vec::new();
+    if let Some((_, ref name)) = ctx.add_to_project {
+        targets.push(format!("project {name}"));
+    }
+    if let Some(ref cycle) = cycle {
+        targets.push(cycle.to_string());
+    }
+    if targets.is_empty() {
+        return;
+    }
+    let targets = targets.join(" and ");
+    let identifier = &ticket.identifier;
+    if ctx.config.dry_run {
+        log!("Would add {identifier} to {targets}");
+        ctx.estimate.add(Provider::Linear, Request::Mutation, 1);
+        return;
+    }
+    let project_id = ctx.add_to_project.as_ref().map(|(id, _)| id.as_str());
+    let cycle_id = cycle.as_ref().map(|cycle| cycle.id.as_str());
+    match link_issue(
+        &ticket.id,
+        project_id,
+        cycle_id,
+        ctx.linear_of_team(&ticket.team_key),
+    ) {
+        Ok(()) => {
+            log!("Added {identifier} to {targets}");
+        }
+        Err(e) => {
+            log!(Warn, "Failed to add {identifier} to {targets}: {e}");
+        }
+    }
+}
+
+/// With `--add-label`, applies the label to an updated ticket that doesn't
+/// carry it (or logs that it would, in dry-run). Failures are logged and
+/// don't fail the ticket.
+fn label_release(ctx: &Context<'_>, issue_id: &str, state: &RunState) {
+    let Some(ref label) = ctx.label else {
+        return;
+    };
+    let Some(ticket) = state.tickets.cached(issue_id) else {
+        return;
+    };
+    let identifier = &ticket.identifier;
+    let name = &label.name;
+    if label.is_on(&ticket.labels) {
+        log!(Verbose, "{identifier} already has label {name}");
+        return;
+    }
+    if ctx.config.dry_run {
+        log!("Would apply label {name} to {identifier}");
+        ctx.estimate.add(Provider::Linear, Request::Mutation, 1);
+        return;
+    }
+    match label.apply(&ticket.id, &ctx.linear) {
+        Ok(()) => {
+            log!("Applied label {name} to {identifier}");
+        }
+        Err(e) => {
+            log!(Warn, "Failed to apply label {name} to {identifier}: {e}");
+        }
+    }
+}
+
+/// With `--attach-release`, attaches the release to an updated ticket (or
+/// logs that it would, in dry-run). Failures are logged and don't fail the
+/// ticket.
+fn attach_release(ctx: &Context<'_>, issue_id: &str, state: &RunState) {
+    let Some(ref attachment) = ctx.attachment else {
+        return;
+    };
+    let Some(ticket) = state.tickets.cached(issue_id) else {
+        return;
+    };
+    let identifier = &ticket.identifier;
+    let tag = &attachment.tag;
+    if ctx.config.dry_run {
+        log!("Would attach release {tag} to {identifier}");
+        ctx.estimate.add(Provider::Linear, Request::Mutation, 1);
+        return;
+    }
+    match attachment.attach(&ticket.id, &ctx.linear) {
+        Ok(()) => {
+            log!("Attached release {tag} to {identifier}");
+        }
+        Err(e) => {
+            log!(Warn, "Failed to attach release {tag} to {identifier}: {e}");
+        }
+    }
+}
+
+/// With `--webhook-url`, announces an updated ticket to the endpoint (or
+/// logs that it would, in dry-run). Failures are logged and don't fail the
+/// ticket.
+fn send_webhook(ctx: &Context<'_>, issue_id: &str, state: &RunState) {
+    let Some(ref webhook) = ctx.webhook else {
+        return;
+    };
+    let endpoint = &webhook.url;
+    if ctx.config.dry_run {
+        log!(
+            "Would send a {TICKET_RELEASED} event for {issue_id} to {endpoint}"
+        );
+        ctx.estimate.add(Provider::Webhook, Request::Mutation, 1);
+        return;
+    }
+    let url = issue_url(ctx.org_for(issue_id), issue_id);
+    let pr = ctx.sources.lock().ok().and_then(|sources| {
+        sources.get(issue_id).and_then(|prs| prs.first().cloned())
+    });
+    let event = webhook::Event {
+        ticket: issue_id,
+        url: &url,
+        release_tag: ctx.config.release_tag.as_deref(),
+        from_state: state
+            .tickets
+            .cached(issue_id)
+            .map(|ticket| ticket.state_name.as_str()),
+        to_state: target_state(ctx),
+        pr: pr.as_ref(),
+    };
+    if let Err(e) = webhook.send(&event) {
+        log!(
+            Warn,
+            "Failed to send the {TICKET_RELEASED} event for {issue_id}: {e}"
+        );
+    }
+}
+
+/// The `--linear-cycle` of `ticket`'s team, looked up once per team. A team
+/// without it, or whose cycles can't be listed, is logged.
+fn team_cycle(ctx: &Context<'_>, ticket: &Ticket) -> Option<TeamCycle> {
+    let spec = ctx.config.linear_cycle?;
+    if let Ok(cycles) = ctx.cycles.lock()
+        && let Some(cycle) = cycles.get(&ticket.team_id)
+    {
+        return cycle.clone();
+    }
+    let team = &ticket.team_key;
+    let cycle = match cycle::find(
+        spec,
+        &ticket.team_id,
+        ctx.linear_of_team(team),
+    ) {
+        Ok(Some(cycle)) => Some(cycle),
+        Ok(None) => {
+            log!(
+                Warn,
+                "team {team} has no {spec} cycle; its tickets aren't added to one"
+            );
+            None
+        }
+        Err(e) => {
+            log!(
+                Warn,
+                "Failed to look up the {spec} cycle of team {team}: {e}"
+            );
+            None
+        }
+    };
+    if let Ok(mut cycles) = ctx.cycles.lock() {
+        cycles.insert(ticket.team_id.clone(), cycle.clone());
+    }
+    cycle
+}
+
+/// With `--journal`, a new ID for the next state change mutation, so its
+/// journal entries can be matched with the request.
+fn journal_request_id(ctx: &Context<'_>) -> Option<String> {
+    ctx.config.journal.as_ref().map(|_| journal::request_id())
+}
+
+/// `linear`, sending its requests with `X-Request-Id: request_id` if given.
+fn with_request_id<'a>(
+    linear: &'a LinearApi,
+    request_id: Option<&str>,
+) -> Cow<'a, LinearApi> {
+    match request_id {
+        Some(request_id) => {
+            let mut linear = linear.clone();
+            linear.headers.push(format!("X-Request-Id: {request_id}"));
+            Cow::Owned(linear)
+        }
+        None => Cow::Borrowed(linear),
+    }
+}
+
+/// With `--journal`, records the attempted move of `issue_ids` to the state
+/// of `target` (its ID and the mutation's request ID) and how it ended, with
+/// the state each ticket was moved out of (except in dry-run), so audits can
+/// tell what a run changed and `rollback` can move the tickets back.
+fn journal_attempt(
+    ctx: &Context<'_>,
+    tickets: &TicketStore,
+    issue_ids: &[&str],
+    target: (&str, Option<&str>),
+    result: &Result<(), Error>,
+) {
+    let Some(ref path) = ctx.config.journal else {
+        return;
+    };
+    if ctx.config.dry_run {
+        return;
+    }
+    let (to_state_id, request_id) = target;
+    let outcome = match result {
+        Ok(()) => journal::Outcome::Moved,
+        Err(e) => journal::Outcome::Failed(e.to_string()),
+    };
+    let timestamp = utils::utc_now();
+    for &issue_id in issue_ids {
+        let Some(ticket) = tickets.cached(issue_id) else {
+            log!(Warn, "{issue_id} not fetched, not journaling it");
+            continue;
+        };
+        let entry = journal::Entry {
+            timestamp: timestamp.clone(),
+            ticket: ticket.identifier.clone(),
+            from_state_id: ticket.state_id.clone(),
+            to_state_id: to_state_id.to_string(),
+            from_state: ticket.state_name.clone(),
+            to_state: target_state(ctx).to_string(),
+            outcome: outcome.clone(),
+            request_id: request_id.map(String::from),
+        };
+        if let Err(e) = journal::record(path, &entry) {
+            log!(Warn, "{e}");
+        }
+    }
+}
+
+/// With `--comment-release`, comments the release (and its marker) on a
+/// completed ticket.
+fn comment_release(ctx: &Context<'_>, issue_id: &str, state: &mut RunState) {
+    let Some((ref tag, _)) = ctx.release else {
+        return;
+    };
+    // Comment on the current identifier if the ticket was renamed.
+    let ticket = state
+        .tickets
+        .cached(issue_id)
+        .map_or(issue_id, |ticket| ticket.identifier.as_str())
+        .to_string();
+
+    if ctx.config.dry_run {
+        log!("Would comment release {tag} on {ticket}");
+        ctx.estimate.add(Provider::Linear, Request::Mutation, 1);
+        return;
+    }
+    let key = format!("release:{tag}");
+    let body = match ctx.comment_template {
+        Some(ref template) => {
+            let prs = ctx
+                .sources
+                .lock()
+                .ok()
+                .and_then(|sources| sources.get(issue_id).cloned())
+                .unwrap_or_default();
+            template.render(&ticket, &prs)
+        }
+        None => comments::release_comment(tag),
+    };
+    match state
+        .commenter
+        .post(&ticket, &key, &body, ctx.linear_for(&ticket))
+    {
+        Ok(Posted::Created) => {
+            log!("Commented release {tag} on {ticket}");
+        }
+        Ok(Posted::AlreadyPosted) => {
+            log!("Already commented release {tag} on {ticket}");
+        }
+        Err(e) => {
+            log!(Warn, "Failed to comment release {tag} on {ticket}: {e}");
+        }
+    }
+}
+
+/// After a ticket is completed, records open blockers and (with
+/// `--note-blocked`) comments on the in-release tickets it was blocking.
+fn handle_relations_after_update(
+    ctx: &Context<'_>,
+    issue_id: &str,
+    state: &mut RunState,
+) {
+    let Some(relations) = state.relations.get(issue_id) else {
+        return;
+    };
+
+    if ctx.config.dependencies == Dependencies::OrderAndNote {
+        let body = format!(
+            "Blocker {issue_id} was completed as part of this release."
+        );
+        for blocked in &relations.blocks {
+            let blocked = &blocked.identifier;
+            if !state.relations.contains_key(blocked) {
+                continue;
+            }
+            if ctx.config.dry_run {
+                log!("Would note on {blocked}: {body}");
+                ctx.estimate.add(Provider::Linear, Request::Mutation, 1);
+                continue;
+            }
+            let key = format!("blocked-by:{issue_id}");
+            match state.commenter.post(
+                blocked,
+                &key,
+                &body,
+                ctx.linear_for(blocked),
+            ) {
+                Ok(Posted::Created) => {
+                    log!("Noted on {blocked} that {issue_id} is done");
+                }
+                Ok(Posted::AlreadyPosted) => {
+                    log!("Already noted on {blocked} that {issue_id} is done");
+                }
+                Err(e) => {
+                    log!(Warn, "Failed to add note to {blocked}: {e}");
+                }
+            }
+        }
+    }
+
+    let open_blockers: Vec<RelatedIssue> = relations
+        .blockers
+        .iter()
+        .filter(|blocker| {
+            blocker.is_open() && !state.updated.contains(&blocker.identifier)
+        })
+        .cloned()
+        .collect();
+    for blocker in open_blockers {
+        let RelatedIssue {
+            identifier,
+            state_name,
+            ..
+        } = &blocker;
+        log!(
+            Warn,
+            "Issue {issue_id} is blocked by {identifier} which is still open ({state_name})"
+        );
+        state.open_blockers.push((issue_id.to_string(), blocker));
+    }
+}
+
+/// With `--release-issue-team`, creates the release's tracking issue listing
+/// the tickets completed in this run.
+///
+/// # Errors
+/// Returns an error if the issue cannot be created.
+fn create_release_issue(
+    ctx: &Context<'_>,
+    state: &RunState,
+) -> Result<(), Error> {
+    let (Some(team), Some(tag)) = (
+        ctx.config.release_issue_team.as_deref(),
+        ctx.config.release_tag.as_deref(),
+    ) else {
+        return Ok(());
+    };
+
+    let items: Vec<ChecklistItem> = state
+        .completed
+        .iter()
+        .map(|issue_id| match state.tickets.cached(issue_id) {
+            Some(ticket) => ChecklistItem {
+                identifier: ticket.identifier.clone(),
+                title: ticket.title.clone(),
+            },
+            None => ChecklistItem {
+                identifier: issue_id.clone(),
+                title: String::new(),
+            },
+        })
+        .collect();
+    if ctx.config.dry_run && !items.is_empty() {
+        // The team and existing-issue lookups, then the new issue
+        ctx.estimate.add(Provider::Linear, Request::Query, 2);
+        ctx.estimate.add(Provider::Linear, Request::Mutation, 1);
+    }
+    release_issue::create(
+        team,
+        tag,
+        &items,
+        ctx.config.dry_run,
+        ctx.linear_of_team(team),
+    )
+}
+
+/// Logs the end-of-run list of tickets completed while blocked.
+fn report_open_blockers(ctx: &Context<'_>, state: &RunState) {
+    if state.open_blockers.is_empty() {
+        return;
+    }
+    let count = state.open_blockers.len().to_string();
+    let header = ctx
+        .messages
+        .render("blockers.header", &[("count", count.as_str())]);
+    log!("{header}");
+    for (issue_id, blocker) in &state.open_blockers {
+        let item = ctx.messages.render(
+            "blockers.item",
+            &[
+                ("ticket", issue_id.as_str()),
+                ("blocker", blocker.identifier.as_str()),
+                ("state", blocker.state_name.as_str()),
+            ],
+        );
+        log!("{item}");
+    }
+}
+
+/// With `--history-file`, flags a ticket completed in this release that an
+/// earlier release already completed, and records its outcome (except in
+/// dry-run). `None` records a failure, which may quarantine the ticket.
+fn track_history(
+    ctx: &Context<'_>,
+    issue_id: &str,
+    outcome: Option<Outcome>,
+    state: &mut RunState,
+) {
+    let Some(ref history) = ctx.history else {
+        return;
+    };
+
+    if matches!(outcome, Some(Outcome::Updated | Outcome::AlreadyCompleted)) {
+        let releases = history.earlier_releases(issue_id);
+        if !releases.is_empty() {
+            let releases = releases.join(", ");
+            log!(
+                Warn,
+                "Issue {issue_id} was already completed by an earlier release ({releases})"
+            );
+            state.repeat_releases.push((issue_id.to_string(), releases));
+        }
+    }
+
+    if ctx.config.dry_run {
+        return;
+    }
+    if let Err(e) = history.record(issue_id, outcome_name(ctx, outcome)) {
+        log!(Warn, "{e}");
+        return;
+    }
+    if let Some(after) = ctx.config.quarantine_after
+        && outcome.is_none()
+        && history.failed_runs(issue_id) + 1 >= after
+    {
+        log!(
+            Warn,
+            "Issue {issue_id} failed in {after} run(s) in a row and is quarantined from the next run"
+        );
+        state.newly_quarantined.push(issue_id.to_string());
+    }
+}
+
+/// With `--attach-release`, looks up the release and the tickets it is
+/// already attached to, logging how many there are.
+///
+/// # Errors
+/// Returns an error if the release or its attachments can't be looked up.
+fn load_attachment(
+    config: &Config,
+    linear: &LinearApi,
+) -> Result<Option<AttachedRelease>, Error> {
+    let Some(ref tag) = config.release_tag else {
+        return Ok(None);
+    };
+    if config.release_attachment == ReleaseAttachment::Off {
+        return Ok(None);
+    }
+    let attachment = AttachedRelease::load(tag, &config.github()?, linear)?;
+    let count = attachment.count();
+    log!("Release {tag} is already attached to {count} ticket(s)");
+    Ok(Some(attachment))
+}
+
+/// Looks up the `--set-custom-field` label group, and its value label,
+/// creating the value if it doesn't exist (except in dry-run, which logs
+/// that it would).
+///
+/// # Errors
+/// Returns an error if the workspace has no such label group, or the value
+/// can't be looked up or created.
+fn load_custom_field(
+    field: &CustomField,
+    dry_run: bool,
+    linear: &LinearApi,
+) -> Result<FieldValue, Error> {
+    let name = &field.name;
+    let group = labels::find_group(name, linear)?.ok_or_else(|| {
+        Error::Config(format!(
+            "--set-custom-field: the workspace has no label group named {name}"
+        ))
+    })?;
+    let label = &field.value;
+    let id = if group
+        .labels
+        .iter()
+        .any(|(_, existing)| existing.eq_ignore_ascii_case(label))
+    {
+        None
+    } else if dry_run {
+        log!("Label {label} of {name} doesn't exist; the run would create it");
+        None
+    } else {
+        let id = labels::create(label, Some(&group.id), linear)?;
+        log!("Created label {label} of {name}");
+        Some(id)
+    };
+    Ok(FieldValue::new(field, group, id))
+}
+
+/// With `--set-custom-field`, the label change setting the value on the
+/// tickets `issue_ids`, sent with the mutation moving them.
+fn custom_field_change(
+    ctx: &Context<'_>,
+    tickets: &TicketStore,
+    issue_ids: &[&str],
+) -> LabelChange {
+    ctx.custom_field
+        .as_ref()
+        .map(|value| {
+            value.change(issue_ids.iter().filter_map(|issue_id| {
+                tickets
+                    .cached(issue_id)
+                    .map(|ticket| ticket.labels.as_slice())
+            }))
+        })
+        .unwrap_or_default()
+}
+
+/// Looks up the `--add-label` label, creating it if it doesn't exist (except
+/// in dry-run, which logs that it would).
+///
+/// # Errors
+/// Returns an error if the label can't be looked up or created.
+fn load_label(
+    config: &Config,
+    linear: &LinearApi,
+) -> Result<Option<Label>, Error> {
+    let Some(ref name) = config.add_label else {
+        return Ok(None);
+    };
+    let id = match labels::find(name, linear)? {
+        Some(id) => Some(id),
+        None if config.dry_run => {
+            log!("Label {name} doesn't exist; the run would create it");
+            None
+        }
+        None => {
+            let id = labels::create(name, None, linear)?;
+            log!("Created label {name}");
+            Some(id)
+        }
+    };
+    Ok(Some(Label {
+        name: name.clone(),
+        id,
+    }))
+}
+
+/// Loads the `--history-file` and records the `--clear-quarantine` tickets
+/// in it (except in dry-run).
+///
+/// # Errors
+/// Returns an error if the file can't be read or written.
+fn load_history(config: &Config) -> Result<Option<History>, Error> {
+    let (Some(path), Some(tag)) = (
+        config.history_file.as_deref(),
+        config.release_tag.as_deref(),
+    ) else {
+        return Ok(None);
+    };
+    let mut history = History::load(path, tag)?;
+    for ticket in &config.clear_quarantine {
+        history.clear(ticket);
+        if config.dry_run {
+            log!("Would clear the quarantine of {ticket}");
+            continue;
+        }
+        history.record(ticket, history::CLEARED)?;
+        log!("Cleared the quarantine of {ticket}");
+    }
+    Ok(Some(history))
+}
+
+/// Whether `issue_id` failed in its last `--quarantine-after` runs.
+fn quarantined(ctx: &Context<'_>, issue_id: &str) -> bool {
+    let (Some(after), Some(history)) =
+        (ctx.config.quarantine_after, ctx.history.as_ref())
+    else {
+        return false;
+    };
+    history.failed_runs(issue_id) >= after
+}
+
+/// The name of a ticket's outcome in the `--history-file` and result
+/// records. `None` is a failure.
+fn outcome_name(ctx: &Context<'_>, outcome: Option<Outcome>) -> &'static str {
+    match outcome {
+        // Tickets moved to a state short of completion aren't completed by
+        // this release.
+        Some(Outcome::Updated) if !completes(ctx) => "moved",
+        Some(outcome) => outcome.history_name(),
+        None => "failed",
+    }
+}
+
+/// Writes a processed ticket to stdout: in text format its URL (or ID, per
+/// `--url-format`) if `print`, otherwise its result record.
+///
+/// # Errors
+/// Returns an error if the record can't be written (see
+/// [`RecordWriter::write`]).
+fn write_record(
+    ctx: &Context<'_>,
+    issue_id: &str,
+    result: Result<Outcome, Error>,
+    print: bool,
+    state: &mut RunState,
+) -> Result<(), Error> {
+    // The --diff table replaces the list of tickets.
+    let text =
+        (print && state.diff.is_none()).then(|| output_line(ctx, issue_id));
+    let (outcome, error) = match result {
+        Ok(outcome) => (Some(outcome), None),
+        Err(e) => (None, Some(e.to_string())),
+    };
+    let ticket = state.tickets.cached(issue_id);
+    if let Some(ref mut diff) = state.diff {
+        diff.push(diff::Row {
+            ticket: issue_id.to_string(),
+            team: ticket.map(|ticket| ticket.team_key.clone()),
+            from_state: ticket.map(|ticket| ticket.state_name.clone()),
+            to_state: (outcome == Some(Outcome::Updated))
+                .then(|| target_state(ctx).to_string()),
+            result: diff_result(outcome, error.as_deref()),
+        });
+    }
+    state.output.write(text.as_deref(), || UpdateRecord {
+        ticket: issue_id.to_string(),
+        url: issue_url(ctx.org_for(issue_id), issue_id),
+        outcome: outcome_name(ctx, outcome),
+        skip_reason: match outcome {
+            Some(Outcome::Skipped(reason)) => Some(reason),
+            _ => None,
+        },
+        from_state: ticket.map(|ticket| ticket.state_name.clone()),
+        to_state: (outcome == Some(Outcome::Updated))
+            .then(|| target_state(ctx).to_string()),
+        pr: ctx.sources.lock().ok().and_then(|sources| {
+            sources.get(issue_id).and_then(|prs| prs.first().cloned())
+        }),
+        error,
+        dry_run: ctx.config.dry_run,
+    })
+}
+
+/// What would happen to a ticket, for its `--diff` row.
+fn diff_result(outcome: Option<Outcome>, error: Option<&str>) -> String {
+    match outcome {
+        Some(Outcome::Updated) => "update".to_string(),
+        Some(Outcome::AlreadyCompleted) => "already completed".to_string(),
+        Some(Outcome::AlreadyReleased) => "already released".to_string(),
+        Some(Outcome::Skipped(reason)) => {
+            format!("skip: {}", explain::skip_reason(reason))
+        }
+        None => {
+            let error = error.unwrap_or_default();
+            format!("fail: {}", error.lines().next().unwrap_or_default())
+        }
+    }
+}
+
+/// Prints what is held back until the end of the run: the `json` array of
+/// records, or the `--diff` table.
+fn finish_output(state: &mut RunState) {
+    state.output.finish();
+    if let Some(ref diff) = state.diff {
+        diff.print();
+    }
+}
+
+/// Lists the tickets an earlier release already completed, if any.
+fn report_repeat_releases(ctx: &Context<'_>, state: &RunState) {
+    if state.repeat_releases.is_empty() {
+        return;
+    }
+    let count = state.repeat_releases.len().to_string();
+    let header = ctx
+        .messages
+        .render("repeat.header", &[("count", count.as_str())]);
+    log!("{header}");
+    for (issue_id, releases) in &state.repeat_releases {
+        let item = ctx.messages.render(
+            "repeat.item",
+            &[
+                ("ticket", issue_id.as_str()),
+                ("releases", releases.as_str()),
+            ],
+        );
+        log!("{item}");
+    }
+}
+
+/// With `--quarantine-after`, logs the end-of-run lists of tickets skipped
+/// as quarantined and of tickets this run's failures quarantine.
+fn report_quarantine(ctx: &Context<'_>, state: &RunState) {
+    let Some(after) = ctx.config.quarantine_after else {
+        return;
+    };
+    let skipped: Vec<String> = ctx
+        .dropped
+        .lock()
+        .map(|dropped| {
+            dropped
+                .iter()
+                .filter(|(_, reason)| *reason == SkipReason::Quarantined)
+                .map(|(issue_id, _)| issue_id.clone())
+                .collect()
+        })
+        .unwrap_or_default();
+    let runs = after.to_string();
+    for (key, tickets) in [
+        ("quarantine.header", &skipped),
+        ("quarantine.new", &state.newly_quarantined),
+    ] {
+        if tickets.is_empty() {
+            continue;
+        }
+        let count = tickets.len().to_string();
+        let header = ctx
+            .messages
+            .render(key, &[("count", count.as_str()), ("runs", runs.as_str())]);
+        log!("{header}");
+        for issue_id in tickets {
+            let item = ctx
+                .messages
+                .render("quarantine.item", &[("ticket", issue_id.as_str())]);
+            log!("{item}");
+        }
+    }
+}
+
+/// With `--compare-manifest`, reports how the run differs from the dry run
+/// that wrote the manifest: planned tickets whose state changed in between
+/// (e.g. closed by hand), planned tickets this run didn't process, and tickets
+/// updated without having been planned.
+fn report_delta(ctx: &Context<'_>, state: &RunState) {
+    let Some(ref preview) = state.preview else {
+        return;
+    };
+    // Keyed by current identifier, as the manifest is.
+    let seen: BTreeMap<&str, (&str, bool)> = state
+        .tickets
+        .iter()
+        .map(|(issue_id, ticket)| {
+            let updated = state.updated.contains(issue_id);
+            (
+                ticket.identifier.as_str(),
+                (ticket.state_name.as_str(), updated),
+            )
+        })
+        .collect();
+    let planned: HashSet<&str> =
+        preview.iter().map(|entry| entry.ticket.as_str()).collect();
+
+    let mut items = Vec::new();
+    for entry in preview {
+        let ticket = entry.ticket.as_str();
+        match seen.get(ticket) {
+            None => items.push(
+                ctx.messages.render("delta.missing", &[("ticket", ticket)]),
+            ),
+            Some(&(current, _))
+                if !current.eq_ignore_ascii_case(&entry.from_state) =>
+            {
+                items.push(ctx.messages.render(
+                    "delta.changed",
+                    &[
+                        ("ticket", ticket),
+                        ("from", entry.from_state.as_str()),
+                        ("state", current),
+                    ],
+                ));
+            }
+            Some(_) => {}
+        }
+    }
+    for (&ticket, &(_, updated)) in &seen {
+        if updated && !planned.contains(ticket) {
+            items.push(
+                ctx.messages
+                    .render("delta.unplanned", &[("ticket", ticket)]),
+            );
+        }
+    }
+
+    let manifest = ctx.config.compare_manifest.as_deref().unwrap_or_default();
+    if items.is_empty() {
+        let none = ctx.messages.render("delta.none", &[("manifest", manifest)]);
+        log!("{none}");
+        return;
+    }
+    let count = items.len().to_string();
+    let header = ctx.messages.render(
+        "delta.header",
+        &[("count", count.as_str()), ("manifest", manifest)],
+    );
+    log!("{header}");
+    for item in items {
+        log!("{item}");
+    }
+}
+
+/// With `--repeat-release fail`, fails the run if any ticket was already
+/// completed by an earlier release.
+///
+/// # Errors
+/// Returns an error naming the number of such tickets.
+fn check_repeat_releases(
+    ctx: &Context<'_>,
+    state: &RunState,
+) -> Result<(), Error> {
+    let count = state.repeat_releases.len();
+    if ctx.config.repeat_release == RepeatRelease::Fail && count > 0 {
+        return Err(Error::Other(format!(
+            "{count} ticket(s) were already completed by an earlier release (--repeat-release fail)"
+        )));
+    }
+    Ok(())
+}
+
+/// With `--fail-on-partial`, fails the run if any ticket failed to update
+/// (`failed` is their number) with an [`Error::Partial`], exiting with
+/// [`crate::exit_status::PARTIAL`]. Shared with the Jira tracker.
+///
+/// # Errors
+/// Returns an error naming the number of failed tickets.
+pub fn check_failures(config: &Config, failed: usize) -> Result<(), Error> {
+    if config.partial_failure == PartialFailure::Fail && failed > 0 {
+        return Err(Error::Partial(format!(
+            "{failed} ticket(s) failed to update (--fail-on-partial)"
+        )));
+    }
+    Ok(())
+}
+
+/// With `--linear-project`, logs the project's completion after the run:
+/// its completion when loaded plus the project tickets updated (or that
+/// would be, in dry-run).
+fn report_project_after(ctx: &Context<'_>, state: &RunState) {
+    let Some(ref project) = ctx.project else {
+        return;
+    };
+    let mut progress = project.progress();
+    progress.completed += state.project_updated;
+    let key = if ctx.config.dry_run {
+        "project.would_after"
+    } else {
+        "project.after"
+    };
+    report_project(ctx, key, Some(progress));
+}
 
-    log!("reading ticket IDs from input");
-    // Process tickets as they arrive from input (streaming), so an upstream
-    // stage can keep the pipeline flowing and we can start updating tickets
-    // immediately.
-    utils::for_each_input_line(&config.input_sources, |input_line| {
-        let input_line = input_line.trim();
-        if input_line.is_empty() {
-            return Ok(());
-        }
+/// Logs a project completion line; `None` is the project's completion when
+/// loaded.
+fn report_project(ctx: &Context<'_>, key: &str, progress: Option<Progress>) {
+    let Some(ref project) = ctx.project else {
+        return;
+    };
+    let progress = progress.unwrap_or_else(|| project.progress());
+    let completed = progress.completed.to_string();
+    let total = progress.total.to_string();
+    let percent = progress.percent().to_string();
+    let line = ctx.messages.render(
+        key,
+        &[
+            ("project", project.name.as_str()),
+            ("completed", completed.as_str()),
+            ("total", total.as_str()),
+            ("percent", percent.as_str()),
+        ],
+    );
+    log!("{line}");
+}
 
-        let issue_id = match parse_issue_id(input_line) {
-            Ok(issue_id) => issue_id,
-            Err(e) => {
-                log!("Invalid input {input_line}: {e}");
-                log!("{input_line}");
-                return Ok(());
-            }
-        };
+/// In a dry run, logs the requests the real run would send (see
+/// [`crate::estimate`]): besides those left out while processing tickets,
+/// the target state lookup of each team whose state ID isn't known yet and
+/// the state changes, in batches per team with `--quiet-updates`.
+fn report_estimate(ctx: &Context<'_>, state: &RunState) {
+    if !ctx.config.dry_run {
+        return;
+    }
+    let mut by_team: HashMap<&str, u64> = HashMap::new();
+    for issue_id in &state.updated {
+        let team_id = state
+            .tickets
+            .cached(issue_id)
+            .map_or("", |ticket| ticket.team_id.as_str());
+        *by_team.entry(team_id).or_default() += 1;
+    }
+    let lookups = by_team
+        .keys()
+        .filter(|team_id| known_target_state(ctx, team_id).is_none())
+        .count() as u64;
+    // With --batch-lookups, the states of every team are looked up at once
+    let lookups = match ctx.config.lookups {
+        Lookups::Batched => lookups.min(1),
+        Lookups::Single => lookups,
+    };
+    let updates = if ctx.config.notifications == Notifications::Quiet
+        && ctx.capabilities.batch_update
+    {
+        by_team
+            .values()
+            .map(|count| count.div_ceil(QUIET_BATCH_SIZE as u64))
+            .sum()
+    } else {
+        by_team.values().sum()
+    };
+    ctx.estimate.add(Provider::Linear, Request::Query, lookups);
+    ctx.estimate
+        .add(Provider::Linear, Request::Mutation, updates);
+    ctx.estimate.report(NAME);
+}
 
-        let url = issue_url(&linear_org, &issue_id);
-        log!("processing {url}");
+/// Logs the localized end-of-run summary of ticket counts, with tickets not
+/// found counted apart from other failures, and the run's elapsed time.
+fn report_summary(ctx: &Context<'_>, state: &RunState) {
+    let tally = &state.tally;
+    let updated_key = match (ctx.config.dry_run, has_custom_target(ctx)) {
+        (true, false) => "summary.would_update",
+        (false, false) => "summary.updated",
+        (true, true) => "summary.would_move",
+        (false, true) => "summary.moved",
+    };
+    let target = target_state(ctx);
 
-        match update_single_ticket(
-            &issue_id,
-            &linear_org,
-            &linear_api_key,
-            config.dry_run,
-            config.update_all_statuses,
-        ) {
-            Ok(Some(success_url)) => {
-                println!("{success_url}");
-                any_output = true;
-            }
-            Ok(None) => {} // No output (ticket already completed in dry-run)
-            Err(e) => {
-                // Log error to stderr and output failed URL to stderr
-                let url = issue_url(&linear_org, &issue_id);
-                log!("Failed to update {url}: {e}");
-                log!("{url}");
-            }
-        }
-        Ok(())
-    })?;
+    let processed = tally.updated
+        + tally.already_completed
+        + tally.already_released
+        + tally.skipped
+        + tally.failed;
 
-    log!("done");
-    if !any_output {
-        log!("no changes made");
+    let header = ctx.messages.render("summary.header", &[]);
+    log!("{header}");
+    let mut lines = vec![
+        ("summary.processed", processed),
+        (updated_key, tally.updated),
+        ("summary.already_completed", tally.already_completed),
+    ];
+    if ctx.release.is_some() {
+        lines.push(("summary.already_released", tally.already_released));
+    }
+    lines.push((
+        "summary.skipped",
+        tally.skipped - tally.quarantined - tally.checkpointed,
+    ));
+    if ctx.config.quarantine_after.is_some() {
+        lines.push(("summary.quarantined", tally.quarantined));
+    }
+    if ctx.checkpoint.is_some() {
+        lines.push(("summary.checkpointed", tally.checkpointed));
+    }
+    lines.push(("summary.not_found", tally.not_found));
+    lines.push(("summary.failed", tally.failed - tally.not_found));
+    for (key, count) in lines {
+        let count = count.to_string();
+        let line = ctx
+            .messages
+            .render(key, &[("count", count.as_str()), ("state", target)]);
+        log!("  {line}");
     }
 
-    Ok(())
+    if !state.any_output {
+        let no_changes = ctx.messages.render("summary.no_changes", &[]);
+        log!("{no_changes}");
+    }
+    let elapsed = utils::format_elapsed(ctx.started.elapsed());
+    let elapsed = ctx
+        .messages
+        .render("summary.elapsed", &[("elapsed", &elapsed)]);
+    log!("{elapsed}");
+}
+
+/// With `--github-output`, writes the summary counts and the URLs of the
+/// updated tickets for GitHub Actions.
+///
+/// # Errors
+/// Returns an error if the `GITHUB_OUTPUT` or `GITHUB_STEP_SUMMARY` file
+/// can't be written.
+fn write_github_output(
+    ctx: &Context<'_>,
+    state: &RunState,
+) -> Result<(), Error> {
+    if ctx.config.github_output != GithubOutput::Write {
+        return Ok(());
+    }
+    let tally = &state.tally;
+    action_outputs::write(&RunResults {
+        release_tag: ctx.config.release_tag.as_deref(),
+        dry_run: ctx.config.dry_run,
+        updated: tally.updated,
+        already_completed: tally.already_completed,
+        skipped: tally.skipped,
+        failed: tally.failed,
+        urls: state
+            .completed
+            .iter()
+            .filter(|issue_id| state.updated.contains(*issue_id))
+            .map(|issue_id| issue_url(ctx.org_for(issue_id), issue_id))
+            .collect(),
+    })
+}
+
+/// With `--slack-webhook`, posts the summary of the run to Slack (or logs
+/// that it would, in dry-run). A failed post is logged and doesn't fail the
+/// run.
+fn notify_slack(ctx: &Context<'_>, state: &RunState) {
+    let Some(url) = ctx.config.get_slack_webhook() else {
+        return;
+    };
+    if ctx.config.dry_run {
+        log!("Would post the summary of the run to Slack");
+        ctx.estimate.add(Provider::Slack, Request::Mutation, 1);
+        return;
+    }
+    let tally = &state.tally;
+    let summary = slack::Summary {
+        release_tag: ctx.config.release_tag.as_deref(),
+        updated: state
+            .completed
+            .iter()
+            .filter(|issue_id| state.updated.contains(*issue_id))
+            .map(|issue_id| slack::Ticket {
+                id: issue_id,
+                url: issue_url(ctx.org_for(issue_id), issue_id),
+            })
+            .collect(),
+        already_completed: tally.already_completed,
+        skipped: tally.skipped,
+        failed: state
+            .failures
+            .iter()
+            .map(|(issue_id, error)| {
+                let ticket = slack::Ticket {
+                    id: issue_id,
+                    url: issue_url(ctx.org_for(issue_id), issue_id),
+                };
+                (ticket, error.as_str())
+            })
+            .collect(),
+    };
+    match summary.post(&url) {
+        Ok(()) => {
+            log!("Posted the summary of the run to Slack");
+        }
+        Err(e) => {
+            log!(Warn, "Failed to post the summary of the run to Slack: {e}");
+        }
+    }
 }
 
 /// Updates a single Linear ticket to completed state.
 ///
 /// # Arguments
+/// * `ctx` - Per-run settings (credentials, dry-run, filter)
+/// * `tickets` - The run's ticket metadata store
 /// * `issue_id` - The Linear issue identifier (e.g., `ABC-123`)
-/// * `org` - The Linear organization identifier (for output URLs)
-/// * `api_key` - Linear API authentication key
-/// * `dry_run` - If true, preview without making changes
 ///
 /// # Returns
-/// - `Ok(Some(url))` - Ticket was updated or would be updated
-/// - `Ok(None)` - Ticket already completed (in dry-run mode, suppresses output)
-/// - `Err(msg)` - Update failed
+/// - The outcome for the ticket (`Updated` means "would be updated" in
+///   dry-run mode)
+/// - The manifest entry for tickets that would be updated in dry-run mode
 ///
 /// # Dry-Run Behavior
 /// If `dry_run` is true:
 /// - Queries ticket state to check if it's completed
-/// - Returns `Ok(Some(url))` if it would be updated (output URL)
-/// - Returns `Ok(None)` otherwise (no output)
 /// - Skips all mutation logic (team lookup, state update)
 ///
-/// # Normal Mode Behavior
-/// 1. Queries current issue state and team ID
-/// 3. If already Done/Completed, returns success without updating
-/// 4. If current state is not "Passing", skips without updating
-/// 5. Finds the team's completed state ID
-/// 6. Updates issue to completed state
-/// 7. Returns success
-///
 /// # Errors
 /// Returns an error if:
 /// - Linear API queries fail
 /// - Team ID or completed state not found
 /// - Update mutation fails
 fn update_single_ticket(
+    ctx: &Context<'_>,
+    tickets: &mut TicketStore,
     issue_id: &str,
-    org: &str,
-    api_key: &str,
-    dry_run: bool,
-    update_all_statuses: bool,
-) -> Result<Option<String>, String> {
-    let url = issue_url(org, issue_id);
+) -> Result<(Outcome, Option<manifest::Entry>), Error> {
+    let plan = plan_ticket(ctx, tickets, issue_id)?;
+
+    // In dry-run mode, return early after state check
+    if ctx.config.dry_run {
+        if let Plan::Update(ref entry) = plan {
+            validate_target_state(ctx, &entry.ticket, &entry.team_id)?;
+        }
+        return Ok(match plan {
+            Plan::Update(entry) => (Outcome::Updated, Some(entry)),
+            Plan::AlreadyCompleted => (Outcome::AlreadyCompleted, None),
+            Plan::AlreadyReleased => (Outcome::AlreadyReleased, None),
+            Plan::Skip(reason) => (Outcome::Skipped(reason), None),
+        });
+    }
+
+    Ok((apply_plan(ctx, issue_id, &plan, tickets)?, None))
+}
+
+/// Queries a ticket and decides what should happen to it, without mutating
+/// anything.
+///
+/// # Process
+/// 1. Reads the ticket's metadata from the store (querying Linear on first
+///    use)
+/// 2. Skips it if it's archived
+/// 3. With `--comment-release`, skips it if it already carries the release
+///    marker, and with `--attach-release`, if the release is attached to it
+/// 4. If a filter is given and the ticket doesn't match, skips it, as it
+///    does tickets not assigned to the `--assignee` or created by the
+///    `--creator`
+/// 5. With `--linear-project`, skips it if it isn't in the project
+/// 6. If already Done/Completed, plans no update
+/// 7. With a `--state-route` for the release, skips it if it's already in
+///    the route's state
+/// 8. If current state is not "Passing" (or one of `--from-states`) or the state of a `--state-route`
+///    (with `--linear-project`: not of the `started` type) and
+///    `--update-all-statuses` is not set, skips it
+///
+/// # Errors
+/// Returns an error if the Linear API query fails or the issue doesn't exist.
+fn plan_ticket(
+    ctx: &Context<'_>,
+    tickets: &mut TicketStore,
+    issue_id: &str,
+) -> Result<Plan, Error> {
+    let ticket = tickets.get(issue_id, ctx.linear_for(issue_id))?;
+
+    if ticket.archived {
+        log!("Issue {issue_id} is archived, skipping.");
+        return Ok(Plan::Skip(SkipReason::Archived));
+    }
+
+    if let Some((ref tag, ref marker)) = ctx.release
+        && ticket.comments.iter().any(|body| body.contains(marker))
+    {
+        log!("Issue {issue_id} is already marked for release {tag}, skipping.");
+        return Ok(Plan::AlreadyReleased);
+    }
+
+    if let Some(ref attachment) = ctx.attachment
+        && attachment.is_attached(&ticket.id)
+    {
+        let tag = &attachment.tag;
+        log!(
+            "Release {tag} is already attached to issue {issue_id}, skipping."
+        );
+        return Ok(Plan::AlreadyReleased);
+    }
+
+    if let Some(ref filter) = ctx.config.filter
+        && !issue_matches_filter(ticket, filter)?
+    {
+        log!("Issue {issue_id} does not match --filter, skipping.");
+        return Ok(Plan::Skip(SkipReason::Filtered));
+    }
 
-    // Query current issue state from Linear API
-    let issue_details = get_issue_details(issue_id, api_key)?;
-    ensure_no_graphql_errors(&issue_details)?;
+    if let Some(user) = other_user(ctx, ticket) {
+        log!("Issue {issue_id} is not {user}, skipping.");
+        return Ok(Plan::Skip(SkipReason::OtherUser));
+    }
 
-    let issue_is_null =
-        extract_jq_value(&issue_details, ".data.issue == null")?;
-    if issue_is_null == "true" {
-        return Err("Issue not found".to_string());
+    if let Some(ref project) = ctx.project
+        && !project.contains(&ticket.id)
+    {
+        let name = &project.name;
+        log!("Issue {issue_id} is not in project {name}, skipping.");
+        return Ok(Plan::Skip(SkipReason::NotInProject));
     }
 
-    let current_state_name =
-        extract_jq_value(&issue_details, ".data.issue.state.name")?;
+    let current_state_name = ticket.state_name.clone();
 
     // Check if ticket is already in a completed state (matches
     // scripts/linear.sh semantics).
     let is_completed = state_is_done_or_completed(&current_state_name);
-    let is_passing = state_is_passing(&current_state_name);
-    let should_update = !is_completed && (update_all_statuses || is_passing);
+    let is_source = ctx.config.is_source_state(&current_state_name);
 
-    log!("Current state: {current_state_name}");
+    log!(Verbose, "Current state: {current_state_name}");
 
-    // In dry-run mode, return early after state check
-    if dry_run {
-        return if should_update {
-            Ok(Some(url))
-        } else {
-            Ok(None)
-        };
+    if is_completed {
+        return Ok(Plan::AlreadyCompleted);
     }
 
-    // Skip update if already completed
-    if is_completed {
-        log!("Issue {issue_id} is already in a completed state, skipping.");
-        return Ok(Some(url));
+    if let Some(ref route) = ctx.route
+        && current_state_name.eq_ignore_ascii_case(route)
+    {
+        log!("Issue {issue_id} is already in {route}, skipping.");
+        return Ok(Plan::Skip(SkipReason::AlreadyInTarget));
+    }
+    if ctx.route.is_none()
+        && let Some(ref state_type) = ctx.config.target_state_type
+        && ticket.state_type == *state_type
+    {
+        log!("Issue {issue_id} is already in a {state_type} state, skipping.");
+        return Ok(Plan::Skip(SkipReason::AlreadyInTarget));
     }
 
-    if !should_update {
+    // Project releases complete every started ticket of the project. Tickets
+    // an earlier release candidate moved to a route's state stay eligible, so
+    // the final release completes them.
+    let routes = &ctx.config.state_routes;
+    let (is_eligible, eligible_state) = if ctx.project.is_some() {
+        (ticket.state_type == "started", "a started".to_string())
+    } else if routes.is_empty() {
+        (is_source, ctx.config.source_states_label())
+    } else {
+        (
+            is_source || routes.is_route_state(&current_state_name),
+            format!("{} or a --state-route", ctx.config.source_states_label()),
+        )
+    };
+    if !ctx.config.update_all_statuses && !is_eligible {
         log!(
-            "Issue {issue_id} is not in \"Passing\" state, skipping (use --update-all-statuses to override)."
+            "Issue {issue_id} is not in {eligible_state} state, skipping (use --update-all-statuses to override)."
         );
-        return Ok(None);
+        return Ok(Plan::Skip(SkipReason::IneligibleState));
     }
 
-    // Get team ID for this issue
-    let team_id = extract_jq_value(&issue_details, ".data.issue.team.id")?;
+    // Mutations must use the current identifier, which differs from the
+    // input when the issue was found through its previous identifier.
+    let mut entry = manifest::Entry {
+        ticket: ticket.identifier.clone(),
+        team_id: ticket.team_id.clone(),
+        from_state: current_state_name,
+        to_state: target_state(ctx).to_string(),
+        to_state_id: String::new(),
+        added_labels: Vec::new(),
+        removed_labels: Vec::new(),
+    };
+    if ctx.config.manifest.is_some() || ctx.config.approve.is_some() {
+        describe_change(ctx, issue_id, &ticket.labels, &mut entry)?;
+    }
+    Ok(Plan::Update(entry))
+}
 
-    if team_id == "null" || team_id.is_empty() {
-        return Err(format!("Could not find team ID for issue {issue_id}"));
+/// With `--manifest` or `--approve`, fills in where the planned update
+/// `entry` of ticket `issue_id` (carrying the label names `labels`) moves
+/// it, so the manifest and its hash cover the target state, as found in the
+/// team's workflow, and the label change. Snapshots carry no workflows, so
+/// the target is left as given.
+///
+/// # Errors
+/// Returns an error if the target state can't be looked up.
+fn describe_change(
+    ctx: &Context<'_>,
+    issue_id: &str,
+    labels: &[String],
+    entry: &mut manifest::Entry,
+) -> Result<(), Error> {
+    if ctx.config.linear_snapshot.is_none() {
+        if entry.team_id == "null" || entry.team_id.is_empty() {
+            return Err(Error::LinearApi(format!(
+                "Could not find team ID for issue {issue_id}"
+            )));
+        }
+        let linear = ctx.linear_for(issue_id);
+        let target = resolve_target_state(&entry.team_id, linear, ctx)?;
+        entry.to_state = target.name;
+        entry.to_state_id = target.id;
+    }
+    if let Some(ref label) = ctx.label
+        && !label.is_on(labels)
+    {
+        entry.added_labels.push(label.name.clone());
     }
+    if let Some(ref value) = ctx.custom_field {
+        let (added, removed) = value.named_change(labels);
+        entry.added_labels.extend(added);
+        entry.removed_labels.extend(removed);
+    }
+    entry.added_labels.sort();
+    entry.removed_labels.sort();
+    Ok(())
+}
+
+/// Carries out a plan produced by `plan_ticket`.
+///
+/// # Process
+/// 1. Finds the team's completed state ID (or that of the `--state-route`
+///    state)
+/// 2. Updates issue to that state, journaling the attempt with `--journal`
+///
+/// # Errors
+/// Returns an error if:
+/// - Linear API queries fail
+/// - Team ID or completed state not found
+/// - Update mutation fails
+fn apply_plan(
+    ctx: &Context<'_>,
+    issue_id: &str,
+    plan: &Plan,
+    tickets: &TicketStore,
+) -> Result<Outcome, Error> {
+    let entry = match plan {
+        Plan::Update(entry) => entry,
+        // Skip update if already completed
+        Plan::AlreadyCompleted => {
+            log!("Issue {issue_id} is already in a completed state, skipping.");
+            return Ok(Outcome::AlreadyCompleted);
+        }
+        Plan::AlreadyReleased => return Ok(Outcome::AlreadyReleased),
+        Plan::Skip(reason) => return Ok(Outcome::Skipped(*reason)),
+    };
 
-    log!("Found Team ID: {team_id}");
+    // Get team ID for this issue
+    let team_id = &entry.team_id;
+    if team_id == "null" || team_id.is_empty() {
+        return Err(Error::LinearApi(format!(
+            "Could not find team ID for issue {issue_id}"
+        )));
+    }
 
-    // Get workflow states for the team and find completed state ID
-    let workflow_states = get_workflow_states(&team_id, api_key)?;
-    ensure_no_graphql_errors(&workflow_states)?;
-    let completed_state_id = find_completed_state(&workflow_states)?;
+    log!(Verbose, "Found Team ID: {team_id}");
 
-    log!("Found completed state ID: {completed_state_id}");
+    let linear = ctx.linear_for(issue_id);
+    let target_state_id = target_state_id(team_id, linear, ctx)?;
 
     // Execute the mutation to update issue state
-    let update_response =
-        update_issue_state(issue_id, &completed_state_id, api_key)?;
-    ensure_no_graphql_errors(&update_response)?;
+    let request_id = journal_request_id(ctx);
+    let result = update_issue_state(
+        &entry.ticket,
+        &target_state_id,
+        &custom_field_change(ctx, tickets, &[issue_id]),
+        &with_request_id(linear, request_id.as_deref()),
+    )
+    .and_then(|response| utils::ensure_no_graphql_errors(&response));
+    journal_attempt(
+        ctx,
+        tickets,
+        &[issue_id],
+        (&target_state_id, request_id.as_deref()),
+        &result,
+    );
+    result?;
 
-    log!("Successfully updated issue {issue_id} to completed");
+    let target = target_state(ctx);
+    log!("Successfully updated issue {issue_id} to {target}");
+    if let Some(ref value) = ctx.custom_field {
+        let field = &value.field;
+        log!("Set {field} on {issue_id}");
+    }
 
-    Ok(Some(url))
+    Ok(Outcome::Updated)
 }
 
-/// Queries Linear API for issue details (state and team).
+/// Carries out every plan, moving the planned updates with
+/// `issueBatchUpdate` (`--quiet-updates`).
 ///
-/// # Arguments
-/// * `issue_id` - The Linear issue ID (e.g., "ABC-123")
-/// * `api_key` - Linear API authentication key
+/// Updates are grouped by team, since each team has its own workflow states,
+/// and sent in chunks of [`QUIET_BATCH_SIZE`]. If a team's target state
+/// can't be looked up, or a batch fails, its tickets are moved one at a
+/// time instead (see [`apply_plan`]), so each one's result keeps the error
+/// it fails with.
 ///
 /// # Returns
-/// JSON response containing issue state and team information.
+/// One result per plan, in the same order as `plans`.
+fn apply_batched(
+    ctx: &Context<'_>,
+    plans: &[(String, Plan)],
+    tickets: &TicketStore,
+) -> Vec<Result<Outcome, Error>> {
+    let mut results: Vec<Option<Result<Outcome, Error>>> =
+        plans.iter().map(|_| None).collect();
+    let mut by_team: Vec<(&str, Vec<usize>)> = Vec::new();
+    for (idx, (issue_id, plan)) in plans.iter().enumerate() {
+        let Plan::Update(entry) = plan else {
+            results[idx] = Some(apply_plan(ctx, issue_id, plan, tickets));
+            continue;
+        };
+        match by_team.iter_mut().find(|(team, _)| *team == entry.team_id) {
+            Some((_, indices)) => indices.push(idx),
+            None => by_team.push((&entry.team_id, vec![idx])),
+        }
+    }
+
+    for (team_id, indices) in by_team {
+        // A team's tickets are all in the same workspace
+        let linear = ctx.linear_for(&plans[indices[0]].0);
+        let state_id = if team_id == "null" || team_id.is_empty() {
+            Err(Error::LinearApi("Could not find team ID".to_string()))
+        } else {
+            target_state_id(team_id, linear, ctx)
+        };
+        let state_id = match state_id {
+            Ok(state_id) => state_id,
+            Err(e) => {
+                log!(Warn, "{e}; moving the team's tickets one at a time");
+                for idx in indices {
+                    let (issue_id, plan) = &plans[idx];
+                    results[idx] =
+                        Some(apply_plan(ctx, issue_id, plan, tickets));
+                }
+                continue;
+            }
+        };
+
+        for chunk in indices.chunks(QUIET_BATCH_SIZE) {
+            let ids: Result<Vec<&str>, Error> = chunk
+                .iter()
+                .map(|&idx| {
+                    let issue_id = &plans[idx].0;
+                    tickets
+                        .cached(issue_id)
+                        .map(|ticket| ticket.id.as_str())
+                        .ok_or_else(|| {
+                            Error::Other(format!(
+                                "Issue {issue_id} not fetched"
+                            ))
+                        })
+                })
+                .collect();
+            let result = ids.and_then(|ids| {
+                let request_id = journal_request_id(ctx);
+                let issue_ids: Vec<&str> =
+                    chunk.iter().map(|&idx| plans[idx].0.as_str()).collect();
+                let result = batch_update_issue_state(
+                    &ids,
+                    &state_id,
+                    &custom_field_change(ctx, tickets, &issue_ids),
+                    &with_request_id(linear, request_id.as_deref()),
+                );
+                journal_attempt(
+                    ctx,
+                    tickets,
+                    &issue_ids,
+                    (&state_id, request_id.as_deref()),
+                    &result,
+                );
+                result
+            });
+            let count = chunk.len();
+            if let Err(e) = result {
+                log!(
+                    Warn,
+                    "Batch of {count} issue(s) failed ({e}); moving them one at a time"
+                );
+                for &idx in chunk {
+                    let (issue_id, plan) = &plans[idx];
+                    results[idx] =
+                        Some(apply_plan(ctx, issue_id, plan, tickets));
+                }
+                continue;
+            }
+            let target = target_state(ctx);
+            log!("Moved {count} issue(s) to {target} in one batch");
+            for &idx in chunk {
+                results[idx] = Some(Ok(Outcome::Updated));
+            }
+        }
+    }
+
+    results
+        .into_iter()
+        .map(|result| {
+            result.unwrap_or_else(|| {
+                Err(Error::Other("Ticket was not applied".to_string()))
+            })
+        })
+        .collect()
+}
+
+/// The `--state-route` state for the release tag, if any route matches it.
+fn state_route(config: &Config) -> Option<String> {
+    let tag = config.release_tag.as_deref()?;
+    let (pattern, state) = config.state_routes.route(tag)?;
+    log!(
+        "release {tag} matches --state-route {pattern}: moving tickets to {state}"
+    );
+    Some(state.to_string())
+}
+
+/// Name of the state tickets are moved to, for log and summary lines.
+fn target_state<'a>(ctx: &'a Context<'_>) -> &'a str {
+    ctx.route
+        .as_deref()
+        .or(ctx.config.target_state_type.as_deref())
+        .unwrap_or("completed")
+}
+
+/// Whether tickets are moved somewhere other than the completed state, by a
+/// `--state-route`, `--target-state` or `--target-state-type`.
+fn has_custom_target(ctx: &Context<'_>) -> bool {
+    ctx.route.is_some() || ctx.config.target_state_type.is_some()
+}
+
+/// Whether moving a ticket completes it, i.e. it's moved to a Done/Completed
+/// state (or, with `--target-state-type` alone, a `completed` one).
+fn completes(ctx: &Context<'_>) -> bool {
+    match ctx.route {
+        Some(ref route) => state_is_done_or_completed(route),
+        None => ctx
+            .config
+            .target_state_type
+            .as_deref()
+            .is_none_or(|state_type| state_type == "completed"),
+    }
+}
+
+/// In a dry run with `--target-state` or `--target-state-type`, checks that
+/// the team of ticket `issue_id` has the target state, as the real run
+/// would. Snapshots carry no state IDs, so they aren't checked.
+///
+/// # Errors
+/// Returns an error if the team has no such state or the lookup fails.
+fn validate_target_state(
+    ctx: &Context<'_>,
+    issue_id: &str,
+    team_id: &str,
+) -> Result<(), Error> {
+    let config = ctx.config;
+    if (config.target_state.is_none() && config.target_state_type.is_none())
+        || config.linear_snapshot.is_some()
+    {
+        return Ok(());
+    }
+    if team_id == "null" || team_id.is_empty() {
+        return Err(Error::LinearApi("Could not find team ID".to_string()));
+    }
+    resolve_target_state(team_id, ctx.linear_for(issue_id), ctx).map(drop)
+}
+
+/// The workflow state a team's tickets are moved to, as found in its
+/// workflow.
+#[derive(Debug, Clone)]
+struct TargetState {
+    id: String,
+    name: String,
+}
+
+impl TargetState {
+    /// Renders the state for `--cache-dir`, as `ID<TAB>NAME`.
+    fn to_cached(&self) -> String {
+        format!("{}\t{}", self.id, self.name)
+    }
+
+    /// Reads a state rendered by [`TargetState::to_cached`]. Entries of
+    /// older runs, holding the ID alone, are looked up again.
+    fn from_cached(cached: &str) -> Option<Self> {
+        let (id, name) = cached.split_once('\t')?;
+        Some(Self {
+            id: id.to_string(),
+            name: name.to_string(),
+        })
+    }
+}
+
+/// Looks up the workflow state a team's tickets are moved to (the completed
+/// state, or the `--state-route`/`--target-state` state), reusing one
+/// looked up earlier in the run or cached in `--cache-dir` by an earlier
+/// run. `linear` is the team's workspace.
+///
+/// # Errors
+/// Returns an error if the query fails, no state matches (see
+/// [`find_target_state`]) or the cache can't be written.
+fn resolve_target_state(
+    team_id: &str,
+    linear: &LinearApi,
+    ctx: &Context<'_>,
+) -> Result<TargetState, Error> {
+    let target = target_state(ctx);
+    if let Some(state) = known_target_state(ctx, team_id) {
+        let id = &state.id;
+        log!(Verbose, "Using cached {target} state ID: {id}");
+        return Ok(state);
+    }
+
+    let workflow_states = get_workflow_states(team_id, linear)?;
+    utils::ensure_no_graphql_errors(&workflow_states)?;
+    let state = find_target_state(ctx, &workflow_states)?;
+    remember_target_state(ctx, team_id, &state)?;
+
+    let id = &state.id;
+    log!(Verbose, "Found {target} state ID: {id}");
+    Ok(state)
+}
+
+/// Looks up the ID of the workflow state a team's tickets are moved to (see
+/// [`resolve_target_state`]).
+///
+/// # Errors
+/// Returns an error if the state can't be looked up.
+fn target_state_id(
+    team_id: &str,
+    linear: &LinearApi,
+    ctx: &Context<'_>,
+) -> Result<String, Error> {
+    resolve_target_state(team_id, linear, ctx).map(|state| state.id)
+}
+
+/// Key of a team's target state, in this run's lookups and in
+/// `--cache-dir`.
+fn state_cache_key(ctx: &Context<'_>, team_id: &str) -> String {
+    let state_type = ctx.config.target_state_type.as_deref();
+    match (ctx.route.as_deref(), state_type) {
+        (Some(route), None) => {
+            format!("state-{team_id}-{}", route.to_lowercase())
+        }
+        (Some(route), Some(state_type)) => {
+            format!("state-{team_id}-{}-{state_type}", route.to_lowercase())
+        }
+        (None, Some(state_type)) => format!("{state_type}-state-{team_id}"),
+        (None, None) => format!("completed-state-{team_id}"),
+    }
+}
+
+/// A team's target state, if this run or (through `--cache-dir`) an
+/// earlier one already looked it up.
+fn known_target_state(ctx: &Context<'_>, team_id: &str) -> Option<TargetState> {
+    let cache_key = state_cache_key(ctx, team_id);
+    if let Some(state) = ctx
+        .target_states
+        .lock()
+        .ok()
+        .and_then(|states| states.get(&cache_key).cloned())
+    {
+        return Some(state);
+    }
+    let cached = ctx.cache.as_ref()?.get("linear", &cache_key)?;
+    let state = TargetState::from_cached(&cached)?;
+    if let Ok(mut states) = ctx.target_states.lock() {
+        states.insert(cache_key, state.clone());
+    }
+    Some(state)
+}
+
+/// Keeps a team's target state for the rest of the run, and in
+/// `--cache-dir`.
+///
+/// # Errors
+/// Returns an error if the cache can't be written.
+fn remember_target_state(
+    ctx: &Context<'_>,
+    team_id: &str,
+    state: &TargetState,
+) -> Result<(), Error> {
+    let cache_key = state_cache_key(ctx, team_id);
+    if let Some(ref cache) = ctx.cache {
+        cache.put("linear", &cache_key, &state.to_cached())?;
+    }
+    if let Ok(mut states) = ctx.target_states.lock() {
+        states.insert(cache_key, state.clone());
+    }
+    Ok(())
+}
+
+/// Describes how `ticket` fails the `--assignee` and `--creator`
+/// (e.g. `assigned to ann@acme.com`), or `None` if it passes both. Emails
+/// are compared case-insensitively.
+fn other_user(ctx: &Context<'_>, ticket: &Ticket) -> Option<String> {
+    [
+        ("assigned to", &ctx.assignee, &ticket.assignee),
+        ("created by", &ctx.creator, &ticket.creator),
+    ]
+    .into_iter()
+    .find_map(|(relation, wanted, actual)| {
+        let wanted = wanted.as_deref()?;
+        (!actual.eq_ignore_ascii_case(wanted))
+            .then(|| format!("{relation} {wanted}"))
+    })
+}
+
+/// Resolves the `--assignee` and `--creator` to emails, looking up `me`
+/// (the user of the API key) once.
 ///
 /// # GraphQL Query
 /// ```graphql
-/// query($issueId: String!) {
-///   issue(id: $issueId) {
-///     team { id }
-///     state { name }
-///   }
-/// }
+/// { viewer { email } }
 /// ```
-fn get_issue_details(issue_id: &str, api_key: &str) -> Result<String, String> {
-    let query = format!(
-        r#"{{"query": "query($issueId: String!) {{ issue(id: $issueId) {{ team {{ id }} state {{ name }} }} }}", "variables": {{"issueId": "{issue_id}"}}}}"#
-    );
+///
+/// # Errors
+/// Returns an error if `me` is given with `--linear-snapshot`, or the
+/// lookup fails.
+fn resolve_users(
+    config: &Config,
+    linear: &LinearApi,
+) -> Result<(Option<String>, Option<String>), Error> {
+    let wants_me = [&config.assignee, &config.creator]
+        .iter()
+        .any(|user| user.as_deref() == Some("me"));
+    let me = if wants_me {
+        if config.linear_snapshot.is_some() {
+            return Err(Error::Config(
+                "--assignee me and --creator me need the Linear API; pass an email with --linear-snapshot"
+                    .to_string(),
+            ));
+        }
+        let response = utils::graphql_request(
+            r#"{"query": "{ viewer { email } }"}"#,
+            linear,
+        )?;
+        utils::ensure_no_graphql_errors(&response)?;
+        let email = response.get("data.viewer.email").str_or_empty();
+        if email.is_empty() {
+            return Err(Error::LinearApi(
+                "Linear returned no email for the API key's user".to_string(),
+            ));
+        }
+        log!(Verbose, "--assignee/--creator me is {email}");
+        email.to_string()
+    } else {
+        String::new()
+    };
+    let resolve = |user: &Option<String>| {
+        user.as_deref().map(|user| {
+            if user == "me" {
+                me.clone()
+            } else {
+                user.to_string()
+            }
+        })
+    };
+    Ok((resolve(&config.assignee), resolve(&config.creator)))
+}
 
-    utils::graphql_request(&query, api_key)
+/// Evaluates a `--filter` expression against a ticket's metadata.
+///
+/// # Field Mapping
+/// - `id` - issue identifier (e.g. `ABC-123`)
+/// - `team` / `team_name` - team key / team display name
+/// - `state` / `state_type` - workflow state name / type (e.g. `started`)
+/// - `title` - issue title
+/// - `assignee` - assignee email (empty if unassigned)
+/// - `creator` - creator email (empty if created by an integration)
+/// - `labels` - list of label names
+///
+/// # Errors
+/// Returns an error if the expression cannot be evaluated.
+fn issue_matches_filter(
+    ticket: &Ticket,
+    filter: &Filter,
+) -> Result<bool, Error> {
+    filter.matches(&|name| match name {
+        "id" => Value::Str(ticket.identifier.clone()),
+        "team" => Value::Str(ticket.team_key.clone()),
+        "team_name" => Value::Str(ticket.team_name.clone()),
+        "state" => Value::Str(ticket.state_name.clone()),
+        "state_type" => Value::Str(ticket.state_type.clone()),
+        "title" => Value::Str(ticket.title.clone()),
+        "assignee" => Value::Str(ticket.assignee.clone()),
+        "creator" => Value::Str(ticket.creator.clone()),
+        _ => Value::List(ticket.labels.clone()),
+    })
 }
 
 /// Queries Linear API for a team's workflow states.
 ///
 /// # Arguments
 /// * `team_id` - The Linear team ID
-/// * `api_key` - Linear API authentication key
+/// * `linear` - Linear API connection settings
 ///
 /// # Returns
 /// JSON response containing all workflow states for the team.
@@ -277,40 +3605,84 @@ fn get_issue_details(issue_id: &str, api_key: &str) -> Result<String, String> {
 ///   }
 /// }
 /// ```
-fn get_workflow_states(team_id: &str, api_key: &str) -> Result<String, String> {
+///
+/// # Errors
+/// Returns an error if the request fails or Linear answers with errors.
+pub fn get_workflow_states(
+    team_id: &str,
+    linear: &LinearApi,
+) -> Result<json::Value, Error> {
     let query = format!(
-        r#"{{"query": "query($teamId: String!) {{ team(id: $teamId) {{ states {{ nodes {{ id name type }} }} }} }}", "variables": {{"teamId": "{team_id}"}}}}"#
+        r#"{{"query": "query($teamId: String!) {{ team(id: $teamId) {{ states {{ nodes {{ id name type }} }} }} }}", "variables": {{"teamId": {}}}}}"#,
+        utils::json_string(team_id)
     );
 
-    utils::graphql_request(&query, api_key)
+    utils::graphql_request(&query, linear)
 }
 
-/// Finds a completed state ID from workflow states response.
-///
-/// # Arguments
-/// * `workflow_response` - JSON response from `get_workflow_states`
-///
-/// # Returns
-/// The state ID for a "Completed" or "Done" state.
+/// Finds the state tickets are moved to (its ID and name) in a
+/// `get_workflow_states` response.
 ///
 /// # Search Strategy
-/// Uses case-insensitive regex to find any state with name matching "completed"
-/// or "done". Returns the first matching state ID.
+/// Returns the first state that [`matches_target_state`] the run's target:
+/// by default, the first state whose name contains "completed" or "done"
+/// (case-insensitive); otherwise the state named by the route (see
+/// `Context::route`), of the `--target-state-type` type if given.
 ///
 /// # Errors
-/// Returns an error if no completed/done state is found in the workflow.
-fn find_completed_state(workflow_response: &str) -> Result<String, String> {
-    // jq query to find first state matching "completed" or "done"
-    // (case-insensitive)
-    let jq_query = r#".data.team.states.nodes[] | select(.name | test("completed|done"; "i")) | .id"#;
-    let state_id = utils::run_jq(workflow_response, jq_query)?;
-    let state_id = state_id.lines().next().unwrap_or("").trim();
-
-    if state_id.is_empty() || state_id == "null" {
-        return Err("Could not find a 'Completed' or 'Done' state".to_string());
+/// Returns an error naming the missing state (and the team's states) if no
+/// state matches.
+fn find_target_state(
+    ctx: &Context<'_>,
+    workflow_response: &json::Value,
+) -> Result<TargetState, Error> {
+    let name = ctx.route.as_deref();
+    let state_type = ctx.config.target_state_type.as_deref();
+    let states = workflow_response.get("data.team.states.nodes").items();
+    let found = states
+        .iter()
+        .find(|state| {
+            matches_target_state(
+                state.get("name").str_or_empty(),
+                state.get("type").str_or_empty(),
+                name,
+                state_type,
+            )
+        })
+        .filter(|state| !state.get("id").str_or_empty().is_empty());
+    if let Some(state) = found {
+        return Ok(TargetState {
+            id: state.get("id").str_or_empty().to_string(),
+            name: state.get("name").str_or_empty().to_string(),
+        });
     }
 
-    Ok(state_id.to_string())
+    let missing = match (name, state_type) {
+        (None, None) => {
+            return Err(Error::LinearApi(
+                "Could not find a 'Completed' or 'Done' state".to_string(),
+            ));
+        }
+        (Some(name), None) => format!("a '{name}' state"),
+        (None, Some(state_type)) => format!("a state of type '{state_type}'"),
+        (Some(name), Some(state_type)) => {
+            format!("a '{name}' state of type '{state_type}'")
+        }
+    };
+    let available: Vec<String> = states
+        .iter()
+        .map(|state| {
+            format!(
+                "{} ({})",
+                state.get("name").str_or_empty(),
+                state.get("type").str_or_empty()
+            )
+        })
+        .collect();
+    Err(Error::LinearApi(format!(
+        "Could not find {missing} in the team's workflow (states: {})",
+        available.join(", ")
+    )))
 }
 
 /// Updates a Linear issue to a specific state.
@@ -318,73 +3690,176 @@ fn find_completed_state(workflow_response: &str) -> Result<String, String> {
 /// # Arguments
 /// * `issue_id` - The Linear issue ID
 /// * `state_id` - The target state ID (typically a completed state)
-/// * `api_key` - Linear API authentication key
+/// * `linear` - Linear API connection settings
 ///
 /// # Returns
 /// The GraphQL response on success.
 ///
 /// # GraphQL Mutation
 /// ```graphql
-/// mutation($issueId: String!, $stateId: String!) {
-///   issueUpdate(id: $issueId, input: { stateId: $stateId }) {
+/// mutation($issueId: String!, $input: IssueUpdateInput!) {
+///   issueUpdate(id: $issueId, input: $input) {
 ///     success
 ///   }
 /// }
 /// ```
+/// with the `stateId` (and the `labels` change) as the input.
 ///
 /// # Errors
 /// Returns an error if the mutation returns `success: false`.
-fn update_issue_state(
+pub fn update_issue_state(
     issue_id: &str,
     state_id: &str,
-    api_key: &str,
-) -> Result<String, String> {
+    labels: &LabelChange,
+    linear: &LinearApi,
+) -> Result<json::Value, Error> {
     let query = format!(
-        r#"{{"query": "mutation($issueId: String!, $stateId: String!) {{ issueUpdate(id: $issueId, input: {{ stateId: $stateId }}) {{ success }} }}", "variables": {{"issueId": "{issue_id}", "stateId": "{state_id}"}}}}"#
+        r#"{{"query": "mutation($issueId: String!, $input: IssueUpdateInput!) {{ issueUpdate(id: $issueId, input: $input) {{ success }} }}", "variables": {{"issueId": {}, "input": {{"stateId": {}{}}}}}}}"#,
+        utils::json_string(issue_id),
+        utils::json_string(state_id),
+        labels.input_fields()
     );
 
-    let response = utils::graphql_request(&query, api_key)?;
-    let success = extract_jq_value(&response, ".data.issueUpdate.success")?;
+    let response = utils::graphql_request(&query, linear)?;
+    let success = response.get("data.issueUpdate.success").as_bool();
 
-    if success == "true" {
+    if success == Some(true) {
         Ok(response)
     } else {
-        Err(format!("Update failed: {response}"))
+        Err(Error::LinearApi(format!("Update failed: {response}")))
+    }
+}
+
+/// Adds a Linear issue to a project and/or cycle.
+///
+/// # GraphQL Mutation
+/// ```graphql
+/// mutation($issueId: String!, $input: IssueUpdateInput!) {
+///   issueUpdate(id: $issueId, input: $input) {
+///     success
+///   }
+/// }
+/// ```
+/// with `projectId` and/or `cycleId` as the input.
+///
+/// # Errors
+/// Returns an error if the mutation returns `success: false`.
+fn link_issue(
+    issue_id: &str,
+    project_id: Option<&str>,
+    cycle_id: Option<&str>,
+    linear: &LinearApi,
+) -> Result<(), Error> {
+    let input: Vec<String> = [("projectId", project_id), ("cycleId", cycle_id)]
+        .into_iter()
+        .filter_map(|(field, id)| {
+            id.map(|id| format!(r#""{field}": {}"#, utils::json_string(id)))
+        })
+        .collect();
+    let query = format!(
+        r#"{{"query": "mutation($issueId: String!, $input: IssueUpdateInput!) {{ issueUpdate(id: $issueId, input: $input) {{ success }} }}", "variables": {{"issueId": {}, "input": {{{}}}}}}}"#,
+        utils::json_string(issue_id),
+        input.join(", ")
+    );
+
+    let response = utils::graphql_request(&query, linear)?;
+    if response.get("data.issueUpdate.success").as_bool() == Some(true) {
+        Ok(())
+    } else {
+        Err(Error::LinearApi(format!("Update failed: {response}")))
     }
 }
 
-/// Extracts a single value from JSON using jq.
+/// Moves several Linear issues to one state in a single request.
 ///
 /// # Arguments
-/// * `json` - The JSON string to parse
-/// * `query` - The jq query expression
+/// * `issue_ids` - Issue UUIDs (`issueBatchUpdate` does not accept
+///   identifiers like `ENG-123`)
+/// * `state_id` - The target state ID
+/// * `labels` - Labels added and removed along with the state
+/// * `linear` - Linear API connection settings
 ///
-/// # Returns
-/// The extracted value, trimmed of whitespace.
+/// # GraphQL Mutation
+/// ```graphql
+/// mutation($ids: [UUID!]!, $input: IssueUpdateInput!) {
+///   issueBatchUpdate(ids: $ids, input: $input) {
+///     success
+///   }
+/// }
+/// ```
 ///
-/// # Usage
-/// This is a convenience wrapper around `run_jq` that trims the result.
-fn extract_jq_value(json: &str, query: &str) -> Result<String, String> {
-    let result = utils::run_jq(json, query)?;
-    Ok(result.trim().to_string())
+/// # Errors
+/// Returns an error if the mutation returns `success: false`.
+fn batch_update_issue_state(
+    issue_ids: &[&str],
+    state_id: &str,
+    labels: &LabelChange,
+    linear: &LinearApi,
+) -> Result<(), Error> {
+    let ids = issue_ids
+        .iter()
+        .map(|id| utils::json_string(id))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let query = format!(
+        r#"{{"query": "mutation($ids: [UUID!]!, $input: IssueUpdateInput!) {{ issueBatchUpdate(ids: $ids, input: $input) {{ success }} }}", "variables": {{"ids": [{ids}], "input": {{"stateId": {}{}}}}}}}"#,
+        utils::json_string(state_id),
+        labels.input_fields()
+    );
+
+    let response = utils::graphql_request(&query, linear)?;
+    utils::ensure_no_graphql_errors(&response)?;
+    let success = response.get("data.issueBatchUpdate.success").as_bool();
+
+    if success == Some(true) {
+        Ok(())
+    } else {
+        Err(Error::LinearApi(format!("Batch update failed: {response}")))
+    }
 }
 
-fn issue_url(org: &str, issue_id: &str) -> String {
+/// The Linear URL of a ticket of workspace `org`.
+#[must_use]
+pub fn issue_url(org: &str, issue_id: &str) -> String {
     format!("https://linear.app/{org}/issue/{issue_id}")
 }
 
-fn parse_issue_id(input: &str) -> Result<String, String> {
+/// Formats a processed ticket for stdout according to `--url-format`.
+fn output_line(ctx: &Context<'_>, issue_id: &str) -> String {
+    match ctx.config.url_format {
+        UrlFormat::Id => issue_id.to_string(),
+        UrlFormat::Url => issue_url(ctx.org_for(issue_id), issue_id),
+        UrlFormat::Markdown => {
+            format!(
+                "[{issue_id}]({})",
+                issue_url(ctx.org_for(issue_id), issue_id)
+            )
+        }
+    }
+}
+
+fn parse_issue_id(config: &Config, input: &str) -> Result<String, Error> {
     let trimmed = input.trim();
     if trimmed.is_empty() {
-        return Err("Empty ticket ID".to_string());
+        return Err(Error::Other("Empty ticket ID".to_string()));
     }
-    if !is_valid_ticket_id(trimmed) {
-        return Err("Expected ticket ID like ABC-123".to_string());
+    if !config.is_ticket_id(trimmed)? {
+        return Err(Error::Other(match config.ticket_pattern {
+            Some(ref pattern) => {
+                format!(
+                    "Expected ticket ID matching --ticket-pattern {pattern}"
+                )
+            }
+            None => "Expected ticket ID like ABC-123".to_string(),
+        }));
     }
     Ok(trimmed.to_string())
 }
 
-fn is_valid_ticket_id(input: &str) -> bool {
+/// Whether `input` is a ticket ID of the default form: 3 uppercase letters,
+/// a hyphen and a number (`ABC-123`).
+#[must_use]
+pub fn is_default_ticket_id(input: &str) -> bool {
     let Some((prefix, digits)) = input.split_once('-') else {
         return false;
     };
@@ -394,20 +3869,37 @@ fn is_valid_ticket_id(input: &str) -> bool {
     !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
 }
 
-fn ensure_no_graphql_errors(response_json: &str) -> Result<(), String> {
-    // Linear GraphQL can return HTTP 200 with an `errors` field. Treat that as
-    // a failure.
-    let messages = utils::run_jq(response_json, ".errors[]?.message // empty")?;
-    if messages.trim().is_empty() {
-        return Ok(());
+/// Whether a workflow state name contains one of [`COMPLETED_STATE_NAMES`]
+/// (case-insensitive), i.e. tickets may be moved to it.
+#[must_use]
+pub fn matches_completed_state(state_name: &str) -> bool {
+    let name = state_name.to_lowercase();
+    COMPLETED_STATE_NAMES.iter().any(|part| name.contains(part))
+}
+
+/// Whether a workflow state is the one tickets are moved to: named
+/// `target_name` (case-insensitive) and of type `target_type`, whichever are
+/// given, or by default named like [`COMPLETED_STATE_NAMES`].
+#[must_use]
+pub fn matches_target_state(
+    state_name: &str,
+    state_type: &str,
+    target_name: Option<&str>,
+    target_type: Option<&str>,
+) -> bool {
+    if target_name.is_none() && target_type.is_none() {
+        return matches_completed_state(state_name);
     }
-    Err(format!("Linear API returned errors: {}", messages.trim()))
+    target_name.is_none_or(|name| state_name.eq_ignore_ascii_case(name))
+        && target_type.is_none_or(|target| state_type == target)
 }
 
-fn state_is_done_or_completed(state_name: &str) -> bool {
+#[must_use]
+pub fn state_is_done_or_completed(state_name: &str) -> bool {
     state_name.contains("Done") || state_name.contains("Completed")
 }
 
-fn state_is_passing(state_name: &str) -> bool {
+#[must_use]
+pub fn state_is_passing(state_name: &str) -> bool {
     state_name.eq_ignore_ascii_case("Passing")
 }
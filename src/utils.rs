@@ -1,20 +1,37 @@
 //! Utility functions for external command execution and I/O.
 //!
-//! This module provides wrappers around external commands (grep, jq, curl)
-//! and I/O operations (stdin, file reading). By delegating to system commands
+//! This module provides wrappers around external commands (grep, curl) and
+//! I/O operations (stdin, file reading). By delegating to system commands
 //! rather than bundling libraries, the binary remains small and leverages
-//! well-tested Unix tools.
+//! well-tested Unix tools. JSON is parsed in-process (see [`crate::json`]),
+//! and HTTP requests are sent in-process (see [`crate::http`]).
 //!
 //! ## External Dependencies
 //! - `grep` - Pattern matching with regex support
-//! - `jq` - JSON parsing and manipulation
-//! - `curl` - HTTP requests to Linear GraphQL API
+//! - `curl` - HTTPS requests, only in builds without the `native-http`
+//!   feature
+//! - `sha256sum` - Manifest hashing
 
-use std::fmt;
-use std::io::{self, BufRead, Write};
-use std::process::{Command, Stdio};
+use std::cell::{Cell, RefCell};
+use std::fmt::{self, Write as _};
+use std::fs::OpenOptions;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::process::{Command, Output, Stdio};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
+use crate::cache::{ApiBudget, RateLimiter};
 use crate::config::InputSource;
+use crate::error::Error;
+use crate::estimate::{self, Provider};
+use crate::exit_status;
+use crate::explain::Subject;
+use crate::http;
+use crate::json::{self, Value};
+use crate::protocol::Input;
 
 // Keep prefixes aligned in stderr output:
 //
@@ -23,12 +40,250 @@ use crate::config::InputSource;
 // update-tickets  :
 const LOG_PREFIX_WIDTH: usize = 16;
 
+/// Prefix of the GraphQL requests and responses logged at
+/// [`LogLevel::Debug`].
+const GRAPHQL_LOG: &str = "graphql";
+
+/// Times a request is sent while its API keeps rate limiting it.
+const RATE_LIMIT_ATTEMPTS: u32 = 4;
+
+/// Pause after a rate-limited request, doubled after every further one.
+const RATE_LIMIT_PAUSE: Duration = Duration::from_secs(5);
+
+thread_local! {
+    /// Log lines held back by [`capture_logs`] on this thread.
+    static CAPTURED: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+    /// Ticket or PR the logs of this thread are about, set by
+    /// [`with_log_subject`].
+    static SUBJECT: RefCell<Option<Subject>> = const { RefCell::new(None) };
+    /// How the run on this thread logs, set by [`with_log_settings`].
+    static SETTINGS: Cell<LogSettings> = const {
+        Cell::new(LogSettings {
+            level: LogLevel::Info,
+            format: LogFormat::Text,
+        })
+    };
+}
+
+/// How much a run logs to stderr (`--quiet`, `-v`, `-vv`). Each level logs
+/// the lines of the levels before it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    /// Failures and warnings only (`--quiet`)
+    Warn,
+    /// Also the progress of each stage
+    #[default]
+    Info,
+    /// Also the details of each ticket, such as its current state (`-v`)
+    Verbose,
+    /// Also every GraphQL request and response (`-vv`)
+    Debug,
+}
+
+impl LogLevel {
+    /// The level's name, as the `level` of JSON log records.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Warn => "warn",
+            Self::Info => "info",
+            Self::Verbose => "verbose",
+            Self::Debug => "debug",
+        }
+    }
+
+    /// The flag selecting the level, if it isn't the default.
+    pub fn flag(self) -> Option<&'static str> {
+        match self {
+            Self::Warn => Some("--quiet"),
+            Self::Info => None,
+            Self::Verbose => Some("-v"),
+            Self::Debug => Some("-vv"),
+        }
+    }
+}
+
+/// How log lines are written to stderr (`--log-format`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// `STAGE           : MESSAGE`, the stage padded to a fixed width
+    #[default]
+    Text,
+    /// One JSON object per line, with the time, level, stage, ticket or PR
+    /// and message
+    Json,
+}
+
+impl LogFormat {
+    /// The format's `--log-format` value.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Text => "text",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// How a run logs: how much (`--quiet`, `-v`, `-vv`) and in which format
+/// (`--log-format`).
+///
+/// The settings belong to the run rather than the process, so runs in one
+/// process (of `serve`, the in-process orchestrator or a library) each log
+/// with their own. They are kept per thread: a run's threads are started
+/// with [`with_log_settings`] of [`log_settings`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LogSettings {
+    /// Most detailed level logged
+    pub level: LogLevel,
+    /// How log lines are written
+    pub format: LogFormat,
+}
+
+/// Runs `f` with the lines it logs on this thread logged with `settings`.
+pub fn with_log_settings<R>(settings: LogSettings, f: impl FnOnce() -> R) -> R {
+    let outer = SETTINGS.replace(settings);
+    let result = f();
+    SETTINGS.set(outer);
+    result
+}
+
+/// The log settings of the run on this thread, to start its other threads
+/// with.
+pub fn log_settings() -> LogSettings {
+    SETTINGS.get()
+}
+
+/// Runs `f` with the lines it logs on this thread about `subject`: the
+/// `ticket` or `pr` of their JSON records.
+pub fn with_log_subject<R>(subject: Subject, f: impl FnOnce() -> R) -> R {
+    let outer = SUBJECT.replace(Some(subject));
+    let result = f();
+    SUBJECT.set(outer);
+    result
+}
+
+/// Whether lines of `level` are logged.
+pub fn logs(level: LogLevel) -> bool {
+    level <= SETTINGS.get().level
+}
+
+/// Logs `message` to stderr with the stage `prefix`, at [`LogLevel::Info`].
 pub fn log(prefix: &str, message: fmt::Arguments<'_>) {
-    // Intentionally hard-coded width for stable, greppable logs.
-    eprintln!("{prefix:<LOG_PREFIX_WIDTH$}: {message}");
+    log_at(LogLevel::Info, prefix, message);
+}
+
+/// Logs `message` to stderr with the stage `prefix`, if lines of `level`
+/// are logged (see [`LogSettings`]).
+pub fn log_at(level: LogLevel, prefix: &str, message: fmt::Arguments<'_>) {
+    if !logs(level) {
+        return;
+    }
+    let line = if SETTINGS.get().format == LogFormat::Json {
+        log_record(level.name(), Some(prefix), &message.to_string())
+    } else {
+        // Intentionally hard-coded width for stable, greppable logs.
+        format!("{prefix:<LOG_PREFIX_WIDTH$}: {message}")
+    };
+    CAPTURED.with_borrow_mut(|captured| match captured {
+        Some(lines) => lines.push(line),
+        None => eprintln!("{line}"),
+    });
 }
 
-/// Calls a function once per input line across multiple sources.
+/// Prints `message`, the error that ended the run, to stderr: as
+/// `Error: MESSAGE`, or as a JSON record of level `error`.
+pub fn log_error(message: &str) {
+    if SETTINGS.get().format == LogFormat::Json {
+        eprintln!("{}", log_record("error", None, message));
+    } else {
+        eprintln!("Error: {message}");
+    }
+}
+
+/// Renders a JSON log record, e.g. `{"timestamp":"2024-05-01T10:00:00Z",
+/// "level":"info","stage":"update-tickets","ticket":"ENG-2","message":"..."}`,
+/// with the ticket or PR of [`with_log_subject`], if any.
+fn log_record(level: &str, stage: Option<&str>, message: &str) -> String {
+    let mut record = String::from("{\"timestamp\":");
+    record.push_str(&json_string(&utc_now()));
+    record.push_str(",\"level\":");
+    record.push_str(&json_string(level));
+    if let Some(stage) = stage {
+        record.push_str(",\"stage\":");
+        record.push_str(&json_string(stage));
+    }
+    SUBJECT.with_borrow(|subject| match subject {
+        Some(Subject::Ticket(ticket)) => {
+            record.push_str(",\"ticket\":");
+            record.push_str(&json_string(ticket));
+        }
+        Some(Subject::Pr(pr)) => {
+            record.push_str(",\"pr\":");
+            record.push_str(&json_string(&pr.to_text()));
+        }
+        None => {}
+    });
+    record.push_str(",\"message\":");
+    record.push_str(&json_string(message));
+    record.push('}');
+    record
+}
+
+/// Runs `f`, collecting the lines it logs on this thread instead of printing
+/// them, so work done on worker threads can be reported in a stable order.
+///
+/// # Returns
+/// `f`'s result and the captured log lines, to be passed to [`print_logs`].
+pub fn capture_logs<R>(f: impl FnOnce() -> R) -> (R, Vec<String>) {
+    let outer = CAPTURED.replace(Some(Vec::new()));
+    let result = f();
+    let lines = CAPTURED.replace(outer).unwrap_or_default();
+    (result, lines)
+}
+
+/// Prints log lines collected by [`capture_logs`].
+pub fn print_logs(lines: &[String]) {
+    for line in lines {
+        eprintln!("{line}");
+    }
+}
+
+/// Default of `--stdin-timeout`, in seconds.
+pub const DEFAULT_STDIN_TIMEOUT_SECS: u64 = 30;
+
+/// How long to wait for the first line of a terminal stdin
+/// (`--stdin-timeout`).
+#[derive(Debug, Clone, Copy)]
+pub struct NoInputTimeout<'a> {
+    /// Seconds to wait (default: [`DEFAULT_STDIN_TIMEOUT_SECS`]; 0 waits
+    /// forever)
+    pub secs: Option<u64>,
+    /// What the input should contain, for the error message (e.g. "ticket
+    /// IDs")
+    pub expected: &'a str,
+}
+
+/// Calls a function once per input line across multiple sources, with the
+/// line, or the text form of a record of an in-process stage (see
+/// [`for_each_input`]).
+///
+/// # Errors
+/// Returns the first error of [`for_each_input`] or `on_line`.
+pub fn for_each_input_line<F>(
+    sources: &[InputSource],
+    no_input: NoInputTimeout<'_>,
+    mut on_line: F,
+) -> Result<(), Error>
+where
+    F: FnMut(&str) -> Result<(), Error>,
+{
+    for_each_input(sources, no_input, |input| match input {
+        Input::Line(ref line) => on_line(line),
+        Input::Record(_) => on_line(&input.to_string()),
+    })
+}
+
+/// Calls a function once per input item across multiple sources: each line
+/// of stdin or a file, or each record of the previous in-process stage.
 ///
 /// # Behavior
 /// - Processes sources in the order provided
@@ -36,13 +291,32 @@ pub fn log(prefix: &str, message: fmt::Arguments<'_>) {
 ///   File("b.txt")]`)
 /// - For stdin, processes lines as they arrive (streaming; does not wait for
 ///   EOF before starting)
-pub fn for_each_input_line<F>(
+/// - If stdin is a terminal and no line arrives within `no_input`'s timeout,
+///   fails with an error explaining how to pass input. Piped stdin (e.g. from
+///   an upstream stage or in CI) is never timed out.
+/// - If more than one source is a stream (stdin, a named pipe or a process
+///   substitution such as `<(cmd)`), all sources are read at once, and lines
+///   are processed as they arrive from any of them (see
+///   [`for_each_line_concurrently`]). Concurrent producers then don't block
+///   each other. So is a terminal stdin that may time out, whose blocked
+///   read can't be interrupted.
+///
+/// # Errors
+/// Returns the first read error, the first error of `on_input`, or an
+/// error if a terminal stdin sends nothing in time.
+pub fn for_each_input<F>(
     sources: &[InputSource],
-    mut on_line: F,
-) -> Result<(), String>
+    no_input: NoInputTimeout<'_>,
+    mut on_input: F,
+) -> Result<(), Error>
 where
-    F: FnMut(&str) -> Result<(), String>,
+    F: FnMut(Input) -> Result<(), Error>,
 {
+    if sources.iter().filter(|source| is_stream(source)).count() > 1
+        || stdin_timeout(sources, no_input).is_some()
+    {
+        return for_each_line_concurrently(sources, no_input, on_input);
+    }
     for source in sources {
         match source {
             InputSource::Stdin => {
@@ -52,22 +326,31 @@ where
                 let stdin = io::stdin();
                 for line_result in stdin.lock().lines() {
                     let line = line_result.map_err(|e| {
-                        format!("Failed to read from stdin: {e}")
+                        Error::io("Failed to read from stdin", e)
                     })?;
-                    on_line(&line)?;
+                    on_input(Input::Line(line))?;
                 }
             }
             InputSource::File(path) => {
                 // Files are processed line-by-line for consistent behavior with
                 // stdin.
-                let file = std::fs::File::open(path)
-                    .map_err(|e| format!("Failed to open file {path}: {e}"))?;
+                let file = std::fs::File::open(path).map_err(|e| {
+                    Error::io(format!("Failed to open file {path}"), e)
+                })?;
                 let reader = io::BufReader::new(file);
                 for line_result in reader.lines() {
                     let line = line_result.map_err(|e| {
-                        format!("Failed to read file {path}: {e}")
+                        Error::io(format!("Failed to read file {path}"), e)
                     })?;
-                    on_line(&line)?;
+                    on_input(Input::Line(line))?;
+                }
+            }
+            InputSource::Channel(records) => {
+                let records = records.lock().map_err(|_| {
+                    Error::Other("Stage input poisoned".to_string())
+                })?;
+                for record in records.iter() {
+                    on_input(Input::Record(record))?;
                 }
             }
         }
@@ -75,6 +358,138 @@ where
     Ok(())
 }
 
+/// Whether `source` is a stream rather than a regular file: stdin, a named
+/// pipe, a process substitution (`/dev/fd/N`) or an in-process stage.
+fn is_stream(source: &InputSource) -> bool {
+    match source {
+        InputSource::Stdin | InputSource::Channel(_) => true,
+        InputSource::File(path) => {
+            std::fs::metadata(path).is_ok_and(|metadata| !metadata.is_file())
+        }
+    }
+}
+
+/// The time a terminal stdin among `sources` may take to send its first
+/// line, if it is timed out (see [`NoInputTimeout`]).
+fn stdin_timeout(
+    sources: &[InputSource],
+    no_input: NoInputTimeout<'_>,
+) -> Option<Duration> {
+    let secs = no_input.secs.unwrap_or(DEFAULT_STDIN_TIMEOUT_SECS);
+    (secs > 0
+        && sources
+            .iter()
+            .any(|source| matches!(source, InputSource::Stdin))
+        && io::stdin().is_terminal())
+    .then(|| Duration::from_secs(secs))
+}
+
+/// Reads every source on a thread of its own, calling `on_input` (on this
+/// thread) with each line as it arrives. Lines of one source stay in order;
+/// lines of different sources are interleaved.
+///
+/// # Errors
+/// Returns the first read error, or the first error of `on_input`, without
+/// waiting for the other sources; or an error if a terminal stdin sends
+/// nothing within `no_input`'s timeout.
+fn for_each_line_concurrently<F>(
+    sources: &[InputSource],
+    no_input: NoInputTimeout<'_>,
+    mut on_input: F,
+) -> Result<(), Error>
+where
+    F: FnMut(Input) -> Result<(), Error>,
+{
+    // Set once stdin sends a line or ends.
+    let received = Arc::new(AtomicBool::new(false));
+    let deadline = stdin_timeout(sources, no_input)
+        .map(|timeout| (timeout, Instant::now() + timeout));
+    let (sender, lines) = mpsc::channel();
+    for source in sources.iter().cloned() {
+        let sender = sender.clone();
+        let received =
+            matches!(source, InputSource::Stdin).then(|| Arc::clone(&received));
+        // Not scoped: after an error, a reader blocked on a stream that
+        // never ends mustn't keep the caller waiting.
+        thread::spawn(move || {
+            let (name, reader): (String, Box<dyn BufRead>) = match source {
+                InputSource::Stdin => {
+                    ("stdin".to_string(), Box::new(io::stdin().lock()))
+                }
+                InputSource::File(path) => match std::fs::File::open(&path) {
+                    Ok(file) => (
+                        format!("file {path}"),
+                        Box::new(io::BufReader::new(file)),
+                    ),
+                    Err(e) => {
+                        let _ = sender.send(Err(Error::io(
+                            format!("Failed to open file {path}"),
+                            e,
+                        )));
+                        return;
+                    }
+                },
+                InputSource::Channel(records) => {
+                    let Ok(records) = records.lock() else {
+                        return;
+                    };
+                    for record in records.iter() {
+                        if sender.send(Ok(Input::Record(record))).is_err() {
+                            return;
+                        }
+                    }
+                    return;
+                }
+            };
+            for line_result in reader.lines() {
+                if let Some(ref received) = received {
+                    received.store(true, Ordering::Relaxed);
+                }
+                let line = line_result.map(Input::Line).map_err(|e| {
+                    Error::io(format!("Failed to read from {name}"), e)
+                });
+                let failed = line.is_err();
+                if sender.send(line).is_err() || failed {
+                    return;
+                }
+            }
+            if let Some(ref received) = received {
+                received.store(true, Ordering::Relaxed);
+            }
+        });
+    }
+    drop(sender);
+    loop {
+        let line = match deadline {
+            Some((timeout, deadline)) if !received.load(Ordering::Relaxed) => {
+                let left = deadline.saturating_duration_since(Instant::now());
+                match lines.recv_timeout(left) {
+                    Ok(line) => line,
+                    Err(RecvTimeoutError::Timeout) => {
+                        if received.load(Ordering::Relaxed) {
+                            continue;
+                        }
+                        // The reader stays blocked on stdin, but no longer
+                        // holds up the run.
+                        let secs = timeout.as_secs();
+                        let expected = no_input.expected;
+                        return Err(Error::Other(format!(
+                            "no input received in {secs}s; pass {expected} on stdin or as file arguments"
+                        )));
+                    }
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            }
+            _ => match lines.recv() {
+                Ok(line) => line,
+                Err(_) => break,
+            },
+        };
+        on_input(line?)?;
+    }
+    Ok(())
+}
+
 /// Runs grep to find pattern matches in text.
 ///
 /// # Arguments
@@ -97,40 +512,70 @@ where
 /// - Output contains invalid UTF-8
 ///
 /// # Example
-/// ```
+/// ```ignore
 /// let text = "Issue #123 and #456";
 /// let matches = run_grep(text, r"#[0-9]+")?;
 /// // matches: "#123\n#456"
 /// ```
-pub fn run_grep(input: &str, pattern: &str) -> Result<String, String> {
+pub fn run_grep(input: &str, pattern: &str) -> Result<String, Error> {
+    grep(&["-oE", pattern], input)
+}
+
+/// Returns the lines of `input` that `pattern` matches in full (`grep -xE`),
+/// e.g. to check ticket IDs against a denylist.
+///
+/// # Errors
+/// Returns an error if grep can't be run or the pattern is invalid.
+///
+/// # Example
+/// ```ignore
+/// let excluded = grep_whole_lines("TMP-1\nENG-2", "TMP-.*|SAND-.*")?;
+/// // excluded: ["TMP-1"]
+/// ```
+pub fn grep_whole_lines(
+    input: &str,
+    pattern: &str,
+) -> Result<Vec<String>, Error> {
+    Ok(grep(&["-xE", pattern], input)?
+        .lines()
+        .map(String::from)
+        .collect())
+}
+
+/// Runs grep with `args` over `input`, returning its output (empty if
+/// nothing matched).
+fn grep(args: &[&str], input: &str) -> Result<String, Error> {
     // Spawn grep process with piped I/O
     let mut child = Command::new("grep")
-        .args(["-oE", pattern])
+        .args(args)
         .stdin(Stdio::piped()) // Write input via pipe
         .stdout(Stdio::piped()) // Capture output
         .stderr(Stdio::piped()) // Capture errors
         .spawn()
-        .map_err(|e| format!("Failed to spawn grep: {e}"))?;
+        .map_err(|e| {
+            exit_status::spawn_error("Failed to spawn grep", e)
+        })?;
 
     // Write input text to grep's stdin
     if let Some(mut stdin) = child.stdin.take() {
         stdin
             .write_all(input.as_bytes())
-            .map_err(|e| format!("Failed to write to grep stdin: {e}"))?;
+            .map_err(|e| Error::io("Failed to write to grep stdin", e))?;
     }
 
     // Wait for grep to complete and capture output
     let output = child
         .wait_with_output()
-        .map_err(|e| format!("Failed to wait for grep: {e}"))?;
+        .map_err(|e| Error::io("Failed to wait for grep", e))?;
 
     // grep exits with:
     // - 0: matches found
     // - 1: no matches (not an error for this tool)
     // - 2: error (e.g. invalid regex)
     if output.status.success() {
-        return String::from_utf8(output.stdout)
-            .map_err(|e| format!("Invalid UTF-8 from grep: {e}"));
+        return String::from_utf8(output.stdout).map_err(|e| {
+            Error::command(format!("Invalid UTF-8 from grep: {e}"))
+        });
     }
 
     if output.status.code() == Some(1) {
@@ -139,123 +584,809 @@ pub fn run_grep(input: &str, pattern: &str) -> Result<String, String> {
 
     let stderr = String::from_utf8(output.stderr)
         .unwrap_or_else(|_| "<non-utf8 stderr>".to_string());
-    Err(format!("grep failed: {stderr}"))
+    Err(Error::command(format!("grep failed: {}", stderr.trim())))
+}
+
+/// Default Linear GraphQL endpoint.
+pub const DEFAULT_LINEAR_API_URL: &str = "https://api.linear.app/graphql";
+
+/// Connection settings for the Linear GraphQL API.
+///
+/// Besides the API key, this carries endpoint overrides so requests can be
+/// routed through internal API gateways in locked-down networks.
+#[derive(Debug, Clone)]
+pub struct LinearApi {
+    /// Linear API authentication key
+    pub api_key: String,
+    /// GraphQL endpoint URL (defaults to [`DEFAULT_LINEAR_API_URL`])
+    pub url: String,
+    /// Extra HTTP headers, each as `Name: value`
+    pub headers: Vec<String>,
+    /// Static host resolutions (as with curl's `--resolve`), each as
+    /// `HOST:PORT:ADDRESS`
+    pub resolve: Vec<String>,
+    /// OAuth access token that mutations are sent with instead of
+    /// `api_key`, so they are made as that app (`--linear-actor-token`)
+    pub actor_token: Option<String>,
+    /// Rate limiter shared with concurrent pipelines (`--rate-limit`)
+    pub limiter: Option<RateLimiter>,
+    /// Request budget of the run (`--api-budget`)
+    pub budget: Option<ApiBudget>,
+    /// File that unparseable responses are appended to (`--debug-dump`)
+    pub debug_dump: Option<String>,
 }
 
-/// Runs jq to extract or transform JSON data.
+/// Makes a GraphQL request to the Linear API.
 ///
 /// # Arguments
-/// * `input` - JSON string to process
-/// * `query` - jq query expression
+/// * `query` - GraphQL query or mutation as JSON string
+/// * `linear` - Linear API connection settings
 ///
 /// # Returns
-/// The result of the jq query as a string.
+/// The parsed JSON response from the Linear API.
 ///
-/// # Options
-/// - `-r`: Raw output (no JSON encoding of strings)
-/// - stderr redirected to null (errors suppressed)
+/// # Request Details
+/// - Method: POST
+/// - Endpoint: `linear.url` (default `https://api.linear.app/graphql`)
+/// - Headers:
+///   - `Content-Type: application/json`
+///   - `Authorization: <api_key>`, or `Authorization: Bearer <actor_token>`
+///     for mutations when `linear.actor_token` is set
+///   - Any extra `linear.headers`
+/// - Resolution: any `linear.resolve` entries pin the endpoint host to an IP
+///   without DNS (curl's `--resolve`)
+/// - Body: The query parameter
+/// - Budget: with `linear.budget`, claims one request from it first
+/// - Pacing: with `linear.limiter`, waits for a token of its bucket first;
+///   while Linear rate limits the request, pauses and sends it again (see
+///   [`retry_rate_limited`])
+/// - Transport: the built-in client (see [`crate::http`]), or `curl` for
+///   `https://` endpoints in builds without the `native-http` feature
+/// - Logging: at [`LogLevel::Debug`] (`-vv`), the request body and the
+///   response are logged (the headers, which carry the credentials, aren't)
 ///
 /// # Errors
 /// Returns an error if:
-/// - `jq` command cannot be spawned
-/// - Failed to write input to jq's stdin
-/// - Failed to read jq's output
-/// - Output contains invalid UTF-8
+/// - The request budget is used up (nothing is sent), or its file can't be
+///   updated
+/// - The rate limiter's file can't be updated
+/// - The connection fails or times out, or `curl` is needed but cannot be
+///   spawned
+/// - The request fails, or the response status isn't 2xx (the error carries
+///   the status and response body)
+/// - The response is larger than [`http::MAX_RESPONSE_BYTES`]
+/// - Response contains invalid UTF-8, isn't valid JSON, or isn't an object
+///   with `data` or `errors`
+///
+/// Errors name the request (see [`describe_request`]), e.g.
+/// `Invalid Linear API response to issue query for ENG-123: ...`, and quote
+/// the start of the response. With `linear.debug_dump`, the raw response of
+/// a failed parse is appended to that file (see [`dump_response`]).
 ///
 /// # Example
+/// ```ignore
+/// let query = r#"{"query": "{ viewer { name } }"}"#;
+/// let response = graphql_request(query, &linear)?;
+/// let name = response.get("data.viewer.name").str_or_empty();
 /// ```
-/// let json = r#"{"name": "Alice", "age": 30}"#;
-/// let name = run_jq(json, ".name")?;
-/// // name: "Alice"
-/// ```
-pub fn run_jq(input: &str, query: &str) -> Result<String, String> {
-    // Spawn jq process with piped I/O
-    let mut child = Command::new("jq")
-        .args(["-r", query])
-        .stdin(Stdio::piped()) // Write JSON via pipe
-        .stdout(Stdio::piped()) // Capture result
-        .stderr(Stdio::piped()) // Capture errors
-        .spawn()
-        .map_err(|e| format!("Failed to spawn jq: {e}"))?;
+pub fn graphql_request(
+    query: &str,
+    linear: &LinearApi,
+) -> Result<Value, Error> {
+    if let Some(ref budget) = linear.budget {
+        budget.spend()?;
+    }
+    if let Some(ref limiter) = linear.limiter {
+        limiter.wait()?;
+    }
+    estimate::count(Provider::Linear);
 
-    // Write JSON input to jq's stdin
-    if let Some(mut stdin) = child.stdin.take() {
-        stdin
-            .write_all(input.as_bytes())
-            .map_err(|e| format!("Failed to write to jq stdin: {e}"))?;
+    let authorization = match linear.actor_token {
+        Some(ref token) if is_mutation(query) => format!("Bearer {token}"),
+        _ => linear.api_key.clone(),
+    };
+    let mut headers = vec![
+        "Content-Type: application/json".to_string(),
+        format!("Authorization: {authorization}"),
+    ];
+    headers.extend(linear.headers.iter().cloned());
+    log_at(LogLevel::Debug, GRAPHQL_LOG, format_args!("-> {query}"));
+
+    let response = retry_rate_limited(
+        "Linear",
+        linear.limiter.as_ref(),
+        || {
+            http_request(
+                "POST",
+                &linear.url,
+                &headers,
+                &linear.resolve,
+                Some(query),
+                Error::LinearApi,
+            )
+        },
+        is_rate_limited,
+    )
+    .map_err(|e| {
+        e.context(format!("Linear {} failed", describe_request(query)))
+    })?;
+    log_at(
+        LogLevel::Debug,
+        GRAPHQL_LOG,
+        format_args!("<- HTTP {} {}", response.status, response.body.trim()),
+    );
+    if !(200..300).contains(&response.status) {
+        let status = response.status;
+        let body = response.body.trim();
+        let message = format!(
+            "Linear API returned HTTP {status} to {}: {body}",
+            describe_request(query)
+        );
+        return Err(if status == 401 {
+            Error::Credentials(message)
+        } else {
+            Error::LinearApi(message)
+        });
     }
+    let parsed = json::parse(&response.body).and_then(|value| {
+        if value.get("data").is_null() && value.get("errors").is_null() {
+            Err(Error::LinearApi(
+                "expected an object with \"data\" or \"errors\"".to_string(),
+            ))
+        } else {
+            Ok(value)
+        }
+    });
+    parsed.map_err(|e| {
+        let request = describe_request(query);
+        let dumped =
+            dump_response(linear.debug_dump.as_deref(), &request, &response.body);
+        Error::LinearApi(format!(
+            "Invalid Linear API response to {request}: {e} (response starts: {}){dumped}",
+            excerpt(&response.body)
+        ))
+    })
+}
 
-    // Wait for jq to complete and capture output
-    let output = child
-        .wait_with_output()
-        .map_err(|e| format!("Failed to wait for jq: {e}"))?;
+/// Checks a GraphQL response for an `errors` field: Linear can answer
+/// HTTP 200 with `errors` instead of (or next to) `data`.
+///
+/// # Errors
+/// Returns an [`Error::GraphQl`] of the response's error messages, if it has
+/// any.
+pub fn ensure_no_graphql_errors(response: &Value) -> Result<(), Error> {
+    let messages = response.error_messages().join("\n");
+    if messages.trim().is_empty() {
+        return Ok(());
+    }
+    Err(Error::graphql("Linear API", &messages))
+}
 
-    if !output.status.success() {
-        let stderr = String::from_utf8(output.stderr)
-            .unwrap_or_else(|_| "<non-utf8 stderr>".to_string());
-        return Err(format!("jq failed: {stderr}"));
+/// Whether a GraphQL request is a mutation.
+fn is_mutation(query: &str) -> bool {
+    json::parse(query).is_ok_and(|request| {
+        request
+            .get("query")
+            .str_or_empty()
+            .trim_start()
+            .starts_with("mutation")
+    })
+}
+
+/// Longest part of a response quoted in an error message, in characters.
+const EXCERPT_CHARS: usize = 120;
+
+/// Describes a GraphQL request for error messages, e.g.
+/// `issue query for ENG-123` or `issueUpdate mutation for ENG-123`.
+///
+/// The name is the first field selected by the operation, and the ticket is
+/// taken from the `issueId` or `term` variable, when there is one. Requests
+/// that can't be described fall back to `request`.
+fn describe_request(query: &str) -> String {
+    let Ok(request) = json::parse(query) else {
+        return "request".to_string();
+    };
+    let document = request.get("query").str_or_empty().trim_start();
+    let (kind, selection) = match document.strip_prefix("mutation") {
+        Some(rest) => ("mutation", rest),
+        None => ("query", document.strip_prefix("query").unwrap_or(document)),
+    };
+    // Skip the operation name and variable definitions up to the selection
+    // set, then take its first field (after its alias, if any).
+    let name_end = |s: &str| {
+        s.find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .unwrap_or(s.len())
+    };
+    let first = selection.split_once('{').map_or("", |(_, rest)| rest);
+    let first = first.trim_start();
+    let (mut field, rest) = first.split_at(name_end(first));
+    if let Some(aliased) = rest.trim_start().strip_prefix(':') {
+        let aliased = aliased.trim_start();
+        field = &aliased[..name_end(aliased)];
     }
+    let mut description = if field.is_empty() {
+        kind.to_string()
+    } else {
+        format!("{field} {kind}")
+    };
+    let variables = request.get("variables");
+    if let Some(ticket) = ["issueId", "term"]
+        .iter()
+        .map(|name| variables.get(name).str_or_empty())
+        .find(|ticket| !ticket.is_empty())
+    {
+        let _ = write!(description, " for {ticket}");
+    }
+    description
+}
 
-    String::from_utf8(output.stdout)
-        .map_err(|e| format!("Invalid UTF-8 from jq: {e}"))
+/// The start of `body` on a single line, for error messages.
+pub fn excerpt(body: &str) -> String {
+    let body = body.trim();
+    if body.is_empty() {
+        return "<empty>".to_string();
+    }
+    let mut excerpt: String = body
+        .chars()
+        .take(EXCERPT_CHARS)
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect();
+    if body.chars().nth(EXCERPT_CHARS).is_some() {
+        excerpt.push_str("...");
+    }
+    excerpt
 }
 
-/// Makes a GraphQL request to the Linear API using curl.
+/// Appends a raw response that failed to parse to the `--debug-dump` file.
 ///
-/// # Arguments
-/// * `query` - GraphQL query or mutation as JSON string
-/// * `api_key` - Linear API authentication key
+/// Each entry is a `--- <unix time> <request>` line followed by the response
+/// body as received, so the file can be shared with whoever runs the API or
+/// gateway.
 ///
 /// # Returns
-/// The JSON response from the Linear API.
+/// A note for the error message: where the response was saved, why it
+/// couldn't be, or nothing without a dump file.
+pub fn dump_response(path: Option<&str>, request: &str, body: &str) -> String {
+    let Some(path) = path else {
+        return String::new();
+    };
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let entry = format!("--- {timestamp} {request}\n{body}\n");
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(entry.as_bytes()));
+    match written {
+        Ok(()) => format!("; raw response saved to {path}"),
+        Err(e) => format!("; failed to save raw response to {path}: {e}"),
+    }
+}
+
+/// Sends an HTTP request, returning the status and body.
 ///
-/// # Request Details
-/// - Method: POST
-/// - Endpoint: `https://api.linear.app/graphql`
-/// - Headers:
-///   - `Content-Type: application/json`
-///   - `Authorization: <api_key>`
-/// - Body: The query parameter
+/// The built-in client ([`http::send`]) sends it, unless it's an `https://`
+/// URL and the crate is built without the `native-http` feature: `curl`
+/// then does (see [`crate::http`]). A response too large or malformed fails
+/// with an `invalid` error, e.g. [`Error::LinearApi`].
 ///
 /// # Errors
-/// Returns an error if:
-/// - `curl` command cannot be spawned
-/// - HTTP request fails (non-zero exit code)
-/// - Response contains invalid UTF-8
+/// Returns an error if the request fails before a response is received.
+/// Non-2xx statuses are returned as a response, not as an error.
+pub fn http_request(
+    method: &str,
+    url: &str,
+    headers: &[String],
+    resolve: &[String],
+    body: Option<&str>,
+    invalid: fn(String) -> Error,
+) -> Result<http::Response, Error> {
+    if http::supports(url) {
+        http::send(method, url, headers, resolve, body, invalid)
+    } else {
+        curl_request(method, url, headers, resolve, body, invalid)
+    }
+}
+
+/// Sends a request to `api` with `send`, pausing and resuming while the API
+/// answers that it's rate limiting requests (`rate_limited`).
+///
+/// A rate-limited request holds back every request sharing `limiter` (or
+/// just this one, without a limiter) for [`RATE_LIMIT_PAUSE`], doubled on
+/// each further one, then is sent again; the last of
+/// [`RATE_LIMIT_ATTEMPTS`] answers is returned as is.
+///
+/// # Errors
+/// Returns an error if `send` fails or the limiter's file can't be updated.
+pub fn retry_rate_limited<T>(
+    api: &str,
+    limiter: Option<&RateLimiter>,
+    mut send: impl FnMut() -> Result<T, Error>,
+    rate_limited: impl Fn(&T) -> bool,
+) -> Result<T, Error> {
+    let mut pause = RATE_LIMIT_PAUSE;
+    for _ in 1..RATE_LIMIT_ATTEMPTS {
+        let answer = send()?;
+        if !rate_limited(&answer) {
+            return Ok(answer);
+        }
+        let secs = pause.as_secs();
+        log_at(
+            LogLevel::Warn,
+            "rate-limit",
+            format_args!(
+                "{api} is rate limiting requests; pausing for {secs}s"
+            ),
+        );
+        match limiter {
+            Some(limiter) => {
+                limiter.pause(pause)?;
+                limiter.wait()?;
+            }
+            None => thread::sleep(pause),
+        }
+        pause *= 2;
+    }
+    send()
+}
+
+/// Whether an HTTP response says its API is rate limiting requests: HTTP
+/// 429, GitHub's primary and secondary rate limits (HTTP 403) or Linear's
+/// `RATELIMITED` error (HTTP 400).
+pub fn is_rate_limited(response: &http::Response) -> bool {
+    let body = || response.body.to_ascii_lowercase();
+    match response.status {
+        429 => true,
+        403 => body().contains("rate limit"),
+        400 => body().contains("\"ratelimited\""),
+        _ => false,
+    }
+}
+
+/// Sends a request with `curl`, returning the status and body.
+///
+/// `-w` appends the status code on a line of its own after the body, so the
+/// body of an error response is kept rather than dropped as with `-f`.
+///
+/// # Errors
+/// Returns an error if `curl` can't be run or the request fails before a
+/// response is received (e.g. DNS or TLS errors), or an `invalid` error if
+/// the response is too large.
+fn curl_request(
+    method: &str,
+    url: &str,
+    headers: &[String],
+    resolve: &[String],
+    body: Option<&str>,
+    invalid: fn(String) -> Error,
+) -> Result<http::Response, Error> {
+    let mut command = Command::new("curl");
+    command.args([
+        "-sS", // Silent mode, but show errors
+        "-X",
+        method, // HTTP method
+        "-w",
+        "\\n%{http_code}", // Status code after the body
+    ]);
+    for header in headers {
+        command.args(["-H", header]);
+    }
+    for entry in resolve {
+        command.args(["--resolve", entry]);
+    }
+    if let Some(body) = body {
+        command.args(["--data", body]);
+    }
+    command.args(["--max-filesize", &http::MAX_RESPONSE_BYTES.to_string()]);
+    let output = command
+        .arg(url)
+        .output()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => exit_status::spawn_error(
+                "Failed to execute curl (install curl, or build with the native-http feature to send HTTPS requests in-process)",
+                e,
+            ),
+            _ => exit_status::spawn_error("Failed to execute curl", e),
+        })?;
+
+    // Check if curl succeeded
+    if !output.status.success() {
+        let stderr = String::from_utf8(output.stderr)
+            .unwrap_or_else(|_| "<non-utf8 stderr>".to_string());
+        return Err(Error::command(format!(
+            "curl request failed: {}",
+            stderr.trim()
+        )));
+    }
+
+    // `--max-filesize` can't stop a response without a Content-Length on
+    // older curl versions, so check what was actually received.
+    if output.stdout.len() > http::MAX_RESPONSE_BYTES {
+        return Err(invalid(format!(
+            "Response from {url} exceeds {} bytes",
+            http::MAX_RESPONSE_BYTES
+        )));
+    }
+    let stdout = String::from_utf8(output.stdout)
+        .map_err(|e| Error::command(format!("Invalid UTF-8 from curl: {e}")))?;
+    let (body, status) = stdout.rsplit_once('\n').unwrap_or(("", &stdout));
+    let status = status.trim().parse::<u16>().map_err(|_| {
+        Error::command(format!("curl reported no HTTP status for {url}"))
+    })?;
+    Ok(http::Response {
+        status,
+        body: body.to_string(),
+    })
+}
+
+/// Builds a `gh` command, applying the `--github-host` override.
+///
+/// `gh` reads the target host from `GH_HOST`, which lets requests go to a
+/// GitHub Enterprise Server or an internal gateway in front of github.com.
+/// When `github_host` is `None`, the inherited environment is left untouched.
+pub fn gh_command(github_host: Option<&str>) -> Command {
+    let mut command = Command::new("gh");
+    if let Some(host) = github_host {
+        command.env("GH_HOST", host);
+    }
+    command
+}
+
+/// Runs a `gh` command, pausing and running it again while GitHub rate
+/// limits it (see [`retry_rate_limited`]).
+///
+/// # Errors
+/// Returns an error if `gh` can't be run.
+pub fn gh_output(
+    command: &mut Command,
+    limiter: Option<&RateLimiter>,
+) -> Result<Output, Error> {
+    retry_rate_limited(
+        "GitHub",
+        limiter,
+        || {
+            command.output().map_err(|e| {
+                exit_status::spawn_error("Failed to execute gh command", e)
+            })
+        },
+        |output| {
+            !output.status.success()
+                && String::from_utf8_lossy(&output.stderr)
+                    .to_ascii_lowercase()
+                    .contains("rate limit")
+        },
+    )
+}
+
+/// Percent-encodes everything but unreserved URL characters, so a repository
+/// path or tag fits in one path segment (`group/project` ->
+/// `group%2Fproject`).
+pub fn percent_encode(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            out.push(char::from(byte));
+        } else {
+            let _ = write!(out, "%{byte:02X}");
+        }
+    }
+    out
+}
+
+/// Encodes a string as a JSON string literal (including the quotes).
 ///
 /// # Example
+/// ```ignore
+/// assert_eq!(json_string("say \"hi\""), r#""say \"hi\"""#);
 /// ```
-/// let query = r#"{"query": "{ viewer { name } }"}"#;
-/// let response = graphql_request(query, "lin_api_...")?;
-/// // response: JSON string with viewer data
+pub fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if u32::from(c) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", u32::from(c));
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Encodes a string as standard, padded base64 (e.g. for HTTP Basic
+/// authentication).
+///
+/// # Example
+/// ```ignore
+/// assert_eq!(base64_encode("user:token"), "dXNlcjp0b2tlbg==");
 /// ```
-pub fn graphql_request(query: &str, api_key: &str) -> Result<String, String> {
-    // Execute curl command with GraphQL request
-    let output = Command::new("curl")
-        .args([
-            "-sS", // Silent mode, but show errors
-            "-f",  // Fail on HTTP errors
-            "-X",
-            "POST", // HTTP POST method
-            "-H",
-            "Content-Type: application/json", // JSON content type
-            "-H",
-            &format!("Authorization: {api_key}"), // API key for auth
-            "--data",
-            query,                            // GraphQL query as body
-            "https://api.linear.app/graphql", // Linear API endpoint
-        ])
-        .output()
-        .map_err(|e| format!("Failed to execute curl: {e}"))?;
+pub fn base64_encode(input: &str) -> String {
+    const ALPHABET: &[u8; 64] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.as_bytes().chunks(3) {
+        let bytes = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let group = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for idx in 0..4 {
+            if idx <= chunk.len() {
+                let sextet = (group >> (18 - 6 * idx)) & 0x3f;
+                out.push(char::from(ALPHABET[sextet as usize]));
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Formats a run time for the end-of-run summary: `850ms`, `12.3s` or
+/// `4m 05s`.
+pub fn format_elapsed(elapsed: Duration) -> String {
+    let millis = elapsed.as_millis();
+    match elapsed.as_secs() {
+        0 => format!("{millis}ms"),
+        secs @ 1..60 => format!("{secs}.{}s", elapsed.subsec_millis() / 100),
+        secs => format!("{}m {:02}s", secs / 60, secs % 60),
+    }
+}
+
+/// Parses an ISO 8601 date or timestamp into Unix seconds.
+///
+/// # Accepted Formats
+/// - `2024-05-01` - midnight UTC
+/// - `2024-05-01T12:30:00Z` - UTC (seconds and fractions of a second are
+///   optional)
+/// - `2024-05-01T14:30:00+02:00` - with a UTC offset
+///
+/// # Errors
+/// Returns an error if the value isn't in one of these formats or names an
+/// invalid date or time.
+pub fn parse_timestamp(value: &str) -> Result<i64, Error> {
+    let invalid = || {
+        Error::Other(format!(
+            "Invalid timestamp {value}: expected YYYY-MM-DD or YYYY-MM-DDTHH:MM:SSZ"
+        ))
+    };
+    let number = |text: &str, min: i64, max: i64| {
+        text.parse::<i64>()
+            .ok()
+            .filter(|n| (min..=max).contains(n) && !text.starts_with('+'))
+            .ok_or_else(invalid)
+    };
+
+    let (date, time) = match value.split_once('T') {
+        Some((date, time)) => (date, Some(time)),
+        None => (value, None),
+    };
+    let mut fields = date.splitn(3, '-');
+    let (Some(year), Some(month), Some(day)) =
+        (fields.next(), fields.next(), fields.next())
+    else {
+        return Err(invalid());
+    };
+    let (year, month) = (number(year, 1970, 9999)?, number(month, 1, 12)?);
+    let day = number(day, 1, days_in_month(year, month))?;
+
+    let mut seconds = days_from_civil(year, month, day) * 86_400;
+    let Some(time) = time else {
+        return Ok(seconds);
+    };
+
+    // Offset: `Z`, `+HH:MM` or `-HH:MM`
+    let (clock, offset) = if let Some(clock) = time.strip_suffix(['Z', 'z']) {
+        (clock, 0)
+    } else {
+        let sign_at = time.rfind(['+', '-']).ok_or_else(invalid)?;
+        let (clock, offset) = time.split_at(sign_at);
+        let sign = if offset.starts_with('-') { -1 } else { 1 };
+        let (hours, minutes) =
+            offset[1..].split_once(':').ok_or_else(invalid)?;
+        let offset =
+            number(hours, 0, 23)? * 3600 + number(minutes, 0, 59)? * 60;
+        (clock, sign * offset)
+    };
+
+    // Clock: `HH:MM`, `HH:MM:SS` or `HH:MM:SS.fff`
+    let clock = clock.split_once('.').map_or(clock, |(whole, _)| whole);
+    let mut fields = clock.splitn(3, ':');
+    let (Some(hours), Some(minutes)) = (fields.next(), fields.next()) else {
+        return Err(invalid());
+    };
+    seconds += number(hours, 0, 23)? * 3600 + number(minutes, 0, 59)? * 60;
+    if let Some(secs) = fields.next() {
+        seconds += number(secs, 0, 60)?;
+    }
+    Ok(seconds - offset)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// The UTC date (`YYYY-MM-DD`) of Unix time `seconds` (Howard Hinnant's
+/// `civil_from_days`, the inverse of [`days_from_civil`]).
+pub fn utc_date(seconds: i64) -> String {
+    let days = seconds.div_euclid(86_400) + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524
+        - day_of_era / 146_096)
+        / 365;
+    let day_of_year =
+        day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = era * 400 + year_of_era + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// The current UTC time (`YYYY-MM-DDTHH:MM:SSZ`).
+pub fn utc_now() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    utc_timestamp(i64::try_from(seconds).unwrap_or_default())
+}
+
+/// The UTC time (`YYYY-MM-DDTHH:MM:SSZ`) of Unix time `seconds`.
+pub fn utc_timestamp(seconds: i64) -> String {
+    let of_day = seconds.rem_euclid(86_400);
+    format!(
+        "{}T{:02}:{:02}:{:02}Z",
+        utc_date(seconds),
+        of_day / 3600,
+        of_day / 60 % 60,
+        of_day % 60
+    )
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date (Howard Hinnant's
+/// `days_from_civil`).
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era =
+        year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Computes the SHA-256 digest of `input` using `sha256sum`.
+///
+/// # Returns
+/// The lowercase hex digest.
+///
+/// # Errors
+/// Returns an error if:
+/// - `sha256sum` command cannot be spawned or fails
+/// - Output is not in the expected `<hex>  -` format
+pub fn sha256_hex(input: &str) -> Result<String, Error> {
+    sha256sum(input.as_bytes())
+}
+
+/// Computes the HMAC-SHA256 (RFC 2104) of `message` keyed with `key`, with
+/// ring (the `native-http` feature).
+///
+/// # Returns
+/// The lowercase hex MAC.
+///
+/// # Errors
+/// Returns an error if the MAC can't be formatted. Builds without the
+/// feature take the digests with `sha256sum`, which can fail.
+#[cfg(feature = "native-http")]
+pub fn hmac_sha256_hex(key: &[u8], message: &[u8]) -> Result<String, Error> {
+    let key = ring::hmac::Key::new(ring::hmac::HMAC_SHA256, key);
+    let tag = ring::hmac::sign(&key, message);
+    tag.as_ref()
+        .iter()
+        .try_fold(String::new(), |mut hex, byte| {
+            write!(hex, "{byte:02x}").map(|()| hex)
+        })
+        .map_err(|e| Error::Other(format!("Failed to format HMAC: {e}")))
+}
+
+/// Computes the HMAC-SHA256 (RFC 2104) of `message` keyed with `key`, with
+/// the two digests taken by `sha256sum` (without the `native-http`
+/// feature).
+///
+/// # Returns
+/// The lowercase hex MAC.
+///
+/// # Errors
+/// Returns an error if `sha256sum` fails (see [`sha256_hex`]).
+#[cfg(not(feature = "native-http"))]
+pub fn hmac_sha256_hex(key: &[u8], message: &[u8]) -> Result<String, Error> {
+    const BLOCK_SIZE: usize = 64;
+    let mut block = if key.len() > BLOCK_SIZE {
+        hex_bytes(&sha256sum(key)?)?
+    } else {
+        key.to_vec()
+    };
+    block.resize(BLOCK_SIZE, 0);
+    let mut inner: Vec<u8> = block.iter().map(|byte| byte ^ 0x36).collect();
+    inner.extend_from_slice(message);
+    let mut outer: Vec<u8> = block.iter().map(|byte| byte ^ 0x5c).collect();
+    outer.extend(hex_bytes(&sha256sum(&inner)?)?);
+    sha256sum(&outer)
+}
+
+/// Decodes a `sha256sum` hex digest into its bytes.
+#[cfg(not(feature = "native-http"))]
+fn hex_bytes(hex: &str) -> Result<Vec<u8>, Error> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|start| {
+            hex.get(start..start + 2)
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| {
+                    Error::command(format!(
+                        "Unexpected sha256sum digest: {hex}"
+                    ))
+                })
+        })
+        .collect()
+}
+
+/// Runs `sha256sum` on `input`, returning the lowercase hex digest.
+fn sha256sum(input: &[u8]) -> Result<String, Error> {
+    let mut child = Command::new("sha256sum")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| {
+            exit_status::spawn_error("Failed to spawn sha256sum", e)
+        })?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(input)
+            .map_err(|e| Error::io("Failed to write to sha256sum stdin", e))?;
+    }
+
+    let output = child
+        .wait_with_output()
+        .map_err(|e| Error::io("Failed to wait for sha256sum", e))?;
 
-    // Check if curl succeeded
     if !output.status.success() {
         let stderr = String::from_utf8(output.stderr)
             .unwrap_or_else(|_| "<non-utf8 stderr>".to_string());
-        return Err(format!("curl request failed: {stderr}"));
+        return Err(Error::command(format!(
+            "sha256sum failed: {}",
+            stderr.trim()
+        )));
     }
 
-    // Convert response to string
-    String::from_utf8(output.stdout)
-        .map_err(|e| format!("Invalid UTF-8 from curl: {e}"))
+    let stdout = String::from_utf8(output.stdout).map_err(|e| {
+        Error::command(format!("Invalid UTF-8 from sha256sum: {e}"))
+    })?;
+    stdout
+        .split_whitespace()
+        .next()
+        .map(String::from)
+        .ok_or_else(|| Error::command("Unexpected sha256sum output"))
 }
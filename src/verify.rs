@@ -0,0 +1,133 @@
+//! Release verification: whether every ticket of a release is completed.
+//!
+//! This module implements the `verify` mode, a post-release gate. Like
+//! `report`, it runs the parse-notes and extract-tickets stages and looks
+//! every ticket of the release up in Linear (or the `--linear-snapshot`),
+//! without changing anything. A ticket passes if it is in the state
+//! update-tickets moves tickets to: a Done/Completed state, or the
+//! `--target-state`/`--target-state-type` state.
+//!
+//! ## Output
+//! Every ticket that isn't completed, or couldn't be looked up, is printed
+//! to stdout (as `--url-format` says) and logged with its state, and the run
+//! fails. When all tickets are completed, nothing is printed and the run
+//! succeeds.
+
+use crate::config::{Config, Lookups, UrlFormat};
+use crate::error::Error;
+use crate::report;
+use crate::tickets::TicketStore;
+use crate::update_tickets;
+use crate::utils;
+
+const NAME: &str = "verify";
+
+macro_rules! log {
+    ($level:ident, $fmt:expr) => {
+        utils::log_at(utils::LogLevel::$level, NAME, format_args!($fmt));
+    };
+    ($fmt:expr) => {
+        utils::log(NAME, format_args!($fmt));
+    };
+}
+
+/// Ticket counts reported at the end of the verification.
+#[derive(Debug, Default)]
+struct Tally {
+    completed: usize,
+    incomplete: usize,
+    failed: usize,
+}
+
+/// Runs the verify mode.
+///
+/// # Errors
+/// Returns an error if:
+/// - `--release-tag`, `LINEAR_API_KEY` or `LINEAR_ORG` is not provided
+/// - The snapshot can't be read
+/// - parse-notes or extract-tickets fail (other than by running out of
+///   `--api-budget`)
+/// - Any ticket of the release isn't completed or couldn't be looked up
+pub fn run(config: &Config) -> Result<(), Error> {
+    let release_tag = config.release_tag.as_deref().ok_or_else(|| {
+        Error::Config("verify requires --release-tag".to_string())
+    })?;
+    let linear = config.linear_api()?;
+    let org = config.get_linear_org()?;
+    let snapshot = config.snapshot()?;
+
+    let prs = report::release_prs(config, release_tag)?;
+
+    if let Some(ref snapshot) = snapshot {
+        let path = snapshot.path();
+        log!("reading tickets from snapshot {path}");
+    }
+    let mut store = TicketStore::new(false, false, snapshot);
+    let issue_ids = report::ticket_ids(&prs);
+    let count = issue_ids.len();
+    log!("verifying {count} tickets of release {release_tag}");
+    if config.lookups == Lookups::Batched {
+        store.prefetch(&issue_ids, &linear);
+    }
+
+    let mut tally = Tally::default();
+    for issue_id in &issue_ids {
+        match store.get(issue_id, &linear) {
+            Ok(ticket)
+                if update_tickets::matches_target_state(
+                    &ticket.state_name,
+                    &ticket.state_type,
+                    config.target_state.as_deref(),
+                    config.target_state_type.as_deref(),
+                ) =>
+            {
+                tally.completed += 1;
+                continue;
+            }
+            Ok(ticket) => {
+                let state_name = &ticket.state_name;
+                log!(
+                    Warn,
+                    "Issue {issue_id} is not completed, it is in {state_name}"
+                );
+                tally.incomplete += 1;
+            }
+            Err(e) => {
+                log!(Warn, "Failed to look up {issue_id}: {e}");
+                tally.failed += 1;
+            }
+        }
+        println!("{}", output_line(config, &org, issue_id));
+    }
+
+    let Tally {
+        completed,
+        incomplete,
+        failed,
+    } = tally;
+    log!("done");
+    log!("  {completed} ticket(s) completed");
+    log!("  {incomplete} ticket(s) not completed");
+    log!("  {failed} ticket(s) could not be looked up");
+    let unverified = incomplete + failed;
+    if unverified > 0 {
+        return Err(Error::Other(format!(
+            "{unverified} of {count} ticket(s) of release {release_tag} are not verified as completed"
+        )));
+    }
+    Ok(())
+}
+
+/// A ticket as printed to stdout, per `--url-format`.
+fn output_line(config: &Config, org: &str, issue_id: &str) -> String {
+    match config.url_format {
+        UrlFormat::Id => issue_id.to_string(),
+        UrlFormat::Url => update_tickets::issue_url(org, issue_id),
+        UrlFormat::Markdown => {
+            format!(
+                "[{issue_id}]({})",
+                update_tickets::issue_url(org, issue_id)
+            )
+        }
+    }
+}
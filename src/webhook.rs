@@ -0,0 +1,150 @@
+//! Ticket events for downstream systems (`--webhook-url`).
+//!
+//! With `--webhook-url URL`, update-tickets POSTs a small JSON event to `URL`
+//! for every ticket it moves, right after the move, so that services such as
+//! analytics or customer notifications can react to a ticket shipping:
+//!
+//! ```json
+//! {"event":"ticket.released","ticket":"ENG-2","url":"https://linear.app/acme/issue/ENG-2",
+//!  "release_tag":"v1.2.3","from":"Passing","to":"completed","pr":12,"repo":"acme/app",
+//!  "timestamp":"2024-05-01T10:00:00Z"}
+//! ```
+//!
+//! `release_tag` is there with `--release-tag`, and `pr`/`repo` when the ticket
+//! records say which PR the ticket was found in. The request carries an
+//! `X-Release-Event: ticket.released` header and, with `--webhook-secret` (or
+//! `WEBHOOK_SECRET`), an `X-Release-Signature-256: sha256=HEX` header: the
+//! HMAC-SHA256 of the body keyed with the secret, for the receiver to check
+//! the event came from this tool (as with GitHub's webhook signatures).
+//!
+//! Events are sent once, without retries. A failed delivery or a non-2xx
+//! answer is logged and doesn't fail the ticket; in dry-run, the events are
+//! only logged.
+
+use std::fmt::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::Config;
+use crate::error::Error;
+use crate::estimate::{self, Provider};
+use crate::protocol::PrRef;
+use crate::utils;
+
+/// Name of the event sent for a moved ticket.
+pub const TICKET_RELEASED: &str = "ticket.released";
+
+/// Where events are sent, and the secret they are signed with.
+#[derive(Debug)]
+pub struct Webhook {
+    pub url: String,
+    secret: Option<String>,
+}
+
+/// A ticket the run moved.
+#[derive(Debug)]
+pub struct Event<'a> {
+    /// Linear ticket ID (e.g. `ENG-2`)
+    pub ticket: &'a str,
+    /// The ticket's Linear URL
+    pub url: &'a str,
+    /// The `--release-tag` the ticket shipped in
+    pub release_tag: Option<&'a str>,
+    /// Workflow state name before the move
+    pub from_state: Option<&'a str>,
+    /// Workflow state the ticket was moved to
+    pub to_state: &'a str,
+    /// The PR the ticket was found in, from its ticket record
+    pub pr: Option<&'a PrRef>,
+}
+
+impl Event<'_> {
+    /// Renders the JSON body of the event, stamped with the current time.
+    pub fn to_json(&self) -> String {
+        let mut body = format!(
+            "{{\"event\":\"{TICKET_RELEASED}\",\"ticket\":{},\"url\":{}",
+            utils::json_string(self.ticket),
+            utils::json_string(self.url)
+        );
+        for (name, value) in [
+            ("release_tag", self.release_tag),
+            ("from", self.from_state),
+            ("to", Some(self.to_state)),
+        ] {
+            if let Some(value) = value {
+                let _ =
+                    write!(body, ",\"{name}\":{}", utils::json_string(value));
+            }
+        }
+        if let Some(pr) = self.pr {
+            let _ = write!(body, ",\"pr\":{}", pr.number);
+            if let Some(ref repo) = pr.repo {
+                body.push_str(",\"repo\":");
+                body.push_str(&utils::json_string(repo));
+            }
+        }
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        let _ = write!(
+            body,
+            ",\"timestamp\":\"{}\"}}",
+            utils::utc_timestamp(i64::try_from(seconds).unwrap_or_default())
+        );
+        body
+    }
+}
+
+impl Webhook {
+    /// The `--webhook-url` endpoint, signed with `--webhook-secret` or else
+    /// the `WEBHOOK_SECRET` environment variable, if set.
+    pub fn from_config(config: &Config) -> Option<Self> {
+        let url = config.webhook_url.clone()?;
+        Some(Self {
+            url,
+            secret: config.get_webhook_secret(),
+        })
+    }
+
+    /// POSTs `event` to the endpoint.
+    ///
+    /// # Errors
+    /// Returns an error if the request fails, the endpoint doesn't answer
+    /// with a 2xx status, or the body can't be signed.
+    pub fn send(&self, event: &Event<'_>) -> Result<(), Error> {
+        let body = event.to_json();
+        let mut headers = vec![
+            "Content-Type: application/json".to_string(),
+            format!("X-Release-Event: {TICKET_RELEASED}"),
+            concat!(
+                "User-Agent: release-linear-ticket-update/",
+                env!("CARGO_PKG_VERSION")
+            )
+            .to_string(),
+        ];
+        if let Some(ref secret) = self.secret {
+            let signature =
+                utils::hmac_sha256_hex(secret.as_bytes(), body.as_bytes())?;
+            headers
+                .push(format!("X-Release-Signature-256: sha256={signature}"));
+        }
+        estimate::count(Provider::Webhook);
+        let response = utils::http_request(
+            "POST",
+            &self.url,
+            &headers,
+            &[],
+            Some(&body),
+            Error::Other,
+        )?;
+        if (200..300).contains(&response.status) {
+            Ok(())
+        } else {
+            let status = response.status;
+            let excerpt = utils::excerpt(response.body.trim());
+            Err(Error::Other(format!(
+                "{} answered HTTP {status}: {excerpt}",
+                self.url
+            )))
+        }
+    }
+}